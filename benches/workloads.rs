@@ -0,0 +1,79 @@
+//! Release-over-release benchmarks on the `sekejap::bench` workloads.
+//!
+//! Run with `cargo bench --features bench --bench workloads`.
+//!
+//! Scenarios (all on a seeded [`Workload`], so every run sees the same data):
+//!   put         — put_many of N nodes into an empty in-memory database
+//!   ingest      — ingest_vectors of N embeddings (no HNSW maintenance)
+//!   traversal   — 3-hop forward expansion from one node
+//!   near        — geo radius query (5 km) on the spatial grid
+//!   similar     — top-10 VECTOR_NEAR through the HNSW index
+//!   query_json  — SQL SELECT with LIMIT, hits serialised with Hit::to_json
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, black_box};
+use sekejap::bench::{Workload, VECTOR_FIELD};
+use sekejap::{CoreDB, HitJsonOptions};
+
+const SIZES: [usize; 2] = [1_000, 10_000];
+const K: usize = 10;
+
+fn bench_put(c: &mut Criterion) {
+    let mut group = c.benchmark_group("put");
+    group.sample_size(10);
+    for n in SIZES {
+        let nodes: Vec<(String, String)> = Workload::new(n).nodes().collect();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &nodes, |b, nodes| {
+            b.iter(|| {
+                let mut db = CoreDB::new();
+                db.put_many(nodes.iter().map(|(s, j)| (s.as_str(), j.as_str()))).unwrap();
+                black_box(db.node_count())
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_ingest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ingest");
+    group.sample_size(10);
+    for n in SIZES {
+        let vectors: Vec<(String, Vec<f32>)> = Workload::new(n).vectors().collect();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &vectors, |b, vectors| {
+            b.iter(|| {
+                let mut db = CoreDB::new();
+                black_box(db.ingest_vectors(VECTOR_FIELD, vectors.iter().cloned()))
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_reads(c: &mut Criterion) {
+    for n in SIZES {
+        let w = Workload::new(n);
+        let db = w.populate();
+        let start = w.slug(0);
+        let query = w.query_vector(0);
+
+        c.bench_function(&format!("traversal/{n}"), |b| {
+            b.iter(|| black_box(db.one(&start).hops(3).count()))
+        });
+        c.bench_function(&format!("near/{n}"), |b| {
+            b.iter(|| black_box(db.all().near(-6.2, 106.8, 5.0).count()))
+        });
+        c.bench_function(&format!("similar/{n}"), |b| {
+            b.iter(|| black_box(db.all().vector_near(VECTOR_FIELD, query.clone(), K).count()))
+        });
+        let options = HitJsonOptions::default();
+        c.bench_function(&format!("query_json/{n}"), |b| {
+            b.iter(|| {
+                let hits = db.query("SELECT * FROM items WHERE score > 50 LIMIT 100").unwrap().collect();
+                let rows: Vec<_> = hits.iter().map(|h| h.to_json(&options)).collect();
+                black_box(serde_json::Value::Array(rows).to_string())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_put, bench_ingest, bench_reads);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+//! Arbitrary bytes through every SekejapQL parser; see `sql::parse_unchecked`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    sekejap::sql::parse_unchecked(data);
+});
@@ -0,0 +1,237 @@
+//! Per-statement authorization for [`CoreDB::query_as`] / [`CoreDB::execute_as`]
+//! and the mutation documents of [`CoreDB::mutate_json_as`] /
+//! [`CoreDB::mutate_ndjson_as`].
+//!
+//! An embedding server (HTTP, gRPC, …) implements [`Authorizer`] for its
+//! callers; every statement is resolved to the kind of access it needs and
+//! the collections it touches before anything runs.
+
+use crate::query::{FromSource, Step};
+use crate::sql::{AlterTableOp, CompiledMutation, MatchOrAgg};
+use crate::storage::wal::WalEntry;
+use crate::{sk_hash, CoreDB};
+
+/// The kind of access a statement needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// `SELECT` / `MATCH` queries.
+    Read,
+    /// Node and edge mutations (`INSERT`, `UPDATE`, `DELETE`, transactions).
+    Write,
+    /// DDL: `CREATE` / `DROP` / `ALTER TABLE`, index management.
+    Schema,
+}
+
+/// Decides whether a caller may run a statement.
+///
+/// `collections` lists the collections the statement touches. It is `None`
+/// when the statement is not confined to known collections — it scans every
+/// node, starts from nodes outside any collection, or follows edges (which
+/// can reach any collection). Collection-scoped callers should be refused
+/// in that case.
+///
+/// Closures `Fn(Access, Option<&[String]>) -> Result<(), String>` implement
+/// the trait.
+pub trait Authorizer {
+    /// Return `Err(reason)` to reject the statement.
+    fn authorize(&self, access: Access, collections: Option<&[String]>) -> Result<(), String>;
+}
+
+impl<F> Authorizer for F
+where
+    F: Fn(Access, Option<&[String]>) -> Result<(), String>,
+{
+    fn authorize(&self, access: Access, collections: Option<&[String]>) -> Result<(), String> {
+        self(access, collections)
+    }
+}
+
+impl std::fmt::Debug for dyn Authorizer + Send + Sync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Authorizer")
+    }
+}
+
+/// Collections a statement touches; `None` once anything unscoped is seen.
+struct Scope(Option<Vec<String>>);
+
+impl Scope {
+    fn new() -> Self {
+        Scope(Some(Vec::new()))
+    }
+
+    fn add(&mut self, name: &str) {
+        if let Some(names) = &mut self.0 {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+
+    fn unscoped(&mut self) {
+        self.0 = None;
+    }
+
+    /// The collection of the node behind `slug_hash`, or unscoped if it has none.
+    fn add_node(&mut self, db: &CoreDB, slug_hash: u64) {
+        match db.node_data(slug_hash) {
+            Some(n) if !n.collection.is_empty() => self.add(&n.collection),
+            Some(_) => self.unscoped(),
+            // A missing node reads as nothing; an edge to it names no collection.
+            None => {}
+        }
+    }
+
+    fn add_steps(&mut self, db: &CoreDB, steps: &[Step]) {
+        for step in steps {
+            match step {
+                Step::Collection(h) => {
+                    if let Some(name) = db.collection_name(*h) {
+                        self.add(name);
+                    }
+                }
+                Step::One(h) => self.add_node(db, *h),
+                Step::Many(hs) => hs.iter().for_each(|h| self.add_node(db, *h)),
+                Step::All
+                | Step::Forward(_)
+                | Step::Backward(_)
+                | Step::Both(_)
+                | Step::Hops(_)
+                | Step::HopsTyped { .. } => self.unscoped(),
+                Step::Intersect(v)
+                | Step::Union(v)
+                | Step::Subtract(v)
+                | Step::HopFilter(v)
+                | Step::Having(v) => self.add_steps(db, v),
+                Step::WhereOr(branches) => branches.iter().for_each(|b| self.add_steps(db, b)),
+                Step::WhereNot(inner) => self.add_steps(db, std::slice::from_ref(inner.as_ref())),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Collections a parsed read touches. Graph patterns are unscoped.
+pub(crate) fn read_scope(db: &CoreDB, parsed: &MatchOrAgg) -> Option<Vec<String>> {
+    let mut scope = Scope::new();
+    match parsed {
+        MatchOrAgg::Steps(steps) => scope.add_steps(db, steps),
+        MatchOrAgg::Agg(_) | MatchOrAgg::Shortest(_) => scope.unscoped(),
+        MatchOrAgg::MultiFrom(stmt) => {
+            for source in &stmt.sources {
+                match source {
+                    FromSource::Collection { name_hash, .. } => {
+                        if let Some(name) = db.collection_name(*name_hash) {
+                            scope.add(name);
+                        }
+                    }
+                    FromSource::Match(_) | FromSource::Shortest(_) => scope.unscoped(),
+                }
+            }
+        }
+    }
+    scope.0
+}
+
+/// Access kind and collections a compiled mutation touches.
+pub(crate) fn mutation_scope(db: &CoreDB, m: &CompiledMutation) -> (Access, Option<Vec<String>>) {
+    let mut scope = Scope::new();
+    let access = match m {
+        CompiledMutation::Insert { collection, .. } | CompiledMutation::InsertBatch { collection, .. } => {
+            scope.add(collection);
+            Access::Write
+        }
+        CompiledMutation::Delete(steps) | CompiledMutation::Update { steps, .. } => {
+            scope.add_steps(db, steps);
+            Access::Write
+        }
+        CompiledMutation::InsertEdge(edges) => {
+            for e in edges {
+                scope.add_node(db, sk_hash(&e.from));
+                scope.add_node(db, sk_hash(&e.to));
+            }
+            Access::Write
+        }
+        CompiledMutation::DeleteEdge(edges) => {
+            for e in edges {
+                scope.add_node(db, sk_hash(&e.from));
+                scope.add_node(db, sk_hash(&e.to));
+            }
+            Access::Write
+        }
+        CompiledMutation::MatchInsert { match_steps, target, .. } => {
+            scope.add_steps(db, match_steps);
+            scope.add_node(db, sk_hash(target));
+            Access::Write
+        }
+        CompiledMutation::CreateTable { collection, .. }
+        | CompiledMutation::CreateIndex { collection, .. }
+        | CompiledMutation::DropTable { collection, .. }
+        | CompiledMutation::DropIndex { collection, .. }
+        | CompiledMutation::Reindex { collection, .. } => {
+            scope.add(collection);
+            Access::Schema
+        }
+        CompiledMutation::AlterTable { collection, op } => {
+            scope.add(collection);
+            if let AlterTableOp::RenameTable { new_name } = op {
+                scope.add(new_name);
+            }
+            Access::Schema
+        }
+        CompiledMutation::Begin | CompiledMutation::Commit | CompiledMutation::Rollback => Access::Write,
+    };
+    (access, scope.0)
+}
+
+/// Access kind and collections one mutation document touches, or `None` for
+/// the `txn_begin` / `txn_end` markers, which touch nothing themselves.
+pub(crate) fn entry_scope(db: &CoreDB, entry: &WalEntry) -> Option<(Access, Option<Vec<String>>)> {
+    let mut scope = Scope::new();
+    let access = match entry {
+        WalEntry::TxnBegin | WalEntry::TxnEnd => return None,
+        WalEntry::Put { slug, payload } => {
+            // A put can move an existing node, so both collections count.
+            scope.add_node(db, sk_hash(slug));
+            let collection = serde_json::from_str::<serde_json::Value>(payload).ok()
+                .and_then(|p| p.get("_collection")?.as_str().map(str::to_string));
+            match collection {
+                Some(c) if !c.is_empty() => scope.add(&c),
+                _ => scope.unscoped(),
+            }
+            Access::Write
+        }
+        WalEntry::Remove { slug } | WalEntry::SoftRemove { slug } | WalEntry::PutVector { slug, .. } => {
+            scope.add_node(db, sk_hash(slug));
+            Access::Write
+        }
+        WalEntry::Restore { slug } | WalEntry::Purge { slug } => {
+            match db.trashed_collection(sk_hash(slug)) {
+                Some(c) if !c.is_empty() => scope.add(&c),
+                _ => scope.unscoped(),
+            }
+            Access::Write
+        }
+        WalEntry::Link { from, to, .. } | WalEntry::LinkMeta { from, to, .. } | WalEntry::Unlink { from, to, .. } => {
+            scope.add_node(db, sk_hash(from));
+            scope.add_node(db, sk_hash(to));
+            Access::Write
+        }
+        WalEntry::CreateTable { collection, .. }
+        | WalEntry::CreateIndex { collection, .. }
+        | WalEntry::DropTable { collection }
+        | WalEntry::DropIndex { collection, .. }
+        | WalEntry::SetCollectionFilter { collection, .. }
+        | WalEntry::SetEdgeAggregate { collection, .. }
+        | WalEntry::SetProjectionProfile { collection, .. } => {
+            scope.add(collection);
+            Access::Schema
+        }
+        // A rename names its target only inside `op_json`.
+        WalEntry::AlterTable { .. } | WalEntry::BuildHnsw { .. } | WalEntry::Manifest(_) | WalEntry::Unknown => {
+            scope.unscoped();
+            Access::Schema
+        }
+    };
+    Some((access, scope.0))
+}
@@ -0,0 +1,174 @@
+//! Reproducible synthetic workloads for benchmarking (`bench` feature).
+//!
+//! A [`Workload`] describes a dataset — nodes with a point geometry and a
+//! few scalar fields, random out-edges, and unit-length embeddings — that
+//! is a pure function of its parameters and seed. The same workload built
+//! against two releases gives the same bytes, so timings are comparable.
+//!
+//! ```
+//! use sekejap::bench::Workload;
+//!
+//! let w = Workload::new(100).seed(7).dim(8);
+//! let db = w.populate();
+//! assert_eq!(db.node_count(), 100);
+//! assert_eq!(db.edge_count(), 100 * 3);
+//! ```
+
+use crate::CoreDB;
+
+/// Edge type used by [`Workload::edges`].
+pub const EDGE_TYPE: &str = "links";
+/// Vector field used by [`Workload::populate`].
+pub const VECTOR_FIELD: &str = "embedding";
+
+/// Parameters of a synthetic dataset. Build with [`Workload::new`] and the
+/// chained setters; every generator is deterministic in these fields.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    nodes: usize,
+    seed: u64,
+    out_degree: usize,
+    dim: usize,
+    collection: String,
+}
+
+impl Workload {
+    /// `nodes` nodes in collection `items`, 3 out-edges each, 64-dim vectors,
+    /// seed 42.
+    pub fn new(nodes: usize) -> Self {
+        Self { nodes, seed: 42, out_degree: 3, dim: 64, collection: "items".into() }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn out_degree(mut self, out_degree: usize) -> Self {
+        self.out_degree = out_degree;
+        self
+    }
+
+    pub fn dim(mut self, dim: usize) -> Self {
+        self.dim = dim;
+        self
+    }
+
+    pub fn collection(mut self, collection: &str) -> Self {
+        self.collection = collection.to_string();
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes == 0
+    }
+
+    /// Slug of node `i`.
+    pub fn slug(&self, i: usize) -> String {
+        format!("{}/n{i:07}", self.collection)
+    }
+
+    /// `(slug, json)` for every node: `_collection`, `_key`, `category`
+    /// (one of 16), `score` in `[0, 100)` and a GeoJSON point inside a
+    /// 1° × 1° box around (-6.2, 106.8).
+    pub fn nodes(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        (0..self.nodes).map(move |i| {
+            let mut rng = self.rng(0, i);
+            let lat = -6.7 + rng.next_f64();
+            let lon = 106.3 + rng.next_f64();
+            let json = serde_json::json!({
+                "_collection": self.collection,
+                "_key": format!("n{i:07}"),
+                "category": format!("c{}", rng.next_u64() % 16),
+                "score": (rng.next_f64() * 100.0 * 1000.0).round() / 1000.0,
+                "geometry": {"type": "Point", "coordinates": [lon, lat]},
+            });
+            (self.slug(i), json.to_string())
+        })
+    }
+
+    /// `(from, to)` slugs of `out_degree` random out-edges per node, to
+    /// distinct targets other than the node itself.
+    pub fn edges(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        (0..self.nodes).flat_map(move |i| {
+            let mut rng = self.rng(1, i);
+            let want = self.out_degree.min(self.nodes.saturating_sub(1));
+            let mut targets: Vec<usize> = Vec::with_capacity(want);
+            while targets.len() < want {
+                let t = (i + 1 + (rng.next_u64() as usize) % (self.nodes - 1)) % self.nodes;
+                if !targets.contains(&t) {
+                    targets.push(t);
+                }
+            }
+            targets.into_iter().map(move |t| (self.slug(i), self.slug(t)))
+        })
+    }
+
+    /// `(slug, vector)` for every node: unit-length, `dim` components.
+    pub fn vectors(&self) -> impl Iterator<Item = (String, Vec<f32>)> + '_ {
+        (0..self.nodes).map(move |i| (self.slug(i), self.unit_vector(2, i)))
+    }
+
+    /// The `n`-th query vector; drawn from a separate stream so queries are
+    /// not copies of stored vectors.
+    pub fn query_vector(&self, n: usize) -> Vec<f32> {
+        self.unit_vector(3, n)
+    }
+
+    /// A fresh in-memory database holding the whole workload: nodes, edges
+    /// of type [`EDGE_TYPE`], vectors in [`VECTOR_FIELD`] with an HNSW index
+    /// (m = 16, ef_construction = 100), and the spatial grid.
+    pub fn populate(&self) -> CoreDB {
+        let mut db = CoreDB::new();
+        let nodes: Vec<(String, String)> = self.nodes().collect();
+        db.put_many(nodes.iter().map(|(s, j)| (s.as_str(), j.as_str())))
+            .expect("generated payloads are valid JSON");
+        for (from, to) in self.edges() {
+            db.link(&from, &to, EDGE_TYPE, 1.0);
+        }
+        if self.nodes > 0 && self.dim > 0 {
+            db.ingest_vectors(VECTOR_FIELD, self.vectors());
+            db.build_hnsw_index(VECTOR_FIELD, 16, 100)
+                .expect("vectors were just ingested");
+        }
+        db.build_spatial_index();
+        db
+    }
+
+    fn unit_vector(&self, stream: u64, i: usize) -> Vec<f32> {
+        let mut rng = self.rng(stream, i);
+        let mut v: Vec<f32> = (0..self.dim).map(|_| rng.next_f64() as f32 * 2.0 - 1.0).collect();
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt().max(f32::EPSILON);
+        v.iter_mut().for_each(|x| *x /= norm);
+        v
+    }
+
+    /// Independent generator per (stream, item), so any item can be built
+    /// without generating the ones before it.
+    fn rng(&self, stream: u64, i: usize) -> SplitMix64 {
+        let mut seeder = SplitMix64(self.seed ^ stream.wrapping_mul(0xA24B_AED4_963E_E407));
+        SplitMix64(seeder.next_u64() ^ (i as u64).wrapping_mul(0x9FB2_1C65_1E98_DF25))
+    }
+}
+
+/// SplitMix64 — tiny, fast, and stable across platforms and releases.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
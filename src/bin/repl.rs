@@ -7,6 +7,8 @@
 //! Commands:
 //!   .open <path>   open (or create) a persistent DB at path
 //!   .compact       write snapshot + truncate WAL
+//!   .backup <dir>  copy the database files into dir
+//!   .jobs          list running and recent long operations
 //!   .help          show this help
 //!   .quit / .q     exit
 //!
@@ -27,8 +29,11 @@
 
 use sekejap::CoreDB;
 use sekejap::Hit;
+use sekejap::Jobs;
 use std::io::{self, BufRead, Write};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 fn main() {
     let arg = std::env::args().nth(1);
@@ -90,11 +95,39 @@ fn main() {
                 ".quit" | ".q" => break,
                 ".help" => print_help(),
                 ".compact" => {
-                    match db.compact() {
+                    let jobs = db.jobs();
+                    match with_progress(&jobs, || db.compact()) {
                         Ok(_)  => println!("compacted"),
                         Err(e) => eprintln!("error: {e}"),
                     }
                 }
+                ".backup" => {
+                    if parts.len() < 2 || parts[1].trim().is_empty() {
+                        eprintln!("usage: .backup <dir>");
+                    } else {
+                        let jobs = db.jobs();
+                        match with_progress(&jobs, || db.backup_to(parts[1].trim())) {
+                            Ok(m)  => println!("backed up {} files to {}", m.files.len(), parts[1].trim()),
+                            Err(e) => eprintln!("error: {e}"),
+                        }
+                    }
+                }
+                ".jobs" => {
+                    for job in db.jobs().list() {
+                        let state = match &job.state {
+                            sekejap::JobState::Failed(msg) => format!("failed: {msg}"),
+                            other => other.name().to_string(),
+                        };
+                        println!(
+                            "#{:<3} {:<13} {:>4.0}%  {:>10}  {state}  {}",
+                            job.id,
+                            job.kind.name(),
+                            job.progress() * 100.0,
+                            fmt_duration(Duration::from_millis(job.elapsed_ms)),
+                            job.target,
+                        );
+                    }
+                }
                 ".open" => {
                     if parts.len() < 2 || parts[1].trim().is_empty() {
                         eprintln!("usage: .open <path>");
@@ -123,6 +156,32 @@ fn main() {
     }
 }
 
+/// Run `op`, showing the progress of the job it starts on stderr once it
+/// has taken a moment.
+fn with_progress<T>(jobs: &Jobs, op: impl FnOnce() -> T) -> T {
+    let finished = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let (jobs, finished) = (jobs.clone(), Arc::clone(&finished));
+        std::thread::spawn(move || {
+            let mut shown = false;
+            while !finished.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(200));
+                if let Some(job) = jobs.list().into_iter().rev().find(|j| !j.state.is_finished()) {
+                    eprint!("\r{} {:>3.0}%", job.kind.name(), job.progress() * 100.0);
+                    shown = true;
+                }
+            }
+            if shown {
+                eprint!("\r{:20}\r", "");
+            }
+        })
+    };
+    let out = op();
+    finished.store(true, Ordering::Relaxed);
+    let _ = watcher.join();
+    out
+}
+
 fn fmt_duration(d: std::time::Duration) -> String {
     let us = d.as_micros();
     if us < 1_000 {
@@ -224,6 +283,8 @@ sekejap REPL commands
 ──────────────────────────
 .open <path>   open persistent DB (restart required)
 .compact       flush snapshot, truncate WAL
+.backup <dir>  copy the database files into dir
+.jobs          list running and recent long operations
 .help          show this help
 .quit / .q     exit
 
@@ -0,0 +1,60 @@
+//! sekejap HTTP server
+//!
+//! Usage:
+//!   cargo run --features server --bin sekejap-server -- [options] [path/to/db]
+//!
+//! Options:
+//!   --addr HOST:PORT        listen address (default 127.0.0.1:7878)
+//!   --token TOKEN           accept `Authorization: Bearer TOKEN`; repeatable
+//!   --max-connections N     concurrent connections before 503 (default 64)
+//!
+//! Tokens may also be given as a comma-separated list in SEKEJAP_TOKENS.
+//! Without any token the server accepts every request. Without a path the
+//! database is in-memory.
+
+use sekejap::server::{Server, ServerConfig};
+use sekejap::CoreDB;
+
+fn main() {
+    let mut config = ServerConfig::default();
+    if let Ok(tokens) = std::env::var("SEKEJAP_TOKENS") {
+        config.tokens.extend(tokens.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from));
+    }
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().unwrap_or_else(|| fail(&format!("{flag} needs a value")));
+        match arg.as_str() {
+            "--addr" => config.addr = value("--addr"),
+            "--token" => config.tokens.push(value("--token")),
+            "--max-connections" => {
+                config.max_connections = value("--max-connections")
+                    .parse()
+                    .unwrap_or_else(|_| fail("--max-connections needs a number"));
+            }
+            flag if flag.starts_with("--") => fail(&format!("unknown option {flag}")),
+            _ => path = Some(arg),
+        }
+    }
+
+    let db = match &path {
+        Some(p) => CoreDB::open(p).unwrap_or_else(|e| fail(&format!("cannot open {p}: {e}"))),
+        None => CoreDB::new(),
+    };
+    let server = Server::bind(db, config.clone())
+        .unwrap_or_else(|e| fail(&format!("cannot bind {}: {e}", config.addr)));
+    eprintln!(
+        "sekejap-server on http://{} ({}, {})",
+        server.local_addr().map(|a| a.to_string()).unwrap_or(config.addr),
+        path.as_deref().unwrap_or("in-memory"),
+        if config.tokens.is_empty() { "no auth" } else { "bearer auth" },
+    );
+    if let Err(e) = server.run() {
+        fail(&e.to_string());
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("sekejap-server: {msg}");
+    std::process::exit(2);
+}
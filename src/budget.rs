@@ -0,0 +1,255 @@
+//! Time budgets and cancellation for running queries: [`CancelToken`],
+//! [`Set::timeout`](crate::Set::timeout) and
+//! [`QueryLimits::max_execution_ms`](crate::QueryLimits::max_execution_ms).
+//!
+//! While a query runs, its budget sits in a thread-local, so the executor
+//! and every sub-pipeline it starts can check it with [`spent`] without
+//! the budget being passed through each step. The executor checks before
+//! every step and every [`CHECK_EVERY`] payload checks or visited nodes;
+//! once a budget is spent, the pipeline stops and its rows are discarded.
+//! Hits already loaded when the stop comes are kept as the partial result.
+//!
+//! The same thread-local records traversals that outgrow
+//! [`QueryLimits::max_frontier`](crate::QueryLimits::max_frontier)
+//! (see [`frontier_fits`]), so a strict collect can report them.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::query::Hit;
+use crate::ResultTooLarge;
+
+/// Calls to [`spent`] between looks at the clock and the cancel flag.
+const CHECK_EVERY: u32 = 256;
+
+/// A flag another thread raises to stop the queries watching it; see
+/// [`Set::cancel_on`](crate::Set::cancel_on). Clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop every query watching this token at its next check.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a query stopped before finishing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterruptCause {
+    /// The run went past its time budget.
+    TimedOut { limit_ms: u64 },
+    /// A [`CancelToken`] the run watched was cancelled.
+    Cancelled,
+}
+
+/// A query stopped by its time budget or a [`CancelToken`].
+#[derive(Clone, Debug)]
+pub struct Interrupted {
+    pub cause: InterruptCause,
+    /// Hits loaded before the stop. Each matches the query, but more may
+    /// have; empty when the stop came before the pipeline finished.
+    pub partial: Vec<Hit>,
+}
+
+impl std::fmt::Display for InterruptCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterruptCause::TimedOut { limit_ms } => write!(f, "query ran past its {limit_ms} ms budget"),
+            InterruptCause::Cancelled => write!(f, "query cancelled"),
+        }
+    }
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({} partial hits)", self.cause, self.partial.len())
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Why [`Set::run`](crate::Set::run) returned no complete result.
+#[derive(Debug)]
+pub enum QueryError {
+    TooLarge(ResultTooLarge),
+    Interrupted(Interrupted),
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::TooLarge(e) => e.fmt(f),
+            QueryError::Interrupted(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<ResultTooLarge> for QueryError {
+    fn from(e: ResultTooLarge) -> Self {
+        QueryError::TooLarge(e)
+    }
+}
+
+/// Limits on one query run, carried by a [`Set`](crate::Set).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Budget {
+    /// When the run must stop, and the budget it came from in ms.
+    deadline: Option<(Instant, u64)>,
+    cancel: Option<CancelToken>,
+    /// The frontier cap, and whether the first traversal over it stops the
+    /// run; traversals over it are recorded either way.
+    frontier: Option<(usize, bool)>,
+}
+
+/// How a run under a [`Budget`] ended.
+pub(crate) struct RunEnd {
+    pub(crate) stopped: Option<InterruptCause>,
+    /// Largest traversal that did not fit the frontier cap.
+    pub(crate) frontier: Option<usize>,
+}
+
+struct Active {
+    budget: Budget,
+    calls: u32,
+    stopped: Option<InterruptCause>,
+    frontier: Option<usize>,
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Active>> = const { RefCell::new(None) };
+}
+
+/// Clears the thread's budget when a run ends, even by panic.
+struct Installed;
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        ACTIVE.with(|a| a.borrow_mut().take());
+    }
+}
+
+impl Budget {
+    /// A budget of `limit_ms` from now.
+    pub(crate) fn within(limit_ms: u64) -> Self {
+        let mut budget = Budget::default();
+        budget.limit(limit_ms);
+        budget
+    }
+
+    /// Tighten the deadline to `limit_ms` from now.
+    pub(crate) fn limit(&mut self, limit_ms: u64) {
+        let deadline = Instant::now() + Duration::from_millis(limit_ms);
+        if self.deadline.is_none_or(|(d, _)| deadline < d) {
+            self.deadline = Some((deadline, limit_ms));
+        }
+    }
+
+    pub(crate) fn watch(&mut self, token: &CancelToken) {
+        self.cancel = Some(token.clone());
+    }
+
+    /// Hold traversals during the run to `cap` nodes, recording those over
+    /// it and stopping at the first one when `strict`.
+    pub(crate) fn track_frontier(&mut self, cap: usize, strict: bool) {
+        self.frontier = Some((cap, strict));
+    }
+
+    /// Run `f` with this budget installed on the current thread, returning
+    /// what stopped it, if anything. Inside a run that already has a budget,
+    /// the outer budget stays in charge.
+    pub(crate) fn run<T>(&self, f: impl FnOnce() -> T) -> (T, RunEnd) {
+        if self.deadline.is_none() && self.cancel.is_none() && self.frontier.is_none() {
+            return (f(), RunEnd { stopped: None, frontier: None });
+        }
+        let nested = ACTIVE.with(|a| {
+            let mut a = a.borrow_mut();
+            if a.is_some() {
+                return true;
+            }
+            *a = Some(Active { budget: self.clone(), calls: 0, stopped: None, frontier: None });
+            false
+        });
+        let _installed = (!nested).then_some(Installed);
+        let out = f();
+        let end = ACTIVE.with(|a| match a.borrow().as_ref() {
+            Some(a) => RunEnd { stopped: a.stopped, frontier: a.frontier },
+            None => RunEnd { stopped: None, frontier: None },
+        });
+        (out, end)
+    }
+}
+
+/// The frontier cap of the run on this thread, or `fallback` outside a
+/// run that tracks one.
+pub(crate) fn frontier_cap(fallback: Option<usize>) -> Option<usize> {
+    ACTIVE
+        .with(|a| a.borrow().as_ref().and_then(|a| a.budget.frontier).map(|(cap, _)| cap))
+        .or(fallback)
+}
+
+/// Whether a traversal holding `len` nodes fits `cap`. Inside a run, a
+/// traversal that does not is recorded, and a strict run stops.
+pub(crate) fn frontier_fits(cap: Option<usize>, len: usize) -> bool {
+    if cap.is_none_or(|cap| len <= cap) {
+        return true;
+    }
+    ACTIVE.with(|a| {
+        if let Some(active) = a.borrow_mut().as_mut() {
+            active.frontier = Some(active.frontier.map_or(len, |f| f.max(len)));
+        }
+    });
+    false
+}
+
+fn poll(force: bool) -> bool {
+    ACTIVE.with(|a| {
+        let mut a = a.borrow_mut();
+        let Some(active) = a.as_mut() else { return false };
+        if active.stopped.is_some() || (active.frontier.is_some() && active.budget.frontier.is_some_and(|(_, strict)| strict)) {
+            return true;
+        }
+        active.calls += 1;
+        if !force && active.calls < CHECK_EVERY {
+            return false;
+        }
+        active.calls = 0;
+        if active.budget.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            active.stopped = Some(InterruptCause::Cancelled);
+        } else if let Some((deadline, limit_ms)) = active.budget.deadline {
+            if Instant::now() >= deadline {
+                active.stopped = Some(InterruptCause::TimedOut { limit_ms });
+            }
+        }
+        active.stopped.is_some()
+    })
+}
+
+/// Whether the query running on this thread must stop. Cheap enough for
+/// inner loops: the clock and cancel flag are read every [`CHECK_EVERY`]
+/// calls, and a spent budget stays spent.
+pub(crate) fn spent() -> bool {
+    poll(false)
+}
+
+/// [`spent`], reading the clock and cancel flag on every call.
+pub(crate) fn check() -> bool {
+    poll(true)
+}
+
+/// What stopped the query running on this thread, if anything has.
+pub(crate) fn stopped() -> Option<InterruptCause> {
+    ACTIVE.with(|a| a.borrow().as_ref().and_then(|a| a.stopped))
+}
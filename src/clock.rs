@@ -0,0 +1,80 @@
+//! Wall-clock source for the timestamps written by [`CoreDB`](crate::CoreDB).
+//!
+//! Every write stamp — `_created_unix` / `_updated_unix`, edge
+//! `created_unix`, TTL expiries — and the "now" used by `unexpired()` comes
+//! from one [`Clock`]. The default reads the system time; tests install a
+//! [`ManualClock`] with [`CoreDBBuilder::clock`](crate::CoreDBBuilder::clock)
+//! or [`CoreDB::set_clock`](crate::CoreDB::set_clock) to get the same
+//! timestamps on every run.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+/// A source of the current time in Unix milliseconds.
+///
+/// Closures `Fn() -> i64` implement the trait.
+pub trait Clock: Send + Sync {
+    /// Current time in Unix milliseconds.
+    fn now_millis(&self) -> i64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> i64 + Send + Sync,
+{
+    fn now_millis(&self) -> i64 {
+        self()
+    }
+}
+
+/// The system wall clock. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use sekejap::{CoreDB, ManualClock};
+/// let clock = Arc::new(ManualClock::new(1_700_000_000_000));
+/// let mut db = CoreDB::new();
+/// db.set_clock(clock.clone());
+/// db.put("a", r#"{"_collection":"t"}"#).unwrap();
+/// clock.advance(std::time::Duration::from_secs(60));
+/// db.put("a", r#"{"_collection":"t","v":2}"#).unwrap();
+/// let v: serde_json::Value = serde_json::from_str(&db.get("a").unwrap()).unwrap();
+/// assert_eq!(v["_updated_unix"], 1_700_000_060_000i64);
+/// ```
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+impl ManualClock {
+    /// A clock stopped at `millis` (Unix milliseconds).
+    pub fn new(millis: i64) -> Self {
+        Self { millis: AtomicI64::new(millis) }
+    }
+
+    /// Jump to `millis`; going backwards is allowed.
+    pub fn set(&self, millis: i64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Move forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        self.millis.fetch_add(by.as_millis() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> i64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
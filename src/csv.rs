@@ -0,0 +1,364 @@
+//! CSV bulk loading for [`CoreDB::import_csv`] and
+//! [`CoreDB::import_csv_edges`].
+//!
+//! Rows are parsed here (RFC 4180: quoted fields, doubled quotes, line
+//! breaks inside quotes), turned into payloads or edges by a mapping, and
+//! handed to [`CoreDB::put_many`] / [`CoreDB::link_many`] one chunk at a
+//! time, so a large file costs one WAL sync per chunk rather than per row.
+
+use std::collections::HashMap;
+use std::io;
+
+use serde_json::{Map, Value};
+
+use crate::{CoreDB, EdgeInsert};
+
+/// How a CSV column becomes a payload field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CsvType {
+    /// The cell as a JSON string. The default for unlisted columns.
+    #[default]
+    Text,
+    /// A JSON integer.
+    Int,
+    /// A JSON number.
+    Float,
+    /// `true` / `false`, also `1` / `0`, `yes` / `no`, `t` / `f`.
+    Bool,
+    /// The cell parsed as JSON (arrays, objects).
+    Json,
+    /// Not stored.
+    Skip,
+}
+
+/// Column mapping for [`CoreDB::import_csv`].
+///
+/// Each row becomes node `"{collection}/{slug}"` whose payload holds
+/// `_collection`, `_key` (the slug column) and every other column by header
+/// name, typed by `types`. With `lat_column` and `lon_column` set, those two
+/// become a GeoJSON `Point` under `geometry` instead of plain fields. Empty
+/// cells are left out, except in `Text` columns.
+#[derive(Clone, Debug)]
+pub struct CsvMapping {
+    /// Collection every row is stored in.
+    pub collection: String,
+    /// Header of the column holding each row's key.
+    pub slug_column: String,
+    pub lat_column: Option<String>,
+    pub lon_column: Option<String>,
+    /// Column types by header; unlisted columns are [`CsvType::Text`].
+    pub types: HashMap<String, CsvType>,
+    /// Field separator, `b','` by default.
+    pub delimiter: u8,
+    /// Rows written per [`CoreDB::put_many`] batch.
+    pub chunk_size: usize,
+}
+
+impl CsvMapping {
+    /// A mapping with no geometry and every column as text.
+    pub fn new(collection: &str, slug_column: &str) -> Self {
+        Self {
+            collection: collection.to_string(),
+            slug_column: slug_column.to_string(),
+            lat_column: None,
+            lon_column: None,
+            types: HashMap::new(),
+            delimiter: b',',
+            chunk_size: 10_000,
+        }
+    }
+
+    /// Build `geometry` from these two columns.
+    pub fn point(mut self, lat_column: &str, lon_column: &str) -> Self {
+        self.lat_column = Some(lat_column.to_string());
+        self.lon_column = Some(lon_column.to_string());
+        self
+    }
+
+    /// Store `column` as `ty`.
+    pub fn column(mut self, column: &str, ty: CsvType) -> Self {
+        self.types.insert(column.to_string(), ty);
+        self
+    }
+}
+
+/// Column mapping for [`CoreDB::import_csv_edges`]. The defaults read
+/// headers `from`, `to`, `type` and `weight`.
+#[derive(Clone, Debug)]
+pub struct CsvEdgeMapping {
+    pub from_column: String,
+    pub to_column: String,
+    pub type_column: String,
+    /// Edge strength; a missing column or empty cell means `1.0`.
+    pub weight_column: Option<String>,
+    /// Prefix bare keys in the from / to columns as `"{collection}/{key}"`.
+    /// `None` takes the cells as full slugs.
+    pub from_collection: Option<String>,
+    pub to_collection: Option<String>,
+    /// Field separator, `b','` by default.
+    pub delimiter: u8,
+    /// Edges written per [`CoreDB::link_many`] batch.
+    pub chunk_size: usize,
+}
+
+impl Default for CsvEdgeMapping {
+    fn default() -> Self {
+        Self {
+            from_column: "from".into(),
+            to_column: "to".into(),
+            type_column: "type".into(),
+            weight_column: Some("weight".into()),
+            from_collection: None,
+            to_collection: None,
+            delimiter: b',',
+            chunk_size: 10_000,
+        }
+    }
+}
+
+fn invalid(line: usize, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"))
+}
+
+/// Reads one record at a time, joining physical lines inside quotes.
+struct Records<R> {
+    reader: R,
+    delimiter: u8,
+    /// Physical line number of the next unread line, from 1.
+    line: usize,
+}
+
+impl<R: io::BufRead> Records<R> {
+    fn new(reader: R, delimiter: u8) -> Self {
+        Self { reader, delimiter, line: 1 }
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> io::Result<bool> {
+        buf.clear();
+        if self.reader.read_line(buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        if buf.ends_with('\n') {
+            buf.pop();
+            if buf.ends_with('\r') {
+                buf.pop();
+            }
+        }
+        Ok(true)
+    }
+
+    /// The next non-blank record and the line it starts on.
+    fn next_record(&mut self) -> io::Result<Option<(usize, Vec<String>)>> {
+        let mut buf = String::new();
+        loop {
+            let start = self.line;
+            if !self.read_line(&mut buf)? {
+                return Ok(None);
+            }
+            if start == 1 && buf.starts_with('\u{feff}') {
+                buf.drain(..'\u{feff}'.len_utf8());
+            }
+            if buf.trim().is_empty() {
+                continue;
+            }
+            let delimiter = self.delimiter as char;
+            let mut fields = Vec::new();
+            let mut field = String::new();
+            let mut quoted = false;
+            let mut line = std::mem::take(&mut buf);
+            loop {
+                let mut chars = line.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if quoted {
+                        if c == '"' {
+                            if chars.peek() == Some(&'"') {
+                                chars.next();
+                                field.push('"');
+                            } else {
+                                quoted = false;
+                            }
+                        } else {
+                            field.push(c);
+                        }
+                    } else if c == '"' && field.is_empty() {
+                        quoted = true;
+                    } else if c == delimiter {
+                        fields.push(std::mem::take(&mut field));
+                    } else {
+                        field.push(c);
+                    }
+                }
+                if !quoted {
+                    break;
+                }
+                if !self.read_line(&mut line)? {
+                    return Err(invalid(start, "unterminated quoted field"));
+                }
+                field.push('\n');
+            }
+            fields.push(field);
+            return Ok(Some((start, fields)));
+        }
+    }
+}
+
+/// Header row → column positions, failing on a missing required column.
+struct Header {
+    names: Vec<String>,
+}
+
+impl Header {
+    fn read<R: io::BufRead>(records: &mut Records<R>) -> io::Result<Self> {
+        match records.next_record()? {
+            Some((_, names)) => Ok(Self { names: names.into_iter().map(|n| n.trim().to_string()).collect() }),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "CSV has no header row")),
+        }
+    }
+
+    fn position(&self, column: &str) -> io::Result<usize> {
+        self.names.iter().position(|n| n == column).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("CSV has no column '{column}'"))
+        })
+    }
+}
+
+fn typed(cell: &str, ty: CsvType, line: usize, column: &str) -> io::Result<Option<Value>> {
+    if ty == CsvType::Text {
+        return Ok(Some(Value::String(cell.to_string())));
+    }
+    let cell = cell.trim();
+    if cell.is_empty() || ty == CsvType::Skip {
+        return Ok(None);
+    }
+    let bad = |what: &str| invalid(line, format!("column '{column}': expected {what}, got '{cell}'"));
+    let value = match ty {
+        CsvType::Int => Value::from(cell.parse::<i64>().map_err(|_| bad("an integer"))?),
+        CsvType::Float => {
+            let f = cell.parse::<f64>().map_err(|_| bad("a number"))?;
+            serde_json::Number::from_f64(f).map(Value::Number).ok_or_else(|| bad("a finite number"))?
+        }
+        CsvType::Bool => match cell.to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Value::Bool(true),
+            "false" | "f" | "no" | "n" | "0" => Value::Bool(false),
+            _ => return Err(bad("a boolean")),
+        },
+        CsvType::Json => serde_json::from_str(cell).map_err(|_| bad("JSON"))?,
+        CsvType::Text | CsvType::Skip => unreachable!(),
+    };
+    Ok(Some(value))
+}
+
+fn cell<'a>(fields: &'a [String], at: usize, line: usize, column: &str) -> io::Result<&'a str> {
+    fields.get(at).map(String::as_str).ok_or_else(|| invalid(line, format!("row has no '{column}' cell")))
+}
+
+pub(crate) fn import_nodes(db: &mut CoreDB, reader: impl io::BufRead, mapping: &CsvMapping) -> io::Result<usize> {
+    let mut records = Records::new(reader, mapping.delimiter);
+    let header = Header::read(&mut records)?;
+    let slug_at = header.position(&mapping.slug_column)?;
+    let point = match (&mapping.lat_column, &mapping.lon_column) {
+        (Some(lat), Some(lon)) => Some((header.position(lat)?, header.position(lon)?)),
+        (None, None) => None,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "CSV mapping needs both lat_column and lon_column, or neither",
+            ))
+        }
+    };
+    let fields: Vec<(usize, &str, CsvType)> = header
+        .names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != slug_at && point.is_none_or(|(lat, lon)| *i != lat && *i != lon))
+        .map(|(i, name)| (i, name.as_str(), mapping.types.get(name).copied().unwrap_or_default()))
+        .filter(|(_, _, ty)| *ty != CsvType::Skip)
+        .collect();
+
+    let chunk_size = mapping.chunk_size.max(1);
+    let mut chunk: Vec<(String, String)> = Vec::with_capacity(chunk_size);
+    let mut written = 0;
+    let mut flush = |db: &mut CoreDB, chunk: &mut Vec<(String, String)>| -> io::Result<()> {
+        db.put_many(chunk.iter().map(|(s, j)| (s.as_str(), j.as_str())))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        written += chunk.len();
+        chunk.clear();
+        Ok(())
+    };
+    while let Some((line, row)) = records.next_record()? {
+        let key = cell(&row, slug_at, line, &mapping.slug_column)?.trim();
+        if key.is_empty() {
+            return Err(invalid(line, format!("empty '{}'", mapping.slug_column)));
+        }
+        let mut payload = Map::new();
+        payload.insert("_collection".into(), Value::String(mapping.collection.clone()));
+        payload.insert("_key".into(), Value::String(key.to_string()));
+        for &(at, name, ty) in &fields {
+            let raw = row.get(at).map(String::as_str).unwrap_or("");
+            if let Some(value) = typed(raw, ty, line, name)? {
+                payload.insert(name.to_string(), value);
+            }
+        }
+        if let Some((lat_at, lon_at)) = point {
+            let lat = typed(cell(&row, lat_at, line, &header.names[lat_at])?, CsvType::Float, line, &header.names[lat_at])?;
+            let lon = typed(cell(&row, lon_at, line, &header.names[lon_at])?, CsvType::Float, line, &header.names[lon_at])?;
+            if let (Some(lat), Some(lon)) = (lat, lon) {
+                payload.insert(
+                    "geometry".into(),
+                    serde_json::json!({ "type": "Point", "coordinates": [lon, lat] }),
+                );
+            }
+        }
+        chunk.push((format!("{}/{key}", mapping.collection), Value::Object(payload).to_string()));
+        if chunk.len() >= chunk_size {
+            flush(db, &mut chunk)?;
+        }
+    }
+    flush(db, &mut chunk)?;
+    Ok(written)
+}
+
+pub(crate) fn import_edges(db: &mut CoreDB, reader: impl io::BufRead, mapping: &CsvEdgeMapping) -> io::Result<usize> {
+    let mut records = Records::new(reader, mapping.delimiter);
+    let header = Header::read(&mut records)?;
+    let from_at = header.position(&mapping.from_column)?;
+    let to_at = header.position(&mapping.to_column)?;
+    let type_at = header.position(&mapping.type_column)?;
+    let weight_at = mapping.weight_column.as_deref().and_then(|c| header.position(c).ok());
+    let slug = |collection: &Option<String>, key: &str| match collection {
+        Some(c) => format!("{c}/{key}"),
+        None => key.to_string(),
+    };
+
+    let chunk_size = mapping.chunk_size.max(1);
+    let mut chunk: Vec<EdgeInsert> = Vec::with_capacity(chunk_size);
+    let mut written = 0;
+    while let Some((line, row)) = records.next_record()? {
+        let from = cell(&row, from_at, line, &mapping.from_column)?.trim();
+        let to = cell(&row, to_at, line, &mapping.to_column)?.trim();
+        let edge_type = cell(&row, type_at, line, &mapping.type_column)?.trim();
+        if from.is_empty() || to.is_empty() || edge_type.is_empty() {
+            return Err(invalid(line, "edge needs from, to and type"));
+        }
+        let strength = match weight_at {
+            Some(at) => match typed(row.get(at).map(String::as_str).unwrap_or(""), CsvType::Float, line, &header.names[at])? {
+                Some(w) => w.as_f64().unwrap_or(1.0) as f32,
+                None => 1.0,
+            },
+            None => 1.0,
+        };
+        chunk.push(EdgeInsert {
+            from: slug(&mapping.from_collection, from),
+            to: slug(&mapping.to_collection, to),
+            edge_type: edge_type.to_string(),
+            strength,
+            props_json: None,
+        });
+        if chunk.len() >= chunk_size {
+            written += db.link_many(chunk.drain(..)).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+    }
+    written += db.link_many(chunk).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(written)
+}
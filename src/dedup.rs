@@ -0,0 +1,208 @@
+//! Duplicate-candidate detection for [`CoreDB::duplicate_candidates`].
+//!
+//! Pairs are proposed by blocking (shared tokens, shared neighbours, vector
+//! nearest neighbours) so the job never compares every pair, then scored by
+//! combining whichever signals both nodes carry.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::query::json_path_get;
+use crate::vector::{CosineDistance, Distance, VectorAccess};
+use crate::{sk_hash, CoreDB};
+
+/// Tuning knobs for [`CoreDB::duplicate_candidates`].
+#[derive(Debug, Clone)]
+pub struct DedupOptions {
+    /// Vector field compared by cosine similarity. `None` disables the signal.
+    pub vector_field: Option<String>,
+    /// Nearest neighbours per node considered by the vector signal.
+    pub vector_k: usize,
+    /// Payload fields (dotted paths allowed) whose tokens are compared by
+    /// Jaccard overlap. Empty disables the signal.
+    pub text_fields: Vec<String>,
+    /// Pairs scoring below this are not reported.
+    pub min_confidence: f64,
+    /// Tokens or neighbours shared by more nodes than this are ignored when
+    /// proposing pairs (they say little and would make the job quadratic).
+    pub max_block_size: usize,
+    /// Relative weights of the vector, text and shared-entity signals. A
+    /// signal missing on either node drops out of the weighted mean.
+    pub vector_weight: f64,
+    pub text_weight: f64,
+    pub entity_weight: f64,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            vector_field: None,
+            vector_k: 5,
+            text_fields: Vec::new(),
+            min_confidence: 0.5,
+            max_block_size: 64,
+            vector_weight: 0.5,
+            text_weight: 0.3,
+            entity_weight: 0.2,
+        }
+    }
+}
+
+/// A pair of nodes that look like the same entity. `a < b` by slug.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCandidate {
+    pub a: String,
+    pub b: String,
+    /// Weighted mean of the available signals, in `0.0..=1.0`.
+    pub confidence: f64,
+    /// Cosine similarity of the two vectors, if both have one.
+    pub vector_similarity: Option<f64>,
+    /// Jaccard overlap of the text-field tokens, if both have any.
+    pub text_overlap: Option<f64>,
+    /// Number of nodes linked (in either direction) to both.
+    pub shared_entities: usize,
+}
+
+struct Profile {
+    tokens: HashSet<String>,
+    neighbours: HashSet<u64>,
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let inter = a.intersection(b).count();
+    Some(inter as f64 / (a.len() + b.len() - inter) as f64)
+}
+
+/// Add every pair within each group, skipping singleton and oversized groups.
+fn block_pairs(groups: impl Iterator<Item = Vec<u64>>, max: usize, pairs: &mut HashSet<(u64, u64)>) {
+    for group in groups {
+        if group.len() < 2 || group.len() > max {
+            continue;
+        }
+        for (i, &x) in group.iter().enumerate() {
+            for &y in &group[i + 1..] {
+                pairs.insert(ordered(x, y));
+            }
+        }
+    }
+}
+
+fn ordered(x: u64, y: u64) -> (u64, u64) {
+    if x < y { (x, y) } else { (y, x) }
+}
+
+pub(crate) fn candidates(db: &CoreDB, collection: &str, opts: &DedupOptions) -> Vec<DuplicateCandidate> {
+    let members: Vec<u64> = db.collection_members(sk_hash(collection)).cloned().unwrap_or_default();
+    let member_set: HashSet<u64> = members.iter().copied().collect();
+
+    let mut profiles: HashMap<u64, Profile> = HashMap::with_capacity(members.len());
+    for &h in &members {
+        let mut tokens = HashSet::new();
+        if !opts.text_fields.is_empty() {
+            if let Some(payload) = db.get_payload(h) {
+                for f in &opts.text_fields {
+                    if let Some(serde_json::Value::String(s)) = json_path_get(f, &payload) {
+                        tokens.extend(crate::bm25::tokenize(&s));
+                    }
+                }
+            }
+        }
+        let neighbours: HashSet<u64> = db.fwd_edges(h).into_iter().flatten()
+            .chain(db.rev_edges(h).into_iter().flatten())
+            .map(|e| e.other)
+            .filter(|&o| o != h && !member_set.contains(&o))
+            .collect();
+        profiles.insert(h, Profile { tokens, neighbours });
+    }
+
+    // ── Blocking ─────────────────────────────────────────────────────────────
+    let mut pairs: HashSet<(u64, u64)> = HashSet::new();
+    let mut by_token: HashMap<&str, Vec<u64>> = HashMap::new();
+    let mut by_neighbour: HashMap<u64, Vec<u64>> = HashMap::new();
+    for &h in &members {
+        let p = &profiles[&h];
+        for t in &p.tokens {
+            by_token.entry(t.as_str()).or_default().push(h);
+        }
+        for &n in &p.neighbours {
+            by_neighbour.entry(n).or_default().push(h);
+        }
+    }
+    block_pairs(by_token.into_values(), opts.max_block_size, &mut pairs);
+    block_pairs(by_neighbour.into_values(), opts.max_block_size, &mut pairs);
+
+    let vectors = opts.vector_field.as_deref().and_then(|f| Some((db.vector_field(f)?, f)));
+    if let Some((store, field)) = vectors {
+        for &h in &members {
+            let Some(v) = store.get(h) else { continue };
+            let near: Vec<u64> = match db.hnsw_index(field) {
+                Some(hnsw) => {
+                    let k = opts.vector_k + 1;
+                    hnsw.search::<CosineDistance, _>(v, store, k, (k * 3).max(50))
+                }
+                None => {
+                    let mut scored: Vec<(u64, f32)> = members.iter()
+                        .filter(|&&o| o != h)
+                        .filter_map(|&o| Some((o, CosineDistance::eval(v, store.get(o)?))))
+                        .collect();
+                    scored.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+                    scored.truncate(opts.vector_k);
+                    scored.into_iter().map(|(o, _)| o).collect()
+                }
+            };
+            for o in near {
+                if o != h && member_set.contains(&o) {
+                    pairs.insert(ordered(h, o));
+                }
+            }
+        }
+    }
+
+    // ── Scoring ──────────────────────────────────────────────────────────────
+    let mut out = Vec::new();
+    for (x, y) in pairs {
+        let (px, py) = (&profiles[&x], &profiles[&y]);
+        let vector_similarity = vectors.and_then(|(store, _)| {
+            let d = CosineDistance::eval(store.get(x)?, store.get(y)?);
+            Some((1.0 - d as f64).clamp(0.0, 1.0))
+        });
+        let text_overlap = jaccard(&px.tokens, &py.tokens);
+        let shared_entities = px.neighbours.intersection(&py.neighbours).count();
+        let entity = jaccard(&px.neighbours, &py.neighbours);
+
+        let (mut sum, mut weight) = (0.0, 0.0);
+        for (signal, w) in [
+            (vector_similarity, opts.vector_weight),
+            (text_overlap, opts.text_weight),
+            (entity, opts.entity_weight),
+        ] {
+            if let Some(s) = signal {
+                sum += s * w;
+                weight += w;
+            }
+        }
+        if weight <= 0.0 {
+            continue;
+        }
+        let confidence = sum / weight;
+        if confidence < opts.min_confidence {
+            continue;
+        }
+        let (Some(sx), Some(sy)) = (db.slug_of(x), db.slug_of(y)) else { continue };
+        let (a, b) = if sx < sy { (sx, sy) } else { (sy, sx) };
+        out.push(DuplicateCandidate {
+            a: a.to_string(),
+            b: b.to_string(),
+            confidence,
+            vector_similarity,
+            text_overlap,
+            shared_entities,
+        });
+    }
+    out.sort_by(|p, q| {
+        q.confidence.total_cmp(&p.confidence).then_with(|| (&p.a, &p.b).cmp(&(&q.a, &q.b)))
+    });
+    out
+}
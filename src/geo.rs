@@ -125,7 +125,7 @@ pub fn extract_centroid(payload: &Value) -> Option<(f64, f64)> {
 // ── Spatial metadata ─────────────────────────────────────────────────────────
 
 /// Cached spatial metadata for a node: centroid + axis-aligned bounding box.
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct SpatialMeta {
     pub centroid_lat: f64,
     pub centroid_lon: f64,
@@ -239,6 +239,21 @@ impl SpatialGrid {
         self.meta.get(&hash)
     }
 
+    /// Number of nodes in the grid.
+    pub fn node_count(&self) -> usize {
+        self.meta.len()
+    }
+
+    /// Every indexed node with its cached metadata.
+    pub fn entries(&self) -> impl Iterator<Item = (u64, &SpatialMeta)> {
+        self.meta.iter().map(|(&h, m)| (h, m))
+    }
+
+    /// Cell entries pointing at a node the grid no longer holds.
+    pub fn orphan_cell_entries(&self) -> usize {
+        self.cells.values().flatten().filter(|h| !self.meta.contains_key(h)).count()
+    }
+
     /// Return candidate node hashes within `km` of `(lat, lon)`.
     pub fn candidates_within_distance(&self, lat: f64, lon: f64, km: f64) -> Vec<u64> {
         // Convert km to approximate degree range (conservative)
@@ -254,6 +269,21 @@ impl SpatialGrid {
         )
     }
 
+    /// Rough number of cell entries `candidates_within_distance` would walk:
+    /// cells covered by the query box times the average occupancy of a
+    /// non-empty cell, capped at the node count. O(1).
+    pub fn estimate_within_distance(&self, lat: f64, km: f64) -> usize {
+        if self.cells.is_empty() {
+            return 0;
+        }
+        let deg = km / 111.0;
+        let lon_expand = deg / (lat.to_radians().cos().abs().max(0.01));
+        let span = |expand: f64| ((2.0 * expand / self.cell_size).ceil() + 1.0).max(1.0);
+        let covered = (span(deg) * span(lon_expand)).min(self.cells.len() as f64);
+        let per_cell = self.meta.len() as f64 / self.cells.len() as f64;
+        ((covered * per_cell).ceil() as usize).min(self.meta.len())
+    }
+
     /// Return candidate node hashes whose bbox overlaps the query bbox.
     pub fn candidates_in_bbox(
         &self,
@@ -326,6 +356,66 @@ impl SpatialGrid {
         result
     }
 
+    // ── Binary persistence (spatial.bin) ─────────────────────────────────────
+
+    /// Serialize cell size, per-node metadata and cell lists, so a reload
+    /// skips recomputing extents and cell membership.
+    pub fn write_binary(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        w.write_all(&self.cell_size.to_le_bytes())?;
+        w.write_all(&(self.meta.len() as u64).to_le_bytes())?;
+        for (hash, m) in &self.meta {
+            w.write_all(&hash.to_le_bytes())?;
+            for v in [m.centroid_lat, m.centroid_lon, m.bbox_min_lat, m.bbox_min_lon, m.bbox_max_lat, m.bbox_max_lon] {
+                w.write_all(&v.to_le_bytes())?;
+            }
+        }
+        w.write_all(&(self.cells.len() as u64).to_le_bytes())?;
+        for (&(cy, cx), hashes) in &self.cells {
+            w.write_all(&cy.to_le_bytes())?;
+            w.write_all(&cx.to_le_bytes())?;
+            w.write_all(&(hashes.len() as u32).to_le_bytes())?;
+            for h in hashes {
+                w.write_all(&h.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a grid written by [`write_binary`](Self::write_binary).
+    pub fn read_binary(r: &mut impl std::io::Read) -> std::io::Result<Self> {
+        fn take<const N: usize>(r: &mut impl std::io::Read) -> std::io::Result<[u8; N]> {
+            let mut buf = [0u8; N];
+            r.read_exact(&mut buf)?;
+            Ok(buf)
+        }
+        let cell_size = f64::from_le_bytes(take(r)?);
+        let mut meta = HashMap::new();
+        for _ in 0..u64::from_le_bytes(take(r)?) {
+            let hash = u64::from_le_bytes(take(r)?);
+            let mut v = [0f64; 6];
+            for x in &mut v {
+                *x = f64::from_le_bytes(take(r)?);
+            }
+            meta.insert(hash, SpatialMeta {
+                centroid_lat: v[0],
+                centroid_lon: v[1],
+                bbox_min_lat: v[2],
+                bbox_min_lon: v[3],
+                bbox_max_lat: v[4],
+                bbox_max_lon: v[5],
+            });
+        }
+        let mut cells = HashMap::new();
+        for _ in 0..u64::from_le_bytes(take(r)?) {
+            let key = (i32::from_le_bytes(take(r)?), i32::from_le_bytes(take(r)?));
+            let hashes = (0..u32::from_le_bytes(take(r)?))
+                .map(|_| take(r).map(u64::from_le_bytes))
+                .collect::<std::io::Result<Vec<u64>>>()?;
+            cells.insert(key, hashes);
+        }
+        Ok(Self { cell_size, cells, meta })
+    }
+
     // ── Internal helpers ─────────────────────────────────────────────────────
 
     fn cell_key(&self, lat: f64, lon: f64) -> (i32, i32) {
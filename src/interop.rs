@@ -0,0 +1,303 @@
+//! Graph exports for other tools: GraphML for [`CoreDB::export_graphml`]
+//! (Gephi, yEd, NetworkX) and openCypher `CREATE` statements for
+//! [`CoreDB::export_cypher`] (Neo4j, Memgraph).
+//!
+//! Both walk the same [`Graph`] view: live nodes by slug, their top-level
+//! payload fields, and every edge between live nodes with its type name,
+//! strength, creation time and metadata fields.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use serde_json::{Map, Value};
+
+use crate::storage::wal::WalEntry;
+use crate::CoreDB;
+
+struct GraphNode {
+    slug: String,
+    collection: String,
+    fields: Map<String, Value>,
+}
+
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    edge_type: String,
+    strength: f32,
+    created_unix: i64,
+    meta: Map<String, Value>,
+}
+
+/// Nodes sorted by slug and edges by (from, to, type, created), so repeated
+/// exports of the same data are byte-identical.
+struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+impl Graph {
+    fn of(db: &CoreDB) -> Self {
+        let mut nodes: Vec<GraphNode> = db
+            .nodes
+            .values()
+            .filter_map(|n| {
+                let fields = match db.payload_store.get(n.payload_offset, n.payload_len)? {
+                    Value::Object(mut map) => {
+                        map.remove("_collection");
+                        map
+                    }
+                    _ => Map::new(),
+                };
+                Some(GraphNode { slug: n.slug.clone(), collection: n.collection.clone(), fields })
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.slug.cmp(&b.slug));
+        let index: std::collections::HashMap<&str, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (n.slug.as_str(), i)).collect();
+
+        let mut edges = Vec::new();
+        for (from_h, list) in db.edges.iter_fwd() {
+            let Some(from) = db.nodes.get(from_h) else { continue };
+            for e in list {
+                let Some(to) = db.nodes.get(&e.other) else { continue };
+                let (Some(&from_i), Some(&to_i)) = (index.get(from.slug.as_str()), index.get(to.slug.as_str())) else {
+                    continue;
+                };
+                let (edge_type, meta) = match db.edge_record(&from.slug, &to.slug, e) {
+                    WalEntry::Link { edge_type, .. } => (edge_type, Map::new()),
+                    WalEntry::LinkMeta { edge_type, meta, .. } => match serde_json::from_str(&meta) {
+                        Ok(Value::Object(map)) => (edge_type, map),
+                        _ => (edge_type, Map::new()),
+                    },
+                    _ => continue,
+                };
+                edges.push(GraphEdge {
+                    from: from_i,
+                    to: to_i,
+                    edge_type,
+                    strength: e.strength,
+                    created_unix: e.created_unix,
+                    meta,
+                });
+            }
+        }
+        edges.sort_by(|a, b| {
+            (a.from, a.to, &a.edge_type, a.created_unix).cmp(&(b.from, b.to, &b.edge_type, b.created_unix))
+        });
+        Graph { nodes, edges }
+    }
+}
+
+// ── GraphML ───────────────────────────────────────────────────────────────────
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// GraphML attribute type covering every value seen for one key.
+fn graphml_type(values: &mut dyn Iterator<Item = &Value>) -> &'static str {
+    let (mut bools, mut ints, mut numbers, mut total) = (0, 0, 0, 0);
+    for v in values {
+        total += 1;
+        match v {
+            Value::Bool(_) => bools += 1,
+            Value::Number(n) if n.is_i64() || n.is_u64() => ints += 1,
+            Value::Number(_) => numbers += 1,
+            _ => {}
+        }
+    }
+    match total {
+        0 => "string",
+        _ if bools == total => "boolean",
+        _ if ints == total => "long",
+        _ if ints + numbers == total => "double",
+        _ => "string",
+    }
+}
+
+/// Scalars as text; arrays and objects as JSON.
+fn graphml_text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Key declarations for the fields of one element kind: field → (id, type).
+fn graphml_keys<'a>(
+    prefix: &str,
+    maps: impl Iterator<Item = &'a Map<String, Value>> + Clone,
+) -> BTreeMap<String, (String, &'static str)> {
+    let mut names: Vec<&String> = maps.clone().flat_map(|m| m.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let ty = graphml_type(&mut maps.clone().filter_map(|m| m.get(name)).filter(|v| !v.is_null()));
+            (name.clone(), (format!("{prefix}{i}"), ty))
+        })
+        .collect()
+}
+
+pub(crate) fn write_graphml(db: &CoreDB, mut w: impl io::Write) -> io::Result<usize> {
+    let graph = Graph::of(db);
+    let node_keys = graphml_keys("nk", graph.nodes.iter().map(|n| &n.fields));
+    let edge_keys = graphml_keys("ek", graph.edges.iter().map(|e| &e.meta));
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(w, r#"  <key id="collection" for="node" attr.name="collection" attr.type="string"/>"#)?;
+    for (name, (id, ty)) in &node_keys {
+        writeln!(w, r#"  <key id="{id}" for="node" attr.name="{}" attr.type="{ty}"/>"#, xml_escape(name))?;
+    }
+    writeln!(w, r#"  <key id="label" for="edge" attr.name="label" attr.type="string"/>"#)?;
+    writeln!(w, r#"  <key id="weight" for="edge" attr.name="weight" attr.type="double"/>"#)?;
+    writeln!(w, r#"  <key id="created_unix" for="edge" attr.name="created_unix" attr.type="long"/>"#)?;
+    for (name, (id, ty)) in &edge_keys {
+        writeln!(w, r#"  <key id="{id}" for="edge" attr.name="{}" attr.type="{ty}"/>"#, xml_escape(name))?;
+    }
+    writeln!(w, r#"  <graph id="sekejap" edgedefault="directed">"#)?;
+
+    let data = |w: &mut dyn io::Write, keys: &BTreeMap<String, (String, &str)>, fields: &Map<String, Value>| {
+        for (name, v) in fields {
+            if v.is_null() {
+                continue;
+            }
+            if let Some((id, _)) = keys.get(name) {
+                writeln!(w, r#"      <data key="{id}">{}</data>"#, xml_escape(&graphml_text(v)))?;
+            }
+        }
+        io::Result::Ok(())
+    };
+    for node in &graph.nodes {
+        writeln!(w, r#"    <node id="{}">"#, xml_escape(&node.slug))?;
+        if !node.collection.is_empty() {
+            writeln!(w, r#"      <data key="collection">{}</data>"#, xml_escape(&node.collection))?;
+        }
+        data(&mut w, &node_keys, &node.fields)?;
+        writeln!(w, "    </node>")?;
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        writeln!(
+            w,
+            r#"    <edge id="e{i}" source="{}" target="{}">"#,
+            xml_escape(&graph.nodes[edge.from].slug),
+            xml_escape(&graph.nodes[edge.to].slug),
+        )?;
+        writeln!(w, r#"      <data key="label">{}</data>"#, xml_escape(&edge.edge_type))?;
+        writeln!(w, r#"      <data key="weight">{}</data>"#, edge.strength)?;
+        writeln!(w, r#"      <data key="created_unix">{}</data>"#, edge.created_unix)?;
+        data(&mut w, &edge_keys, &edge.meta)?;
+        writeln!(w, "    </edge>")?;
+    }
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")?;
+    w.flush()?;
+    Ok(graph.nodes.len() + graph.edges.len())
+}
+
+// ── openCypher ────────────────────────────────────────────────────────────────
+
+fn cypher_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// A label, relationship type or property key, backquoted unless it is a
+/// plain identifier.
+fn cypher_name(s: &str) -> String {
+    let plain = s.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        s.to_string()
+    } else {
+        format!("`{}`", s.replace('`', "``"))
+    }
+}
+
+/// A property value. Neo4j properties hold scalars and lists of scalars,
+/// so objects and mixed lists are stored as JSON strings.
+fn cypher_value(v: &Value) -> String {
+    match v {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => cypher_string(s),
+        Value::Array(items) if items.iter().all(|i| i.is_string()) || items.iter().all(|i| i.is_number()) => {
+            let parts: Vec<String> = items.iter().map(cypher_value).collect();
+            format!("[{}]", parts.join(", "))
+        }
+        other => cypher_string(&other.to_string()),
+    }
+}
+
+fn cypher_props(props: &[(&str, String)]) -> String {
+    let parts: Vec<String> = props.iter().map(|(k, v)| format!("{}: {v}", cypher_name(k))).collect();
+    format!("{{{}}}", parts.join(", "))
+}
+
+fn cypher_label(collection: &str) -> String {
+    cypher_name(if collection.is_empty() { "Node" } else { collection })
+}
+
+pub(crate) fn write_cypher(db: &CoreDB, mut w: impl io::Write) -> io::Result<usize> {
+    let graph = Graph::of(db);
+    for node in &graph.nodes {
+        let mut props = vec![("_slug", cypher_string(&node.slug))];
+        props.extend(
+            node.fields.iter().filter(|(k, v)| !v.is_null() && k.as_str() != "_slug").map(|(k, v)| (k.as_str(), cypher_value(v))),
+        );
+        writeln!(w, "CREATE (:{} {});", cypher_label(&node.collection), cypher_props(&props))?;
+    }
+    for edge in &graph.edges {
+        let (from, to) = (&graph.nodes[edge.from], &graph.nodes[edge.to]);
+        let mut props = vec![
+            ("weight", format!("{:?}", edge.strength)),
+            ("created_unix", edge.created_unix.to_string()),
+        ];
+        props.extend(
+            edge.meta
+                .iter()
+                .filter(|(k, v)| !v.is_null() && !matches!(k.as_str(), "weight" | "created_unix"))
+                .map(|(k, v)| (k.as_str(), cypher_value(v))),
+        );
+        writeln!(
+            w,
+            "MATCH (a:{} {{_slug: {}}}), (b:{} {{_slug: {}}}) CREATE (a)-[:{} {}]->(b);",
+            cypher_label(&from.collection),
+            cypher_string(&from.slug),
+            cypher_label(&to.collection),
+            cypher_string(&to.slug),
+            cypher_name(&edge.edge_type),
+            cypher_props(&props),
+        )?;
+    }
+    w.flush()?;
+    Ok(graph.nodes.len() + graph.edges.len())
+}
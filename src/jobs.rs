@@ -0,0 +1,393 @@
+//! Progress, cancellation and completion for long-running operations.
+//!
+//! Backups, incremental restores, compaction, HNSW builds and deferred index
+//! rebuilds each run as a [`Job`] in the database's [`Jobs`] registry, however
+//! they were started: a direct call such as [`CoreDB::compact`], a
+//! [`JobRequest`] passed to [`CoreDB::run_job`], the HTTP server or a binding.
+//! The operation still runs on the calling thread and holds the database for
+//! its duration; [`CoreDB::jobs`] hands out a registry handle that another
+//! thread keeps, without locking the database, to watch progress and cancel.
+//!
+//! Cancellation is cooperative. An operation checks for it between units of
+//! work while nothing has been changed yet, or while what has been done can
+//! be discarded, and then fails with [`io::ErrorKind::Interrupted`]:
+//!
+//! | Kind            | Progress unit                | Cancellable                        |
+//! |-----------------|------------------------------|------------------------------------|
+//! | `backup`        | bytes copied                 | between files; `backup.json` is not written |
+//! | `restore`       | records verified, then the apply counting as many | until verification ends |
+//! | `compact`       | live payloads rewritten, then 2 stages | until payloads are rewritten |
+//! | `hnsw_build`    | vectors inserted             | throughout; the old index is kept  |
+//! | `index_rebuild` | index families rebuilt       | between families; the rebuild stays pending |
+//!
+//! [`CoreDB::compact`]: crate::CoreDB::compact
+//! [`CoreDB::run_job`]: crate::CoreDB::run_job
+//! [`CoreDB::jobs`]: crate::CoreDB::jobs
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+/// Finished jobs kept for [`Jobs::list`] besides the running ones.
+const FINISHED_KEPT: usize = 32;
+
+/// What a [`Job`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    /// [`CoreDB::backup_to`](crate::CoreDB::backup_to).
+    Backup,
+    /// [`CoreDB::restore_incremental`](crate::CoreDB::restore_incremental).
+    Restore,
+    /// [`CoreDB::compact`](crate::CoreDB::compact).
+    Compact,
+    /// [`CoreDB::build_hnsw_index`](crate::CoreDB::build_hnsw_index) and
+    /// [`build_hnsw_with`](crate::CoreDB::build_hnsw_with).
+    HnswBuild,
+    /// [`CoreDB::rebuild_pending_indexes`](crate::CoreDB::rebuild_pending_indexes).
+    IndexRebuild,
+}
+
+impl JobKind {
+    /// `backup`, `restore`, `compact`, `hnsw_build` or `index_rebuild`.
+    pub fn name(self) -> &'static str {
+        match self {
+            JobKind::Backup => "backup",
+            JobKind::Restore => "restore",
+            JobKind::Compact => "compact",
+            JobKind::HnswBuild => "hnsw_build",
+            JobKind::IndexRebuild => "index_rebuild",
+        }
+    }
+}
+
+/// Where a [`Job`] stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    /// Failed with this error message.
+    Failed(String),
+    /// Stopped by [`Job::cancel`] before it changed anything it could not
+    /// take back.
+    Cancelled,
+}
+
+impl JobState {
+    /// `running`, `succeeded`, `failed` or `cancelled`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed(_) => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != JobState::Running
+    }
+}
+
+/// A point-in-time view of a [`Job`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobInfo {
+    /// Unique within one database handle, in start order.
+    pub id: u64,
+    pub kind: JobKind,
+    /// The backup directory, restored file or vector field; empty for
+    /// whole-database jobs.
+    pub target: String,
+    pub state: JobState,
+    /// Units of work done out of `total`; see the [module docs](self).
+    pub done: u64,
+    pub total: u64,
+    /// Time since the job started, or its run time once finished.
+    pub elapsed_ms: u64,
+}
+
+impl JobInfo {
+    /// Fraction done in `0.0..=1.0`; `1.0` once the job succeeded.
+    pub fn progress(&self) -> f64 {
+        match (&self.state, self.total) {
+            (JobState::Succeeded, _) => 1.0,
+            (_, 0) => 0.0,
+            (_, total) => (self.done.min(total) as f64) / total as f64,
+        }
+    }
+
+    /// `{"id", "kind", "target", "state", "done", "total", "progress",
+    /// "elapsed_ms"}`, plus `"error"` for a failed job.
+    pub fn to_json(&self) -> Value {
+        let mut out = json!({
+            "id": self.id,
+            "kind": self.kind.name(),
+            "target": self.target,
+            "state": self.state.name(),
+            "done": self.done,
+            "total": self.total,
+            "progress": self.progress(),
+            "elapsed_ms": self.elapsed_ms,
+        });
+        if let JobState::Failed(msg) = &self.state {
+            out["error"] = json!(msg);
+        }
+        out
+    }
+}
+
+struct JobInner {
+    id: u64,
+    kind: JobKind,
+    target: String,
+    started: Instant,
+    done: AtomicU64,
+    total: AtomicU64,
+    cancelled: AtomicBool,
+    /// State and the run time once finished.
+    finished: Mutex<(JobState, u64)>,
+}
+
+/// A handle on one running or finished job. Cheap to clone and `Send`, so
+/// it can be kept by another thread.
+#[derive(Clone)]
+pub struct Job(Arc<JobInner>);
+
+impl Job {
+    fn new(id: u64, kind: JobKind, target: String) -> Self {
+        Job(Arc::new(JobInner {
+            id,
+            kind,
+            target,
+            started: Instant::now(),
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+            finished: Mutex::new((JobState::Running, 0)),
+        }))
+    }
+
+    /// A job outside any registry, for operations that report progress
+    /// only when started as a job of their own.
+    pub(crate) fn detached(kind: JobKind) -> Self {
+        Job::new(0, kind, String::new())
+    }
+
+    pub fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    pub fn info(&self) -> JobInfo {
+        let (state, run_ms) = self.0.finished.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        JobInfo {
+            id: self.0.id,
+            kind: self.0.kind,
+            target: self.0.target.clone(),
+            elapsed_ms: if state.is_finished() { run_ms } else { self.0.started.elapsed().as_millis() as u64 },
+            state,
+            done: self.0.done.load(Ordering::Relaxed),
+            total: self.0.total.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Ask the job to stop at its next cancellation point. Returns whether
+    /// it was still running; it may yet finish if no such point is left.
+    pub fn cancel(&self) -> bool {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+        !self.info().state.is_finished()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Record progress without offering to stop.
+    pub(crate) fn report(&self, done: u64, total: u64) {
+        self.0.total.store(total, Ordering::Relaxed);
+        self.0.done.store(done, Ordering::Relaxed);
+    }
+
+    /// Record progress and stop with `Interrupted` if cancelled.
+    pub(crate) fn checkpoint(&self, done: u64, total: u64) -> io::Result<()> {
+        self.report(done, total);
+        if self.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "job cancelled"));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.info().fmt(f)
+    }
+}
+
+type Listener = Arc<dyn Fn(&JobInfo) + Send + Sync>;
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    jobs: Vec<Job>,
+    listeners: Vec<Listener>,
+}
+
+/// The jobs of one database: those running and the most recent finished
+/// ones. Returned by [`CoreDB::jobs`](crate::CoreDB::jobs); clones share the
+/// registry.
+///
+/// ```
+/// # use sekejap::{CoreDB, JobKind, JobState};
+/// let mut db = CoreDB::new();
+/// let jobs = db.jobs();
+/// let done = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+/// let seen = done.clone();
+/// jobs.on_complete(move |job| seen.lock().unwrap().push(job.kind));
+///
+/// db.ingest_vectors("emb", (0..4).map(|i| (format!("v{i}"), vec![i as f32, 1.0])));
+/// db.build_hnsw_index("emb", 16, 100).unwrap();
+/// let last = jobs.list().pop().unwrap();
+/// assert_eq!((last.kind, last.state, last.done), (JobKind::HnswBuild, JobState::Succeeded, 4));
+/// assert_eq!(*done.lock().unwrap(), [JobKind::HnswBuild]);
+/// ```
+#[derive(Clone, Default)]
+pub struct Jobs(Arc<Mutex<Registry>>);
+
+impl Jobs {
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Running and recently finished jobs, oldest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.registry().jobs.iter().map(Job::info).collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.registry().jobs.iter().find(|j| j.id() == id).cloned()
+    }
+
+    /// [`Job::cancel`] by id; `false` for an unknown or finished job.
+    pub fn cancel(&self, id: u64) -> bool {
+        self.get(id).is_some_and(|job| job.cancel())
+    }
+
+    /// Call `f` on the thread that ran each job, as it finishes in any
+    /// state.
+    pub fn on_complete(&self, f: impl Fn(&JobInfo) + Send + Sync + 'static) {
+        self.registry().listeners.push(Arc::new(f));
+    }
+
+    pub(crate) fn start(&self, kind: JobKind, target: String) -> Job {
+        let mut reg = self.registry();
+        reg.next_id += 1;
+        let job = Job::new(reg.next_id, kind, target);
+        reg.jobs.push(job.clone());
+        let finished = reg.jobs.iter().filter(|j| j.info().state.is_finished()).count();
+        if finished > FINISHED_KEPT {
+            let mut excess = finished - FINISHED_KEPT;
+            reg.jobs.retain(|j| {
+                let drop = excess > 0 && j.info().state.is_finished();
+                excess -= drop as usize;
+                !drop
+            });
+        }
+        job
+    }
+
+    /// Mark `job` finished from its outcome and tell the listeners. An
+    /// error after [`Job::cancel`] counts as a cancellation.
+    pub(crate) fn finish<T, E: fmt::Display>(&self, job: &Job, result: Result<T, E>) -> Result<T, E> {
+        let state = match &result {
+            Ok(_) => JobState::Succeeded,
+            Err(_) if job.is_cancelled() => JobState::Cancelled,
+            Err(e) => JobState::Failed(e.to_string()),
+        };
+        *job.0.finished.lock().unwrap_or_else(|e| e.into_inner()) =
+            (state, job.0.started.elapsed().as_millis() as u64);
+        let info = job.info();
+        let listeners = self.registry().listeners.clone();
+        for listener in listeners {
+            listener(&info);
+        }
+        result
+    }
+
+    /// Run `work` as a new job of `kind`.
+    pub(crate) fn run<T, E: fmt::Display>(
+        &self,
+        kind: JobKind,
+        target: String,
+        work: impl FnOnce(&Job) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let job = self.start(kind, target);
+        self.finish(&job, work(&job))
+    }
+}
+
+/// A job for [`CoreDB::run_job`](crate::CoreDB::run_job), as sent by the
+/// server and the bindings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobRequest {
+    /// [`CoreDB::backup_to`](crate::CoreDB::backup_to) into `dest`.
+    Backup { dest: PathBuf },
+    /// [`CoreDB::restore_incremental`](crate::CoreDB::restore_incremental)
+    /// from `path`.
+    Restore { path: PathBuf },
+    Compact,
+    HnswBuild { field: String, m: usize, ef_construction: usize },
+    IndexRebuild,
+}
+
+impl JobRequest {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            JobRequest::Backup { .. } => JobKind::Backup,
+            JobRequest::Restore { .. } => JobKind::Restore,
+            JobRequest::Compact => JobKind::Compact,
+            JobRequest::HnswBuild { .. } => JobKind::HnswBuild,
+            JobRequest::IndexRebuild => JobKind::IndexRebuild,
+        }
+    }
+
+    /// The backup directory, restored file or vector field.
+    pub(crate) fn target(&self) -> String {
+        match self {
+            JobRequest::Backup { dest } => dest.display().to_string(),
+            JobRequest::Restore { path } => path.display().to_string(),
+            JobRequest::HnswBuild { field, .. } => field.clone(),
+            JobRequest::Compact | JobRequest::IndexRebuild => String::new(),
+        }
+    }
+
+    /// Parse `{"kind": "backup", "dest": "..."}`, `{"kind": "restore",
+    /// "path": "..."}`, `{"kind": "compact"}`, `{"kind": "hnsw_build",
+    /// "field": "...", "m": 16, "ef_construction": 200}` (both optional) or
+    /// `{"kind": "index_rebuild"}`.
+    pub fn from_json(v: &Value) -> Result<Self, String> {
+        let text = |key: &str| {
+            v.get(key).and_then(Value::as_str).map(str::to_string).ok_or_else(|| format!("job needs \"{key}\""))
+        };
+        let number = |key: &str, default: usize| match v.get(key) {
+            None | Some(Value::Null) => Ok(default),
+            Some(n) => n.as_u64().map(|n| n as usize).ok_or_else(|| format!("\"{key}\" must be a positive integer")),
+        };
+        match v.get("kind").and_then(Value::as_str) {
+            Some("backup") => Ok(JobRequest::Backup { dest: text("dest")?.into() }),
+            Some("restore") => Ok(JobRequest::Restore { path: text("path")?.into() }),
+            Some("compact") => Ok(JobRequest::Compact),
+            Some("hnsw_build") => Ok(JobRequest::HnswBuild {
+                field: text("field")?,
+                m: number("m", 16)?,
+                ef_construction: number("ef_construction", 200)?,
+            }),
+            Some("index_rebuild") => Ok(JobRequest::IndexRebuild),
+            Some(other) => Err(format!("unknown job kind `{other}`")),
+            None => Err("job needs \"kind\"".into()),
+        }
+    }
+}
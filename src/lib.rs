@@ -25,27 +25,47 @@
 //! db.compact().unwrap();  // flush snapshot + truncate WAL
 //! ```
 
+mod auth;
+mod budget;
+mod clock;
+mod csv;
+#[cfg(feature = "bench")]
+pub mod bench;
 pub mod bm25;
+mod dedup;
+mod interop;
+mod jobs;
 #[cfg(feature = "engine")]
 pub mod engine;
 pub mod geo;
 mod query;
 pub mod scalar;
 pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod sql;
+mod sketch;
 mod storage;
+mod ulid;
 pub mod text_index;
 pub mod vector;
 
+pub use auth::{Access, Authorizer};
+pub use budget::{CancelToken, InterruptCause, Interrupted, QueryError};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use csv::{CsvEdgeMapping, CsvMapping, CsvType};
+pub use dedup::{DedupOptions, DuplicateCandidate};
+pub use jobs::{Job, JobInfo, JobKind, JobRequest, JobState, Jobs};
 pub use vector::{CosineDistance, Distance, DotProduct, L2Distance};
 
-pub use query::{CmpOp, DestWhere, Hit, MathExpr, MatchAggReturn, MatchAggStart, MatchAggStmt, Set, Step, WhereValue, WithExpr, WithOutExpr, WithRow, WithStage};
+pub use query::{CmpOp, DestWhere, Fusion, Hit, HitDecodeError, HitJsonOptions, HitMeta, MathExpr, MatchAggReturn, MatchAggStart, MatchAggStmt, Set, Step, StepTrace, Trace, WhereValue, WithExpr, WithOutExpr, WithRow, WithStage};
 pub use sql::{CompiledMutation, EdgeDelete, EdgeInsert, FieldDef, FieldType, SqlError, TableSchema};
 pub use storage::edgestore::EdgeMode;
+pub use storage::wal::WalStore;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io;
 use std::path::{Path, PathBuf};
 
@@ -57,7 +77,7 @@ use text_index::gist::GiSTIndex;
 
 /// Bump when the snapshot schema changes in a backwards-incompatible way.
 /// Old binaries that encounter a higher version return an error on open().
-const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+const SNAPSHOT_FORMAT_VERSION: u32 = 2; // 2: edges may live in edges.bin
 
 /// Bump each constant when the corresponding index algorithm changes in a way
 /// that makes indexes built by the previous version produce wrong results.
@@ -66,6 +86,18 @@ const BM25_INDEX_VERSION:    u32 = 1;
 const BTREE_INDEX_VERSION:   u32 = 1;
 const HNSW_INDEX_VERSION:    u32 = 1;
 
+/// Lines applied between WAL syncs in [`CoreDB::mutate_ndjson`].
+const NDJSON_COMMIT_BATCH: usize = 1024;
+
+/// Lines [`CoreDB::replicate_to`] queues for a follower; one further behind
+/// is dropped.
+const REPLICATION_QUEUE: usize = 1024;
+
+/// Payload field holding a node's expiry time (unix milliseconds).
+/// Set by [`CoreDB::put_with_ttl`], honoured by [`CoreDB::evict_expired`]
+/// and [`Set::unexpired`].
+pub const EXPIRES_FIELD: &str = "_expires_unix";
+
 // ── Field index key ───────────────────────────────────────────────────────────
 
 /// Totally-ordered wrapper for f64 (NaN sorts last, uses `total_cmp`).
@@ -107,6 +139,33 @@ impl FieldKey {
     }
 }
 
+/// Ids in a field index whose key is a number within `(lo, hi)`. Null, bool
+/// and string keys sort around the numbers and never match; an empty or
+/// inverted range matches nothing.
+pub(crate) fn numeric_index_range(
+    idx: &BTreeMap<FieldKey, Vec<u64>>,
+    lo: std::ops::Bound<f64>,
+    hi: std::ops::Bound<f64>,
+) -> Vec<u64> {
+    use std::ops::Bound;
+    let edge = |b: Bound<f64>, open: f64| match b {
+        Bound::Included(f) | Bound::Excluded(f) => f,
+        Bound::Unbounded => open,
+    };
+    let (lo_f, hi_f) = (edge(lo, f64::NEG_INFINITY), edge(hi, f64::INFINITY));
+    if lo_f > hi_f || (lo_f == hi_f && !matches!((lo, hi), (Bound::Included(_), Bound::Included(_)))) {
+        return Vec::new();
+    }
+    let key = |b: Bound<f64>, open: f64| match b {
+        Bound::Included(f) => Bound::Included(FieldKey::from_f64(f)),
+        Bound::Excluded(f) => Bound::Excluded(FieldKey::from_f64(f)),
+        Bound::Unbounded => Bound::Included(FieldKey::from_f64(open)),
+    };
+    idx.range((key(lo, f64::NEG_INFINITY), key(hi, f64::INFINITY)))
+        .flat_map(|(_, ids)| ids.iter().copied())
+        .collect()
+}
+
 // ── Internal types ────────────────────────────────────────────────────────────
 
 /// Hash a string with SeaHash (fast, non-cryptographic, deterministic).
@@ -129,6 +188,11 @@ pub(crate) struct PayloadStore {
 #[cfg(unix)]
 use storage::mmap::MmapView;
 
+/// Unmapped bytes appended to `payloads.bin` before the mmap is grown to
+/// cover them. Reads past the mapping still work (pread), just slower.
+#[cfg(unix)]
+const PAYLOAD_REMAP_GROWTH: u64 = 32 << 20;
+
 enum PayloadInner {
     Memory { data: Vec<u8> },
     Disk {
@@ -141,6 +205,14 @@ enum PayloadInner {
     Remote {
         cache: std::sync::Mutex<engine::cache::BlockCache>,
     },
+    /// Read-only replica: the first `base_len` bytes come from a
+    /// `payloads.bin` that another process writes (never written here);
+    /// later appends, i.e. WAL replay, stay in `tail`.
+    Replica {
+        base: Box<PayloadStore>,
+        base_len: u64,
+        tail: Vec<u8>,
+    },
 }
 
 impl PayloadStore {
@@ -180,6 +252,21 @@ impl PayloadStore {
         } })
     }
 
+    /// Open the first `total_len` bytes of `path` for a read-only replica;
+    /// see [`PayloadInner::Replica`].
+    fn open_replica(path: &std::path::Path, total_len: u64) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        #[cfg(unix)]
+        let mmap = MmapView::try_new(&file, total_len as usize);
+        let base = Self { inner: PayloadInner::Disk {
+            file,
+            total_len,
+            #[cfg(unix)]
+            mmap,
+        } };
+        Ok(Self { inner: PayloadInner::Replica { base: Box::new(base), base_len: total_len, tail: Vec::new() } })
+    }
+
     /// Create a remote-backed store that fetches blocks from S3 on demand.
     #[cfg(feature = "s3")]
     fn new_remote(
@@ -215,12 +302,19 @@ impl PayloadStore {
                 data.extend_from_slice(bytes);
                 (offset, bytes.len() as u32)
             }
-            PayloadInner::Disk { file, total_len, .. } => {
+            PayloadInner::Disk { file, total_len, #[cfg(unix)] mmap } => {
                 #[cfg(unix)]
                 {
                     use std::os::unix::fs::FileExt;
                     file.write_all_at(bytes, *total_len)
                         .expect("sekejap: payload disk write failed");
+                    // Grow the mapping once enough unmapped tail has piled up,
+                    // so long-lived handles don't fall back to pread forever.
+                    let mapped = mmap.as_ref().map_or(0, |m| m.len() as u64);
+                    let new_len = *total_len + bytes.len() as u64;
+                    if new_len - mapped >= PAYLOAD_REMAP_GROWTH {
+                        *mmap = MmapView::try_new(file, new_len as usize);
+                    }
                 }
                 #[cfg(not(unix))]
                 {
@@ -238,6 +332,54 @@ impl PayloadStore {
             PayloadInner::Remote { .. } => {
                 panic!("sekejap: cannot write to remote payload store (read-only)");
             }
+            PayloadInner::Replica { base_len, tail, .. } => {
+                let offset = *base_len + tail.len() as u64;
+                tail.extend_from_slice(bytes);
+                (offset, bytes.len() as u32)
+            }
+        }
+    }
+
+    /// Rewrite the blob at `offset` in place when `bytes` fits in its old
+    /// `len`; returns `false` (nothing written) when the caller must append.
+    ///
+    /// Disk stores only rewrite bytes past the mmap: everything a persisted
+    /// snapshot points at is mapped, so those blobs are never touched.
+    fn overwrite(&mut self, offset: u64, len: u32, bytes: &[u8]) -> bool {
+        if bytes.len() > len as usize {
+            return false;
+        }
+        match &mut self.inner {
+            PayloadInner::Memory { data } => {
+                let start = offset as usize;
+                match data.get_mut(start..start + bytes.len()) {
+                    Some(slot) => { slot.copy_from_slice(bytes); true }
+                    None => false,
+                }
+            }
+            #[cfg(unix)]
+            PayloadInner::Disk { file, total_len, mmap } => {
+                use std::os::unix::fs::FileExt;
+                let mapped = mmap.as_ref().map_or(0, |m| m.len() as u64);
+                if offset < mapped || offset + len as u64 > *total_len {
+                    return false;
+                }
+                file.write_all_at(bytes, offset)
+                    .expect("sekejap: payload disk write failed");
+                true
+            }
+            #[cfg(not(unix))]
+            PayloadInner::Disk { .. } => false,
+            #[cfg(feature = "s3")]
+            PayloadInner::Remote { .. } => false,
+            PayloadInner::Replica { base_len, tail, .. } => {
+                let Some(start) = offset.checked_sub(*base_len) else { return false };
+                let start = start as usize;
+                match tail.get_mut(start..start + bytes.len()) {
+                    Some(slot) => { slot.copy_from_slice(bytes); true }
+                    None => false,
+                }
+            }
         }
     }
 
@@ -288,6 +430,13 @@ impl PayloadStore {
             PayloadInner::Remote { cache } => {
                 cache.lock().ok()?.get_raw_at(abs_offset, read_len)
             }
+            PayloadInner::Replica { base, base_len, tail } => match abs_offset.checked_sub(*base_len) {
+                Some(start) => {
+                    let start = start as usize;
+                    tail.get(start..start.checked_add(read_len)?).map(<[u8]>::to_vec)
+                }
+                None => base.get_raw_at(abs_offset, read_len),
+            },
         }
     }
 
@@ -307,6 +456,24 @@ impl PayloadStore {
             }
             #[cfg(feature = "s3")]
             PayloadInner::Remote { .. } => None,
+            PayloadInner::Replica { base, base_len, tail } => match abs_offset.checked_sub(*base_len) {
+                Some(start) => {
+                    let start = start as usize;
+                    tail.get(start..start.checked_add(read_len)?)
+                }
+                None => base.get_slice(abs_offset, read_len),
+            },
+        }
+    }
+
+    /// Re-map the disk file so the mmap covers every byte appended so far.
+    /// No-op for memory and remote stores.
+    fn remap(&mut self) {
+        #[cfg(unix)]
+        if let PayloadInner::Disk { file, total_len, mmap } = &mut self.inner {
+            if mmap.as_ref().map_or(0, |m| m.len() as u64) < *total_len {
+                *mmap = MmapView::try_new(file, *total_len as usize);
+            }
         }
     }
 
@@ -346,9 +513,242 @@ pub struct EdgeHit {
     pub edge_type: Option<String>,
     pub edge_type_hash: u64,
     pub strength: f32,
+    /// When the edge was created (unix milliseconds); `0` if unknown.
+    pub created_unix: i64,
     pub meta: Option<Value>,
 }
 
+// ── NodeVersion ───────────────────────────────────────────────────────────────
+
+/// A prior payload of a node, returned by [`CoreDB::history`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeVersion {
+    /// The payload as it was stored.
+    pub payload: Value,
+    /// When this version was written (unix milliseconds, from its
+    /// `_updated_unix`); `0` if unknown.
+    pub updated_unix: i64,
+}
+
+// ── MutationSummary ───────────────────────────────────────────────────────────
+
+/// Result of [`CoreDB::mutate_ndjson`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MutationSummary {
+    /// Mutation lines applied successfully.
+    pub applied: usize,
+    /// Lines that could not be parsed or applied.
+    pub failed: usize,
+    /// `(line_number, message)` for the first failures (1-based line numbers,
+    /// capped at [`MutationSummary::MAX_ERRORS`] entries).
+    pub errors: Vec<(usize, String)>,
+}
+
+impl MutationSummary {
+    /// Maximum number of per-line errors kept in [`MutationSummary::errors`].
+    pub const MAX_ERRORS: usize = 100;
+}
+
+// ── ExportManifest ────────────────────────────────────────────────────────────
+
+/// Trailer record of [`CoreDB::export_ndjson`], checked by
+/// [`CoreDB::import_ndjson`] before anything is applied.
+///
+/// Checksums are CRC32 over the exported lines (newline included).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// Records before the manifest line.
+    pub records: usize,
+    pub nodes: usize,
+    pub edges: usize,
+    pub vectors: usize,
+    /// Checksum of every record line.
+    pub checksum: u32,
+    /// Per-collection node count and checksum of its `put` lines.
+    pub collections: BTreeMap<String, CollectionDigest>,
+}
+
+/// One collection's entry in an [`ExportManifest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionDigest {
+    pub nodes: usize,
+    pub checksum: u32,
+}
+
+// ── Binary backups ────────────────────────────────────────────────────────────
+
+/// Index of a [`CoreDB::backup_to`] directory, saved there as `backup.json`
+/// and checked by [`CoreDB::restore_snapshot`] before anything is copied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Backup layout version; see [`BACKUP_FORMAT_VERSION`].
+    pub version: u32,
+    /// When the backup was taken, in Unix milliseconds.
+    pub created_unix: i64,
+    pub files: Vec<BackupFile>,
+}
+
+/// One database file in a [`BackupManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// File name inside the database directory.
+    pub name: String,
+    pub len: u64,
+    /// CRC32 of the whole file.
+    pub crc32: u32,
+}
+
+/// Layout version written by [`CoreDB::backup_to`].
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Copy `src` to `dst`, returning its length and CRC32.
+fn copy_with_crc(src: &Path, dst: &Path) -> io::Result<(u64, u32)> {
+    use std::io::{Read, Write};
+    let mut input = std::fs::File::open(src)?;
+    let mut output = std::fs::File::create(dst)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 1 << 20];
+    let mut len = 0u64;
+    loop {
+        let n = input.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        output.write_all(&buf[..n])?;
+        len += n as u64;
+    }
+    output.sync_all()?;
+    Ok((len, hasher.finalize()))
+}
+
+// ── Collection snapshots ──────────────────────────────────────────────────────
+
+/// Membership of one collection at a point in time, from
+/// [`CoreDB::collection_snapshot`]. Hand it back to
+/// [`CoreDB::collection_diff`] to learn which nodes joined or left since.
+///
+/// Held as a bitmap of slug hashes; [`to_bytes`](Self::to_bytes) gives a
+/// compact token an external sync job can store between runs. The default
+/// snapshot is empty, so diffing against it lists every current member.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionSnapshot {
+    members: roaring::RoaringTreemap,
+}
+
+impl CollectionSnapshot {
+    /// Number of nodes in the snapshot.
+    pub fn len(&self) -> usize {
+        self.members.len() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Serialise to the portable roaring format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.members.serialized_size());
+        self.members.serialize_into(&mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Read a token written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Ok(Self { members: roaring::RoaringTreemap::deserialize_from(bytes)? })
+    }
+}
+
+/// Result of [`CoreDB::collection_diff`]. Node ids are slug hashes, as in
+/// [`Hit::slug_hash`]; resolve added ones with [`CoreDB::slug_of`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionDiff {
+    /// Members now that were not in the earlier snapshot, ascending.
+    pub added: Vec<u64>,
+    /// Members of the earlier snapshot that are gone, ascending. Includes
+    /// nodes removed outright and nodes moved to another collection.
+    pub removed: Vec<u64>,
+    /// Current membership, to diff against next time.
+    pub snapshot: CollectionSnapshot,
+}
+
+// ── SpatialStats ──────────────────────────────────────────────────────────────
+
+/// Consistency report from [`CoreDB::spatial_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpatialStats {
+    /// Whether the spatial grid has been built (see [`CoreDB::build_spatial_index`]).
+    pub grid_built: bool,
+    /// Live nodes with a GeoJSON `geometry`.
+    pub nodes_with_geometry: usize,
+    /// Nodes held by the grid.
+    pub indexed: usize,
+    /// Grid entries for removed nodes, or with coordinates the node no
+    /// longer has.
+    pub stale: usize,
+    /// Nodes with geometry that the grid does not hold.
+    pub missing: usize,
+}
+
+impl SpatialStats {
+    /// `true` when the grid matches the stored geometries exactly.
+    pub fn is_consistent(&self) -> bool {
+        self.stale == 0 && self.missing == 0
+    }
+}
+
+// ── MergeStrategy ─────────────────────────────────────────────────────────────
+
+/// How [`CoreDB::put_merge`] combines an incoming payload with the stored one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Top-level keys from the incoming payload overwrite stored ones; keys
+    /// it does not mention are kept.
+    Shallow,
+    /// Nested objects are merged recursively; any other incoming value wins.
+    Deep,
+    /// Like [`Deep`](Self::Deep), but arrays are unioned: stored elements keep
+    /// their order and incoming elements not already present are appended.
+    ArrayUnion,
+    /// Compare the timestamp in `field` (RFC 3339, `YYYY-MM-DD` or epoch) on
+    /// both sides. Every top-level field takes the value from the newer
+    /// payload; fields only the older payload has are kept. A missing or
+    /// unparseable timestamp counts as older; ties go to the incoming payload.
+    LatestTimestamp(String),
+}
+
+// ── SetMutation ───────────────────────────────────────────────────────────────
+
+/// A mutation applied to every node a pipeline matches; see
+/// [`CoreDB::mutate_where`].
+///
+/// Serialises as `{"op":"delete"}` or
+/// `{"op":"set_field","field":"status","value":"archived"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SetMutation {
+    /// Remove every matching node, as SQL `DELETE` does.
+    Delete,
+    /// Set `field` to `value` on every matching node, as SQL `UPDATE … SET`
+    /// does.
+    SetField { field: String, value: Value },
+}
+
+// ── EdgeAggregate ─────────────────────────────────────────────────────────────
+
+/// A value kept in a payload field and recomputed from the node's own edges
+/// whenever they change; see [`CoreDB::set_edge_aggregate`].
+///
+/// Serialises as `{"op":"count","edge_type":"located_in","incoming":true}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EdgeAggregate {
+    /// Number of `edge_type` edges arriving at (`incoming`) or leaving the node.
+    Count { edge_type: String, incoming: bool },
+    /// Sum of the strengths of those edges.
+    SumStrength { edge_type: String, incoming: bool },
+}
+
 // ── BfsPath (internal only) ───────────────────────────────────────────────────
 
 /// Internal result of `bfs_shortest_path`. Not part of the public API.
@@ -413,6 +813,10 @@ pub struct CoreDB {
     /// Built via `CREATE INDEX ON collection(field) USING btree`.
     /// Maintained incrementally on every put()/remove().
     field_indexes: HashMap<(u64, String), BTreeMap<FieldKey, Vec<u64>>>,
+    /// SimHash fingerprints: node hash → fingerprint over the text fields its
+    /// collection declares via `CREATE INDEX … USING simhash`.
+    /// Maintained on every put()/remove(); rebuilt on open, never persisted.
+    simhashes: HashMap<u64, u64>,
     /// Build params for each HNSW index: field → (m, ef_construction).
     /// Populated by build_hnsw_index(); used to auto-rebuild on version mismatch.
     hnsw_params: HashMap<String, (usize, usize)>,
@@ -423,26 +827,256 @@ pub struct CoreDB {
     /// Guards expensive per-entry rebuilds (e.g. HNSW entry-point check in remove_raw)
     /// that must not fire O(N) times during replay — open() handles those once at the end.
     replaying: bool,
+    /// Set by [`CoreDB::catch_up`]: puts keep the `_updated_unix` they carry.
+    keep_timestamps: bool,
     /// SQL transaction buffer. `Some` when a `BEGIN` has been issued;
     /// mutations are queued here until `COMMIT` (replay) or `ROLLBACK` (drop).
     pending_txn: Option<Vec<sql::CompiledMutation>>,
     /// When true, `wal_write` appends without fsync.
     /// Used by batch operations (UPDATE, DELETE, COMMIT) to coalesce syncs.
     defer_wal_sync: bool,
+    /// fsync policy from [`Config::wal`].
+    wal_sync: WalSync,
+    /// WAL entries appended since the last fsync.
+    wal_unsynced: usize,
+    /// Caps applied to every query (see [`QueryLimits`]).
+    query_limits: QueryLimits,
+    /// Fixed candidate count below which spatial filters check nodes one by
+    /// one; `None` picks adaptively from `filter_costs`.
+    near_filter_threshold: Option<usize>,
+    /// Measured per-item costs of the ways a filter step can run.
+    filter_costs: query::FilterCosts,
+    /// Write quotas: collection hash → cap. Not persisted.
+    quotas: HashMap<u64, Quota>,
+    /// Versions kept per node on overwrite: collection hash → count. Not persisted.
+    history_retention: HashMap<u64, usize>,
+    /// Prior payload slots per node, newest first; see [`CoreDB::history`].
+    history: HashMap<u64, VecDeque<(u64, u32)>>,
+    /// When false, linking an existing (from, to, type) replaces that edge.
+    allow_parallel_edges: bool,
+    /// Index rebuild skipped by [`Config::defer_index_rebuild`]; holds
+    /// whether the WAL replay changed payloads.
+    pending_index_rebuild: Option<bool>,
+    /// Open stopped WAL replay at a corrupted frame. Cleared by compact(),
+    /// which rewrites the WAL from the recovered state.
+    wal_corrupted: bool,
+    /// Implicit filters: collection hash → (collection, WHERE text, parsed steps).
+    /// Spliced after `Step::Collection` in every pipeline that starts there.
+    collection_filters: HashMap<u64, (String, String, Vec<Step>)>,
+    /// Soft-deleted nodes: slug hash → everything needed to restore them.
+    /// Filled by `remove_soft()`, drained by `restore()` / `purge()`.
+    trash: HashMap<u64, TrashedNode>,
+    /// Materialised edge aggregates: (collection, field, aggregate).
+    edge_aggregates: Vec<(String, String, EdgeAggregate)>,
+    /// Projection profiles: (collection, profile, fields left out).
+    projection_profiles: Vec<(String, String, Vec<String>)>,
+    /// Set for read-only opens: how to reopen, and what the directory
+    /// looked like when this handle was loaded.
+    replica: Option<Replica>,
+    /// Log shipping from [`CoreDB::replicate_to`]: every WAL entry is also
+    /// queued here as one NDJSON line.
+    replication: Option<Shipper>,
+    /// Why the replication sink was dropped, for `stop_replication()`.
+    replication_error: Option<io::Error>,
+    /// Writes since the oldest incremental-backup checkpoint; `None` until
+    /// the first full [`CoreDB::backup_incremental`].
+    change_log: Option<ChangeLog>,
+    /// Time source for write stamps and TTL checks; see [`CoreDB::set_clock`].
+    clock: std::sync::Arc<dyn Clock>,
+    /// Last id generated by [`CoreDB::put_auto`].
+    auto_ids: ulid::UlidGen,
+    /// Long-running operations, shared with [`CoreDB::jobs`] handles.
+    jobs: jobs::Jobs,
+    /// Statements from [`CoreDB::prepare`], keyed by SQL text hash.
+    prepared: std::sync::Mutex<HashMap<u64, PreparedQuery>>,
     /// Exclusive file lock held for the lifetime of the database.
     /// Prevents concurrent access from multiple processes.
     _lock_file: Option<std::fs::File>,
 }
 
+/// State of a read-only handle for [`CoreDB::refresh`].
+/// Writer thread feeding a follower from [`CoreDB::replicate_to`].
+struct Shipper {
+    lines: std::sync::mpsc::SyncSender<Vec<u8>>,
+    writer: std::thread::JoinHandle<io::Result<()>>,
+}
+
+impl Shipper {
+    fn spawn(mut sink: Box<dyn io::Write + Send>) -> Self {
+        let (lines, queue) = std::sync::mpsc::sync_channel::<Vec<u8>>(REPLICATION_QUEUE);
+        let writer = std::thread::spawn(move || {
+            while let Ok(line) = queue.recv() {
+                sink.write_all(&line)?;
+                // Flush once the queue drains rather than per line.
+                for line in queue.try_iter() {
+                    sink.write_all(&line)?;
+                }
+                sink.flush()?;
+            }
+            Ok(())
+        });
+        Self { lines, writer }
+    }
+
+    /// Wait for the queued lines to be written; the writer's outcome.
+    fn finish(self) -> io::Result<()> {
+        drop(self.lines);
+        self.writer.join().unwrap_or_else(|_| Err(io::Error::other("replication writer panicked")))
+    }
+}
+
+struct Replica {
+    config: Config,
+    stamp: ReplicaStamp,
+}
+
+/// Length and mtime of `snapshot.json` and `wal.log`: every write appends
+/// to the WAL and every compaction replaces the snapshot.
+type ReplicaStamp = [Option<(u64, std::time::SystemTime)>; 2];
+
+fn replica_stamp(dir: &Path) -> ReplicaStamp {
+    ["snapshot.json", "wal.log"].map(|name| {
+        let meta = std::fs::metadata(dir.join(name)).ok()?;
+        Some((meta.len(), meta.modified().ok()?))
+    })
+}
+
+/// A soft-deleted node: its last payload plus the edges and vectors that
+/// were detached from it, so `restore()` can put everything back.
+#[derive(Clone, Serialize, Deserialize)]
+struct TrashedNode {
+    slug: String,
+    payload: String,
+    #[serde(default)]
+    edges: Vec<TrashedEdge>,
+    #[serde(default)]
+    vectors: Vec<(String, Vec<f32>)>,
+}
+
+/// An edge detached by `remove_soft()`. Endpoints are kept as hashes because
+/// the other side may itself be in the trash when this node is restored.
+#[derive(Clone, Serialize, Deserialize)]
+struct TrashedEdge {
+    from: u64,
+    to: u64,
+    edge_type: String,
+    strength: f32,
+    #[serde(default)]
+    created_unix: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Value>,
+}
+
+/// What changed since the oldest checkpoint [`CoreDB::backup_incremental`]
+/// may still be asked for. Started by the first full backup; every logged
+/// write after that bumps `lsn`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ChangeLog {
+    /// Sequence number of the last recorded write.
+    lsn: u64,
+    /// Checkpoints before this LSN have been forgotten.
+    floor: u64,
+    /// Node slug → LSN of the last write to it or its outgoing edges.
+    nodes: HashMap<String, u64>,
+    /// Schema, index and settings entries, in log order.
+    schema: Vec<(u64, WalEntry)>,
+}
+
+impl ChangeLog {
+    fn record(&mut self, entry: &WalEntry) {
+        let slug = match entry {
+            WalEntry::TxnBegin | WalEntry::TxnEnd | WalEntry::Manifest(_) | WalEntry::Unknown => return,
+            // Purging only touches the trash, which backups do not carry.
+            WalEntry::Purge { .. } => return,
+            WalEntry::Put { slug, .. }
+            | WalEntry::Remove { slug }
+            | WalEntry::SoftRemove { slug }
+            | WalEntry::Restore { slug }
+            | WalEntry::PutVector { slug, .. } => slug,
+            // An edge is re-sent with its source node.
+            WalEntry::Link { from, .. } | WalEntry::LinkMeta { from, .. } | WalEntry::Unlink { from, .. } => from,
+            other => {
+                self.lsn += 1;
+                self.schema.push((self.lsn, other.clone()));
+                return;
+            }
+        };
+        self.lsn += 1;
+        self.nodes.insert(slug.clone(), self.lsn);
+    }
+
+    /// Drop what only checkpoints before `lsn` would need.
+    fn forget_before(&mut self, lsn: u64) {
+        self.nodes.retain(|_, &mut l| l > lsn);
+        self.schema.retain(|&(l, _)| l > lsn);
+        self.floor = self.floor.max(lsn);
+    }
+}
+
+/// What [`CoreDB::replay_wal`] found in a log.
+#[derive(Default)]
+struct WalReplay {
+    corrupted: bool,
+    /// A Put/Remove/PutVector was applied — text indexes need rebuilding.
+    had_payload: bool,
+    /// A Link/LinkMeta/Unlink was applied.
+    had_graph: bool,
+    /// The log ended inside a transaction, which was discarded.
+    incomplete_txn: bool,
+}
+
 /// Configuration for [`CoreDB::open_with_config`].
+#[derive(Clone)]
 pub struct Config {
     /// How edges are stored.  [`EdgeMode::Fat`] keeps metadata in RAM
     /// (original behaviour); [`EdgeMode::Compact`] puts metadata on disk
-    /// and uses ~2.7× less RAM per edge.
+    /// and uses ~2× less RAM per edge.
     pub edge_mode: EdgeMode,
-    /// When `true`, skip the exclusive file lock and WAL writer.
-    /// The database will not accept writes — use for read replicas.
+    /// When `true`, skip the exclusive file lock and WAL writer, and never
+    /// write to the directory, so a second process can open the directory
+    /// a writer is using. Catch up with [`CoreDB::refresh`].
     pub read_only: bool,
+    /// WAL durability settings.
+    pub wal: WalConfig,
+    /// Caps on queries and their results; see [`CoreDB::set_query_limits`].
+    pub limits: QueryLimits,
+    /// Candidate count below which `ST_DWithin` / near filters skip the
+    /// spatial grid; `None` (the default) adapts to measured costs. See
+    /// [`CoreDB::set_near_filter_threshold`].
+    pub near_filter_threshold: Option<usize>,
+    /// Per-collection write quotas; see [`CoreDB::set_quota`].
+    pub quotas: HashMap<String, Quota>,
+    /// Prior versions kept per node, by collection; see
+    /// [`CoreDB::set_history_retention`].
+    pub history_retention: HashMap<String, usize>,
+    /// Keep every `link` as its own edge instead of upserting by
+    /// (from, to, type); see [`CoreDB::set_allow_parallel_edges`].
+    pub allow_parallel_edges: bool,
+    /// Return from open before rebuilding declared BM25 / GIN / HNSW /
+    /// search / SimHash indexes; see [`CoreDB::rebuild_pending_indexes`].
+    pub defer_index_rebuild: bool,
+    /// Nodes to reserve room for up front, so loading a large database does
+    /// not rehash the node tables repeatedly. 0 grows on demand.
+    pub capacity: usize,
+    /// Time source for `_created_unix` / `_updated_unix`, edge creation
+    /// times and TTL expiries; see [`CoreDB::set_clock`].
+    pub clock: std::sync::Arc<dyn Clock>,
+}
+
+/// Stage of [`CoreDB::open_with_progress`], reported as it starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenStage {
+    /// Reading `snapshot.json` and the binary vector files.
+    Snapshot,
+    /// Replaying WAL entries written after the snapshot.
+    WalReplay,
+    /// Building the spatial grid from node geometry.
+    SpatialGrid,
+    /// Loading or rebuilding declared secondary indexes. Skipped when
+    /// [`Config::defer_index_rebuild`] is set.
+    Indexes,
+    /// Moving in-memory vector fields to disk-backed stores.
+    Vectors,
 }
 
 impl Default for Config {
@@ -450,135 +1084,685 @@ impl Default for Config {
         Self {
             edge_mode: EdgeMode::Compact,
             read_only: false,
+            wal: WalConfig::default(),
+            limits: QueryLimits::default(),
+            near_filter_threshold: None,
+            quotas: HashMap::new(),
+            history_retention: HashMap::new(),
+            allow_parallel_edges: false,
+            defer_index_rebuild: false,
+            capacity: 0,
+            clock: std::sync::Arc::new(SystemClock),
         }
     }
 }
 
-impl Default for CoreDB {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Chained [`Config`] for [`CoreDB::builder`].
+///
+/// ```
+/// # use sekejap::{CoreDB, WalSync};
+/// let dir = tempfile::tempdir().unwrap();
+/// let db = CoreDB::builder(dir.path())
+///     .capacity(10_000)
+///     .wal(WalSync::Batch { max_entries: 64 })
+///     .allow_parallel_edges(true)
+///     .open()
+///     .unwrap();
+/// assert!(db.allow_parallel_edges());
+/// ```
+#[must_use = "call .open() to open the database"]
+pub struct CoreDBBuilder {
+    dir: PathBuf,
+    config: Config,
 }
 
-impl CoreDB {
-    // ── Constructors ──────────────────────────────────────────────────────────
+impl CoreDBBuilder {
+    /// See [`Config::edge_mode`].
+    pub fn edge_mode(mut self, mode: EdgeMode) -> Self {
+        self.config.edge_mode = mode;
+        self
+    }
 
-    /// Create a new in-memory database (no persistence).
-    pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-            slug_map: HashMap::new(),
-            edges: storage::edgestore::EdgeStore::new_fat(),
-            collections: HashMap::new(),
-            collection_names_map: HashMap::new(),
-            wal: None,
-            data_dir: None,
-            spatial_grid: None,
-            text_indexes: HashMap::new(),
-            gin_indexes: HashMap::new(),
-            bm25_indexes: HashMap::new(),
-            search_indexes: HashMap::new(),
-            schemas: HashMap::new(),
-            vectors: HashMap::new(),
-            hnsw_indexes: HashMap::new(),
-            field_indexes: HashMap::new(),
-            hnsw_params: HashMap::new(),
-            payload_store: PayloadStore::new(),
-            replaying: false,
-            pending_txn: None,
-            defer_wal_sync: false,
-            _lock_file: None,
-        }
+    /// See [`Config::read_only`].
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.config.read_only = read_only;
+        self
     }
 
-    /// Open (or create) a persistent database in `dir`.
-    ///
-    /// Uses [`EdgeMode::Compact`] by default (disk-first edge metadata).
-    /// For the original all-in-RAM behaviour, use
-    /// [`open_with_config`](Self::open_with_config) with [`EdgeMode::Fat`].
-    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
-        Self::open_with_config(dir, Config::default())
+    /// WAL fsync policy; see [`WalConfig::sync`].
+    pub fn wal(mut self, sync: WalSync) -> Self {
+        self.config.wal.sync = sync;
+        self
     }
 
-    /// Open a database in read-only mode (no lock, no WAL writer).
-    ///
-    /// Suitable for read replicas that sync their local directory from S3.
-    /// Write operations will silently skip WAL persistence.
-    pub fn open_read_only(dir: impl AsRef<Path>) -> io::Result<Self> {
-        Self::open_with_config(
-            dir,
-            Config { read_only: true, ..Config::default() },
-        )
+    /// See [`Config::limits`].
+    pub fn limits(mut self, limits: QueryLimits) -> Self {
+        self.config.limits = limits;
+        self
     }
 
-    /// Open a read-only database backed by S3 remote storage.
-    ///
-    /// Downloads only the snapshot (node index, ~100 B/node) and loads it
-    /// into RAM. Payloads stay on S3 — each `get_payload()` call fetches
-    /// the relevant 64 KB block via `GET_RANGE` and caches it in a bounded
-    /// LRU. No local `payloads.bin` file is needed.
-    ///
-    /// This allows querying a 1 TB dataset from a machine with 50 GB of disk:
-    /// the node index stays in RAM (~hundreds of MB), the block cache keeps
-    /// hot payload blocks on local storage, and cold blocks are fetched on
-    /// demand from S3.
-    /// Open a read-only database backed by S3.
-    ///
-    /// Payloads are fetched on demand via S3 `GET_RANGE` and cached in an
-    /// LRU cache bounded by `cache_budget`.
-    ///
-    /// - Without `cache_dir`: budget controls RAM cache size. Evicted blocks
-    ///   are discarded.
-    /// - With `cache_dir`: budget controls disk cache size (RAM tier is 256 MB).
-    ///   Evicted blocks are spilled to disk and survive restarts.
-    #[cfg(feature = "s3")]
-    pub fn open_s3(
-        remote: &engine::remote::RemoteSync,
-        cache_budget: engine::cache::CacheBudget,
-        cache_dir: Option<&std::path::Path>,
-    ) -> Result<Self, String> {
-        Self::open_s3_inner(remote, cache_budget, cache_dir)
+    /// See [`Config::near_filter_threshold`].
+    pub fn near_filter_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.config.near_filter_threshold = threshold;
+        self
     }
 
-    #[cfg(feature = "s3")]
-    fn open_s3_inner(
-        remote: &engine::remote::RemoteSync,
-        cache_budget: engine::cache::CacheBudget,
-        cache_dir: Option<&std::path::Path>,
-    ) -> Result<Self, String> {
-        let manifest = remote
-            .get_manifest()?
-            .ok_or("no manifest found on remote")?;
+    /// Write quota for one collection; see [`Config::quotas`].
+    pub fn quota(mut self, collection: &str, quota: Quota) -> Self {
+        self.config.quotas.insert(collection.to_string(), quota);
+        self
+    }
 
-        // Find payloads.bin size from manifest.
-        let payload_size = manifest
-            .segments
-            .iter()
-            .find(|s| s.name == "payloads.bin")
-            .map(|s| s.size)
-            .unwrap_or(0);
+    /// History kept for one collection; see [`Config::history_retention`].
+    pub fn history_retention(mut self, collection: &str, versions: usize) -> Self {
+        self.config.history_retention.insert(collection.to_string(), versions);
+        self
+    }
 
-        // Fetch snapshot.json via RemoteSync (reuses its existing Runtime/connection).
-        let snap_bytes = remote.fetch_file("snapshot.json")?;
+    /// See [`Config::allow_parallel_edges`].
+    pub fn allow_parallel_edges(mut self, allow: bool) -> Self {
+        self.config.allow_parallel_edges = allow;
+        self
+    }
 
-        let snap: Snapshot = serde_json::from_slice(&snap_bytes)
-            .map_err(|e| format!("parsing snapshot: {e}"))?;
+    /// See [`Config::defer_index_rebuild`].
+    pub fn defer_index_rebuild(mut self, defer: bool) -> Self {
+        self.config.defer_index_rebuild = defer;
+        self
+    }
 
-        let mut block_cache = if cache_dir.is_some() {
-            // Disk-cached mode: budget controls disk tier, RAM is default 256MB.
-            engine::cache::BlockCache::new(
-                remote.store(),
-                remote.prefix(),
-                "payloads.bin",
-                payload_size,
-                cache_budget,
-            )?
-        } else {
-            // RAM-only mode: budget controls RAM tier, no disk.
-            engine::cache::BlockCache::new(
-                remote.store(),
-                remote.prefix(),
-                "payloads.bin",
+    /// See [`Config::capacity`].
+    pub fn capacity(mut self, nodes: usize) -> Self {
+        self.config.capacity = nodes;
+        self
+    }
+
+    /// See [`Config::clock`].
+    pub fn clock(mut self, clock: std::sync::Arc<dyn Clock>) -> Self {
+        self.config.clock = clock;
+        self
+    }
+
+    /// The settings collected so far.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// [`CoreDB::open_with_config`] with the collected settings.
+    pub fn open(self) -> io::Result<CoreDB> {
+        CoreDB::open_with_config(self.dir, self.config)
+    }
+
+    /// [`CoreDB::open_with_progress`] with the collected settings.
+    pub fn open_with_progress(self, progress: impl FnMut(OpenStage)) -> io::Result<CoreDB> {
+        CoreDB::open_with_progress(self.dir, self.config, progress)
+    }
+}
+
+/// WAL durability settings for [`Config::wal`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalConfig {
+    /// When the WAL is fsynced. Every entry is always handed to the OS
+    /// immediately; this only controls how often it is forced to disk.
+    pub sync: WalSync,
+}
+
+/// Settings to change on an open database with [`CoreDB::reconfigure`].
+/// `None` and absent map entries leave the current value alone.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigUpdate {
+    /// New WAL fsync policy.
+    pub wal: Option<WalSync>,
+    /// New query caps; see [`CoreDB::set_query_limits`].
+    pub limits: Option<QueryLimits>,
+    /// New spatial filter crossover; `Some(None)` returns to adaptive. See
+    /// [`CoreDB::set_near_filter_threshold`].
+    pub near_filter_threshold: Option<Option<usize>>,
+    /// Quotas to set per collection; [`Quota::default`] lifts one.
+    pub quotas: HashMap<String, Quota>,
+    /// Versions to keep per collection; `0` stops retaining.
+    pub history_retention: HashMap<String, usize>,
+    /// See [`CoreDB::set_allow_parallel_edges`].
+    pub allow_parallel_edges: Option<bool>,
+}
+
+/// Caps on queries, so a public-facing endpoint can be held to stricter
+/// bounds than internal jobs. The SQL caps (`max_sql_len`, `max_steps`,
+/// `max_execution_ms`) apply to [`CoreDB::query`] and friends; the result
+/// caps (`max_results`, `max_payload_bytes`, `max_frontier`) apply to every
+/// [`Set`], however it was built, so an unbounded `all().collect()` cannot
+/// exhaust memory. `None` means unlimited (the default).
+///
+/// A result over a result cap is handled the same way for all three:
+/// [`Set::collect`] cuts it to fit, [`Set::try_collect`] and [`Set::run`]
+/// report it as [`ResultTooLarge`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Longest accepted SQL text, in bytes.
+    pub max_sql_len: Option<usize>,
+    /// Most pipeline steps a compiled query may have, counting steps inside
+    /// sub-pipelines (`OR` branches, set algebra).
+    pub max_steps: Option<usize>,
+    /// Most hits one result may hold: the nodes a query loads, checked
+    /// before their payloads are read, or the rows left after grouping and
+    /// aggregation.
+    pub max_results: Option<usize>,
+    /// Most stored payload bytes one result may load, counted before any
+    /// field projection.
+    pub max_payload_bytes: Option<u64>,
+    /// Most nodes one traversal may hold while it runs: the reached set of
+    /// a `hops` BFS, the in-flight paths of a `MATCH`, the queue of
+    /// [`Set::paths`] or a shortest-path search. `collect()` stops the
+    /// traversal where it is.
+    pub max_frontier: Option<usize>,
+    /// Time budget for running a query, in milliseconds from the query
+    /// call. A pipeline over budget stops: [`Set::run`] reports it as
+    /// [`QueryError::Interrupted`], `collect()` returns the hits loaded so
+    /// far. Aggregate statements run inside the query call and fail with
+    /// [`SqlError::LimitExceeded`] instead.
+    pub max_execution_ms: Option<u64>,
+}
+
+impl QueryLimits {
+    /// The stricter of `self` and `other`, field by field.
+    pub fn tighten(&self, other: &QueryLimits) -> QueryLimits {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        QueryLimits {
+            max_sql_len: min(self.max_sql_len, other.max_sql_len),
+            max_steps: min(self.max_steps, other.max_steps),
+            max_results: min(self.max_results, other.max_results),
+            max_payload_bytes: min(self.max_payload_bytes, other.max_payload_bytes),
+            max_frontier: min(self.max_frontier, other.max_frontier),
+            max_execution_ms: min(self.max_execution_ms, other.max_execution_ms),
+        }
+    }
+}
+
+/// A read statement parsed once by [`CoreDB::prepare`] and run any number
+/// of times with [`CoreDB::query_prepared`]. Cheap to clone.
+#[derive(Clone)]
+pub struct PreparedQuery {
+    sql: std::sync::Arc<str>,
+    stmt: std::sync::Arc<sql::PreparedStmt>,
+}
+
+impl PreparedQuery {
+    /// The statement text as prepared.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// How many values a run must bind: the highest `$n` placeholder, or
+    /// the number of distinct `$name` placeholders.
+    pub fn param_count(&self) -> usize {
+        self.stmt.arity()
+    }
+
+    /// The `$name` placeholders a [`CoreDB::query_prepared_named`] run must
+    /// bind, in order of first appearance. Empty for `$1..$n` statements.
+    pub fn param_names(&self) -> &[String] {
+        self.stmt.names()
+    }
+}
+
+impl std::fmt::Debug for PreparedQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreparedQuery").field("sql", &self.sql).finish()
+    }
+}
+
+/// Most statements [`CoreDB::prepare`] keeps; the cache starts over when full.
+const PREPARED_CACHE_CAP: usize = 256;
+
+/// A result over the [`QueryLimits`] result caps, from [`Set::try_collect`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResultTooLarge {
+    /// Hits the query matched.
+    pub hits: usize,
+    /// Stored payload bytes of those hits; 0 for computed rows.
+    pub payload_bytes: u64,
+    /// Nodes the largest traversal over `max_frontier` had reached when it
+    /// was stopped; 0 when the hit or byte cap was the one exceeded.
+    pub frontier: usize,
+    pub limits: QueryLimits,
+}
+
+impl std::fmt::Display for ResultTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.frontier > 0 {
+            let limit = self.limits.max_frontier.map_or_else(|| "none".to_string(), |m| m.to_string());
+            return write!(
+                f,
+                "traversal too large: reached {} nodes (limit {limit}); narrow the start set or the hop range",
+                self.frontier
+            );
+        }
+        write!(f, "result too large: {} hits, {} payload bytes (limit", self.hits, self.payload_bytes)?;
+        match (self.limits.max_results, self.limits.max_payload_bytes) {
+            (Some(h), Some(b)) => write!(f, " {h} hits, {b} bytes")?,
+            (Some(h), None) => write!(f, " {h} hits")?,
+            (None, Some(b)) => write!(f, " {b} bytes")?,
+            (None, None) => write!(f, " none")?,
+        }
+        write!(f, "); paginate with LIMIT / OFFSET or take() / skip()")
+    }
+}
+
+impl std::error::Error for ResultTooLarge {}
+
+// ── Quotas ────────────────────────────────────────────────────────────────────
+
+/// Per-collection caps enforced by [`CoreDB::put`] (and the SQL `INSERT` /
+/// `UPDATE` paths built on it), so one runaway writer cannot fill the whole
+/// store. `None` means unlimited (the default).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// Most live nodes the collection may hold.
+    pub max_nodes: Option<usize>,
+    /// Most stored payload bytes the collection may hold. A write is checked
+    /// against the size of the submitted JSON.
+    pub max_payload_bytes: Option<u64>,
+}
+
+/// A write rejected by a collection [`Quota`].
+///
+/// [`CoreDB::put`] returns it wrapped in a `serde_json::Error`; recover it
+/// with [`QuotaExceeded::from_put_error`]. SQL writes return
+/// [`SqlError::QuotaExceeded`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    /// The collection already holds `limit` nodes.
+    Nodes { collection: String, limit: usize },
+    /// The write would grow the collection's payloads to `requested` bytes.
+    PayloadBytes { collection: String, limit: u64, requested: u64 },
+}
+
+impl QuotaExceeded {
+    /// Unwrap the quota error carried by a [`CoreDB::put`] error. Any other
+    /// error is handed back unchanged.
+    pub fn from_put_error(err: serde_json::Error) -> Result<QuotaExceeded, serde_json::Error> {
+        take_put_error(err)
+    }
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaExceeded::Nodes { collection, limit } => {
+                write!(f, "quota exceeded: collection `{collection}` is limited to {limit} nodes")
+            }
+            QuotaExceeded::PayloadBytes { collection, limit, requested } => write!(
+                f,
+                "quota exceeded: collection `{collection}` is limited to {limit} payload bytes (write needs {requested})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// A write refused because a fixed-width store is full. Nodes have no such
+/// cap; edge metadata is indexed by `u32` ids with `u32` lengths.
+///
+/// [`CoreDB::link_meta`] and [`CoreDB::link_many`] return it wrapped in a
+/// `serde_json::Error`; recover it with [`CapacityExceeded::from_link_error`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CapacityExceeded {
+    /// Every edge metadata id is taken. [`CoreDB::compact`] frees the ids
+    /// of removed edges.
+    EdgeMetaEntries { limit: u64 },
+    /// One edge's metadata is longer than an entry can hold.
+    EdgeMetaBytes { limit: u64, requested: u64 },
+}
+
+impl CapacityExceeded {
+    /// Unwrap the capacity error carried by a [`CoreDB::link_meta`] error.
+    /// Any other error is handed back unchanged.
+    pub fn from_link_error(err: serde_json::Error) -> Result<CapacityExceeded, serde_json::Error> {
+        take_put_error(err)
+    }
+}
+
+impl std::fmt::Display for CapacityExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapacityExceeded::EdgeMetaEntries { limit } => {
+                write!(f, "capacity exceeded: all {limit} edge metadata ids are in use; compact to reclaim removed edges")
+            }
+            CapacityExceeded::EdgeMetaBytes { limit, requested } => write!(
+                f,
+                "capacity exceeded: edge metadata is limited to {limit} bytes (got {requested})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CapacityExceeded {}
+
+// ── Unique constraints ────────────────────────────────────────────────────────
+
+/// A write rejected because another node in the collection already holds
+/// the value of a schema `unique` field.
+///
+/// [`CoreDB::put`] returns it wrapped in a `serde_json::Error`; recover it
+/// with [`UniqueViolation::from_put_error`]. SQL writes return
+/// [`SqlError::UniqueViolation`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UniqueViolation {
+    pub collection: String,
+    pub field: String,
+    pub value: Value,
+    /// Slug of the node that already holds `value`.
+    pub existing: String,
+}
+
+impl UniqueViolation {
+    /// Unwrap the unique-constraint error carried by a [`CoreDB::put`]
+    /// error. Any other error is handed back unchanged.
+    pub fn from_put_error(err: serde_json::Error) -> Result<UniqueViolation, serde_json::Error> {
+        take_put_error(err)
+    }
+}
+
+impl std::fmt::Display for UniqueViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unique constraint violated: `{}.{}` = {} is already used by `{}`",
+            self.collection, self.field, self.value, self.existing
+        )
+    }
+}
+
+impl std::error::Error for UniqueViolation {}
+
+// ── Payload integrity ─────────────────────────────────────────────────────────
+
+/// A stored payload that does not decode; see [`CoreDB::verify_payloads`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayloadError {
+    pub slug: String,
+    pub slug_hash: u64,
+    /// Position of the blob in the payload store.
+    pub offset: u64,
+    pub len: u32,
+    pub fault: PayloadFault,
+}
+
+/// What is wrong with a [`PayloadError`] blob.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PayloadFault {
+    /// The bytes lie past the end of the payload store.
+    Unreadable,
+    /// Not UTF-8; the first bad byte is at `offset + valid_up_to`.
+    InvalidUtf8 { valid_up_to: usize },
+    /// UTF-8, but not JSON.
+    InvalidJson(String),
+}
+
+impl std::fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "payload of `{}` ({} bytes at offset {}) ", self.slug, self.len, self.offset)?;
+        match &self.fault {
+            PayloadFault::Unreadable => write!(f, "cannot be read"),
+            PayloadFault::InvalidUtf8 { valid_up_to } => {
+                write!(f, "is not UTF-8 at byte {}", self.offset + *valid_up_to as u64)
+            }
+            PayloadFault::InvalidJson(e) => write!(f, "is not JSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
+
+/// Recover a typed error that [`CoreDB::put`] wrapped in a `serde_json::Error`.
+fn take_put_error<E>(err: serde_json::Error) -> Result<E, serde_json::Error>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    if !err.is_io() {
+        return Err(err);
+    }
+    let io_err: std::io::Error = err.into();
+    if io_err.get_ref().is_some_and(|e| e.is::<E>()) {
+        let inner = io_err.into_inner().expect("checked above");
+        return Ok(*inner.downcast::<E>().expect("checked above"));
+    }
+    Err(serde_json::Error::io(io_err))
+}
+
+// ── Health ────────────────────────────────────────────────────────────────────
+
+/// Overall verdict of [`CoreDB::health`], from best to worst.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    /// Serving, but something needs attention (compact, finish an index
+    /// rebuild, raise a quota).
+    Degraded,
+    /// Data was lost or the store can no longer persist writes.
+    Unhealthy,
+}
+
+/// Result of [`CoreDB::health`]: the worst status found plus one
+/// human-readable reason per problem.
+///
+/// For Kubernetes probes, fail liveness on [`HealthStatus::Unhealthy`] and
+/// readiness on anything other than [`HealthStatus::Ok`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Health {
+    pub status: HealthStatus,
+    pub reasons: Vec<String>,
+}
+
+impl Health {
+    fn report(&mut self, status: HealthStatus, reason: String) {
+        self.status = self.status.max(status);
+        self.reasons.push(reason);
+    }
+}
+
+/// Limits past which [`CoreDB::health_with`] reports
+/// [`HealthStatus::Degraded`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthThresholds {
+    /// WAL size, in bytes, above which a [`CoreDB::compact`] is overdue.
+    pub wal_backlog_bytes: u64,
+    /// Fraction of a [`Quota`] a collection may fill (`0.0..=1.0`).
+    pub quota_fill: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self { wal_backlog_bytes: 64 * 1024 * 1024, quota_fill: 0.9 }
+    }
+}
+
+/// fsync policy for the WAL.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WalSync {
+    /// fsync after every write (safest, slowest).
+    #[default]
+    Always,
+    /// Group commit: fsync once every `max_entries` writes. Entries not yet
+    /// synced are flushed by [`CoreDB::sync`], batch operations and drop.
+    /// A power loss can lose at most the last unsynced group.
+    Batch { max_entries: usize },
+    /// Never fsync explicitly; leave it to the OS page cache.
+    Never,
+}
+
+impl Default for CoreDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for CoreDB {
+    fn drop(&mut self) {
+        // Flush a pending group commit; errors cannot be reported from drop.
+        if self.wal_unsynced > 0 && self.wal_sync != WalSync::Never {
+            let _ = self.sync();
+        }
+    }
+}
+
+impl CoreDB {
+    // ── Constructors ──────────────────────────────────────────────────────────
+
+    /// Create a new in-memory database (no persistence).
+    pub fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            slug_map: HashMap::new(),
+            edges: storage::edgestore::EdgeStore::new_fat(),
+            collections: HashMap::new(),
+            collection_names_map: HashMap::new(),
+            wal: None,
+            data_dir: None,
+            spatial_grid: None,
+            text_indexes: HashMap::new(),
+            gin_indexes: HashMap::new(),
+            bm25_indexes: HashMap::new(),
+            search_indexes: HashMap::new(),
+            schemas: HashMap::new(),
+            vectors: HashMap::new(),
+            hnsw_indexes: HashMap::new(),
+            field_indexes: HashMap::new(),
+            simhashes: HashMap::new(),
+            hnsw_params: HashMap::new(),
+            payload_store: PayloadStore::new(),
+            replaying: false,
+            keep_timestamps: false,
+            pending_txn: None,
+            defer_wal_sync: false,
+            wal_sync: WalSync::Always,
+            wal_unsynced: 0,
+            query_limits: QueryLimits::default(),
+            near_filter_threshold: None,
+            filter_costs: query::FilterCosts::default(),
+            quotas: HashMap::new(),
+            history_retention: HashMap::new(),
+            history: HashMap::new(),
+            allow_parallel_edges: false,
+            pending_index_rebuild: None,
+            wal_corrupted: false,
+            collection_filters: HashMap::new(),
+            trash: HashMap::new(),
+            edge_aggregates: Vec::new(),
+            projection_profiles: Vec::new(),
+            replica: None,
+            replication: None,
+            replication_error: None,
+            change_log: None,
+            clock: std::sync::Arc::new(SystemClock),
+            auto_ids: ulid::UlidGen::default(),
+            jobs: jobs::Jobs::default(),
+            prepared: std::sync::Mutex::new(HashMap::new()),
+            _lock_file: None,
+        }
+    }
+
+    /// Open (or create) a persistent database in `dir`.
+    ///
+    /// Uses [`EdgeMode::Compact`] by default (disk-first edge metadata).
+    /// For the original all-in-RAM behaviour, use
+    /// [`open_with_config`](Self::open_with_config) with [`EdgeMode::Fat`].
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_config(dir, Config::default())
+    }
+
+    /// Start configuring a persistent database in `dir`; finish with
+    /// [`CoreDBBuilder::open`]. Unset options take their [`Config`] defaults.
+    pub fn builder(dir: impl AsRef<Path>) -> CoreDBBuilder {
+        CoreDBBuilder { dir: dir.as_ref().to_path_buf(), config: Config::default() }
+    }
+
+    /// Open a database in read-only mode (no lock, no WAL writer).
+    ///
+    /// Suitable for read replicas that sync their local directory from S3.
+    /// Write operations will silently skip WAL persistence.
+    pub fn open_read_only(dir: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_config(
+            dir,
+            Config { read_only: true, ..Config::default() },
+        )
+    }
+
+    /// Open a read-only database backed by S3 remote storage.
+    ///
+    /// Downloads only the snapshot (node index, ~100 B/node) and loads it
+    /// into RAM. Payloads stay on S3 — each `get_payload()` call fetches
+    /// the relevant 64 KB block via `GET_RANGE` and caches it in a bounded
+    /// LRU. No local `payloads.bin` file is needed.
+    ///
+    /// This allows querying a 1 TB dataset from a machine with 50 GB of disk:
+    /// the node index stays in RAM (~hundreds of MB), the block cache keeps
+    /// hot payload blocks on local storage, and cold blocks are fetched on
+    /// demand from S3.
+    /// Open a read-only database backed by S3.
+    ///
+    /// Payloads are fetched on demand via S3 `GET_RANGE` and cached in an
+    /// LRU cache bounded by `cache_budget`.
+    ///
+    /// - Without `cache_dir`: budget controls RAM cache size. Evicted blocks
+    ///   are discarded.
+    /// - With `cache_dir`: budget controls disk cache size (RAM tier is 256 MB).
+    ///   Evicted blocks are spilled to disk and survive restarts.
+    #[cfg(feature = "s3")]
+    pub fn open_s3(
+        remote: &engine::remote::RemoteSync,
+        cache_budget: engine::cache::CacheBudget,
+        cache_dir: Option<&std::path::Path>,
+    ) -> Result<Self, String> {
+        Self::open_s3_inner(remote, cache_budget, cache_dir)
+    }
+
+    #[cfg(feature = "s3")]
+    fn open_s3_inner(
+        remote: &engine::remote::RemoteSync,
+        cache_budget: engine::cache::CacheBudget,
+        cache_dir: Option<&std::path::Path>,
+    ) -> Result<Self, String> {
+        let manifest = remote
+            .get_manifest()?
+            .ok_or("no manifest found on remote")?;
+
+        // Find payloads.bin size from manifest.
+        let payload_size = manifest
+            .segments
+            .iter()
+            .find(|s| s.name == "payloads.bin")
+            .map(|s| s.size)
+            .unwrap_or(0);
+
+        // Fetch snapshot.json via RemoteSync (reuses its existing Runtime/connection).
+        let snap_bytes = remote.fetch_file("snapshot.json")?;
+
+        let snap: Snapshot = serde_json::from_slice(&snap_bytes)
+            .map_err(|e| format!("parsing snapshot: {e}"))?;
+
+        let mut block_cache = if cache_dir.is_some() {
+            // Disk-cached mode: budget controls disk tier, RAM is default 256MB.
+            engine::cache::BlockCache::new(
+                remote.store(),
+                remote.prefix(),
+                "payloads.bin",
+                payload_size,
+                cache_budget,
+            )?
+        } else {
+            // RAM-only mode: budget controls RAM tier, no disk.
+            engine::cache::BlockCache::new(
+                remote.store(),
+                remote.prefix(),
+                "payloads.bin",
                 payload_size,
                 engine::cache::CacheBudget::new(0),
             )?
@@ -597,8 +1781,19 @@ impl CoreDB {
             },
         };
 
+        let sidecar_gen = snap.sidecar_gen;
         db.load_snapshot(snap);
 
+        // Edges of a compacted snapshot live in edges.bin, not snapshot.json.
+        if let Some(generation) = sidecar_gen {
+            let bytes = remote.fetch_file("edges.bin")?;
+            if bytes.len() < 16 || &bytes[..8] != b"SKEDGE01" || bytes[8..16] != generation.to_le_bytes() {
+                return Err(format!("remote edges.bin does not match snapshot generation {generation}"));
+            }
+            db.edges.read_adjacency(&mut &bytes[16..])
+                .map_err(|e| format!("reading edges.bin: {e}"))?;
+        }
+
         // Download small index files (GIN, search) if they exist on remote,
         // then load them to restore full-text search capability.
         let has_gin = manifest.segments.iter().any(|s| s.name == "gin.bin" && s.size > 12);
@@ -639,6 +1834,7 @@ impl CoreDB {
 
         // Rebuild HNSW from vectors loaded via snapshot.
         db.rebuild_declared_hnsw_indexes();
+        db.rebuild_declared_simhash_indexes();
 
         Ok(db)
     }
@@ -657,11 +1853,37 @@ impl CoreDB {
     /// Returns an error if the directory cannot be created, the snapshot
     /// cannot be parsed, or the WAL file cannot be opened.
     pub fn open_with_config(dir: impl AsRef<Path>, config: Config) -> io::Result<Self> {
-        let dir = dir.as_ref();
-        std::fs::create_dir_all(dir)?;
+        Self::open_with_progress(dir, config, |_| {})
+    }
 
-        // Acquire exclusive file lock to prevent concurrent access.
-        // Skipped in read-only mode (read replicas don't need exclusion).
+    /// Like [`open_with_config`](Self::open_with_config), calling `progress`
+    /// as each [`OpenStage`] starts so callers can report on long opens.
+    ///
+    /// Combine with [`Config::defer_index_rebuild`] to start serving as soon
+    /// as nodes, edges and the spatial grid are loaded, then finish the
+    /// secondary indexes with [`rebuild_pending_indexes`](Self::rebuild_pending_indexes).
+    ///
+    /// ```
+    /// # use sekejap::{Config, CoreDB, OpenStage};
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let mut stages = Vec::new();
+    /// let config = Config { defer_index_rebuild: true, ..Config::default() };
+    /// let mut db = CoreDB::open_with_progress(dir.path(), config, |s| stages.push(s)).unwrap();
+    /// assert!(!stages.contains(&OpenStage::Indexes));
+    /// assert!(db.has_pending_indexes());
+    /// db.rebuild_pending_indexes();
+    /// assert!(!db.has_pending_indexes());
+    /// ```
+    pub fn open_with_progress(
+        dir: impl AsRef<Path>,
+        config: Config,
+        mut progress: impl FnMut(OpenStage),
+    ) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        // Acquire exclusive file lock to prevent concurrent access.
+        // Skipped in read-only mode (read replicas don't need exclusion).
         let lock_file = if config.read_only {
             None
         } else {
@@ -681,10 +1903,31 @@ impl CoreDB {
         let mut db = Self::new();
         db.data_dir = Some(dir.to_path_buf());
         db._lock_file = lock_file;
+        if config.read_only {
+            // Stamped before reading, so a write that lands mid-open shows
+            // up as a change on the next refresh().
+            db.replica = Some(Replica { config: config.clone(), stamp: replica_stamp(dir) });
+        }
+        db.nodes.reserve(config.capacity);
+        db.slug_map.reserve(config.capacity);
+        db.clock = config.clock.clone();
+        db.wal_sync = config.wal.sync;
+        db.query_limits = config.limits;
+        db.near_filter_threshold = config.near_filter_threshold;
+        for (collection, quota) in config.quotas {
+            db.set_quota(&collection, quota);
+        }
+        for (collection, versions) in config.history_retention {
+            db.set_history_retention(&collection, versions);
+        }
+        db.allow_parallel_edges = config.allow_parallel_edges;
 
         // Apply edge storage mode from config.
+        //    Read-only handles keep edge metadata in RAM: the compact store
+        //    appends to edge_meta.bin, which belongs to the writer.
         #[cfg(unix)]
         match config.edge_mode {
+            EdgeMode::Compact if config.read_only => {}
             EdgeMode::Compact => {
                 db.edges = storage::edgestore::EdgeStore::open_compact(dir)?;
             }
@@ -694,6 +1937,7 @@ impl CoreDB {
         let _ = &config;
 
         // 1. Load snapshot (peek before touching payloads.bin).
+        progress(OpenStage::Snapshot);
         //    Disk-backed snapshots store only metadata — payloads stay in payloads.bin.
         //    We must NOT truncate payloads.bin in that case.
         let snap_path = dir.join("snapshot.json");
@@ -731,16 +1975,29 @@ impl CoreDB {
         let pay_path = dir.join("payloads.bin");
         let preserve      = snap.as_ref().map_or(false, |s| s.is_disk_backed);
         let has_vec_files = snap.as_ref().map_or(false, |s| s.has_vector_files);
+        //    Read-only handles never write payloads.bin: they map what is there
+        //    and keep replayed payloads in memory.
         if preserve && pay_path.exists() {
             let existing_len = std::fs::metadata(&pay_path)?.len();
-            db.payload_store = PayloadStore::open_existing(&pay_path, existing_len)?;
+            db.payload_store = if config.read_only {
+                PayloadStore::open_replica(&pay_path, existing_len)?
+            } else {
+                PayloadStore::open_existing(&pay_path, existing_len)?
+            };
+        } else if config.read_only {
+            db.payload_store = PayloadStore::new();
         } else {
             db.payload_store = PayloadStore::open_file(&pay_path)?;
         }
 
+        let sidecar_gen = snap.as_ref().and_then(|s| s.sidecar_gen);
         if let Some(snap) = snap {
             db.load_snapshot(snap);
         }
+        if let Some(generation) = sidecar_gen {
+            db.load_edge_binary(dir, generation)?;
+            db.load_spatial_binary(&dir.join("spatial.bin"), generation);
+        }
 
         // Open disk-backed vector stores directly from .bin files.
         // When has_vector_files is set, load_snapshot() skipped parsing vectors
@@ -758,8 +2015,17 @@ impl CoreDB {
                         .and_then(|s| s.strip_suffix(".bin"))
                     {
                         if !field.is_empty() && !db.vectors.contains_key(field) {
-                            let store =
+                            let mut store =
                                 storage::vecstore::VectorStore::open_disk(dir, field)?;
+                            if config.read_only {
+                                // Copy into RAM so replayed PutVectors do not
+                                // append to the writer's file.
+                                let mut mem = storage::vecstore::VectorStore::new();
+                                for (id, data) in store.iter() {
+                                    mem.put(id, data.to_vec());
+                                }
+                                store = mem;
+                            }
                             db.vectors.insert(field.to_string(), store);
                         }
                     }
@@ -772,8 +2038,8 @@ impl CoreDB {
         // A normal disk-backed snapshot with 89k nodes is ~50-80 MB (pretty-printed).
         // The legacy bloated variant (gin_indexes embedded as JSON) was 1-10 GB.
         // Use 500 MB as the threshold — safely above any real snapshot, far below bloated ones.
-        if snap_file_size > 500 * 1024 * 1024 {
-            if let Ok(snap_json) = serde_json::to_vec(&db.build_snapshot()) {
+        if snap_file_size > 500 * 1024 * 1024 && !config.read_only {
+            if let Ok(snap_json) = serde_json::to_vec(&db.build_snapshot(None)) {
                 let snap_tmp = snap_path.with_extension("json.tmp");
                 if let Ok(mut sf) = std::fs::File::create(&snap_tmp) {
                     if std::io::Write::write_all(&mut sf, &snap_json).is_ok()
@@ -795,53 +2061,13 @@ impl CoreDB {
         let wal_path = dir.join("wal.log");
         let mut wal_had_payload = false;
         let mut wal_had_graph   = false;
+        progress(OpenStage::WalReplay);
         if wal_path.exists() {
             db.replaying = true;
-            // Transaction-aware replay: entries between TxnBegin and TxnEnd
-            // are buffered and applied together. If TxnEnd is missing (crash
-            // during COMMIT), the entire group is discarded.
-            let mut txn_buf: Option<Vec<WalEntry>> = None;
-            let corrupted = WalReader::open(&wal_path)?.replay_all(|entry| {
-                match &entry {
-                    WalEntry::TxnBegin => {
-                        txn_buf = Some(Vec::new());
-                        return;
-                    }
-                    WalEntry::TxnEnd => {
-                        if let Some(buf) = txn_buf.take() {
-                            for e in buf {
-                                match &e {
-                                    WalEntry::Put { .. }
-                                    | WalEntry::Remove { .. }
-                                    | WalEntry::PutVector { .. } => wal_had_payload = true,
-                                    WalEntry::Link { .. }
-                                    | WalEntry::LinkMeta { .. }
-                                    | WalEntry::Unlink { .. } => wal_had_graph = true,
-                                    _ => {}
-                                }
-                                db.replay(e);
-                            }
-                        }
-                        return;
-                    }
-                    _ => {}
-                }
-                if let Some(buf) = &mut txn_buf {
-                    buf.push(entry);
-                } else {
-                    match &entry {
-                        WalEntry::Put { .. }
-                        | WalEntry::Remove { .. }
-                        | WalEntry::PutVector { .. } => wal_had_payload = true,
-                        WalEntry::Link { .. }
-                        | WalEntry::LinkMeta { .. }
-                        | WalEntry::Unlink { .. } => wal_had_graph = true,
-                        _ => {}
-                    }
-                    db.replay(entry);
-                }
-            });
-            if txn_buf.is_some() {
+            let replayed = db.replay_wal(WalReader::open(&wal_path)?);
+            (wal_had_payload, wal_had_graph) = (replayed.had_payload, replayed.had_graph);
+            let corrupted = replayed.corrupted;
+            if replayed.incomplete_txn {
                 eprintln!(
                     "sekejap: WAL at `{}` had an incomplete transaction — \
                      discarded uncommitted entries.",
@@ -849,6 +2075,7 @@ impl CoreDB {
                 );
             }
             db.replaying = false;
+            db.wal_corrupted = corrupted;
             if corrupted {
                 eprintln!(
                     "sekejap: WAL at `{}` had a corrupted frame — \
@@ -858,57 +2085,40 @@ impl CoreDB {
             }
         }
 
-        // Remap edge metadata mmap so reads cover data written during
-        // snapshot load + WAL replay.
+        // Remap edge metadata and payload mmaps so reads cover data written
+        // during snapshot load + WAL replay.
         #[cfg(unix)]
         db.edges.remap_meta();
+        db.payload_store.remap();
 
         // 3. Open WAL in append mode (skip for read-only replicas).
         if !config.read_only {
             db.wal = Some(WalWriter::open(&wal_path)?);
         }
 
-        // 4. Build spatial index from loaded data
-        db.rebuild_spatial_grid();
+        // 4. Build spatial index from loaded data, unless spatial.bin supplied
+        //    it (WAL replay has already patched it incrementally).
+        progress(OpenStage::SpatialGrid);
+        if db.spatial_grid.is_none() {
+            db.rebuild_spatial_grid();
+        }
 
-        // 5. Rebuild GIN and HNSW when WAL added new data, or load GIN from the
-        //    binary sidecar gin.bin (compact, fast — no JSON parsing overhead).
-        //    GIN: only rebuild when payload-mutating entries (Put/Remove) were in WAL.
-        //    HNSW: rebuild when any data changed (payloads or vectors).
-        let gin_bin_path = dir.join("gin.bin");
-        let search_bin_path = dir.join("search.bin");
-        if wal_had_payload {
-            // Payload changed — rebuild all declared indexes from current data.
-            // BM25/GIN/HNSW/Search builds are skipped during replay (apply_index guards
-            // on self.replaying) so we must rebuild them all here, once.
-            db.rebuild_declared_bm25_indexes();
-            db.rebuild_declared_gin_indexes();
-            db.rebuild_declared_hnsw_indexes();
-            db.rebuild_declared_search_indexes();
-            let _ = db.save_gin_binary(&gin_bin_path);
-            let _ = db.save_search_binary(&search_bin_path);
+        // 5. Secondary indexes — now, or later via rebuild_pending_indexes().
+        if config.defer_index_rebuild {
+            db.pending_index_rebuild = Some(wal_had_payload);
         } else {
-            // No payload changes — try loading GIN from gin.bin. If missing or
-            // stale, rebuild once (covers first open after CREATE INDEX, etc.).
-            if !db.load_gin_binary(&gin_bin_path) {
-                db.rebuild_declared_gin_indexes();
-                let _ = db.save_gin_binary(&gin_bin_path);
-            }
-            if !db.load_search_binary(&search_bin_path) {
-                db.rebuild_declared_search_indexes();
-                let _ = db.save_search_binary(&search_bin_path);
-            }
-            // HNSW: rebuild only when vectors changed (PutVector is part of wal_had_payload,
-            // so here vectors are unchanged — no rebuild needed).
+            progress(OpenStage::Indexes);
+            let _ = db.rebuild_open_indexes(wal_had_payload, &Job::detached(JobKind::IndexRebuild));
         }
         let _ = wal_had_graph; // used only to determine topology was replayed (no index rebuild needed)
 
         // 6. Migrate in-memory vectors to disk-backed stores.
+        progress(OpenStage::Vectors);
         //    Stores already opened as disk (from .bin files) are left alone.
         //    Only memory-mode stores (from legacy snapshot or WAL-only fields)
         //    are written out to binary files and switched to disk mode.
         #[cfg(unix)]
-        {
+        if !config.read_only {
             let fields: Vec<String> = db.vectors.keys().cloned().collect();
             for field in fields {
                 if db.vectors.get(&field).map_or(false, |s| s.is_disk()) {
@@ -929,12 +2139,162 @@ impl CoreDB {
         Ok(db)
     }
 
+    /// An in-memory database made durable by a caller-supplied [`WalStore`],
+    /// for platforms without a filesystem (a browser build backs the store
+    /// with OPFS or IndexedDB). Every frame in `store` is replayed, then new
+    /// writes are appended to it under the usual [`WalSync`] policy, and
+    /// [`compact`](Self::compact) atomically replaces the log with the
+    /// current state.
+    ///
+    /// Replay goes through the same path as live writes, so every declared
+    /// index is up to date when this returns. A torn tail is dropped as on
+    /// [`open`](Self::open) and reported by [`health`](Self::health).
+    ///
+    /// # Errors
+    /// Fails if the store cannot be read.
+    pub fn open_with_wal_store(store: Box<dyn WalStore>) -> io::Result<Self> {
+        let mut db = CoreDB::new();
+        let mut wal = WalWriter::from_store(store);
+        let replayed = db.replay_wal(wal.reader()?);
+        db.wal_corrupted = replayed.corrupted;
+        db.wal = Some(wal);
+        Ok(db)
+    }
+
+    /// Whether open left index rebuilds for
+    /// [`rebuild_pending_indexes`](Self::rebuild_pending_indexes).
+    /// Until then, GIN, BM25, HNSW, search and SimHash lookups see only what
+    /// was loaded from their sidecar files, which may be nothing.
+    pub fn has_pending_indexes(&self) -> bool {
+        self.pending_index_rebuild.is_some()
+    }
+
+    /// Finish the index work skipped by [`Config::defer_index_rebuild`].
+    /// No-op when nothing is pending.
+    ///
+    /// Runs as a [`JobKind::IndexRebuild`] job. Cancelled between index
+    /// families, the rebuild stays pending and starts over next time.
+    pub fn rebuild_pending_indexes(&mut self) {
+        if self.pending_index_rebuild.is_some() {
+            let jobs = self.jobs.clone();
+            let _ = jobs.run(JobKind::IndexRebuild, String::new(), |job| self.rebuild_pending_in(job));
+        }
+    }
+
+    fn rebuild_pending_in(&mut self, job: &Job) -> io::Result<()> {
+        if let Some(wal_had_payload) = self.pending_index_rebuild {
+            self.rebuild_open_indexes(wal_had_payload, job)?;
+            self.pending_index_rebuild = None;
+        }
+        Ok(())
+    }
+
+    /// Catch a read-only handle up with the process writing its directory.
+    ///
+    /// Reopens with the original [`Config`] when `snapshot.json` or
+    /// `wal.log` changed since this handle was loaded (a write or a
+    /// compaction); returns whether it did. Call it on a timer for a
+    /// one-writer, many-reader-process deployment.
+    ///
+    /// # Errors
+    /// `InvalidInput` on a database not opened read-only; otherwise any
+    /// error from reopening, in which case `self` is left as it was.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let mut writer = CoreDB::open(dir.path()).unwrap();
+    /// let mut reader = CoreDB::open_read_only(dir.path()).unwrap();
+    /// writer.put("k/1", r#"{"v":1}"#).unwrap();
+    /// assert!(!reader.contains("k/1"));
+    /// assert!(reader.refresh().unwrap());
+    /// assert!(reader.contains("k/1"));
+    /// assert!(!reader.refresh().unwrap());
+    /// ```
+    pub fn refresh(&mut self) -> io::Result<bool> {
+        let (Some(replica), Some(dir)) = (&self.replica, &self.data_dir) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "refresh() needs a read-only database"));
+        };
+        if replica_stamp(dir) == replica.stamp {
+            return Ok(false);
+        }
+        let jobs = self.jobs.clone();
+        *self = Self::open_with_config(dir.clone(), replica.config.clone())?;
+        self.jobs = jobs;
+        Ok(true)
+    }
+
+    /// Step 5 of open: rebuild GIN and HNSW when WAL added new data, or load
+    /// GIN from the binary sidecar gin.bin (compact, fast — no JSON parsing
+    /// overhead).
+    /// Reports each index family rebuilt to `job`, which may stop the
+    /// rebuild between them.
+    fn rebuild_open_indexes(&mut self, wal_had_payload: bool, job: &Job) -> io::Result<()> {
+        let Some(dir) = self.data_dir.clone() else { return Ok(()) };
+        let gin_bin_path = dir.join("gin.bin");
+        let search_bin_path = dir.join("search.bin");
+        const FAMILIES: u64 = 6;
+        job.checkpoint(0, FAMILIES)?;
+        self.rebuild_declared_simhash_indexes();
+        if wal_had_payload {
+            // Payload changed — rebuild all declared indexes from current data.
+            // BM25/GIN/HNSW/Search builds are skipped during replay (apply_index guards
+            // on self.replaying) so we must rebuild them all here, once.
+            job.checkpoint(1, FAMILIES)?;
+            self.rebuild_declared_bm25_indexes();
+            job.checkpoint(2, FAMILIES)?;
+            self.rebuild_declared_gin_indexes();
+            job.checkpoint(3, FAMILIES)?;
+            self.rebuild_declared_hnsw_indexes();
+            job.checkpoint(4, FAMILIES)?;
+            self.rebuild_declared_search_indexes();
+            if self.replica.is_none() {
+                let _ = self.save_gin_binary(&gin_bin_path);
+                let _ = self.save_search_binary(&search_bin_path);
+            }
+        } else {
+            // No payload changes — try loading GIN from gin.bin. If missing or
+            // stale, rebuild once (covers first open after CREATE INDEX, etc.).
+            job.checkpoint(2, FAMILIES)?;
+            if !self.load_gin_binary(&gin_bin_path) {
+                self.rebuild_declared_gin_indexes();
+                if self.replica.is_none() {
+                    let _ = self.save_gin_binary(&gin_bin_path);
+                }
+            }
+            job.checkpoint(4, FAMILIES)?;
+            if !self.load_search_binary(&search_bin_path) {
+                self.rebuild_declared_search_indexes();
+                if self.replica.is_none() {
+                    let _ = self.save_search_binary(&search_bin_path);
+                }
+            }
+            // HNSW: rebuild only when vectors changed (PutVector is part of wal_had_payload,
+            // so here vectors are unchanged — no rebuild needed).
+        }
+        job.checkpoint(5, FAMILIES)?;
+        self.build_missing_hnsw_indexes();
+        job.report(FAMILIES, FAMILIES);
+        Ok(())
+    }
+
     // ── Raw internals (no WAL write — used during replay and open) ────────────
 
+    /// Store a node's serialized payload, reusing its previous blob (`old`)
+    /// when the new bytes fit there so upserts don't grow the slab.
+    fn store_payload(&mut self, old: Option<(u64, u32)>, bytes: &[u8]) -> (u64, u32) {
+        if let Some((off, len)) = old {
+            if self.payload_store.overwrite(off, len, bytes) {
+                return (off, bytes.len() as u32);
+            }
+        }
+        self.payload_store.append(bytes)
+    }
+
     fn put_raw(&mut self, slug: &str, payload_json: &str) -> Result<u64, serde_json::Error> {
         let mut payload: Value = serde_json::from_str(payload_json)?;
         let hash = sk_hash(slug);
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = self.now_millis();
 
         // Collect old node metadata (separate let to release borrow before mutations)
         let old_info: Option<(String, u64, u32)> = self.nodes
@@ -963,7 +2323,12 @@ impl CoreDB {
             } else {
                 obj.insert("_created_unix".into(), serde_json::json!(now));
             }
-            obj.insert("_updated_unix".into(), serde_json::json!(now));
+            if !(self.keep_timestamps && obj.contains_key("_updated_unix")) {
+                obj.insert("_updated_unix".into(), serde_json::json!(now));
+            }
+        }
+        if !self.edge_aggregates.is_empty() {
+            self.apply_edge_aggregates(hash, &mut payload);
         }
 
         // Extract spatial meta now (while we have the parsed Value in hand).
@@ -971,6 +2336,14 @@ impl CoreDB {
         // re-parsing geometry from disk.
         let spatial_meta = geo::extract_spatial_meta(&payload);
 
+        let fingerprint = payload.get("_collection")
+            .and_then(|v| v.as_str())
+            .and_then(|coll| self.simhash_of(coll, &payload));
+        match fingerprint {
+            Some(fp) => { self.simhashes.insert(hash, fp); }
+            None => { self.simhashes.remove(&hash); }
+        }
+
         // Remove old collection + field-index entries for this hash (if updating)
         if let Some((ref old_coll, old_off, old_len)) = old_info {
             if !old_coll.is_empty() {
@@ -987,7 +2360,7 @@ impl CoreDB {
                     for ((idx_coll, idx_field), btree) in &mut self.field_indexes {
                         if *idx_coll == coll_hash {
                             if let Some(key) = FieldKey::from_json(
-                                old_payload.get(idx_field.as_str()).unwrap_or(&Value::Null)
+                                &crate::query::json_path_get(idx_field, &old_payload).unwrap_or(Value::Null)
                             ) {
                                 if let Some(ids) = btree.get_mut(&key) {
                                     ids.retain(|&id| id != hash);
@@ -1011,7 +2384,7 @@ impl CoreDB {
             for ((idx_coll, idx_field), btree) in &mut self.field_indexes {
                 if *idx_coll == coll_hash {
                     if let Some(key) = FieldKey::from_json(
-                        payload.get(idx_field.as_str()).unwrap_or(&Value::Null)
+                        &crate::query::json_path_get(idx_field, &payload).unwrap_or(Value::Null)
                     ) {
                         let ids = btree.entry(key).or_default();
                         if !ids.contains(&hash) { ids.push(hash); }
@@ -1035,7 +2408,23 @@ impl CoreDB {
 
         // Serialize updated payload and store bytes in the slab.
         let serialized = serde_json::to_string(&payload)?;
-        let (offset, len) = self.payload_store.append(serialized.as_bytes());
+        let old_slot = old_info.as_ref().map(|(_, off, len)| (*off, *len));
+        let keep = payload.get("_collection")
+            .and_then(|v| v.as_str())
+            .and_then(|coll| self.history_retention.get(&sk_hash(coll)))
+            .copied()
+            .unwrap_or(0);
+        let (offset, len) = match old_slot {
+            // A retained version keeps its bytes, so the new one must not
+            // overwrite them in place.
+            Some(slot) if keep > 0 => {
+                let versions = self.history.entry(hash).or_default();
+                versions.push_front(slot);
+                versions.truncate(keep);
+                self.payload_store.append(serialized.as_bytes())
+            }
+            _ => self.store_payload(old_slot, serialized.as_bytes()),
+        };
 
         let collection_str = payload.get("_collection")
             .and_then(|v| v.as_str())
@@ -1069,10 +2458,25 @@ impl CoreDB {
         Ok(hash)
     }
 
+    /// Replace a node's cached geometry after its payload was rewritten
+    /// outside `put_raw`, keeping the spatial grid in step.
+    fn set_spatial_meta(&mut self, hash: u64, meta: Option<geo::SpatialMeta>) {
+        let Some(node) = self.nodes.get_mut(&hash) else { return };
+        node.spatial_meta = meta.clone();
+        if let Some(grid) = &mut self.spatial_grid {
+            grid.remove(hash);
+            if let Some(meta) = meta {
+                grid.insert(hash, meta);
+            }
+        }
+    }
+
     fn remove_raw(&mut self, slug: &str) {
         let hash = sk_hash(slug);
         if let Some(node) = self.nodes.remove(&hash) {
             self.slug_map.remove(slug);
+            self.simhashes.remove(&hash);
+            self.history.remove(&hash);
             if !node.collection.is_empty() {
                 let coll_hash = sk_hash(&node.collection);
                 if let Some(members) = self.collections.get_mut(&coll_hash) {
@@ -1090,7 +2494,7 @@ impl CoreDB {
                     for ((idx_coll, idx_field), btree) in &mut self.field_indexes {
                         if *idx_coll == coll_hash {
                             if let Some(key) = FieldKey::from_json(
-                                old_payload.get(idx_field.as_str()).unwrap_or(&Value::Null)
+                                &crate::query::json_path_get(idx_field, &old_payload).unwrap_or(Value::Null)
                             ) {
                                 if let Some(ids) = btree.get_mut(&key) {
                                     ids.retain(|&id| id != hash);
@@ -1102,7 +2506,15 @@ impl CoreDB {
                 }
             }
             // Cascade-delete edges involving this node (both directions).
+            let neighbours: Vec<u64> = if self.edge_aggregates.is_empty() {
+                Vec::new()
+            } else {
+                let fwd = self.edges.fwd_edges(hash).unwrap_or(&[]).iter();
+                let rev = self.edges.rev_edges(hash).unwrap_or(&[]).iter();
+                fwd.chain(rev).map(|e| e.other).filter(|&o| o != hash).collect()
+            };
             self.edges.remove_node(hash);
+            self.refresh_edge_aggregates(&neighbours);
 
             if let Some(grid) = &mut self.spatial_grid {
                 grid.remove(hash);
@@ -1149,6 +2561,109 @@ impl CoreDB {
         }
     }
 
+    /// Move a live node into the trash. Captures its payload, edges and
+    /// vectors before `remove_raw` detaches them. Returns `false` if the
+    /// slug is not live or an earlier version of it is already trashed.
+    fn remove_soft_raw(&mut self, slug: &str) -> bool {
+        use crate::vector::VectorAccess;
+        let hash = sk_hash(slug);
+        if !self.nodes.contains_key(&hash) || self.trash.contains_key(&hash) {
+            return false;
+        }
+        // A payload the store can no longer read is trashed as empty text,
+        // which `restore` refuses.
+        let payload = self.get(slug).unwrap_or_default();
+
+        let mut edges: Vec<TrashedEdge> = Vec::new();
+        for e in self.edges.fwd_edges(hash).unwrap_or(&[]) {
+            edges.push(TrashedEdge {
+                from: hash,
+                to: e.other,
+                edge_type: self.edges.type_name(e.edge_type).unwrap_or_default().to_string(),
+                strength: e.strength,
+                created_unix: e.created_unix,
+                meta: self.edges.edge_meta(e),
+            });
+        }
+        // Self-loops were already captured from the forward list.
+        for e in self.edges.rev_edges(hash).unwrap_or(&[]).iter().filter(|e| e.other != hash) {
+            edges.push(TrashedEdge {
+                from: e.other,
+                to: hash,
+                edge_type: self.edges.type_name(e.edge_type).unwrap_or_default().to_string(),
+                strength: e.strength,
+                created_unix: e.created_unix,
+                meta: self.edges.edge_meta(e),
+            });
+        }
+
+        let vectors: Vec<(String, Vec<f32>)> = self.vectors.iter()
+            .filter_map(|(field, store)| store.get(hash).map(|v| (field.clone(), v.to_vec())))
+            .collect();
+
+        self.remove_raw(slug);
+        self.trash.insert(hash, TrashedNode { slug: slug.to_string(), payload, edges, vectors });
+        true
+    }
+
+    /// Re-insert a trashed node with its edges and vectors. Returns `false`
+    /// if the slug is not in the trash or a live node has since taken it.
+    fn restore_raw(&mut self, slug: &str) -> bool {
+        let hash = sk_hash(slug);
+        if self.nodes.contains_key(&hash) || !self.trash.contains_key(&hash) {
+            return false;
+        }
+        let node = self.trash.remove(&hash).unwrap();
+        if self.put_raw(&node.slug, &node.payload).is_err() {
+            self.trash.insert(hash, node);
+            return false;
+        }
+
+        let mut relinked = vec![hash];
+        for e in node.edges {
+            let other = if e.from == hash { e.to } else { e.from };
+            if other == hash || self.nodes.contains_key(&other) {
+                relinked.push(other);
+                match e.meta {
+                    Some(meta) => self.edges.link_meta(e.from, e.to, &e.edge_type, e.strength, e.created_unix, meta),
+                    None => self.edges.link(e.from, e.to, &e.edge_type, e.strength, e.created_unix),
+                }
+            } else if let Some(peer) = self.trash.get_mut(&other) {
+                // The other endpoint is trashed too — hand the edge over so it
+                // comes back when that node is restored.
+                peer.edges.push(e);
+            }
+            // Otherwise the other endpoint was purged or removed: drop the edge.
+        }
+        self.refresh_edge_aggregates(&relinked);
+
+        let mut vec_fields: Vec<String> = Vec::new();
+        for (field, data) in node.vectors {
+            self.ensure_vector_store(&field);
+            self.vectors.get_mut(&field).unwrap().put(hash, data);
+            vec_fields.push(field);
+        }
+
+        // Secondary indexes that put_raw does not maintain. During replay
+        // open() rebuilds these once at the end.
+        if !self.replaying {
+            let coll = self.nodes.get(&hash).map(|n| n.collection.clone()).unwrap_or_default();
+            let gin_fields: Vec<String> = self.schemas.get(&coll)
+                .map(|s| s.indexes.fulltext.clone())
+                .unwrap_or_default();
+            for field in gin_fields {
+                self.build_gin_index(&field);
+            }
+            for field in vec_fields {
+                if self.hnsw_indexes.contains_key(&field) {
+                    let (m, ef) = self.hnsw_params.get(&field).copied().unwrap_or((16, 200));
+                    let _ = self.build_hnsw_raw(&field, m, ef, |_, _| true);
+                }
+            }
+        }
+        true
+    }
+
     /// Drop an entire collection: removes all its nodes (cascading all edges),
     /// clears the declared schema, and removes the collection-level btree index
     /// entries. Returns the number of nodes deleted.
@@ -1180,6 +2695,7 @@ impl CoreDB {
 
         // Remove declared schema (if any)
         self.schemas.remove(collection);
+        self.collection_filters.remove(&col_hash);
 
         count
     }
@@ -1223,6 +2739,7 @@ impl CoreDB {
                     let ix = &mut schema.indexes;
                     ix.range.retain(|f| f != &name);
                     ix.hash.retain(|f| f != &name);
+                    ix.unique.retain(|f| f != &name);
                     let had_fulltext = ix.fulltext.iter().any(|f| f == &name);
                     ix.fulltext.retain(|f| f != &name);
                     let had_bm25 = ix.bm25.iter().any(|f| f == &name);
@@ -1257,11 +2774,15 @@ impl CoreDB {
                         }
                     }
                 }
+                let drops_geometry = name == "geometry";
                 for (h, new_off, new_len) in node_updates {
                     if let Some(node) = self.nodes.get_mut(&h) {
                         node.payload_offset = new_off;
                         node.payload_len = new_len;
                     }
+                    if drops_geometry {
+                        self.set_spatial_meta(h, None);
+                    }
                 }
 
                 // Rebuild global indexes from remaining data (nodes for the dropped
@@ -1299,7 +2820,8 @@ impl CoreDB {
                     .filter_map(|&h| self.nodes.get(&h).map(|n| (h, n.payload_offset, n.payload_len)))
                     .collect();
                 let mut count = 0usize;
-                let mut node_updates: Vec<(u64, u64, u32)> = Vec::new();
+                let moves_geometry = old_name == "geometry" || new_name == "geometry";
+                let mut node_updates: Vec<(u64, u64, u32, Option<geo::SpatialMeta>)> = Vec::new();
                 for (h, off, len) in node_meta {
                     if let Some(mut p) = self.payload_store.get(off, len) {
                         if let Some(obj) = p.as_object_mut() {
@@ -1308,17 +2830,20 @@ impl CoreDB {
                                 let new_json = serde_json::to_string(&p)
                                     .unwrap_or_else(|_| "{}".to_string());
                                 let (new_off, new_len) = self.payload_store.append(new_json.as_bytes());
-                                node_updates.push((h, new_off, new_len));
+                                node_updates.push((h, new_off, new_len, geo::extract_spatial_meta(&p)));
                                 count += 1;
                             }
                         }
                     }
                 }
-                for (h, new_off, new_len) in node_updates {
+                for (h, new_off, new_len, spatial_meta) in node_updates {
                     if let Some(node) = self.nodes.get_mut(&h) {
                         node.payload_offset = new_off;
                         node.payload_len = new_len;
                     }
+                    if moves_geometry {
+                        self.set_spatial_meta(h, spatial_meta);
+                    }
                 }
 
                 // Move the btree index data from old field name to new field name
@@ -1336,6 +2861,7 @@ impl CoreDB {
                         &mut schema.indexes.bm25,
                         &mut schema.indexes.spatial,
                         &mut schema.indexes.vector,
+                        &mut schema.indexes.unique,
                     ] {
                         for entry in list.iter_mut() {
                             if *entry == old_name {
@@ -1433,7 +2959,7 @@ impl CoreDB {
                     // Drop stale btree entries, then rebuild from current node data.
                     let col_hash = sk_hash(collection);
                     self.field_indexes.remove(&(col_hash, name.clone()));
-                    self.build_field_index(collection, &name);
+                    self.build_field_index_raw(collection, &name);
                 }
 
                 Ok(0)
@@ -1462,6 +2988,7 @@ impl CoreDB {
                     IndexMethod::Bm25                   => &mut schema.indexes.bm25,
                     IndexMethod::Spatial                => &mut schema.indexes.spatial,
                     IndexMethod::Hnsw                   => &mut schema.indexes.vector,
+                    IndexMethod::SimHash                => &mut schema.indexes.simhash,
                     IndexMethod::Search                 => unreachable!(),
                 };
                 let before = list.len();
@@ -1512,6 +3039,9 @@ impl CoreDB {
                 // Spatial grid covers all GEO nodes regardless of collection;
                 // removing the hint is sufficient — no rebuild needed.
             }
+            IndexMethod::SimHash => {
+                self.build_simhash_index(collection);
+            }
             IndexMethod::Search => {
                 // Clear all search indexes for this collection, then rebuild
                 // only those still declared in the schema.
@@ -1621,11 +3151,26 @@ impl CoreDB {
         }
     }
 
-    fn link_raw(&mut self, from: &str, to: &str, edge_type: &str, strength: f32) {
+    fn link_raw(&mut self, from: &str, to: &str, edge_type: &str, strength: f32, created_unix: i64) {
         let from_h = sk_hash(from);
         let to_h = sk_hash(to);
-        let type_h = sk_hash(edge_type);
-        self.edges.link(from_h, to_h, type_h, edge_type, strength);
+        self.edges.link(from_h, to_h, edge_type, strength, created_unix);
+        self.refresh_edge_aggregates(&[from_h, to_h]);
+    }
+
+    /// Refuse edge metadata the edge store cannot index; see
+    /// [`CapacityExceeded`].
+    fn check_edge_meta_capacity(&self, meta_json: &str) -> Result<(), serde_json::Error> {
+        use storage::edgestore::{MAX_META_BYTES, MAX_META_ENTRIES};
+        let requested = meta_json.len() as u64;
+        let err = if self.edges.meta_entries() >= MAX_META_ENTRIES {
+            CapacityExceeded::EdgeMetaEntries { limit: MAX_META_ENTRIES }
+        } else if requested > MAX_META_BYTES {
+            CapacityExceeded::EdgeMetaBytes { limit: MAX_META_BYTES, requested }
+        } else {
+            return Ok(());
+        };
+        Err(serde_json::Error::io(std::io::Error::other(err)))
     }
 
     fn link_meta_raw(
@@ -1634,13 +3179,14 @@ impl CoreDB {
         to: &str,
         edge_type: &str,
         strength: f32,
+        created_unix: i64,
         meta_json: &str,
     ) -> Result<(), serde_json::Error> {
         let meta: Value = serde_json::from_str(meta_json)?;
         let from_h = sk_hash(from);
         let to_h = sk_hash(to);
-        let type_h = sk_hash(edge_type);
-        self.edges.link_meta(from_h, to_h, type_h, edge_type, strength, meta);
+        self.edges.link_meta(from_h, to_h, edge_type, strength, created_unix, meta);
+        self.refresh_edge_aggregates(&[from_h, to_h]);
         Ok(())
     }
 
@@ -1649,26 +3195,197 @@ impl CoreDB {
         let to_h = sk_hash(to);
         let type_h = sk_hash(edge_type);
         self.edges.unlink(from_h, to_h, type_h);
+        self.refresh_edge_aggregates(&[from_h, to_h]);
+    }
+
+    /// Write the declared edge aggregates of `hash`'s collection into `payload`.
+    fn apply_edge_aggregates(&self, hash: u64, payload: &mut Value) {
+        let Some(coll) = payload.get("_collection").and_then(|v| v.as_str()).map(str::to_string) else {
+            return;
+        };
+        for (c, field, agg) in &self.edge_aggregates {
+            if *c != coll {
+                continue;
+            }
+            let (type_h, incoming, sum) = match agg {
+                EdgeAggregate::Count { edge_type, incoming } => (sk_hash(edge_type), *incoming, false),
+                EdgeAggregate::SumStrength { edge_type, incoming } => (sk_hash(edge_type), *incoming, true),
+            };
+            let edges = if incoming { self.edges.rev_edges(hash) } else { self.edges.fwd_edges(hash) };
+            let matching = edges.unwrap_or(&[]).iter().filter(|e| e.edge_type == type_h);
+            let value = if sum {
+                serde_json::json!(matching.map(|e| e.strength as f64).sum::<f64>())
+            } else {
+                serde_json::json!(matching.count())
+            };
+            if let Some(obj) = payload.as_object_mut() {
+                obj.insert(field.clone(), value);
+            }
+        }
+    }
+
+    /// Recompute the edge aggregates stored on each live node in `hashes`,
+    /// rewriting only payloads whose values changed.
+    fn refresh_edge_aggregates(&mut self, hashes: &[u64]) {
+        if self.edge_aggregates.is_empty() {
+            return;
+        }
+        for &h in hashes {
+            let Some(node) = self.nodes.get(&h) else { continue };
+            if !self.edge_aggregates.iter().any(|(c, _, _)| *c == node.collection) {
+                continue;
+            }
+            let slug = node.slug.clone();
+            let Some(stored) = self.payload_store.get(node.payload_offset, node.payload_len) else { continue };
+            let mut payload = stored.clone();
+            self.apply_edge_aggregates(h, &mut payload);
+            if payload != stored {
+                let _ = self.put_raw(&slug, &payload.to_string());
+            }
+        }
+    }
+
+    /// Creation time of the edge a new from → to link of `edge_type` would
+    /// replace, or `None` when parallel edges are allowed or none exists.
+    fn edge_to_replace(&self, from: &str, to: &str, edge_type: &str) -> Option<i64> {
+        if self.allow_parallel_edges {
+            return None;
+        }
+        let (to_h, type_h) = (sk_hash(to), sk_hash(edge_type));
+        self.edges.fwd_edges(sk_hash(from))?
+            .iter()
+            .find(|e| e.other == to_h && e.edge_type == type_h)
+            .map(|e| e.created_unix)
+    }
+
+    /// Unlink (and log) the edge a new link is about to replace, returning
+    /// its creation time. The logged `Unlink` makes replay reproduce the
+    /// upsert whatever `allow_parallel_edges` is at reopen.
+    fn replace_existing_edge(&mut self, from: &str, to: &str, edge_type: &str) -> Option<i64> {
+        let created = self.edge_to_replace(from, to, edge_type)?;
+        self.wal_write(WalEntry::Unlink {
+            from: from.to_string(),
+            to: to.to_string(),
+            edge_type: edge_type.to_string(),
+        });
+        self.unlink_raw(from, to, edge_type);
+        Some(created)
     }
 
     // ── WAL helpers ───────────────────────────────────────────────────────────
 
     fn wal_write(&mut self, entry: WalEntry) {
+        // Writes made before a deferred index rebuild make the sidecar files
+        // stale, so that rebuild must start from the data instead.
+        if let Some(changed) = &mut self.pending_index_rebuild {
+            *changed |= matches!(entry,
+                WalEntry::Put { .. } | WalEntry::Remove { .. } | WalEntry::SoftRemove { .. }
+                | WalEntry::Restore { .. } | WalEntry::Purge { .. } | WalEntry::PutVector { .. });
+        }
+        if let Some(log) = &mut self.change_log {
+            log.record(&entry);
+        }
         if let Some(wal) = &mut self.wal {
             wal.append(&entry)
                 .expect("sekejap: WAL write failed — disk error");
-            if !self.defer_wal_sync {
+            self.wal_unsynced += 1;
+            let due = !self.defer_wal_sync && match self.wal_sync {
+                WalSync::Always => true,
+                WalSync::Batch { max_entries } => self.wal_unsynced >= max_entries,
+                WalSync::Never => false,
+            };
+            if due {
                 wal.sync()
                     .expect("sekejap: WAL fsync failed — disk error");
+                self.wal_unsynced = 0;
+            }
+        }
+        // Shipped only once the entry is in the local WAL, so a follower
+        // never runs ahead of it.
+        if self.replication.is_some() {
+            self.ship(&entry);
+        }
+    }
+
+    /// Queue one entry for the follower without waiting on it. A follower
+    /// whose writes failed, or that fell [`REPLICATION_QUEUE`] lines behind,
+    /// is dropped so the write itself still succeeds.
+    fn ship(&mut self, entry: &WalEntry) {
+        let Some(shipper) = &self.replication else { return };
+        // Puts carry the stored payload, so the follower keeps this
+        // database's `_created_unix` / `_updated_unix`.
+        let stored = match entry {
+            WalEntry::Put { slug, .. } => self.get(slug).map(|payload| WalEntry::Put { slug: slug.clone(), payload }),
+            _ => None,
+        };
+        let mut line = serde_json::to_vec(stored.as_ref().unwrap_or(entry)).expect("WAL entries serialise");
+        line.push(b'\n');
+        match shipper.lines.try_send(line) {
+            Ok(()) => {}
+            Err(std::sync::mpsc::TrySendError::Full(_)) => {
+                // The writer thread still drains what is queued, then ends.
+                self.replication = None;
+                self.replication_error = Some(io::Error::other(format!(
+                    "follower fell more than {REPLICATION_QUEUE} writes behind"
+                )));
+            }
+            Err(std::sync::mpsc::TrySendError::Disconnected(_)) => {
+                let shipper = self.replication.take().unwrap();
+                let err = shipper.finish().err();
+                self.replication_error = Some(err.unwrap_or_else(|| io::Error::other("replication writer stopped")));
             }
         }
     }
 
     fn wal_flush(&mut self) {
+        if self.wal_sync == WalSync::Never {
+            return;
+        }
         if let Some(wal) = &mut self.wal {
             wal.sync()
                 .expect("sekejap: WAL fsync failed — disk error");
-        }
+            self.wal_unsynced = 0;
+        }
+    }
+
+    /// Apply every frame from `reader`. Entries between `TxnBegin` and
+    /// `TxnEnd` are buffered and applied together; if `TxnEnd` is missing
+    /// (crash during COMMIT), the entire group is discarded.
+    fn replay_wal(&mut self, reader: WalReader) -> WalReplay {
+        let mut out = WalReplay::default();
+        let mut txn_buf: Option<Vec<WalEntry>> = None;
+        let apply = |db: &mut Self, out: &mut WalReplay, entry: WalEntry| {
+            match &entry {
+                WalEntry::Put { .. }
+                | WalEntry::Remove { .. }
+                | WalEntry::SoftRemove { .. }
+                | WalEntry::Restore { .. }
+                | WalEntry::PutVector { .. } => out.had_payload = true,
+                WalEntry::Link { .. }
+                | WalEntry::LinkMeta { .. }
+                | WalEntry::Unlink { .. } => out.had_graph = true,
+                _ => {}
+            }
+            if let Some(log) = &mut db.change_log {
+                log.record(&entry);
+            }
+            db.replay(entry);
+        };
+        let corrupted = reader.replay_all(|entry| match entry {
+            WalEntry::TxnBegin => txn_buf = Some(Vec::new()),
+            WalEntry::TxnEnd => {
+                for e in txn_buf.take().unwrap_or_default() {
+                    apply(self, &mut out, e);
+                }
+            }
+            entry => match &mut txn_buf {
+                Some(buf) => buf.push(entry),
+                None => apply(self, &mut out, entry),
+            },
+        });
+        out.corrupted = corrupted;
+        out.incomplete_txn = txn_buf.is_some();
+        out
     }
 
     fn replay(&mut self, entry: WalEntry) {
@@ -1677,13 +3394,44 @@ impl CoreDB {
                 let _ = self.put_raw(&slug, &payload);
             }
             WalEntry::Remove { slug } => self.remove_raw(&slug),
+            WalEntry::SoftRemove { slug } => {
+                self.remove_soft_raw(&slug);
+            }
+            WalEntry::Restore { slug } => {
+                self.restore_raw(&slug);
+            }
+            WalEntry::Purge { slug } => {
+                self.trash.remove(&sk_hash(&slug));
+            }
+            WalEntry::SetCollectionFilter { collection, filter } => {
+                let _ = self.set_collection_filter_raw(&collection, filter.as_deref());
+            }
+            WalEntry::SetEdgeAggregate { collection, field, aggregate_json } => {
+                let aggregate = aggregate_json.and_then(|j| serde_json::from_str(&j).ok());
+                self.set_edge_aggregate_raw(&collection, &field, aggregate);
+            }
+            WalEntry::SetProjectionProfile { collection, profile, exclude } => {
+                self.set_projection_profile_raw(&collection, &profile, exclude);
+            }
+            WalEntry::BuildHnsw { field, m, ef_construction } => {
+                // During open, vectors replayed after this entry are not in
+                // a graph built now; build_missing_hnsw_indexes() runs once
+                // replay is done.
+                self.hnsw_params.insert(field.clone(), (m, ef_construction));
+                if self.replaying {
+                    self.hnsw_indexes.remove(&field);
+                } else {
+                    let _ = self.build_hnsw_raw(&field, m, ef_construction, |_, _| true);
+                }
+            }
             WalEntry::Link {
                 from,
                 to,
                 edge_type,
                 strength,
+                created_unix,
             } => {
-                self.link_raw(&from, &to, &edge_type, strength);
+                self.link_raw(&from, &to, &edge_type, strength, created_unix.unwrap_or(0));
             }
             WalEntry::LinkMeta {
                 from,
@@ -1691,8 +3439,9 @@ impl CoreDB {
                 edge_type,
                 strength,
                 meta,
+                created_unix,
             } => {
-                let _ = self.link_meta_raw(&from, &to, &edge_type, strength, &meta);
+                let _ = self.link_meta_raw(&from, &to, &edge_type, strength, created_unix.unwrap_or(0), &meta);
             }
             WalEntry::Unlink {
                 from,
@@ -1723,6 +3472,8 @@ impl CoreDB {
                     "bm25"    => IndexMethod::Bm25,
                     "spatial" => IndexMethod::Spatial,
                     "hnsw"    => IndexMethod::Hnsw,
+                    "simhash" => IndexMethod::SimHash,
+                    "search"  => IndexMethod::Search,
                     _ => return,
                 };
                 // WAL replay is fault-tolerant — ignore build failures.
@@ -1741,6 +3492,8 @@ impl CoreDB {
                     "bm25"    => IndexMethod::Bm25,
                     "spatial" => IndexMethod::Spatial,
                     "hnsw"    => IndexMethod::Hnsw,
+                    "simhash" => IndexMethod::SimHash,
+                    "search"  => IndexMethod::Search,
                     _ => return,
                 };
                 self.drop_index_raw(&collection, &m, &field);
@@ -1752,7 +3505,7 @@ impl CoreDB {
             }
             // Transaction markers are handled by the replay loop in open_with_config(),
             // not by individual entry replay. If they reach here, skip them.
-            WalEntry::TxnBegin | WalEntry::TxnEnd => {}
+            WalEntry::TxnBegin | WalEntry::TxnEnd | WalEntry::Manifest(_) => {}
             WalEntry::Unknown => { /* forward-compat: skip entries from newer binaries */ }
         }
     }
@@ -1763,21 +3516,36 @@ impl CoreDB {
     /// registers the node in a named collection for `db.collection()` queries.
     ///
     /// Returns the slug hash on success.
+    ///
+    /// # Errors
+    /// Fails on invalid JSON, a non-object payload, a write that would
+    /// break the collection's [`Quota`] (see [`QuotaExceeded::from_put_error`]),
+    /// or a duplicate value in a schema `unique` field (see
+    /// [`UniqueViolation::from_put_error`]).
     pub fn put(&mut self, slug: &str, payload_json: &str) -> Result<u64, serde_json::Error> {
         // Validate JSON before writing anything.
-        serde_json::from_str::<Value>(payload_json)?;
-
-        // WAL first — if we crash after this but before put_raw, replay recovers.
-        self.wal_write(WalEntry::Put {
-            slug: slug.to_string(),
-            payload: payload_json.to_string(),
-        });
+        let parsed = serde_json::from_str::<Value>(payload_json)?;
+        let collection = parsed.get("_collection").and_then(|v| v.as_str()).unwrap_or("");
+        if !self.quotas.is_empty() {
+            if let Err(q) = self.check_quota(slug, collection, payload_json.len() as u64) {
+                return Err(serde_json::Error::io(std::io::Error::other(q)));
+            }
+        }
+        if let Err(u) = self.check_unique(slug, collection, &parsed) {
+            return Err(serde_json::Error::io(std::io::Error::other(u)));
+        }
 
         // Check before put_raw so we know whether this is a new node or an update.
         let node_hash = sk_hash(slug);
         let is_update = self.nodes.contains_key(&node_hash);
 
         let hash = self.put_raw(slug, payload_json)?;
+        // Logged before returning, as a transaction commit does, so the
+        // entry shipped to a follower carries the stored payload.
+        self.wal_write(WalEntry::Put {
+            slug: slug.to_string(),
+            payload: payload_json.to_string(),
+        });
 
         // Auto-maintain GIN indexes for any field declared fulltext in this collection.
         if let Ok(payload) = serde_json::from_str::<Value>(payload_json) {
@@ -1809,42 +3577,464 @@ impl CoreDB {
         Ok(hash)
     }
 
-    /// Bulk insert. Stops and returns the first error encountered.
-    pub fn put_many<'a>(
+    /// Like [`put`](Self::put), but stamps the node with an expiry time
+    /// `ttl` from now in [`EXPIRES_FIELD`]. Expired nodes stay readable until
+    /// [`evict_expired`](Self::evict_expired) runs; filter them out of
+    /// queries with [`Set::unexpired`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put_with_ttl("buf/1", r#"{"v":1}"#, Duration::from_secs(60)).unwrap();
+    /// db.put_with_ttl("buf/2", r#"{"v":2}"#, Duration::ZERO).unwrap();
+    /// assert_eq!(db.all().unexpired().count(), 1);
+    /// assert_eq!(db.evict_expired(), 1);
+    /// assert!(db.contains("buf/1") && !db.contains("buf/2"));
+    /// ```
+    ///
+    /// # Errors
+    /// As for [`put`](Self::put).
+    pub fn put_with_ttl(
         &mut self,
-        items: impl IntoIterator<Item = (&'a str, &'a str)>,
-    ) -> Result<Vec<u64>, serde_json::Error> {
-        self.defer_wal_sync = true;
-        let result: Result<Vec<u64>, _> = items
-            .into_iter()
-            .map(|(slug, json)| self.put(slug, json))
-            .collect();
-        self.defer_wal_sync = false;
-        self.wal_flush();
-        result
-    }
-
-    /// Remove a node by slug. Also removes its collection membership and edges.
-    pub fn remove(&mut self, slug: &str) {
-        self.wal_write(WalEntry::Remove {
-            slug: slug.to_string(),
-        });
-        self.remove_raw(slug);
-    }
-
-    /// Create a directed edge: `from` → `to` with a type label and strength.
+        slug: &str,
+        payload_json: &str,
+        ttl: std::time::Duration,
+    ) -> Result<u64, serde_json::Error> {
+        let mut payload: Value = serde_json::from_str(payload_json)?;
+        let Some(obj) = payload.as_object_mut() else {
+            return Err(serde_json::Error::io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "payload must be a JSON object",
+            )));
+        };
+        let expires = self.now_millis()
+            .saturating_add(i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX));
+        obj.insert(EXPIRES_FIELD.into(), serde_json::json!(expires));
+        self.put(slug, &payload.to_string())
+    }
+
+    /// Insert a new node into `collection` under a generated id and return
+    /// its slug, `{collection}/{id}`. The id is a 26-character ULID: it sorts
+    /// by creation time, and ids from one database strictly increase. An id
+    /// whose slug is already taken is skipped, so the call never overwrites
+    /// an existing node. `_collection` and `_key` (the id) are set in the
+    /// stored payload.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// let a = db.put_auto("orders", r#"{"total":12}"#).unwrap();
+    /// let b = db.put_auto("orders", r#"{"total":30}"#).unwrap();
+    /// assert!(a.starts_with("orders/") && a.len() == "orders/".len() + 26);
+    /// assert!(a < b);
+    /// assert_eq!(db.collection("orders").count(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// As for [`put`](Self::put).
+    pub fn put_auto(&mut self, collection: &str, payload_json: &str) -> Result<String, serde_json::Error> {
+        let mut payload: Value = serde_json::from_str(payload_json)?;
+        let Some(obj) = payload.as_object_mut() else {
+            return Err(serde_json::Error::io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "payload must be a JSON object",
+            )));
+        };
+        let (slug, key) = loop {
+            let key = ulid::encode(self.auto_ids.next(self.now_millis()));
+            let slug = format!("{collection}/{key}");
+            if !self.slug_map.contains_key(&slug) {
+                break (slug, key);
+            }
+        };
+        obj.insert("_collection".into(), Value::String(collection.to_string()));
+        obj.insert("_key".into(), Value::String(key));
+        self.put(&slug, &payload.to_string())?;
+        Ok(slug)
+    }
+
+    /// Insert a node, or fuse `payload_json` into the existing node according
+    /// to `strategy`. The merged document is written through [`put`](Self::put),
+    /// so indexes and the WAL see a single ordinary update.
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, MergeStrategy};
+    ///
+    /// let mut db = CoreDB::new();
+    /// db.put("p/1", r#"{"tags":["a"],"addr":{"city":"Ubud"}}"#).unwrap();
+    /// db.put_merge("p/1", r#"{"tags":["b"],"addr":{"zip":"80571"}}"#, MergeStrategy::ArrayUnion).unwrap();
+    /// let v: serde_json::Value = serde_json::from_str(&db.get("p/1").unwrap()).unwrap();
+    /// assert_eq!(v["tags"], serde_json::json!(["a", "b"]));
+    /// assert_eq!(v["addr"]["city"], "Ubud");
+    /// ```
+    pub fn put_merge(
+        &mut self,
+        slug: &str,
+        payload_json: &str,
+        strategy: MergeStrategy,
+    ) -> Result<u64, serde_json::Error> {
+        let incoming: Value = serde_json::from_str(payload_json)?;
+        let existing = self.get_payload(sk_hash(slug));
+        let merged = match existing {
+            Some(existing) => merge_payloads(existing, incoming, &strategy),
+            None => incoming,
+        };
+        self.put(slug, &merged.to_string())
+    }
+
+    /// Propose pairs of nodes in `collection` that likely describe the same
+    /// entity, best first. Combines vector similarity, token overlap across
+    /// text fields, and shared linked entities as configured in `opts`; feed
+    /// accepted pairs to [`fuse`](Self::fuse).
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, DedupOptions};
+    ///
+    /// let mut db = CoreDB::new();
+    /// db.put("p/1", r#"{"_collection":"people","name":"Ann Marie Smith"}"#).unwrap();
+    /// db.put("p/2", r#"{"_collection":"people","name":"Smith, Ann Marie"}"#).unwrap();
+    /// db.put("p/3", r#"{"_collection":"people","name":"Bob Jones"}"#).unwrap();
+    /// let opts = DedupOptions { text_fields: vec!["name".into()], ..Default::default() };
+    /// let pairs = db.duplicate_candidates("people", &opts);
+    /// assert_eq!(pairs.len(), 1);
+    /// assert_eq!((pairs[0].a.as_str(), pairs[0].b.as_str()), ("p/1", "p/2"));
+    /// ```
+    pub fn duplicate_candidates(&self, collection: &str, opts: &DedupOptions) -> Vec<DuplicateCandidate> {
+        dedup::candidates(self, collection, opts)
+    }
+
+    /// Fuse `duplicate` into `primary`: merge the duplicate's payload (minus
+    /// `_collection`, `_id` and the timestamps) into the primary with `strategy`, move its outgoing and incoming edges onto the
+    /// primary and soft-delete the duplicate (or remove it, if an earlier
+    /// version of it is already in the trash). The primary's `_fused_from`
+    /// array records the duplicate's slug, after any slugs fused into the
+    /// duplicate before, so provenance survives a later [`purge`](Self::purge).
+    ///
+    /// Edges the primary already has (same type and endpoint) are not
+    /// duplicated; edges between the two nodes, and edges to nodes that do not
+    /// exist, are dropped. All writes share one WAL transaction, and only the
+    /// first, the primary's merged payload, can fail: an error (invalid JSON,
+    /// a quota or a unique field) leaves both nodes and their edges as they
+    /// were.
+    ///
+    /// Returns `Ok(false)` without writing anything if either node is missing
+    /// or both slugs are the same.
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, MergeStrategy};
+    ///
+    /// let mut db = CoreDB::new();
+    /// db.put("p/1", r#"{"name":"Ann"}"#).unwrap();
+    /// db.put("p/2", r#"{"phone":"123"}"#).unwrap();
+    /// db.put("org", "{}").unwrap();
+    /// db.link("p/2", "org", "works_at", 1.0);
+    /// db.fuse("p/1", "p/2", MergeStrategy::Deep).unwrap();
+    /// assert!(db.is_trashed("p/2"));
+    /// assert_eq!(db.one("p/1").forward("works_at").count(), 1);
+    /// ```
+    pub fn fuse(
+        &mut self,
+        primary: &str,
+        duplicate: &str,
+        strategy: MergeStrategy,
+    ) -> Result<bool, serde_json::Error> {
+        if primary == duplicate || !self.contains(primary) {
+            return Ok(false);
+        }
+        let Some(dup_payload) = self.get(duplicate) else {
+            return Ok(false);
+        };
+        let (primary_h, dup_h) = (sk_hash(primary), sk_hash(duplicate));
+        let has_edge = |edges: Option<&[Edge]>, other: u64, type_h: u64| {
+            edges.unwrap_or(&[]).iter().any(|e| e.other == other && e.edge_type == type_h)
+        };
+
+        // (from, to, edge_type, strength, meta) — resolved before any write.
+        let mut moves: Vec<(String, String, String, f32, Option<Value>)> = Vec::new();
+        for e in self.edges_from(duplicate) {
+            let (Some(to), Some(et)) = (e.to_slug, e.edge_type) else { continue };
+            let to_h = sk_hash(&to);
+            if to_h == primary_h || to_h == dup_h {
+                continue;
+            }
+            if !has_edge(self.fwd_edges(primary_h), to_h, e.edge_type_hash) {
+                moves.push((primary.to_string(), to, et, e.strength, e.meta));
+            }
+        }
+        for e in self.edges_to(duplicate) {
+            let (Some(from), Some(et)) = (e.from_slug, e.edge_type) else { continue };
+            let from_h = sk_hash(&from);
+            if from_h == primary_h || from_h == dup_h {
+                continue;
+            }
+            if !has_edge(self.rev_edges(primary_h), from_h, e.edge_type_hash) {
+                moves.push((from, primary.to_string(), et, e.strength, e.meta));
+            }
+        }
+        let unlinks: Vec<(String, String, String)> = self
+            .edges_from(duplicate)
+            .into_iter()
+            .chain(self.edges_to(duplicate))
+            .filter_map(|e| Some((e.from_slug?, e.to_slug?, e.edge_type?)))
+            .collect();
+
+        // The primary keeps its own identity and timestamps.
+        let mut incoming: Value = serde_json::from_str(&dup_payload)?;
+        let mut sources = Vec::new();
+        if let Value::Object(map) = &mut incoming {
+            for k in ["_collection", "_id", "_created_unix", "_updated_unix"] {
+                map.remove(k);
+            }
+            if let Some(Value::Array(earlier)) = map.remove("_fused_from") {
+                sources = earlier;
+            }
+        }
+        sources.push(Value::String(duplicate.to_string()));
+        let mut merged = match self.get_payload(primary_h) {
+            Some(existing) => merge_payloads(existing, incoming, &strategy),
+            None => incoming,
+        };
+        if let Value::Object(map) = &mut merged {
+            let fused_from = map.entry("_fused_from").or_insert_with(|| Value::Array(Vec::new()));
+            match fused_from {
+                Value::Array(list) => list.extend(sources),
+                other => *other = Value::Array(sources),
+            }
+        }
+
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        self.wal_write(WalEntry::TxnBegin);
+        // A failed put changes nothing, so the transaction is left empty.
+        let result = self.put(primary, &merged.to_string());
+        if result.is_ok() {
+            for (from, to, et) in &unlinks {
+                self.unlink(from, to, et);
+            }
+            for (from, to, et, strength, meta) in &moves {
+                match meta {
+                    Some(m) => self
+                        .link_meta(from, to, et, *strength, &m.to_string())
+                        .expect("edge meta is re-serialised from a Value"),
+                    None => self.link(from, to, et, *strength),
+                }
+            }
+            // The trash keeps one version per slug; an older one stays.
+            if !self.remove_soft(duplicate) {
+                self.remove(duplicate);
+            }
+        }
+        self.wal_write(WalEntry::TxnEnd);
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        result.map(|_| true)
+    }
+
+    /// Bulk insert. Stops and returns the first error encountered.
+    pub fn put_many<'a>(
+        &mut self,
+        items: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Vec<u64>, serde_json::Error> {
+        self.defer_wal_sync = true;
+        let result: Result<Vec<u64>, _> = items
+            .into_iter()
+            .map(|(slug, json)| self.put(slug, json))
+            .collect();
+        self.defer_wal_sync = false;
+        self.wal_flush();
+        result
+    }
+
+    /// Bulk edge insert: one WAL sync for the whole batch and one creation
+    /// time shared by every edge. Edges with `props_json` are stored with
+    /// that metadata, as by [`link_meta`](Self::link_meta).
+    ///
+    /// Stops at the first edge with invalid metadata JSON or metadata the
+    /// store cannot hold ([`CapacityExceeded`]); edges before it are kept.
+    /// Returns the number of edges created.
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, EdgeInsert};
+    /// let mut db = CoreDB::new();
+    /// let edges = (0..3).map(|i| EdgeInsert {
+    ///     from: "hub".into(),
+    ///     to: format!("leaf/{i}"),
+    ///     edge_type: "has".into(),
+    ///     strength: 1.0,
+    ///     props_json: (i == 0).then(|| r#"{"primary":true}"#.to_string()),
+    /// });
+    /// assert_eq!(db.link_many(edges).unwrap(), 3);
+    /// assert_eq!(db.edges_from("hub").len(), 3);
+    /// ```
+    pub fn link_many(
+        &mut self,
+        edges: impl IntoIterator<Item = EdgeInsert>,
+    ) -> Result<usize, serde_json::Error> {
+        let now = self.now_millis();
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        let mut count = 0;
+        let result = edges.into_iter().try_for_each(|edge| {
+            let EdgeInsert { from, to, edge_type, strength, props_json } = edge;
+            if let Some(meta) = &props_json {
+                serde_json::from_str::<Value>(meta)?;
+                self.check_edge_meta_capacity(meta)?;
+            }
+            let created = self.replace_existing_edge(&from, &to, &edge_type).unwrap_or(now);
+            match props_json {
+                Some(meta) => {
+                    self.wal_write(WalEntry::LinkMeta {
+                        from: from.clone(),
+                        to: to.clone(),
+                        edge_type: edge_type.clone(),
+                        strength,
+                        meta: meta.clone(),
+                        created_unix: Some(created),
+                    });
+                    self.link_meta_raw(&from, &to, &edge_type, strength, created, &meta)?;
+                }
+                None => {
+                    self.wal_write(WalEntry::Link {
+                        from: from.clone(),
+                        to: to.clone(),
+                        edge_type: edge_type.clone(),
+                        strength,
+                        created_unix: Some(created),
+                    });
+                    self.link_raw(&from, &to, &edge_type, strength, created);
+                }
+            }
+            count += 1;
+            Ok(())
+        });
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        result.map(|_| count)
+    }
+
+    /// Remove a node by slug. Also removes its collection membership and edges.
+    pub fn remove(&mut self, slug: &str) {
+        self.wal_write(WalEntry::Remove {
+            slug: slug.to_string(),
+        });
+        self.remove_raw(slug);
+    }
+
+    /// Soft-delete a node: it disappears from reads and queries like
+    /// [`remove`](Self::remove), but its payload, edges and vectors are kept
+    /// in the trash until [`restore`](Self::restore) or [`purge`](Self::purge).
+    ///
+    /// Returns `false` if no live node has this slug, or if an earlier
+    /// version of it is still in the trash, which this would overwrite;
+    /// [`purge`](Self::purge) that one first.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("alice", r#"{"name":"Alice"}"#).unwrap();
+    /// db.remove_soft("alice");
+    /// assert!(!db.contains("alice"));
+    /// db.restore("alice");
+    /// assert!(db.contains("alice"));
+    /// ```
+    pub fn remove_soft(&mut self, slug: &str) -> bool {
+        if !self.contains(slug) || self.is_trashed(slug) {
+            return false;
+        }
+        self.wal_write(WalEntry::SoftRemove { slug: slug.to_string() });
+        self.remove_soft_raw(slug)
+    }
+
+    /// Bring a soft-deleted node back, re-attaching its edges and vectors.
+    /// Edges to nodes that are still in the trash come back when those
+    /// nodes are restored; edges to purged nodes are dropped.
+    ///
+    /// Returns `false` if the slug is not in the trash, or if a live node
+    /// has been written under the same slug since it was soft-deleted.
+    pub fn restore(&mut self, slug: &str) -> bool {
+        let hash = sk_hash(slug);
+        if self.nodes.contains_key(&hash) || !self.trash.contains_key(&hash) {
+            return false;
+        }
+        self.wal_write(WalEntry::Restore { slug: slug.to_string() });
+        self.restore_raw(slug)
+    }
+
+    /// Permanently delete a soft-deleted node. Returns `false` if the slug
+    /// is not in the trash.
+    pub fn purge(&mut self, slug: &str) -> bool {
+        if !self.trash.contains_key(&sk_hash(slug)) {
+            return false;
+        }
+        self.wal_write(WalEntry::Purge { slug: slug.to_string() });
+        self.trash.remove(&sk_hash(slug)).is_some()
+    }
+
+    /// Whether `slug` is currently soft-deleted.
+    pub fn is_trashed(&self, slug: &str) -> bool {
+        self.trash.contains_key(&sk_hash(slug))
+    }
+
+    /// The `_collection` of the trashed node behind `slug_hash`, if any.
+    pub(crate) fn trashed_collection(&self, slug_hash: u64) -> Option<String> {
+        let node = self.trash.get(&slug_hash)?;
+        let payload: Value = serde_json::from_str(&node.payload).ok()?;
+        Some(payload.get("_collection")?.as_str()?.to_string())
+    }
+
+    /// Remove every node whose [`EXPIRES_FIELD`] is at or before now, as
+    /// [`remove`](Self::remove) would: WAL tombstone, edges, vectors and
+    /// all indexes. Returns the number of nodes evicted.
+    ///
+    /// Runs on demand and reads every payload; call it periodically (e.g.
+    /// from a timer in the embedding application) to keep a TTL'd buffer
+    /// bounded.
+    pub fn evict_expired(&mut self) -> usize {
+        let now = self.now_millis();
+        let expired: Vec<String> = self.nodes.values()
+            .filter(|n| {
+                self.payload_store.get(n.payload_offset, n.payload_len)
+                    .and_then(|p| p.get(EXPIRES_FIELD).and_then(Value::as_f64))
+                    .is_some_and(|at| at <= now as f64)
+            })
+            .map(|n| n.slug.clone())
+            .collect();
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        for slug in &expired {
+            self.remove(slug);
+        }
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        expired.len()
+    }
+
+    /// Create a directed edge: `from` → `to` with a type label and strength.
     /// Nodes do not need to exist before linking.
+    /// The edge is stamped with the current time (see [`Set::forward_since`]).
     pub fn link(&mut self, from: &str, to: &str, edge_type: &str, strength: f32) {
+        let now = self.now_millis();
+        let created = self.replace_existing_edge(from, to, edge_type).unwrap_or(now);
         self.wal_write(WalEntry::Link {
             from: from.to_string(),
             to: to.to_string(),
             edge_type: edge_type.to_string(),
             strength,
+            created_unix: Some(created),
         });
-        self.link_raw(from, to, edge_type, strength);
+        self.link_raw(from, to, edge_type, strength, created);
     }
 
-    /// Like `link` but attaches a JSON metadata object to the edge.
+    /// Like `link` but attaches a JSON metadata object to the edge. Fails on
+    /// invalid JSON, or with a [`CapacityExceeded`] when the edge metadata
+    /// store is full.
     pub fn link_meta(
         &mut self,
         from: &str,
@@ -1854,14 +4044,18 @@ impl CoreDB {
         meta_json: &str,
     ) -> Result<(), serde_json::Error> {
         serde_json::from_str::<Value>(meta_json)?;
+        self.check_edge_meta_capacity(meta_json)?;
+        let now = self.now_millis();
+        let created = self.replace_existing_edge(from, to, edge_type).unwrap_or(now);
         self.wal_write(WalEntry::LinkMeta {
             from: from.to_string(),
             to: to.to_string(),
             edge_type: edge_type.to_string(),
             strength,
             meta: meta_json.to_string(),
+            created_unix: Some(created),
         });
-        self.link_meta_raw(from, to, edge_type, strength, meta_json)?;
+        self.link_meta_raw(from, to, edge_type, strength, created, meta_json)?;
         Ok(())
     }
 
@@ -1875,6 +4069,798 @@ impl CoreDB {
         self.unlink_raw(from, to, edge_type);
     }
 
+    /// Whether linking an existing (from, to, type) adds a parallel edge
+    /// (`true`) or replaces the existing one (`false`, the default; also
+    /// settable via [`Config::allow_parallel_edges`]).
+    ///
+    /// A replaced edge takes the new strength and metadata but keeps its
+    /// original creation time, so repeating a `link` call is idempotent.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.link("a", "b", "knows", 0.5);
+    /// db.link("a", "b", "knows", 0.9);
+    /// assert_eq!(db.edges_from("a").len(), 1);
+    ///
+    /// db.set_allow_parallel_edges(true);
+    /// db.link("a", "b", "knows", 0.1);
+    /// assert_eq!(db.edges_from("a").len(), 2);
+    /// ```
+    pub fn set_allow_parallel_edges(&mut self, allow: bool) {
+        self.allow_parallel_edges = allow;
+    }
+
+    /// See [`set_allow_parallel_edges`](Self::set_allow_parallel_edges).
+    pub fn allow_parallel_edges(&self) -> bool {
+        self.allow_parallel_edges
+    }
+
+    /// Write the whole database as newline-delimited mutation documents that
+    /// [`CoreDB::mutate_ndjson`] can load into another database.
+    ///
+    /// Records come out in a fixed order: table schemas, nodes, vectors,
+    /// edges, collection filters, then one `create_index` per declared index
+    /// so the target builds each index once over the loaded data. Within each
+    /// group records are sorted, so exporting the same data twice yields the
+    /// same bytes. A closing [`ExportManifest`] line carries record counts and
+    /// checksums for [`CoreDB::import_ndjson`] to verify.
+    ///
+    /// The export is a point-in-time view: it borrows `&self`, so writes wait
+    /// until it returns while readers sharing the database keep running.
+    /// Trashed nodes are not exported.
+    ///
+    /// Returns the number of records written, not counting the manifest.
+    ///
+    /// ```
+    /// use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("alice", r#"{"name":"Alice"}"#).unwrap();
+    /// db.put("bob", r#"{"name":"Bob"}"#).unwrap();
+    /// db.link("alice", "bob", "follows", 1.0);
+    ///
+    /// let mut out = Vec::new();
+    /// assert_eq!(db.export_ndjson(&mut out).unwrap(), 3);
+    ///
+    /// let mut copy = CoreDB::new();
+    /// copy.import_ndjson(out.as_slice()).unwrap();
+    /// assert_eq!(copy.one("alice").forward("follows").count(), 1);
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if writing to `writer` fails.
+    pub fn export_ndjson(&self, mut writer: impl io::Write) -> io::Result<usize> {
+        use crate::vector::VectorAccess;
+
+        let mut digest = ManifestBuilder::default();
+        let mut emit = |entry: WalEntry| -> io::Result<()> {
+            let line = serde_json::to_string(&entry)?;
+            digest.add(&line, &entry);
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\n")
+        };
+
+        // Schemas go out without their index hints; indexes are declared last.
+        let mut schemas: Vec<&sql::TableSchema> = self.schemas.values().collect();
+        schemas.sort_by(|a, b| a.collection.cmp(&b.collection));
+        for schema in &schemas {
+            let bare = sql::TableSchema {
+                collection: schema.collection.clone(),
+                fields: schema.fields.clone(),
+                // Unique fields are a constraint, not an index: keep them.
+                indexes: sql::IndexHint {
+                    unique: schema.indexes.unique.clone(),
+                    ..sql::IndexHint::default()
+                },
+            };
+            emit(WalEntry::CreateTable {
+                collection: schema.collection.clone(),
+                schema_json: serde_json::to_string(&bare)?,
+            })?;
+        }
+
+        let mut nodes: Vec<&NodeData> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.slug.cmp(&b.slug));
+        for node in &nodes {
+            if let Some(payload) = self.payload_store.get(node.payload_offset, node.payload_len) {
+                emit(WalEntry::Put {
+                    slug: node.slug.clone(),
+                    payload: serde_json::to_string(&payload)?,
+                })?;
+            }
+        }
+
+        let mut fields: Vec<&String> = self.vectors.keys().collect();
+        fields.sort();
+        for field in fields {
+            let store = &self.vectors[field];
+            for node in &nodes {
+                if let Some(data) = store.get(sk_hash(&node.slug)) {
+                    emit(WalEntry::PutVector {
+                        slug: node.slug.clone(),
+                        field: field.clone(),
+                        data: data.to_vec(),
+                    })?;
+                }
+            }
+        }
+
+        let mut edges: Vec<WalEntry> = Vec::new();
+        for (&from_h, edge_list) in self.edges.iter_fwd() {
+            let Some(from) = self.nodes.get(&from_h) else { continue };
+            for e in edge_list {
+                let Some(to) = self.nodes.get(&e.other) else { continue };
+                edges.push(self.edge_record(&from.slug, &to.slug, e));
+            }
+        }
+        let edge_key = |entry: &WalEntry| match entry {
+            WalEntry::Link { from, to, edge_type, created_unix, .. }
+            | WalEntry::LinkMeta { from, to, edge_type, created_unix, .. } => {
+                (from.clone(), to.clone(), edge_type.clone(), *created_unix)
+            }
+            _ => unreachable!(),
+        };
+        edges.sort_by_key(edge_key);
+        for entry in edges {
+            emit(entry)?;
+        }
+
+        let mut filters: Vec<&(String, String, Vec<Step>)> = self.collection_filters.values().collect();
+        filters.sort_by(|a, b| a.0.cmp(&b.0));
+        for (collection, filter, _) in filters {
+            emit(WalEntry::SetCollectionFilter {
+                collection: collection.clone(),
+                filter: Some(filter.clone()),
+            })?;
+        }
+        for (collection, field, aggregate) in &self.edge_aggregates {
+            emit(WalEntry::SetEdgeAggregate {
+                collection: collection.clone(),
+                field: field.clone(),
+                aggregate_json: Some(serde_json::to_string(aggregate)?),
+            })?;
+        }
+        for (collection, profile, exclude) in &self.projection_profiles {
+            emit(WalEntry::SetProjectionProfile {
+                collection: collection.clone(),
+                profile: profile.clone(),
+                exclude: Some(exclude.clone()),
+            })?;
+        }
+
+        for schema in &schemas {
+            let hints = &schema.indexes;
+            let default_hash = sql::IndexHint::default().hash;
+            let single = [
+                ("btree", &hints.range),
+                ("hash", &hints.hash),
+                ("gin", &hints.fulltext),
+                ("bm25", &hints.bm25),
+                ("spatial", &hints.spatial),
+                ("hnsw", &hints.vector),
+                ("simhash", &hints.simhash),
+            ];
+            for (method, list) in single {
+                for field in list {
+                    if method == "hash" && default_hash.contains(field) {
+                        continue;
+                    }
+                    emit(WalEntry::CreateIndex {
+                        collection: schema.collection.clone(),
+                        method: method.to_string(),
+                        fields: vec![field.clone()],
+                    })?;
+                }
+            }
+            for fields in &hints.search {
+                emit(WalEntry::CreateIndex {
+                    collection: schema.collection.clone(),
+                    method: "search".to_string(),
+                    fields: fields.clone(),
+                })?;
+            }
+        }
+        // HNSW graphs built directly rather than declared by a schema.
+        let mut standalone: Vec<(&String, &(usize, usize))> = self.hnsw_params.iter()
+            .filter(|(f, _)| self.hnsw_indexes.contains_key(*f))
+            .filter(|(f, _)| !schemas.iter().any(|s| s.indexes.vector.contains(f)))
+            .collect();
+        standalone.sort();
+        for (field, &(m, ef_construction)) in standalone {
+            emit(WalEntry::BuildHnsw { field: field.clone(), m, ef_construction })?;
+        }
+
+        let manifest = digest.finish();
+        let written = manifest.records;
+        serde_json::to_writer(&mut writer, &WalEntry::Manifest(manifest))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Write the graph as GraphML for Gephi, yEd or NetworkX: one `<node>`
+    /// per node, keyed by slug, with its collection and top-level payload
+    /// fields as attributes, and one directed `<edge>` per edge with its
+    /// type as `label`, its strength as `weight`, its creation time and its
+    /// metadata fields. Attribute types are inferred across all values of a
+    /// field; arrays and objects are written as JSON text. Vectors are not
+    /// exported.
+    ///
+    /// Returns the number of nodes plus edges written.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("people/ann", r#"{"_collection":"people","age":41}"#).unwrap();
+    /// db.put("people/bo", r#"{"_collection":"people","age":37}"#).unwrap();
+    /// db.link("people/ann", "people/bo", "knows", 0.5);
+    /// let mut out = Vec::new();
+    /// assert_eq!(db.export_graphml(&mut out).unwrap(), 3);
+    /// let xml = String::from_utf8(out).unwrap();
+    /// assert!(xml.contains(r#"attr.name="age" attr.type="long""#));
+    /// assert!(xml.contains(r#"<edge id="e0" source="people/ann" target="people/bo">"#));
+    /// ```
+    pub fn export_graphml(&self, writer: impl io::Write) -> io::Result<usize> {
+        interop::write_graphml(self, writer)
+    }
+
+    /// Write the graph as openCypher for Neo4j or Memgraph: one `CREATE`
+    /// per node, labelled by collection (`Node` without one) and carrying
+    /// its slug as `_slug` plus its top-level payload fields, then one
+    /// `MATCH … CREATE` per edge, typed by edge type, with `weight`,
+    /// `created_unix` and the metadata fields as properties. Objects and
+    /// mixed arrays become JSON strings, since Neo4j properties cannot hold
+    /// them. Create an index on `_slug` for each label before loading a
+    /// large export.
+    ///
+    /// Returns the number of nodes plus edges written.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("people/ann", r#"{"_collection":"people","name":"Ann"}"#).unwrap();
+    /// db.put("people/bo", r#"{"_collection":"people","name":"Bo"}"#).unwrap();
+    /// db.link("people/ann", "people/bo", "knows", 1.0);
+    /// let mut out = Vec::new();
+    /// db.export_cypher(&mut out).unwrap();
+    /// let cypher = String::from_utf8(out).unwrap();
+    /// assert!(cypher.starts_with("CREATE (:people {_slug: 'people/ann', "));
+    /// assert!(cypher.contains(", name: 'Ann'});"));
+    /// assert!(cypher.contains("CREATE (a)-[:knows {weight: 1.0, "));
+    /// ```
+    pub fn export_cypher(&self, writer: impl io::Write) -> io::Result<usize> {
+        interop::write_cypher(self, writer)
+    }
+
+    /// The `link` / `link_meta` record that recreates edge `e` from → to.
+    fn edge_record(&self, from: &str, to: &str, e: &Edge) -> WalEntry {
+        let edge_type = self
+            .edges
+            .type_name(e.edge_type)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("{:016x}", e.edge_type));
+        let (from, to) = (from.to_string(), to.to_string());
+        let created_unix = Some(e.created_unix);
+        match self.edges.edge_meta(e) {
+            None => WalEntry::Link { from, to, edge_type, strength: e.strength, created_unix },
+            Some(meta) => WalEntry::LinkMeta {
+                from, to, edge_type, strength: e.strength, meta: meta.to_string(), created_unix,
+            },
+        }
+    }
+
+    /// Load a stream written by [`CoreDB::export_ndjson`], checking it
+    /// against its [`ExportManifest`] first.
+    ///
+    /// The whole stream is read and verified before anything is applied: a
+    /// missing manifest (truncated file), a record count that does not add
+    /// up, or a checksum mismatch fails with `InvalidData` naming the first
+    /// problem, and the database is left untouched. A verified stream is
+    /// then applied as by [`CoreDB::mutate_ndjson`].
+    ///
+    /// # Errors
+    /// Fails if reading fails or the stream does not match its manifest.
+    pub fn import_ndjson(&mut self, reader: impl io::BufRead) -> io::Result<MutationSummary> {
+        self.import_ndjson_in(reader, &Job::detached(JobKind::Restore))
+    }
+
+    /// [`import_ndjson`](Self::import_ndjson) counting verified records,
+    /// then applied ones, as progress.
+    fn import_ndjson_in(&mut self, reader: impl io::BufRead, job: &Job) -> io::Result<MutationSummary> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut lines: Vec<String> = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                lines.push(line);
+            }
+        }
+        let expected = match lines.pop().map(|l| serde_json::from_str::<WalEntry>(&l)) {
+            Some(Ok(WalEntry::Manifest(m))) => m,
+            _ => return Err(invalid("export has no manifest; the file is truncated or not an export".into())),
+        };
+
+        let mut digest = ManifestBuilder::default();
+        let total = 2 * lines.len() as u64;
+        for (i, line) in lines.iter().enumerate() {
+            job.checkpoint(i as u64, total)?;
+            let entry = serde_json::from_str::<WalEntry>(line)
+                .map_err(|e| invalid(format!("record {}: {e}", i + 1)))?;
+            digest.add(line, &entry);
+        }
+        let found = digest.finish();
+        if found.records != expected.records {
+            return Err(invalid(format!(
+                "export has {} records, manifest expects {}", found.records, expected.records
+            )));
+        }
+        for (name, want) in &expected.collections {
+            match found.collections.get(name) {
+                Some(got) if got == want => {}
+                Some(got) => return Err(invalid(format!(
+                    "collection '{name}': {} nodes with checksum {:08x}, manifest expects {} with {:08x}",
+                    got.nodes, got.checksum, want.nodes, want.checksum
+                ))),
+                None => return Err(invalid(format!("collection '{name}' is missing from the export"))),
+            }
+        }
+        if found != expected {
+            return Err(invalid(format!(
+                "export checksum {:08x} does not match manifest {:08x}", found.checksum, expected.checksum
+            )));
+        }
+
+        job.checkpoint(total / 2, total)?;
+        let summary = self.mutate_ndjson(io::Cursor::new(lines.join("\n")))?;
+        job.report(total, total);
+        Ok(summary)
+    }
+
+    /// Load nodes from CSV with a header row, one node per row, as
+    /// described by `mapping`. Rows go in through [`put_many`](Self::put_many)
+    /// in batches of [`CsvMapping::chunk_size`]. Returns the number of
+    /// nodes written.
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, CsvMapping, CsvType};
+    /// let csv = "id,name,pop,lat,lon\nmel,Melbourne,5000000,-37.81,144.96\nsyd,\"Sydney, NSW\",5300000,-33.87,151.21\n";
+    /// let mapping = CsvMapping::new("cities", "id").point("lat", "lon").column("pop", CsvType::Int);
+    /// let mut db = CoreDB::new();
+    /// assert_eq!(db.import_csv(csv.as_bytes(), &mapping).unwrap(), 2);
+    /// let syd: serde_json::Value = serde_json::from_str(&db.get("cities/syd").unwrap()).unwrap();
+    /// assert_eq!(syd["name"], "Sydney, NSW");
+    /// assert_eq!(syd["pop"], 5300000);
+    /// assert_eq!(syd["geometry"]["coordinates"][0], 151.21);
+    /// ```
+    ///
+    /// # Errors
+    /// `InvalidInput` if a mapped column is not in the header; `InvalidData`
+    /// naming the line for a malformed row or a cell that does not parse as
+    /// its type. Batches before the failing one are kept.
+    pub fn import_csv(&mut self, reader: impl io::BufRead, mapping: &CsvMapping) -> io::Result<usize> {
+        csv::import_nodes(self, reader, mapping)
+    }
+
+    /// Load edges from CSV with a header row (by default `from`, `to`,
+    /// `type`, `weight`), in [`link_many`](Self::link_many) batches.
+    /// Returns the number of edges written. Errors as for
+    /// [`import_csv`](Self::import_csv).
+    ///
+    /// ```
+    /// use sekejap::{CoreDB, CsvEdgeMapping};
+    /// let csv = "from,to,type,weight\nmel,syd,flight,0.8\nsyd,mel,flight,\n";
+    /// let mapping = CsvEdgeMapping {
+    ///     from_collection: Some("cities".into()),
+    ///     to_collection: Some("cities".into()),
+    ///     ..Default::default()
+    /// };
+    /// let mut db = CoreDB::new();
+    /// assert_eq!(db.import_csv_edges(csv.as_bytes(), &mapping).unwrap(), 2);
+    /// assert_eq!(db.edges_from("cities/mel").len(), 1);
+    /// ```
+    pub fn import_csv_edges(&mut self, reader: impl io::BufRead, mapping: &CsvEdgeMapping) -> io::Result<usize> {
+        csv::import_edges(self, reader, mapping)
+    }
+
+    /// Start shipping this database to a follower: write a full
+    /// [`export_ndjson`](Self::export_ndjson) of the current state to
+    /// `follower`, then every later write as one
+    /// [`mutate_ndjson`](Self::mutate_ndjson) line, in WAL order. `follower`
+    /// is typically a `TcpStream` or a file on a backup volume; the other
+    /// end applies the stream with [`catch_up`](Self::catch_up).
+    ///
+    /// Replication is asynchronous: each write is queued for `follower` once
+    /// it is in this database's WAL, and a background thread writes the
+    /// queue out, flushing whenever it drains. A slow follower never blocks
+    /// a write. If writing to `follower` fails, or it falls more than 1024
+    /// writes behind, shipping stops and writes go ahead;
+    /// [`stop_replication`](Self::stop_replication) reports why, and the
+    /// follower must catch up afresh. Puts carry the stored payload, so the
+    /// follower keeps this database's `_created_unix` / `_updated_unix`.
+    /// Calling this again replaces the previous follower.
+    ///
+    /// Returns the number of bootstrap records written.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let log = dir.path().join("ship.ndjson");
+    /// let mut leader = CoreDB::new();
+    /// leader.put("a", r#"{"v":1}"#).unwrap();
+    /// leader.replicate_to(std::fs::File::create(&log).unwrap()).unwrap();
+    /// leader.put("b", r#"{"v":2}"#).unwrap();
+    /// leader.stop_replication().unwrap();
+    ///
+    /// let mut follower = CoreDB::new();
+    /// let reader = std::io::BufReader::new(std::fs::File::open(&log).unwrap());
+    /// follower.catch_up(reader).unwrap();
+    /// assert!(follower.contains("a") && follower.contains("b"));
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if writing the bootstrap export fails; replication is then
+    /// not started.
+    pub fn replicate_to(&mut self, follower: impl io::Write + Send + 'static) -> io::Result<usize> {
+        let mut sink: Box<dyn io::Write + Send> = Box::new(follower);
+        self.replication = None;
+        self.replication_error = None;
+        let written = self.export_ndjson(&mut sink)?;
+        self.replication = Some(Shipper::spawn(sink));
+        Ok(written)
+    }
+
+    /// Whether writes are being shipped to a follower.
+    pub fn is_replicating(&self) -> bool {
+        self.replication.is_some()
+    }
+
+    /// Drop the follower set by [`replicate_to`](Self::replicate_to), once
+    /// the writes queued for it have been written and flushed.
+    ///
+    /// # Errors
+    /// Returns the error that stopped shipping early, if one did, or the
+    /// error writing the last queued writes.
+    pub fn stop_replication(&mut self) -> io::Result<()> {
+        let finished = self.replication.take().map_or(Ok(()), Shipper::finish);
+        if let Some(e) = self.replication_error.take() {
+            return Err(e);
+        }
+        finished
+    }
+
+    /// Follower side of [`replicate_to`](Self::replicate_to): apply the
+    /// leader's stream (bootstrap export, then live writes) until it ends.
+    /// It blocks while the stream is open, so a socket follower keeps
+    /// applying writes as they arrive. The follower's own WAL is synced
+    /// every 1024 records and when the stream ends.
+    ///
+    /// Puts keep the `_created_unix` / `_updated_unix` the leader stamped.
+    ///
+    /// The bootstrap cannot undo deletes, so the follower must start empty:
+    /// after a broken connection, catch up into a fresh database.
+    ///
+    /// # Errors
+    /// `InvalidInput` if this database already holds nodes; otherwise only
+    /// read errors, as for [`mutate_ndjson`](Self::mutate_ndjson).
+    pub fn catch_up(&mut self, leader: impl io::BufRead) -> io::Result<MutationSummary> {
+        if !self.nodes.is_empty() || !self.trash.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "catch_up() needs an empty follower database"));
+        }
+        self.keep_timestamps = true;
+        let summary = self.mutate_ndjson(leader);
+        self.keep_timestamps = false;
+        summary
+    }
+
+    /// Apply a stream of newline-delimited mutation documents.
+    ///
+    /// Each line uses the same JSON shape as a WAL record, so a change feed
+    /// exported from one database can be replayed into another:
+    /// ```text
+    /// {"op":"put","slug":"alice","payload":"{\"name\":\"Alice\"}"}
+    /// {"op":"link","from":"alice","to":"bob","edge_type":"follows","strength":1.0}
+    /// {"op":"remove","slug":"bob"}
+    /// ```
+    /// Every record is routed through the regular write path (WAL, indexes);
+    /// edge records that carry a `created_unix` keep that creation time.
+    /// WAL syncs are batched every 1024 lines. Blank lines
+    /// are skipped; malformed or failing lines are counted and reported in the
+    /// summary without stopping the stream.
+    ///
+    /// Lines between `{"op":"txn_begin"}` and `{"op":"txn_end"}` form an
+    /// atomic batch applied through [`CoreDB::begin`]: if any line in the
+    /// batch is malformed or is not a node/edge/vector write, none of it is
+    /// applied and every line of the batch counts as failed. A batch left
+    /// open at the end of the stream is discarded the same way.
+    ///
+    /// # Errors
+    /// Only fails if reading from `reader` fails; lines applied before the
+    /// error are synced and kept.
+    pub fn mutate_ndjson(&mut self, reader: impl io::BufRead) -> io::Result<MutationSummary> {
+        self.mutate_ndjson_with(None, reader)
+    }
+
+    /// [`mutate_ndjson`](Self::mutate_ndjson) on behalf of a caller: each
+    /// line is checked by `auth` with the access it needs and the
+    /// collections it touches (see [`Authorizer`]), and a rejected line
+    /// fails like a malformed one. A rejected line inside an atomic batch
+    /// rejects the batch.
+    pub fn mutate_ndjson_as(&mut self, auth: &dyn Authorizer, reader: impl io::BufRead) -> io::Result<MutationSummary> {
+        self.mutate_ndjson_with(Some(auth), reader)
+    }
+
+    fn mutate_ndjson_with(&mut self, auth: Option<&dyn Authorizer>, reader: impl io::BufRead) -> io::Result<MutationSummary> {
+        let mut summary = MutationSummary::default();
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        let mut result = Ok(());
+        /// Open atomic batch: line of `txn_begin`, parsed entries, first error.
+        struct PendingBatch {
+            start: usize,
+            entries: Vec<WalEntry>,
+            err: Option<(usize, String)>,
+        }
+        let mut batch: Option<PendingBatch> = None;
+        for (i, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => { result = Err(e); break; }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = serde_json::from_str::<WalEntry>(&line).map_err(|e| e.to_string())
+                .and_then(|entry| self.authorize_entry(auth, entry));
+            let outcome = match (parsed, &mut batch) {
+                (Ok(WalEntry::TxnBegin), Some(_)) => Err("nested txn_begin".to_string()),
+                (Ok(WalEntry::TxnBegin), None) => {
+                    batch = Some(PendingBatch { start: i + 1, entries: Vec::new(), err: None });
+                    continue;
+                }
+                (Ok(WalEntry::TxnEnd), Some(_)) => {
+                    let PendingBatch { start, entries, err } = batch.take().unwrap();
+                    let n = entries.len();
+                    let applied = match err {
+                        Some(e) => Err(e),
+                        None => self.apply_atomic_batch(entries).map_err(|e| (start, e)),
+                    };
+                    match applied {
+                        Ok(()) => summary.applied += n,
+                        Err((line, e)) => {
+                            summary.failed += n;
+                            if summary.errors.len() < MutationSummary::MAX_ERRORS {
+                                summary.errors.push((line, format!("atomic batch rejected: {e}")));
+                            }
+                        }
+                    }
+                    continue;
+                }
+                (Ok(WalEntry::TxnEnd), None) => Err("txn_end without txn_begin".to_string()),
+                (parsed, Some(PendingBatch { entries, err, .. })) => {
+                    match parsed {
+                        Ok(entry) => entries.push(entry),
+                        Err(e) => {
+                            // Keep a placeholder so the failed line is counted.
+                            entries.push(WalEntry::Unknown);
+                            err.get_or_insert((i + 1, e));
+                        }
+                    }
+                    continue;
+                }
+                (parsed, None) => parsed.and_then(|entry| self.apply_mutation_entry(entry)),
+            };
+            match outcome {
+                Ok(()) => summary.applied += 1,
+                Err(msg) => {
+                    summary.failed += 1;
+                    if summary.errors.len() < MutationSummary::MAX_ERRORS {
+                        summary.errors.push((i + 1, msg));
+                    }
+                }
+            }
+            if (i + 1) % NDJSON_COMMIT_BATCH == 0 {
+                self.wal_flush();
+            }
+        }
+        if let Some(PendingBatch { start, entries, .. }) = batch {
+            summary.failed += entries.len();
+            if summary.errors.len() < MutationSummary::MAX_ERRORS {
+                summary.errors.push((start, "atomic batch not closed by txn_end".into()));
+            }
+        }
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        result.map(|_| summary)
+    }
+
+    /// Apply one mutation document, or a batch of them, from a JSON string.
+    ///
+    /// Documents use the same shape as [`mutate_ndjson`](Self::mutate_ndjson)
+    /// lines. A batch is `{"mutations":[...]}`, applied in order with a single
+    /// WAL sync; add `"atomic":true` to apply it through [`CoreDB::begin`] so
+    /// that one bad item rejects the whole batch. Batches let bindings load
+    /// many records per call instead of paying the boundary cost per record.
+    ///
+    /// Returns one result per document, in order.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// let results = db.mutate_json(r#"{"mutations":[
+    ///     {"op":"put","slug":"a","payload":"{}"},
+    ///     {"op":"put","slug":"b","payload":"not json"},
+    ///     {"op":"link","from":"a","to":"c","edge_type":"knows","strength":1.0}
+    /// ]}"#).unwrap();
+    /// assert!(results[0].is_ok() && results[1].is_err() && results[2].is_ok());
+    /// ```
+    ///
+    /// # Errors
+    /// Fails only if `json` is not a mutation document or batch object;
+    /// per-item failures are reported in the returned results.
+    pub fn mutate_json(&mut self, json: &str) -> Result<Vec<Result<(), String>>, serde_json::Error> {
+        self.mutate_json_with(None, json)
+    }
+
+    /// [`mutate_json`](Self::mutate_json) on behalf of a caller: each
+    /// document is checked by `auth` as in
+    /// [`mutate_ndjson_as`](Self::mutate_ndjson_as), and a rejected one
+    /// reports its reason in its result.
+    ///
+    /// ```
+    /// # use sekejap::{Access, CoreDB};
+    /// let mut db = CoreDB::new();
+    /// let users_only = |_: Access, cols: Option<&[String]>| match cols {
+    ///     Some(c) if c.iter().all(|n| n == "users") => Ok(()),
+    ///     _ => Err("token is scoped to users".to_string()),
+    /// };
+    /// let results = db.mutate_json_as(&users_only, r#"{"mutations":[
+    ///     {"op":"put","slug":"u/1","payload":"{\"_collection\":\"users\"}"},
+    ///     {"op":"put","slug":"s/1","payload":"{\"_collection\":\"secrets\"}"}
+    /// ]}"#).unwrap();
+    /// assert!(results[0].is_ok() && results[1].is_err());
+    /// assert!(db.get("s/1").is_none());
+    /// ```
+    pub fn mutate_json_as(&mut self, auth: &dyn Authorizer, json: &str) -> Result<Vec<Result<(), String>>, serde_json::Error> {
+        self.mutate_json_with(Some(auth), json)
+    }
+
+    fn mutate_json_with(&mut self, auth: Option<&dyn Authorizer>, json: &str) -> Result<Vec<Result<(), String>>, serde_json::Error> {
+        #[derive(Deserialize)]
+        struct Batch {
+            mutations: Vec<Value>,
+            #[serde(default)]
+            atomic: bool,
+        }
+        let doc: Value = serde_json::from_str(json)?;
+        let Batch { mutations, atomic } = if doc.get("mutations").is_some() {
+            serde_json::from_value(doc)?
+        } else {
+            Batch { mutations: vec![doc], atomic: false }
+        };
+        let entries: Vec<Result<WalEntry, String>> = mutations.into_iter()
+            .map(|m| serde_json::from_value(m).map_err(|e| e.to_string()))
+            .map(|entry| entry.and_then(|entry| self.authorize_entry(auth, entry)))
+            .collect();
+
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        let results = if atomic {
+            let n = entries.len();
+            let applied = entries.into_iter()
+                .enumerate()
+                .map(|(i, e)| e.map_err(|e| format!("item {i}: {e}")))
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|entries| self.apply_atomic_batch(entries));
+            match applied {
+                Ok(()) => vec![Ok(()); n],
+                Err(e) => vec![Err(format!("atomic batch rejected: {e}")); n],
+            }
+        } else {
+            entries.into_iter()
+                .map(|e| e.and_then(|entry| self.apply_mutation_entry(entry)))
+                .collect()
+        };
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        Ok(results)
+    }
+
+    /// Pass `entry` through when `auth` is absent or allows it.
+    fn authorize_entry(&self, auth: Option<&dyn Authorizer>, entry: WalEntry) -> Result<WalEntry, String> {
+        let Some(auth) = auth else { return Ok(entry) };
+        if let Some((access, collections)) = auth::entry_scope(self, &entry) {
+            auth.authorize(access, collections.as_deref())?;
+        }
+        Ok(entry)
+    }
+
+    /// Apply a `txn_begin` … `txn_end` group from `mutate_ndjson` as one
+    /// [`Transaction`]. Nothing is applied if any entry is not allowed.
+    fn apply_atomic_batch(&mut self, entries: Vec<WalEntry>) -> Result<(), String> {
+        let mut txn = self.begin();
+        for entry in entries {
+            match entry {
+                WalEntry::Put { slug, payload } => {
+                    txn.put(&slug, &payload).map_err(|e| format!("{slug}: {e}"))?;
+                }
+                WalEntry::Remove { slug } => txn.remove(&slug),
+                WalEntry::Link { from, to, edge_type, strength, .. } => {
+                    txn.link(&from, &to, &edge_type, strength);
+                }
+                WalEntry::LinkMeta { from, to, edge_type, strength, meta, .. } => {
+                    txn.link_meta(&from, &to, &edge_type, strength, &meta)
+                        .map_err(|e| e.to_string())?;
+                }
+                WalEntry::Unlink { from, to, edge_type } => txn.unlink(&from, &to, &edge_type),
+                WalEntry::PutVector { slug, field, data } => txn.put_vector(&slug, &field, data),
+                other => return Err(format!("{other:?} is not allowed in an atomic batch")),
+            }
+        }
+        txn.commit().map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    /// Route one decoded mutation record through the public write path.
+    fn apply_mutation_entry(&mut self, entry: WalEntry) -> Result<(), String> {
+        match entry {
+            WalEntry::Put { slug, payload } => {
+                self.put(&slug, &payload).map_err(|e| e.to_string())?;
+            }
+            WalEntry::Remove { slug } => self.remove(&slug),
+            WalEntry::SoftRemove { slug } => {
+                if !self.remove_soft(&slug) {
+                    return Err(format!("`{slug}` is not live, or an earlier version of it is in the trash"));
+                }
+            }
+            WalEntry::Restore { slug } => {
+                if !self.restore(&slug) {
+                    return Err(format!("`{slug}` is not in the trash, or a live node has taken its slug"));
+                }
+            }
+            WalEntry::Purge { slug } => {
+                if !self.purge(&slug) {
+                    return Err(format!("`{slug}` is not in the trash"));
+                }
+            }
+            // Edges that carry a creation time (e.g. from `export_ndjson`) keep it.
+            edge @ (WalEntry::Link { created_unix: Some(_), .. }
+            | WalEntry::LinkMeta { created_unix: Some(_), .. }) => {
+                if let WalEntry::LinkMeta { meta, .. } = &edge {
+                    serde_json::from_str::<Value>(meta).map_err(|e| e.to_string())?;
+                }
+                if let WalEntry::Link { from, to, edge_type, .. }
+                | WalEntry::LinkMeta { from, to, edge_type, .. } = &edge
+                {
+                    self.replace_existing_edge(from, to, edge_type);
+                }
+                self.wal_write(edge.clone());
+                self.replay(edge);
+            }
+            WalEntry::Link { from, to, edge_type, strength, .. } => {
+                self.link(&from, &to, &edge_type, strength);
+            }
+            WalEntry::LinkMeta { from, to, edge_type, strength, meta, .. } => {
+                self.link_meta(&from, &to, &edge_type, strength, &meta)
+                    .map_err(|e| e.to_string())?;
+            }
+            WalEntry::Unlink { from, to, edge_type } => self.unlink(&from, &to, &edge_type),
+            WalEntry::PutVector { slug, field, data } => {
+                self.put_vector(&slug, &field, &data).map_err(|e| e.to_string())?;
+            }
+            WalEntry::TxnBegin | WalEntry::TxnEnd | WalEntry::Manifest(_) => {}
+            WalEntry::Unknown => return Err("unknown mutation op".into()),
+            // Schema and index changes: log, then apply exactly as replay would.
+            ddl => {
+                self.wal_write(ddl.clone());
+                self.replay(ddl);
+            }
+        }
+        Ok(())
+    }
+
     // ── Persistence ───────────────────────────────────────────────────────────
 
     /// Compact the database: write a full snapshot then truncate the WAL.
@@ -1882,12 +4868,24 @@ impl CoreDB {
     /// After compaction the WAL is empty and `snapshot.json` contains the
     /// complete current state. All previous WAL entries are discarded.
     ///
-    /// In-memory (`CoreDB::new()`) databases silently ignore this call.
+    /// In-memory (`CoreDB::new()`) databases silently ignore this call; one
+    /// opened with [`open_with_wal_store`](Self::open_with_wal_store) has its
+    /// log replaced by the records of an [`export_ndjson`](Self::export_ndjson).
+    ///
+    /// Runs as a [`JobKind::Compact`] job that can be cancelled until the
+    /// live payloads have been rewritten.
     pub fn compact(&mut self) -> io::Result<()> {
+        self.jobs.clone().run(JobKind::Compact, String::new(), |job| self.compact_in(job))
+    }
+
+    fn compact_in(&mut self, job: &Job) -> io::Result<()> {
         let dir = match self.data_dir.clone() {
             Some(d) => d,
-            None => return Ok(()),
+            None => return self.compact_wal_store(),
         };
+        if self.replica.is_some() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "cannot compact a read-only database"));
+        }
 
         // 1. Compact payload store: rebuild from live nodes only.
         // Must happen BEFORE build_snapshot() so the snapshot records the
@@ -1896,6 +4894,16 @@ impl CoreDB {
         // Disk DB: streaming rewrite to payloads.bin.tmp then atomic rename.
         // Neither approach loads all payloads into RAM simultaneously.
         let node_keys: Vec<u64> = self.nodes.keys().copied().collect();
+        // One unit per live node, then the vector stores, then the snapshot
+        // and WAL swap.
+        let total = node_keys.len() as u64 + 2;
+        // Retained versions are not live nodes: lift their bytes out now and
+        // append them to the rewritten store below.
+        let retained: Vec<(u64, Vec<Vec<u8>>)> = self.history.iter()
+            .map(|(&h, kept)| {
+                (h, kept.iter().filter_map(|&(off, len)| self.payload_store.get_raw(off, len)).collect())
+            })
+            .collect();
         if self.payload_store.is_disk() {
             // Disk-backed: stream each live node's bytes through a temp file.
             let pay_tmp  = dir.join("payloads.bin.tmp");
@@ -1909,7 +4917,12 @@ impl CoreDB {
                     let tmp_file = std::fs::OpenOptions::new()
                         .read(true).write(true).create(true).truncate(true)
                         .open(&pay_tmp)?;
-                    for &h in &node_keys {
+                    for (i, &h) in node_keys.iter().enumerate() {
+                        if let Err(e) = job.checkpoint(i as u64, total) {
+                            drop(tmp_file);
+                            let _ = std::fs::remove_file(&pay_tmp);
+                            return Err(e);
+                        }
                         if let Some(node) = self.nodes.get(&h) {
                             if let Some(bytes) = self.payload_store.get_raw(
                                 node.payload_offset, node.payload_len)
@@ -1938,33 +4951,49 @@ impl CoreDB {
         } else {
             // Memory DB: rebuild Vec<u8> without touching disk.
             let mut new_slab: Vec<u8> = Vec::new();
-            for h in node_keys {
+            let mut node_new_offsets: Vec<(u64, u64)> = Vec::new(); // (hash, off)
+            for (i, &h) in node_keys.iter().enumerate() {
+                job.checkpoint(i as u64, total)?;
                 if let Some(node) = self.nodes.get(&h) {
-                    let old_off = node.payload_offset;
-                    let old_len = node.payload_len;
-                    if let Some(bytes) = self.payload_store.get_raw(old_off, old_len) {
-                        let new_off = new_slab.len() as u64;
+                    if let Some(bytes) = self.payload_store.get_raw(node.payload_offset, node.payload_len) {
+                        node_new_offsets.push((h, new_slab.len() as u64));
                         new_slab.extend_from_slice(&bytes);
-                        if let Some(n) = self.nodes.get_mut(&h) {
-                            n.payload_offset = new_off;
-                            n.payload_len    = old_len;
-                        }
                     }
                 }
             }
+            for (h, new_off) in node_new_offsets {
+                if let Some(n) = self.nodes.get_mut(&h) {
+                    n.payload_offset = new_off;
+                }
+            }
             self.payload_store.reset(new_slab);
         }
+        for (h, versions) in retained {
+            let slots = versions.iter().map(|bytes| self.payload_store.append(bytes)).collect();
+            self.history.insert(h, slots);
+        }
 
-        // 2. Compact disk-backed vector stores (reclaim dead space from
-        //    overwrites and deletes).
+        // 2. Compact disk-backed vector stores and edge metadata (reclaim
+        //    dead space from overwrites and deletes, and the metadata ids of
+        //    removed edges).
+        job.report(total - 2, total);
         #[cfg(unix)]
         for store in self.vectors.values_mut() {
             store.compact()?;
         }
+        #[cfg(unix)]
+        self.edges.compact_meta()?;
+        job.report(total - 1, total);
 
         // 3. Write snapshot atomically (tmp → rename) — AFTER payload compaction
         //    so disk-backed SnapNode offsets match the new payloads.bin layout.
-        let snap_json = serde_json::to_vec(&self.build_snapshot())
+        //    Edges go to edges.bin.tmp first; it is renamed into place only
+        //    after the snapshot that names its generation, and open() falls
+        //    back to the .tmp file if a crash lands between the two renames.
+        let generation = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+        let edges_tmp = dir.join("edges.bin.tmp");
+        self.save_edge_binary(&edges_tmp, generation)?;
+        let snap_json = serde_json::to_vec(&self.build_snapshot(Some(generation)))
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         let snap_tmp = dir.join("snapshot.json.tmp");
         let snap_path = dir.join("snapshot.json");
@@ -1974,6 +5003,9 @@ impl CoreDB {
             sf.sync_all()?;
         }
         std::fs::rename(&snap_tmp, &snap_path)?;
+        std::fs::rename(&edges_tmp, dir.join("edges.bin"))?;
+        // The grid can always be rebuilt, so a stale or missing file is harmless.
+        let _ = self.save_spatial_binary(&dir.join("spatial.bin"), generation);
 
         // 3. Truncate WAL: close current writer → rename → open fresh → delete old
         self.wal = None;
@@ -1986,6 +5018,7 @@ impl CoreDB {
         if wal_old.exists() {
             std::fs::remove_file(&wal_old)?;
         }
+        self.wal_corrupted = false;
 
         // Regenerate gin.bin so the next open loads GIN instantly.
         if let Some(ref gin_bin_path) = self.data_dir.as_ref().map(|d| d.join("gin.bin")) {
@@ -1996,6 +5029,29 @@ impl CoreDB {
             let _ = self.save_search_binary(search_bin_path);
         }
 
+        job.report(total, total);
+        Ok(())
+    }
+
+    /// [`compact`](Self::compact) for a database without a data directory:
+    /// swap the [`WalStore`] log for the current state, if there is a store.
+    fn compact_wal_store(&mut self) -> io::Result<()> {
+        if self.wal.is_none() {
+            return Ok(());
+        }
+        let mut records = Vec::new();
+        self.export_ndjson(&mut records)?;
+        let entries = records
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_slice::<WalEntry>)
+            .filter(|e| !matches!(e, Ok(WalEntry::Manifest(_))))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(wal) = &mut self.wal {
+            wal.rewrite(&entries)?;
+        }
+        self.wal_unsynced = 0;
+        self.wal_corrupted = false;
         Ok(())
     }
 
@@ -2063,20 +5119,407 @@ impl CoreDB {
         true
     }
 
+    /// Write live edges to `path` as `edges.bin`: magic, `generation`, then
+    /// the CSR adjacency from [`EdgeStore::write_adjacency`].
+    fn save_edge_binary(&self, path: &Path, generation: u64) -> io::Result<()> {
+        use std::io::Write;
+        let mut f = std::io::BufWriter::new(std::fs::File::create(path)?);
+        f.write_all(b"SKEDGE01")?;
+        f.write_all(&generation.to_le_bytes())?;
+        self.edges.write_adjacency(&mut f, |from, to| {
+            self.nodes.contains_key(&from) && self.nodes.contains_key(&to)
+        })?;
+        f.flush()?;
+        f.get_ref().sync_all()
+    }
+
+    /// Load the edges of a snapshot that keeps them in `edges.bin`. Uses
+    /// `edges.bin.tmp` (and renames it into place) when only that file has
+    /// the snapshot's generation — `compact()` was interrupted after writing
+    /// the snapshot.
+    ///
+    /// Errors if neither file matches: the snapshot's edges would be lost.
+    fn load_edge_binary(&mut self, dir: &Path, generation: u64) -> io::Result<()> {
+        let read = |path: &Path| -> Option<Vec<u8>> {
+            let data = std::fs::read(path).ok()?;
+            (data.len() >= 16
+                && &data[..8] == b"SKEDGE01"
+                && data[8..16] == generation.to_le_bytes())
+                .then_some(data)
+        };
+        let path = dir.join("edges.bin");
+        let tmp = dir.join("edges.bin.tmp");
+        let data = match read(&path) {
+            Some(d) => d,
+            None => {
+                let d = read(&tmp).ok_or_else(|| io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("edges.bin does not match snapshot generation {generation}"),
+                ))?;
+                if self.replica.is_none() {
+                    std::fs::rename(&tmp, &path)?;
+                }
+                d
+            }
+        };
+        self.edges.read_adjacency(&mut &data[16..])?;
+        Ok(())
+    }
+
+    /// Write the spatial grid to `spatial.bin`, stamped with `generation`.
+    fn save_spatial_binary(&self, path: &Path, generation: u64) -> io::Result<()> {
+        use std::io::Write;
+        let Some(grid) = &self.spatial_grid else { return Ok(()) };
+        let tmp = path.with_extension("bin.tmp");
+        let mut f = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+        f.write_all(b"SKGRID01")?;
+        f.write_all(&generation.to_le_bytes())?;
+        grid.write_binary(&mut f)?;
+        f.flush()?;
+        f.get_ref().sync_all()?;
+        drop(f);
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Restore the spatial grid from `spatial.bin` if it was written with
+    /// `generation`; otherwise leave it for `rebuild_spatial_grid`.
+    fn load_spatial_binary(&mut self, path: &Path, generation: u64) {
+        let Ok(data) = std::fs::read(path) else { return };
+        if data.len() < 16 || &data[..8] != b"SKGRID01" || data[8..16] != generation.to_le_bytes() {
+            return;
+        }
+        if let Ok(grid) = geo::SpatialGrid::read_binary(&mut &data[16..]) {
+            self.spatial_grid = Some(grid);
+        }
+    }
+
+    /// Copy the database files into `dest` (created if missing) with a
+    /// `backup.json` [`BackupManifest`] of per-file checksums.
+    ///
+    /// The files are copied as they are, mmap'd payloads, vector files,
+    /// edge and index sidecars included, so a backup is fast and loses
+    /// nothing: restoring it is an ordinary open of the copied directory.
+    /// The copy is point-in-time because it borrows `&mut self`; the WAL
+    /// is synced first so it holds every write made so far.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let (live, backup, restored) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    /// let mut db = CoreDB::open(live.path()).unwrap();
+    /// db.put("a", r#"{"v":1}"#).unwrap();
+    /// db.backup_to(backup.path()).unwrap();
+    /// db.put("b", r#"{"v":2}"#).unwrap();
+    ///
+    /// CoreDB::restore_snapshot(backup.path(), restored.path()).unwrap();
+    /// let copy = CoreDB::open(restored.path()).unwrap();
+    /// assert!(copy.contains("a") && !copy.contains("b"));
+    /// ```
+    ///
+    /// Runs as a [`JobKind::Backup`] job that can be cancelled between
+    /// files, leaving `dest` without a `backup.json`.
+    ///
+    /// # Errors
+    /// `InvalidInput` for an in-memory database or when `dest` is the
+    /// database directory; otherwise any I/O error while copying.
+    pub fn backup_to(&mut self, dest: impl AsRef<Path>) -> io::Result<BackupManifest> {
+        let dest = dest.as_ref();
+        self.jobs.clone().run(JobKind::Backup, dest.display().to_string(), |job| self.backup_in(dest, job))
+    }
+
+    fn backup_in(&mut self, dest: &Path, job: &Job) -> io::Result<BackupManifest> {
+        let dir = self.data_dir.clone().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "backup_to() needs a persistent database")
+        })?;
+        std::fs::create_dir_all(dest)?;
+        if std::fs::canonicalize(dest)? == std::fs::canonicalize(&dir)? {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot back up a database into itself"));
+        }
+        self.sync()?;
+
+        let mut names: Vec<String> = Vec::new();
+        let mut total = 0u64;
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // The lock, interrupted-write leftovers and the S3 block cache
+            // are not database state.
+            let skip = name == "db.lock" || name == "wal.old" || name.ends_with(".tmp");
+            if entry.file_type()?.is_file() && !skip {
+                total += entry.metadata()?.len();
+                names.push(name);
+            }
+        }
+        names.sort();
+
+        let mut files = Vec::with_capacity(names.len());
+        let mut copied = 0u64;
+        for name in names {
+            job.checkpoint(copied, total)?;
+            let (len, crc32) = copy_with_crc(&dir.join(&name), &dest.join(&name))?;
+            copied += len;
+            files.push(BackupFile { name, len, crc32 });
+        }
+        job.report(copied, copied.max(total));
+        let manifest = BackupManifest {
+            version: BACKUP_FORMAT_VERSION,
+            created_unix: chrono::Utc::now().timestamp_millis(),
+            files,
+        };
+        let tmp = dest.join("backup.json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&manifest)?)?;
+        std::fs::rename(&tmp, dest.join("backup.json"))?;
+        Ok(manifest)
+    }
+
+    /// Copy a [`backup_to`](Self::backup_to) directory into `target`, which
+    /// must be empty or missing, checking every file against the manifest.
+    /// Open `target` afterwards as usual.
+    ///
+    /// # Errors
+    /// `InvalidData` if the manifest is missing, newer than this build, or
+    /// a file's length or checksum does not match; `AlreadyExists` if
+    /// `target` is not empty. Files copied before a mismatch are left in
+    /// `target`.
+    pub fn restore_snapshot(backup: impl AsRef<Path>, target: impl AsRef<Path>) -> io::Result<BackupManifest> {
+        let (backup, target) = (backup.as_ref(), target.as_ref());
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let bytes = std::fs::read(backup.join("backup.json"))
+            .map_err(|e| invalid(format!("{}: no backup.json ({e})", backup.display())))?;
+        let manifest: BackupManifest = serde_json::from_slice(&bytes)
+            .map_err(|e| invalid(format!("backup.json: {e}")))?;
+        if manifest.version > BACKUP_FORMAT_VERSION {
+            return Err(invalid(format!(
+                "backup version {} requires a newer sekejap (max supported: {BACKUP_FORMAT_VERSION})",
+                manifest.version
+            )));
+        }
+        std::fs::create_dir_all(target)?;
+        if std::fs::read_dir(target)?.next().is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is not empty", target.display()),
+            ));
+        }
+        for file in &manifest.files {
+            if file.name.contains(['/', '\\']) || file.name.starts_with('.') {
+                return Err(invalid(format!("backup.json lists an unsafe file name `{}`", file.name)));
+            }
+            let got = copy_with_crc(&backup.join(&file.name), &target.join(&file.name))?;
+            if got != (file.len, file.crc32) {
+                return Err(invalid(format!(
+                    "{}: {} bytes with CRC32 {:08x}, manifest expects {} with {:08x}",
+                    file.name, got.0, got.1, file.len, file.crc32
+                )));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Write what changed since checkpoint `since_lsn` to the file at
+    /// `path`, and return the checkpoint to pass next time.
+    ///
+    /// `since_lsn == 0` writes a full [`export_ndjson`](Self::export_ndjson)
+    /// and starts change tracking. Later calls write only the nodes whose
+    /// payload, vectors or outgoing edges changed since `since_lsn`, the
+    /// edges touching them, removals, and schema or settings changes, so a
+    /// nightly backup costs what the day wrote rather than the database
+    /// size. Load the files in order with
+    /// [`restore_incremental`](Self::restore_incremental): the full one into
+    /// an empty database, then each delta.
+    ///
+    /// Asking for `since_lsn` forgets everything before it, so older
+    /// checkpoints stop working; the log only grows between backups.
+    /// Trashed nodes are not
+    /// backed up, and a node soft-removed since the checkpoint is removed
+    /// outright on restore. Tracking is saved by [`compact`](Self::compact);
+    /// a crash before the first compact after the full backup loses it, and
+    /// the next delta then asks for a new full backup.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let (full, delta) = (dir.path().join("full.ndjson"), dir.path().join("delta.ndjson"));
+    /// let mut db = CoreDB::new();
+    /// db.put("a", r#"{"v":1}"#).unwrap();
+    /// let lsn = db.backup_incremental(&full, 0).unwrap();
+    /// db.put("b", r#"{"v":2}"#).unwrap();
+    /// db.remove("a");
+    /// db.backup_incremental(&delta, lsn).unwrap();
+    ///
+    /// let mut copy = CoreDB::new();
+    /// copy.restore_incremental(&full).unwrap();
+    /// copy.restore_incremental(&delta).unwrap();
+    /// assert!(copy.contains("b") && !copy.contains("a"));
+    /// ```
+    ///
+    /// # Errors
+    /// `InvalidInput` when `since_lsn` is ahead of this database or older
+    /// than the change log reaches; otherwise any I/O error while writing.
+    pub fn backup_incremental(&mut self, path: impl AsRef<Path>, since_lsn: u64) -> io::Result<u64> {
+        use crate::vector::VectorAccess;
+        use std::io::Write;
+
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut writer = io::BufWriter::new(std::fs::File::create(&tmp)?);
+
+        if since_lsn == 0 {
+            // Checkpoints start at 1 so none is mistaken for a full backup.
+            let lsn = self.change_log.get_or_insert_with(|| ChangeLog { lsn: 1, floor: 1, ..Default::default() }).lsn;
+            self.export_ndjson(&mut writer)?;
+            writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            std::fs::rename(&tmp, path)?;
+            return Ok(lsn);
+        }
+        let log = match &mut self.change_log {
+            Some(log) if since_lsn > log.lsn => Err(format!(
+                "checkpoint {since_lsn} is ahead of this database (at {})", log.lsn
+            )),
+            Some(log) if since_lsn < log.floor => Err(format!(
+                "checkpoint {since_lsn} is older than the change log (from {}); take a full backup", log.floor
+            )),
+            Some(log) => Ok(log),
+            None => Err("no change log yet; take a full backup with since_lsn 0".to_string()),
+        };
+        let log = log.map_err(|msg| {
+            let _ = std::fs::remove_file(&tmp);
+            io::Error::new(io::ErrorKind::InvalidInput, msg)
+        })?;
+        log.forget_before(since_lsn);
+        let log = self.change_log.as_ref().expect("checked above");
+
+        let mut digest = ManifestBuilder::default();
+        let mut emit = |entry: WalEntry| -> io::Result<()> {
+            let line = serde_json::to_string(&entry)?;
+            digest.add(&line, &entry);
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\n")
+        };
+
+        for (_, entry) in &log.schema {
+            emit(entry.clone())?;
+        }
+        // Changed nodes go out whole: removing them first clears their old
+        // edges in both directions on the restoring side, and the edges
+        // they have now are re-sent below.
+        let mut touched: Vec<&String> = log.nodes.keys().collect();
+        touched.sort();
+        for slug in &touched {
+            emit(WalEntry::Remove { slug: slug.to_string() })?;
+        }
+        let live: Vec<&NodeData> = touched.iter().filter_map(|s| self.nodes.get(&sk_hash(s))).collect();
+        let mut fields: Vec<&String> = self.vectors.keys().collect();
+        fields.sort();
+        for node in &live {
+            if let Some(payload) = self.payload_store.get(node.payload_offset, node.payload_len) {
+                emit(WalEntry::Put { slug: node.slug.clone(), payload: serde_json::to_string(&payload)? })?;
+            }
+            for field in &fields {
+                if let Some(data) = self.vectors[*field].get(sk_hash(&node.slug)) {
+                    emit(WalEntry::PutVector { slug: node.slug.clone(), field: field.to_string(), data: data.to_vec() })?;
+                }
+            }
+        }
+        let is_touched = |h: u64| self.nodes.get(&h).is_some_and(|n| log.nodes.contains_key(&n.slug));
+        for node in &live {
+            let hash = sk_hash(&node.slug);
+            for e in self.edges.fwd_edges(hash).unwrap_or(&[]) {
+                if let Some(to) = self.nodes.get(&e.other) {
+                    emit(self.edge_record(&node.slug, &to.slug, e))?;
+                }
+            }
+            // Edges from a changed node were sent with it.
+            for e in self.edges.rev_edges(hash).unwrap_or(&[]).iter().filter(|e| !is_touched(e.other)) {
+                if let Some(from) = self.nodes.get(&e.other) {
+                    emit(self.edge_record(&from.slug, &node.slug, e))?;
+                }
+            }
+        }
+
+        let lsn = log.lsn;
+        serde_json::to_writer(&mut writer, &WalEntry::Manifest(digest.finish()))?;
+        writer.write_all(b"\n")?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(lsn)
+    }
+
+    /// Apply a file written by [`backup_incremental`](Self::backup_incremental),
+    /// verified as by [`import_ndjson`](Self::import_ndjson). Files must be
+    /// applied in the order they were taken.
+    ///
+    /// Runs as a [`JobKind::Restore`] job that can be cancelled until the
+    /// file has been verified, before anything is applied.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or does not match its manifest.
+    pub fn restore_incremental(&mut self, path: impl AsRef<Path>) -> io::Result<MutationSummary> {
+        let path = path.as_ref();
+        self.jobs.clone().run(JobKind::Restore, path.display().to_string(), |job| {
+            let file = std::fs::File::open(path)?;
+            self.import_ndjson_in(io::BufReader::new(file), job)
+        })
+    }
+
     /// Force WAL data to reach disk (fsync).
-    /// By default writes are flushed to the OS buffer but not fsynced.
-    /// Call this after a critical batch of writes if you need guaranteed
-    /// on-disk durability before the OS flushes on its own schedule.
+    /// Writes are always flushed to the OS buffer; how often they are fsynced
+    /// is set by [`WalSync`]. Call this after a critical batch of writes if
+    /// you need guaranteed on-disk durability under `Batch` or `Never`.
     pub fn sync(&mut self) -> io::Result<()> {
         if let Some(wal) = &mut self.wal {
             wal.sync()?;
+            self.wal_unsynced = 0;
         }
         Ok(())
     }
 
+    // ── Jobs ──────────────────────────────────────────────────────────────────
+
+    /// The registry of this database's long-running operations. Keep the
+    /// handle on another thread to follow progress, [`Jobs::cancel`] a job
+    /// or hear about completions without waiting for the database.
+    pub fn jobs(&self) -> Jobs {
+        self.jobs.clone()
+    }
+
+    /// Run `request` as a job and return how it ended. Errors are recorded
+    /// in the returned [`JobState`] rather than returned.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, JobRequest, JobState};
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let mut db = CoreDB::open(dir.path()).unwrap();
+    /// db.put("a", r#"{"v":1}"#).unwrap();
+    /// let job = db.run_job(JobRequest::Compact);
+    /// assert_eq!((&job.state, job.progress()), (&JobState::Succeeded, 1.0));
+    /// ```
+    pub fn run_job(&mut self, request: JobRequest) -> JobInfo {
+        let jobs = self.jobs.clone();
+        let job = jobs.start(request.kind(), request.target());
+        let result = match request {
+            JobRequest::Backup { dest } => self.backup_in(&dest, &job).map(drop).map_err(|e| e.to_string()),
+            JobRequest::Restore { path } => std::fs::File::open(&path)
+                .and_then(|file| self.import_ndjson_in(io::BufReader::new(file), &job))
+                .map(drop)
+                .map_err(|e| e.to_string()),
+            JobRequest::Compact => self.compact_in(&job).map_err(|e| e.to_string()),
+            JobRequest::HnswBuild { field, m, ef_construction } => {
+                self.build_hnsw_in(&field, m, ef_construction, &job, |_, _| {}).map(|()| {
+                    self.wal_write(WalEntry::BuildHnsw { field, m, ef_construction });
+                })
+            }
+            JobRequest::IndexRebuild => self.rebuild_pending_in(&job).map_err(|e| e.to_string()),
+        };
+        let _ = jobs.finish(&job, result);
+        job.info()
+    }
+
     // ── Snapshot helpers ──────────────────────────────────────────────────────
 
-    fn build_snapshot(&self) -> Snapshot {
+    /// With `sidecar_gen`, edges are left to `edges.bin` (see `compact()`).
+    fn build_snapshot(&self, sidecar_gen: Option<u64>) -> Snapshot {
         let is_disk = self.payload_store.is_disk();
         let nodes: Vec<SnapNode> = if is_disk {
             // Disk-backed: payloads live in payloads.bin — only store metadata.
@@ -2107,7 +5550,8 @@ impl CoreDB {
         };
 
         let mut edges: Vec<SnapEdge> = Vec::new();
-        for (&from_h, edge_list) in self.edges.iter_fwd() {
+        let inline_edges = self.edges.iter_fwd().filter(|_| sidecar_gen.is_none());
+        for (&from_h, edge_list) in inline_edges {
             let from_slug = match self.nodes.get(&from_h) {
                 Some(n) => n.slug.clone(),
                 None => continue, // dangling edge, skip
@@ -2127,6 +5571,7 @@ impl CoreDB {
                     to: to_slug,
                     edge_type,
                     strength: e.strength,
+                    created_unix: e.created_unix,
                     meta: self.edges.edge_meta(e),
                 });
             }
@@ -2184,6 +5629,20 @@ impl CoreDB {
             None
         };
 
+        let snap_history: Vec<SnapHistory> = self.history.iter()
+            .filter_map(|(h, kept)| {
+                let slug = self.nodes.get(h)?.slug.clone();
+                Some(if is_disk {
+                    SnapHistory { slug, slots: kept.iter().copied().collect(), payloads: Vec::new() }
+                } else {
+                    let payloads = kept.iter()
+                        .filter_map(|&(off, len)| self.payload_store.get(off, len))
+                        .collect();
+                    SnapHistory { slug, slots: Vec::new(), payloads }
+                })
+            })
+            .collect();
+
         Snapshot {
             version: SNAPSHOT_FORMAT_VERSION,
             is_disk_backed: is_disk,
@@ -2194,6 +5653,21 @@ impl CoreDB {
             vectors: if snap_vectors.is_empty() { None } else { Some(snap_vectors) },
             hnsw_indexes: if snap_hnsw.is_empty() { None } else { Some(snap_hnsw) },
             btree_indexes: snap_btree,
+            trash: if self.trash.is_empty() { None } else { Some(self.trash.values().cloned().collect()) },
+            collection_filters: if self.collection_filters.is_empty() {
+                None
+            } else {
+                Some(self.collection_filters.values().map(|(c, f, _)| (c.clone(), f.clone())).collect())
+            },
+            edge_aggregates: if self.edge_aggregates.is_empty() { None } else { Some(self.edge_aggregates.clone()) },
+            projection_profiles: if self.projection_profiles.is_empty() {
+                None
+            } else {
+                Some(self.projection_profiles.clone())
+            },
+            history: if snap_history.is_empty() { None } else { Some(snap_history) },
+            change_log: self.change_log.clone(),
+            sidecar_gen,
             gin_indexes: Ignored,
         }
     }
@@ -2226,10 +5700,11 @@ impl CoreDB {
         }
         for e in snap.edges {
             if let Some(meta) = e.meta {
-                let _ =
-                    self.link_meta_raw(&e.from, &e.to, &e.edge_type, e.strength, &meta.to_string());
+                let _ = self.link_meta_raw(
+                    &e.from, &e.to, &e.edge_type, e.strength, e.created_unix, &meta.to_string(),
+                );
             } else {
-                self.link_raw(&e.from, &e.to, &e.edge_type, e.strength);
+                self.link_raw(&e.from, &e.to, &e.edge_type, e.strength, e.created_unix);
             }
         }
         if let Some(schemas) = snap.schemas {
@@ -2237,6 +5712,27 @@ impl CoreDB {
                 self.schemas.insert(schema.collection.clone(), schema);
             }
         }
+        if let Some(trash) = snap.trash {
+            for t in trash {
+                self.trash.insert(sk_hash(&t.slug), t);
+            }
+        }
+        if let Some(filters) = snap.collection_filters {
+            for (collection, filter) in filters {
+                let _ = self.set_collection_filter_raw(&collection, Some(&filter));
+            }
+        }
+        // Stored values are already current; only the definitions come back.
+        self.edge_aggregates = snap.edge_aggregates.unwrap_or_default();
+        self.projection_profiles = snap.projection_profiles.unwrap_or_default();
+        self.change_log = snap.change_log;
+        for kept in snap.history.into_iter().flatten() {
+            let mut slots: VecDeque<(u64, u32)> = kept.slots.into_iter().collect();
+            for payload in kept.payloads {
+                slots.push_back(self.payload_store.append(payload.to_string().as_bytes()));
+            }
+            self.history.insert(sk_hash(&kept.slug), slots);
+        }
         // Restore vector index from snapshot — WAL replay will add anything
         // written after the snapshot was taken.
         // When has_vector_files is set, vectors live in .bin files — skip JSON
@@ -2257,7 +5753,7 @@ impl CoreDB {
                     self.hnsw_indexes.insert(sh.field, sh.graph);
                 } else {
                     // Version mismatch — rebuild from stored vectors.
-                    let _ = self.build_hnsw_index(&sh.field, sh.m, sh.ef_construction);
+                    let _ = self.build_hnsw_raw(&sh.field, sh.m, sh.ef_construction, |_, _| true);
                 }
             }
         }
@@ -2272,12 +5768,12 @@ impl CoreDB {
             }
         }
 
-        // Rebuild btree field indexes — only when stored version mismatches,
+        // Rebuild btree/hash field indexes — only when stored version mismatches,
         // or when no btree snapshot was present (legacy snapshot or new index).
         let btree_rebuild: Vec<(String, String)> = self
             .schemas
             .values()
-            .flat_map(|s| s.indexes.range.iter().map(|f| {
+            .flat_map(|s| s.indexes.range.iter().chain(&s.indexes.hash).map(|f| {
                 let v = s.indexes.build_versions.get(&format!("btree:{f}")).copied().unwrap_or(0);
                 (s.collection.clone(), f.clone(), v)
             }))
@@ -2294,7 +5790,7 @@ impl CoreDB {
             .map(|(c, f, _)| (c, f))
             .collect();
         for (coll, field) in btree_rebuild {
-            self.build_field_index(&coll, &field);
+            self.build_field_index_raw(&coll, &field);
         }
 
         // Rebuild BM25 indexes — only when stored version mismatches.
@@ -2334,6 +5830,63 @@ impl CoreDB {
             .map(|b| String::from_utf8_lossy(&b).into_owned())
     }
 
+    /// Like [`get`](Self::get), but a payload that is not valid UTF-8 JSON is
+    /// an error naming its slug and store offset instead of being passed on
+    /// lossily (`get`) or skipped (query filters).
+    ///
+    /// # Errors
+    /// [`PayloadError`] when the stored bytes do not decode.
+    pub fn get_strict(&self, slug: &str) -> Result<Option<String>, PayloadError> {
+        let hash = sk_hash(slug);
+        match self.nodes.get(&hash) {
+            Some(node) => self.decode_payload(hash, node).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Check every live payload and report those that do not decode, in slug
+    /// order. Reads each blob once; nothing is changed.
+    pub fn verify_payloads(&self) -> Vec<PayloadError> {
+        let mut bad: Vec<PayloadError> = self.nodes.iter()
+            .filter_map(|(&hash, node)| self.decode_payload(hash, node).err())
+            .collect();
+        bad.sort_by(|a, b| a.slug.cmp(&b.slug));
+        bad
+    }
+
+    /// Move every node whose payload does not decode into the trash (as
+    /// [`remove_soft`](Self::remove_soft) does, edges and vectors included)
+    /// and return what was wrong with each. Queries stop tripping over them;
+    /// [`purge`](Self::purge) drops them for good. Bytes that are not UTF-8
+    /// are kept with U+FFFD replacements, unreadable ones as empty text, and
+    /// [`restore`](Self::restore) refuses a record until its text parses as
+    /// JSON. A node whose slug already has a trashed version stays live and
+    /// is left out of the result.
+    pub fn quarantine_bad_payloads(&mut self) -> Vec<PayloadError> {
+        let mut bad = self.verify_payloads();
+        bad.retain(|err| self.remove_soft(&err.slug));
+        bad
+    }
+
+    fn decode_payload(&self, hash: u64, node: &NodeData) -> Result<String, PayloadError> {
+        let error = |fault| PayloadError {
+            slug: node.slug.clone(),
+            slug_hash: hash,
+            offset: node.payload_offset,
+            len: node.payload_len,
+            fault,
+        };
+        let bytes = self.payload_store.get_raw(node.payload_offset, node.payload_len)
+            .ok_or_else(|| error(PayloadFault::Unreadable))?;
+        let text = String::from_utf8(bytes).map_err(|e| error(PayloadFault::InvalidUtf8 {
+            valid_up_to: e.utf8_error().valid_up_to(),
+        }))?;
+        if let Err(e) = serde_json::from_str::<serde::de::IgnoredAny>(&text) {
+            return Err(error(PayloadFault::InvalidJson(e.to_string())));
+        }
+        Ok(text)
+    }
+
     /// Parse and return the JSON payload for a node hash. Returns `None` if
     /// the node does not exist or the payload cannot be parsed.
     pub(crate) fn get_payload(&self, hash: u64) -> Option<Value> {
@@ -2509,6 +6062,13 @@ impl CoreDB {
         self.nodes.contains_key(&sk_hash(slug))
     }
 
+    /// Reverse-resolve a slug hash (as found in [`Hit::slug_hash`] or edge
+    /// endpoints) to its slug. Slugs are held in memory, so no payload read
+    /// is needed. Returns `None` for unknown hashes.
+    pub fn slug_of(&self, hash: u64) -> Option<&str> {
+        self.nodes.get(&hash).map(|n| n.slug.as_str())
+    }
+
     /// Total number of nodes.
     pub fn node_count(&self) -> usize {
         self.nodes.len()
@@ -2519,6 +6079,39 @@ impl CoreDB {
         self.edges.edge_count()
     }
 
+    /// Current membership of `collection`; see [`CollectionSnapshot`].
+    pub fn collection_snapshot(&self, collection: &str) -> CollectionSnapshot {
+        let members = self.collection_members(sk_hash(collection))
+            .map(|m| m.iter().copied().collect())
+            .unwrap_or_default();
+        CollectionSnapshot { members }
+    }
+
+    /// Nodes that joined or left `collection` since `since` was taken.
+    /// Payload edits to nodes that stayed are not reported.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, CollectionSnapshot};
+    /// let mut db = CoreDB::new();
+    /// db.put("users/a", r#"{"_collection":"users"}"#).unwrap();
+    /// let first = db.collection_diff("users", &CollectionSnapshot::default());
+    /// assert_eq!(first.added.len(), 1);
+    ///
+    /// db.put("users/b", r#"{"_collection":"users"}"#).unwrap();
+    /// db.remove("users/a");
+    /// let next = db.collection_diff("users", &first.snapshot);
+    /// assert_eq!(db.slug_of(next.added[0]), Some("users/b"));
+    /// assert_eq!(next.removed.len(), 1);
+    /// ```
+    pub fn collection_diff(&self, collection: &str, since: &CollectionSnapshot) -> CollectionDiff {
+        let snapshot = self.collection_snapshot(collection);
+        CollectionDiff {
+            added: (&snapshot.members - &since.members).iter().collect(),
+            removed: (&since.members - &snapshot.members).iter().collect(),
+            snapshot,
+        }
+    }
+
     /// Returns all distinct collection names present in the graph, sorted.
     ///
     /// Includes collections that have nodes but no explicit `CREATE TABLE` schema.
@@ -2578,6 +6171,7 @@ impl CoreDB {
                         edge_type: self.edges.type_name(e.edge_type).map(|s| s.to_string()),
                         edge_type_hash: e.edge_type,
                         strength: e.strength,
+                        created_unix: e.created_unix,
                         meta: self.edges.edge_meta(e),
                     })
                     .collect()
@@ -2599,6 +6193,7 @@ impl CoreDB {
                         edge_type: self.edges.type_name(e.edge_type).map(|s| s.to_string()),
                         edge_type_hash: e.edge_type,
                         strength: e.strength,
+                        created_unix: e.created_unix,
                         meta: self.edges.edge_meta(e),
                     })
                     .collect()
@@ -2620,6 +6215,7 @@ impl CoreDB {
                         edge_type: self.edges.type_name(e.edge_type).map(|s| s.to_string()),
                         edge_type_hash: e.edge_type,
                         strength: e.strength,
+                        created_unix: e.created_unix,
                         meta: self.edges.edge_meta(e),
                     });
                 }
@@ -2738,30 +6334,360 @@ impl CoreDB {
                 }
             }
         }
-        triples.sort();
-        triples
+        triples.sort();
+        triples
+    }
+
+    /// Self-diagnostics with the default [`HealthThresholds`]; see
+    /// [`health_with`](Self::health_with).
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, HealthStatus, Quota};
+    /// let mut db = CoreDB::new();
+    /// assert_eq!(db.health().status, HealthStatus::Ok);
+    /// db.set_quota("logs", Quota { max_nodes: Some(2), max_payload_bytes: None });
+    /// db.put("l/1", r#"{"_collection":"logs"}"#).unwrap();
+    /// db.put("l/2", r#"{"_collection":"logs"}"#).unwrap();
+    /// let health = db.health();
+    /// assert_eq!(health.status, HealthStatus::Degraded);
+    /// assert!(health.reasons[0].contains("logs"));
+    /// ```
+    pub fn health(&self) -> Health {
+        self.health_with(&HealthThresholds::default())
+    }
+
+    /// Check the database for problems an operator should know about.
+    ///
+    /// [`HealthStatus::Unhealthy`] when the WAL had a corrupted frame at open
+    /// (entries after it were lost; [`compact`](Self::compact) clears this) or
+    /// the data directory has disappeared. [`HealthStatus::Degraded`] when the
+    /// WAL has grown past `thresholds.wal_backlog_bytes`, a deferred index
+    /// rebuild is pending, or a collection has filled `thresholds.quota_fill`
+    /// of its [`Quota`].
+    pub fn health_with(&self, thresholds: &HealthThresholds) -> Health {
+        let mut health = Health { status: HealthStatus::Ok, reasons: Vec::new() };
+
+        if self.wal_corrupted {
+            health.report(
+                HealthStatus::Unhealthy,
+                "WAL had a corrupted frame at open; entries after it were lost — run compact()".into(),
+            );
+        }
+        if let Some(dir) = &self.data_dir {
+            if !dir.is_dir() {
+                health.report(
+                    HealthStatus::Unhealthy,
+                    format!("data directory `{}` is missing", dir.display()),
+                );
+            } else if let Ok(meta) = std::fs::metadata(dir.join("wal.log")) {
+                if meta.len() > thresholds.wal_backlog_bytes {
+                    health.report(
+                        HealthStatus::Degraded,
+                        format!("WAL backlog of {} bytes since the last compact", meta.len()),
+                    );
+                }
+            }
+        }
+        if self.pending_index_rebuild.is_some() {
+            health.report(
+                HealthStatus::Degraded,
+                "secondary index rebuild pending; text, vector and search lookups may be incomplete".into(),
+            );
+        }
+
+        let mut quotas: Vec<(&str, &Quota)> = self.quotas.iter()
+            .filter_map(|(h, q)| Some((self.collection_names_map.get(h)?.as_str(), q)))
+            .collect();
+        quotas.sort_by_key(|(name, _)| *name);
+        for (name, quota) in quotas {
+            let (count, bytes) = self.collection_usage(name);
+            if let Some(limit) = quota.max_nodes {
+                if count as f64 >= limit as f64 * thresholds.quota_fill {
+                    health.report(
+                        HealthStatus::Degraded,
+                        format!("collection `{name}` holds {count} of its {limit} node quota"),
+                    );
+                }
+            }
+            if let Some(limit) = quota.max_payload_bytes {
+                if bytes as f64 >= limit as f64 * thresholds.quota_fill {
+                    health.report(
+                        HealthStatus::Degraded,
+                        format!("collection `{name}` holds {bytes} of its {limit} payload byte quota"),
+                    );
+                }
+            }
+        }
+        health
+    }
+
+    // ── Query starters ────────────────────────────────────────────────────────
+
+    /// Start a query from a single node.
+    pub fn one(&self, slug: &str) -> Set<'_> {
+        Set::new(self, Step::One(sk_hash(slug)))
+    }
+
+    /// Start a query from a set of nodes.
+    pub fn many<'a>(&self, slugs: impl IntoIterator<Item = &'a str>) -> Set<'_> {
+        Set::new(self, Step::Many(slugs.into_iter().map(sk_hash).collect()))
+    }
+
+    /// Start a query over all nodes.
+    pub fn all(&self) -> Set<'_> {
+        Set::new(self, Step::All)
+    }
+
+    /// Start from the nodes within `radius_km` of node `slug`'s centroid, the
+    /// anchor excluded. Empty when `slug` is missing or has no geometry.
+    pub fn near_node(&self, slug: &str, radius_km: f64) -> Set<'_> {
+        Set::new(self, Step::NearNode(sk_hash(slug), radius_km))
+    }
+
+    /// Start a query over all nodes in a named collection.
+    pub fn collection(&self, name: &str) -> Set<'_> {
+        Set::new(self, Step::Collection(sk_hash(name)))
+    }
+
+    /// Start from a collection *without* its implicit filter (see
+    /// [`set_collection_filter`](Self::set_collection_filter)). Use for admin
+    /// and maintenance code that must see every row.
+    pub fn collection_unfiltered(&self, name: &str) -> Set<'_> {
+        Set::from_steps_unscoped(self, vec![Step::Collection(sk_hash(name))])
+    }
+
+    /// Run `select` and apply `mutation` to every node it matches, in one
+    /// call rather than collecting slugs and looping on the client.
+    ///
+    /// The pipeline is resolved once, up front; the mutation then runs with
+    /// the same WAL, index and schema handling as SQL `UPDATE` / `DELETE`.
+    /// Inside a SQL transaction it is buffered until `COMMIT`.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, SetMutation};
+    /// let mut db = CoreDB::new();
+    /// db.put("t/1", r#"{"_collection":"tasks","done":true}"#).unwrap();
+    /// db.put("t/2", r#"{"_collection":"tasks","done":false}"#).unwrap();
+    /// let archived = db.mutate_where(
+    ///     |db| db.collection("tasks").where_eq("done", true),
+    ///     SetMutation::SetField { field: "status".into(), value: "archived".into() },
+    /// ).unwrap();
+    /// assert_eq!(archived, 1);
+    /// assert_eq!(db.collection("tasks").where_eq("status", "archived").count(), 1);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`SqlError`] if a `SetField` value violates the collection
+    /// schema or a unique constraint.
+    pub fn mutate_where(
+        &mut self,
+        select: impl for<'s> FnOnce(&'s CoreDB) -> Set<'s>,
+        mutation: SetMutation,
+    ) -> Result<usize, SqlError> {
+        let hashes = select(self).into_hashes();
+        let steps = vec![Step::Many(hashes)];
+        let compiled = match mutation {
+            SetMutation::Delete => sql::CompiledMutation::Delete(steps),
+            SetMutation::SetField { field, value } => {
+                sql::CompiledMutation::Update { steps, updates: vec![(field, value)] }
+            }
+        };
+        self.execute_mutation(compiled)
+    }
+
+    /// Declare an implicit filter for `collection`: a SQL `WHERE` condition
+    /// appended to every pipeline that starts with that collection — the
+    /// builder API, `SELECT … FROM collection`, and `UPDATE` / `DELETE`
+    /// targets alike. Pass `None` to remove it.
+    ///
+    /// The filter is persisted and survives reopen. Only row filters are
+    /// accepted (no `ORDER BY` / `LIMIT` / projections).
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("u/1", r#"{"_collection":"users","deleted":false}"#).unwrap();
+    /// db.put("u/2", r#"{"_collection":"users","deleted":true}"#).unwrap();
+    /// db.set_collection_filter("users", Some("deleted != true")).unwrap();
+    /// assert_eq!(db.collection("users").count(), 1);
+    /// assert_eq!(db.collection_unfiltered("users").count(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`SqlError`] if `filter` does not parse as a row filter.
+    pub fn set_collection_filter(&mut self, collection: &str, filter: Option<&str>) -> Result<(), SqlError> {
+        self.set_collection_filter_raw(collection, filter)?;
+        self.wal_write(WalEntry::SetCollectionFilter {
+            collection: collection.to_string(),
+            filter: filter.map(|f| f.to_string()),
+        });
+        Ok(())
+    }
+
+    /// The implicit filter declared for `collection`, if any.
+    pub fn collection_filter(&self, collection: &str) -> Option<&str> {
+        self.collection_filters.get(&sk_hash(collection)).map(|(_, f, _)| f.as_str())
+    }
+
+    /// Keep `field` on every node of `collection` equal to `aggregate` over
+    /// that node's edges, so readers fetch one payload instead of counting.
+    /// Existing nodes are filled in now; afterwards the value is updated
+    /// whenever an edge of the node is linked, unlinked or cascaded away, and
+    /// reapplied on every `put`. Pass `None` to stop maintaining it (the last
+    /// stored value stays).
+    ///
+    /// The definition is persisted and survives reopen.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, EdgeAggregate};
+    /// let mut db = CoreDB::new();
+    /// db.put("geo/d1", r#"{"_collection":"geo"}"#).unwrap();
+    /// db.set_edge_aggregate("geo", "incident_count", Some(EdgeAggregate::Count {
+    ///     edge_type: "located_in".into(),
+    ///     incoming: true,
+    /// }));
+    /// db.put("inc/1", "{}").unwrap();
+    /// db.put("inc/2", "{}").unwrap();
+    /// db.link("inc/1", "geo/d1", "located_in", 1.0);
+    /// db.link("inc/2", "geo/d1", "located_in", 1.0);
+    /// db.remove("inc/1");
+    /// let d1: serde_json::Value = serde_json::from_str(&db.get("geo/d1").unwrap()).unwrap();
+    /// assert_eq!(d1["incident_count"], 1);
+    /// ```
+    pub fn set_edge_aggregate(&mut self, collection: &str, field: &str, aggregate: Option<EdgeAggregate>) {
+        self.wal_write(WalEntry::SetEdgeAggregate {
+            collection: collection.to_string(),
+            field: field.to_string(),
+            aggregate_json: aggregate.as_ref().and_then(|a| serde_json::to_string(a).ok()),
+        });
+        self.set_edge_aggregate_raw(collection, field, aggregate);
+    }
+
+    /// The edge aggregate maintained in `field` of `collection`, if any.
+    pub fn edge_aggregate(&self, collection: &str, field: &str) -> Option<&EdgeAggregate> {
+        self.edge_aggregates.iter()
+            .find(|(c, f, _)| c == collection && f == field)
+            .map(|(_, _, a)| a)
+    }
+
+    fn set_edge_aggregate_raw(&mut self, collection: &str, field: &str, aggregate: Option<EdgeAggregate>) {
+        self.edge_aggregates.retain(|(c, f, _)| !(c == collection && f == field));
+        let Some(aggregate) = aggregate else { return };
+        self.edge_aggregates.push((collection.to_string(), field.to_string(), aggregate));
+        let members = self.collections.get(&sk_hash(collection)).cloned().unwrap_or_default();
+        self.refresh_edge_aggregates(&members);
+    }
+
+    /// Declare projection profile `profile` for `collection`: the payload
+    /// fields in `exclude` are removed from every hit of that collection in
+    /// a query that selects the profile with [`Set::profile`] (or the
+    /// `profile` argument of the bindings). Pass `None` to drop the profile.
+    /// Collections without the profile are returned unchanged.
+    ///
+    /// The profile is persisted and survives reopen.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("u/1", r#"{"_collection":"users","name":"Ani","secret":"s3"}"#).unwrap();
+    /// db.set_projection_profile("users", "public", Some(vec!["secret".into()]));
+    /// let hit = db.collection("users").profile("public").unwrap().first().unwrap();
+    /// assert!(hit.payload.unwrap().get("secret").is_none());
+    /// let hit = db.collection("users").first().unwrap();
+    /// assert_eq!(hit.payload.unwrap()["secret"], "s3");
+    /// ```
+    pub fn set_projection_profile(&mut self, collection: &str, profile: &str, exclude: Option<Vec<String>>) {
+        self.wal_write(WalEntry::SetProjectionProfile {
+            collection: collection.to_string(),
+            profile: profile.to_string(),
+            exclude: exclude.clone(),
+        });
+        self.set_projection_profile_raw(collection, profile, exclude);
+    }
+
+    /// The fields projection profile `profile` leaves out of `collection`, if
+    /// that profile is declared.
+    pub fn projection_profile(&self, collection: &str, profile: &str) -> Option<&[String]> {
+        self.projection_profiles.iter()
+            .find(|(c, p, _)| c == collection && p == profile)
+            .map(|(_, _, exclude)| exclude.as_slice())
+    }
+
+    fn set_projection_profile_raw(&mut self, collection: &str, profile: &str, exclude: Option<Vec<String>>) {
+        self.projection_profiles.retain(|(c, p, _)| !(c == collection && p == profile));
+        if let Some(exclude) = exclude {
+            self.projection_profiles.push((collection.to_string(), profile.to_string(), exclude));
+        }
     }
 
-    // ── Query starters ────────────────────────────────────────────────────────
+    /// The fields projection profile `profile` hides on node `h`, or `None`
+    /// when it hides nothing there.
+    pub(crate) fn profile_hides(&self, profile: &str, h: u64) -> Option<&[String]> {
+        let node = self.nodes.get(&h)?;
+        self.projection_profile(&node.collection, profile).filter(|exclude| !exclude.is_empty())
+    }
 
-    /// Start a query from a single node.
-    pub fn one(&self, slug: &str) -> Set<'_> {
-        Set::new(self, Step::One(sk_hash(slug)))
+    /// Every field projection profile `profile` hides in any collection, or
+    /// `None` when no collection declares it.
+    pub(crate) fn profile_fields(&self, profile: &str) -> Option<Vec<&str>> {
+        let mut declared = false;
+        let mut fields = Vec::new();
+        for (_, p, exclude) in &self.projection_profiles {
+            if p == profile {
+                declared = true;
+                fields.extend(exclude.iter().map(String::as_str));
+            }
+        }
+        declared.then_some(fields)
     }
 
-    /// Start a query from a set of nodes.
-    pub fn many<'a>(&self, slugs: impl IntoIterator<Item = &'a str>) -> Set<'_> {
-        Set::new(self, Step::Many(slugs.into_iter().map(sk_hash).collect()))
+    /// Remove the fields `profile` excludes for each hit's collection.
+    pub(crate) fn apply_projection_profile(&self, profile: &str, hits: &mut [Hit]) {
+        for hit in hits {
+            let Some(exclude) = self.profile_hides(profile, hit.slug_hash) else { continue };
+            if let Some(map) = hit.payload.as_mut().and_then(Value::as_object_mut) {
+                for field in exclude {
+                    map.remove(field);
+                }
+            }
+        }
     }
 
-    /// Start a query over all nodes.
-    pub fn all(&self) -> Set<'_> {
-        Set::new(self, Step::All)
+    fn set_collection_filter_raw(&mut self, collection: &str, filter: Option<&str>) -> Result<(), SqlError> {
+        let coll_hash = sk_hash(collection);
+        let filter = match filter {
+            Some(f) => f,
+            None => {
+                self.collection_filters.remove(&coll_hash);
+                return Ok(());
+            }
+        };
+        // Parse as a plain SELECT and keep everything after the starter.
+        let sql = format!("SELECT * FROM {collection} WHERE {filter}");
+        let steps = match sql::parse_match_or_agg(&sql)? {
+            sql::MatchOrAgg::Steps(steps) => steps,
+            _ => return Err(SqlError::InvalidValue(format!("not a row filter: {filter}"))),
+        };
+        let filter_steps: Vec<Step> = steps.into_iter()
+            .skip_while(|s| matches!(s, Step::Collection(_)))
+            .collect();
+        if filter_steps.is_empty() || !filter_steps.iter().all(is_row_filter) {
+            return Err(SqlError::InvalidValue(format!("not a row filter: {filter}")));
+        }
+        self.collection_filters.insert(
+            coll_hash,
+            (collection.to_string(), filter.to_string(), filter_steps),
+        );
+        Ok(())
     }
 
-    /// Start a query over all nodes in a named collection.
-    pub fn collection(&self, name: &str) -> Set<'_> {
-        Set::new(self, Step::Collection(sk_hash(name)))
+    /// Splice the implicit filter after a leading `Step::Collection`.
+    pub(crate) fn apply_collection_filter(&self, steps: &mut Vec<Step>) {
+        if let Some(Step::Collection(h)) = steps.first() {
+            if let Some((_, _, filter)) = self.collection_filters.get(h) {
+                steps.splice(1..1, filter.iter().cloned());
+            }
+        }
     }
 
     /// Execute a SQL query and return a lazy [`Set`].
@@ -2822,23 +6748,7 @@ impl CoreDB {
     /// assert_eq!(hits[0].slug, "alice");
     /// ```
     pub fn query(&self, sql: &str) -> Result<Set<'_>, SqlError> {
-        match sql::parse_match_or_agg(sql)? {
-            sql::MatchOrAgg::Agg(stmt) => {
-                let hits = query::execute_match_agg(self, stmt);
-                Ok(Set::from_hits(self, hits))
-            }
-            sql::MatchOrAgg::Shortest(stmt) => {
-                let hits = query::execute_shortest_select(self, stmt);
-                Ok(Set::from_hits(self, hits))
-            }
-            sql::MatchOrAgg::MultiFrom(stmt) => {
-                let hits = query::execute_multi_from(self, stmt);
-                Ok(Set::from_hits(self, hits))
-            }
-            sql::MatchOrAgg::Steps(steps) => {
-                Ok(Set::from_steps(self, steps))
-            }
-        }
+        self.query_with_limits(sql, &[], &QueryLimits::default())
     }
 
     /// Parameterized SELECT / MATCH query.
@@ -2859,23 +6769,618 @@ impl CoreDB {
     /// assert_eq!(hits[0].slug, "users/alice");
     /// ```
     pub fn query_params(&self, sql: &str, params: &[Value]) -> Result<Set<'_>, SqlError> {
-        match sql::parse_match_or_agg_params(sql, params.to_vec())? {
-            sql::MatchOrAgg::Agg(stmt) => {
-                let hits = query::execute_match_agg(self, stmt);
-                Ok(Set::from_hits(self, hits))
+        self.query_with_limits(sql, params, &QueryLimits::default())
+    }
+
+    /// [`query_params`](Self::query_params) under per-call `limits`, e.g. from
+    /// a binding serving untrusted callers. Per-call limits can only tighten
+    /// the database-wide ones set with [`set_query_limits`](Self::set_query_limits).
+    ///
+    /// # Errors
+    /// [`SqlError::LimitExceeded`] when the SQL text or the compiled pipeline
+    /// is over its cap. The result caps travel with the returned [`Set`] and
+    /// are checked when it is collected.
+    ///
+    /// # Example
+    /// ```
+    /// # use sekejap::{CoreDB, QueryLimits};
+    /// let mut db = CoreDB::new();
+    /// for i in 0..5 {
+    ///     db.put(&format!("u/{i}"), r#"{"_collection":"users"}"#).unwrap();
+    /// }
+    /// let limits = QueryLimits { max_results: Some(2), ..Default::default() };
+    /// let hits = db.query_with_limits("SELECT * FROM users", &[], &limits).unwrap().collect();
+    /// assert_eq!(hits.len(), 2);
+    /// ```
+    pub fn query_with_limits(
+        &self,
+        sql: &str,
+        params: &[Value],
+        limits: &QueryLimits,
+    ) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits.tighten(limits);
+        let parsed = parse_query_within(sql, params, &limits)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    /// SELECT / MATCH query with `$name` placeholders bound from a params
+    /// object, so values never have to be spliced into the SQL text.
+    ///
+    /// Every placeholder is checked against `params` before the statement
+    /// is parsed; entries no placeholder uses are ignored. `$name` and
+    /// `$1` placeholders cannot be mixed in one statement.
+    ///
+    /// # Errors
+    /// [`SqlError::UnboundParam`] for a placeholder missing from `params`.
+    ///
+    /// # Example
+    /// ```
+    /// # use sekejap::{CoreDB, SqlError};
+    /// # use serde_json::json;
+    /// let mut db = CoreDB::new();
+    /// db.put("users/alice", r#"{"name":"Alice","age":30,"_collection":"users"}"#).unwrap();
+    /// let params = json!({"name": "Alice", "min_age": 18});
+    /// let hits = db.query_named(
+    ///     "SELECT * FROM users WHERE name = $name AND age >= $min_age",
+    ///     params.as_object().unwrap(),
+    /// ).unwrap().collect();
+    /// assert_eq!(hits[0].slug, "users/alice");
+    /// assert!(matches!(
+    ///     db.query_named("SELECT * FROM users WHERE name = $nme", params.as_object().unwrap()),
+    ///     Err(SqlError::UnboundParam(name)) if name == "nme"
+    /// ));
+    /// ```
+    pub fn query_named(&self, sql: &str, params: &serde_json::Map<String, Value>) -> Result<Set<'_>, SqlError> {
+        self.query_named_with_limits(sql, params, &QueryLimits::default())
+    }
+
+    /// [`query_named`](Self::query_named) under per-call `limits`, as in
+    /// [`query_with_limits`](Self::query_with_limits).
+    pub fn query_named_with_limits(
+        &self,
+        sql: &str,
+        params: &serde_json::Map<String, Value>,
+        limits: &QueryLimits,
+    ) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits.tighten(limits);
+        check_sql_len(sql, &limits)?;
+        let parsed = sql::parse_match_or_agg_named(sql, params)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    /// [`query_params`](Self::query_params) on behalf of a caller: `auth`
+    /// sees the statement's [`Access`] and collections before it runs.
+    ///
+    /// # Errors
+    /// [`SqlError::Unauthorized`] when `auth` rejects the statement.
+    ///
+    /// # Example
+    /// ```
+    /// # use sekejap::{Access, CoreDB, SqlError};
+    /// let mut db = CoreDB::new();
+    /// db.put("u/1", r#"{"_collection":"users"}"#).unwrap();
+    /// db.put("s/1", r#"{"_collection":"secrets"}"#).unwrap();
+    /// let users_only = |_: Access, cols: Option<&[String]>| match cols {
+    ///     Some(c) if c.iter().all(|n| n == "users") => Ok(()),
+    ///     _ => Err("token is scoped to users".to_string()),
+    /// };
+    /// assert_eq!(db.query_as(&users_only, "SELECT * FROM users", &[]).unwrap().count(), 1);
+    /// assert!(matches!(
+    ///     db.query_as(&users_only, "SELECT * FROM secrets", &[]),
+    ///     Err(SqlError::Unauthorized(_))
+    /// ));
+    /// ```
+    pub fn query_as(&self, auth: &dyn Authorizer, sql: &str, params: &[Value]) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits;
+        let parsed = parse_query_within(sql, params, &limits)?;
+        let collections = auth::read_scope(self, &parsed);
+        auth.authorize(Access::Read, collections.as_deref())
+            .map_err(SqlError::Unauthorized)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    /// [`query_named`](Self::query_named) on behalf of a caller, checked by
+    /// `auth` as in [`query_as`](Self::query_as).
+    pub fn query_named_as(
+        &self,
+        auth: &dyn Authorizer,
+        sql: &str,
+        params: &serde_json::Map<String, Value>,
+    ) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits;
+        check_sql_len(sql, &limits)?;
+        let parsed = sql::parse_match_or_agg_named(sql, params)?;
+        let collections = auth::read_scope(self, &parsed);
+        auth.authorize(Access::Read, collections.as_deref())
+            .map_err(SqlError::Unauthorized)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    /// Parse a SELECT / MATCH statement once for repeated runs with
+    /// [`query_prepared`](Self::query_prepared). Statements are cached by
+    /// their text, so preparing the same SQL again returns the cached plan.
+    /// Placeholders, `$1..$n` or `$name`, are bound per run.
+    ///
+    /// # Errors
+    /// [`SqlError`] when the statement does not tokenize, when a statement
+    /// without placeholders does not parse, or when the text is over the
+    /// [`QueryLimits::max_sql_len`] set on the database.
+    ///
+    /// # Example
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// # use serde_json::json;
+    /// let mut db = CoreDB::new();
+    /// db.put("users/alice", r#"{"name":"Alice","_collection":"users"}"#).unwrap();
+    /// db.put("users/bob", r#"{"name":"Bob","_collection":"users"}"#).unwrap();
+    /// let by_name = db.prepare("SELECT * FROM users WHERE name = $1").unwrap();
+    /// assert_eq!(by_name.param_count(), 1);
+    /// for (name, slug) in [("Alice", "users/alice"), ("Bob", "users/bob")] {
+    ///     let hits = db.query_prepared(&by_name, &[json!(name)]).unwrap().collect();
+    ///     assert_eq!(hits[0].slug, slug);
+    /// }
+    /// ```
+    pub fn prepare(&self, sql: &str) -> Result<PreparedQuery, SqlError> {
+        check_sql_len(sql, &self.query_limits)?;
+        let key = sk_hash(sql);
+        let mut cache = self.prepared.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(hit) = cache.get(&key).filter(|p| &*p.sql == sql) {
+            return Ok(hit.clone());
+        }
+        let prepared = PreparedQuery {
+            sql: sql.into(),
+            stmt: std::sync::Arc::new(sql::PreparedStmt::new(sql)?),
+        };
+        if cache.len() >= PREPARED_CACHE_CAP {
+            cache.clear();
+        }
+        cache.insert(key, prepared.clone());
+        Ok(prepared)
+    }
+
+    /// Run a statement from [`prepare`](Self::prepare) with `params` bound
+    /// to its `$1..$n` placeholders, under the database's query limits.
+    ///
+    /// # Errors
+    /// [`SqlError::ParamOutOfRange`] when fewer values than
+    /// [`PreparedQuery::param_count`] are given, and the parse errors of
+    /// [`query_params`](Self::query_params) for placeholders in positions
+    /// that reject the bound value.
+    pub fn query_prepared(&self, prepared: &PreparedQuery, params: &[Value]) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits;
+        let parsed = prepared.stmt.bind(params)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    /// Run a statement from [`prepare`](Self::prepare) with its `$name`
+    /// placeholders bound from `params`, as in [`query_named`](Self::query_named).
+    ///
+    /// # Errors
+    /// [`SqlError::UnboundParam`] for a placeholder missing from `params`;
+    /// [`SqlError::InvalidValue`] for a statement using `$1..$n` instead.
+    pub fn query_prepared_named(
+        &self,
+        prepared: &PreparedQuery,
+        params: &serde_json::Map<String, Value>,
+    ) -> Result<Set<'_>, SqlError> {
+        let limits = self.query_limits;
+        let parsed = prepared.stmt.bind_named(params)?;
+        self.run_parsed_query(parsed, &limits)
+    }
+
+    fn run_parsed_query(&self, parsed: sql::MatchOrAgg, limits: &QueryLimits) -> Result<Set<'_>, SqlError> {
+        let mut budget = limits.max_execution_ms.map(budget::Budget::within).unwrap_or_default();
+        if let Some(cap) = limits.max_frontier {
+            budget.track_frontier(cap, true);
+        }
+        let (hits, end) = budget.run(|| match parsed {
+            sql::MatchOrAgg::Agg(stmt) => Ok(Ok(query::execute_match_agg(self, stmt))),
+            sql::MatchOrAgg::Shortest(stmt) => Ok(query::execute_shortest_select(self, stmt)),
+            sql::MatchOrAgg::MultiFrom(stmt) => Ok(query::execute_multi_from(self, stmt)),
+            sql::MatchOrAgg::Steps(steps) => Err(steps),
+        });
+        let hits = match (hits, end.stopped, end.frontier) {
+            (Ok(_), Some(cause), _) => return Err(SqlError::LimitExceeded(cause.to_string())),
+            (Ok(Err(err)), None, _) => return Err(SqlError::LimitExceeded(err.to_string())),
+            (Ok(_), None, Some(frontier)) => {
+                let err = ResultTooLarge { hits: 0, payload_bytes: 0, frontier, limits: *limits };
+                return Err(SqlError::LimitExceeded(err.to_string()));
+            }
+            (Ok(Ok(hits)), None, None) => hits,
+            (Err(steps), _, _) => {
+                if let Some(max) = limits.max_steps {
+                    let n = step_count(&steps);
+                    if n > max {
+                        return Err(SqlError::LimitExceeded(format!(
+                            "query compiles to {n} steps, limit is {max}"
+                        )));
+                    }
+                }
+                let mut set = Set::from_steps(self, steps);
+                set.limits = *limits;
+                set.budget = budget;
+                return Ok(set);
+            }
+        };
+        let mut set = Set::from_hits(self, hits);
+        set.limits = *limits;
+        Ok(set)
+    }
+
+    /// Change runtime settings without reopening; see [`ConfigUpdate`].
+    ///
+    /// A new WAL policy applies to the next write. Entries the old policy
+    /// left unsynced are fsynced first, so moving to a stricter policy
+    /// gives its guarantee from this call on.
+    ///
+    /// ```
+    /// # use sekejap::{ConfigUpdate, CoreDB, QueryLimits, WalSync};
+    /// let mut db = CoreDB::new();
+    /// db.reconfigure(ConfigUpdate {
+    ///     wal: Some(WalSync::Batch { max_entries: 128 }),
+    ///     limits: Some(QueryLimits { max_results: Some(100), ..Default::default() }),
+    ///     ..Default::default()
+    /// }).unwrap();
+    /// assert_eq!(db.wal_sync(), WalSync::Batch { max_entries: 128 });
+    /// assert_eq!(db.query_limits().max_results, Some(100));
+    /// ```
+    pub fn reconfigure(&mut self, update: ConfigUpdate) -> io::Result<()> {
+        if let Some(sync) = update.wal {
+            if self.wal_unsynced > 0 {
+                self.sync()?;
+            }
+            self.wal_sync = sync;
+        }
+        if let Some(limits) = update.limits {
+            self.set_query_limits(limits);
+        }
+        if let Some(threshold) = update.near_filter_threshold {
+            self.set_near_filter_threshold(threshold);
+        }
+        for (collection, quota) in update.quotas {
+            self.set_quota(&collection, quota);
+        }
+        for (collection, versions) in update.history_retention {
+            self.set_history_retention(&collection, versions);
+        }
+        if let Some(allow) = update.allow_parallel_edges {
+            self.set_allow_parallel_edges(allow);
+        }
+        Ok(())
+    }
+
+    /// The WAL fsync policy in force.
+    pub fn wal_sync(&self) -> WalSync {
+        self.wal_sync
+    }
+
+    /// Replace the time source used for `_created_unix` / `_updated_unix`,
+    /// edge creation times, TTL expiries and [`Set::unexpired`]. Also
+    /// settable via [`Config::clock`].
+    pub fn set_clock(&mut self, clock: std::sync::Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Current time from the installed [`Clock`], in Unix milliseconds.
+    pub fn now_millis(&self) -> i64 {
+        self.clock.now_millis()
+    }
+
+    /// Caps applied to every query on this database (also settable via
+    /// [`Config::limits`]): the SQL caps to [`query`](Self::query) and
+    /// friends, the result caps to every `collect()`, for the builder API
+    /// and SQL alike. Pass [`QueryLimits::default`] to lift them.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, QueryLimits};
+    /// let mut db = CoreDB::new();
+    /// for i in 0..5 {
+    ///     db.put(&format!("n/{i}"), r#"{"_collection":"n"}"#).unwrap();
+    /// }
+    /// db.set_query_limits(QueryLimits { max_results: Some(3), ..Default::default() });
+    /// let err = db.collection("n").try_collect().unwrap_err();
+    /// assert_eq!(err.hits, 5);
+    /// assert_eq!(db.collection("n").take(3).try_collect().unwrap().len(), 3);
+    /// assert_eq!(db.collection("n").collect().len(), 3);
+    /// ```
+    pub fn set_query_limits(&mut self, limits: QueryLimits) {
+        self.query_limits = limits;
+    }
+
+    /// The database-wide query caps.
+    pub fn query_limits(&self) -> &QueryLimits {
+        &self.query_limits
+    }
+
+    /// The traversal cap in force: the running query's, or the database's
+    /// outside a query run.
+    pub(crate) fn frontier_cap(&self) -> Option<usize> {
+        budget::frontier_cap(self.query_limits.max_frontier)
+    }
+
+    /// How `ST_DWithin` and near-node steps filter an existing candidate
+    /// set when a spatial grid is built (also settable via
+    /// [`Config::near_filter_threshold`]). With `Some(n)`, fewer than `n`
+    /// candidates are checked one by one and larger sets are intersected
+    /// with a grid query. With `None` (the default) each step compares the
+    /// two, using the grid's occupancy around the query point and the
+    /// per-item cost each strategy took on recent queries. Both give the
+    /// same hits.
+    pub fn set_near_filter_threshold(&mut self, threshold: Option<usize>) {
+        self.near_filter_threshold = threshold;
+    }
+
+    /// The fixed spatial filter crossover, or `None` when adaptive.
+    pub fn near_filter_threshold(&self) -> Option<usize> {
+        self.near_filter_threshold
+    }
+
+    pub(crate) fn filter_costs(&self) -> &query::FilterCosts {
+        &self.filter_costs
+    }
+
+    /// Hold the nodes a query is about to load to the result caps in
+    /// `limits`: cut `hashes` to fit, or with `strict` report the overflow
+    /// instead.
+    pub(crate) fn fit_result(
+        &self,
+        hashes: &mut Vec<u64>,
+        limits: &QueryLimits,
+        strict: bool,
+    ) -> Result<(), ResultTooLarge> {
+        let limits = *limits;
+        if limits.max_results.is_none() && limits.max_payload_bytes.is_none() {
+            return Ok(());
+        }
+        let max_hits = limits.max_results.unwrap_or(usize::MAX);
+        let max_bytes = limits.max_payload_bytes.unwrap_or(u64::MAX);
+        let size = |h: &u64| self.nodes.get(h).map_or(0, |n| n.payload_len as u64);
+        let mut bytes = 0u64;
+        let mut fits = hashes.len();
+        for (i, h) in hashes.iter().enumerate() {
+            bytes += size(h);
+            if i >= max_hits || bytes > max_bytes {
+                fits = i;
+                break;
             }
-            sql::MatchOrAgg::Shortest(stmt) => {
-                let hits = query::execute_shortest_select(self, stmt);
-                Ok(Set::from_hits(self, hits))
+        }
+        if fits == hashes.len() {
+            return Ok(());
+        }
+        if strict {
+            return Err(ResultTooLarge {
+                hits: hashes.len(),
+                payload_bytes: hashes.iter().map(size).sum(),
+                frontier: 0,
+                limits,
+            });
+        }
+        hashes.truncate(fits);
+        Ok(())
+    }
+
+    /// Cap what `collection` may hold (also settable via [`Config::quotas`]).
+    /// Existing data over the cap stays; only writes that would grow the
+    /// collection further are rejected. Pass [`Quota::default`] to lift it.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, Quota, QuotaExceeded};
+    /// let mut db = CoreDB::new();
+    /// db.set_quota("logs", Quota { max_nodes: Some(1), ..Default::default() });
+    /// db.put("l/1", r#"{"_collection":"logs"}"#).unwrap();
+    /// let err = db.put("l/2", r#"{"_collection":"logs"}"#).unwrap_err();
+    /// assert!(matches!(QuotaExceeded::from_put_error(err), Ok(QuotaExceeded::Nodes { .. })));
+    /// ```
+    pub fn set_quota(&mut self, collection: &str, quota: Quota) {
+        if quota == Quota::default() {
+            self.quotas.remove(&sk_hash(collection));
+        } else {
+            self.quotas.insert(sk_hash(collection), quota);
+        }
+    }
+
+    /// The quota set for `collection`, if any.
+    pub fn quota(&self, collection: &str) -> Option<&Quota> {
+        self.quotas.get(&sk_hash(collection))
+    }
+
+    /// Keep the last `versions` payloads of every node in `collection` when
+    /// it is overwritten (also settable via [`Config::history_retention`]).
+    /// Read them back with [`history`](Self::history). Pass `0` to stop
+    /// retaining; versions already kept beyond the new count are dropped.
+    ///
+    /// Retained versions survive `compact()` and reopen; removing a node
+    /// drops its history.
+    pub fn set_history_retention(&mut self, collection: &str, versions: usize) {
+        let coll_hash = sk_hash(collection);
+        if versions == 0 {
+            self.history_retention.remove(&coll_hash);
+        } else {
+            self.history_retention.insert(coll_hash, versions);
+        }
+        for h in self.collections.get(&coll_hash).into_iter().flatten() {
+            if let Some(kept) = self.history.get_mut(h) {
+                kept.truncate(versions);
             }
-            sql::MatchOrAgg::MultiFrom(stmt) => {
-                let hits = query::execute_multi_from(self, stmt);
-                Ok(Set::from_hits(self, hits))
+        }
+        self.history.retain(|_, kept| !kept.is_empty());
+    }
+
+    /// How many prior versions `collection` keeps per node (`0` = none).
+    pub fn history_retention(&self, collection: &str) -> usize {
+        self.history_retention.get(&sk_hash(collection)).copied().unwrap_or(0)
+    }
+
+    /// Prior payloads of `slug`, newest first, excluding the current one.
+    /// Empty unless its collection retains history; see
+    /// [`set_history_retention`](Self::set_history_retention).
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.set_history_retention("docs", 2);
+    /// for rev in 1..=4 {
+    ///     db.put("docs/a", &format!(r#"{{"_collection":"docs","rev":{rev}}}"#)).unwrap();
+    /// }
+    /// let revs: Vec<_> = db.history("docs/a").iter().map(|v| v.payload["rev"].clone()).collect();
+    /// assert_eq!(revs, [3, 2]);
+    /// ```
+    pub fn history(&self, slug: &str) -> Vec<NodeVersion> {
+        self.history.get(&sk_hash(slug)).into_iter().flatten()
+            .filter_map(|&(off, len)| self.payload_store.get(off, len))
+            .map(|payload| NodeVersion {
+                updated_unix: payload.get("_updated_unix").and_then(|v| v.as_i64()).unwrap_or(0),
+                payload,
+            })
+            .collect()
+    }
+
+    /// Live node count and stored payload bytes of `collection`.
+    pub fn collection_usage(&self, collection: &str) -> (usize, u64) {
+        let members = self.collections.get(&sk_hash(collection));
+        let bytes = members.into_iter().flatten()
+            .filter_map(|h| self.nodes.get(h))
+            .map(|n| n.payload_len as u64)
+            .sum();
+        (members.map_or(0, |m| m.len()), bytes)
+    }
+
+    /// Would writing `bytes` of payload to `slug` in `collection` break its quota?
+    /// An update only counts the growth over the node's current payload.
+    fn check_quota(&self, slug: &str, collection: &str, bytes: u64) -> Result<(), QuotaExceeded> {
+        let Some(quota) = self.quotas.get(&sk_hash(collection)) else { return Ok(()) };
+        let existing = self.nodes.get(&sk_hash(slug)).filter(|n| n.collection == collection);
+        let (count, used) = self.collection_usage(collection);
+        if let Some(limit) = quota.max_nodes {
+            if existing.is_none() && count >= limit {
+                return Err(QuotaExceeded::Nodes { collection: collection.to_string(), limit });
+            }
+        }
+        if let Some(limit) = quota.max_payload_bytes {
+            let requested = used - existing.map_or(0, |n| n.payload_len as u64) + bytes;
+            if requested > limit {
+                return Err(QuotaExceeded::PayloadBytes {
+                    collection: collection.to_string(),
+                    limit,
+                    requested,
+                });
             }
-            sql::MatchOrAgg::Steps(steps) => {
-                Ok(Set::from_steps(self, steps))
+        }
+        Ok(())
+    }
+
+    /// Would growing `collection` by `nodes` nodes and `bytes` payload bytes
+    /// break its quota? Shrinking, or holding steady, never does.
+    fn check_quota_growth(&self, collection: &str, nodes: i64, bytes: i64) -> Result<(), QuotaExceeded> {
+        let Some(quota) = self.quotas.get(&sk_hash(collection)) else { return Ok(()) };
+        let (count, used) = self.collection_usage(collection);
+        if let Some(limit) = quota.max_nodes {
+            if nodes > 0 && count as i64 + nodes > limit as i64 {
+                return Err(QuotaExceeded::Nodes { collection: collection.to_string(), limit });
             }
         }
+        if let Some(limit) = quota.max_payload_bytes {
+            let requested = (used as i64 + bytes).max(0) as u64;
+            if bytes > 0 && requested > limit {
+                return Err(QuotaExceeded::PayloadBytes { collection: collection.to_string(), limit, requested });
+            }
+        }
+        Ok(())
+    }
+
+    /// Would writing `payload` to `slug` duplicate a `unique` field of
+    /// `collection`? Null and missing values never conflict. The field index
+    /// backing each unique field is built on first use.
+    fn check_unique(&mut self, slug: &str, collection: &str, payload: &Value) -> Result<(), UniqueViolation> {
+        self.check_unique_among(slug, collection, payload, &HashSet::new(), &mut BTreeMap::new())
+    }
+
+    /// [`check_unique`](Self::check_unique) for one put of a batch. Stored
+    /// nodes in `rewritten` are judged by what the batch writes for them,
+    /// collected in `claimed` as each put is checked.
+    fn check_unique_among(
+        &mut self,
+        slug: &str,
+        collection: &str,
+        payload: &Value,
+        rewritten: &HashSet<u64>,
+        claimed: &mut BTreeMap<(u64, String, FieldKey), String>,
+    ) -> Result<(), UniqueViolation> {
+        let fields = match self.schemas.get(collection) {
+            Some(schema) if !schema.indexes.unique.is_empty() => schema.indexes.unique.clone(),
+            _ => return Ok(()),
+        };
+        let coll_hash = sk_hash(collection);
+        let own = sk_hash(slug);
+        for field in fields {
+            let value = crate::query::json_path_get(&field, payload).unwrap_or(Value::Null);
+            let key = match FieldKey::from_json(&value) {
+                Some(FieldKey::Null) | None => continue,
+                Some(k) => k,
+            };
+            if !self.field_indexes.contains_key(&(coll_hash, field.clone())) {
+                self.build_field_index_raw(collection, &field);
+            }
+            let holder = self.field_indexes[&(coll_hash, field.clone())]
+                .get(&key)
+                .and_then(|hashes| hashes.iter().find(|&&h| h != own && !rewritten.contains(&h)));
+            let existing = match holder.and_then(|h| self.nodes.get(h)) {
+                Some(node) => Some(node.slug.clone()),
+                None => claimed.get(&(coll_hash, field.clone(), key.clone())).filter(|s| *s != slug).cloned(),
+            };
+            if let Some(existing) = existing {
+                return Err(UniqueViolation { collection: collection.to_string(), field, value, existing });
+            }
+            claimed.insert((coll_hash, field, key), slug.to_string());
+        }
+        Ok(())
+    }
+
+    /// Check the puts of a [`Transaction`] against the collections' unique
+    /// fields and quotas before any of them is applied. Unique values are
+    /// checked against stored nodes the batch leaves alone and against each
+    /// other; quotas against the node and byte totals the whole batch would
+    /// leave. Only the last put or remove queued for a slug counts.
+    fn check_txn(&mut self, ops: &[TxnOp]) -> Result<(), serde_json::Error> {
+        if self.quotas.is_empty() && self.schemas.values().all(|s| s.indexes.unique.is_empty()) {
+            return Ok(());
+        }
+        let mut order: Vec<&str> = Vec::new();
+        let mut last: HashMap<&str, Option<&str>> = HashMap::new();
+        for op in ops {
+            let (slug, json) = match op {
+                TxnOp::Put(slug, json) => (slug.as_str(), Some(json.as_str())),
+                TxnOp::Remove(slug) => (slug.as_str(), None),
+                _ => continue,
+            };
+            if last.insert(slug, json).is_none() {
+                order.push(slug);
+            }
+        }
+        let rewritten: HashSet<u64> = order.iter().map(|s| sk_hash(s)).collect();
+        let mut claimed = BTreeMap::new();
+        // Node and byte growth per collection.
+        let mut growth: HashMap<String, (i64, i64)> = HashMap::new();
+        for slug in order {
+            if let Some(node) = self.nodes.get(&sk_hash(slug)) {
+                let g = growth.entry(node.collection.clone()).or_default();
+                *g = (g.0 - 1, g.1 - node.payload_len as i64);
+            }
+            let Some(json) = last[slug] else { continue };
+            let payload: Value = serde_json::from_str(json)?;
+            let collection = payload.get("_collection").and_then(Value::as_str).unwrap_or("");
+            if let Err(u) = self.check_unique_among(slug, collection, &payload, &rewritten, &mut claimed) {
+                return Err(serde_json::Error::io(std::io::Error::other(u)));
+            }
+            let g = growth.entry(collection.to_string()).or_default();
+            *g = (g.0 + 1, g.1 + json.len() as i64);
+        }
+        for (collection, (nodes, bytes)) in growth {
+            if let Err(q) = self.check_quota_growth(&collection, nodes, bytes) {
+                return Err(serde_json::Error::io(std::io::Error::other(q)));
+            }
+        }
+        Ok(())
     }
 
     /// `EXPLAIN SELECT ...` — return the query plan as result rows.
@@ -2897,6 +7402,7 @@ impl CoreDB {
                 rows.push(query::Hit {
                     slug: String::new(), slug_hash: 0,
                     payload: Some(Value::Object(map)),
+                    score: None,
                 });
                 Ok(rows)
             }
@@ -2906,6 +7412,7 @@ impl CoreDB {
                 Ok(vec![query::Hit {
                     slug: String::new(), slug_hash: 0,
                     payload: Some(Value::Object(map)),
+                    score: None,
                 }])
             }
             sql::MatchOrAgg::MultiFrom(_) => {
@@ -2914,6 +7421,7 @@ impl CoreDB {
                 Ok(vec![query::Hit {
                     slug: String::new(), slug_hash: 0,
                     payload: Some(Value::Object(map)),
+                    score: None,
                 }])
             }
         }
@@ -2936,6 +7444,7 @@ impl CoreDB {
         rows.push(query::Hit {
             slug: String::new(), slug_hash: 0,
             payload: Some(Value::Object(map)),
+            score: None,
         });
         Ok(rows)
     }
@@ -2945,16 +7454,19 @@ impl CoreDB {
     /// BFS from `start` to `end`, tracking the parent pointer and edge used at
     /// each hop so the path can be reconstructed.
     ///
-    /// Returns `None` when no path exists.
-    /// Returns a zero-hop `BfsPath` when `start == end`.
-    pub(crate) fn bfs_shortest_path(&self, start: u64, end: u64) -> Option<BfsPath> {
+    /// Returns `Ok(None)` when no path exists, a zero-hop `BfsPath` when
+    /// `start == end`, and `Err` when the search outgrew
+    /// [`QueryLimits::max_frontier`] before reaching `end`, so "no path"
+    /// is never a guess.
+    pub(crate) fn bfs_shortest_path(&self, start: u64, end: u64) -> Result<Option<BfsPath>, ResultTooLarge> {
         use std::collections::{HashMap, VecDeque};
 
         // Sentinel: parent for the start node points to itself with a zero
         // edge_type hash so we can detect "we are at the root" during
         // reconstruction without a separate visited set.
-        // (from_hash, edge_type_hash, strength, meta)
-        let mut parent: HashMap<u64, (u64, u64, f32, Option<Value>)> = HashMap::new();
+        // (from_hash, edge_type_hash, strength, created_unix, meta)
+        type Parent = (u64, u64, f32, i64, Option<Value>);
+        let mut parent: HashMap<u64, Parent> = HashMap::new();
 
         // Same-node degenerate case
         if start == end {
@@ -2963,19 +7475,21 @@ impl CoreDB {
                     slug: node.slug.clone(),
                     slug_hash: start,
                     payload: self.payload_store.get(node.payload_offset, node.payload_len),
+                    score: None,
                 };
-                return Some(BfsPath { nodes: vec![hit], edges: vec![], length: 0 });
+                return Ok(Some(BfsPath { nodes: vec![hit], edges: vec![], length: 0 }));
             } else {
-                return None; // start node doesn't exist
+                return Ok(None); // start node doesn't exist
             }
         }
 
         // The start node must exist
         if !self.nodes.contains_key(&start) {
-            return None;
+            return Ok(None);
         }
 
-        parent.insert(start, (start, 0, 0.0, None)); // sentinel
+        parent.insert(start, (start, 0, 0.0, 0, None)); // sentinel
+        let cap = self.frontier_cap();
         let mut queue: VecDeque<u64> = VecDeque::new();
         queue.push_back(start);
 
@@ -2985,14 +7499,18 @@ impl CoreDB {
                     if parent.contains_key(&e.other) {
                         continue; // already visited
                     }
-                    parent.insert(e.other, (current, e.edge_type, e.strength, self.edges.edge_meta(e)));
+                    parent.insert(e.other, (current, e.edge_type, e.strength, e.created_unix, self.edges.edge_meta(e)));
+                    if e.other != end && !budget::frontier_fits(cap, parent.len()) {
+                        let limits = QueryLimits { max_frontier: cap, ..self.query_limits };
+                        return Err(ResultTooLarge { hits: 0, payload_bytes: 0, frontier: parent.len(), limits });
+                    }
                     if e.other == end {
                         // Reconstruct path: walk parent map from end → start, then reverse.
                         let mut node_hashes: Vec<u64> = Vec::new();
                         let mut cur = end;
                         loop {
                             node_hashes.push(cur);
-                            let (prev, _, _, _) = parent[&cur];
+                            let (prev, ..) = parent[&cur];
                             if prev == cur {
                                 break; // reached the sentinel (start node)
                             }
@@ -3008,6 +7526,7 @@ impl CoreDB {
                                     slug: n.slug.clone(),
                                     slug_hash: h,
                                     payload: self.payload_store.get(n.payload_offset, n.payload_len),
+                                    score: None,
                                 })
                             })
                             .collect();
@@ -3016,27 +7535,102 @@ impl CoreDB {
                         let edges: Vec<EdgeHit> = node_hashes
                             .windows(2)
                             .map(|w| {
-                                let (_, edge_type_hash, strength, meta) = parent[&w[1]].clone();
+                                let (_, edge_type_hash, strength, created_unix, meta) = parent[&w[1]].clone();
                                 EdgeHit {
                                     from_slug: self.nodes.get(&w[0]).map(|n| n.slug.clone()),
                                     to_slug: self.nodes.get(&w[1]).map(|n| n.slug.clone()),
                                     edge_type: self.edges.type_name(edge_type_hash).map(|s| s.to_string()),
                                     edge_type_hash,
                                     strength,
+                                    created_unix,
                                     meta,
                                 }
                             })
                             .collect();
 
                         let length = edges.len();
-                        return Some(BfsPath { nodes, edges, length });
+                        return Ok(Some(BfsPath { nodes, edges, length }));
                     }
                     queue.push_back(e.other);
                 }
             }
         }
 
-        None // no path found
+        Ok(None) // no path found
+    }
+
+    /// Every simple path (no repeated node) from `from` to `to` following
+    /// forward edges, optionally restricted to one `edge_type`, with at most
+    /// `max_depth` hops. Each path is the ordered slug sequence including both
+    /// endpoints.
+    ///
+    /// Paths come out shortest first (breadth-first), so `limit` keeps the
+    /// shortest ones. Returns an empty list when either endpoint is missing
+    /// or nothing connects them within `max_depth`.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["a/1", "a/2", "a/3"] { db.put(s, "{}").unwrap(); }
+    /// db.link("a/1", "a/2", "causes", 1.0);
+    /// db.link("a/2", "a/3", "causes", 1.0);
+    /// db.link("a/1", "a/3", "causes", 1.0);
+    /// let paths = db.paths("a/1", "a/3", Some("causes"), 6, 10);
+    /// assert_eq!(paths, vec![vec!["a/1", "a/3"], vec!["a/1", "a/2", "a/3"]]);
+    /// ```
+    pub fn paths(
+        &self,
+        from: &str,
+        to: &str,
+        edge_type: Option<&str>,
+        max_depth: usize,
+        limit: usize,
+    ) -> Vec<Vec<String>> {
+        use std::collections::VecDeque;
+
+        let (start, end) = (sk_hash(from), sk_hash(to));
+        if limit == 0 || !self.nodes.contains_key(&start) || !self.nodes.contains_key(&end) {
+            return Vec::new();
+        }
+        if start == end {
+            return vec![vec![self.nodes[&start].slug.clone()]];
+        }
+        let type_hash = edge_type.map(sk_hash);
+
+        let mut found: Vec<Vec<u64>> = Vec::new();
+        let mut queue: VecDeque<Vec<u64>> = VecDeque::from([vec![start]]);
+        'bfs: while let Some(path) = queue.pop_front() {
+            if path.len() > max_depth {
+                continue;
+            }
+            let current = path[path.len() - 1];
+            for e in self.edges.fwd_edges(current).into_iter().flatten() {
+                if type_hash.is_some_and(|t| t != e.edge_type) || path.contains(&e.other) {
+                    continue;
+                }
+                let mut next = path.clone();
+                next.push(e.other);
+                if e.other == end {
+                    // Parallel edges of the same hop sequence are one path.
+                    if !found.contains(&next) {
+                        found.push(next);
+                        if found.len() >= limit {
+                            break 'bfs;
+                        }
+                    }
+                } else if self.nodes.contains_key(&e.other) {
+                    queue.push_back(next);
+                    if !budget::frontier_fits(self.frontier_cap(), queue.len()) {
+                        break 'bfs;
+                    }
+                }
+            }
+        }
+
+        found
+            .into_iter()
+            .map(|p| p.iter().filter_map(|h| self.nodes.get(h).map(|n| n.slug.clone())).collect())
+            .collect()
     }
 
     /// Execute a `SHOW` introspection statement.
@@ -3070,6 +7664,7 @@ impl CoreDB {
             slug: String::new(),
             slug_hash: 0,
             payload: Some(payload),
+            score: None,
         };
 
         match stmt {
@@ -3323,6 +7918,34 @@ impl CoreDB {
         }
     }
 
+    /// [`execute_params`](Self::execute_params) on behalf of a caller: `auth`
+    /// sees the statement's [`Access`] and collections before it runs. Inside
+    /// a SQL transaction each statement is checked as it is queued.
+    ///
+    /// # Errors
+    /// [`SqlError::Unauthorized`] when `auth` rejects the statement.
+    ///
+    /// # Example
+    /// ```
+    /// # use sekejap::{Access, CoreDB, SqlError};
+    /// let mut db = CoreDB::new();
+    /// let read_only = |access: Access, _: Option<&[String]>| match access {
+    ///     Access::Read => Ok(()),
+    ///     _ => Err("read-only key".to_string()),
+    /// };
+    /// assert!(matches!(
+    ///     db.execute_as(&read_only, "INSERT INTO users (_key) VALUES ('u1')", &[]),
+    ///     Err(SqlError::Unauthorized(_))
+    /// ));
+    /// ```
+    pub fn execute_as(&mut self, auth: &dyn Authorizer, sql: &str, params: &[Value]) -> Result<usize, SqlError> {
+        let mutation = sql::parse_mutation_params(sql, params.to_vec())?;
+        let (access, collections) = auth::mutation_scope(self, &mutation);
+        auth.authorize(access, collections.as_deref())
+            .map_err(SqlError::Unauthorized)?;
+        self.execute_mutation(mutation)
+    }
+
     /// Internal: execute an already-parsed mutation.
     fn execute_mutation(&mut self, mutation: sql::CompiledMutation) -> Result<usize, SqlError> {
         // ── Transaction control ──────────────────────────────────────
@@ -3368,7 +7991,7 @@ impl CoreDB {
             return Ok(0);
         }
 
-        match mutation {
+        let result = match mutation {
             sql::CompiledMutation::Insert { collection, mut slug, payload_json, mut vectors } => {
                 let payload_json = if let Some(schema) = self.schemas.get(&collection).cloned() {
                     let mut payload: Value = serde_json::from_str(&payload_json)
@@ -3428,8 +8051,7 @@ impl CoreDB {
                 } else {
                     payload_json
                 };
-                self.put(&slug, &payload_json)
-                    .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
+                self.put(&slug, &payload_json).map_err(put_error_to_sql)?;
                 for (field, data) in vectors {
                     self.put_vector(&slug, &field, &data)
                         .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
@@ -3440,6 +8062,8 @@ impl CoreDB {
                 let schema = self.schemas.get(&collection).cloned();
                 let mut affected_vec_fields: std::collections::HashSet<String> = std::collections::HashSet::new();
                 let count = items.len();
+                // Group commit: one fsync for the whole batch.
+                self.defer_wal_sync = true;
                 for (mut slug, payload_json, mut vectors) in items {
                     let payload_json = if let Some(ref schema) = schema {
                         let mut payload: Value = serde_json::from_str(&payload_json)
@@ -3499,8 +8123,12 @@ impl CoreDB {
                     } else {
                         payload_json
                     };
-                    self.put(&slug, &payload_json)
-                        .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
+                    if let Err(e) = self.put(&slug, &payload_json) {
+                        // Rows already written stay; make them durable before bailing.
+                        self.defer_wal_sync = false;
+                        self.wal_flush();
+                        return Err(put_error_to_sql(e));
+                    }
                     // Store vectors without HNSW rebuild — defer to end
                     for (field, data) in vectors {
                         self.wal_write(WalEntry::PutVector {
@@ -3520,9 +8148,11 @@ impl CoreDB {
                         .any(|s| s.indexes.vector.contains(field));
                     if hnsw_declared {
                         let (m, ef) = self.hnsw_params.get(field.as_str()).copied().unwrap_or((16, 200));
-                        let _ = self.build_hnsw_index(field, m, ef);
+                        let _ = self.build_hnsw_raw(field, m, ef, |_, _| true);
                     }
                 }
+                self.defer_wal_sync = false;
+                self.wal_flush();
                 Ok(count)
             }
             sql::CompiledMutation::Delete(steps) => {
@@ -3541,19 +8171,7 @@ impl CoreDB {
                 Ok(count)
             }
             sql::CompiledMutation::InsertEdge(edges) => {
-                let count = edges.len();
-                self.defer_wal_sync = true;
-                for edge in edges {
-                    match edge.props_json {
-                        Some(json) => self
-                            .link_meta(&edge.from, &edge.to, &edge.edge_type, edge.strength, &json)
-                            .map_err(|e| SqlError::InvalidValue(e.to_string()))?,
-                        None => self.link(&edge.from, &edge.to, &edge.edge_type, edge.strength),
-                    }
-                }
-                self.defer_wal_sync = false;
-                self.wal_flush();
-                Ok(count)
+                self.link_many(edges).map_err(|e| SqlError::InvalidValue(e.to_string()))
             }
             sql::CompiledMutation::DeleteEdge(edges) => {
                 let count = edges.len();
@@ -3597,13 +8215,19 @@ impl CoreDB {
             sql::CompiledMutation::Update { steps, updates } => {
                 // Decide: splice fast path (no vector/geo field updates) or full-parse slow path
                 let has_vec = updates.iter().any(|(_, v)| value_as_f32_vec(v).is_some());
-                let has_geo = !has_vec && self.schemas.values().any(|schema| {
-                    updates.iter().any(|(field, _)| {
-                        schema.fields.iter().any(|f| &f.name == field && matches!(f.ty, sql::FieldType::Geo))
-                    })
+                // `geometry` feeds the spatial grid whether or not a schema declares it.
+                let has_geo = !has_vec && (updates.iter().any(|(field, _)| field == "geometry")
+                    || self.schemas.values().any(|schema| {
+                        updates.iter().any(|(field, _)| {
+                            schema.fields.iter().any(|f| &f.name == field && matches!(f.ty, sql::FieldType::Geo))
+                        })
+                    }));
+                // Unique fields are checked per row by put().
+                let has_unique = self.schemas.values().any(|schema| {
+                    updates.iter().any(|(field, _)| schema.indexes.unique.contains(field))
                 });
 
-                if !has_vec && !has_geo {
+                if !has_vec && !has_geo && !has_unique {
                     // ── FAST PATH: byte-level splice (zero serde per row) ──────────
                     let hits: Vec<(String, u64, Vec<u8>)> = Set::from_steps(self, steps)
                         .collect()
@@ -3641,8 +8265,20 @@ impl CoreDB {
                     } else {
                         vec![]
                     };
+                    // Nested-path indexes (`coords.lat`) under an updated field are
+                    // rebuilt after the batch rather than patched per row.
+                    let nested_indexed: Vec<String> = match coll_hash {
+                        Some(ch) => self.field_indexes.keys()
+                            .filter(|(c, f)| *c == ch && updates.iter().any(|(u, _)| {
+                                f.strip_prefix(u.as_str())
+                                    .is_some_and(|rest| rest.starts_with(['.', '[']))
+                            }))
+                            .map(|(_, f)| f.clone())
+                            .collect(),
+                        None => vec![],
+                    };
 
-                    let now = chrono::Utc::now().timestamp_millis();
+                    let now = self.now_millis();
                     let now_bytes = now.to_string().into_bytes();
 
                     self.defer_wal_sync = true;
@@ -3678,7 +8314,8 @@ impl CoreDB {
                         }
 
                         // Payload store first (takes &[u8]), then WAL (takes String)
-                        let (offset, len) = self.payload_store.append(&buf);
+                        let old_slot = self.nodes.get(&hash).map(|n| (n.payload_offset, n.payload_len));
+                        let (offset, len) = self.store_payload(old_slot, &buf);
                         let json_str = String::from_utf8(buf)
                             .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
                         self.wal_write(WalEntry::Put { slug: slug.clone(), payload: json_str });
@@ -3703,6 +8340,18 @@ impl CoreDB {
                         }
                     }
 
+                    for field in &nested_indexed {
+                        self.build_field_index_raw(&coll_name, field);
+                    }
+                    let simhash_updated = self.schemas.get(&coll_name).is_some_and(|s| {
+                        s.indexes.simhash.iter().any(|f| updates.iter().any(|(u, _)| {
+                            f == u || f.strip_prefix(u.as_str()).is_some_and(|rest| rest.starts_with(['.', '[']))
+                        }))
+                    });
+                    if simhash_updated {
+                        self.build_simhash_index(&coll_name);
+                    }
+
                     // Rebuild GIN/BM25 for any updated fulltext fields
                     for (field, _) in &updates {
                         if self.gin_indexes.contains_key(field.as_str()) {
@@ -3759,8 +8408,11 @@ impl CoreDB {
                         }
                         let json = serde_json::to_string(&payload)
                             .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
-                        self.put(&slug, &json)
-                            .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
+                        if let Err(e) = self.put(&slug, &json) {
+                            self.defer_wal_sync = false;
+                            self.wal_flush();
+                            return Err(put_error_to_sql(e));
+                        }
                         for (field, data) in vec_updates {
                             self.wal_write(WalEntry::PutVector {
                                 slug: slug.clone(),
@@ -3778,7 +8430,7 @@ impl CoreDB {
                             .any(|s| s.indexes.vector.contains(field));
                         if hnsw_declared {
                             let (m, ef) = self.hnsw_params.get(field.as_str()).copied().unwrap_or((16, 200));
-                            let _ = self.build_hnsw_index(field, m, ef);
+                            let _ = self.build_hnsw_raw(field, m, ef, |_, _| true);
                         }
                     }
                     self.defer_wal_sync = false;
@@ -3790,7 +8442,7 @@ impl CoreDB {
                 let schema_json = serde_json::to_string(&schema)
                     .map_err(|e| SqlError::InvalidValue(e.to_string()))?;
                 self.wal_write(WalEntry::CreateTable { collection: collection.clone(), schema_json });
-                self.schemas.insert(collection, schema.clone());
+                self.schemas.insert(collection, *schema);
                 Ok(1)
             }
             sql::CompiledMutation::CreateIndex { name: _, collection, method, fields } => {
@@ -3854,7 +8506,13 @@ impl CoreDB {
             sql::CompiledMutation::Begin
             | sql::CompiledMutation::Commit
             | sql::CompiledMutation::Rollback => unreachable!(),
+        };
+        // A batch arm that bailed out early must not leave later writes unsynced.
+        if result.is_err() && self.defer_wal_sync {
+            self.defer_wal_sync = false;
+            self.wal_flush();
         }
+        result
     }
 
     // ── Internal accessors for the query executor ─────────────────────────────
@@ -3930,6 +8588,39 @@ impl CoreDB {
         self.rebuild_spatial_grid();
     }
 
+    /// Compare the spatial grid against the geometry stored on each node.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("p", r#"{"geometry":{"type":"Point","coordinates":[115.26,-8.51]}}"#).unwrap();
+    /// db.build_spatial_index();
+    /// db.put("p", r#"{"geometry":{"type":"Point","coordinates":[115.30,-8.60]}}"#).unwrap();
+    /// let stats = db.spatial_stats();
+    /// assert_eq!((stats.indexed, stats.nodes_with_geometry), (1, 1));
+    /// assert!(stats.is_consistent());
+    /// ```
+    pub fn spatial_stats(&self) -> SpatialStats {
+        let nodes_with_geometry = self.nodes.values().filter(|n| n.spatial_meta.is_some()).count();
+        let Some(grid) = &self.spatial_grid else {
+            return SpatialStats { nodes_with_geometry, ..SpatialStats::default() };
+        };
+        let stale = grid.entries()
+            .filter(|(h, meta)| self.nodes.get(h).and_then(|n| n.spatial_meta.as_ref()) != Some(*meta))
+            .count()
+            + grid.orphan_cell_entries();
+        let missing = self.nodes.iter()
+            .filter(|(h, n)| n.spatial_meta.is_some() && grid.get_meta(**h).is_none())
+            .count();
+        SpatialStats {
+            grid_built: true,
+            nodes_with_geometry,
+            indexed: grid.node_count(),
+            stale,
+            missing,
+        }
+    }
+
     fn rebuild_spatial_grid(&mut self) {
         let items: Vec<(u64, geo::SpatialMeta)> = self.nodes.iter()
             .filter_map(|(&hash, node)| node.spatial_meta.clone().map(|m| (hash, m)))
@@ -4252,6 +8943,43 @@ impl CoreDB {
         Ok(hash)
     }
 
+    /// Bulk-store vectors for `field` without maintaining its HNSW index.
+    ///
+    /// [`put_vector`](Self::put_vector) inserts into a declared HNSW graph
+    /// one vector at a time; for large imports it is much cheaper to stage
+    /// everything here and then call [`build_hnsw_with`](Self::build_hnsw_with)
+    /// once. Until that rebuild, ANN search does not see the staged vectors.
+    /// The WAL is synced once for the whole batch.
+    ///
+    /// Returns the number of vectors stored.
+    pub fn ingest_vectors<S: AsRef<str>>(
+        &mut self,
+        field: &str,
+        items: impl IntoIterator<Item = (S, Vec<f32>)>,
+    ) -> usize {
+        let was_deferred = self.defer_wal_sync;
+        self.defer_wal_sync = true;
+        self.ensure_vector_store(field);
+        let mut count = 0;
+        for (slug, data) in items {
+            let slug = slug.as_ref();
+            self.wal_write(WalEntry::PutVector {
+                slug: slug.to_string(),
+                field: field.to_string(),
+                data: data.clone(),
+            });
+            self.vectors.get_mut(field).unwrap().put(sk_hash(slug), data);
+            count += 1;
+        }
+        #[cfg(unix)]
+        self.vectors.get_mut(field).unwrap().remap();
+        self.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.wal_flush();
+        }
+        count
+    }
+
     /// Retrieve the stored vector for a node under a named field.
     ///
     /// Returns `None` if the node has no vector for that field.
@@ -4271,6 +8999,13 @@ impl CoreDB {
         self.hnsw_indexes.get(field)
     }
 
+    /// Vector fields that currently have an HNSW index, sorted.
+    pub fn hnsw_fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = self.hnsw_indexes.keys().cloned().collect();
+        fields.sort();
+        fields
+    }
+
     /// Ensure a VectorStore exists for `field`. Creates a disk-backed store
     /// when a data directory is configured, otherwise a memory-backed one.
     fn ensure_vector_store(&mut self, field: &str) {
@@ -4291,12 +9026,24 @@ impl CoreDB {
 
     /// Build (or rebuild) a btree field index for a specific collection and field.
     ///
-    /// Scans all collection members and builds an ordered BTreeMap from field
-    /// value → `[node_hash, …]`. Called automatically by
-    /// `CREATE INDEX ON coll(field) USING btree`.
+    /// Same as `CREATE INDEX ON collection USING btree (field)`: the index is
+    /// declared in the collection schema and logged, so it is restored on
+    /// reopen (from the snapshot when one holds it, otherwise rebuilt).
     ///
     /// Incrementally maintained by every subsequent `put()` / `remove()`.
     pub fn build_field_index(&mut self, collection: &str, field: &str) {
+        let fields = vec![field.to_string()];
+        self.wal_write(WalEntry::CreateIndex {
+            collection: collection.to_string(),
+            method: sql::IndexMethod::Btree.to_string(),
+            fields: fields.clone(),
+        });
+        let _ = self.apply_index(collection, &sql::IndexMethod::Btree, &fields);
+    }
+
+    /// Scan all collection members and build an ordered BTreeMap from field
+    /// value → `[node_hash, …]`. Backs both btree and hash indexes.
+    fn build_field_index_raw(&mut self, collection: &str, field: &str) {
         let coll_hash = sk_hash(collection);
         let members: Vec<u64> = self.collections.get(&coll_hash).cloned().unwrap_or_default();
         let mut btree: BTreeMap<FieldKey, Vec<u64>> = BTreeMap::new();
@@ -4304,7 +9051,7 @@ impl CoreDB {
             if let Some(node) = self.nodes.get(&hash) {
                 let payload = self.payload_store.get(node.payload_offset, node.payload_len)
                     .unwrap_or(Value::Null);
-                if let Some(fk) = FieldKey::from_json(payload.get(field).unwrap_or(&Value::Null)) {
+                if let Some(fk) = FieldKey::from_json(&crate::query::json_path_get(field, &payload).unwrap_or(Value::Null)) {
                     btree.entry(fk).or_default().push(hash);
                 }
             }
@@ -4321,7 +9068,8 @@ impl CoreDB {
             let declares = match method {
                 "gin"   => schema.indexes.fulltext.contains(&field.to_string()),
                 "bm25"  => schema.indexes.bm25.contains(&field.to_string()),
-                "btree" => schema.indexes.range.contains(&field.to_string()),
+                "btree" => schema.indexes.range.contains(&field.to_string())
+                    || schema.indexes.hash.contains(&field.to_string()),
                 _       => false,
             };
             if declares {
@@ -4375,144 +9123,51 @@ impl CoreDB {
                         }
                     }
                 }
-                Step::WhereGt(field, lo) => {
+                Step::WhereGt(field, lo) | Step::WhereGte(field, lo) => {
                     if let Some(idx) = self.field_indexes.get(&(coll_hash, field.clone())) {
-                        let fk_lo = FieldKey::from_f64(*lo);
+                        let lower = match step {
+                            Step::WhereGt(..) => Bound::Excluded(*lo),
+                            _ => Bound::Included(*lo),
+                        };
                         // Look ahead: combine with WhereLte/WhereLt on same field into
                         // a single btree range scan, consuming both steps.
                         let upper = remaining[j + 1..].iter().enumerate().find_map(|(k, s)| {
                             match s {
-                                Step::WhereLte(f2, hi) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Included(FieldKey::from_f64(*hi)))),
-                                Step::WhereLt(f2, hi) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Excluded(FieldKey::from_f64(*hi)))),
+                                Step::WhereLte(f2, hi) if f2 == field => Some((j + 1 + k, Bound::Included(*hi))),
+                                Step::WhereLt(f2, hi) if f2 == field => Some((j + 1 + k, Bound::Excluded(*hi))),
                                 _ => None,
                             }
                         });
-                        return if let Some((pair_j, upper_bound)) = upper {
-                            Some((
-                                idx.range((Bound::Excluded(fk_lo), upper_bound))
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                Some(pair_j),
-                            ))
-                        } else {
-                            Some((
-                                idx.range((Bound::Excluded(fk_lo), Bound::Unbounded))
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                None,
-                            ))
-                        };
+                        let (pair_j, upper) = upper.map_or((None, Bound::Unbounded), |(k, b)| (Some(k), b));
+                        return Some((numeric_index_range(idx, lower, upper), j, pair_j));
                     }
                 }
-                Step::WhereLt(field, hi) => {
+                Step::WhereLt(field, hi) | Step::WhereLte(field, hi) => {
                     if let Some(idx) = self.field_indexes.get(&(coll_hash, field.clone())) {
-                        let fk_hi = FieldKey::from_f64(*hi);
+                        let upper = match step {
+                            Step::WhereLt(..) => Bound::Excluded(*hi),
+                            _ => Bound::Included(*hi),
+                        };
                         // Look ahead for lower bound on same field.
                         let lower = remaining[j + 1..].iter().enumerate().find_map(|(k, s)| {
                             match s {
-                                Step::WhereGte(f2, lo) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Included(FieldKey::from_f64(*lo)))),
-                                Step::WhereGt(f2, lo) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Excluded(FieldKey::from_f64(*lo)))),
-                                _ => None,
-                            }
-                        });
-                        return if let Some((pair_j, lower_bound)) = lower {
-                            Some((
-                                idx.range((lower_bound, Bound::Excluded(fk_hi)))
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                Some(pair_j),
-                            ))
-                        } else {
-                            Some((
-                                idx.range(..fk_hi)
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                None,
-                            ))
-                        };
-                    }
-                }
-                Step::WhereGte(field, lo) => {
-                    if let Some(idx) = self.field_indexes.get(&(coll_hash, field.clone())) {
-                        let fk_lo = FieldKey::from_f64(*lo);
-                        let upper = remaining[j + 1..].iter().enumerate().find_map(|(k, s)| {
-                            match s {
-                                Step::WhereLte(f2, hi) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Included(FieldKey::from_f64(*hi)))),
-                                Step::WhereLt(f2, hi) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Excluded(FieldKey::from_f64(*hi)))),
+                                Step::WhereGte(f2, lo) if f2 == field => Some((j + 1 + k, Bound::Included(*lo))),
+                                Step::WhereGt(f2, lo) if f2 == field => Some((j + 1 + k, Bound::Excluded(*lo))),
                                 _ => None,
                             }
                         });
-                        return if let Some((pair_j, upper_bound)) = upper {
-                            Some((
-                                idx.range((Bound::Included(fk_lo), upper_bound))
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                Some(pair_j),
-                            ))
-                        } else {
-                            Some((
-                                idx.range(fk_lo..)
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                None,
-                            ))
-                        };
+                        let (pair_j, lower) = lower.map_or((None, Bound::Unbounded), |(k, b)| (Some(k), b));
+                        return Some((numeric_index_range(idx, lower, upper), j, pair_j));
                     }
                 }
-                Step::WhereLte(field, hi) => {
+                Step::WhereBetween(field, lo, hi) => {
                     if let Some(idx) = self.field_indexes.get(&(coll_hash, field.clone())) {
-                        let fk_hi = FieldKey::from_f64(*hi);
-                        let lower = remaining[j + 1..].iter().enumerate().find_map(|(k, s)| {
-                            match s {
-                                Step::WhereGte(f2, lo) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Included(FieldKey::from_f64(*lo)))),
-                                Step::WhereGt(f2, lo) if f2 == field =>
-                                    Some((j + 1 + k, Bound::Excluded(FieldKey::from_f64(*lo)))),
-                                _ => None,
-                            }
-                        });
-                        return if let Some((pair_j, lower_bound)) = lower {
-                            Some((
-                                idx.range((lower_bound, Bound::Included(fk_hi)))
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                Some(pair_j),
-                            ))
-                        } else {
-                            Some((
-                                idx.range(..=fk_hi)
-                                    .flat_map(|(_, ids)| ids.iter().copied())
-                                    .collect(),
-                                j,
-                                None,
-                            ))
-                        };
+                        return Some((numeric_index_range(idx, Bound::Included(*lo), Bound::Included(*hi)), j, None));
                     }
                 }
-                Step::WhereBetween(field, lo, hi) => {
-                    if let Some(idx) = self.field_indexes.get(&(coll_hash, field.clone())) {
-                        let fk_lo = FieldKey::from_f64(*lo);
-                        let fk_hi = FieldKey::from_f64(*hi);
-                        return Some((
-                            idx.range(fk_lo..=fk_hi)
-                                .flat_map(|(_, ids)| ids.iter().copied())
-                                .collect(),
-                            j,
-                            None,
-                        ));
+                Step::WhereOr(branches) => {
+                    if let Some(ids) = self.or_eq_index_union(coll_hash, branches) {
+                        return Some((ids, j, None));
                     }
                 }
                 _ => {}
@@ -4521,6 +9176,32 @@ impl CoreDB {
         None
     }
 
+    /// Union of btree lookups for an OR whose branches are all single
+    /// `WhereEq` steps on indexed fields (the shape `where_any_of` builds).
+    ///
+    /// Returns `None` if any branch is not an indexed equality — the caller
+    /// then falls back to per-candidate evaluation.
+    pub(crate) fn or_eq_index_union(&self, coll_hash: u64, branches: &[Vec<Step>]) -> Option<Vec<u64>> {
+        if branches.is_empty() {
+            return None;
+        }
+        let mut seen = std::collections::HashSet::new();
+        let mut out = Vec::new();
+        for branch in branches {
+            let [Step::WhereEq(field, value)] = branch.as_slice() else {
+                return None;
+            };
+            let idx = self.field_indexes.get(&(coll_hash, field.clone()))?;
+            let fk = FieldKey::from_json(value)?;
+            for &h in idx.get(&fk).into_iter().flatten() {
+                if seen.insert(h) {
+                    out.push(h);
+                }
+            }
+        }
+        Some(out)
+    }
+
     /// Try to seed the candidate list for a `Collection` step using an ORDER BY index scan.
     ///
     /// Applies when there are **no filter steps** between `Collection` and `Sort`, the
@@ -4554,9 +9235,7 @@ impl CoreDB {
         let idx = self.field_indexes.get(&(coll_hash, field.clone()))?;
 
         // Look ahead for a Take limit — enables O(k) extraction instead of O(N)
-        let take_n = remaining[sort_pos + 1..]
-            .iter()
-            .find_map(|s| if let Step::Take(n) = s { Some(*n) } else { None });
+        let take_n = crate::query::find_sort_limit(&remaining[sort_pos + 1..]);
 
         let result: Vec<u64> = if *asc {
             idx.values().flat_map(|ids| ids.iter().copied()).collect()
@@ -4588,6 +9267,67 @@ impl CoreDB {
         field: &str,
         m: usize,
         ef_construction: usize,
+    ) -> Result<(), String> {
+        self.build_hnsw_with(field, m, ef_construction, |_, _| {})
+    }
+
+    /// Like [`build_hnsw_index`](Self::build_hnsw_index), reporting
+    /// `progress(done, total)` after each vector is inserted. Pair with
+    /// [`ingest_vectors`](Self::ingest_vectors) to build once after a bulk
+    /// load instead of incrementally per vector.
+    ///
+    /// Runs as a [`JobKind::HnswBuild`] job; cancelling it keeps the
+    /// previous index, if any, and returns an error.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.ingest_vectors("emb", (0..4).map(|i| (format!("v{i}"), vec![i as f32, 1.0])));
+    /// let mut last = (0, 0);
+    /// db.build_hnsw_with("emb", 16, 100, |done, total| last = (done, total)).unwrap();
+    /// assert_eq!(last, (4, 4));
+    /// ```
+    pub fn build_hnsw_with(
+        &mut self,
+        field: &str,
+        m: usize,
+        ef_construction: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), String> {
+        let jobs = self.jobs.clone();
+        jobs.run(JobKind::HnswBuild, field.to_string(), |job| {
+            self.build_hnsw_in(field, m, ef_construction, job, &mut progress)
+        })?;
+        // Logged so the index survives a crash before compact() and travels
+        // with exports, even when no schema declares it.
+        self.wal_write(WalEntry::BuildHnsw { field: field.to_string(), m, ef_construction });
+        Ok(())
+    }
+
+    /// [`build_hnsw_raw`](Self::build_hnsw_raw) reporting to `job` and
+    /// stopping when it is cancelled.
+    fn build_hnsw_in(
+        &mut self,
+        field: &str,
+        m: usize,
+        ef_construction: usize,
+        job: &Job,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), String> {
+        self.build_hnsw_raw(field, m, ef_construction, |done, total| {
+            progress(done, total);
+            job.checkpoint(done as u64, total as u64).is_ok()
+        })
+    }
+
+    /// Build into a local graph and swap it in, unless `progress` returns
+    /// `false` first.
+    fn build_hnsw_raw(
+        &mut self,
+        field: &str,
+        m: usize,
+        ef_construction: usize,
+        progress: impl FnMut(usize, usize) -> bool,
     ) -> Result<(), String> {
         // Ensure mmap covers any recently-appended vectors.
         #[cfg(unix)]
@@ -4600,8 +9340,10 @@ impl CoreDB {
             .ok_or_else(|| format!("no vectors stored for field '{field}'"))?;
 
         // Build entirely into a local — zero writes to self until this line.
-        let graph =
-            vector::HnswGraph::build::<CosineDistance, _>(field_vecs, m, ef_construction);
+        let graph = vector::HnswGraph::build_until::<CosineDistance, _>(
+            field_vecs, m, ef_construction, progress,
+        )
+        .ok_or("job cancelled")?;
 
         // Atomic replace: old index (if any) is dropped here.
         self.hnsw_indexes.insert(field.to_string(), graph);
@@ -4643,6 +9385,7 @@ impl CoreDB {
                     IndexMethod::Gin | IndexMethod::Gist => &mut schema.indexes.fulltext,
                     IndexMethod::Btree   => &mut schema.indexes.range,
                     IndexMethod::Hash    => &mut schema.indexes.hash,
+                    IndexMethod::SimHash => &mut schema.indexes.simhash,
                     IndexMethod::Search  => unreachable!(),
                 };
                 if !list.contains(field) {
@@ -4666,7 +9409,14 @@ impl CoreDB {
             IndexMethod::Hnsw => {
                 if !self.replaying {
                     for field in fields {
-                        let _ = self.build_hnsw_index(field, 16, 200);
+                        // Nothing to build (or to report as a job) before
+                        // the field has vectors.
+                        if self.vectors.contains_key(field.as_str()) {
+                            let jobs = self.jobs.clone();
+                            let _ = jobs.run(JobKind::HnswBuild, field.clone(), |job| {
+                                self.build_hnsw_in(field, 16, 200, job, |_, _| {})
+                            });
+                        }
                     }
                 }
             }
@@ -4696,12 +9446,12 @@ impl CoreDB {
             }
             IndexMethod::Btree => {
                 for field in fields {
-                    self.build_field_index(collection, field);
+                    self.build_field_index_raw(collection, field);
                 }
             }
             IndexMethod::Hash => {
                 for field in fields {
-                    self.build_field_index(collection, field);
+                    self.build_field_index_raw(collection, field);
                 }
             }
             IndexMethod::Search => {
@@ -4709,11 +9459,58 @@ impl CoreDB {
                     self.build_search_index(collection, fields);
                 }
             }
+            IndexMethod::SimHash => {
+                self.build_simhash_index(collection);
+            }
         }
 
         Ok(())
     }
 
+    /// SimHash fingerprint of `payload` over `collection`'s declared simhash
+    /// fields, or `None` if none are declared or they hold no tokens.
+    fn simhash_of(&self, collection: &str, payload: &Value) -> Option<u64> {
+        let fields = &self.schemas.get(collection)?.indexes.simhash;
+        let mut tokens = Vec::new();
+        for f in fields {
+            if let Some(Value::String(text)) = crate::query::json_path_get(f, payload) {
+                tokens.extend(bm25::tokenize(&text));
+            }
+        }
+        if tokens.is_empty() {
+            return None;
+        }
+        Some(sketch::simhash(tokens.iter().map(String::as_str)))
+    }
+
+    /// Recompute the fingerprints of every node in `collection`.
+    fn build_simhash_index(&mut self, collection: &str) {
+        let members = self.collection_members(sk_hash(collection)).cloned().unwrap_or_default();
+        for h in members {
+            let fp = self.get_payload(h).and_then(|p| self.simhash_of(collection, &p));
+            match fp {
+                Some(fp) => { self.simhashes.insert(h, fp); }
+                None => { self.simhashes.remove(&h); }
+            }
+        }
+    }
+
+    /// Rebuild fingerprints for every collection that declares simhash
+    /// fields. Fingerprints are not persisted, so open() always calls this.
+    fn rebuild_declared_simhash_indexes(&mut self) {
+        let collections: Vec<String> = self.schemas.values()
+            .filter(|s| !s.indexes.simhash.is_empty())
+            .map(|s| s.collection.clone())
+            .collect();
+        for c in collections {
+            self.build_simhash_index(&c);
+        }
+    }
+
+    pub(crate) fn simhash_fingerprint(&self, hash: u64) -> Option<u64> {
+        self.simhashes.get(&hash).copied()
+    }
+
     /// Rebuild all declared GIN indexes from all currently loaded nodes.
     /// Rebuild all declared BM25 indexes from current data.
     ///
@@ -4857,6 +9654,19 @@ impl CoreDB {
     ///
     /// Called after WAL replay in `open()` so that vectors written after the
     /// original `CREATE INDEX` are incorporated.
+    /// Build HNSW indexes that were requested with `build_hnsw_index` (so
+    /// have parameters) but are not loaded, e.g. after WAL replay.
+    fn build_missing_hnsw_indexes(&mut self) {
+        let mut missing: Vec<(String, usize, usize)> = self.hnsw_params.iter()
+            .filter(|(f, _)| !self.hnsw_indexes.contains_key(*f))
+            .map(|(f, &(m, ef))| (f.clone(), m, ef))
+            .collect();
+        missing.sort();
+        for (field, m, ef) in missing {
+            let _ = self.build_hnsw_raw(&field, m, ef, |_, _| true);
+        }
+    }
+
     fn rebuild_declared_hnsw_indexes(&mut self) {
         let params: Vec<(String, usize, usize)> = {
             let mut seen = std::collections::HashSet::new();
@@ -4870,7 +9680,7 @@ impl CoreDB {
                 .collect()
         };
         for (field, m, ef) in params {
-            let _ = self.build_hnsw_index(&field, m, ef);
+            let _ = self.build_hnsw_raw(&field, m, ef, |_, _| true);
         }
     }
 }
@@ -4936,6 +9746,39 @@ fn validate_updates_against_schema(
     None
 }
 
+/// Number of steps in a pipeline, including those in nested sub-pipelines.
+fn step_count(steps: &[Step]) -> usize {
+    steps.iter().map(|s| 1 + match s {
+        Step::Intersect(v) | Step::Union(v) | Step::Subtract(v) | Step::HopFilter(v) | Step::Having(v) => step_count(v),
+        Step::WhereOr(branches) => branches.iter().map(|b| step_count(b)).sum(),
+        Step::WhereNot(inner) => step_count(std::slice::from_ref(inner.as_ref())),
+        _ => 0,
+    }).sum()
+}
+
+/// Returns true for steps that only drop rows based on the row's own payload.
+fn is_row_filter(s: &Step) -> bool {
+    matches!(
+        s,
+        Step::WhereEq(..)
+            | Step::WhereNeq(..)
+            | Step::WhereGt(..)
+            | Step::WhereLt(..)
+            | Step::WhereGte(..)
+            | Step::WhereLte(..)
+            | Step::WhereBetween(..)
+            | Step::WhereIn(..)
+            | Step::ArrayContains(..)
+            | Step::Like(..)
+            | Step::WhereContains(..)
+            | Step::WhereStartsWith(..)
+            | Step::WhereRegex(..)
+            | Step::WhereIsNull(..)
+            | Step::WhereNot(..)
+            | Step::WhereOr(..)
+    )
+}
+
 /// Returns true for any step that narrows, reorders, or re-sources the candidate list.
 /// Used to detect whether a btree ORDER BY index scan is safe for `Collection → Sort`.
 fn is_filter_or_traversal(s: &Step) -> bool {
@@ -4950,17 +9793,24 @@ fn is_filter_or_traversal(s: &Step) -> bool {
             | Step::WhereBetween(..)
             | Step::WhereIn(..)
             | Step::Like(..)
+            | Step::WhereContains(..)
+            | Step::WhereStartsWith(..)
+            | Step::WhereRegex(..)
             | Step::WhereNot(..)
             | Step::WhereOr(..)
             | Step::WhereIsNull(..)
             | Step::Forward(..)
             | Step::Backward(..)
+            | Step::Both(..)
+            | Step::EdgeWindow(..)
+            | Step::HopFilter(..)
             | Step::Hops(..)
             | Step::HopsTyped { .. }
             | Step::MinStrength(..)
             | Step::Leaves
             | Step::Roots
             | Step::StDWithin(..)
+            | Step::NearNode(..)
             | Step::StContainsPoint(..)
             | Step::StWithin(..)
             | Step::StContains(..)
@@ -4969,10 +9819,12 @@ fn is_filter_or_traversal(s: &Step) -> bool {
             | Step::StLength(..)
             | Step::StArea(..)
             | Step::VectorNear { .. }
+            | Step::Hybrid { .. }
             | Step::Bm25Filter(..)
             | Step::Intersect(..)
             | Step::Union(..)
             | Step::Subtract(..)
+            | Step::TopPerGroup { .. }
     )
 }
 
@@ -5016,13 +9868,57 @@ impl CoreDB {
 }
 
 impl<'db> Transaction<'db> {
-    /// Queue a node insert/update. Validates JSON immediately; returns error on bad JSON.
+    /// Queue a node insert/update. Validates JSON immediately; returns error on
+    /// bad JSON or a payload that is not an object, so `commit` cannot fail halfway.
     pub fn put(&mut self, slug: &str, payload_json: &str) -> Result<(), serde_json::Error> {
-        serde_json::from_str::<Value>(payload_json)?;
+        if !serde_json::from_str::<Value>(payload_json)?.is_object() {
+            return Err(serde_json::Error::io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "payload must be a JSON object",
+            )));
+        }
         self.ops.push(TxnOp::Put(slug.to_string(), payload_json.to_string()));
         Ok(())
     }
 
+    /// Read a node as this transaction sees it: the latest queued `put` or
+    /// `remove` for `slug` wins, otherwise the committed payload is returned.
+    ///
+    /// Queued payloads are returned as written (no `_created_unix` /
+    /// `_updated_unix` until commit).
+    pub fn get(&self, slug: &str) -> Option<String> {
+        for op in self.ops.iter().rev() {
+            match op {
+                TxnOp::Put(s, json) if s == slug => return Some(json.clone()),
+                TxnOp::Remove(s) if s == slug => return None,
+                _ => {}
+            }
+        }
+        self.db.get(slug)
+    }
+
+    /// Whether `slug` exists as this transaction sees it. See [`get`](Self::get).
+    pub fn contains(&self, slug: &str) -> bool {
+        for op in self.ops.iter().rev() {
+            match op {
+                TxnOp::Put(s, _) if s == slug => return true,
+                TxnOp::Remove(s) if s == slug => return false,
+                _ => {}
+            }
+        }
+        self.db.contains(slug)
+    }
+
+    /// Number of queued operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// `true` if nothing has been queued.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
     /// Queue a node removal.
     pub fn remove(&mut self, slug: &str) {
         self.ops.push(TxnOp::Remove(slug.to_string()));
@@ -5068,20 +9964,31 @@ impl<'db> Transaction<'db> {
     /// Returns the number of operations committed.
     ///
     /// # Errors
-    /// Only fails if a queued `Put` payload is invalid JSON (shouldn't happen if
-    /// the `put()` helper was used, since it validates eagerly).
+    /// A put that would duplicate a schema `unique` field, of a stored node
+    /// or of another put in the batch (see [`UniqueViolation::from_put_error`]),
+    /// or a batch that would take a collection past its [`Quota`] (see
+    /// [`QuotaExceeded::from_put_error`]). Nothing is applied then.
     pub fn commit(self) -> Result<usize, serde_json::Error> {
+        self.db.check_txn(&self.ops)?;
         let count = self.ops.len();
+        let now = self.db.now_millis();
+        // Creation time of the edge each link op replaced (upsert), per op.
+        let mut replaced: Vec<Option<i64>> = vec![None; count];
         // Apply all ops to in-memory store in order
-        for op in &self.ops {
+        for (i, op) in self.ops.iter().enumerate() {
             match op {
                 TxnOp::Put(slug, json) => { self.db.put_raw(slug, json)?; }
                 TxnOp::Remove(slug) => { self.db.remove_raw(slug); }
                 TxnOp::Link(from, to, et, strength) => {
-                    self.db.link_raw(from, to, et, *strength);
+                    replaced[i] = self.db.edge_to_replace(from, to, et);
+                    if replaced[i].is_some() { self.db.unlink_raw(from, to, et); }
+                    self.db.link_raw(from, to, et, *strength, replaced[i].unwrap_or(now));
                 }
                 TxnOp::LinkMeta(from, to, et, strength, meta) => {
-                    self.db.link_meta_raw(from, to, et, *strength, meta)?;
+                    self.db.check_edge_meta_capacity(meta)?;
+                    replaced[i] = self.db.edge_to_replace(from, to, et);
+                    if replaced[i].is_some() { self.db.unlink_raw(from, to, et); }
+                    self.db.link_meta_raw(from, to, et, *strength, replaced[i].unwrap_or(now), meta)?;
                 }
                 TxnOp::Unlink(from, to, et) => { self.db.unlink_raw(from, to, et); }
                 TxnOp::PutVector(slug, field, data) => {
@@ -5091,8 +9998,20 @@ impl<'db> Transaction<'db> {
                 }
             }
         }
-        // Write all ops to WAL in one sequential batch
-        for op in self.ops {
+        // Write all ops to WAL in one sequential batch, framed so that replay
+        // applies the group together or not at all.
+        let was_deferred = self.db.defer_wal_sync;
+        self.db.defer_wal_sync = true;
+        self.db.wal_write(WalEntry::TxnBegin);
+        for (op, replaced) in self.ops.into_iter().zip(replaced) {
+            if let (TxnOp::Link(from, to, edge_type, _) | TxnOp::LinkMeta(from, to, edge_type, ..), Some(_)) =
+                (&op, replaced)
+            {
+                self.db.wal_write(WalEntry::Unlink {
+                    from: from.clone(), to: to.clone(), edge_type: edge_type.clone(),
+                });
+            }
+            let created_unix = Some(replaced.unwrap_or(now));
             match op {
                 TxnOp::Put(slug, payload) => {
                     self.db.wal_write(WalEntry::Put { slug, payload });
@@ -5101,10 +10020,14 @@ impl<'db> Transaction<'db> {
                     self.db.wal_write(WalEntry::Remove { slug });
                 }
                 TxnOp::Link(from, to, edge_type, strength) => {
-                    self.db.wal_write(WalEntry::Link { from, to, edge_type, strength });
+                    self.db.wal_write(WalEntry::Link {
+                        from, to, edge_type, strength, created_unix,
+                    });
                 }
                 TxnOp::LinkMeta(from, to, edge_type, strength, meta) => {
-                    self.db.wal_write(WalEntry::LinkMeta { from, to, edge_type, strength, meta });
+                    self.db.wal_write(WalEntry::LinkMeta {
+                        from, to, edge_type, strength, meta, created_unix,
+                    });
                 }
                 TxnOp::Unlink(from, to, edge_type) => {
                     self.db.wal_write(WalEntry::Unlink { from, to, edge_type });
@@ -5114,6 +10037,11 @@ impl<'db> Transaction<'db> {
                 }
             }
         }
+        self.db.wal_write(WalEntry::TxnEnd);
+        self.db.defer_wal_sync = was_deferred;
+        if !was_deferred {
+            self.db.wal_flush();
+        }
         Ok(count)
     }
 
@@ -5191,6 +10119,81 @@ impl CoreDB {
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Accumulates an [`ExportManifest`] over exported lines; shared by export
+/// and import so both sides compute it identically.
+#[derive(Default)]
+struct ManifestBuilder {
+    manifest: ExportManifest,
+    hasher: crc32fast::Hasher,
+    collections: BTreeMap<String, (usize, crc32fast::Hasher)>,
+}
+
+impl ManifestBuilder {
+    fn add(&mut self, line: &str, entry: &WalEntry) {
+        self.manifest.records += 1;
+        self.hasher.update(line.as_bytes());
+        self.hasher.update(b"\n");
+        match entry {
+            WalEntry::Put { payload, .. } => {
+                self.manifest.nodes += 1;
+                let collection = serde_json::from_str::<Value>(payload).ok()
+                    .and_then(|p| p.get("_collection").and_then(|c| c.as_str()).map(str::to_string));
+                if let Some(name) = collection {
+                    let (nodes, hasher) = self.collections.entry(name).or_default();
+                    *nodes += 1;
+                    hasher.update(line.as_bytes());
+                    hasher.update(b"\n");
+                }
+            }
+            WalEntry::Link { .. } | WalEntry::LinkMeta { .. } => self.manifest.edges += 1,
+            WalEntry::PutVector { .. } => self.manifest.vectors += 1,
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> ExportManifest {
+        let mut manifest = self.manifest;
+        manifest.checksum = self.hasher.finalize();
+        manifest.collections = self.collections.into_iter()
+            .map(|(name, (nodes, hasher))| (name, CollectionDigest { nodes, checksum: hasher.finalize() }))
+            .collect();
+        manifest
+    }
+}
+
+/// Refuse SQL text longer than `limits` allows.
+fn check_sql_len(sql: &str, limits: &QueryLimits) -> Result<(), SqlError> {
+    match limits.max_sql_len {
+        Some(max) if sql.len() > max => Err(SqlError::LimitExceeded(format!(
+            "SQL text is {} bytes, limit is {max}", sql.len()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Parse a read query, refusing SQL text longer than `limits` allows.
+fn parse_query_within(sql: &str, params: &[Value], limits: &QueryLimits) -> Result<sql::MatchOrAgg, SqlError> {
+    check_sql_len(sql, limits)?;
+    if params.is_empty() {
+        sql::parse_match_or_agg(sql)
+    } else {
+        sql::parse_match_or_agg_params(sql, params.to_vec())
+    }
+}
+
+/// Map a [`CoreDB::put`] error onto [`SqlError`], keeping quota and unique
+/// constraint errors typed.
+fn put_error_to_sql(err: serde_json::Error) -> SqlError {
+    let err = match QuotaExceeded::from_put_error(err) {
+        Ok(q) => return SqlError::QuotaExceeded(q),
+        Err(e) => e,
+    };
+    match UniqueViolation::from_put_error(err) {
+        Ok(u) => SqlError::UniqueViolation(u),
+        Err(e) => SqlError::InvalidValue(e.to_string()),
+    }
+}
+
 /// If a `serde_json::Value::Array` contains only numbers, return them as `Vec<f32>`.
 /// Used by the SQL executor to detect vector literals in INSERT/UPDATE values.
 fn value_as_f32_vec(v: &Value) -> Option<Vec<f32>> {
@@ -5203,6 +10206,58 @@ fn value_as_f32_vec(v: &Value) -> Option<Vec<f32>> {
         .collect()
 }
 
+/// Combine a stored payload with an incoming one for [`CoreDB::put_merge`].
+fn merge_payloads(existing: Value, incoming: Value, strategy: &MergeStrategy) -> Value {
+    match strategy {
+        MergeStrategy::Shallow => shallow_merge(existing, incoming),
+        MergeStrategy::Deep => deep_merge(existing, incoming, false),
+        MergeStrategy::ArrayUnion => deep_merge(existing, incoming, true),
+        MergeStrategy::LatestTimestamp(field) => {
+            let ts = |v: &Value| v.get(field.as_str()).and_then(scalar::timestamp_from_value);
+            if ts(&incoming) >= ts(&existing) {
+                shallow_merge(existing, incoming)
+            } else {
+                shallow_merge(incoming, existing)
+            }
+        }
+    }
+}
+
+/// `over`'s top-level keys replace `base`'s. Non-object inputs: `over` wins.
+fn shallow_merge(base: Value, over: Value) -> Value {
+    match (base, over) {
+        (Value::Object(mut b), Value::Object(o)) => {
+            b.extend(o);
+            Value::Object(b)
+        }
+        (_, over) => over,
+    }
+}
+
+fn deep_merge(base: Value, over: Value, union_arrays: bool) -> Value {
+    match (base, over) {
+        (Value::Object(mut b), Value::Object(o)) => {
+            for (k, v) in o {
+                let merged = match b.remove(&k) {
+                    Some(prev) => deep_merge(prev, v, union_arrays),
+                    None => v,
+                };
+                b.insert(k, merged);
+            }
+            Value::Object(b)
+        }
+        (Value::Array(mut b), Value::Array(o)) if union_arrays => {
+            for v in o {
+                if !b.contains(&v) {
+                    b.push(v);
+                }
+            }
+            Value::Array(b)
+        }
+        (_, over) => over,
+    }
+}
+
 // ── Snapshot format ───────────────────────────────────────────────────────────
 
 /// Serde visitor that tokenizes and discards any JSON value without allocating.
@@ -5244,6 +10299,28 @@ struct Snapshot {
     /// to be rebuilt by scanning payloads.bin on every open.
     #[serde(skip_serializing_if = "Option::is_none")]
     btree_indexes: Option<Vec<SnapBtree>>,
+    /// Soft-deleted nodes awaiting restore or purge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trash: Option<Vec<TrashedNode>>,
+    /// Implicit collection filters: (collection, WHERE text).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection_filters: Option<Vec<(String, String)>>,
+    /// Materialised edge aggregates: (collection, field, aggregate).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    edge_aggregates: Option<Vec<(String, String, EdgeAggregate)>>,
+    /// Projection profiles: (collection, profile, fields left out).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    projection_profiles: Option<Vec<(String, String, Vec<String>)>>,
+    /// Retained prior node versions; see `CoreDB::history`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<Vec<SnapHistory>>,
+    /// Incremental-backup change tracking; see `CoreDB::backup_incremental`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    change_log: Option<ChangeLog>,
+    /// Set by `compact()`: edges live in `edges.bin` (and the spatial grid in
+    /// `spatial.bin`) stamped with this generation, and `edges` is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sidecar_gen: Option<u64>,
     /// Legacy field written by older builds — never serialised, silently consumed
     /// during deserialisation to avoid allocating a multi-GB serde_json Value.
     #[serde(default, skip_serializing)]
@@ -5264,6 +10341,17 @@ struct SnapHnsw {
 fn default_hnsw_m()  -> usize { 16 }
 fn default_hnsw_ef() -> usize { 200 }
 
+/// Retained versions of one node, newest first. Disk-backed snapshots point
+/// into payloads.bin (`slots`); in-memory ones carry the `payloads`.
+#[derive(Serialize, Deserialize)]
+struct SnapHistory {
+    slug: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slots: Vec<(u64, u32)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    payloads: Vec<Value>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct SnapNode {
     slug: String,
@@ -5287,6 +10375,8 @@ struct SnapEdge {
     to: String,
     edge_type: String,
     strength: f32,
+    #[serde(default)]
+    created_unix: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     meta: Option<Value>,
 }
@@ -1,5 +1,6 @@
 //! Chainable query builder and executor.
 
+use crate::budget::{self, Budget, CancelToken, Interrupted, QueryError};
 use crate::{sk_hash, CoreDB, FieldKey};
 use crate::vector::VectorAccess;
 use serde_json::Value;
@@ -14,6 +15,126 @@ pub struct Hit {
     pub slug_hash: u64,
     /// Full payload, or projected subset if `.select()` was used.
     pub payload: Option<Value>,
+    /// What the last scoring step said about this node: cosine distance for
+    /// [`Set::vector_near`] (lower is nearer), the BM25 score for BM25
+    /// filters and sorts, the fused score for [`Set::hybrid`] (higher is
+    /// better), km from the point for spatial radius and distance filters.
+    /// `None` when no step scored it, or for computed rows.
+    pub score: Option<f32>,
+}
+
+/// Shape of the JSON object built by [`Hit::to_json`]. Bindings use it so
+/// every language returns the same response shape.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct HitJsonOptions {
+    /// Include `"slug"`.
+    pub slug: bool,
+    /// Include `"payload"`.
+    pub payload: bool,
+    /// Emit the payload as a nested object; `false` emits it as a JSON string.
+    pub payload_as_object: bool,
+    /// Include `"score"` for hits that have a [`Hit::score`].
+    pub score: bool,
+    /// Payload fields copied to the top level under the same name — e.g.
+    /// `score` from `SELECT …, VECTOR_COSINE(…) AS score` or `_depth` from a
+    /// bound edge. Dotted paths are allowed; missing fields are left out.
+    pub lift: Vec<String>,
+}
+
+impl Default for HitJsonOptions {
+    fn default() -> Self {
+        Self { slug: true, payload: true, payload_as_object: true, score: true, lift: Vec::new() }
+    }
+}
+
+impl Hit {
+    /// Serialise to a JSON object shaped by `options`. The defaults give
+    /// `{"slug": …, "payload": {…}}`.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, HitJsonOptions};
+    /// let mut db = CoreDB::new();
+    /// db.put("p/1", r#"{"name":"Ani","rank":2}"#).unwrap();
+    /// let hit = db.one("p/1").collect().remove(0);
+    /// assert_eq!(hit.to_json(&HitJsonOptions::default())["payload"]["rank"], 2);
+    /// let opts = HitJsonOptions { payload: false, lift: vec!["rank".into()], ..Default::default() };
+    /// assert_eq!(hit.to_json(&opts), serde_json::json!({"slug":"p/1","rank":2}));
+    /// ```
+    pub fn to_json(&self, options: &HitJsonOptions) -> Value {
+        let mut obj = serde_json::Map::new();
+        if options.slug {
+            obj.insert("slug".into(), Value::String(self.slug.clone()));
+        }
+        if options.payload {
+            let payload = match &self.payload {
+                Some(p) if !options.payload_as_object => Value::String(p.to_string()),
+                Some(p) => p.clone(),
+                None => Value::Null,
+            };
+            obj.insert("payload".into(), payload);
+        }
+        if let Some(score) = self.score.filter(|_| options.score) {
+            obj.insert("score".into(), serde_json::json!(score));
+        }
+        if let Some(p) = &self.payload {
+            for field in &options.lift {
+                if let Some(v) = resolve_field(field, p) {
+                    obj.insert(field.clone(), v);
+                }
+            }
+        }
+        Value::Object(obj)
+    }
+
+    /// Decode the payload into a caller-defined type.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// #[derive(serde::Deserialize)]
+    /// struct Person { name: String, rank: u32 }
+    ///
+    /// let mut db = CoreDB::new();
+    /// db.put("p/1", r#"{"name":"Ani","rank":2}"#).unwrap();
+    /// let p: Person = db.one("p/1").first().unwrap().deserialize_into().unwrap();
+    /// assert_eq!((p.name.as_str(), p.rank), ("Ani", 2));
+    /// ```
+    pub fn deserialize_into<T: serde::de::DeserializeOwned>(&self) -> Result<T, HitDecodeError> {
+        T::deserialize(self.payload.as_ref().unwrap_or(&Value::Null)).map_err(|error| HitDecodeError {
+            slug: self.slug.clone(),
+            target: std::any::type_name::<T>(),
+            error,
+        })
+    }
+}
+
+/// Which node a payload decoded by [`Set::collect_as_with_meta`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HitMeta {
+    pub slug: String,
+    pub slug_hash: u64,
+}
+
+/// A payload that does not fit the type asked for by
+/// [`Hit::deserialize_into`] or [`Set::collect_as`].
+#[derive(Debug)]
+pub struct HitDecodeError {
+    pub slug: String,
+    /// Name of the target type.
+    pub target: &'static str,
+    pub error: serde_json::Error,
+}
+
+impl std::fmt::Display for HitDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot decode `{}` as {}: {}", self.slug, self.target, self.error)
+    }
+}
+
+impl std::error::Error for HitDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
 // ── VecMetric ─────────────────────────────────────────────────────────────────
@@ -31,6 +152,26 @@ pub enum VecMetric {
     L1,
 }
 
+// ── Fusion ────────────────────────────────────────────────────────────────────
+
+/// How [`Set::hybrid`] merges its vector and keyword rankings into one score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fusion {
+    /// Reciprocal rank fusion: a node gains `1 / (k + rank)` from each list
+    /// it appears in, ranks counted from 1. Needs no score calibration;
+    /// `k = 60` is the usual constant.
+    Rrf { k: f32 },
+    /// `vector * (1 - cosine distance) + (1 - vector) * bm25 / best bm25`,
+    /// with `vector` the weight of the vector side in `0.0..=1.0`.
+    Weighted { vector: f32 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf { k: 60.0 }
+    }
+}
+
 // ── ScoreExpr ─────────────────────────────────────────────────────────────────
 
 /// An arithmetic score expression used in `ORDER BY` scoring.
@@ -98,6 +239,11 @@ pub enum Step {
     Forward(u64),
     /// Follow incoming edges of the given type.
     Backward(u64),
+    /// Follow edges of the given type in either direction (undirected).
+    Both(u64),
+    /// Restrict the preceding `Forward` / `Backward` / `Both` to edges created
+    /// in `since..=until` (unix milliseconds).
+    EdgeWindow(i64, i64),
     /// BFS up to N hops forward over any edge type.
     Hops(u32),
     /// Typed BFS: follow only edges matching `type_hash`, collect at depths `min..=max`.
@@ -106,6 +252,10 @@ pub enum Step {
         min_depth: u32,
         max_depth: u32,
     },
+    /// Node predicate (a list of filter steps, all must pass) applied to every
+    /// node the preceding traversal reaches. After `Hops` / `HopsTyped` it
+    /// prunes the BFS, so nodes failing it are neither returned nor expanded.
+    HopFilter(Vec<Step>),
     /// Filter: only traverse edges whose strength >= threshold (applied after Forward/Backward).
     MinStrength(f32),
     /// Keep only nodes with no outgoing edges.
@@ -126,10 +276,19 @@ pub enum Step {
     ArrayContains(String, Vec<Value>),
     /// Substring match. Third param: `true` = case-insensitive (ILIKE).
     Like(String, String, bool),
+    /// Literal substring match (no wildcards). Field `_slug` matches the node slug.
+    WhereContains(String, String),
+    /// Literal prefix match. Field `_slug` matches the node slug.
+    WhereStartsWith(String, String),
+    /// Regular-expression match (`regex` crate syntax), compiled once when the step is built.
+    WhereRegex(String, regex::Regex),
 
     // ── Spatial filters ───────────────────────────────────────────────────
     /// Centroid within `distance_km` of `(lat, lon)`. Uses Haversine.
     StDWithin(f64, f64, f64),
+    /// Centroid within `distance_km` of the anchor node's centroid, anchor
+    /// excluded. Matches nothing when the anchor is missing or has no geometry.
+    NearNode(u64, f64),
     /// Node geometry contains query point (reverse geocoding).
     StContainsPoint(f64, f64),
     /// Node geometry completely within query polygon. Ring: `[[lat, lon], ...]`.
@@ -163,6 +322,18 @@ pub enum Step {
     /// Add score projection columns to result (expr, alias).
     ScoreProject(Vec<(ScoreExpr, String)>),
 
+    // ── Hybrid vector + keyword ───────────────────────────────────────────
+    /// Top-k by the [`Fusion`] of cosine nearness on `vector_field` and
+    /// BM25 relevance on `text_field`, best first.
+    Hybrid {
+        vector_field: String,
+        query: Vec<f32>,
+        text_field: String,
+        text: String,
+        k: usize,
+        fusion: Fusion,
+    },
+
     // ── Null / logical ────────────────────────────────────────────────────────
     /// `field IS NULL` (negated=false) or `IS NOT NULL` (negated=true).
     WhereIsNull(String, bool),
@@ -194,6 +365,9 @@ pub enum Step {
     /// `ascending = false` (default for scores): highest score → first result.
     /// `ascending = true`: lowest score → first result.
     SortByExpr { expr: ScoreExpr, ascending: bool },
+    /// Keep the `n` nodes with the highest `sort_field` within each distinct
+    /// `group_field` value. Groups appear in first-seen order.
+    TopPerGroup { group_field: String, sort_field: String, n: usize },
     Skip(usize),
     Take(usize),
     /// Project only these fields in the returned payload.
@@ -213,6 +387,9 @@ pub fn describe_step(step: &Step, db: &CoreDB) -> serde_json::Map<String, Value>
         Step::All => ("Seq Scan", "all nodes".into()),
         Step::Forward(h) => ("Forward", format!("edge type {h}")),
         Step::Backward(h) => ("Backward", format!("edge type {h}")),
+        Step::Both(h) => ("Both", format!("edge type {h}")),
+        Step::EdgeWindow(since, until) => ("Edge Filter", format!("created {since}..={until}")),
+        Step::HopFilter(pred) => ("Hop Filter", format!("{} predicate(s) per hop", pred.len())),
         Step::Hops(n) => ("BFS", format!("up to {n} hops")),
         Step::HopsTyped { type_hash, min_depth, max_depth } => {
             ("BFS Typed", format!("type {type_hash} depth {min_depth}..{max_depth}"))
@@ -236,7 +413,11 @@ pub fn describe_step(step: &Step, db: &CoreDB) -> serde_json::Map<String, Value>
             let op = if *ci { "ILIKE" } else { "LIKE" };
             ("Filter", format!("{f} {op} '{p}'"))
         }
+        Step::WhereContains(f, p) => ("Filter", format!("{f} CONTAINS '{p}'")),
+        Step::WhereStartsWith(f, p) => ("Filter", format!("{f} STARTS WITH '{p}'")),
+        Step::WhereRegex(f, p) => ("Filter", format!("{f} ~ '{p}'")),
         Step::StDWithin(lat, lon, km) => ("Spatial Filter", format!("ST_DWithin({lat},{lon},{km}km)")),
+        Step::NearNode(h, km) => ("Spatial Filter", format!("within {km}km of slug hash {h}")),
         Step::StContainsPoint(lat, lon) => ("Spatial Filter", format!("ST_Contains(POINT({lat},{lon}))")),
         Step::StWithin(_) => ("Spatial Filter", "ST_Within(polygon)".into()),
         Step::StContains(_) => ("Spatial Filter", "ST_Contains(polygon)".into()),
@@ -246,6 +427,10 @@ pub fn describe_step(step: &Step, db: &CoreDB) -> serde_json::Map<String, Value>
         Step::StArea(f, km2) => ("Spatial Filter", format!("ST_Area({f}) > {km2}km²")),
         Step::VectorNear { field, k, .. } => ("Vector Scan", format!("{field} top-{k} nearest")),
         Step::SearchFilter(q) => ("Search Filter", format!("SEARCH('{q}')")),
+        Step::Hybrid { vector_field, text_field, text, k, fusion, .. } => (
+            "Hybrid Scan",
+            format!("{vector_field} + BM25({text_field}, '{text}') top-{k} by {fusion:?}"),
+        ),
         Step::Bm25Filter(f, q, s) => ("BM25 Filter", format!("{f} match '{q}' score > {s}")),
         Step::Bm25Sort(f, q, asc) => ("BM25 Sort", format!("{f} match '{q}' {}", if *asc { "ASC" } else { "DESC" })),
         Step::ScoreProject(projs) => ("Score Project", format!("{} projection(s)", projs.len())),
@@ -269,6 +454,9 @@ pub fn describe_step(step: &Step, db: &CoreDB) -> serde_json::Map<String, Value>
         }
         Step::SortByVector { field, .. } => ("Vector Sort", format!("{field}")),
         Step::SortByExpr { ascending, .. } => ("Score Sort", format!("{}", if *ascending { "ASC" } else { "DESC" })),
+        Step::TopPerGroup { group_field, sort_field, n } => {
+            ("Top-N Per Group", format!("{n} by {sort_field} DESC per {group_field}"))
+        }
         Step::Take(n) => ("Limit", format!("{n}")),
         Step::Skip(n) => ("Offset", format!("{n}")),
         Step::Select(fields) => ("Project", fields.join(", ")),
@@ -304,10 +492,105 @@ pub fn explain_steps(db: &CoreDB, steps: &[Step]) -> Vec<Hit> {
         if has_index {
             map.insert("index".into(), Value::String("btree".into()));
         }
-        Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)) }
+        Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)), score: None }
     }).collect()
 }
 
+// ── Trace ─────────────────────────────────────────────────────────────────────
+
+/// Timing of one executed step, from [`Set::trace`].
+#[derive(Debug, Clone)]
+pub struct StepTrace {
+    /// Position in its pipeline.
+    pub seq: usize,
+    /// Step name and detail as shown by EXPLAIN.
+    pub step: String,
+    pub detail: String,
+    pub rows_in: usize,
+    pub rows_out: usize,
+    /// Wall time including `children`.
+    pub elapsed: std::time::Duration,
+    /// Steps of an `Intersect` / `Union` / `Subtract` sub-pipeline.
+    pub children: Vec<StepTrace>,
+}
+
+/// Per-step timings of one pipeline run. Steps folded into an index lookup
+/// by an earlier step, and steps skipped because nothing was left to
+/// filter, do not appear.
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    pub steps: Vec<StepTrace>,
+    /// The caller's request id from [`Set::correlation_id`], for joining
+    /// these timings with application request logs.
+    pub correlation_id: Option<String>,
+}
+
+impl Trace {
+    /// Total wall time of the top-level steps.
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.steps.iter().map(|s| s.elapsed).sum()
+    }
+
+    /// The trace in folded-stack format, one `frame;frame;… count` line per
+    /// step with the step's own time (children excluded) in microseconds,
+    /// rooted at `query`. Feed it to `flamegraph.pl` or `inferno-flamegraph`.
+    /// Steps that took under a microsecond are left out.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("a", r#"{"_collection":"t","v":1}"#).unwrap();
+    /// let trace = db.collection("t").where_eq("v", serde_json::json!(1)).trace();
+    /// for line in trace.to_folded().lines() {
+    ///     assert!(line.starts_with("query;0 ") || line.starts_with("query;1 "));
+    /// }
+    /// ```
+    pub fn to_folded(&self) -> String {
+        fn frame(s: &StepTrace) -> String {
+            let name = format!("{} {}", s.seq, s.step);
+            let name = if s.detail.is_empty() { name } else { format!("{name} ({})", s.detail) };
+            name.replace([';', '\n'], " ")
+        }
+        fn walk(steps: &[StepTrace], stack: &str, out: &mut String) {
+            for s in steps {
+                let path = format!("{stack};{}", frame(s));
+                let own = s.elapsed.saturating_sub(s.children.iter().map(|c| c.elapsed).sum());
+                if own.as_micros() > 0 {
+                    out.push_str(&format!("{path} {}\n", own.as_micros()));
+                }
+                walk(&s.children, &path, out);
+            }
+        }
+        let mut out = String::new();
+        walk(&self.steps, "query", &mut out);
+        out
+    }
+
+    /// The trace as JSON: `{"correlation_id": …, "elapsed_us": …, "steps":
+    /// [{"seq", "step", "detail", "rows_in", "rows_out", "elapsed_us",
+    /// "children"}, …]}`, with a `null` id when none was attached.
+    /// This is the `trace` member of a traced query response in the
+    /// wrappers and the HTTP server.
+    pub fn to_json(&self) -> Value {
+        fn step(s: &StepTrace) -> Value {
+            serde_json::json!({
+                "seq": s.seq,
+                "step": s.step,
+                "detail": s.detail,
+                "rows_in": s.rows_in,
+                "rows_out": s.rows_out,
+                "elapsed_us": s.elapsed.as_micros() as u64,
+                "children": s.children.iter().map(step).collect::<Vec<_>>(),
+            })
+        }
+        serde_json::json!({
+            "correlation_id": self.correlation_id,
+            "elapsed_us": self.elapsed().as_micros() as u64,
+            "steps": self.steps.iter().map(step).collect::<Vec<_>>(),
+        })
+    }
+}
+
 // ── Set ───────────────────────────────────────────────────────────────────────
 
 /// Chainable, lazy query builder. Execute with `.collect()`, `.count()`, etc.
@@ -316,25 +599,39 @@ pub struct Set<'db> {
     pub(crate) steps: Vec<Step>,
     /// Pre-computed hits (for aggregate MATCH — bypasses the step executor).
     pub(crate) precomputed: Option<Vec<Hit>>,
+    /// Result caps `collect()` holds the query to: the database's, or the
+    /// tighter per-call ones of [`CoreDB::query_with_limits`].
+    pub(crate) limits: crate::QueryLimits,
+    /// Projection profile applied by `collect()`; see [`Set::profile`].
+    pub(crate) profile: Option<String>,
+    /// Request id copied into the run's [`Trace`]; see [`Set::correlation_id`].
+    pub(crate) correlation_id: Option<String>,
+    /// Time budget and cancel token for `collect()`; see [`Set::timeout`].
+    pub(crate) budget: Budget,
 }
 
 impl<'db> Set<'db> {
     pub(crate) fn new(db: &'db CoreDB, starter: Step) -> Self {
-        Self {
-            db,
-            steps: vec![starter],
-            precomputed: None,
-        }
+        Self::from_steps(db, vec![starter])
     }
 
     /// Build a Set from a pre-constructed step list (useful for serialisation / Python bindings).
-    pub fn from_steps(db: &'db CoreDB, steps: Vec<Step>) -> Self {
-        Self { db, steps, precomputed: None }
+    ///
+    /// A leading `Step::Collection` picks up that collection's implicit filter
+    /// (see [`CoreDB::set_collection_filter`]).
+    pub fn from_steps(db: &'db CoreDB, mut steps: Vec<Step>) -> Self {
+        db.apply_collection_filter(&mut steps);
+        Self { db, steps, precomputed: None, limits: *db.query_limits(), profile: None, correlation_id: None, budget: Budget::default() }
+    }
+
+    /// Like [`from_steps`](Self::from_steps) but never applies an implicit filter.
+    pub(crate) fn from_steps_unscoped(db: &'db CoreDB, steps: Vec<Step>) -> Self {
+        Self { db, steps, precomputed: None, limits: *db.query_limits(), profile: None, correlation_id: None, budget: Budget::default() }
     }
 
     /// Build a Set wrapping pre-computed hits (used for aggregate MATCH results).
     pub(crate) fn from_hits(db: &'db CoreDB, hits: Vec<Hit>) -> Self {
-        Self { db, steps: Vec::new(), precomputed: Some(hits) }
+        Self { db, steps: Vec::new(), precomputed: Some(hits), limits: *db.query_limits(), profile: None, correlation_id: None, budget: Budget::default() }
     }
 
     // ── Graph traversal ───────────────────────────────────────────────────────
@@ -349,6 +646,51 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Like [`forward`](Self::forward), but only follows edges created in
+    /// `since..=until` (unix milliseconds, as in `_created_unix`). Useful for
+    /// restricting a root-cause walk to an incident window.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["a", "b"] { db.put(s, "{}").unwrap(); }
+    /// db.link("a", "b", "causes", 1.0);
+    /// let now = chrono::Utc::now().timestamp_millis();
+    /// assert_eq!(db.one("a").forward_since("causes", now - 60_000, now + 60_000).count(), 1);
+    /// assert_eq!(db.one("a").forward_since("causes", 0, now - 60_000).count(), 0);
+    /// ```
+    pub fn forward_since(mut self, edge_type: &str, since: i64, until: i64) -> Self {
+        self.steps.push(Step::Forward(sk_hash(edge_type)));
+        self.steps.push(Step::EdgeWindow(since, until));
+        self
+    }
+
+    /// Like [`backward`](Self::backward), but only follows edges created in
+    /// `since..=until` (unix milliseconds).
+    pub fn backward_since(mut self, edge_type: &str, since: i64, until: i64) -> Self {
+        self.steps.push(Step::Backward(sk_hash(edge_type)));
+        self.steps.push(Step::EdgeWindow(since, until));
+        self
+    }
+
+    /// Follow edges of `edge_type` in both directions, for logically
+    /// undirected relationships such as `"similar_to"`. Equivalent to the
+    /// union of [`forward`](Self::forward) and [`backward`](Self::backward)
+    /// in a single pass.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["a", "b", "c"] { db.put(s, "{}").unwrap(); }
+    /// db.link("a", "b", "similar_to", 1.0);
+    /// db.link("c", "b", "similar_to", 1.0);
+    /// assert_eq!(db.one("b").both("similar_to").count(), 2);
+    /// ```
+    pub fn both(mut self, edge_type: &str) -> Self {
+        self.steps.push(Step::Both(sk_hash(edge_type)));
+        self
+    }
+
     /// Filter traversal results to only nodes reached via edges with strength >= threshold.
     /// Place this after `.forward()` or `.backward()`.
     pub fn min_strength(mut self, threshold: f32) -> Self {
@@ -362,6 +704,32 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Restrict the preceding traversal to nodes matching `pred`, built from
+    /// an empty pipeline of filters. After [`hops`](Self::hops) or
+    /// [`hops_typed`](Self::hops_typed) the predicate is checked at every
+    /// frontier expansion, so paths through non-matching intermediate nodes
+    /// are cut instead of only filtering the final result. Start nodes are
+    /// not tested.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("a", r#"{"type":"event"}"#).unwrap();
+    /// db.put("b", r#"{"type":"note"}"#).unwrap();
+    /// db.put("c", r#"{"type":"event"}"#).unwrap();
+    /// db.link("a", "b", "causes", 1.0);
+    /// db.link("b", "c", "causes", 1.0);
+    /// let n = db.one("a").hops_typed("causes", 3)
+    ///     .where_each_hop(|s| s.where_eq("type", "event"))
+    ///     .count();
+    /// assert_eq!(n, 0); // c is only reachable through the note
+    /// ```
+    pub fn where_each_hop(mut self, pred: impl FnOnce(Set<'db>) -> Set<'db>) -> Self {
+        let pred = pred(Set { db: self.db, steps: Vec::new(), precomputed: None, limits: self.limits, profile: None, correlation_id: None, budget: Budget::default() });
+        self.steps.push(Step::HopFilter(pred.steps));
+        self
+    }
+
     /// Typed BFS: follow only `edge_type` edges up to `max_depth` hops.
     ///
     /// Equivalent to the MATCH `(a)-[:edge_type*1..max_depth]->(b)` clause.
@@ -423,6 +791,18 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Drop nodes whose [`EXPIRES_FIELD`](crate::EXPIRES_FIELD) is at or
+    /// before now (see [`CoreDB::put_with_ttl`](crate::CoreDB::put_with_ttl)).
+    /// Nodes without an expiry are kept.
+    pub fn unexpired(mut self) -> Self {
+        let now = self.db.now_millis() as f64;
+        self.steps.push(Step::WhereOr(vec![
+            vec![Step::WhereIsNull(crate::EXPIRES_FIELD.to_string(), false)],
+            vec![Step::WhereGt(crate::EXPIRES_FIELD.to_string(), now)],
+        ]));
+        self
+    }
+
     pub fn where_between(mut self, field: &str, lo: f64, hi: f64) -> Self {
         self.steps
             .push(Step::WhereBetween(field.to_string(), lo, hi));
@@ -434,6 +814,22 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Keep nodes where **any** of `fields` equals `value` — e.g. search
+    /// `"Jakarta"` across `city`, `location` and `region` at once.
+    ///
+    /// Expands to an OR of per-field `where_eq` filters; when every field
+    /// has a btree index the lookups are unioned without reading payloads.
+    pub fn where_any_of(mut self, fields: &[&str], value: impl Into<Value>) -> Self {
+        let value = value.into();
+        self.steps.push(Step::WhereOr(
+            fields
+                .iter()
+                .map(|f| vec![Step::WhereEq(f.to_string(), value.clone())])
+                .collect(),
+        ));
+        self
+    }
+
     /// Case-sensitive substring filter.
     pub fn like(mut self, field: &str, pattern: &str) -> Self {
         self.steps
@@ -448,6 +844,33 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Literal substring filter — unlike [`like`](Self::like), `%` has no
+    /// special meaning. Use field `_slug` to match against node slugs.
+    pub fn where_contains(mut self, field: &str, needle: &str) -> Self {
+        self.steps
+            .push(Step::WhereContains(field.to_string(), needle.to_string()));
+        self
+    }
+
+    /// Literal prefix filter. Use field `_slug` to match against node slugs.
+    pub fn where_starts_with(mut self, field: &str, prefix: &str) -> Self {
+        self.steps
+            .push(Step::WhereStartsWith(field.to_string(), prefix.to_string()));
+        self
+    }
+
+    /// Regular-expression filter (`regex` crate syntax, unanchored).
+    ///
+    /// # Errors
+    /// [`SqlError::InvalidValue`](crate::SqlError::InvalidValue) if `pattern`
+    /// does not compile.
+    pub fn where_regex(mut self, field: &str, pattern: &str) -> Result<Self, crate::SqlError> {
+        let re = regex::Regex::new(pattern)
+            .map_err(|e| crate::SqlError::InvalidValue(format!("regex `{pattern}`: {e}")))?;
+        self.steps.push(Step::WhereRegex(field.to_string(), re));
+        Ok(self)
+    }
+
     // ── Spatial filters ───────────────────────────────────────────────────
 
     /// Keep nodes whose centroid is within `distance_km` of `(lat, lon)`.
@@ -461,6 +884,16 @@ impl<'db> Set<'db> {
         self.st_dwithin(lat, lon, radius_km)
     }
 
+    /// Keep nodes within `radius_km` of node `slug`, using the anchor's own
+    /// centroid as the center; the anchor itself is not kept. Matches nothing
+    /// if `slug` is missing or has no geometry. To start from the
+    /// neighbourhood rather than filter a set, use
+    /// [`CoreDB::near_node`](crate::CoreDB::near_node).
+    pub fn near_node(mut self, slug: &str, radius_km: f64) -> Self {
+        self.steps.push(Step::NearNode(crate::sk_hash(slug), radius_km));
+        self
+    }
+
     /// Keep nodes whose geometry contains the query point.
     pub fn st_contains_point(mut self, lat: f64, lon: f64) -> Self {
         self.steps.push(Step::StContainsPoint(lat, lon));
@@ -489,8 +922,10 @@ impl<'db> Set<'db> {
 
     /// Return the top-k nodes by cosine similarity to `query` in the named vector field.
     ///
-    /// Acts as a STARTER when no prior steps have produced candidates, otherwise
-    /// re-ranks the existing candidate set. Results are sorted ascending by cosine
+    /// Acts as a STARTER when it is the first step, otherwise ranks only the
+    /// existing candidate set (an empty set stays empty). Large scoped sets use a
+    /// filter-aware HNSW walk, so a collection-scoped search still returns up to
+    /// `k` members of that collection. Results are sorted ascending by cosine
     /// distance (lower = more similar).
     pub fn vector_near(mut self, field: &str, query: Vec<f32>, k: usize) -> Self {
         self.steps
@@ -498,6 +933,46 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Top-k nodes by vector nearness and keyword relevance together: the
+    /// cosine ranking of `query` on `vector_field` and the BM25 ranking of
+    /// `text` on `text_field` are merged by `fusion` into one score, best
+    /// first. A node found by only one side still ranks, unlike an
+    /// intersection of [`vector_near`](Self::vector_near) and a BM25 filter.
+    ///
+    /// Acts as a STARTER when it is the first step, otherwise ranks only the
+    /// existing candidates. Each side contributes its best `max(4k, 50)`.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, Fusion};
+    /// let mut db = CoreDB::new();
+    /// db.put("d/1", r#"{"body":"rust borrow checker"}"#).unwrap();
+    /// db.put("d/2", r#"{"body":"gardening tips"}"#).unwrap();
+    /// db.put_vector("d/1", "emb", &[0.0, 1.0]).unwrap();
+    /// db.put_vector("d/2", "emb", &[1.0, 0.0]).unwrap();
+    /// db.build_bm25_index("body");
+    /// let hits = db.all().hybrid("emb", vec![1.0, 0.1], "body", "rust", 2, Fusion::default()).collect();
+    /// assert_eq!(hits.len(), 2, "each side brings its own best match");
+    /// ```
+    pub fn hybrid(
+        mut self,
+        vector_field: &str,
+        query: Vec<f32>,
+        text_field: &str,
+        text: &str,
+        k: usize,
+        fusion: Fusion,
+    ) -> Self {
+        self.steps.push(Step::Hybrid {
+            vector_field: vector_field.to_string(),
+            query,
+            text_field: text_field.to_string(),
+            text: text.to_string(),
+            k,
+            fusion,
+        });
+        self
+    }
+
     // ── BM25 full-text filter ──────────────────────────────────────────────
 
     /// Keep nodes where BM25 score on `field` for `query` exceeds `min_score`.
@@ -540,6 +1015,20 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Keep the `n` best nodes per group — highest `sort_field` first within
+    /// each distinct `group_field` value (e.g. latest 3 events per district).
+    ///
+    /// Nodes missing `sort_field` rank last; nodes missing `group_field`
+    /// form their own group.
+    pub fn top_per_group(mut self, group_field: &str, sort_field: &str, n: usize) -> Self {
+        self.steps.push(Step::TopPerGroup {
+            group_field: group_field.to_string(),
+            sort_field: sort_field.to_string(),
+            n,
+        });
+        self
+    }
+
     pub fn skip(mut self, n: usize) -> Self {
         self.steps.push(Step::Skip(n));
         self
@@ -557,6 +1046,50 @@ impl<'db> Set<'db> {
         self
     }
 
+    /// Apply projection profile `name` to everything this set emits: hits of
+    /// [`collect`](Self::collect), [`first`](Self::first) and the edge
+    /// terminals lose the payload fields that profile excludes for their
+    /// collection (see [`CoreDB::set_projection_profile`]), and field
+    /// terminals such as [`any`](Self::any), [`min`](Self::min) or
+    /// [`to_viz_json`](Self::to_viz_json) see those fields as missing.
+    ///
+    /// Fails when no collection declares `name`, when a `SELECT` or
+    /// `GROUP BY` field names a field the profile hides in any collection
+    /// (an alias or expression would carry it past the redaction), and for
+    /// aggregate, path and join results under a profile that hides anything.
+    /// Call it once the pipeline is built: hits of a projection added later
+    /// that names a hidden field come back without payloads.
+    pub fn profile(mut self, name: &str) -> Result<Self, crate::SqlError> {
+        if let Some(reason) = self.profile_conflict(name) {
+            return Err(crate::SqlError::InvalidValue(reason));
+        }
+        self.profile = Some(name.to_string());
+        Ok(self)
+    }
+
+    /// Why profile `name` cannot redact this set, or `None` if it can.
+    fn profile_conflict(&self, name: &str) -> Option<String> {
+        let Some(hidden) = self.db.profile_fields(name) else {
+            return Some(format!("unknown projection profile `{name}`"));
+        };
+        if hidden.is_empty() {
+            return None;
+        }
+        if self.precomputed.is_some() {
+            return Some(format!("projection profile `{name}` cannot redact aggregate, path or join results"));
+        }
+        let projected = self.steps.iter().flat_map(|s| match s {
+            Step::Select(fields) | Step::GroupBy(fields) => fields.as_slice(),
+            _ => &[],
+        });
+        for expr in projected {
+            if let Some(field) = hidden.iter().find(|f| names_field(expr, f)) {
+                return Some(format!("projection profile `{name}` hides `{field}`, which the query projects"));
+            }
+        }
+        None
+    }
+
     // ── Execute ───────────────────────────────────────────────────────────────
 
     /// Resolve nodes and the edge that connected them to the previous step.
@@ -574,6 +1107,27 @@ impl<'db> Set<'db> {
     /// assert!((pairs[0].1.strength - 0.9).abs() < 1e-6);
     /// ```
     pub fn edge_collect(self) -> Vec<(Hit, crate::EdgeHit)> {
+        self.edge_collect_where(|_| true)
+    }
+
+    /// Like [`edge_collect`](Self::edge_collect), but only keeps edges that
+    /// satisfy `pred`. A destination is returned if any connecting edge
+    /// passes, paired with the first one that does. Use it to filter on
+    /// edge weight, type or creation time:
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("a", "{}").unwrap();
+    /// db.put("b", "{}").unwrap();
+    /// db.put("c", "{}").unwrap();
+    /// db.link("a", "b", "rel", 0.9);
+    /// db.link("a", "c", "rel", 0.2);
+    /// let strong = db.one("a").forward("rel").edge_collect_where(|e| e.strength > 0.5);
+    /// assert_eq!(strong.len(), 1);
+    /// assert_eq!(strong[0].0.slug, "b");
+    /// ```
+    pub fn edge_collect_where(self, pred: impl Fn(&crate::EdgeHit) -> bool) -> Vec<(Hit, crate::EdgeHit)> {
         // Find the last Forward or Backward step to determine edge type and direction.
         let last_traversal = self
             .steps
@@ -589,6 +1143,7 @@ impl<'db> Set<'db> {
             Some(x) => x,
             None => return vec![],
         };
+        let window = edge_window(&self.steps[trav_idx + 1..]);
 
         // Run steps up to the traversal to get source nodes.
         let sources: std::collections::HashSet<u64> = execute(self.db, &self.steps[..trav_idx])
@@ -599,49 +1154,244 @@ impl<'db> Set<'db> {
         let dests = execute(self.db, &self.steps);
 
         let db = self.db;
-        dests
+        let mut rows: Vec<(Hit, crate::EdgeHit)> = dests
             .into_iter()
             .filter_map(|dest_h| {
                 let dest_node = db.node_data(dest_h)?;
                 // Find an edge from a source node to this dest (or vice versa for backward).
-                let edge = if is_forward {
+                let candidates = if is_forward {
                     // Look in rev_edges of dest for a source
                     db.rev_edges(dest_h)?
-                        .iter()
-                        .find(|e| e.edge_type == type_h && sources.contains(&e.other))
-                        .map(|e| crate::EdgeHit {
-                            from_slug: db.node_data(e.other).map(|n| n.slug.clone()),
-                            to_slug: Some(dest_node.slug.clone()),
-                            edge_type: db.resolve_edge_type(e.edge_type),
-                            edge_type_hash: e.edge_type,
-                            strength: e.strength,
-                            meta: db.edge_meta(e),
-                        })
                 } else {
                     // Backward: look in fwd_edges of dest for a source
                     db.fwd_edges(dest_h)?
-                        .iter()
-                        .find(|e| e.edge_type == type_h && sources.contains(&e.other))
-                        .map(|e| crate::EdgeHit {
-                            from_slug: Some(dest_node.slug.clone()),
-                            to_slug: db.node_data(e.other).map(|n| n.slug.clone()),
+                };
+                let edge = candidates
+                    .iter()
+                    .filter(|e| e.edge_type == type_h && in_window(e, window) && sources.contains(&e.other))
+                    .map(|e| {
+                        let (from_slug, to_slug) = if is_forward {
+                            (db.node_data(e.other).map(|n| n.slug.clone()), Some(dest_node.slug.clone()))
+                        } else {
+                            (Some(dest_node.slug.clone()), db.node_data(e.other).map(|n| n.slug.clone()))
+                        };
+                        crate::EdgeHit {
+                            from_slug,
+                            to_slug,
                             edge_type: db.resolve_edge_type(e.edge_type),
                             edge_type_hash: e.edge_type,
                             strength: e.strength,
+                            created_unix: e.created_unix,
                             meta: db.edge_meta(e),
-                        })
-                }?;
+                        }
+                    })
+                    .find(|hit| pred(hit))?;
                 let hit = Hit {
                     slug: dest_node.slug.clone(),
                     slug_hash: dest_h,
                     payload: db.get_payload(dest_h),
+                    score: None,
                 };
                 Some((hit, edge))
             })
-            .collect()
+            .collect();
+        if let Some(profile) = &self.profile {
+            let mut hits: Vec<Hit> = rows.iter().map(|(hit, _)| hit.clone()).collect();
+            db.apply_projection_profile(profile, &mut hits);
+            for ((hit, _), stripped) in rows.iter_mut().zip(hits) {
+                *hit = stripped;
+            }
+        }
+        rows
+    }
+
+    /// Resolve nodes together with every edge the last `.forward()` /
+    /// `.backward()` / `.both()` step crossed to reach them, as
+    /// `(destination_hit, edge_hit)` pairs.
+    ///
+    /// Unlike [`edge_collect`](Self::edge_collect), a destination reached
+    /// over several edges appears once per edge, so weights, metadata and
+    /// timestamps of parallel paths are all kept. `edge_hit.from_slug`
+    /// names the node the edge came from. Results follow the pipeline's
+    /// order, projection and limit; an `.edge_window()` or `.min_strength()`
+    /// after the traversal also filters the edges.
+    ///
+    /// Returns an empty `Vec` when the pipeline has no single-hop traversal.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["quake", "fire", "flood"] { db.put(s, "{}").unwrap(); }
+    /// db.link("quake", "fire", "causes", 0.4);
+    /// db.link("flood", "fire", "causes", 0.7);
+    /// let rows = db.many(["quake", "flood"]).forward("causes").collect_with_edges();
+    /// assert_eq!(rows.len(), 2);
+    /// assert!(rows.iter().all(|(hit, _)| hit.slug == "fire"));
+    /// ```
+    pub fn collect_with_edges(self) -> Vec<(Hit, crate::EdgeHit)> {
+        let Some((trav_idx, type_h, direction)) = self.last_traversal() else {
+            return vec![];
+        };
+        if self.precomputed.is_some() {
+            return vec![];
+        }
+        let db = self.db;
+        let window = edge_window(&self.steps[trav_idx + 1..]);
+        let min_strength = min_strength_after(&self.steps[trav_idx + 1..]);
+        let sources: HashSet<u64> = execute(db, &self.steps[..trav_idx]).into_iter().collect();
+
+        let edge_hit = |from: u64, to: u64, e: &crate::Edge| crate::EdgeHit {
+            from_slug: db.node_data(from).map(|n| n.slug.clone()),
+            to_slug: db.node_data(to).map(|n| n.slug.clone()),
+            edge_type: db.resolve_edge_type(e.edge_type),
+            edge_type_hash: e.edge_type,
+            strength: e.strength,
+            created_unix: e.created_unix,
+            meta: db.edge_meta(e),
+        };
+        let crossed = |e: &crate::Edge| {
+            e.edge_type == type_h
+                && e.strength >= min_strength
+                && in_window(e, window)
+                && sources.contains(&e.other)
+        };
+
+        let mut rows = Vec::new();
+        for hit in self.collect() {
+            let dest = hit.slug_hash;
+            // Forward reached `dest` over its incoming edges; backward over
+            // its outgoing ones.
+            if direction != Some(false) {
+                for e in db.rev_edges(dest).unwrap_or(&[]).iter().filter(|e| crossed(e)) {
+                    rows.push((hit.clone(), edge_hit(e.other, dest, e)));
+                }
+            }
+            if direction != Some(true) {
+                for e in db.fwd_edges(dest).unwrap_or(&[]).iter().filter(|e| crossed(e)) {
+                    rows.push((hit.clone(), edge_hit(dest, e.other, e)));
+                }
+            }
+        }
+        rows
+    }
+
+    /// Resolve nodes together with every edge arriving at them, as
+    /// `(target_hit, edge_hit)` pairs — the "who links to me" view.
+    ///
+    /// Unlike [`edge_collect`](Self::edge_collect), no traversal step is
+    /// needed: the incoming edges of every node in the final result are
+    /// listed, whatever their type, with `edge_hit.from_slug` naming the
+    /// source and `meta` decoded. A node with no incoming edges yields no row.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["home", "blog", "wiki"] { db.put(s, "{}").unwrap(); }
+    /// db.link("blog", "home", "links", 1.0);
+    /// db.link("wiki", "home", "cites", 0.5);
+    /// let rows = db.one("home").edge_collect_incoming();
+    /// let mut sources: Vec<_> = rows.iter().filter_map(|(_, e)| e.from_slug.clone()).collect();
+    /// sources.sort();
+    /// assert_eq!(sources, ["blog", "wiki"]);
+    /// ```
+    pub fn edge_collect_incoming(self) -> Vec<(Hit, crate::EdgeHit)> {
+        let db = self.db;
+        let mut rows = Vec::new();
+        for hit in self.collect() {
+            for e in db.rev_edges(hit.slug_hash).unwrap_or(&[]) {
+                let edge = crate::EdgeHit {
+                    from_slug: db.node_data(e.other).map(|n| n.slug.clone()),
+                    to_slug: Some(hit.slug.clone()),
+                    edge_type: db.resolve_edge_type(e.edge_type),
+                    edge_type_hash: e.edge_type,
+                    strength: e.strength,
+                    created_unix: e.created_unix,
+                    meta: db.edge_meta(e),
+                };
+                rows.push((hit.clone(), edge));
+            }
+        }
+        rows
+    }
+
+    /// Sum the strengths of every edge crossed by the last `.forward()` /
+    /// `.backward()` step, counting only edges that land on a node still in
+    /// the final result.
+    ///
+    /// Returns `0.0` when the pipeline has no traversal or no edges.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for s in ["alert", "db", "net"] { db.put(s, "{}").unwrap(); }
+    /// db.link("alert", "db", "caused_by", 0.75);
+    /// db.link("alert", "net", "caused_by", 0.25);
+    /// assert!((db.one("alert").forward("caused_by").sum_edge_weight() - 1.0).abs() < 1e-6);
+    /// ```
+    pub fn sum_edge_weight(self) -> f64 {
+        self.traversed_edge_weights().iter().map(|&w| w as f64).sum()
+    }
+
+    /// Mean strength of the edges crossed by the last traversal step, or
+    /// `None` when no edge was crossed. See [`sum_edge_weight`](Self::sum_edge_weight).
+    pub fn avg_edge_weight(self) -> Option<f64> {
+        let weights = self.traversed_edge_weights();
+        if weights.is_empty() {
+            return None;
+        }
+        Some(weights.iter().map(|&w| w as f64).sum::<f64>() / weights.len() as f64)
+    }
+
+    /// Strengths of all edges between the sources and the final destinations
+    /// of the last Forward/Backward/Both step. A `MinStrength` after that step
+    /// also drops the weaker edges from the aggregate.
+    fn traversed_edge_weights(&self) -> Vec<f32> {
+        let Some((trav_idx, type_h, is_forward)) = self.last_traversal() else {
+            return vec![];
+        };
+        let window = edge_window(&self.steps[trav_idx + 1..]);
+        let min_strength = min_strength_after(&self.steps[trav_idx + 1..]);
+
+        let dests: HashSet<u64> = execute(self.db, &self.steps).into_iter().collect();
+        let sources = execute(self.db, &self.steps[..trav_idx]);
+
+        let mut weights = Vec::new();
+        for src in sources {
+            let fwd = if is_forward != Some(false) { self.db.fwd_edges(src) } else { None };
+            let rev = if is_forward != Some(true) { self.db.rev_edges(src) } else { None };
+            for e in fwd.into_iter().flatten().chain(rev.into_iter().flatten()) {
+                if e.edge_type == type_h
+                    && e.strength >= min_strength
+                    && in_window(e, window)
+                    && dests.contains(&e.other)
+                {
+                    weights.push(e.strength);
+                }
+            }
+        }
+        weights
+    }
+
+    /// Position, edge type and direction (`Some(true)` forward, `Some(false)`
+    /// backward, `None` both) of the last single-hop traversal step.
+    fn last_traversal(&self) -> Option<(usize, u64, Option<bool>)> {
+        self.steps.iter().enumerate().rev().find_map(|(i, s)| match s {
+            Step::Forward(h) => Some((i, *h, Some(true))),
+            Step::Backward(h) => Some((i, *h, Some(false))),
+            Step::Both(h) => Some((i, *h, None)),
+            _ => None,
+        })
     }
 }
 
+/// Strongest `MinStrength` threshold among the steps after a traversal.
+fn min_strength_after(after_traversal: &[Step]) -> f32 {
+    after_traversal
+        .iter()
+        .filter_map(|s| if let Step::MinStrength(t) = s { Some(*t) } else { None })
+        .fold(f32::NEG_INFINITY, f32::max)
+}
+
 /// Return the output JSON key name for a field expression.
 ///
 /// Rules (in priority order):
@@ -673,6 +1423,16 @@ fn field_output_key(expr: &str) -> String {
     expr.to_string()
 }
 
+/// Whether encoded SELECT expression `expr` names payload field `field`,
+/// matched on identifier boundaries so `__AS__e\x01email` names `email`.
+fn names_field(expr: &str, field: &str) -> bool {
+    expr.match_indices(field).any(|(i, _)| {
+        let before = expr[..i].chars().next_back();
+        let after = expr[i + field.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric()) && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
 /// Extract the raw (unaliased) field name from an encoded SELECT expression.
 ///
 /// Strips `__AS__alias\x01` if present and returns the inner expression.
@@ -732,14 +1492,46 @@ pub(crate) fn json_path_get(field: &str, payload: &serde_json::Value) -> Option<
             cur = cur.get(*key)?;
         }
         Some(cur.clone())
+    } else if let Some(v) = payload.get(field) {
+        Some(v.clone())
+    } else if field.contains(['.', '[']) {
+        nested_path_get(field, payload).cloned()
     } else {
-        payload.get(field).cloned()
+        None
     }
 }
 
-fn eval_field_expr(expr: &str, payload: &serde_json::Value) -> Option<serde_json::Value> {
-    // AS alias: __AS__alias\x01inner  — strip alias, evaluate inner expression.
-    if let Some(rest) = expr.strip_prefix("__AS__") {
+/// Walk a dotted path with optional array indexes: `coordinates.lat`,
+/// `entities.people[0]`, `rows[2].cells[1].text`.
+///
+/// A literal key that happens to contain a dot is still found by the plain
+/// lookup in [`json_path_get`], which runs first.
+pub(crate) fn nested_path_get<'a>(path: &str, payload: &'a Value) -> Option<&'a Value> {
+    let mut cur = payload;
+    for segment in path.split('.') {
+        let (key, mut rest) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            cur = cur.get(key)?;
+        }
+        while let Some(inner) = rest.strip_prefix('[') {
+            let close = inner.find(']')?;
+            let idx: usize = inner[..close].trim().parse().ok()?;
+            cur = cur.get(idx)?;
+            rest = &inner[close + 1..];
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+    }
+    Some(cur)
+}
+
+fn eval_field_expr(expr: &str, payload: &serde_json::Value) -> Option<serde_json::Value> {
+    // AS alias: __AS__alias\x01inner  — strip alias, evaluate inner expression.
+    if let Some(rest) = expr.strip_prefix("__AS__") {
         if let Some(idx) = rest.find('\x01') {
             let inner = &rest[idx + 1..];
             return eval_field_expr(inner, payload);
@@ -802,7 +1594,7 @@ fn eval_field_expr(expr: &str, payload: &serde_json::Value) -> Option<serde_json
             payload_map,
         ));
     }
-    payload.get(expr).cloned()
+    json_path_get(expr, payload)
 }
 
 // ── Aggregation helpers ───────────────────────────────────────────────────────
@@ -834,18 +1626,27 @@ struct AggAccum {
     count_notnull: usize,
     min: Option<f64>,
     max: Option<f64>,
+    /// Serialized non-null values seen — only filled for `COUNT_DISTINCT`.
+    distinct: HashSet<String>,
+    /// Numeric sample — only allocated for `MEDIAN`.
+    sample: Option<crate::sketch::QuantileSample>,
 }
 
 impl AggAccum {
     fn new(func: &str, arg: &str) -> Self {
+        let func = func.to_uppercase();
+        let sample = (func == "MEDIAN")
+            .then(|| crate::sketch::QuantileSample::new(crate::sketch::QuantileSample::DEFAULT_CAPACITY));
         Self {
-            func: func.to_uppercase(),
+            func,
             arg: arg.to_string(),
             all_count: 0,
             sum: 0.0,
             count_notnull: 0,
             min: None,
             max: None,
+            distinct: HashSet::new(),
+            sample,
         }
     }
 
@@ -854,11 +1655,26 @@ impl AggAccum {
         if self.arg == "*" {
             return;
         }
-        if let Some(f) = payload.get(&self.arg).and_then(|v| v.as_f64()) {
-            self.count_notnull += 1;
-            self.sum += f;
-            self.min = Some(self.min.map_or(f, |m: f64| m.min(f)));
-            self.max = Some(self.max.map_or(f, |m: f64| m.max(f)));
+        if self.func == "COUNT_DISTINCT" {
+            if let Some(v) = json_path_get(&self.arg, payload).filter(|v| !v.is_null()) {
+                self.count_notnull += 1;
+                self.distinct.insert(serde_json::to_string(&v).unwrap_or_default());
+            }
+            return;
+        }
+        if let Some(f) = json_path_get(&self.arg, payload).and_then(|v| v.as_f64()) {
+            self.push_num(f);
+        }
+    }
+
+    /// Accumulate one non-null numeric value (payload or index sourced).
+    fn push_num(&mut self, f: f64) {
+        self.count_notnull += 1;
+        self.sum += f;
+        self.min = Some(self.min.map_or(f, |m: f64| m.min(f)));
+        self.max = Some(self.max.map_or(f, |m: f64| m.max(f)));
+        if let Some(sample) = self.sample.as_mut() {
+            sample.insert(f);
         }
     }
 
@@ -878,6 +1694,11 @@ impl AggAccum {
             }
             "MIN" => self.min.map(|v| serde_json::json!(v)).unwrap_or(Value::Null),
             "MAX" => self.max.map(|v| serde_json::json!(v)).unwrap_or(Value::Null),
+            "MEDIAN" => self.sample.as_ref()
+                .and_then(|s| s.quantile(0.5))
+                .map(|v| serde_json::json!(v))
+                .unwrap_or(Value::Null),
+            "COUNT_DISTINCT" => serde_json::json!(self.distinct.len() as i64),
             _ => Value::Null,
         }
     }
@@ -971,6 +1792,8 @@ impl<'db> Set<'db> {
         let mut arg_val_maps: HashMap<String, HashMap<u64, f64>> = HashMap::new();
         for af in &agg_fields {
             if af.arg == "*" { continue; }
+            // The reverse maps hold numbers only; distinct counts need every value.
+            if af.func == "COUNT_DISTINCT" { return None; }
             let arg_idx = self.db.field_index(collection_hash, &af.arg)?;
             if !arg_val_maps.contains_key(&af.arg) {
                 let mut m: HashMap<u64, f64> = HashMap::new();
@@ -1020,10 +1843,7 @@ impl<'db> Set<'db> {
                         acc.all_count = group_hashes.len();
                         for &h in group_hashes {
                             if let Some(&f) = vm.get(&h) {
-                                acc.count_notnull += 1;
-                                acc.sum += f;
-                                acc.min = Some(acc.min.map_or(f, |m: f64| m.min(f)));
-                                acc.max = Some(acc.max.map_or(f, |m: f64| m.max(f)));
+                                acc.push_num(f);
                             }
                         }
                         acc.finalize()
@@ -1066,7 +1886,7 @@ impl<'db> Set<'db> {
         }
 
         let hits = rows.into_iter().map(|map| {
-            Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)) }
+            Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)), score: None }
         }).collect();
 
         Some(hits)
@@ -1134,10 +1954,168 @@ impl<'db> Set<'db> {
         }
     }
 
+    /// Stop `collect()` once `limit` has passed since this call: the
+    /// pipeline is checked before each step and every few hundred payload
+    /// checks or visited nodes. [`run`](Self::run) reports the stop;
+    /// `collect()` and `try_collect()` return the hits loaded so far.
+    ///
+    /// ```
+    /// # use sekejap::{CoreDB, InterruptCause, QueryError};
+    /// # use std::time::Duration;
+    /// let mut db = CoreDB::new();
+    /// db.put("n/1", r#"{"_collection":"n"}"#).unwrap();
+    /// let err = db.collection("n").timeout(Duration::ZERO).run().unwrap_err();
+    /// assert!(matches!(err, QueryError::Interrupted(i) if i.cause == InterruptCause::TimedOut { limit_ms: 0 }));
+    /// ```
+    pub fn timeout(mut self, limit: std::time::Duration) -> Self {
+        self.budget.limit(limit.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Stop `collect()` when `token` is cancelled, from any thread, with
+    /// the same checks and results as [`timeout`](Self::timeout).
+    ///
+    /// ```
+    /// # use sekejap::{CancelToken, CoreDB, QueryError};
+    /// let mut db = CoreDB::new();
+    /// db.put("n/1", r#"{"_collection":"n"}"#).unwrap();
+    /// let token = CancelToken::new();
+    /// let query = db.collection("n").cancel_on(&token);
+    /// token.cancel();
+    /// assert!(matches!(query.run(), Err(QueryError::Interrupted(_))));
+    /// ```
+    pub fn cancel_on(mut self, token: &CancelToken) -> Self {
+        self.budget.watch(token);
+        self
+    }
+
+    /// Tag this query with the caller's request id. The traced collects
+    /// record it as [`Trace::correlation_id`], so engine timings can be
+    /// joined with application request logs.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("n/1", r#"{"_collection":"n"}"#).unwrap();
+    /// let (_, trace) = db.collection("n").correlation_id("req-42").collect_traced();
+    /// assert_eq!(trace.to_json()["correlation_id"], "req-42");
+    /// ```
+    pub fn correlation_id(mut self, id: impl Into<String>) -> Self {
+        self.correlation_id = Some(id.into());
+        self
+    }
+
+    /// Run the query and load its hits. A result over the result caps of
+    /// [`QueryLimits`](crate::QueryLimits) is cut to fit; use
+    /// [`try_collect`](Self::try_collect) to be told instead. A run stopped
+    /// by its [`timeout`](Self::timeout) or cancel token returns the hits
+    /// loaded before the stop; use [`run`](Self::run) to be told.
     pub fn collect(self) -> Vec<Hit> {
+        match self.collect_limited(false, None) {
+            Ok(hits) => hits,
+            Err(QueryError::Interrupted(i)) => i.partial,
+            Err(QueryError::TooLarge(_)) => unreachable!("a lenient collect cuts instead of failing"),
+        }
+    }
+
+    /// [`collect`](Self::collect), also returning the [`Trace`] of the run
+    /// that produced the hits.
+    pub fn collect_traced(mut self) -> (Vec<Hit>, Trace) {
+        let mut steps = Vec::new();
+        let correlation_id = self.correlation_id.take();
+        let hits = match self.collect_limited(false, Some(&mut steps)) {
+            Ok(hits) => hits,
+            Err(QueryError::Interrupted(i)) => i.partial,
+            Err(QueryError::TooLarge(_)) => unreachable!("a lenient collect cuts instead of failing"),
+        };
+        (hits, Trace { steps, correlation_id })
+    }
+
+    /// [`collect`](Self::collect), but a result over the result caps of
+    /// [`QueryLimits`](crate::QueryLimits) is an error, raised before any
+    /// payload is read where the caps can be checked that early.
+    pub fn try_collect(self) -> Result<Vec<Hit>, crate::ResultTooLarge> {
+        match self.collect_limited(true, None) {
+            Ok(hits) => Ok(hits),
+            Err(QueryError::Interrupted(i)) => Ok(i.partial),
+            Err(QueryError::TooLarge(e)) => Err(e),
+        }
+    }
+
+    /// [`try_collect`](Self::try_collect) with the run's [`Trace`].
+    pub fn try_collect_traced(mut self) -> Result<(Vec<Hit>, Trace), crate::ResultTooLarge> {
+        let mut steps = Vec::new();
+        let correlation_id = self.correlation_id.take();
+        let hits = match self.collect_limited(true, Some(&mut steps)) {
+            Ok(hits) => hits,
+            Err(QueryError::Interrupted(i)) => i.partial,
+            Err(QueryError::TooLarge(e)) => return Err(e),
+        };
+        Ok((hits, Trace { steps, correlation_id }))
+    }
+
+    /// [`try_collect`](Self::try_collect), but a run stopped by its
+    /// [`timeout`](Self::timeout), its cancel token or
+    /// [`QueryLimits::max_execution_ms`](crate::QueryLimits::max_execution_ms)
+    /// is an error too, carrying the hits loaded before the stop.
+    pub fn run(self) -> Result<Vec<Hit>, QueryError> {
+        self.collect_limited(true, None)
+    }
+
+    /// [`run`](Self::run) with the run's [`Trace`].
+    pub fn run_traced(mut self) -> Result<(Vec<Hit>, Trace), QueryError> {
+        let mut steps = Vec::new();
+        let correlation_id = self.correlation_id.take();
+        let hits = self.collect_limited(true, Some(&mut steps))?;
+        Ok((hits, Trace { steps, correlation_id }))
+    }
+
+    fn collect_limited(
+        mut self,
+        strict: bool,
+        trace: Option<&mut Vec<StepTrace>>,
+    ) -> Result<Vec<Hit>, QueryError> {
+        let limits = self.limits;
+        let profile = self.profile.take();
+        let redact_all = profile.as_deref().is_some_and(|p| self.profile_conflict(p).is_some());
+        let db = self.db;
+        let mut budget = std::mem::take(&mut self.budget);
+        if let Some(cap) = limits.max_frontier {
+            budget.track_frontier(cap, strict);
+        }
+        let (hits, end) = budget.run(|| self.collect_uncapped(strict, trace));
+        let mut hits = hits?;
+        if let (Some(frontier), None, true) = (end.frontier, end.stopped, strict) {
+            return Err(QueryError::TooLarge(crate::ResultTooLarge { hits: 0, payload_bytes: 0, frontier, limits }));
+        }
+        // Grouped and precomputed rows are only counted once built.
+        if let Some(max) = limits.max_results.filter(|&m| hits.len() > m) {
+            if strict {
+                let hits = hits.len();
+                return Err(QueryError::TooLarge(crate::ResultTooLarge { hits, payload_bytes: 0, frontier: 0, limits }));
+            }
+            hits.truncate(max);
+        }
+        if let Some(profile) = profile {
+            db.apply_projection_profile(&profile, &mut hits);
+        }
+        if redact_all {
+            hits.iter_mut().for_each(|hit| hit.payload = None);
+        }
+        match end.stopped {
+            Some(cause) => Err(QueryError::Interrupted(Interrupted { cause, partial: hits })),
+            None => Ok(hits),
+        }
+    }
+
+    fn collect_uncapped(
+        self,
+        strict: bool,
+        mut trace: Option<&mut Vec<StepTrace>>,
+    ) -> Result<Vec<Hit>, crate::ResultTooLarge> {
         // Short-circuit for pre-computed aggregate results.
         if let Some(hits) = self.precomputed {
-            return hits;
+            return Ok(hits);
         }
 
         let select_fields: Option<Vec<String>> = self.steps.iter().find_map(|s| {
@@ -1211,10 +2189,13 @@ impl<'db> Set<'db> {
             // When true, the btree already holds `value → Vec<hashes>` — we just
             // iterate its entries and take `len()` as the count. Zero disk reads.
             if let Some(hits) = self.try_index_only_group_by(group_fields, &select_fields) {
-                return hits;
+                return Ok(hits);
             }
 
-            let hashes = execute(self.db, &self.steps);
+            let hashes = execute_traced(self.db, &self.steps, trace.as_deref_mut());
+            if budget::stopped().is_some() {
+                return Ok(Vec::new());
+            }
             let fields = select_fields.as_deref().unwrap_or(&[]);
             let having_steps: Vec<&[Step]> = self.steps.iter().filter_map(|s| {
                 if let Step::Having(inner) = s { Some(inner.as_slice()) } else { None }
@@ -1336,7 +2317,7 @@ impl<'db> Set<'db> {
                         .unwrap_or_else(|| Value::Object(serde_json::Map::new()))
                 };
 
-                Some(Hit { slug: String::new(), slug_hash: 0, payload: Some(map) })
+                Some(Hit { slug: String::new(), slug_hash: 0, payload: Some(map), score: None })
             }).collect();
 
             // Sort grouped results.
@@ -1364,7 +2345,7 @@ impl<'db> Set<'db> {
                     seen.insert(key)
                 });
             }
-            return results;
+            return Ok(results);
         }
 
         // ── Aggregation mode ──────────────────────────────────────────────────
@@ -1375,7 +2356,10 @@ impl<'db> Set<'db> {
             .map_or(false, |fields| fields.iter().any(|f| agg_inner(f).is_some()));
 
         if has_agg {
-            let hashes = execute(self.db, &self.steps);
+            let hashes = execute_traced(self.db, &self.steps, trace.as_deref_mut());
+            if budget::stopped().is_some() {
+                return Ok(Vec::new());
+            }
             let fields = match &select_fields {
                 Some(f) => f.as_slice(),
                 None => &[],
@@ -1405,7 +2389,7 @@ impl<'db> Set<'db> {
                 for f in fields {
                     map.insert(field_output_key(f), Value::Number(serde_json::Number::from(n)));
                 }
-                return vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)) }];
+                return Ok(vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)), score: None }]);
             }
 
             // Index-only aggregate fast path: when btree indexes exist for all
@@ -1456,6 +2440,17 @@ impl<'db> Set<'db> {
                         Some(i) => i,
                         None => break 'index_agg,
                     };
+                    if info.func == "COUNT_DISTINCT" {
+                        // One btree key per distinct value: count the keys that
+                        // still have a member in the result set.
+                        let n = idx.iter()
+                            .filter(|(key, ids)| {
+                                !matches!(key, FieldKey::Null) && ids.iter().any(|h| hash_set.contains(h))
+                            })
+                            .count();
+                        map.insert(info.out_key.clone(), serde_json::json!(n as i64));
+                        continue;
+                    }
                     let mut acc = AggAccum::new(&info.func, &info.arg);
                     acc.all_count = total;
                     // Iterate btree entries and accumulate for matching hashes.
@@ -1464,10 +2459,7 @@ impl<'db> Set<'db> {
                         if let Some(f) = val.as_f64() {
                             for &h in node_hashes {
                                 if hash_set.contains(&h) {
-                                    acc.count_notnull += 1;
-                                    acc.sum += f;
-                                    acc.min = Some(acc.min.map_or(f, |m: f64| m.min(f)));
-                                    acc.max = Some(acc.max.map_or(f, |m: f64| m.max(f)));
+                                    acc.push_num(f);
                                 }
                             }
                         }
@@ -1475,7 +2467,7 @@ impl<'db> Set<'db> {
                     map.insert(info.out_key.clone(), acc.finalize());
                 }
 
-                return vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)) }];
+                return Ok(vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)), score: None }]);
             }
 
             for &hash in &hashes {
@@ -1507,11 +2499,12 @@ impl<'db> Set<'db> {
                     map.insert(key, v.clone());
                 }
             }
-            return vec![Hit {
+            return Ok(vec![Hit {
                 slug: String::new(),
                 slug_hash: 0,
                 payload: Some(Value::Object(map)),
-            }];
+                score: None,
+            }]);
         }
 
         // Determine whether the fast raw-byte field extractor can be used.
@@ -1536,7 +2529,11 @@ impl<'db> Set<'db> {
         // This eliminates the N-syscall cost for typical point-attribute queries.
         if can_use_fast_path {
             let fields = select_fields.as_ref().unwrap(); // safe: can_use_fast_path requires Some
-            let hashes = execute(self.db, &self.steps);
+            let (mut hashes, scores) = execute_scored(self.db, &self.steps, trace.as_deref_mut());
+            if budget::stopped().is_some() {
+                return Ok(Vec::new());
+            }
+            self.db.fit_result(&mut hashes, &self.limits, strict)?;
             // Collect which hashes need a full payload and which can be batched.
             let raw_map: HashMap<u64, Vec<u8>> = {
                 let small: Vec<u64> = hashes.iter().copied().filter(|&h| {
@@ -1549,7 +2546,7 @@ impl<'db> Set<'db> {
                     HashMap::new()
                 }
             };
-            let mut hits: Vec<Hit> = hashes.into_iter().filter_map(|hash| {
+            let mut hits: Vec<Hit> = hashes.into_iter().take_while(|_| !budget::spent()).filter_map(|hash| {
                 let node = self.db.node_data(hash)?;
                 let out = if node.payload_len > FAST_PATH_THRESHOLD {
                     // Large payload: head + tail read (avoids loading e.g. 12 MB GeoJSON).
@@ -1598,6 +2595,7 @@ impl<'db> Set<'db> {
                     slug: node.slug.clone(),
                     slug_hash: hash,
                     payload: Some(Value::Object(out)),
+                    score: scores.get(&hash).copied(),
                 })
             }).collect();
             let distinct = self.steps.iter().any(|s| matches!(s, Step::Distinct));
@@ -1611,11 +2609,17 @@ impl<'db> Set<'db> {
                 });
             }
             Self::resolve_vectors(self.db, &mut hits, &select_fields, &self.steps);
-            return hits;
+            return Ok(hits);
         }
 
-        let mut hits: Vec<Hit> = execute(self.db, &self.steps)
+        let (mut hashes, scores) = execute_scored(self.db, &self.steps, trace);
+        if budget::stopped().is_some() {
+            return Ok(Vec::new());
+        }
+        self.db.fit_result(&mut hashes, &self.limits, strict)?;
+        let mut hits: Vec<Hit> = hashes
             .into_iter()
+            .take_while(|_| !budget::spent())
             .filter_map(|hash| {
                 let node = self.db.node_data(hash)?;
                 let payload = match &select_fields {
@@ -1699,6 +2703,7 @@ impl<'db> Set<'db> {
                     slug: node.slug.clone(),
                     slug_hash: hash,
                     payload,
+                    score: scores.get(&hash).copied(),
                 })
             })
             .collect::<Vec<_>>();
@@ -1714,7 +2719,7 @@ impl<'db> Set<'db> {
             });
         }
         Self::resolve_vectors(self.db, &mut hits, &select_fields, &self.steps);
-        hits
+        Ok(hits)
     }
 
     /// Return the number of matching nodes without resolving payloads.
@@ -1725,6 +2730,90 @@ impl<'db> Set<'db> {
         execute(self.db, &self.steps).len()
     }
 
+    /// Run the step pipeline, timing each step, without loading payloads.
+    /// See [`Trace::to_folded`] for flamegraph output.
+    pub fn trace(&self) -> Trace {
+        let mut steps = Vec::new();
+        if self.precomputed.is_none() {
+            execute_traced(self.db, &self.steps, Some(&mut steps));
+        }
+        Trace { steps, correlation_id: self.correlation_id.clone() }
+    }
+
+    /// [`collect`](Self::collect) and decode every payload into `T`,
+    /// stopping at the first one that does not fit.
+    pub fn collect_as<T: serde::de::DeserializeOwned>(self) -> Result<Vec<T>, HitDecodeError> {
+        Ok(self.collect_as_with_meta()?.into_iter().map(|(_, t)| t).collect())
+    }
+
+    /// [`collect_as`](Self::collect_as), keeping each hit's slug alongside
+    /// its value.
+    ///
+    /// A plain pipeline (no projection, grouping, `DISTINCT`, score columns
+    /// or vector columns) decodes each stored payload straight from its raw
+    /// bytes, skipping the intermediate `serde_json::Value`; anything else is
+    /// collected first and then decoded.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// #[derive(serde::Deserialize)]
+    /// struct Venue { name: String }
+    ///
+    /// let mut db = CoreDB::new();
+    /// db.put("v/1", r#"{"_collection":"venues","name":"Corner"}"#).unwrap();
+    /// let rows = db.collection("venues").collect_as_with_meta::<Venue>().unwrap();
+    /// assert_eq!((rows[0].0.slug.as_str(), rows[0].1.name.as_str()), ("v/1", "Corner"));
+    /// ```
+    pub fn collect_as_with_meta<T: serde::de::DeserializeOwned>(self) -> Result<Vec<(HitMeta, T)>, HitDecodeError> {
+        let decode_err = |slug: &str, error| HitDecodeError {
+            slug: slug.to_string(),
+            target: std::any::type_name::<T>(),
+            error,
+        };
+        if !self.decodes_raw() {
+            return self
+                .collect()
+                .into_iter()
+                .map(|h| {
+                    let value = h.deserialize_into()?;
+                    Ok((HitMeta { slug: h.slug, slug_hash: h.slug_hash }, value))
+                })
+                .collect();
+        }
+        let mut hashes = execute(self.db, &self.steps);
+        self.db.fit_result(&mut hashes, &self.limits, false).expect("a lenient fit cuts instead of failing");
+        let mut raw = self.db.read_raw_payloads_batched(&hashes);
+        hashes
+            .into_iter()
+            .filter_map(|h| Some((h, self.db.node_data(h)?, raw.remove(&h)?)))
+            .map(|(h, node, bytes)| {
+                let value = serde_json::from_slice(&bytes).map_err(|e| decode_err(&node.slug, e))?;
+                Ok((HitMeta { slug: node.slug.clone(), slug_hash: h }, value))
+            })
+            .collect()
+    }
+
+    /// Whether `collect()` would return each node's stored payload unchanged.
+    fn decodes_raw(&self) -> bool {
+        if self.precomputed.is_some() || self.profile.is_some() {
+            return false;
+        }
+        let shaped = self.steps.iter().any(|s| {
+            matches!(
+                s,
+                Step::Select(_) | Step::GroupBy(_) | Step::Having(_) | Step::Distinct | Step::ScoreProject(_)
+            )
+        });
+        let has_vector_columns = self
+            .steps
+            .iter()
+            .find_map(|s| if let Step::Collection(h) = s { Some(*h) } else { None })
+            .and_then(|h| self.db.collection_name(h))
+            .and_then(|name| self.db.table_schema(name))
+            .is_some_and(|schema| schema.fields.iter().any(|f| matches!(f.ty, crate::sql::FieldType::Vector)));
+        !shaped && !has_vector_columns
+    }
+
     /// Return the first matching node, or `None`.
     pub fn first(self) -> Option<Hit> {
         // Re-use collect; a future optimisation could short-circuit.
@@ -1738,6 +2827,396 @@ impl<'db> Set<'db> {
         }
         !execute(self.db, &self.steps).is_empty()
     }
+
+    /// Return `true` as soon as one matching node satisfies `predicate` on
+    /// `field`, without loading the payloads of the rest.
+    ///
+    /// `predicate` is a JSON value: a scalar tests equality, `null` tests
+    /// for a missing or null field, and an object applies every operator it
+    /// holds: `eq`, `ne`, `gt`, `gte`, `lt`, `lte` (numbers), `in` (array),
+    /// `like` / `ilike` (pattern) and `is_null` (bool).
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// # use serde_json::json;
+    /// let mut db = CoreDB::new();
+    /// db.put("i/1", r#"{"_collection":"incidents","severity":"critical","resolved":true}"#).unwrap();
+    /// db.put("i/2", r#"{"_collection":"incidents","severity":"low","age_h":30}"#).unwrap();
+    /// let open = || db.collection("incidents").where_neq("resolved", true);
+    /// assert!(!open().any("severity", "critical").unwrap());
+    /// assert!(open().any("age_h", json!({"gte": 24, "lt": 48})).unwrap());
+    /// assert!(db.collection("incidents").none("severity", json!({"in": ["major"]})).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// [`SqlError::InvalidValue`](crate::SqlError::InvalidValue) for an
+    /// unknown operator or an operand of the wrong type.
+    pub fn any(self, field: &str, predicate: impl Into<Value>) -> Result<bool, crate::SqlError> {
+        let conds = predicate_steps(field, predicate.into())?;
+        let db = self.db;
+        let profile = self.profile.clone();
+        let hashes = self.into_hashes();
+        Ok(hashes.into_iter().any(|h| match profile.as_deref().and_then(|p| db.profile_hides(p, h)) {
+            Some(hidden) => {
+                let payload = without_fields(db.get_payload(h).unwrap_or(Value::Null), hidden);
+                conds.iter().all(|c| eval_step_on_payload(c, &payload))
+            }
+            None => conds.iter().all(|c| eval_cond(db, h, c)),
+        }))
+    }
+
+    /// `!any(field, predicate)`: no matching node satisfies `predicate`.
+    pub fn none(self, field: &str, predicate: impl Into<Value>) -> Result<bool, crate::SqlError> {
+        self.any(field, predicate).map(|found| !found)
+    }
+
+    /// Estimate the number of distinct non-null values of `field` with a
+    /// HyperLogLog sketch (≈ 0.8 % standard error, 16 KiB of state).
+    ///
+    /// Memory stays constant no matter how many nodes match; use it where an
+    /// exact `COUNT(DISTINCT …)` would have to hold every value.
+    pub fn approx_distinct(self, field: &str) -> u64 {
+        let mut hll = crate::sketch::HyperLogLog::new(crate::sketch::HyperLogLog::DEFAULT_PRECISION);
+        self.scan_field(field, |v| {
+            if !v.is_null() {
+                let key = serde_json::to_vec(&v).unwrap_or_default();
+                hll.insert_hash(seahash::hash(&key));
+            }
+        });
+        hll.estimate().round() as u64
+    }
+
+    /// The `q`-quantile (`0.0..=1.0`) of numeric `field`, e.g. `0.99` for p99
+    /// latency. Non-numeric and missing values are ignored; `None` when no
+    /// node has a numeric value.
+    ///
+    /// Exact up to 65 536 values, estimated from a uniform sample beyond that.
+    pub fn quantile(self, field: &str, q: f64) -> Option<f64> {
+        let mut sample = crate::sketch::QuantileSample::new(crate::sketch::QuantileSample::DEFAULT_CAPACITY);
+        self.scan_field(field, |v| {
+            if let Some(f) = v.as_f64() {
+                sample.insert(f);
+            }
+        });
+        sample.quantile(q)
+    }
+
+    /// Smallest numeric value of `field`, or `None` if no node has one.
+    pub fn min(self, field: &str) -> Option<f64> {
+        let mut min: Option<f64> = None;
+        self.scan_field(field, |v| {
+            if let Some(f) = v.as_f64() {
+                min = Some(min.map_or(f, |m| m.min(f)));
+            }
+        });
+        min
+    }
+
+    /// Largest numeric value of `field`, or `None` if no node has one.
+    pub fn max(self, field: &str) -> Option<f64> {
+        let mut max: Option<f64> = None;
+        self.scan_field(field, |v| {
+            if let Some(f) = v.as_f64() {
+                max = Some(max.map_or(f, |m| m.max(f)));
+            }
+        });
+        max
+    }
+
+    /// Median of numeric `field` — shorthand for [`quantile(field, 0.5)`](Self::quantile).
+    pub fn median(self, field: &str) -> Option<f64> {
+        self.quantile(field, 0.5)
+    }
+
+    /// Exact number of distinct non-null values of `field`.
+    ///
+    /// Holds every distinct value in memory; prefer
+    /// [`approx_distinct`](Self::approx_distinct) for very high cardinalities.
+    pub fn count_distinct(self, field: &str) -> usize {
+        let mut seen: HashSet<String> = HashSet::new();
+        self.scan_field(field, |v| {
+            if !v.is_null() {
+                seen.insert(serde_json::to_string(&v).unwrap_or_default());
+            }
+        });
+        seen.len()
+    }
+
+    /// Count numeric values of `field` in fixed-width buckets.
+    ///
+    /// Returns `(bucket_start, count)` pairs in ascending order, where
+    /// `bucket_start = floor(v / bucket_width) * bucket_width`. Empty buckets
+    /// are omitted; a non-positive or non-finite width yields no buckets.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// for (i, ms) in [3, 7, 12, 18, 25].iter().enumerate() {
+    ///     db.put(&format!("r{i}"), &format!(r#"{{"ms":{ms}}}"#)).unwrap();
+    /// }
+    /// assert_eq!(db.all().histogram("ms", 10.0), vec![(0.0, 2), (10.0, 2), (20.0, 1)]);
+    /// ```
+    pub fn histogram(self, field: &str, bucket_width: f64) -> Vec<(f64, usize)> {
+        if !(bucket_width.is_finite() && bucket_width > 0.0) {
+            return Vec::new();
+        }
+        let mut buckets: BTreeMap<i64, usize> = BTreeMap::new();
+        self.scan_field(field, |v| {
+            if let Some(f) = v.as_f64().filter(|f| f.is_finite()) {
+                *buckets.entry((f / bucket_width).floor() as i64).or_default() += 1;
+            }
+        });
+        buckets
+            .into_iter()
+            .map(|(b, n)| (b as f64 * bucket_width, n))
+            .collect()
+    }
+
+    /// Count matching nodes per time bucket of timestamp `field`.
+    ///
+    /// `granularity` takes the `DATE_TRUNC` units: `year`, `quarter`,
+    /// `month`, `week` (ISO, Monday start), `day`, `hour`, `minute`. Returns
+    /// `(bucket_start_rfc3339, count)` in ascending time order; empty buckets
+    /// are omitted. Timestamps may be RFC 3339 strings, `YYYY-MM-DD` dates or
+    /// unix epoch numbers (seconds, or milliseconds from 1e11 up).
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("e1", r#"{"at":"2026-03-01T08:00:00Z"}"#).unwrap();
+    /// db.put("e2", r#"{"at":"2026-03-01T21:30:00Z"}"#).unwrap();
+    /// db.put("e3", r#"{"at":"2026-03-02T01:00:00Z"}"#).unwrap();
+    /// let per_day = db.all().bucket_time("at", "day");
+    /// assert_eq!(per_day[0], ("2026-03-01T00:00:00+00:00".to_string(), 2));
+    /// assert_eq!(per_day[1].1, 1);
+    /// ```
+    pub fn bucket_time(self, field: &str, granularity: &str) -> Vec<(String, usize)> {
+        let mut buckets: BTreeMap<i64, usize> = BTreeMap::new();
+        self.scan_field(field, |v| {
+            if let Some(ts) = crate::scalar::timestamp_from_value(&v) {
+                let start = crate::scalar::date_trunc(granularity, ts);
+                if let Ok(start) = chrono::DateTime::parse_from_rfc3339(&start) {
+                    *buckets.entry(start.timestamp()).or_default() += 1;
+                }
+            }
+        });
+        buckets
+            .into_iter()
+            .filter_map(|(secs, n)| {
+                chrono::DateTime::from_timestamp(secs, 0).map(|dt| (dt.to_rfc3339(), n))
+            })
+            .collect()
+    }
+
+    /// Pairs of matching nodes whose SimHash fingerprints differ in at most
+    /// `max_hamming` bits, as `(slug_a, slug_b, distance)` sorted by distance
+    /// (`slug_a < slug_b`). Fingerprints cover the text fields declared with
+    /// `CREATE INDEX ON coll USING simhash (field, …)`; nodes without one are
+    /// ignored. Candidates are bucketed by fingerprint bands, so only pairs
+    /// that can be within range are compared.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.execute("CREATE INDEX ON news USING simhash (body)").unwrap();
+    /// db.put("n/1", r#"{"_collection":"news","body":"Flooding closes the main road into Ubud after heavy overnight rain"}"#).unwrap();
+    /// db.put("n/2", r#"{"_collection":"news","body":"Flooding closes the main road into Ubud after heavy rain overnight"}"#).unwrap();
+    /// db.put("n/3", r#"{"_collection":"news","body":"Central bank holds interest rates steady for a third month"}"#).unwrap();
+    /// let pairs = db.collection("news").near_duplicates(3);
+    /// assert_eq!(pairs.len(), 1);
+    /// assert_eq!((pairs[0].0.as_str(), pairs[0].1.as_str()), ("n/1", "n/2"));
+    /// ```
+    pub fn near_duplicates(self, max_hamming: u32) -> Vec<(String, String, u32)> {
+        let db = self.db;
+        let hashes = self.into_hashes();
+        let fps: Vec<(u64, u64)> = hashes
+            .into_iter()
+            .filter_map(|h| Some((h, db.simhash_fingerprint(h)?)))
+            .collect();
+
+        let mut buckets: HashMap<(u32, u64), Vec<usize>> = HashMap::new();
+        for (i, &(_, fp)) in fps.iter().enumerate() {
+            for key in crate::sketch::simhash_bands(fp, max_hamming.saturating_add(1)) {
+                buckets.entry(key).or_default().push(i);
+            }
+        }
+        let mut seen: HashSet<(usize, usize)> = HashSet::new();
+        let mut out = Vec::new();
+        for group in buckets.values() {
+            for (n, &i) in group.iter().enumerate() {
+                for &j in &group[n + 1..] {
+                    let dist = (fps[i].1 ^ fps[j].1).count_ones();
+                    if dist > max_hamming || !seen.insert((i.min(j), i.max(j))) {
+                        continue;
+                    }
+                    let (Some(a), Some(b)) = (db.slug_of(fps[i].0), db.slug_of(fps[j].0)) else {
+                        continue;
+                    };
+                    let (a, b) = if a < b { (a, b) } else { (b, a) };
+                    out.push((a.to_string(), b.to_string(), dist));
+                }
+            }
+        }
+        out.sort_by(|x, y| (x.2, &x.0, &x.1).cmp(&(y.2, &y.0, &y.1)));
+        out
+    }
+
+    /// Render the matching nodes and the edges between them in the
+    /// `{nodes, links}` shape D3, vis.js and Cytoscape load directly:
+    ///
+    /// ```text
+    /// {"nodes":[{"id":"a","label":"Alice","group":"people", ...fields}],
+    ///  "links":[{"source":"a","target":"b","value":1.0,"type":"knows"}]}
+    /// ```
+    ///
+    /// `label` is the node's `name`, `title` or `label` field, falling back to
+    /// the slug; `group` is its collection (`null` if none). Each of `fields`
+    /// present in the payload is copied onto the node. Only edges whose both
+    /// ends are in the set become links.
+    ///
+    /// ```
+    /// # use sekejap::CoreDB;
+    /// let mut db = CoreDB::new();
+    /// db.put("p/a", r#"{"_collection":"people","name":"Alice","age":30}"#).unwrap();
+    /// db.put("p/b", r#"{"_collection":"people","name":"Bob"}"#).unwrap();
+    /// db.link("p/a", "p/b", "knows", 0.5);
+    /// let viz = db.collection("people").to_viz_json(&["age"]);
+    /// assert_eq!(viz["nodes"][0]["label"], "Alice");
+    /// assert_eq!(viz["nodes"][0]["age"], 30);
+    /// assert_eq!(viz["links"][0]["source"], "p/a");
+    /// assert_eq!(viz["links"][0]["value"], 0.5);
+    /// ```
+    pub fn to_viz_json(self, fields: &[&str]) -> Value {
+        let db = self.db;
+        let profile = self.profile.clone();
+        let hashes = self.into_hashes();
+        let members: HashSet<u64> = hashes.iter().copied().collect();
+
+        let mut nodes = Vec::with_capacity(hashes.len());
+        let mut links = Vec::new();
+        for &h in &hashes {
+            let Some(node) = db.node_data(h) else { continue };
+            let mut payload = db.get_payload(h).unwrap_or(Value::Null);
+            if let Some(hidden) = profile.as_deref().and_then(|p| db.profile_hides(p, h)) {
+                payload = without_fields(payload, hidden);
+            }
+            let label = ["name", "title", "label"]
+                .iter()
+                .find_map(|k| payload.get(*k).and_then(Value::as_str))
+                .unwrap_or(&node.slug);
+            let mut obj = serde_json::Map::new();
+            obj.insert("id".into(), Value::String(node.slug.clone()));
+            obj.insert("label".into(), Value::String(label.to_string()));
+            obj.insert(
+                "group".into(),
+                if node.collection.is_empty() { Value::Null } else { Value::String(node.collection.clone()) },
+            );
+            for field in fields {
+                if let Some(v) = resolve_field(field, &payload) {
+                    obj.insert((*field).to_string(), v);
+                }
+            }
+            nodes.push(Value::Object(obj));
+
+            for e in db.fwd_edges(h).unwrap_or(&[]) {
+                if !members.contains(&e.other) {
+                    continue;
+                }
+                let Some(target) = db.node_data(e.other) else { continue };
+                links.push(serde_json::json!({
+                    "source": node.slug,
+                    "target": target.slug,
+                    "value": e.strength,
+                    "type": db.resolve_edge_type(e.edge_type),
+                }));
+            }
+        }
+        serde_json::json!({ "nodes": nodes, "links": links })
+    }
+
+    /// Slug hashes of the matching nodes, in pipeline order.
+    pub(crate) fn into_hashes(self) -> Vec<u64> {
+        match self.precomputed {
+            Some(hits) => hits.iter().map(|h| h.slug_hash).collect(),
+            None => execute(self.db, &self.steps),
+        }
+    }
+
+    /// Feed the value of `field` for every matching node to `f`; nodes
+    /// without the field, or whose profile hides it, are skipped.
+    fn scan_field(self, field: &str, mut f: impl FnMut(Value)) {
+        let db = self.db;
+        let profile = self.profile;
+        let hidden = |h: u64| profile.as_deref().and_then(|p| db.profile_hides(p, h));
+        match self.precomputed {
+            Some(hits) => hits
+                .into_iter()
+                .filter_map(|h| {
+                    let payload = h.payload?;
+                    let payload = match hidden(h.slug_hash) {
+                        Some(exclude) => without_fields(payload, exclude),
+                        None => payload,
+                    };
+                    resolve_field(field, &payload)
+                })
+                .for_each(f),
+            None => {
+                let (profiled, plain): (Vec<u64>, Vec<u64>) =
+                    execute(db, &self.steps).into_iter().partition(|&h| hidden(h).is_some());
+                for_each_field_value(db, &plain, field, |_, v| {
+                    if let Some(v) = v {
+                        f(v);
+                    }
+                });
+                for h in profiled {
+                    let (Some(payload), Some(exclude)) = (db.get_payload(h), hidden(h)) else { continue };
+                    if let Some(v) = resolve_field(field, &without_fields(payload, exclude)) {
+                        f(v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `payload` without the top-level fields in `exclude`.
+fn without_fields(mut payload: Value, exclude: &[String]) -> Value {
+    if let Some(map) = payload.as_object_mut() {
+        for field in exclude {
+            map.remove(field);
+        }
+    }
+    payload
+}
+
+/// Visit `field` for every node in `hashes`, reading payloads in bounded
+/// batches so peak memory does not grow with the result size.
+///
+/// Plain top-level fields are pulled out of the raw bytes without a JSON
+/// parse; nested paths, expressions and non-scalar values fall back to a
+/// full parse of that one payload.
+fn for_each_field_value(db: &CoreDB, hashes: &[u64], field: &str, mut f: impl FnMut(u64, Option<Value>)) {
+    const CHUNK: usize = 4096;
+    if !is_simple_field(field) {
+        for &h in hashes {
+            f(h, db.get_payload(h).and_then(|p| resolve_field(field, &p)));
+        }
+        return;
+    }
+    let fields = vec![field.to_string()];
+    for chunk in hashes.chunks(CHUNK) {
+        let raw = db.read_raw_payloads_batched(chunk);
+        for &h in chunk {
+            let v = raw.get(&h).and_then(|bytes| {
+                extract_fields_by_search(bytes, &fields).remove(field).or_else(|| {
+                    serde_json::from_slice::<Value>(bytes)
+                        .ok()
+                        .and_then(|p| resolve_field(field, &p))
+                })
+            });
+            f(h, v);
+        }
+    }
 }
 
 // ── Condition evaluator ───────────────────────────────────────────────────────
@@ -1753,6 +3232,89 @@ fn values_eq(a: &Value, b: &Value) -> bool {
     }
 }
 
+/// Compiled form of the literal/regex string filter steps.
+enum StrMatcher<'a> {
+    Contains(&'a str),
+    StartsWith(&'a str),
+    Regex(&'a regex::Regex),
+}
+
+impl<'a> StrMatcher<'a> {
+    /// Returns the filtered field and its matcher for `WhereContains`,
+    /// `WhereStartsWith` and `WhereRegex`; `None` for any other step.
+    fn from_step(step: &'a Step) -> Option<(&'a str, Self)> {
+        match step {
+            Step::WhereContains(f, p) => Some((f, StrMatcher::Contains(p))),
+            Step::WhereStartsWith(f, p) => Some((f, StrMatcher::StartsWith(p))),
+            Step::WhereRegex(f, re) => Some((f, StrMatcher::Regex(re))),
+            _ => None,
+        }
+    }
+
+    fn is_match(&self, s: &str) -> bool {
+        match self {
+            StrMatcher::Contains(n) => s.contains(n),
+            StrMatcher::StartsWith(p) => s.starts_with(p),
+            StrMatcher::Regex(re) => re.is_match(s),
+        }
+    }
+
+    /// The literal text every match must contain, if any.
+    fn literal(&self) -> Option<&str> {
+        match self {
+            StrMatcher::Contains(n) | StrMatcher::StartsWith(n) => Some(n),
+            StrMatcher::Regex(_) => None,
+        }
+    }
+}
+
+/// String value of `field` for node `h`; `_slug` resolves to the node slug.
+fn string_field(db: &CoreDB, h: u64, field: &str) -> Option<String> {
+    if field == "_slug" {
+        return db.node_data(h).map(|n| n.slug.clone());
+    }
+    db.get_payload(h)
+        .and_then(|p| resolve_field(field, &p))
+        .and_then(|v| v.as_str().map(str::to_string))
+}
+
+/// If the step after a BFS traversal is a `HopFilter`, consume it (the BFS
+/// applies it while expanding) and return its predicate.
+fn hop_filter_after<'s>(remaining: &'s [Step], i: usize, skip_set: &mut HashSet<usize>) -> Option<&'s [Step]> {
+    match remaining.first() {
+        Some(Step::HopFilter(pred)) => {
+            skip_set.insert(i + 1);
+            Some(pred)
+        }
+        _ => None,
+    }
+}
+
+/// If the step after a single-hop traversal is an `EdgeWindow`, consume it
+/// and return its `(since, until)` bounds.
+fn edge_window_after(remaining: &[Step], i: usize, skip_set: &mut HashSet<usize>) -> Option<(i64, i64)> {
+    let window = edge_window(remaining);
+    if window.is_some() {
+        skip_set.insert(i + 1);
+    }
+    window
+}
+
+fn edge_window(after_traversal: &[Step]) -> Option<(i64, i64)> {
+    match after_traversal.first() {
+        Some(Step::EdgeWindow(since, until)) => Some((*since, *until)),
+        _ => None,
+    }
+}
+
+fn in_window(e: &crate::Edge, window: Option<(i64, i64)>) -> bool {
+    window.is_none_or(|(since, until)| (since..=until).contains(&e.created_unix))
+}
+
+fn hop_passes(db: &CoreDB, h: u64, pred: Option<&[Step]>) -> bool {
+    pred.is_none_or(|p| p.iter().all(|s| eval_cond(db, h, s)))
+}
+
 /// Evaluate a filter step directly against a `Value` payload (no DB lookup).
 /// Used for HAVING conditions evaluated against synthetic per-group payloads.
 /// Resolve a field value from a payload for WHERE/HAVING evaluation.
@@ -1986,6 +3548,8 @@ pub(crate) fn extract_fields_by_search(
 fn is_simple_field(expr: &str) -> bool {
     !expr.contains("__")
         && !expr.contains("->")
+        && !expr.contains('.')
+        && !expr.contains('[')
         && !expr.contains('*')
         && !expr.contains('(')
         && !expr.contains(')')
@@ -2015,6 +3579,23 @@ fn eval_step_on_payload(step: &Step, payload: &Value) -> bool {
             .and_then(|v| v.as_f64())
             .map(|f| f <= *t)
             .unwrap_or(false),
+        Step::WhereBetween(field, lo, hi) => resolve_field(field, payload)
+            .and_then(|v| v.as_f64())
+            .map(|f| f >= *lo && f <= *hi)
+            .unwrap_or(false),
+        Step::WhereIn(field, values) => resolve_field(field, payload)
+            .map(|v| value_in(&v, values))
+            .unwrap_or(false),
+        Step::Like(field, pattern, case_insensitive) => {
+            use crate::text_index::query::{ilike_matches, like_matches};
+            resolve_field(field, payload)
+                .and_then(|v| {
+                    v.as_str().map(|s| {
+                        if *case_insensitive { ilike_matches(s, pattern) } else { like_matches(s, pattern) }
+                    })
+                })
+                .unwrap_or(false)
+        }
         Step::ArrayContains(field, values) => resolve_field(field, payload)
             .and_then(|v| v.as_array().cloned())
             .map(|arr| values.iter().all(|needle| arr.contains(needle)))
@@ -2025,6 +3606,12 @@ fn eval_step_on_payload(step: &Step, payload: &Value) -> bool {
                 .unwrap_or(true);
             if *negated { !is_null } else { is_null }
         }
+        Step::WhereContains(..) | Step::WhereStartsWith(..) | Step::WhereRegex(..) => {
+            let (field, matcher) = StrMatcher::from_step(step).expect("string filter step");
+            resolve_field(field, payload)
+                .and_then(|v| v.as_str().map(|s| matcher.is_match(s)))
+                .unwrap_or(false)
+        }
         Step::WhereNot(inner) => !eval_step_on_payload(inner, payload),
         Step::WhereOr(branches) => branches
             .iter()
@@ -2037,6 +3624,9 @@ fn eval_step_on_payload(step: &Step, payload: &Value) -> bool {
 /// `true` when nested inside NOT/OR — callers should avoid those patterns
 /// for best results.
 fn eval_cond(db: &CoreDB, h: u64, step: &Step) -> bool {
+    if budget::spent() {
+        return false;
+    }
     match step {
         Step::WhereEq(field, value) => db
             .get_payload(h)
@@ -2108,6 +3698,12 @@ fn eval_cond(db: &CoreDB, h: u64, step: &Step) -> bool {
                 })
                 .unwrap_or(false)
         }
+        Step::WhereContains(..) | Step::WhereStartsWith(..) | Step::WhereRegex(..) => {
+            let (field, matcher) = StrMatcher::from_step(step).expect("string filter step");
+            string_field(db, h, field)
+                .map(|s| matcher.is_match(&s))
+                .unwrap_or(false)
+        }
         Step::WhereNot(inner) => !eval_cond(db, h, inner),
         Step::WhereOr(branches) => branches
             .iter()
@@ -2117,6 +3713,48 @@ fn eval_cond(db: &CoreDB, h: u64, step: &Step) -> bool {
     }
 }
 
+/// The filter steps a [`Set::any`] predicate stands for, all of which must hold.
+fn predicate_steps(field: &str, predicate: Value) -> Result<Vec<Step>, crate::SqlError> {
+    let bad = |msg: String| crate::SqlError::InvalidValue(msg);
+    let f = field.to_string();
+    let ops = match predicate {
+        Value::Null => return Ok(vec![Step::WhereIsNull(f, false)]),
+        Value::Object(ops) => ops,
+        Value::Array(_) => return Err(bad(format!("predicate on '{field}': use {{\"in\": [...]}} for a list"))),
+        scalar => return Ok(vec![Step::WhereEq(f, scalar)]),
+    };
+    if ops.is_empty() {
+        return Err(bad(format!("predicate on '{field}' has no operator")));
+    }
+    ops.into_iter()
+        .map(|(op, operand)| {
+            let number = || operand.as_f64().ok_or_else(|| bad(format!("'{op}' on '{field}' needs a number")));
+            let text = || {
+                operand.as_str().map(str::to_string).ok_or_else(|| bad(format!("'{op}' on '{field}' needs a string")))
+            };
+            Ok(match op.as_str() {
+                "eq" => Step::WhereEq(f.clone(), operand.clone()),
+                "ne" => Step::WhereNeq(f.clone(), operand.clone()),
+                "gt" => Step::WhereGt(f.clone(), number()?),
+                "gte" => Step::WhereGte(f.clone(), number()?),
+                "lt" => Step::WhereLt(f.clone(), number()?),
+                "lte" => Step::WhereLte(f.clone(), number()?),
+                "in" => match &operand {
+                    Value::Array(values) => Step::WhereIn(f.clone(), values.clone()),
+                    _ => return Err(bad(format!("'in' on '{field}' needs an array"))),
+                },
+                "like" => Step::Like(f.clone(), text()?, false),
+                "ilike" => Step::Like(f.clone(), text()?, true),
+                "is_null" => match operand {
+                    Value::Bool(is_null) => Step::WhereIsNull(f.clone(), !is_null),
+                    _ => return Err(bad(format!("'is_null' on '{field}' needs a bool"))),
+                },
+                other => return Err(bad(format!("unknown predicate operator '{other}'"))),
+            })
+        })
+        .collect()
+}
+
 // ── ScoreExpr helpers ─────────────────────────────────────────────────────────
 
 /// Collect all unique (field, query) pairs from BM25 leaves in the expression.
@@ -2227,19 +3865,348 @@ fn eval_score(
 
 // ── Executor ──────────────────────────────────────────────────────────────────
 
+/// Moving average of one filtering strategy's cost, in nanoseconds per item.
+#[derive(Debug)]
+pub(crate) struct StepCost(std::sync::atomic::AtomicU64);
+
+impl StepCost {
+    fn seeded(nanos: f64) -> Self {
+        Self(std::sync::atomic::AtomicU64::new(nanos.to_bits()))
+    }
+
+    fn nanos(&self) -> f64 {
+        f64::from_bits(self.0.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    fn record(&self, elapsed: std::time::Duration, items: usize) {
+        if items == 0 {
+            return;
+        }
+        let sample = elapsed.as_nanos() as f64 / items as f64;
+        let blended = self.nanos() * 0.8 + sample * 0.2;
+        self.0.store(blended.to_bits(), std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Measured costs of the ways a step can filter an existing candidate set.
+///
+/// The two spatial strategies start equal, so until timings arrive that
+/// choice is made on item counts alone. A payload check starts well above
+/// an index posting, so an index is preferred until timings say otherwise.
+#[derive(Debug)]
+pub(crate) struct FilterCosts {
+    /// Centroid lookup + Haversine for one candidate.
+    per_candidate: StepCost,
+    /// One grid entry walked (plus one set probe per candidate).
+    per_grid_entry: StepCost,
+    /// One candidate's payload read and tested.
+    per_payload_check: StepCost,
+    /// One btree posting or GIN bitmap entry collected (plus one set probe
+    /// per candidate).
+    per_index_entry: StepCost,
+}
+
+impl Default for FilterCosts {
+    fn default() -> Self {
+        Self {
+            per_candidate: StepCost::seeded(1.0),
+            per_grid_entry: StepCost::seeded(1.0),
+            per_payload_check: StepCost::seeded(1000.0),
+            per_index_entry: StepCost::seeded(20.0),
+        }
+    }
+}
+
+/// Whether filtering `n` candidates through an index that yields about
+/// `entries` ids is predicted to beat checking each candidate's payload.
+fn index_filter_pays(db: &CoreDB, n: usize, entries: usize) -> bool {
+    let costs = db.filter_costs();
+    ((entries + n) as f64) * costs.per_index_entry.nanos() < n as f64 * costs.per_payload_check.nanos()
+}
+
+/// Filter `candidates` either by intersecting with the ids `from_index`
+/// collects (about `entries` of them) or with `scan`, which checks each
+/// payload, whichever [`index_filter_pays`] picks, and time the one taken.
+/// Both must keep the same candidates.
+fn filter_with_index(
+    db: &CoreDB,
+    candidates: &mut Vec<u64>,
+    entries: usize,
+    from_index: impl FnOnce() -> HashSet<u64>,
+    scan: impl FnOnce(&mut Vec<u64>),
+) {
+    let n = candidates.len();
+    let costs = db.filter_costs();
+    let started = std::time::Instant::now();
+    if index_filter_pays(db, n, entries) {
+        let ids = from_index();
+        let walked = ids.len() + n;
+        candidates.retain(|h| ids.contains(h));
+        costs.per_index_entry.record(started.elapsed(), walked);
+    } else {
+        scan(candidates);
+        costs.per_payload_check.record(started.elapsed(), n);
+    }
+}
+
+/// Keep the candidates whose `field` resolves to a value passing `check`.
+fn retain_by_payload(db: &CoreDB, candidates: &mut Vec<u64>, field: &str, check: impl Fn(&Value) -> bool) {
+    candidates.retain(|&h| {
+        !budget::spent() && db.get_payload(h)
+            .and_then(|p| resolve_field(field, &p))
+            .is_some_and(|v| check(&v))
+    });
+}
+
+/// Keep the candidates whose `field` equals `value`, byte-searching batched
+/// raw payloads for simple fields on larger sets.
+fn retain_eq_by_payload(db: &CoreDB, candidates: &mut Vec<u64>, field: &str, value: &Value) {
+    const FILTER_BATCH_MIN: usize = 64;
+    if is_simple_field(field) && candidates.len() >= FILTER_BATCH_MIN {
+        let raw_map = db.read_raw_payloads_batched(candidates);
+        let fq = vec![field.to_string()];
+        candidates.retain(|&h| {
+            !budget::spent() && raw_map.get(&h)
+                .and_then(|bytes| {
+                    extract_fields_by_search(bytes, &fq).remove(field)
+                })
+                .map(|v| values_eq(&v, value))
+                .unwrap_or(false)
+        });
+    } else {
+        retain_by_payload(db, candidates, field, |v| values_eq(v, value));
+    }
+}
+
+/// Postings with a numeric key between `lo` and `hi`, interpolated over
+/// the index's numeric key span: the `total` ids it covers are assumed
+/// spread evenly between its smallest and largest number.
+fn estimate_numeric_range(idx: &BTreeMap<FieldKey, Vec<u64>>, lo: f64, hi: f64, total: usize) -> usize {
+    let number = |k: Option<(&FieldKey, _)>| match k {
+        Some((FieldKey::Number(n), _)) => Some(n.0),
+        _ => None,
+    };
+    let (Some(min), Some(max)) = (
+        number(idx.range(FieldKey::from_f64(f64::NEG_INFINITY)..).next()),
+        number(idx.range(..FieldKey::Str(String::new())).next_back()),
+    ) else {
+        return 0;
+    };
+    let (lo, hi) = (lo.max(min), hi.min(max));
+    if lo > hi {
+        return 0;
+    }
+    if max == min {
+        return total;
+    }
+    (((hi - lo) / (max - min)) * total as f64).ceil() as usize
+}
+
+/// Keep the candidates whose numeric `field` lies within `(lo, hi)`,
+/// through the field index when there is one and [`filter_with_index`]
+/// prefers it. Non-numeric values never match.
+fn retain_in_numeric_range(
+    db: &CoreDB,
+    candidates: &mut Vec<u64>,
+    coll: Option<u64>,
+    field: &str,
+    lo: std::ops::Bound<f64>,
+    hi: std::ops::Bound<f64>,
+) {
+    use std::ops::{Bound, RangeBounds};
+    let check = |v: &Value| v.as_f64().is_some_and(|f| (lo, hi).contains(&f));
+    let Some((coll, idx)) = coll.and_then(|c| Some((c, db.field_index(c, field)?))) else {
+        return retain_by_payload(db, candidates, field, check);
+    };
+    let edge = |b: Bound<f64>, open: f64| match b {
+        Bound::Included(f) | Bound::Excluded(f) => f,
+        Bound::Unbounded => open,
+    };
+    let total = db.collections.get(&coll).map_or(0, Vec::len);
+    filter_with_index(
+        db,
+        candidates,
+        estimate_numeric_range(idx, edge(lo, f64::NEG_INFINITY), edge(hi, f64::INFINITY), total),
+        || crate::numeric_index_range(idx, lo, hi).into_iter().collect(),
+        |c| retain_by_payload(db, c, field, check),
+    );
+}
+
+/// Whether a spatial filter over `n` candidates should check each one
+/// directly rather than intersect with a grid query. A fixed
+/// [`CoreDB::near_filter_threshold`] wins; otherwise compare the predicted
+/// cost of both from the grid's occupancy and recent timings.
+fn near_filter_per_node(db: &CoreDB, grid: &crate::geo::SpatialGrid, n: usize, lat: f64, km: f64) -> bool {
+    if let Some(threshold) = db.near_filter_threshold() {
+        return n < threshold;
+    }
+    let costs = db.filter_costs();
+    let walked = grid.estimate_within_distance(lat, km) + n;
+    n as f64 * costs.per_candidate.nanos() < walked as f64 * costs.per_grid_entry.nanos()
+}
+
+/// Score each candidate with its centroid's distance in km from `(lat, lon)`.
+fn score_by_km(db: &CoreDB, candidates: &[u64], scores: &mut HashMap<u64, f32>, lat: f64, lon: f64) {
+    for &h in candidates {
+        if let Some(m) = db.node_data(h).and_then(|n| n.spatial_meta.as_ref()) {
+            let km = crate::geo::haversine_km(m.centroid_lat, m.centroid_lon, lat, lon);
+            scores.insert(h, km as f32);
+        }
+    }
+}
+
+/// `ST_DWithin` as a starter (empty `candidates`) or a filter: keep nodes
+/// whose centroid lies within `distance_km` of `(lat, lon)`.
+fn retain_within_km(db: &CoreDB, candidates: &mut Vec<u64>, lat: f64, lon: f64, distance_km: f64) {
+    if let Some(grid) = db.spatial_grid() {
+        if candidates.is_empty() {
+            // STARTER: grid → exact Haversine (no large collection scan)
+            *candidates = grid
+                .candidates_within_distance(lat, lon, distance_km)
+                .into_iter()
+                .filter(|&h| {
+                    grid.get_meta(h)
+                        .map(|m| {
+                            crate::geo::haversine_km(
+                                m.centroid_lat,
+                                m.centroid_lon,
+                                lat,
+                                lon,
+                            ) <= distance_km
+                        })
+                        .unwrap_or(false)
+                })
+                .collect();
+        } else if near_filter_per_node(db, grid, candidates.len(), lat, distance_km) {
+            // FILTER, small set: check each candidate's cached centroid
+            let started = std::time::Instant::now();
+            let checked = candidates.len();
+            candidates.retain(|&h| {
+                grid.get_meta(h)
+                    .map(|m| {
+                        crate::geo::haversine_km(
+                            m.centroid_lat,
+                            m.centroid_lon,
+                            lat,
+                            lon,
+                        ) <= distance_km
+                    })
+                    .unwrap_or(false)
+            });
+            db.filter_costs().per_candidate.record(started.elapsed(), checked);
+        } else {
+            // FILTER: intersect current candidates with grid result
+            let started = std::time::Instant::now();
+            let checked = candidates.len();
+            let grid_set: HashSet<u64> = grid
+                .candidates_within_distance(lat, lon, distance_km)
+                .into_iter()
+                .collect();
+            let walked = grid_set.len() + checked;
+            candidates.retain(|h| grid_set.contains(h));
+            candidates.retain(|&h| {
+                grid.get_meta(h)
+                    .map(|m| {
+                        crate::geo::haversine_km(
+                            m.centroid_lat,
+                            m.centroid_lon,
+                            lat,
+                            lon,
+                        ) <= distance_km
+                    })
+                    .unwrap_or(false)
+            });
+            db.filter_costs().per_grid_entry.record(started.elapsed(), walked);
+        }
+    } else {
+        if candidates.is_empty() {
+            *candidates = db.all_hashes();
+        }
+        candidates.retain(|&h| {
+            db.get_payload(h)
+                .and_then(|p| crate::geo::extract_centroid(&p))
+                .map(|(clat, clon)| {
+                    crate::geo::haversine_km(clat, clon, lat, lon) <= distance_km
+                })
+                .unwrap_or(false)
+        });
+    }
+}
+
+/// Steps that, handed no candidates, start from an index or from every
+/// node. That only holds at the head of a pipeline: after a collection or an
+/// earlier filter, an empty set means nothing matched.
+fn starts_when_empty(step: &Step) -> bool {
+    matches!(
+        step,
+        Step::Like(..)
+            | Step::StDWithin(..)
+            | Step::NearNode(..)
+            | Step::StContainsPoint(..)
+            | Step::StWithin(..)
+            | Step::StContains(..)
+            | Step::StIntersects(..)
+            | Step::StDistance(..)
+            | Step::StLength(..)
+            | Step::StArea(..)
+            | Step::SearchFilter(..)
+            | Step::Bm25Filter(..)
+    )
+}
+
 /// Execute the step pipeline and return candidate slug hashes in order.
 fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
+    execute_traced(db, steps, None)
+}
+
+/// [`execute`], timing each step into `trace` when given.
+fn execute_traced(db: &CoreDB, steps: &[Step], trace: Option<&mut Vec<StepTrace>>) -> Vec<u64> {
+    execute_scored(db, steps, trace).0
+}
+
+/// [`execute_traced`], also returning the score the last scoring step gave
+/// each node (see [`Hit::score`]). A step that sources nodes afresh — a
+/// starter or a traversal — drops the scores before it.
+fn execute_scored(
+    db: &CoreDB,
+    steps: &[Step],
+    mut trace: Option<&mut Vec<StepTrace>>,
+) -> (Vec<u64>, HashMap<u64, f32>) {
     let mut candidates: Vec<u64> = Vec::new();
+    let mut scores: HashMap<u64, f32> = HashMap::new();
     // Steps consumed by btree_seed (already applied as the seed filter)
     let mut skip_set: HashSet<usize> = HashSet::new();
     // Track the active collection hash so post-seed filters can use btree indexes.
     let mut current_coll_hash: Option<u64> = None;
 
     for (i, step) in steps.iter().enumerate() {
+        if budget::check() {
+            return (Vec::new(), HashMap::new());
+        }
         if skip_set.contains(&i) {
             continue;
         }
+        if matches!(
+            step,
+            Step::One(_)
+                | Step::Many(_)
+                | Step::Collection(_)
+                | Step::All
+                | Step::Forward(_)
+                | Step::Backward(_)
+                | Step::Both(_)
+                | Step::Hops(_)
+                | Step::HopsTyped { .. }
+        ) {
+            scores.clear();
+        }
+        if candidates.is_empty() && starts_when_empty(step) && !steps[..i].iter().all(|s| matches!(s, Step::All)) {
+            continue;
+        }
         let remaining = &steps[i + 1..];
+        let started = trace.is_some().then(|| (std::time::Instant::now(), candidates.len()));
+        let mut children = Vec::new();
+        let sub_trace = if trace.is_some() { Some(&mut children) } else { None };
         match step {
             // ── Starters ────────────────────────────────────────────────────
             Step::One(hash) => {
@@ -2281,11 +4248,12 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
 
             // ── Graph traversal ──────────────────────────────────────────────
             Step::Forward(type_hash) => {
+                let window = edge_window_after(remaining, i, &mut skip_set);
                 let mut next: HashSet<u64> = HashSet::new();
                 for &node in &candidates {
                     if let Some(edges) = db.fwd_edges(node) {
                         for e in edges {
-                            if e.edge_type == *type_hash {
+                            if e.edge_type == *type_hash && in_window(e, window) {
                                 next.insert(e.other);
                             }
                         }
@@ -2298,11 +4266,12 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     .collect();
             }
             Step::Backward(type_hash) => {
+                let window = edge_window_after(remaining, i, &mut skip_set);
                 let mut next: HashSet<u64> = HashSet::new();
                 for &node in &candidates {
                     if let Some(edges) = db.rev_edges(node) {
                         for e in edges {
-                            if e.edge_type == *type_hash {
+                            if e.edge_type == *type_hash && in_window(e, window) {
                                 next.insert(e.other);
                             }
                         }
@@ -2313,17 +4282,47 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     .filter(|&h| db.node_data(h).is_some())
                     .collect();
             }
+            Step::Both(type_hash) => {
+                let window = edge_window_after(remaining, i, &mut skip_set);
+                let mut next: HashSet<u64> = HashSet::new();
+                for &node in &candidates {
+                    let fwd = db.fwd_edges(node).into_iter().flatten();
+                    for e in fwd.chain(db.rev_edges(node).into_iter().flatten()) {
+                        if e.edge_type == *type_hash && in_window(e, window) {
+                            next.insert(e.other);
+                        }
+                    }
+                }
+                candidates = next
+                    .into_iter()
+                    .filter(|&h| db.node_data(h).is_some())
+                    .collect();
+            }
             Step::Hops(n) => {
                 // BFS: expand forward over any edge type, up to n levels.
+                let hop_pred = hop_filter_after(remaining, i, &mut skip_set);
+                let cap = db.frontier_cap();
+                let mut pruned: HashSet<u64> = HashSet::new();
                 let mut visited: HashSet<u64> = candidates.iter().copied().collect();
                 let mut frontier: Vec<u64> = candidates.clone();
-                for _ in 0..*n {
+                'bfs: for _ in 0..*n {
                     let mut next: Vec<u64> = Vec::new();
                     for &node in &frontier {
+                        if budget::spent() {
+                            break;
+                        }
                         if let Some(edges) = db.fwd_edges(node) {
                             for e in edges {
-                                if visited.insert(e.other) {
+                                if visited.contains(&e.other) || pruned.contains(&e.other) {
+                                    continue;
+                                }
+                                if !hop_passes(db, e.other, hop_pred) {
+                                    pruned.insert(e.other);
+                                } else if visited.insert(e.other) {
                                     next.push(e.other);
+                                    if !budget::frontier_fits(cap, visited.len()) {
+                                        break 'bfs;
+                                    }
                                 }
                             }
                         }
@@ -2345,16 +4344,31 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
             } => {
                 // Typed BFS: follow only edges matching type_hash.
                 // Collect nodes reached at depths min_depth..=max_depth.
+                let hop_pred = hop_filter_after(remaining, i, &mut skip_set);
+                let cap = db.frontier_cap();
                 let mut visited: HashSet<u64> = HashSet::new();
                 let mut frontier: Vec<u64> = candidates.clone();
                 let mut result: Vec<u64> = Vec::new();
-                for depth in 1..=*max_depth {
+                'bfs: for depth in 1..=*max_depth {
                     let mut next: Vec<u64> = Vec::new();
                     for &node in &frontier {
+                        if budget::spent() {
+                            break;
+                        }
                         if let Some(edges) = db.fwd_edges(node) {
                             for e in edges {
-                                if e.edge_type == *type_hash && visited.insert(e.other) {
+                                if e.edge_type == *type_hash
+                                    && !visited.contains(&e.other)
+                                    && hop_passes(db, e.other, hop_pred)
+                                    && visited.insert(e.other)
+                                {
                                     next.push(e.other);
+                                    if !budget::frontier_fits(cap, visited.len()) {
+                                        if depth >= *min_depth {
+                                            result.extend(&next);
+                                        }
+                                        break 'bfs;
+                                    }
                                 }
                             }
                         }
@@ -2372,6 +4386,13 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     .filter(|&h| db.node_data(h).is_some())
                     .collect();
             }
+            Step::EdgeWindow(..) => {
+                // Consumed by the traversal it follows; on its own it is a no-op.
+            }
+            Step::HopFilter(pred) => {
+                // Not fused into a BFS above: filter the traversal's output.
+                candidates.retain(|&h| hop_passes(db, h, Some(pred)));
+            }
             Step::MinStrength(threshold) => {
                 // Find the most recent Forward/Backward step to know which edge type to check.
                 // Walk backwards through the step list up to (but not including) this step.
@@ -2386,21 +4407,25 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     })
                     .unwrap_or(0);
                 let edge_type_hash = steps[..this_pos].iter().rev().find_map(|s| match s {
-                    Step::Forward(h) | Step::Backward(h) => Some(*h),
+                    Step::Forward(h) | Step::Backward(h) => Some((*h, false)),
+                    Step::Both(h) => Some((*h, true)),
                     _ => None,
                 });
-                if let Some(type_h) = edge_type_hash {
+                if let Some((type_h, undirected)) = edge_type_hash {
                     let thr = *threshold;
-                    candidates.retain(|&dest| {
-                        // dest is reachable — check that at least one incoming edge of the
-                        // correct type has strength >= threshold.
-                        db.rev_edges(dest)
+                    let strong = |edges: Option<&[crate::Edge]>| {
+                        edges
                             .map(|edges| {
                                 edges
                                     .iter()
                                     .any(|e| e.edge_type == type_h && e.strength >= thr)
                             })
                             .unwrap_or(false)
+                    };
+                    candidates.retain(|&dest| {
+                        // dest is reachable — check that at least one incoming edge of the
+                        // correct type has strength >= threshold (either direction for Both).
+                        strong(db.rev_edges(dest)) || (undirected && strong(db.fwd_edges(dest)))
                     });
                 }
                 // If no prior Forward/Backward found, MinStrength is a no-op.
@@ -2425,256 +4450,100 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
             // WhereEq: try btree intersection first (zero payload reads).
             // Falls back to batch pread + byte-search, then individual pread.
             Step::WhereEq(field, value) => {
-                // Btree intersection: O(|btree_set|) HashSet build + O(|candidates|) retain.
-                // Zero payload reads — the index already maps value → [hash, …].
-                if let (Some(coll), Some(fk)) = (
-                    current_coll_hash,
-                    FieldKey::from_json(value),
-                ) {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let btree_set: HashSet<u64> = idx
-                            .get(&fk)
-                            .into_iter()
-                            .flat_map(|ids| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                        // Skip payload reads entirely — done.
-                    } else {
-                        // No index: batch read + byte-search.
-                        const FILTER_BATCH_MIN: usize = 64;
-                        if is_simple_field(field) && candidates.len() >= FILTER_BATCH_MIN {
-                            let raw_map = db.read_raw_payloads_batched(&candidates);
-                            let fq = vec![field.clone()];
-                            candidates.retain(|&h| {
-                                raw_map.get(&h)
-                                    .and_then(|bytes| {
-                                        extract_fields_by_search(bytes, &fq).remove(field.as_str())
-                                    })
-                                    .map(|v| values_eq(&v, value))
-                                    .unwrap_or(false)
-                            });
-                        } else {
-                            candidates.retain(|&h| {
-                                db.get_payload(h)
-                                    .and_then(|p| resolve_field(field, &p))
-                                    .map(|v| values_eq(&v, value))
-                                    .unwrap_or(false)
-                            });
-                        }
-                    }
-                } else {
-                    // No collection context or non-indexable value: batch read + byte-search.
-                    const FILTER_BATCH_MIN: usize = 64;
-                    if is_simple_field(field) && candidates.len() >= FILTER_BATCH_MIN {
-                        let raw_map = db.read_raw_payloads_batched(&candidates);
-                        let fq = vec![field.clone()];
-                        candidates.retain(|&h| {
-                            raw_map.get(&h)
-                                .and_then(|bytes| {
-                                    extract_fields_by_search(bytes, &fq).remove(field.as_str())
-                                })
-                                .map(|v| values_eq(&v, value))
-                                .unwrap_or(false)
-                        });
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .map(|v| values_eq(&v, value))
-                                .unwrap_or(false)
-                        });
+                // Btree intersection: zero payload reads, the index already
+                // maps value → [hash, …]. Null keys also hold nodes without
+                // the field, so they always go through the index.
+                let indexed = current_coll_hash
+                    .and_then(|c| db.field_index(c, field))
+                    .zip(FieldKey::from_json(value));
+                match indexed {
+                    Some((idx, FieldKey::Null)) => {
+                        let ids: HashSet<u64> =
+                            idx.get(&FieldKey::Null).into_iter().flatten().copied().collect();
+                        candidates.retain(|h| ids.contains(h));
                     }
-                }
-            }
-            Step::WhereNeq(field, value) => {
-                const FILTER_BATCH_MIN: usize = 64;
-                if is_simple_field(field) && candidates.len() >= FILTER_BATCH_MIN {
-                    let raw_map = db.read_raw_payloads_batched(&candidates);
-                    let fq = vec![field.clone()];
-                    candidates.retain(|&h| {
-                        // field absent → keep (same semantics as individual path)
-                        raw_map.get(&h)
-                            .map(|bytes| {
-                                extract_fields_by_search(bytes, &fq)
-                                    .remove(field.as_str())
-                                    .map(|v| !values_eq(&v, value))
-                                    .unwrap_or(true)
-                            })
-                            .unwrap_or(true)
-                    });
-                } else {
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .map(|v| !values_eq(&v, value))
-                            .unwrap_or(true) // field absent → keep
-                    });
-                }
-            }
-            Step::WhereGt(field, threshold) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let lo = FieldKey::from_f64(*threshold);
-                        let btree_set: HashSet<u64> = idx
-                            .range((std::ops::Bound::Excluded(lo), std::ops::Bound::Unbounded))
-                            .flat_map(|(_, ids)| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .and_then(|v| v.as_f64())
-                                .map(|f| f > *threshold)
-                                .unwrap_or(false)
-                        });
-                    }
-                } else {
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .and_then(|v| v.as_f64())
-                            .map(|f| f > *threshold)
-                            .unwrap_or(false)
-                    });
-                }
-            }
-            Step::WhereLt(field, threshold) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let hi = FieldKey::from_f64(*threshold);
-                        let btree_set: HashSet<u64> = idx
-                            .range((std::ops::Bound::Unbounded, std::ops::Bound::Excluded(hi)))
-                            .flat_map(|(_, ids)| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .and_then(|v| v.as_f64())
-                                .map(|f| f < *threshold)
-                                .unwrap_or(false)
-                        });
+                    Some((idx, fk)) => {
+                        let ids = idx.get(&fk).map_or(&[][..], Vec::as_slice);
+                        filter_with_index(
+                            db,
+                            &mut candidates,
+                            ids.len(),
+                            || ids.iter().copied().collect(),
+                            |c| retain_eq_by_payload(db, c, field, value),
+                        );
                     }
-                } else {
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .and_then(|v| v.as_f64())
-                            .map(|f| f < *threshold)
-                            .unwrap_or(false)
-                    });
+                    None => retain_eq_by_payload(db, &mut candidates, field, value),
                 }
             }
-            Step::WhereGte(field, threshold) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let lo = FieldKey::from_f64(*threshold);
-                        let btree_set: HashSet<u64> = idx
-                            .range(lo..)
-                            .flat_map(|(_, ids)| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .and_then(|v| v.as_f64())
-                                .map(|f| f >= *threshold)
-                                .unwrap_or(false)
-                        });
-                    }
-                } else {
+            Step::WhereNeq(field, value) => {
+                const FILTER_BATCH_MIN: usize = 64;
+                if is_simple_field(field) && candidates.len() >= FILTER_BATCH_MIN {
+                    let raw_map = db.read_raw_payloads_batched(&candidates);
+                    let fq = vec![field.clone()];
                     candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .and_then(|v| v.as_f64())
-                            .map(|f| f >= *threshold)
-                            .unwrap_or(false)
+                        // field absent → keep (same semantics as individual path)
+                        !budget::spent() && raw_map.get(&h)
+                            .map(|bytes| {
+                                extract_fields_by_search(bytes, &fq)
+                                    .remove(field.as_str())
+                                    .map(|v| !values_eq(&v, value))
+                                    .unwrap_or(true)
+                            })
+                            .unwrap_or(true)
                     });
-                }
-            }
-            Step::WhereLte(field, threshold) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let hi = FieldKey::from_f64(*threshold);
-                        let btree_set: HashSet<u64> = idx
-                            .range(..=hi)
-                            .flat_map(|(_, ids)| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .and_then(|v| v.as_f64())
-                                .map(|f| f <= *threshold)
-                                .unwrap_or(false)
-                        });
-                    }
                 } else {
                     candidates.retain(|&h| {
-                        db.get_payload(h)
+                        !budget::spent() && db.get_payload(h)
                             .and_then(|p| resolve_field(field, &p))
-                            .and_then(|v| v.as_f64())
-                            .map(|f| f <= *threshold)
-                            .unwrap_or(false)
+                            .map(|v| !values_eq(&v, value))
+                            .unwrap_or(true) // field absent → keep
                     });
                 }
             }
+            Step::WhereGt(field, threshold) => {
+                use std::ops::Bound::*;
+                retain_in_numeric_range(db, &mut candidates, current_coll_hash, field, Excluded(*threshold), Unbounded);
+            }
+            Step::WhereLt(field, threshold) => {
+                use std::ops::Bound::*;
+                retain_in_numeric_range(db, &mut candidates, current_coll_hash, field, Unbounded, Excluded(*threshold));
+            }
+            Step::WhereGte(field, threshold) => {
+                use std::ops::Bound::*;
+                retain_in_numeric_range(db, &mut candidates, current_coll_hash, field, Included(*threshold), Unbounded);
+            }
+            Step::WhereLte(field, threshold) => {
+                use std::ops::Bound::*;
+                retain_in_numeric_range(db, &mut candidates, current_coll_hash, field, Unbounded, Included(*threshold));
+            }
             Step::WhereBetween(field, lo, hi) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
-                        let lo_key = FieldKey::from_f64(*lo);
-                        let hi_key = FieldKey::from_f64(*hi);
-                        let btree_set: HashSet<u64> = idx
-                            .range(lo_key..=hi_key)
-                            .flat_map(|(_, ids)| ids.iter().copied())
-                            .collect();
-                        candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .and_then(|v| v.as_f64())
-                                .map(|f| f >= *lo && f <= *hi)
-                                .unwrap_or(false)
-                        });
-                    }
-                } else {
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .and_then(|v| v.as_f64())
-                            .map(|f| f >= *lo && f <= *hi)
-                            .unwrap_or(false)
-                    });
-                }
+                use std::ops::Bound::*;
+                retain_in_numeric_range(db, &mut candidates, current_coll_hash, field, Included(*lo), Included(*hi));
             }
             Step::WhereIn(field, values) => {
-                if let Some(coll) = current_coll_hash {
-                    if let Some(idx) = db.field_index(coll, field) {
+                let check = |v: &Value| value_in(v, values);
+                match current_coll_hash.and_then(|c| db.field_index(c, field)) {
+                    Some(idx) if !values.iter().any(Value::is_null) => {
+                        let postings: Vec<&[u64]> = values.iter()
+                            .filter_map(FieldKey::from_json)
+                            .filter_map(|fk| idx.get(&fk).map(Vec::as_slice))
+                            .collect();
+                        filter_with_index(
+                            db,
+                            &mut candidates,
+                            postings.iter().map(|ids| ids.len()).sum(),
+                            || postings.iter().flat_map(|ids| ids.iter().copied()).collect(),
+                            |c| retain_by_payload(db, c, field, check),
+                        );
+                    }
+                    Some(idx) => {
                         let btree_set: HashSet<u64> = values.iter()
-                            .filter_map(|v| FieldKey::from_json(v))
+                            .filter_map(FieldKey::from_json)
                             .flat_map(|fk| idx.get(&fk).into_iter().flat_map(|ids| ids.iter().copied()))
                             .collect();
                         candidates.retain(|h| btree_set.contains(h));
-                    } else {
-                        candidates.retain(|&h| {
-                            db.get_payload(h)
-                                .and_then(|p| resolve_field(field, &p))
-                                .map(|v| value_in(&v, values))
-                                .unwrap_or(false)
-                        });
                     }
-                } else {
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| resolve_field(field, &p))
-                            .map(|v| value_in(&v, values))
-                            .unwrap_or(false)
-                    });
+                    None => retain_by_payload(db, &mut candidates, field, check),
                 }
             }
             Step::ArrayContains(field, values) => {
@@ -2693,7 +4562,40 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                 candidates.retain(|&h| eval_cond(db, h, step) == true);
                 let _ = (field, negated); // used in eval_cond
             }
-            Step::WhereNot(_) | Step::WhereOr(_) => {
+            Step::WhereContains(..) | Step::WhereStartsWith(..) | Step::WhereRegex(..) => {
+                let (field, matcher) = StrMatcher::from_step(step).expect("string filter step");
+                // A GIN trigram index narrows literal needles to a (case-folded)
+                // superset first; every survivor is still verified below.
+                if let Some(needle) = matcher.literal() {
+                    if needle.chars().count() >= 3
+                        && !needle.contains('%')
+                        && db.gin_indexes.contains_key(field)
+                    {
+                        let gin_set: HashSet<u64> = db
+                            .gin_ilike(field, &format!("%{needle}%"), None)
+                            .into_iter()
+                            .collect();
+                        candidates.retain(|h| gin_set.contains(h));
+                    }
+                }
+                candidates.retain(|&h| {
+                    string_field(db, h, field)
+                        .map(|s| matcher.is_match(&s))
+                        .unwrap_or(false)
+                });
+            }
+            Step::WhereOr(branches) => {
+                // All-indexed equality OR (e.g. `where_any_of`): union the
+                // per-field btree lookups instead of reading payloads.
+                match current_coll_hash.and_then(|c| db.or_eq_index_union(c, branches)) {
+                    Some(ids) => {
+                        let btree_set: HashSet<u64> = ids.into_iter().collect();
+                        candidates.retain(|h| btree_set.contains(h));
+                    }
+                    None => candidates.retain(|&h| eval_cond(db, h, step)),
+                }
+            }
+            Step::WhereNot(_) => {
                 candidates.retain(|&h| eval_cond(db, h, step));
             }
             Step::Like(field, pattern, case_insensitive) => {
@@ -2706,19 +4608,35 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                 // If GIN index exists for this field and returns 0 candidates, the index
                 // is complete (every document trigram was indexed), so 0 means no match.
                 // Skip the expensive brute-force scan entirely.
-                let gin_has_index = db.gin_indexes.contains_key(field.as_str());
-                let gin_results = db.gin_ilike(field, pattern, take_limit);
-                if gin_has_index && gin_results.is_empty() {
+                // Text indexes span every collection, so a LIMIT can only
+                // cut the index lookup when it starts the pipeline; as a
+                // filter, other collections' hits would use it up.
+                let index_limit = if candidates.is_empty() { take_limit } else { None };
+                let verify = |h: u64| -> bool {
+                    !budget::spent() && db.get_payload(h)
+                        .and_then(|p| json_path_get(field, &p))
+                        .and_then(|v| v.as_str().map(|s| s.to_string()))
+                        .map(|s| if *case_insensitive { ilike_matches(&s, pattern) }
+                                 else { like_matches(&s, pattern) })
+                        .unwrap_or(false)
+                };
+                // As a filter over few candidates, verifying each payload
+                // can beat collecting the GIN bitmap intersection.
+                let gin = db.gin_indexes.get(field.as_str());
+                let verify_payloads = gin.is_some_and(|gin| {
+                    !candidates.is_empty() && !index_filter_pays(db, candidates.len(), gin.estimate_ilike(pattern))
+                });
+                let gin_has_index = gin.is_some();
+                let gin_results =
+                    if verify_payloads { Vec::new() } else { db.gin_ilike(field, pattern, index_limit) };
+                if verify_payloads {
+                    let started = std::time::Instant::now();
+                    let n = candidates.len();
+                    candidates.retain(|&h| verify(h));
+                    db.filter_costs().per_payload_check.record(started.elapsed(), n);
+                } else if gin_has_index && gin_results.is_empty() {
                     candidates.clear();
                 } else if !gin_results.is_empty() {
-                    let verify = |h: u64| -> bool {
-                        db.get_payload(h)
-                            .and_then(|p| json_path_get(field, &p))
-                            .and_then(|v| v.as_str().map(|s| s.to_string()))
-                            .map(|s| if *case_insensitive { ilike_matches(&s, pattern) }
-                                     else { like_matches(&s, pattern) })
-                            .unwrap_or(false)
-                    };
                     if candidates.is_empty() {
                         // STARTER: verify GIN candidates
                         candidates = gin_results.into_iter().filter(|&h| verify(h)).collect();
@@ -2729,7 +4647,7 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                         candidates.retain(|h| gin_set.contains(h) && verify(*h));
                     }
                 } else if let Some(candidates_from_index) =
-                    db.text_index_candidates_with_limit(field, pattern, take_limit)
+                    db.text_index_candidates_with_limit(field, pattern, index_limit)
                 {
                     // GiST is lossy — use verify() with cached text + memchr for fast verification
                     if candidates.is_empty() {
@@ -2819,57 +4737,18 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
             // forces, which is slow but correct.  Production use should always call
             // `db.build_spatial_index()` before running spatial queries.
             Step::StDWithin(lat, lon, distance_km) => {
-                if let Some(grid) = db.spatial_grid() {
-                    if candidates.is_empty() {
-                        // STARTER: grid → exact Haversine (no large collection scan)
-                        candidates = grid
-                            .candidates_within_distance(*lat, *lon, *distance_km)
-                            .into_iter()
-                            .filter(|&h| {
-                                grid.get_meta(h)
-                                    .map(|m| {
-                                        crate::geo::haversine_km(
-                                            m.centroid_lat,
-                                            m.centroid_lon,
-                                            *lat,
-                                            *lon,
-                                        ) <= *distance_km
-                                    })
-                                    .unwrap_or(false)
-                            })
-                            .collect();
-                    } else {
-                        // FILTER: intersect current candidates with grid result
-                        let grid_set: HashSet<u64> = grid
-                            .candidates_within_distance(*lat, *lon, *distance_km)
-                            .into_iter()
-                            .collect();
-                        candidates.retain(|h| grid_set.contains(h));
-                        candidates.retain(|&h| {
-                            grid.get_meta(h)
-                                .map(|m| {
-                                    crate::geo::haversine_km(
-                                        m.centroid_lat,
-                                        m.centroid_lon,
-                                        *lat,
-                                        *lon,
-                                    ) <= *distance_km
-                                })
-                                .unwrap_or(false)
-                        });
-                    }
-                } else {
-                    if candidates.is_empty() {
-                        candidates = db.all_hashes();
+                retain_within_km(db, &mut candidates, *lat, *lon, *distance_km);
+                score_by_km(db, &candidates, &mut scores, *lat, *lon);
+            }
+            Step::NearNode(anchor, distance_km) => {
+                match db.node_data(*anchor).and_then(|n| n.spatial_meta.as_ref()) {
+                    Some(m) => {
+                        let (lat, lon) = (m.centroid_lat, m.centroid_lon);
+                        retain_within_km(db, &mut candidates, lat, lon, *distance_km);
+                        candidates.retain(|h| h != anchor);
+                        score_by_km(db, &candidates, &mut scores, lat, lon);
                     }
-                    candidates.retain(|&h| {
-                        db.get_payload(h)
-                            .and_then(|p| crate::geo::extract_centroid(&p))
-                            .map(|(clat, clon)| {
-                                crate::geo::haversine_km(clat, clon, *lat, *lon) <= *distance_km
-                            })
-                            .unwrap_or(false)
-                    });
+                    None => candidates.clear(),
                 }
             }
             Step::StContainsPoint(lat, lon) => {
@@ -3085,7 +4964,8 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     candidates = db.all_hashes();
                 }
                 candidates.retain(|&h| {
-                    db.get_payload(h)
+                    let km = db
+                        .get_payload(h)
                         .and_then(|p| p.get(&*field).cloned())
                         .and_then(|geom| {
                             crate::geo::distance_km(
@@ -3096,8 +4976,11 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                                 }),
                             )
                         })
-                        .map(|d| d < *max_km)
-                        .unwrap_or(false)
+                        .filter(|d| d < max_km);
+                    if let Some(km) = km {
+                        scores.insert(h, km as f32);
+                    }
+                    km.is_some()
                 });
             }
             Step::StLength(field, min_km) => {
@@ -3128,20 +5011,44 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
             }
             Step::VectorNear { field, query, k } => {
                 use crate::vector::{CosineDistance, Distance};
+                // Above this many scoped candidates, a filtered HNSW walk beats
+                // scoring every candidate vector.
+                const SCOPED_HNSW_MIN: usize = 1024;
+                // Only a leading search (nothing but `All` before it) may pull
+                // from the whole field; an empty scoped set stays empty.
+                let is_starter = steps[..i].iter().all(|s| matches!(s, Step::All));
+                if !is_starter && candidates.is_empty() {
+                    continue;
+                }
                 if let Some(field_vecs) = db.vector_field(field) {
+                    let ef = (*k * 3).max(50);
                     // ── HNSW fast path ────────────────────────────────────────
-                    if candidates.is_empty() {
-                        if let Some(hnsw) = db.hnsw_index(field) {
+                    let cosine = |h: u64| field_vecs.get(h).map(|v| CosineDistance::eval(query, v));
+                    if let Some(hnsw) = db.hnsw_index(field) {
+                        if is_starter {
                             // HNSW STARTER: approximate search over all vectors.
-                            let ef = (*k * 3).max(50);
                             candidates =
                                 hnsw.search::<CosineDistance, _>(query, field_vecs, *k, ef);
-                            // Skip to next step — HNSW result is already top-k.
+                            scores.extend(candidates.iter().filter_map(|&h| Some((h, cosine(h)?))));
+                            continue;
+                        }
+                        if candidates.len() >= SCOPED_HNSW_MIN {
+                            // Scoped: expand through the whole graph but only
+                            // keep candidates, so the result still fills k.
+                            let set: HashSet<u64> = candidates.iter().copied().collect();
+                            candidates = hnsw.search_filtered::<CosineDistance, _>(
+                                query,
+                                field_vecs,
+                                *k,
+                                ef,
+                                |h| set.contains(&h),
+                            );
+                            scores.extend(candidates.iter().filter_map(|&h| Some((h, cosine(h)?))));
                             continue;
                         }
                     }
                     // ── Flat-scan fallback ────────────────────────────────────
-                    let mut scored: Vec<(u64, f32)> = if candidates.is_empty() {
+                    let mut scored: Vec<(u64, f32)> = if is_starter {
                         // STARTER: scan all vectors in this field
                         field_vecs
                             .iter()
@@ -3161,6 +5068,7 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                             .unwrap_or(std::cmp::Ordering::Equal)
                     });
                     scored.truncate(*k);
+                    scores.extend(scored.iter().copied());
                     candidates = scored.into_iter().map(|(h, _)| h).collect();
                 } else {
                     candidates = vec![];
@@ -3195,19 +5103,22 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                 if candidates.is_empty() {
                     candidates = db.all_hashes();
                 }
-                let min_score = *min_score;
-                candidates.retain(|&h| {
-                    if let Some(hits) = db.bm25_indexes.get(field) {
-                        let results = hits.search(query, 100);
-                        results.iter().any(|r| r.doc_id == h && r.score > min_score)
-                    } else {
-                        false
-                    }
-                });
+                // Score every indexed document: a top-k cut over the shared
+                // index could be filled by other collections.
+                let bm25: HashMap<u64, f64> = db
+                    .bm25_indexes
+                    .get(field)
+                    .map(|index| index.search(query, index.num_docs() as usize))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|r| (r.doc_id, r.score))
+                    .collect();
+                candidates.retain(|h| bm25.get(h).is_some_and(|&s| s > *min_score));
+                scores.extend(candidates.iter().map(|h| (*h, bm25[h] as f32)));
             }
             Step::Bm25Sort(field, query, ascending) => {
                 if let Some(index) = db.bm25_indexes.get(field) {
-                    let hits = index.search(query, candidates.len().max(100));
+                    let hits = index.search(query, index.num_docs() as usize);
                     let asc = *ascending;
                     let score_map: HashMap<u64, f64> =
                         hits.iter().map(|h| (h.doc_id, h.score)).collect();
@@ -3221,19 +5132,33 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                             ord.reverse()
                         }
                     });
+                    for &h in &candidates {
+                        scores.insert(h, score_map.get(&h).copied().unwrap_or(0.0) as f32);
+                    }
                 }
             }
             Step::ScoreProject(_) => {
                 // Score projection annotation happens in collect(), not execute()
             }
+            Step::Hybrid { vector_field, query, text_field, text, k, fusion } => {
+                let is_starter = steps[..i].iter().all(|s| matches!(s, Step::All));
+                if !is_starter && candidates.is_empty() {
+                    continue;
+                }
+                let scope: Option<HashSet<u64>> =
+                    (!is_starter).then(|| candidates.iter().copied().collect());
+                let ranked = hybrid_rank(db, scope.as_ref(), (vector_field, query), (text_field, text), *k, *fusion);
+                scores.extend(ranked.iter().map(|&(h, s)| (h, s as f32)));
+                candidates = ranked.into_iter().map(|(h, _)| h).collect();
+            }
 
             // ── Set algebra ──────────────────────────────────────────────────
             Step::Intersect(sub_steps) => {
-                let other: HashSet<u64> = execute(db, sub_steps).into_iter().collect();
+                let other: HashSet<u64> = execute_traced(db, sub_steps, sub_trace).into_iter().collect();
                 candidates.retain(|h| other.contains(h));
             }
             Step::Union(sub_steps) => {
-                let other = execute(db, sub_steps);
+                let other = execute_traced(db, sub_steps, sub_trace);
                 let existing: HashSet<u64> = candidates.iter().copied().collect();
                 for h in other {
                     if !existing.contains(&h) {
@@ -3242,7 +5167,7 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                 }
             }
             Step::Subtract(sub_steps) => {
-                let other: HashSet<u64> = execute(db, sub_steps).into_iter().collect();
+                let other: HashSet<u64> = execute_traced(db, sub_steps, sub_trace).into_iter().collect();
                 candidates.retain(|h| !other.contains(h));
             }
 
@@ -3259,7 +5184,7 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                         if let Some(idx) = db.field_index(coll, sort_field) {
                             let candidate_set: HashSet<u64> =
                                 candidates.iter().copied().collect();
-                            let limit = find_take_limit(remaining).unwrap_or(usize::MAX);
+                            let limit = find_sort_limit(remaining).unwrap_or(usize::MAX);
                             let mut sorted_result: Vec<u64> =
                                 Vec::with_capacity(limit.min(candidate_set.len()));
                             if *asc {
@@ -3300,54 +5225,51 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                 // fields are simple top-level names, sort by payload_offset and
                 // read in sequential groups — often just ONE pread instead of
                 // O(N) individual preads.
+                //
+                // Top-k path: with a downstream Skip/Take bound k, candidates are
+                // keyed chunk by chunk into a buffer that is cut back to the best
+                // k whenever it reaches 2k — O(n) selection, O(k + chunk) memory.
                 let sort_fields: Vec<String> = columns.iter().map(|(f, _)| f.clone()).collect();
                 let use_fast = sort_fields.iter().all(|f| is_simple_field(f));
                 // Threshold: only batch-read when the candidate set is large enough
                 // that the sort/HashMap overhead is worth paying.
                 const BATCH_THRESHOLD: usize = 64;
-                let raw_map: Option<HashMap<u64, Vec<u8>>> =
-                    if use_fast && candidates.len() >= BATCH_THRESHOLD {
-                        Some(db.read_raw_payloads_batched(&candidates))
-                    } else {
-                        None
-                    };
-                // Pair each candidate with its pre-computed sort keys, sort, then unzip.
-                let mut keyed: Vec<(u64, Vec<Option<Value>>)> = candidates
-                    .iter()
-                    .map(|&h| {
-                        let vals: Vec<Option<Value>> = if let Some(ref rm) = raw_map {
-                            // Batch path: slice bytes from the pre-fetched map.
-                            if let Some(bytes) = rm.get(&h) {
-                                let mut map = extract_fields_by_search(bytes, &sort_fields);
-                                sort_fields.iter().map(|f| map.remove(f.as_str())).collect()
-                            } else {
-                                sort_fields.iter().map(|_| None).collect()
-                            }
-                        } else if use_fast {
-                            if let Some((head, tail)) = db.get_payload_head_tail(h, 512, 16 * 1024) {
-                                let mut map = extract_fields_by_search(&tail, &sort_fields);
-                                let missing: Vec<String> = sort_fields.iter()
-                                    .filter(|f| !map.contains_key(f.as_str()))
-                                    .cloned()
-                                    .collect();
-                                if !missing.is_empty() {
-                                    for (k, v) in extract_fields_by_search(&head, &missing) {
-                                        map.entry(k).or_insert(v);
-                                    }
+                const TOPK_CHUNK: usize = 4096;
+                let top_k = find_sort_limit(remaining).filter(|&k| k < candidates.len());
+                let key_of = |h: u64, raw_map: Option<&HashMap<u64, Vec<u8>>>| -> Vec<Option<Value>> {
+                    if let Some(rm) = raw_map {
+                        // Batch path: slice bytes from the pre-fetched map.
+                        if let Some(bytes) = rm.get(&h) {
+                            let mut map = extract_fields_by_search(bytes, &sort_fields);
+                            sort_fields.iter().map(|f| map.remove(f.as_str())).collect()
+                        } else {
+                            sort_fields.iter().map(|_| None).collect()
+                        }
+                    } else if use_fast {
+                        if let Some((head, tail)) = db.get_payload_head_tail(h, 512, 16 * 1024) {
+                            let mut map = extract_fields_by_search(&tail, &sort_fields);
+                            let missing: Vec<String> = sort_fields.iter()
+                                .filter(|f| !map.contains_key(f.as_str()))
+                                .cloned()
+                                .collect();
+                            if !missing.is_empty() {
+                                for (k, v) in extract_fields_by_search(&head, &missing) {
+                                    map.entry(k).or_insert(v);
                                 }
-                                sort_fields.iter().map(|f| map.remove(f.as_str())).collect()
-                            } else {
-                                sort_fields.iter().map(|_| None).collect()
                             }
-                        } else if let Some(payload) = db.get_payload(h) {
-                            sort_fields.iter().map(|f| json_path_get(f, &payload)).collect()
+                            sort_fields.iter().map(|f| map.remove(f.as_str())).collect()
                         } else {
                             sort_fields.iter().map(|_| None).collect()
-                        };
-                        (h, vals)
-                    })
-                    .collect();
-                keyed.sort_by(|(_, ka), (_, kb)| {
+                        }
+                    } else if let Some(payload) = db.get_payload(h) {
+                        sort_fields.iter().map(|f| json_path_get(f, &payload)).collect()
+                    } else {
+                        sort_fields.iter().map(|_| None).collect()
+                    }
+                };
+                // Ties fall back to candidate position so the order matches a stable sort.
+                let cmp = |(ia, _, ka): &(usize, u64, Vec<Option<Value>>),
+                           (ib, _, kb): &(usize, u64, Vec<Option<Value>>)| {
                     for (i, (_, asc)) in columns.iter().enumerate() {
                         let va = ka.get(i).and_then(|v| v.as_ref());
                         let vb = kb.get(i).and_then(|v| v.as_ref());
@@ -3356,9 +5278,71 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                             return if *asc { ord } else { ord.reverse() };
                         }
                     }
-                    std::cmp::Ordering::Equal
-                });
-                candidates = keyed.into_iter().map(|(h, _)| h).collect();
+                    ia.cmp(ib)
+                };
+                let mut keyed: Vec<(usize, u64, Vec<Option<Value>>)> = Vec::new();
+                if let Some(k) = top_k {
+                    if k == 0 {
+                        candidates.clear();
+                        continue;
+                    }
+                    for (chunk_no, chunk) in candidates.chunks(TOPK_CHUNK).enumerate() {
+                        let raw_map = (use_fast && chunk.len() >= BATCH_THRESHOLD)
+                            .then(|| db.read_raw_payloads_batched(chunk));
+                        for (j, &h) in chunk.iter().enumerate() {
+                            keyed.push((chunk_no * TOPK_CHUNK + j, h, key_of(h, raw_map.as_ref())));
+                            if keyed.len() >= 2 * k {
+                                keyed.select_nth_unstable_by(k - 1, cmp);
+                                keyed.truncate(k);
+                            }
+                        }
+                    }
+                    if keyed.len() > k {
+                        keyed.select_nth_unstable_by(k - 1, cmp);
+                        keyed.truncate(k);
+                    }
+                } else {
+                    let raw_map: Option<HashMap<u64, Vec<u8>>> =
+                        if use_fast && candidates.len() >= BATCH_THRESHOLD {
+                            Some(db.read_raw_payloads_batched(&candidates))
+                        } else {
+                            None
+                        };
+                    // Pair each candidate with its pre-computed sort keys, sort, then unzip.
+                    keyed = candidates
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &h)| (i, h, key_of(h, raw_map.as_ref())))
+                        .collect();
+                }
+                keyed.sort_unstable_by(cmp);
+                candidates = keyed.into_iter().map(|(_, h, _)| h).collect();
+            }
+            Step::TopPerGroup { group_field, sort_field, n } => {
+                let mut order: Vec<String> = Vec::new();
+                let mut groups: HashMap<String, Vec<(u64, Option<Value>)>> = HashMap::new();
+                for &h in &candidates {
+                    let payload = db.get_payload(h);
+                    let get = |f: &str| payload.as_ref().and_then(|p| json_path_get(f, p));
+                    let key = serde_json::to_string(&get(group_field).unwrap_or(Value::Null))
+                        .unwrap_or_default();
+                    let sort_val = get(sort_field);
+                    groups
+                        .entry(key)
+                        .or_insert_with_key(|k| {
+                            order.push(k.clone());
+                            Vec::new()
+                        })
+                        .push((h, sort_val));
+                }
+                candidates = order
+                    .iter()
+                    .flat_map(|k| {
+                        let mut members = groups.remove(k).unwrap_or_default();
+                        members.sort_by(|(_, a), (_, b)| cmp_json(b.as_ref(), a.as_ref()));
+                        members.into_iter().take(*n).map(|(h, _)| h)
+                    })
+                    .collect();
             }
             Step::SortByVector { field, query, metric } => {
                 use crate::vector::{CosineDistance, L2Distance, DotProduct, L1Distance, Distance};
@@ -3389,8 +5373,7 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
                     .into_iter()
                     .filter_map(|(field, query)| {
                         let index = db.bm25_indexes.get(&field)?;
-                        let k = candidates.len().max(100);
-                        let results = index.search(&query, k);
+                        let results = index.search(&query, index.num_docs() as usize);
                         let m: HashMap<u64, f64> =
                             results.iter().map(|h| (h.doc_id, h.score)).collect();
                         Some(((field, query), m))
@@ -3453,15 +5436,43 @@ fn execute(db: &CoreDB, steps: &[Step]) -> Vec<u64> {
             // handled in Set::collect(), not here.
             Step::Select(_) | Step::GroupBy(_) | Step::Having(_) | Step::Distinct => {}
         }
+        if let (Some(trace), Some((at, rows_in))) = (trace.as_deref_mut(), started) {
+            let described = describe_step(step, db);
+            let text = |k: &str| described.get(k).and_then(Value::as_str).unwrap_or_default().to_string();
+            trace.push(StepTrace {
+                seq: i,
+                step: text("step"),
+                detail: text("detail"),
+                rows_in,
+                rows_out: candidates.len(),
+                elapsed: at.elapsed(),
+                children,
+            });
+        }
     }
 
-    candidates
+    (candidates, scores)
 }
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Look ahead in remaining steps to find a Take limit.
 /// Skips Skip and Select which don't affect the limit.
+/// Like [`find_take_limit`] but counts any `Skip` before the `Take`, i.e. how
+/// many leading rows a sort must get right for the pipeline's output.
+pub(crate) fn find_sort_limit(remaining_steps: &[Step]) -> Option<usize> {
+    let mut offset = 0usize;
+    for step in remaining_steps {
+        match step {
+            Step::Take(n) => return Some(offset.saturating_add(*n)),
+            Step::Skip(n) => offset = offset.saturating_add(*n),
+            Step::Select(_) | Step::GroupBy(_) | Step::Having(_) => continue,
+            _ => break,
+        }
+    }
+    None
+}
+
 fn find_take_limit(remaining_steps: &[Step]) -> Option<usize> {
     for step in remaining_steps {
         match step {
@@ -3729,7 +5740,8 @@ impl MatchAggReturn {
             MatchAggReturn::Field { var, field } => rows
                 .first()
                 .and_then(|r| r.get(var.as_str()))
-                .and_then(|v| v.get(field.as_str()))
+                // Bare identifier (field == "*") — the whole bound node.
+                .and_then(|v| if field == "*" { Some(v) } else { v.get(field.as_str()) })
                 .cloned()
                 .unwrap_or(Value::Null),
             MatchAggReturn::Sum(expr) => {
@@ -3942,7 +5954,10 @@ pub struct HopSpec {
     pub node_bind: String,
     /// Optional edge binding — if set, that name is bound in [`PathRow`] to a JSON
     /// object exposing path intrinsics: `_depth`, `_path_keys`, `_path_strength`,
-    /// `_avg_strength`, `_min_strength`, `_max_strength`.
+    /// `_avg_strength`, `_min_strength`, `_max_strength`. A single-depth hop also
+    /// exposes the edge it crossed: `_from`, `_to`, `_type`, `_strength`,
+    /// `_created_unix`, `_meta` and `_direction` (`"out"`, or `"in"` for a
+    /// reversed hop).
     pub edge_bind: Option<String>,
     /// Minimum traversal depth (inclusive). 1 for a plain single-hop `-[:e]->`.
     pub min_depth: u32,
@@ -3950,8 +5965,24 @@ pub struct HopSpec {
     /// When `max_depth > 1`, `collect_paths` uses batch BFS instead of per-node DFS.
     pub max_depth: u32,
     /// Collection label on the destination node pattern (e.g. `"boundary"` from `(b:boundary)`).
-    /// Used by [`try_reverse_anchor`] to validate collection membership.
+    /// Destinations outside that collection are not matched; for a variable-depth
+    /// hop only the final node is checked.
     pub node_label: Option<String>,
+    /// Walk edges against their direction: `(a)<-[:e]-(b)` binds `b` to the
+    /// sources of `e` edges pointing at `a`.
+    pub reverse: bool,
+}
+
+impl HopSpec {
+    /// Whether `node` satisfies this hop's destination label.
+    pub(crate) fn accepts(&self, node: &crate::NodeData) -> bool {
+        self.node_label.as_deref().is_none_or(|label| node.collection == label)
+    }
+
+    /// Edges this hop can cross out of `node`: incoming ones when reversed.
+    pub(crate) fn edges<'a>(&self, db: &'a CoreDB, node: u64) -> Option<&'a [crate::Edge]> {
+        if self.reverse { db.rev_edges(node) } else { db.fwd_edges(node) }
+    }
 }
 
 // ── DestWhere / WhereValue ────────────────────────────────────────────────────
@@ -4139,17 +6170,24 @@ pub(crate) fn collect_raw_paths(
         })
         .collect();
 
+    let cap = db.frontier_cap();
     for hop in hops {
+        if budget::check() {
+            return vec![];
+        }
         let mut next_in_flight: Vec<Partial> = Vec::new();
 
         if hop.max_depth == 1 {
             'single: for partial in in_flight {
-                if let Some(edges) = db.fwd_edges(partial.current_hash) {
+                if budget::spent() {
+                    break;
+                }
+                if let Some(edges) = hop.edges(db, partial.current_hash) {
                     for e in edges {
                         if hop.edge_type_hash != 0 && e.edge_type != hop.edge_type_hash {
                             continue;
                         }
-                        if let Some(node) = db.node_data(e.other) {
+                        if let Some(node) = db.node_data(e.other).filter(|n| hop.accepts(n)) {
                             let mut d = partial.dest_so_far.clone();
                             let mut s = partial.slugs_so_far.clone();
                             let mut st = partial.strengths_so_far.clone();
@@ -4163,7 +6201,9 @@ pub(crate) fn collect_raw_paths(
                                 slugs_so_far:     s,
                                 strengths_so_far: st,
                             });
-                            if limit.map_or(false, |l| next_in_flight.len() >= l) {
+                            if limit.is_some_and(|l| next_in_flight.len() >= l)
+                                || !budget::frontier_fits(cap, next_in_flight.len())
+                            {
                                 break 'single;
                             }
                         }
@@ -4185,7 +6225,10 @@ pub(crate) fn collect_raw_paths(
             for depth in 1..=hop.max_depth {
                 let mut next_pairs: Vec<(usize, u64)> = Vec::new();
                 for &(pidx, current_h) in &pairs {
-                    if let Some(edges) = db.fwd_edges(current_h) {
+                    if budget::spent() || !budget::frontier_fits(cap, next_pairs.len()) {
+                        break;
+                    }
+                    if let Some(edges) = hop.edges(db, current_h) {
                         for e in edges {
                             if hop.edge_type_hash != 0 && e.edge_type != hop.edge_type_hash {
                                 continue;
@@ -4197,7 +6240,9 @@ pub(crate) fn collect_raw_paths(
                     }
                 }
                 if depth >= hop.min_depth {
-                    result_pairs.extend_from_slice(&next_pairs);
+                    result_pairs.extend(next_pairs.iter().copied().filter(|&(_, h)| {
+                        db.node_data(h).is_some_and(|n| hop.accepts(n))
+                    }));
                     if limit.map_or(false, |l| result_pairs.len() >= l) {
                         result_pairs.truncate(limit.unwrap());
                         break;
@@ -4245,6 +6290,97 @@ pub(crate) fn collect_raw_paths(
         .collect()
 }
 
+/// Nodes nearest `query` on `vector_field`, nearest first, with their
+/// cosine distance: at most `depth`, drawn from `scope` when given.
+fn vector_ranked(
+    db: &CoreDB,
+    scope: Option<&HashSet<u64>>,
+    field: &str,
+    query: &[f32],
+    depth: usize,
+) -> Vec<(u64, f32)> {
+    use crate::vector::{CosineDistance, Distance, VectorAccess};
+    // Same cut-over as `VectorNear`: a filtered walk beats a scan of a large scope.
+    const SCOPED_HNSW_MIN: usize = 1024;
+    let Some(field_vecs) = db.vector_field(field) else { return Vec::new() };
+    let ef = (depth * 3).max(50);
+    let hashes = match (db.hnsw_index(field), scope) {
+        (Some(hnsw), None) => Some(hnsw.search::<CosineDistance, _>(query, field_vecs, depth, ef)),
+        (Some(hnsw), Some(set)) if set.len() >= SCOPED_HNSW_MIN => {
+            Some(hnsw.search_filtered::<CosineDistance, _>(query, field_vecs, depth, ef, |h| set.contains(&h)))
+        }
+        _ => None,
+    };
+    let mut scored: Vec<(u64, f32)> = match hashes {
+        Some(hashes) => hashes
+            .into_iter()
+            .filter_map(|h| field_vecs.get(h).map(|v| (h, CosineDistance::eval(query, v))))
+            .collect(),
+        None => field_vecs
+            .iter()
+            .filter(|(h, _)| scope.is_none_or(|s| s.contains(h)))
+            .map(|(h, v)| (h, CosineDistance::eval(query, v)))
+            .collect(),
+    };
+    scored.sort_unstable_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    scored.truncate(depth);
+    scored
+}
+
+/// The [`Step::Hybrid`] ranking: the best `k` nodes by the [`Fusion`] of
+/// their vector and BM25 rankings, with the fused score, best first.
+pub(crate) fn hybrid_rank(
+    db: &CoreDB,
+    scope: Option<&HashSet<u64>>,
+    (vector_field, query): (&str, &[f32]),
+    (text_field, text): (&str, &str),
+    k: usize,
+    fusion: Fusion,
+) -> Vec<(u64, f64)> {
+    let depth = (k * 4).max(50);
+    let by_vector = vector_ranked(db, scope, vector_field, query, depth);
+    // Score every indexed document, then scope: the index is shared across
+    // collections, so a top-k cut could be filled by other ones.
+    let mut by_text: Vec<(u64, f64)> = db
+        .bm25_indexes
+        .get(text_field)
+        .map(|index| index.search(text, index.num_docs() as usize))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.score > 0.0 && scope.is_none_or(|s| s.contains(&r.doc_id)))
+        .map(|r| (r.doc_id, r.score))
+        .collect();
+    by_text.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    by_text.truncate(depth);
+
+    let mut fused: HashMap<u64, f64> = HashMap::new();
+    match fusion {
+        Fusion::Rrf { k: c } => {
+            let c = c as f64;
+            for (rank, &(h, _)) in by_vector.iter().enumerate() {
+                *fused.entry(h).or_default() += 1.0 / (c + rank as f64 + 1.0);
+            }
+            for (rank, &(h, _)) in by_text.iter().enumerate() {
+                *fused.entry(h).or_default() += 1.0 / (c + rank as f64 + 1.0);
+            }
+        }
+        Fusion::Weighted { vector } => {
+            let w = vector.clamp(0.0, 1.0) as f64;
+            for &(h, dist) in &by_vector {
+                *fused.entry(h).or_default() += w * (1.0 - dist as f64);
+            }
+            let best = by_text.first().map_or(1.0, |&(_, s)| s);
+            for &(h, score) in &by_text {
+                *fused.entry(h).or_default() += (1.0 - w) * score / best;
+            }
+        }
+    }
+    let mut ranked: Vec<(u64, f64)> = fused.into_iter().filter(|&(h, _)| db.node_data(h).is_some()).collect();
+    ranked.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(k);
+    ranked
+}
+
 /// Pure-topology BFS for GROUP BY COUNT(*) — returns `final_dest_hash → path_count`.
 ///
 /// Merges the traversal frontier at every level so memory is O(unique_nodes_at_level)
@@ -4276,6 +6412,7 @@ fn collect_final_dest_counts(
         .collect();
 
     let last_hop_idx = hops.len() - 1;
+    let cap = db.frontier_cap();
 
     for (hop_idx, hop) in hops.iter().enumerate() {
         // BFS through this hop's depth range, merging node weights at each level.
@@ -4285,7 +6422,10 @@ fn collect_final_dest_counts(
         for depth in 1u32..=hop.max_depth {
             let mut next: HashMap<u64, usize> = HashMap::new();
             for (&current_h, &count) in &depth_frontier {
-                if let Some(edges) = db.fwd_edges(current_h) {
+                if budget::spent() || !budget::frontier_fits(cap, next.len()) {
+                    break;
+                }
+                if let Some(edges) = hop.edges(db, current_h) {
                     for e in edges {
                         if hop.edge_type_hash != 0 && e.edge_type != hop.edge_type_hash {
                             continue;
@@ -4299,7 +6439,9 @@ fn collect_final_dest_counts(
             // Accumulate results at every depth in [min_depth, max_depth].
             if depth >= hop.min_depth {
                 for (&h, &c) in &next {
-                    *hop_results.entry(h).or_insert(0) += c;
+                    if db.node_data(h).is_some_and(|n| hop.accepts(n)) {
+                        *hop_results.entry(h).or_insert(0) += c;
+                    }
                 }
             }
             depth_frontier = next;
@@ -4380,10 +6522,13 @@ fn try_reverse_anchor(
     let mut raw_paths = Vec::new();
     for &dest_hash in &dest_hashes {
         let dest_slug = match db.node_data(dest_hash) {
-            Some(n) => n.slug.clone(),
-            None => continue,
+            Some(n) if hop.accepts(n) => n.slug.clone(),
+            _ => continue,
         };
-        let rev = match db.rev_edges(dest_hash) {
+        // Walking back from the destination crosses the hop's edges the
+        // other way round.
+        let back = if hop.reverse { db.fwd_edges(dest_hash) } else { db.rev_edges(dest_hash) };
+        let rev = match back {
             Some(r) => r,
             None => continue,
         };
@@ -4473,14 +6618,34 @@ pub(crate) fn build_path_rows_from_raw(
                     let avg = if n > 0.0 { sum / n } else { 0.0 };
                     let min_s = path_strengths.iter().cloned().fold(f32::INFINITY, f32::min);
                     let max_s = path_strengths.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-                    row.insert(edge_bind.clone(), serde_json::json!({
+                    let mut edge_obj = serde_json::json!({
                         "_depth":         hop_idx + 1,
                         "_path_keys":     path_slugs,
                         "_path_strength": path_strengths,
                         "_avg_strength":  avg,
                         "_min_strength":  if min_s.is_infinite() { 0.0_f32 } else { min_s },
                         "_max_strength":  if max_s.is_infinite() { 0.0_f32 } else { max_s },
-                    }));
+                    });
+                    // A single-depth hop crossed exactly one edge: expose it too.
+                    if hop.max_depth == 1 {
+                        let from_h = if hop_idx == 0 { rp.start_hash } else { rp.dest_per_hop[hop_idx - 1] };
+                        let crossed = hop.edges(db, from_h).unwrap_or(&[]).iter().find(|e| {
+                            e.other == dest_h
+                                && (hop.edge_type_hash == 0 || e.edge_type == hop.edge_type_hash)
+                                && e.strength == rp.strengths_per_hop[hop_idx]
+                        });
+                        if let (Some(e), Value::Object(obj)) = (crossed, &mut edge_obj) {
+                            let (from, to) = if hop.reverse { (hop_idx + 1, hop_idx) } else { (hop_idx, hop_idx + 1) };
+                            obj.insert("_from".into(), Value::String(rp.slugs_per_hop[from].clone()));
+                            obj.insert("_to".into(), Value::String(rp.slugs_per_hop[to].clone()));
+                            obj.insert("_type".into(), db.resolve_edge_type(e.edge_type).map_or(Value::Null, Value::String));
+                            obj.insert("_strength".into(), serde_json::json!(e.strength));
+                            obj.insert("_created_unix".into(), serde_json::json!(e.created_unix));
+                            obj.insert("_meta".into(), db.edge_meta(e).unwrap_or(Value::Null));
+                            obj.insert("_direction".into(), Value::from(if hop.reverse { "in" } else { "out" }));
+                        }
+                    }
+                    row.insert(edge_bind.clone(), edge_obj);
                 }
 
                 row.insert(hop.node_bind.clone(), dest_payload);
@@ -4799,6 +6964,9 @@ fn expand_match_stage(
         let starts = resolve_match_start(db, start);
 
         for &start_h in &starts {
+            if budget::spent() {
+                return result;
+            }
             // Check inline WHERE (on start node).
             if !where_clauses.is_empty() {
                 let payload = match db.get_payload(start_h) {
@@ -4850,17 +7018,14 @@ fn expand_match_stage(
 
             while let Some((current_h, hop_idx, bindings)) = stack.pop() {
                 let hop = &hops[hop_idx];
-                let Some(edges) = db.fwd_edges(current_h) else { continue };
+                let Some(edges) = hop.edges(db, current_h) else { continue };
 
                 for e in edges {
                     if hop.edge_type_hash != 0 && e.edge_type != hop.edge_type_hash {
                         continue;
                     }
-                    // Optional collection filter on endpoint.
-                    if let Some(node_data) = db.node_data(e.other) {
-                        // No collection filter in HopSpec for pipeline-style;
-                        // accept all nodes (collection filtering is by label in the pattern).
-                    } else {
+                    // Collection filter on the endpoint, from the pattern label.
+                    if !db.node_data(e.other).is_some_and(|n| hop.accepts(n)) {
                         continue;
                     }
 
@@ -4880,6 +7045,9 @@ fn expand_match_stage(
                         result.push(new_row);
                     } else {
                         stack.push((e.other, next_hop, new_bindings));
+                        if !budget::frontier_fits(db.frontier_cap(), stack.len()) {
+                            return result;
+                        }
                     }
                 }
             }
@@ -5134,7 +7302,7 @@ fn execute_match_agg_with_stages(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
     }
 
     result_rows.into_iter()
-        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
         .collect()
 }
 
@@ -5252,7 +7420,7 @@ pub fn execute_match_agg(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
         for (_, alias) in &stmt.returns {
             map.insert(alias.clone(), serde_json::json!(total as i64));
         }
-        return vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)) }];
+        return vec![Hit { slug: String::new(), slug_hash: 0, payload: Some(Value::Object(map)), score: None }];
     }
 
     // 2. Collect all path rows.
@@ -5596,7 +7764,7 @@ pub fn execute_match_agg(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
                 if let Some(n) = stmt.limit { result_rows.truncate(n); }
                 return result_rows
                     .into_iter()
-                    .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+                    .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
                     .collect();
             }
 
@@ -5747,7 +7915,7 @@ pub fn execute_match_agg(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
             if let Some(n) = stmt.limit { result_rows.truncate(n); }
             return result_rows
                 .into_iter()
-                .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+                .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
                 .collect();
         }
 
@@ -5921,7 +8089,7 @@ pub fn execute_match_agg(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
                 if let Some(n) = stmt.limit { result_rows.truncate(n); }
                 return result_rows
                     .into_iter()
-                    .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+                    .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
                     .collect();
             }
         }
@@ -6072,18 +8240,19 @@ pub fn execute_match_agg(db: &CoreDB, stmt: MatchAggStmt) -> Vec<Hit> {
     // 6. Wrap in Hits (synthetic — no real node slug)
     result_rows
         .into_iter()
-        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
         .collect()
 }
 
 // ── execute_shortest_select ───────────────────────────────────────────────────
 
 /// Build the single `PathRow` for a `SELECT … FROM MATCH SHORTEST` result.
-/// Returns `None` when no path exists (BFS returned nothing).
+/// Returns `Ok(None)` when no path exists, and `Err` when the search was cut
+/// off by [`QueryLimits::max_frontier`](crate::QueryLimits::max_frontier).
 fn build_shortest_path_row(
     db: &CoreDB,
     stmt: &ShortestSelectStmt,
-) -> Option<PathRow> {
+) -> Result<Option<PathRow>, crate::ResultTooLarge> {
     use crate::sk_hash;
 
     let start = sk_hash(&stmt.from_slug);
@@ -6097,17 +8266,19 @@ fn build_shortest_path_row(
             "MATCH SHORTEST: start node '{}' not found — did you specify the collection? e.g. ({}:collection)",
             stmt.from_slug, stmt.start_bind
         );
-        return None;
+        return Ok(None);
     }
     if db.node_data(end).is_none() {
         eprintln!(
             "MATCH SHORTEST: end node '{}' not found — did you specify the collection? e.g. ({}:collection)",
             stmt.to_slug, stmt.end_bind
         );
-        return None;
+        return Ok(None);
     }
 
-    let pr    = db.bfs_shortest_path(start, end)?;
+    let Some(pr) = db.bfs_shortest_path(start, end)? else {
+        return Ok(None);
+    };
 
     let mut row: PathRow = HashMap::new();
 
@@ -6147,7 +8318,7 @@ fn build_shortest_path_row(
         row.insert(pb.clone(), path_obj);
     }
 
-    Some(row)
+    Ok(Some(row))
 }
 
 /// Evaluate a `PathPredicate` against the path stored in `row`.
@@ -6214,23 +8385,24 @@ fn finalize_rows(
     if let Some(n) = limit { result_rows.truncate(n); }
     result_rows
         .into_iter()
-        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v) })
+        .map(|v| Hit { slug: String::new(), slug_hash: 0, payload: Some(v), score: None })
         .collect()
 }
 
 /// Execute a `SELECT … FROM MATCH SHORTEST` statement.
 ///
-/// Returns 0 rows when no path exists, 1 row when found (after predicate filtering).
-pub fn execute_shortest_select(db: &CoreDB, stmt: ShortestSelectStmt) -> Vec<Hit> {
-    let row = match build_shortest_path_row(db, &stmt) {
+/// Returns 0 rows when no path exists, 1 row when found (after predicate
+/// filtering), and `Err` when the search outgrew the frontier cap.
+pub fn execute_shortest_select(db: &CoreDB, stmt: ShortestSelectStmt) -> Result<Vec<Hit>, crate::ResultTooLarge> {
+    let row = match build_shortest_path_row(db, &stmt)? {
         Some(r) => r,
-        None    => return vec![],
+        None    => return Ok(vec![]),
     };
 
     // Apply path predicates
     for pred in &stmt.predicates {
         if !eval_path_predicate(db, pred, &row) {
-            return vec![];
+            return Ok(vec![]);
         }
     }
 
@@ -6241,7 +8413,7 @@ pub fn execute_shortest_select(db: &CoreDB, stmt: ShortestSelectStmt) -> Vec<Hit
         map.insert(alias.clone(), ret_expr.eval_group(rows_slice));
     }
 
-    finalize_rows(vec![Value::Object(map)], stmt.order_by.as_ref(), stmt.limit)
+    Ok(finalize_rows(vec![Value::Object(map)], stmt.order_by.as_ref(), stmt.limit))
 }
 
 // ── execute_multi_from ────────────────────────────────────────────────────────
@@ -6263,9 +8435,10 @@ fn cartesian_product(sources: Vec<Vec<PathRow>>) -> Vec<PathRow> {
 
 /// Execute a `SELECT … FROM source1, source2, …` multi-FROM statement.
 ///
-/// Each source is executed independently; rows are cross-joined (Cartesian product).
-pub fn execute_multi_from(db: &CoreDB, stmt: MultiFromStmt) -> Vec<Hit> {
-    let source_rows: Vec<Vec<PathRow>> = stmt.sources.into_iter().map(|src| match src {
+/// Each source is executed independently; rows are cross-joined (Cartesian
+/// product). `Err` when a `MATCH SHORTEST` source outgrew the frontier cap.
+pub fn execute_multi_from(db: &CoreDB, stmt: MultiFromStmt) -> Result<Vec<Hit>, crate::ResultTooLarge> {
+    let source_rows: Vec<Vec<PathRow>> = stmt.sources.into_iter().map(|src| Ok(match src {
         FromSource::Match(agg) => {
             let starts: Vec<u64> = match agg.start {
                 MatchAggStart::Slug(h)       => if db.node_data(h).is_some() { vec![h] } else { vec![] },
@@ -6274,12 +8447,7 @@ pub fn execute_multi_from(db: &CoreDB, stmt: MultiFromStmt) -> Vec<Hit> {
             };
             collect_paths(db, &starts, &agg.hops, agg.start_var.as_deref(), None)
         }
-        FromSource::Shortest(s) => {
-            match build_shortest_path_row(db, &s) {
-                Some(row) => vec![row],
-                None      => vec![],
-            }
-        }
+        FromSource::Shortest(s) => build_shortest_path_row(db, &s)?.into_iter().collect(),
         FromSource::Collection { alias, name_hash } => {
             db.collection_members(name_hash)
                 .cloned()
@@ -6294,11 +8462,11 @@ pub fn execute_multi_from(db: &CoreDB, stmt: MultiFromStmt) -> Vec<Hit> {
                 })
                 .collect()
         }
-    }).collect();
+    })).collect::<Result<_, crate::ResultTooLarge>>()?;
 
     let all_rows = cartesian_product(source_rows);
     if all_rows.is_empty() {
-        return vec![];
+        return Ok(vec![]);
     }
 
     let result_rows: Vec<Value> = all_rows.into_iter().map(|row| {
@@ -6309,7 +8477,7 @@ pub fn execute_multi_from(db: &CoreDB, stmt: MultiFromStmt) -> Vec<Hit> {
         Value::Object(map)
     }).collect();
 
-    finalize_rows(result_rows, stmt.order_by.as_ref(), stmt.limit)
+    Ok(finalize_rows(result_rows, stmt.order_by.as_ref(), stmt.limit))
 }
 
 fn cmp_json(a: Option<&Value>, b: Option<&Value>) -> std::cmp::Ordering {
@@ -298,9 +298,35 @@ pub fn quarter(date_str: &str) -> Option<u32> {
     parse_iso_datetime(date_str).map(|dt| (dt.month() - 1) / 3 + 1)
 }
 
+/// Interpret a payload value as a UTC timestamp.
+///
+/// Accepts RFC 3339 strings, bare `YYYY-MM-DD` dates, and unix epoch numbers —
+/// milliseconds when the magnitude is ≥ 1e11 (as in `_created_unix`), seconds
+/// otherwise.
+pub fn timestamp_from_value(v: &Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    match v {
+        Value::String(s) => parse_iso_datetime(s).or_else(|| {
+            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|n| chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(n, chrono::Utc))
+        }),
+        Value::Number(n) => {
+            let f = n.as_f64()?;
+            if f.abs() >= 1e11 {
+                chrono::DateTime::from_timestamp_millis(f as i64)
+            } else {
+                chrono::DateTime::from_timestamp(f as i64, 0)
+            }
+        }
+        _ => None,
+    }
+}
+
 /// Truncate a datetime to the given unit, returning an RFC 3339 string.
 ///
-/// Supported units: year, quarter, month, day, hour, minute, second.
+/// Supported units: year, quarter, month, week (ISO, Monday start), day,
+/// hour, minute, second.
 pub fn date_trunc(unit: &str, dt: chrono::DateTime<chrono::Utc>) -> String {
     use chrono::NaiveDateTime;
     let naive = dt.naive_utc();
@@ -323,6 +349,10 @@ pub fn date_trunc(unit: &str, dt: chrono::DateTime<chrono::Utc>) -> String {
             chrono::NaiveDate::from_ymd_opt(nd.year(), nd.month(), 1).unwrap_or(nd),
             zero,
         ),
+        "week" => NaiveDateTime::new(
+            nd - chrono::Duration::days(nd.weekday().num_days_from_monday() as i64),
+            zero,
+        ),
         "day" => NaiveDateTime::new(nd, zero),
         "hour" => NaiveDateTime::new(
             nd,
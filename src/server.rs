@@ -0,0 +1,740 @@
+//! SekejapQL over HTTP (`server` feature).
+//!
+//! A small blocking HTTP/1.1 server on `std::net`, one thread per
+//! connection (capped), with no dependencies beyond the crate's own.
+//! Queries share a read lock on the database; mutations take the write
+//! lock.
+//!
+//! | Route               | Body / result                                        |
+//! |---------------------|------------------------------------------------------|
+//! | `POST /query`       | `{"sql": "...", "params": [...] or {...}, "options": {...}, "profile": "..."}` → JSON array of [`Hit::to_json`](crate::Hit::to_json) objects (`options` is a [`HitJsonOptions`], `profile` a projection profile); with `"trace": true`, `{"rows": [...], "trace": {...}}` (see [`Trace::to_json`](crate::Trace::to_json)), the trace carrying the request's `"correlation_id"` if it sent one |
+//! | `POST /mutate`      | a [`CoreDB::mutate_json`] mutation or batch → one `null` or error string per mutation |
+//! | `GET /nodes/{slug}` | the node's payload under [`ServerConfig::profile`], or 404 |
+//! | `POST /jobs`        | a [`JobRequest`](crate::JobRequest) as JSON → the finished job's [`JobInfo::to_json`](crate::JobInfo::to_json) once it ends |
+//! | `GET /jobs`         | running and recent jobs, oldest first                |
+//! | `GET /jobs/{id}`    | one job, or 404                                      |
+//! | `POST /jobs/{id}/cancel` | `{"cancelled": bool}`, whether it was still running; 404 if unknown |
+//!
+//! `POST /jobs` holds the write lock until the job ends, so other queries
+//! wait; the `/jobs` reads and cancellation do not touch the database and
+//! answer while it runs. Backup and restore jobs write and read the
+//! server's filesystem, so they get 403 unless [`ServerConfig::tokens`] is
+//! non-empty and [`ServerConfig::backup_dir`] is set; their `dest` / `path`
+//! is then a relative path inside that directory.
+//!
+//! Every read runs under [`ServerConfig::profile`], so a server facing
+//! untrusted clients can strip private fields from it (see
+//! [`CoreDB::set_projection_profile`]). A query may name another `profile`
+//! only if [`ServerConfig::token_profiles`] lists it for the request's
+//! token; otherwise it gets 403.
+//!
+//! Errors are `{"error": "..."}` with a 4xx/5xx status; a query over the
+//! result caps of the database's [`QueryLimits`](crate::QueryLimits) gets
+//! 422, one stopped by
+//! [`QueryLimits::max_execution_ms`](crate::QueryLimits::max_execution_ms)
+//! gets 503. When
+//! [`ServerConfig::tokens`] is non-empty every request must carry
+//! `Authorization: Bearer <token>` with one of them. A token with an entry
+//! in [`ServerConfig::authorizers`] is limited by that [`Authorizer`]:
+//! `/query` and `/mutate` run through [`CoreDB::query_as`] and
+//! [`CoreDB::mutate_json_as`], `GET /nodes/{slug}` needs read access to the
+//! node's collection and `POST /jobs` needs unscoped schema access. Refused
+//! requests get 403; refused items of a `/mutate` batch report their reason.
+//!
+//! ```no_run
+//! use sekejap::CoreDB;
+//! use sekejap::server::{Server, ServerConfig};
+//!
+//! let db = CoreDB::open("data").unwrap();
+//! let config = ServerConfig { tokens: vec!["s3cret".into()], ..ServerConfig::default() };
+//! Server::bind(db, config).unwrap().run().unwrap();
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::{Access, Authorizer, CoreDB, HitJsonOptions, JobRequest, Jobs, QueryError, SqlError};
+
+/// Settings for [`Server::bind`].
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Address to listen on, e.g. `127.0.0.1:7878`.
+    pub addr: String,
+    /// Accepted bearer tokens. Empty means no authentication.
+    pub tokens: Vec<String>,
+    /// Connections served at once; further ones get 503.
+    pub max_connections: usize,
+    /// Largest request body accepted; larger ones get 413.
+    pub max_body_bytes: usize,
+    /// Largest request line plus headers accepted; larger ones get 431.
+    pub max_header_bytes: usize,
+    /// Projection profile applied to `GET /nodes/{slug}` and to every
+    /// `POST /query` that names none.
+    pub profile: Option<String>,
+    /// Other projection profiles a token may name in a query's `profile`.
+    pub token_profiles: HashMap<String, Vec<String>>,
+    /// Per-token access checks; tokens without one have full access.
+    pub authorizers: HashMap<String, Arc<dyn Authorizer + Send + Sync>>,
+    /// Directory that `POST /jobs` backup and restore paths are resolved
+    /// in. `None` refuses those jobs.
+    pub backup_dir: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:7878".into(),
+            tokens: Vec::new(),
+            max_connections: 64,
+            max_body_bytes: 16 * 1024 * 1024,
+            max_header_bytes: 64 * 1024,
+            profile: None,
+            token_profiles: HashMap::new(),
+            authorizers: HashMap::new(),
+            backup_dir: None,
+        }
+    }
+}
+
+/// A bound listener serving one database. See the [module docs](self).
+pub struct Server {
+    listener: TcpListener,
+    db: Arc<RwLock<CoreDB>>,
+    /// Kept outside the lock so jobs can be watched while one runs.
+    jobs: Jobs,
+    config: Arc<ServerConfig>,
+    active: Arc<AtomicUsize>,
+}
+
+impl Server {
+    /// Bind `config.addr`. Pass port 0 to pick a free port; see
+    /// [`local_addr`](Self::local_addr).
+    pub fn bind(db: CoreDB, config: ServerConfig) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(&config.addr)?,
+            jobs: db.jobs(),
+            db: Arc::new(RwLock::new(db)),
+            config: Arc::new(config),
+            active: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// The served database, for an embedding application to use alongside
+    /// the HTTP clients.
+    pub fn db(&self) -> Arc<RwLock<CoreDB>> {
+        Arc::clone(&self.db)
+    }
+
+    /// Accept connections until the listener fails.
+    pub fn run(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let mut stream = stream?;
+            if self.active.fetch_add(1, Ordering::AcqRel) >= self.config.max_connections {
+                self.active.fetch_sub(1, Ordering::AcqRel);
+                let _ = write_response(&mut stream, 503, &error_body("too many connections"));
+                continue;
+            }
+            let (db, config, active) = (Arc::clone(&self.db), Arc::clone(&self.config), Arc::clone(&self.active));
+            let jobs = self.jobs.clone();
+            std::thread::spawn(move || {
+                let _ = serve_connection(stream, &db, &jobs, &config);
+                active.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+        Ok(())
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Serve one request, then close the connection.
+fn serve_connection(mut stream: TcpStream, db: &RwLock<CoreDB>, jobs: &Jobs, config: &ServerConfig) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let (status, body) = match read_request(&mut stream, config) {
+        Ok(req) => route(&req, db, jobs, config),
+        Err((status, msg)) => (status, error_body(&msg)),
+    };
+    write_response(&mut stream, status, &body)
+}
+
+/// Headers after the request line; more get 431.
+const MAX_HEADERS: usize = 100;
+
+fn read_request(stream: &mut TcpStream, config: &ServerConfig) -> Result<Request, (u16, String)> {
+    let bad = |msg: &str| (400, msg.to_string());
+    let too_large = || (431, format!("request headers exceed {} bytes", config.max_header_bytes));
+    // Every line is read through the header budget, so one endless line
+    // cannot grow without bound.
+    let mut reader = BufReader::new(stream.take(config.max_header_bytes as u64));
+    let mut line = String::new();
+    let next_line = |line: &mut String, reader: &mut BufReader<io::Take<&mut TcpStream>>| {
+        line.clear();
+        reader.read_line(line).map_err(|e| bad(&e.to_string()))?;
+        if line.ends_with('\n') {
+            Ok(())
+        } else if reader.get_ref().limit() == 0 {
+            Err(too_large())
+        } else {
+            Err(bad("headers not terminated"))
+        }
+    };
+    next_line(&mut line, &mut reader)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad("malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0usize;
+    let mut authorization = None;
+    for headers in 0.. {
+        next_line(&mut line, &mut reader)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Err((431, format!("more than {MAX_HEADERS} headers")));
+        }
+        let Some((name, value)) = header.split_once(':') else { continue };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| bad("bad Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        }
+    }
+    if content_length > config.max_body_bytes {
+        return Err((413, format!("body exceeds {} bytes", config.max_body_bytes)));
+    }
+    reader.get_mut().set_limit(content_length as u64);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| bad(&e.to_string()))?;
+    Ok(Request { method, path, authorization, body })
+}
+
+fn route(req: &Request, db: &RwLock<CoreDB>, jobs: &Jobs, config: &ServerConfig) -> (u16, Value) {
+    let mut token = None;
+    if !config.tokens.is_empty() {
+        token = req.authorization.as_deref().and_then(|a| a.strip_prefix("Bearer "));
+        if !token.is_some_and(|t| config.tokens.iter().fold(false, |found, k| found | token_eq(k, t))) {
+            return (401, error_body("missing or unknown bearer token"));
+        }
+    }
+    let auth: Option<&dyn Authorizer> = token.and_then(|t| config.authorizers.get(t)).map(|a| a.as_ref() as _);
+    let path = req.path.split('?').next().unwrap_or_default();
+    match (req.method.as_str(), path) {
+        ("POST", "/query") => query(&req.body, db, config, token, auth),
+        ("POST", "/mutate") => mutate(&req.body, db, auth),
+        ("GET", p) if p.starts_with("/nodes/") => {
+            let slug = percent_decode(&p["/nodes/".len()..]);
+            let db = db.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(auth) = auth {
+                let collection = db.node_data(crate::sk_hash(&slug)).map(|n| n.collection.clone());
+                let collections = collection.filter(|c| !c.is_empty()).map(|c| vec![c]);
+                if let Err(e) = auth.authorize(Access::Read, collections.as_deref()) {
+                    return (403, error_body(&e));
+                }
+            }
+            if let Some(p) = config.profile.as_deref().filter(|p| db.profile_fields(p).is_none()) {
+                return (500, error_body(&format!("server profile `{p}` is not declared")));
+            }
+            match db.get(&slug).map(|raw| serde_json::from_str::<Value>(&raw)) {
+                Some(Ok(mut payload)) => {
+                    let hidden = config.profile.as_deref().and_then(|p| db.profile_hides(p, crate::sk_hash(&slug)));
+                    if let (Some(hidden), Some(map)) = (hidden, payload.as_object_mut()) {
+                        for field in hidden {
+                            map.remove(field);
+                        }
+                    }
+                    (200, payload)
+                }
+                Some(Err(e)) => (500, error_body(&e.to_string())),
+                None => (404, error_body(&format!("no node `{slug}`"))),
+            }
+        }
+        ("POST", "/jobs") => match auth.map(|a| a.authorize(Access::Schema, None)) {
+            Some(Err(e)) => (403, error_body(&e)),
+            _ => run_job(&req.body, db, config),
+        },
+        ("GET", "/jobs") => (200, jobs.list().iter().map(|j| j.to_json()).collect()),
+        (method, p) if p.starts_with("/jobs/") => {
+            let rest = &p["/jobs/".len()..];
+            let (id, cancel) = match rest.strip_suffix("/cancel") {
+                Some(id) => (id, true),
+                None => (rest, false),
+            };
+            let Some(job) = id.parse().ok().and_then(|id| jobs.get(id)) else {
+                return (404, error_body(&format!("no job `{id}`")));
+            };
+            match (method, cancel) {
+                ("GET", false) => (200, job.info().to_json()),
+                ("POST", true) => (200, json!({ "cancelled": job.cancel() })),
+                (_, true) => (405, error_body("use POST")),
+                _ => (405, error_body("use GET")),
+            }
+        }
+        (_, "/query" | "/mutate") => (405, error_body("use POST")),
+        _ => (404, error_body("unknown route")),
+    }
+}
+
+fn query(
+    body: &[u8],
+    db: &RwLock<CoreDB>,
+    config: &ServerConfig,
+    token: Option<&str>,
+    auth: Option<&dyn Authorizer>,
+) -> (u16, Value) {
+    #[derive(serde::Deserialize)]
+    struct QueryBody {
+        sql: String,
+        /// `[...]` binds `$1..$n`; `{...}` binds `$name`.
+        #[serde(default)]
+        params: Value,
+        #[serde(default)]
+        options: HitJsonOptions,
+        #[serde(default)]
+        trace: bool,
+        /// Recorded in the trace; see [`Set::correlation_id`](crate::Set::correlation_id).
+        #[serde(default)]
+        correlation_id: Option<String>,
+        #[serde(default)]
+        profile: Option<String>,
+    }
+    let req: QueryBody = match serde_json::from_slice(body) {
+        Ok(r) => r,
+        Err(e) => return (400, error_body(&e.to_string())),
+    };
+    let profile = match query_profile(config, token, req.profile.as_deref()) {
+        Ok(p) => p,
+        Err(refused) => return refused,
+    };
+    let db = db.read().unwrap_or_else(|e| e.into_inner());
+    let result = match (&req.params, auth) {
+        (Value::Null, None) => db.query(&req.sql),
+        (Value::Null, Some(auth)) => db.query_as(auth, &req.sql, &[]),
+        (Value::Array(params), None) => db.query_params(&req.sql, params),
+        (Value::Array(params), Some(auth)) => db.query_as(auth, &req.sql, params),
+        (Value::Object(params), None) => db.query_named(&req.sql, params),
+        (Value::Object(params), Some(auth)) => db.query_named_as(auth, &req.sql, params),
+        _ => return (400, error_body("params must be an array or an object")),
+    };
+    let result = result.and_then(|set| match profile {
+        Some(profile) => set.profile(profile),
+        None => Ok(set),
+    });
+    let result = match req.correlation_id {
+        Some(id) => result.map(|set| set.correlation_id(id)),
+        None => result,
+    };
+    let run_error = |e: QueryError| match e {
+        QueryError::TooLarge(e) => (422, error_body(&e.to_string())),
+        QueryError::Interrupted(e) => (503, error_body(&e.cause.to_string())),
+    };
+    match result {
+        Ok(set) if req.trace => match set.run_traced() {
+            Ok((hits, trace)) => {
+                let rows: Vec<Value> = hits.iter().map(|h| h.to_json(&req.options)).collect();
+                (200, json!({ "rows": rows, "trace": trace.to_json() }))
+            }
+            Err(e) => run_error(e),
+        },
+        Ok(set) => match set.run() {
+            Ok(hits) => (200, Value::Array(hits.iter().map(|h| h.to_json(&req.options)).collect())),
+            Err(e) => run_error(e),
+        },
+        Err(e @ SqlError::Unauthorized(_)) => (403, error_body(&e.to_string())),
+        Err(e) => (400, error_body(&e.to_string())),
+    }
+}
+
+/// Compare two tokens without an early exit, so the response time does not
+/// tell a client how much of a guess matched.
+fn token_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The profile a query runs under: [`ServerConfig::profile`], or the one it
+/// names if [`ServerConfig::token_profiles`] allows that for `token`.
+fn query_profile<'a>(config: &'a ServerConfig, token: Option<&str>, requested: Option<&'a str>) -> Result<Option<&'a str>, (u16, Value)> {
+    let Some(requested) = requested.filter(|&p| Some(p) != config.profile.as_deref()) else {
+        return Ok(config.profile.as_deref());
+    };
+    let allowed = token.and_then(|t| config.token_profiles.get(t));
+    if allowed.is_some_and(|profiles| profiles.iter().any(|p| p == requested)) {
+        Ok(Some(requested))
+    } else {
+        Err((403, error_body(&format!("this token may not query under profile `{requested}`"))))
+    }
+}
+
+fn mutate(body: &[u8], db: &RwLock<CoreDB>, auth: Option<&dyn Authorizer>) -> (u16, Value) {
+    let Ok(text) = std::str::from_utf8(body) else {
+        return (400, error_body("body is not UTF-8"));
+    };
+    let mut db = db.write().unwrap_or_else(|e| e.into_inner());
+    let results = match auth {
+        Some(auth) => db.mutate_json_as(auth, text),
+        None => db.mutate_json(text),
+    };
+    match results {
+        Ok(results) => (200, results.into_iter().map(Result::err).collect()),
+        Err(e) => (400, error_body(&e.to_string())),
+    }
+}
+
+fn run_job(body: &[u8], db: &RwLock<CoreDB>, config: &ServerConfig) -> (u16, Value) {
+    let request = match serde_json::from_slice(body).map_err(|e| e.to_string()).and_then(|v| JobRequest::from_json(&v)) {
+        Ok(r) => r,
+        Err(e) => return (400, error_body(&e)),
+    };
+    let request = match request {
+        JobRequest::Backup { dest } => match confine(config, &dest) {
+            Ok(dest) => JobRequest::Backup { dest },
+            Err(refused) => return refused,
+        },
+        JobRequest::Restore { path } => match confine(config, &path) {
+            Ok(path) => JobRequest::Restore { path },
+            Err(refused) => return refused,
+        },
+        other => other,
+    };
+    let mut db = db.write().unwrap_or_else(|e| e.into_inner());
+    (200, db.run_job(request).to_json())
+}
+
+/// Resolve a backup or restore `path` inside [`ServerConfig::backup_dir`],
+/// or the 403 refusing it.
+fn confine(config: &ServerConfig, path: &Path) -> Result<PathBuf, (u16, Value)> {
+    if config.tokens.is_empty() {
+        return Err((403, error_body("backup and restore jobs need bearer tokens")));
+    }
+    let Some(root) = &config.backup_dir else {
+        return Err((403, error_body("backup and restore jobs need a backup_dir")));
+    };
+    if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err((403, error_body(&format!("`{}` is not a relative path inside backup_dir", path.display()))));
+    }
+    Ok(root.join(path))
+}
+
+fn error_body(msg: &str) -> Value {
+    json!({ "error": msg })
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        422 => "Unprocessable Entity",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    let auth = if status == 401 { "WWW-Authenticate: Bearer\r\n" } else { "" };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{auth}Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Decode `%XX` escapes in a URL path segment; invalid escapes pass through.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(db: CoreDB, tokens: &[&str]) -> SocketAddr {
+        serve(db, ServerConfig { tokens: tokens.iter().map(|t| t.to_string()).collect(), ..ServerConfig::default() })
+    }
+
+    fn serve(db: CoreDB, config: ServerConfig) -> SocketAddr {
+        let config = ServerConfig { addr: "127.0.0.1:0".into(), ..config };
+        let server = Server::bind(db, config).unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        addr
+    }
+
+    fn send(addr: SocketAddr, method: &str, path: &str, token: Option<&str>, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let auth = token.map(|t| format!("Authorization: Bearer {t}\r\n")).unwrap_or_default();
+        write!(stream, "{method} {path} HTTP/1.1\r\nHost: test\r\n{auth}Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn routes_query_mutate_and_node_reads() {
+        let addr = start(CoreDB::new(), &[]);
+
+        let (status, results) = send(addr, "POST", "/mutate", None,
+            r#"{"mutations":[
+                {"op":"put","slug":"cafes/kopi/1","payload":"{\"_collection\":\"cafes\",\"name\":\"Kopi\"}"},
+                {"op":"bogus"}
+            ]}"#);
+        assert_eq!(status, 200);
+        assert_eq!(results[0], Value::Null);
+        assert!(results[1].is_string(), "bad items are reported per document");
+
+        let (status, hits) = send(addr, "POST", "/query", None,
+            r#"{"sql":"SELECT * FROM cafes WHERE name = $1","params":["Kopi"]}"#);
+        assert_eq!(status, 200);
+        assert_eq!(hits.as_array().unwrap().len(), 1);
+        assert_eq!(hits[0]["slug"], "cafes/kopi/1");
+        let (status, hits) = send(addr, "POST", "/query", None,
+            r#"{"sql":"SELECT * FROM cafes WHERE name = $name","params":{"name":"Kopi"}}"#);
+        assert_eq!(status, 200);
+        assert_eq!(hits[0]["slug"], "cafes/kopi/1");
+        let (status, _) = send(addr, "POST", "/query", None,
+            r#"{"sql":"SELECT * FROM cafes WHERE name = $name","params":{}}"#);
+        assert_eq!(status, 400);
+
+        let (status, payload) = send(addr, "GET", "/nodes/cafes%2Fkopi/1", None, "");
+        assert_eq!(status, 200);
+        assert_eq!(payload["name"], "Kopi");
+
+        let (status, traced) = send(addr, "POST", "/query", None,
+            r#"{"sql":"SELECT * FROM cafes WHERE name = 'Kopi'","trace":true,"correlation_id":"req-7"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(traced["rows"][0]["slug"], "cafes/kopi/1");
+        assert_eq!(traced["trace"]["steps"][0]["rows_out"], 1);
+        assert_eq!(traced["trace"]["correlation_id"], "req-7");
+
+        assert_eq!(send(addr, "GET", "/nodes/cafes/nope", None, "").0, 404);
+        assert_eq!(send(addr, "POST", "/query", None, r#"{"sql":"SELEKT"}"#).0, 400);
+        assert_eq!(send(addr, "GET", "/query", None, "").0, 405);
+    }
+
+    #[test]
+    fn projection_profiles_apply_to_queries_and_node_reads() {
+        let mut db = CoreDB::new();
+        db.put("users/1", r#"{"_collection":"users","name":"Ani","email":"ani@example.com"}"#).unwrap();
+        db.set_projection_profile("users", "public", Some(vec!["email".into()]));
+        db.set_projection_profile("users", "staff", Some(vec![]));
+        let config = ServerConfig {
+            tokens: vec!["guest".into(), "admin".into()],
+            profile: Some("public".into()),
+            token_profiles: HashMap::from([("admin".into(), vec!["staff".into()])]),
+            ..ServerConfig::default()
+        };
+        let addr = serve(db, config);
+
+        let (_, hits) = send(addr, "POST", "/query", Some("guest"), r#"{"sql":"SELECT * FROM users"}"#);
+        assert_eq!(hits[0]["payload"]["name"], "Ani");
+        assert!(hits[0]["payload"].get("email").is_none());
+        let (_, payload) = send(addr, "GET", "/nodes/users%2F1", Some("guest"), "");
+        assert!(payload.get("email").is_none() && payload["name"] == "Ani");
+        let (_, hits) = send(addr, "POST", "/query", Some("admin"), r#"{"sql":"SELECT * FROM users","profile":"staff"}"#);
+        assert_eq!(hits[0]["payload"]["email"], "ani@example.com");
+
+        // A client cannot pick a weaker or made-up profile for itself.
+        for profile in ["staff", "made-up"] {
+            let body = format!(r#"{{"sql":"SELECT * FROM users","profile":"{profile}"}}"#);
+            assert_eq!(send(addr, "POST", "/query", Some("guest"), &body).0, 403, "{profile}");
+        }
+        let (status, _) = send(addr, "POST", "/query", Some("admin"), r#"{"sql":"SELECT * FROM users","profile":"made-up"}"#);
+        assert_eq!(status, 403);
+
+        // An undeclared server profile fails closed.
+        let misspelt = serve(CoreDB::new(), ServerConfig { profile: Some("pubic".into()), ..ServerConfig::default() });
+        assert_eq!(send(misspelt, "GET", "/nodes/users%2F1", None, "").0, 500);
+        assert_eq!(send(misspelt, "POST", "/query", None, r#"{"sql":"SELECT * FROM users"}"#).0, 400);
+    }
+
+    #[test]
+    fn oversized_query_results_are_refused() {
+        let mut db = CoreDB::new();
+        for i in 0..3 {
+            db.put(&format!("cafes/{i}"), r#"{"_collection":"cafes"}"#).unwrap();
+        }
+        db.set_query_limits(crate::QueryLimits { max_results: Some(2), ..Default::default() });
+        let addr = start(db, &[]);
+        let (status, body) = send(addr, "POST", "/query", None, r#"{"sql":"SELECT * FROM cafes"}"#);
+        assert_eq!(status, 422);
+        assert!(body["error"].as_str().unwrap().contains("paginate"), "{body}");
+        let (status, hits) = send(addr, "POST", "/query", None, r#"{"sql":"SELECT * FROM cafes LIMIT 2"}"#);
+        assert_eq!((status, hits.as_array().unwrap().len()), (200, 2));
+    }
+
+    #[test]
+    fn bearer_tokens_are_enforced_when_configured() {
+        let addr = start(CoreDB::new(), &["alpha", "beta"]);
+        let body = r#"{"sql":"SELECT * FROM cafes"}"#;
+        assert_eq!(send(addr, "POST", "/query", None, body).0, 401);
+        assert_eq!(send(addr, "POST", "/query", Some("gamma"), body).0, 401);
+        assert_eq!(send(addr, "POST", "/query", Some("beta"), body).0, 200);
+    }
+
+    #[test]
+    fn jobs_run_and_report_over_http() {
+        let mut db = CoreDB::new();
+        db.ingest_vectors("emb", (0..5).map(|i| (format!("v{i}"), vec![i as f32, 1.0])));
+        let addr = start(db, &[]);
+
+        let (status, job) = send(addr, "POST", "/jobs", None, r#"{"kind":"hnsw_build","field":"emb","m":8}"#);
+        assert_eq!(status, 200);
+        assert_eq!((job["kind"].as_str(), job["state"].as_str(), job["done"].as_u64()), (Some("hnsw_build"), Some("succeeded"), Some(5)));
+        let (status, failed) = send(addr, "POST", "/jobs", None, r#"{"kind":"hnsw_build","field":"nope"}"#);
+        assert_eq!((status, failed["state"].as_str()), (200, Some("failed")));
+        assert!(failed["error"].as_str().unwrap().contains("nope"));
+
+        let (status, list) = send(addr, "GET", "/jobs", None, "");
+        assert_eq!((status, list.as_array().unwrap().len()), (200, 2));
+        let id = job["id"].as_u64().unwrap();
+        assert_eq!(send(addr, "GET", &format!("/jobs/{id}"), None, "").1, job);
+        let (status, cancel) = send(addr, "POST", &format!("/jobs/{id}/cancel"), None, "");
+        assert_eq!((status, cancel["cancelled"].as_bool()), (200, Some(false)), "a finished job is not cancelled");
+
+        assert_eq!(send(addr, "GET", "/jobs/99", None, "").0, 404);
+        assert_eq!(send(addr, "POST", "/jobs", None, r#"{"kind":"defrag"}"#).0, 400);
+        assert_eq!(send(addr, "GET", &format!("/jobs/{id}/cancel"), None, "").0, 405);
+    }
+
+    #[test]
+    fn backup_and_restore_jobs_stay_inside_backup_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let mut db = CoreDB::open(data.path()).unwrap();
+        db.put("a", "{}").unwrap();
+        let config = ServerConfig {
+            tokens: vec!["t".into()],
+            backup_dir: Some(dir.path().to_path_buf()),
+            ..ServerConfig::default()
+        };
+        let addr = serve(db, config);
+        let (status, job) = send(addr, "POST", "/jobs", Some("t"), r#"{"kind":"backup","dest":"nightly"}"#);
+        assert_eq!((status, job["state"].as_str()), (200, Some("succeeded")), "{job}");
+        assert!(dir.path().join("nightly").is_dir());
+        for body in [
+            r#"{"kind":"backup","dest":"../escape"}"#,
+            r#"{"kind":"backup","dest":"/tmp/escape"}"#,
+            r#"{"kind":"restore","path":"nightly/../../etc/passwd"}"#,
+        ] {
+            assert_eq!(send(addr, "POST", "/jobs", Some("t"), body).0, 403, "{body}");
+        }
+
+        // Without tokens, or without a backup_dir, the jobs are refused.
+        let open = serve(CoreDB::new(), ServerConfig { backup_dir: Some(dir.path().to_path_buf()), ..ServerConfig::default() });
+        assert_eq!(send(open, "POST", "/jobs", None, r#"{"kind":"backup","dest":"x"}"#).0, 403);
+        let unset = start(CoreDB::new(), &["t"]);
+        assert_eq!(send(unset, "POST", "/jobs", Some("t"), r#"{"kind":"restore","path":"x"}"#).0, 403);
+        assert!(!dir.path().join("x").exists());
+    }
+
+    #[test]
+    fn token_authorizers_scope_reads_writes_and_jobs() {
+        let mut db = CoreDB::new();
+        db.put("users/1", r#"{"_collection":"users","name":"Ani"}"#).unwrap();
+        db.put("secrets/1", r#"{"_collection":"secrets","code":"x"}"#).unwrap();
+        let read_users: Arc<dyn Authorizer + Send + Sync> = Arc::new(|access: Access, cols: Option<&[String]>| {
+            match (access, cols) {
+                (Access::Read, Some(c)) if c.iter().all(|n| n == "users") => Ok(()),
+                _ => Err("read-only, users only".to_string()),
+            }
+        });
+        let config = ServerConfig {
+            tokens: vec!["reader".into(), "admin".into()],
+            authorizers: HashMap::from([("reader".to_string(), read_users)]),
+            ..ServerConfig::default()
+        };
+        let addr = serve(db, config);
+
+        let (status, hits) = send(addr, "POST", "/query", Some("reader"), r#"{"sql":"SELECT * FROM users"}"#);
+        assert_eq!((status, hits.as_array().unwrap().len()), (200, 1));
+        let query = r#"{"sql":"SELECT * FROM secrets WHERE code = $c","params":{"c":"x"}}"#;
+        assert_eq!(send(addr, "POST", "/query", Some("reader"), query).0, 403);
+        assert_eq!(send(addr, "GET", "/nodes/users%2F1", Some("reader"), "").0, 200);
+        assert_eq!(send(addr, "GET", "/nodes/secrets%2F1", Some("reader"), "").0, 403);
+        let put = r#"{"op":"put","slug":"users/2","payload":"{\"_collection\":\"users\"}"}"#;
+        let (status, results) = send(addr, "POST", "/mutate", Some("reader"), put);
+        assert_eq!(status, 200);
+        assert!(results[0].as_str().unwrap().contains("read-only"), "{results}");
+        assert_eq!(send(addr, "POST", "/jobs", Some("reader"), r#"{"kind":"compact"}"#).0, 403);
+
+        // Tokens without an authorizer keep full access.
+        assert_eq!(send(addr, "POST", "/query", Some("admin"), query).1.as_array().unwrap().len(), 1);
+        assert_eq!(send(addr, "POST", "/mutate", Some("admin"), put).1, json!([null]));
+    }
+
+    #[test]
+    fn oversized_headers_get_431() {
+        let addr = serve(CoreDB::new(), ServerConfig { max_header_bytes: 4096, ..ServerConfig::default() });
+        let status = |request: String| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let _ = stream.write_all(request.as_bytes());
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response[9..12].parse::<u16>().unwrap()
+        };
+        assert_eq!(status(format!("GET /jobs HTTP/1.1\r\nX-Pad: {}", "a".repeat(8192))), 431);
+        let many: String = (0..=MAX_HEADERS).map(|i| format!("X-{i}: 1\r\n")).collect();
+        assert_eq!(status(format!("GET /jobs HTTP/1.1\r\n{many}\r\n")), 431);
+        assert_eq!(status("GET /jobs HTTP/1.1\r\nHost: test\r\n\r\n".into()), 200);
+    }
+
+    #[test]
+    fn token_eq_matches_whole_tokens_only() {
+        assert!(token_eq("s3cret", "s3cret"));
+        assert!(!token_eq("s3cret", "s3cre"));
+        assert!(!token_eq("s3cret", "s3creT"));
+        assert!(!token_eq("", "x"));
+    }
+
+    #[test]
+    fn percent_decode_handles_escapes_and_stray_percent() {
+        assert_eq!(percent_decode("a%2Fb%20c"), "a/b c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
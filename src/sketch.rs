@@ -0,0 +1,147 @@
+//! Fixed-memory summaries for aggregate terminals over large result sets.
+
+// ── HyperLogLog ───────────────────────────────────────────────────────────────
+
+/// HyperLogLog cardinality sketch over 64-bit hashes.
+///
+/// `2^precision` one-byte registers; standard error ≈ `1.04 / sqrt(2^precision)`
+/// (≈ 0.8 % at the default precision of 14, using 16 KiB).
+pub(crate) struct HyperLogLog {
+    precision: u32,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub(crate) const DEFAULT_PRECISION: u32 = 14;
+
+    pub(crate) fn new(precision: u32) -> Self {
+        let precision = precision.clamp(4, 18);
+        Self { precision, registers: vec![0; 1 << precision] }
+    }
+
+    pub(crate) fn insert_hash(&mut self, hash: u64) {
+        let idx = (hash >> (64 - self.precision)) as usize;
+        // Sentinel bit keeps the rank bounded when the remaining bits are zero.
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[idx] {
+            self.registers[idx] = rank;
+        }
+    }
+
+    pub(crate) fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let mut sum = 0.0;
+        let mut zeros = 0usize;
+        for &r in &self.registers {
+            sum += 1.0 / (1u64 << r) as f64;
+            if r == 0 {
+                zeros += 1;
+            }
+        }
+        let raw = alpha * m * m / sum;
+        // Small-range correction (linear counting). 64-bit hashes make the
+        // large-range correction unnecessary.
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+// ── Quantiles ─────────────────────────────────────────────────────────────────
+
+/// Bounded uniform reservoir of numeric samples for quantile estimates.
+///
+/// Exact while the input fits in `capacity`; beyond that every value has the
+/// same chance of being kept (Algorithm R), so quantiles stay unbiased with
+/// rank error ≈ `1 / sqrt(capacity)`.
+pub(crate) struct QuantileSample {
+    capacity: usize,
+    seen: u64,
+    samples: Vec<f64>,
+    rng: u64,
+}
+
+impl QuantileSample {
+    pub(crate) const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            seen: 0,
+            samples: Vec::new(),
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        self.seen += 1;
+        if self.samples.len() < self.capacity {
+            self.samples.push(v);
+            return;
+        }
+        // xorshift64 — deterministic so repeated queries agree.
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let j = self.rng % self.seen;
+        if (j as usize) < self.capacity {
+            self.samples[j as usize] = v;
+        }
+    }
+
+    /// Linearly interpolated quantile (`q` in `0.0..=1.0`), or `None` when
+    /// nothing was inserted.
+    pub(crate) fn quantile(&self, q: f64) -> Option<f64> {
+        if self.samples.is_empty() || q.is_nan() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+        let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+        let lo = pos.floor() as usize;
+        let hi = pos.ceil() as usize;
+        let frac = pos - lo as f64;
+        Some(sorted[lo] + (sorted[hi] - sorted[lo]) * frac)
+    }
+}
+
+// ── SimHash ───────────────────────────────────────────────────────────────────
+
+/// 64-bit SimHash of a token stream: each bit is the majority vote of that
+/// bit across the token hashes (repeated tokens vote again). Documents that
+/// share most tokens land a small Hamming distance apart.
+pub(crate) fn simhash<'a>(tokens: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut votes = [0i32; 64];
+    for t in tokens {
+        let h = crate::sk_hash(t);
+        for (bit, v) in votes.iter_mut().enumerate() {
+            *v += if (h >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    votes
+        .iter()
+        .enumerate()
+        .filter(|(_, &v)| v > 0)
+        .fold(0, |fp, (bit, _)| fp | (1 << bit))
+}
+
+/// Split a fingerprint into `bands` contiguous bit ranges. Two fingerprints
+/// within Hamming distance `bands - 1` agree on at least one band, so
+/// bucketing by `(band, value)` finds every such pair.
+pub(crate) fn simhash_bands(fp: u64, bands: u32) -> impl Iterator<Item = (u32, u64)> {
+    let bands = bands.clamp(1, 64);
+    let width = 64 / bands;
+    (0..bands).map(move |b| {
+        let lo = b * width;
+        let hi = if b + 1 == bands { 64 } else { lo + width };
+        let mask = if hi - lo == 64 { u64::MAX } else { ((1u64 << (hi - lo)) - 1) << lo };
+        (b, fp & mask)
+    })
+}
@@ -26,6 +26,7 @@
 //! DELETE FROM collection | ALL [WHERE ...]
 //! DELETE ('from')-[:KIND]->('to')
 //! MATCH (node)-[edge]->(node) [WHERE ...] RETURN vars [LIMIT n]   -- simple traversal
+//! MATCH (a:col)-[:e1]->(b)-[:e2]->(c:col) [WHERE ...] RETURN a, b, c   -- one row per path
 //! SELECT expr AS alias [, ...] FROM MATCH (a)-[r]->(b) [WHERE ...] [GROUP BY] [ORDER BY] [LIMIT]
 //! SELECT expr AS alias [, ...] FROM MATCH SHORTEST (a)-[r*]->(b) WHERE a._key='x' AND b._key='y'
 //! SELECT expr FROM MATCH (a)-[:e]->(b), collection AS alias   -- multi-FROM cross-join
@@ -33,7 +34,7 @@
 //! CREATE TABLE collection (field type, ...)
 //!     [_key TEXT PRIMARY KEY, ...]
 //!     [field TIMESTAMPTZ DEFAULT NOW(), ...]
-//! WITH (hash: ['_key'], range: ['age'], fulltext: ['name'], bm25: ['bio'], spatial: ['location'])
+//! WITH (hash: ['_key'], range: ['age'], fulltext: ['name'], bm25: ['bio'], spatial: ['location'], unique: ['email'])
 //! DROP TABLE [IF EXISTS] collection
 //! DROP INDEX [IF EXISTS] ON collection USING method (field)
 //!
@@ -106,8 +107,20 @@ pub enum SqlError {
     ParamOutOfRange { index: usize, count: usize },
     /// Parameter $N has the wrong type.
     ParamTypeMismatch { index: usize, expected: &'static str },
+    /// Placeholder `$name` has no entry in the params object, or the
+    /// statement was run with positional parameters only.
+    UnboundParam(String),
     /// Transaction protocol error (nested BEGIN, COMMIT/ROLLBACK without active transaction).
     TransactionError(String),
+    /// The query exceeds a configured [`QueryLimits`](crate::QueryLimits) cap.
+    LimitExceeded(String),
+    /// A write would break a collection [`Quota`](crate::Quota).
+    QuotaExceeded(crate::QuotaExceeded),
+    /// A write would duplicate a schema `unique` field
+    /// ([`UniqueViolation`](crate::UniqueViolation)).
+    UniqueViolation(crate::UniqueViolation),
+    /// An [`Authorizer`](crate::Authorizer) rejected the statement.
+    Unauthorized(String),
 }
 
 impl fmt::Display for SqlError {
@@ -146,7 +159,12 @@ impl fmt::Display for SqlError {
                 f,
                 "parameter ${index}: expected {expected}"
             ),
+            SqlError::UnboundParam(name) => write!(f, "parameter ${name} is not bound"),
             SqlError::TransactionError(msg) => write!(f, "transaction error: {msg}"),
+            SqlError::LimitExceeded(msg) => write!(f, "query limit exceeded: {msg}"),
+            SqlError::QuotaExceeded(q) => write!(f, "{q}"),
+            SqlError::UniqueViolation(u) => write!(f, "{u}"),
+            SqlError::Unauthorized(msg) => write!(f, "not authorized: {msg}"),
         }
     }
 }
@@ -189,7 +207,9 @@ enum Tok {
     VecDotOp,    // <#>  inner product
     VecL1Op,     // <+>  Manhattan (L1) distance
     ArrayContains, // @>  PostgreSQL array containment
+    Regex(bool),   // ~ / ~*  PostgreSQL regex match (true = case-insensitive)
     Param(usize), // $1, $2, ... (1-indexed, like PostgreSQL)
+    NamedParam(String), // $name, bound from a params object before parsing
     Eof,
 }
 
@@ -438,7 +458,20 @@ fn keyword(s: &str) -> Option<Kw> {
 
 // ── Tokenizer ─────────────────────────────────────────────────────────────────
 
+/// Tokenize a statement bound positionally, where `$name` placeholders have
+/// nothing to bind to.
 fn tokenize(sql: &str) -> Result<Vec<Tok>, SqlError> {
+    let tokens = lex(sql)?;
+    match tokens.iter().find_map(|t| match t {
+        Tok::NamedParam(name) => Some(name),
+        _ => None,
+    }) {
+        Some(name) => Err(SqlError::UnboundParam(name.clone())),
+        None => Ok(tokens),
+    }
+}
+
+fn lex(sql: &str) -> Result<Vec<Tok>, SqlError> {
     let chars: Vec<char> = sql.chars().collect();
     let len = chars.len();
     let mut i = 0;
@@ -624,6 +657,15 @@ fn tokenize(sql: &str) -> Result<Vec<Tok>, SqlError> {
                     None => tokens.push(Tok::Ident(s)),
                 }
             }
+            '~' => {
+                if i + 1 < len && chars[i + 1] == '*' {
+                    tokens.push(Tok::Regex(true));
+                    i += 2;
+                } else {
+                    tokens.push(Tok::Regex(false));
+                    i += 1;
+                }
+            }
             '@' => {
                 if i + 1 < len && chars[i + 1] == '>' {
                     tokens.push(Tok::ArrayContains);
@@ -635,6 +677,14 @@ fn tokenize(sql: &str) -> Result<Vec<Tok>, SqlError> {
                     });
                 }
             }
+            '$' if i + 1 < len && (chars[i + 1].is_alphabetic() || chars[i + 1] == '_') => {
+                i += 1;
+                let start = i;
+                while i < len && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Tok::NamedParam(chars[start..i].iter().collect()));
+            }
             '$' => {
                 i += 1;
                 let start = i;
@@ -709,6 +759,10 @@ enum CondExpr {
         pattern: String,
         case_insensitive: bool,
     },
+    Regex {
+        field: String,
+        pattern: regex::Regex,
+    },
     StDWithin {
         lat: f64,
         lon: f64,
@@ -755,6 +809,15 @@ enum CondExpr {
         query: Vec<f32>,
         k: usize,
     },
+    /// `HYBRID(vec_field, [f32, ...], text_field, 'query', k [, vector_weight])`.
+    Hybrid {
+        vector_field: String,
+        query: Vec<f32>,
+        text_field: String,
+        text: String,
+        k: usize,
+        fusion: crate::query::Fusion,
+    },
     /// `field IS NULL` or `field IS NOT NULL`.
     IsNull {
         field: String,
@@ -872,7 +935,7 @@ pub enum CompiledMutation {
     /// CREATE TABLE: define schema for a collection.
     CreateTable {
         collection: String,
-        schema: TableSchema,
+        schema: Box<TableSchema>,
     },
     /// CREATE INDEX: build an index on a collection field (PostgreSQL style).
     CreateIndex {
@@ -993,6 +1056,9 @@ pub enum IndexMethod {
     Hnsw,
     /// Search: positional inverted index with RoaringBitmaps.
     Search,
+    /// SimHash: 64-bit content fingerprint over the listed text fields, for
+    /// near-duplicate detection (`Set::near_duplicates`).
+    SimHash,
 }
 
 impl std::fmt::Display for IndexMethod {
@@ -1006,6 +1072,7 @@ impl std::fmt::Display for IndexMethod {
             Self::Spatial => "spatial",
             Self::Hnsw    => "hnsw",
             Self::Search  => "search",
+            Self::SimHash => "simhash",
         };
         f.write_str(s)
     }
@@ -1053,6 +1120,13 @@ pub struct IndexHint {
     /// Positional search indexes — each entry is a list of fields covered by one index.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub search: Vec<Vec<String>>,
+    /// Text fields folded into each node's SimHash fingerprint.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub simhash: Vec<String>,
+    /// Fields no two nodes of the collection may share a value for
+    /// (`WITH (unique: ['email'])`). Enforced on write through a field index.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique: Vec<String>,
     /// Version at which each index was last built.
     /// Key: `"method:field"` — e.g. `"gin:name"`, `"btree:price"`.
     /// Absent key (or stored 0) means built before versioning was introduced → rebuild.
@@ -1077,6 +1151,8 @@ impl Default for IndexHint {
             spatial: Vec::new(),
             vector: Vec::new(),
             search: Vec::new(),
+            simhash: Vec::new(),
+            unique: Vec::new(),
             build_versions: std::collections::HashMap::new(),
         }
     }
@@ -1789,15 +1865,21 @@ impl Parser {
                     namespace, name
                 )));
             }
-            // Aggregate functions: COUNT(*|field), SUM(field), AVG(field), MIN(field), MAX(field)
+            // Aggregate functions: COUNT(*|field|DISTINCT field), SUM(field), AVG(field),
+            // MIN(field), MAX(field), MEDIAN(field)
             if matches!(
                 func_upper.as_str(),
-                "COUNT" | "SUM" | "AVG" | "MIN" | "MAX"
+                "COUNT" | "SUM" | "AVG" | "MIN" | "MAX" | "MEDIAN"
             ) {
                 self.advance(); // consume (
+                let mut func_upper = func_upper;
                 let arg = if func_upper == "COUNT" && matches!(self.peek(), Tok::Star) {
                     self.advance(); // consume *
                     "*".to_string()
+                } else if func_upper == "COUNT" && matches!(self.peek(), Tok::Kw(Kw::Distinct)) {
+                    self.advance(); // consume DISTINCT
+                    func_upper = "COUNT_DISTINCT".to_string();
+                    self.expect_ident()?
                 } else {
                     self.expect_ident()?
                 };
@@ -2019,6 +2101,14 @@ impl Parser {
                 let pattern = self.expect_str()?;
                 Ok(CondExpr::Like { field, pattern, case_insensitive: true })
             }
+            Tok::Regex(case_insensitive) => {
+                self.advance();
+                let pattern = self.expect_str()?;
+                let pattern = if case_insensitive { format!("(?i){pattern}") } else { pattern };
+                let pattern = regex::Regex::new(&pattern)
+                    .map_err(|e| SqlError::InvalidValue(format!("regex `{pattern}`: {e}")))?;
+                Ok(CondExpr::Regex { field, pattern })
+            }
             Tok::Kw(Kw::Is) => {
                 self.advance();
                 let negated = if matches!(self.peek(), Tok::Kw(Kw::Not)) {
@@ -2032,7 +2122,7 @@ impl Parser {
             }
             Tok::Eof => Err(SqlError::UnexpectedEnd { expected: "comparison operator" }),
             other => Err(SqlError::UnexpectedToken {
-                expected: "comparison operator (=, !=, <>, >, <, >=, <=, BETWEEN, IN, NOT IN, LIKE, ILIKE)",
+                expected: "comparison operator (=, !=, <>, >, <, >=, <=, BETWEEN, IN, NOT IN, LIKE, ILIKE, ~, ~*)",
                 got: format!("{other:?}"),
             }),
         }
@@ -2083,13 +2173,18 @@ impl Parser {
         // Aggregate functions in HAVING: COUNT(*) > 5, SUM(price) < 100, etc.
         // expect_ident() returns lowercase keyword names ("count", "sum", …)
         let upper = field.to_uppercase();
-        if matches!(upper.as_str(), "COUNT" | "SUM" | "AVG" | "MIN" | "MAX")
+        if matches!(upper.as_str(), "COUNT" | "SUM" | "AVG" | "MIN" | "MAX" | "MEDIAN")
             && matches!(self.peek(), Tok::LParen)
         {
             self.advance(); // consume (
+            let mut upper = upper;
             let arg = if upper == "COUNT" && matches!(self.peek(), Tok::Star) {
                 self.advance();
                 "*".to_string()
+            } else if upper == "COUNT" && matches!(self.peek(), Tok::Kw(Kw::Distinct)) {
+                self.advance();
+                upper = "COUNT_DISTINCT".to_string();
+                self.expect_ident()?
             } else {
                 self.expect_ident()?
             };
@@ -2153,6 +2248,11 @@ impl Parser {
             return self.parse_vector_near_function();
         }
 
+        // HYBRID(vec_field, [f32, ...], text_field, 'query', k [, vector_weight])
+        if upper == "HYBRID" {
+            return self.parse_hybrid_function();
+        }
+
         // Date scalar functions on the LHS: YEAR(field) > 2023, MONTH(created_at) = 4, etc.
         if matches!(
             upper.as_str(),
@@ -2341,6 +2441,33 @@ impl Parser {
         Ok(CondExpr::VectorNear { field, query, k })
     }
 
+    /// Parse `HYBRID(vec_field, [f32, ...], text_field, 'query', k [, vector_weight])`:
+    /// reciprocal rank fusion, or a weighted sum when the weight is given.
+    fn parse_hybrid_function(&mut self) -> Result<CondExpr, SqlError> {
+        self.expect_lparen()?;
+        let vector_field = self.expect_ident()?;
+        self.expect_comma()?;
+        let query = self.parse_f32_array_or_param()?;
+        self.expect_comma()?;
+        let text_field = self.expect_ident()?;
+        self.expect_comma()?;
+        let text = self.expect_str()?;
+        self.expect_comma()?;
+        let k = self.expect_usize()?;
+        let fusion = if matches!(self.peek(), Tok::Comma) {
+            self.advance();
+            let vector = self.expect_num()?;
+            if !(0.0..=1.0).contains(&vector) {
+                return Err(SqlError::InvalidValue(format!("HYBRID vector weight must be in 0..=1, got {vector}")));
+            }
+            crate::query::Fusion::Weighted { vector: vector as f32 }
+        } else {
+            crate::query::Fusion::default()
+        };
+        self.expect_rparen()?;
+        Ok(CondExpr::Hybrid { vector_field, query, text_field, text, k, fusion })
+    }
+
     /// Parse an f32 vector: either `[num, num, ...]` literal or `$N` param.
     fn parse_f32_array_or_param(&mut self) -> Result<Vec<f32>, SqlError> {
         if let Tok::Param(idx) = self.peek().clone() {
@@ -3018,10 +3145,17 @@ impl Parser {
             );
         }
 
+        let indexes = if matches!(self.peek(), Tok::Kw(Kw::With)) {
+            self.advance();
+            self.parse_with_options()?
+        } else {
+            IndexHint::default()
+        };
+
         let mut schema = TableSchema {
             collection,
             fields,
-            indexes: IndexHint::default(),
+            indexes,
         };
 
         schema.fields.push(FieldDef {
@@ -3206,8 +3340,9 @@ impl Parser {
             "spatial" => IndexMethod::Spatial,
             "hnsw"    => IndexMethod::Hnsw,
             "search"  => IndexMethod::Search,
+            "simhash" => IndexMethod::SimHash,
             other => return Err(SqlError::UnexpectedToken {
-                expected: "btree, hash, gin, gist, bm25, spatial, hnsw, or search",
+                expected: "btree, hash, gin, gist, bm25, spatial, hnsw, search, or simhash",
                 got: other.to_string(),
             }),
         };
@@ -3270,9 +3405,13 @@ impl Parser {
                     self.expect_colon()?;
                     hints.vector = self.parse_string_list()?;
                 }
+                "unique" => {
+                    self.expect_colon()?;
+                    hints.unique = self.parse_string_list()?;
+                }
                 _ => {
                     return Err(SqlError::UnexpectedToken {
-                        expected: "hash, range, fulltext, bm25, spatial, or vector",
+                        expected: "hash, range, fulltext, bm25, spatial, vector, or unique",
                         got: ident,
                     })
                 }
@@ -3809,7 +3948,7 @@ impl Parser {
     /// [LIMIT n]
     /// ```
     fn parse_match_agg_path(&mut self) -> Result<crate::query::MatchAggStmt, SqlError> {
-        use crate::query::{HopSpec, MatchAggStart, MatchAggStmt};
+        use crate::query::{MatchAggStart, MatchAggStmt};
 
         // ── Start node ────────────────────────────────────────────────────
         let start_node = self.parse_match_node()?;
@@ -3824,88 +3963,7 @@ impl Parser {
         };
 
         // ── Hop chain ─────────────────────────────────────────────────────
-        // Edge pattern forms:
-        //   -[r:edge_type]->   edge_bind="r", type=edge_type
-        //   -[:edge_type]->    edge_bind=None, type=edge_type
-        //   -[r*]->            edge_bind="r", type=any (0)
-        //   -[r*1..3]->        edge_bind="r", type=any (range ignored by executor for now)
-        //   -[*]->             edge_bind=None, type=any
-        let mut hops: Vec<HopSpec> = Vec::new();
-        while matches!(self.peek(), Tok::Dash) {
-            self.advance(); // consume '-'
-            self.expect_lbracket()?;
-
-            // Determine edge_bind and edge_type_hash from the pattern inside [...]
-            let mut edge_bind: Option<String> = None;
-            let mut edge_type_hash: u64 = 0; // 0 = any
-
-            match self.peek().clone() {
-                Tok::Ident(name) => {
-                    self.advance(); // consume ident (potential bind name)
-                    match self.peek() {
-                        Tok::Colon => {
-                            // r:edge_type
-                            self.advance(); // consume ':'
-                            let et = self.expect_ident()?;
-                            edge_bind = Some(name);
-                            edge_type_hash = sk_hash(&et);
-                        }
-                        _ => {
-                            // r* or r alone — edge bind, any type
-                            edge_bind = Some(name);
-                        }
-                    }
-                }
-                Tok::Colon => {
-                    // :edge_type — no bind name
-                    self.advance(); // consume ':'
-                    let et = self.expect_ident()?;
-                    edge_type_hash = sk_hash(&et);
-                }
-                _ => { /* * or ] — anonymous, any type */ }
-            }
-
-            // Optional depth: *min..max  (e.g. *1..3, *3..3)
-            let (mut min_depth, mut max_depth) = (1u32, 1u32);
-            if matches!(self.peek(), Tok::Star) {
-                self.advance(); // consume '*'
-                if let Tok::Num(_) = self.peek().clone() {
-                    let mn = self.expect_num()? as u32;
-                    if matches!(self.peek(), Tok::DotDot) {
-                        self.advance(); // consume '..'
-                        let mx = self.expect_num()? as u32;
-                        min_depth = mn;
-                        max_depth = mx;
-                    } else {
-                        // *N alone means exactly N hops
-                        min_depth = mn;
-                        max_depth = mn;
-                    }
-                }
-                // bare '*' (no number) → keep defaults (1..1 single hop)
-            }
-            // Consume any remaining tokens until ']' (forward-compat)
-            loop {
-                match self.peek() {
-                    Tok::RBracket | Tok::Eof => break,
-                    _ => { self.advance(); }
-                }
-            }
-            self.expect_rbracket()?;
-
-            if !matches!(self.peek(), Tok::Arrow) {
-                return Err(SqlError::UnexpectedToken {
-                    expected: "->",
-                    got: format!("{:?}", self.peek()),
-                });
-            }
-            self.advance(); // consume '->'
-            self.expect_lparen()?;
-            let node_bind = self.expect_ident()?;
-            let node_label = if matches!(self.peek(), Tok::Colon) { self.advance(); Some(self.expect_ident()?) } else { None };
-            self.expect_rparen()?;
-            hops.push(HopSpec { edge_type_hash, node_bind, edge_bind, min_depth, max_depth, node_label });
-        }
+        let hops = self.parse_hop_chain()?;
 
         // ── WHERE clause (optional) ───────────────────────────────────────
         // Two kinds of conditions:
@@ -4015,7 +4073,7 @@ impl Parser {
     ///
     /// The SELECT list acts as the RETURN clause; no RETURN keyword is present.
     fn parse_select_from_match(&mut self) -> Result<crate::query::MatchAggStmt, SqlError> {
-        use crate::query::{HopSpec, MatchAggStart, MatchAggStmt};
+        use crate::query::{MatchAggStart, MatchAggStmt};
 
         self.expect_kw(Kw::Select, "SELECT")?;
         let returns = self.parse_agg_return_list()?;
@@ -4036,73 +4094,7 @@ impl Parser {
         };
 
         // ── Hop chain (same as parse_match_agg_path) ──────────────────────
-        let mut hops: Vec<HopSpec> = Vec::new();
-        while matches!(self.peek(), Tok::Dash) {
-            self.advance(); // consume '-'
-            self.expect_lbracket()?;
-
-            let mut edge_bind: Option<String> = None;
-            let mut edge_type_hash: u64 = 0;
-
-            match self.peek().clone() {
-                Tok::Ident(name) => {
-                    self.advance();
-                    match self.peek() {
-                        Tok::Colon => {
-                            self.advance();
-                            let et = self.expect_ident()?;
-                            edge_bind = Some(name);
-                            edge_type_hash = sk_hash(&et);
-                        }
-                        _ => { edge_bind = Some(name); }
-                    }
-                }
-                Tok::Colon => {
-                    self.advance();
-                    let et = self.expect_ident()?;
-                    edge_type_hash = sk_hash(&et);
-                }
-                _ => {}
-            }
-
-            // Optional depth: *min..max or *N
-            let (mut min_depth, mut max_depth) = (1u32, 1u32);
-            if matches!(self.peek(), Tok::Star) {
-                self.advance();
-                if let Tok::Num(_) = self.peek().clone() {
-                    let mn = self.expect_num()? as u32;
-                    if matches!(self.peek(), Tok::DotDot) {
-                        self.advance();
-                        let mx = self.expect_num()? as u32;
-                        min_depth = mn;
-                        max_depth = mx;
-                    } else {
-                        min_depth = mn;
-                        max_depth = mn;
-                    }
-                }
-            }
-            loop {
-                match self.peek() {
-                    Tok::RBracket | Tok::Eof => break,
-                    _ => { self.advance(); }
-                }
-            }
-            self.expect_rbracket()?;
-
-            if !matches!(self.peek(), Tok::Arrow) {
-                return Err(SqlError::UnexpectedToken {
-                    expected: "->",
-                    got: format!("{:?}", self.peek()),
-                });
-            }
-            self.advance();
-            self.expect_lparen()?;
-            let node_bind = self.expect_ident()?;
-            let node_label = if matches!(self.peek(), Tok::Colon) { self.advance(); Some(self.expect_ident()?) } else { None };
-            self.expect_rparen()?;
-            hops.push(HopSpec { edge_type_hash, node_bind, edge_bind, min_depth, max_depth, node_label });
-        }
+        let hops = self.parse_hop_chain()?;
 
         // ── WHERE (same as parse_match_agg_path) ──────────────────────────
         let mut dest_where: Vec<crate::query::DestWhere> = Vec::new();
@@ -4504,50 +4496,7 @@ impl Parser {
                                 None => MatchAggStart::All,
                             },
                         };
-                        let mut hops = Vec::new();
-                        while matches!(self.peek(), Tok::Dash) {
-                            self.advance();
-                            self.expect_lbracket()?;
-                            let mut edge_bind: Option<String> = None;
-                            let mut edge_type_hash: u64 = 0;
-                            match self.peek().clone() {
-                                Tok::Ident(name) => {
-                                    self.advance();
-                                    match self.peek() {
-                                        Tok::Colon => { self.advance(); let et = self.expect_ident()?; edge_bind = Some(name); edge_type_hash = sk_hash(&et); }
-                                        _ => { edge_bind = Some(name); }
-                                    }
-                                }
-                                Tok::Colon => { self.advance(); let et = self.expect_ident()?; edge_type_hash = sk_hash(&et); }
-                                _ => {}
-                            }
-                            // Optional depth: *min..max or *N
-                            let (mut min_depth, mut max_depth) = (1u32, 1u32);
-                            if matches!(self.peek(), Tok::Star) {
-                                self.advance();
-                                if let Tok::Num(_) = self.peek().clone() {
-                                    let mn = self.expect_num()? as u32;
-                                    if matches!(self.peek(), Tok::DotDot) {
-                                        self.advance();
-                                        let mx = self.expect_num()? as u32;
-                                        min_depth = mn; max_depth = mx;
-                                    } else {
-                                        min_depth = mn; max_depth = mn;
-                                    }
-                                }
-                            }
-                            loop { match self.peek() { Tok::RBracket | Tok::Eof => break, _ => { self.advance(); } } }
-                            self.expect_rbracket()?;
-                            if !matches!(self.peek(), Tok::Arrow) {
-                                return Err(SqlError::UnexpectedToken { expected: "->", got: format!("{:?}", self.peek()) });
-                            }
-                            self.advance();
-                            self.expect_lparen()?;
-                            let node_bind = self.expect_ident()?;
-                            let node_label = if matches!(self.peek(), Tok::Colon) { self.advance(); Some(self.expect_ident()?) } else { None };
-                            self.expect_rparen()?;
-                            hops.push(crate::query::HopSpec { edge_type_hash, node_bind, edge_bind, min_depth, max_depth, node_label });
-                        }
+                        let hops = self.parse_hop_chain()?;
                         // Optional WHERE (only _key on start var acted on)
                         if matches!(self.peek(), Tok::Kw(Kw::Where)) {
                             self.advance();
@@ -5303,12 +5252,21 @@ impl Parser {
         Ok((start, Some(name), Some(label), inline_where))
     }
 
-    /// Parse hop chain: `-[edge_spec]->(node)` repeated.
+    /// Parse hop chain: `-[edge_spec]->(node)` or `<-[edge_spec]-(node)` repeated.
+    ///
+    /// Edge pattern forms:
+    ///   -[r:edge_type]->   edge_bind="r", type=edge_type
+    ///   -[:edge_type]->    edge_bind=None, type=edge_type
+    ///   -[r*]->            edge_bind="r", type=any (0)
+    ///   -[r*1..3]->        edge_bind="r", type=any, depth 1..3
+    ///   -[*]->             edge_bind=None, type=any
+    ///   <-[r:edge_type]-   same, walking incoming edges (`reverse`)
     fn parse_hop_chain(&mut self) -> Result<Vec<crate::query::HopSpec>, SqlError> {
         use crate::query::HopSpec;
         let mut hops: Vec<HopSpec> = Vec::new();
-        while matches!(self.peek(), Tok::Dash) {
-            self.advance(); // consume '-'
+        while matches!(self.peek(), Tok::Dash | Tok::BackArrow) {
+            let reverse = matches!(self.peek(), Tok::BackArrow);
+            self.advance(); // consume '-' or '<-'
             self.expect_lbracket()?;
 
             let mut edge_bind: Option<String> = None;
@@ -5359,9 +5317,10 @@ impl Parser {
             }
             self.expect_rbracket()?;
 
-            if !matches!(self.peek(), Tok::Arrow) {
+            let (close, expected) = if reverse { (Tok::Dash, "-") } else { (Tok::Arrow, "->") };
+            if *self.peek() != close {
                 return Err(SqlError::UnexpectedToken {
-                    expected: "->",
+                    expected,
                     got: format!("{:?}", self.peek()),
                 });
             }
@@ -5370,7 +5329,7 @@ impl Parser {
             let node_bind = self.expect_ident()?;
             let node_label = if matches!(self.peek(), Tok::Colon) { self.advance(); Some(self.expect_ident()?) } else { None };
             self.expect_rparen()?;
-            hops.push(HopSpec { edge_type_hash, node_bind, edge_bind, min_depth, max_depth, node_label });
+            hops.push(HopSpec { edge_type_hash, node_bind, edge_bind, min_depth, max_depth, node_label, reverse });
         }
         Ok(hops)
     }
@@ -5599,6 +5558,7 @@ fn compile_cond(cond: CondExpr) -> Step {
             pattern,
             case_insensitive,
         } => Step::Like(field, pattern, case_insensitive),
+        CondExpr::Regex { field, pattern } => Step::WhereRegex(field, pattern),
         CondExpr::StDWithin {
             lat,
             lon,
@@ -5623,6 +5583,9 @@ fn compile_cond(cond: CondExpr) -> Step {
         } => Step::Bm25Filter(field, query, min_score),
         CondExpr::Bm25Func { .. } => unreachable!("Bm25Func should not reach compile_cond"),
         CondExpr::VectorNear { field, query, k } => Step::VectorNear { field, query, k },
+        CondExpr::Hybrid { vector_field, query, text_field, text, k, fusion } => {
+            Step::Hybrid { vector_field, query, text_field, text, k, fusion }
+        }
         CondExpr::IsNull { field, negated } => Step::WhereIsNull(field, negated),
         CondExpr::Search { query } => Step::SearchFilter(query),
         CondExpr::Not(inner) => Step::WhereNot(Box::new(compile_cond(*inner))),
@@ -5930,6 +5893,7 @@ fn compile_update(stmt: UpdateStmt) -> CompiledMutation {
 
 /// The result of parsing a MATCH statement — either a compiled step pipeline
 /// (simple graph traversal) or an aggregate statement.
+#[derive(Clone)]
 pub enum MatchOrAgg {
     /// Standard MATCH — compiled to a Vec<Step> pipeline.
     Steps(Vec<Step>),
@@ -5942,8 +5906,10 @@ pub enum MatchOrAgg {
 }
 
 fn parse_match_or_agg_inner(sql: &str, params: Vec<Value>) -> Result<MatchOrAgg, SqlError> {
-    let tokens = tokenize(sql)?;
+    parse_tokens(tokenize(sql)?, params)
+}
 
+fn parse_tokens(tokens: Vec<Tok>, params: Vec<Value>) -> Result<MatchOrAgg, SqlError> {
     // Multi-FROM: SELECT … FROM source1, source2, … (comma between FROM sources)
     if is_multi_from(&tokens) {
         let stmt = Parser::with_params(tokens, params).parse_select_multi_from()?;
@@ -6004,12 +5970,128 @@ pub fn parse_match_or_agg_params(sql: &str, params: Vec<Value>) -> Result<MatchO
     parse_match_or_agg_inner(sql, params)
 }
 
+/// Parse a MATCH/SELECT statement with `$name` placeholders bound from
+/// `params`. Every placeholder must have an entry; extra entries are ignored.
+///
+/// # Errors
+/// [`SqlError::UnboundParam`] for a placeholder missing from `params`, and
+/// [`SqlError::InvalidValue`] when `$name` and `$1` placeholders are mixed.
+pub fn parse_match_or_agg_named(
+    sql: &str,
+    params: &serde_json::Map<String, Value>,
+) -> Result<MatchOrAgg, SqlError> {
+    let (tokens, names) = number_named(lex(sql)?)?;
+    parse_tokens(tokens, named_values(&names, params)?)
+}
+
+/// Rewrite `$name` placeholders into `$1..$n`, numbered by first
+/// appearance, returning the names in that order.
+fn number_named(mut tokens: Vec<Tok>) -> Result<(Vec<Tok>, Vec<String>), SqlError> {
+    let mut names: Vec<String> = Vec::new();
+    if !tokens.iter().any(|t| matches!(t, Tok::NamedParam(_))) {
+        return Ok((tokens, names));
+    }
+    for tok in &mut tokens {
+        match tok {
+            Tok::NamedParam(name) => {
+                let idx = match names.iter().position(|n| n == name) {
+                    Some(pos) => pos + 1,
+                    None => {
+                        names.push(std::mem::take(name));
+                        names.len()
+                    }
+                };
+                *tok = Tok::Param(idx);
+            }
+            Tok::Param(idx) => {
+                return Err(SqlError::InvalidValue(format!(
+                    "cannot mix ${idx} with named placeholders"
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok((tokens, names))
+}
+
+fn named_values(names: &[String], params: &serde_json::Map<String, Value>) -> Result<Vec<Value>, SqlError> {
+    names
+        .iter()
+        .map(|name| params.get(name).cloned().ok_or_else(|| SqlError::UnboundParam(name.clone())))
+        .collect()
+}
+
+/// A read statement tokenized once for [`CoreDB::prepare`](crate::CoreDB::prepare).
+///
+/// Statements without placeholders are parsed up front and every run clones
+/// the compiled plan; with placeholders only the parser runs per bind.
+/// `$name` placeholders are numbered on the way in, so a named bind is a
+/// lookup per name followed by a positional bind.
+#[derive(Clone)]
+pub(crate) struct PreparedStmt {
+    tokens: Vec<Tok>,
+    /// Highest `$n` in the statement, 0 when there are none.
+    arity: usize,
+    /// `$name` placeholders in `$n` order; empty for positional statements.
+    names: Vec<String>,
+    parsed: Option<MatchOrAgg>,
+}
+
+impl PreparedStmt {
+    pub(crate) fn new(sql: &str) -> Result<Self, SqlError> {
+        let (tokens, names) = number_named(lex(sql)?)?;
+        let arity = tokens
+            .iter()
+            .filter_map(|t| match t {
+                Tok::Param(idx) => Some(*idx),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let parsed = if arity == 0 { Some(parse_tokens(tokens.clone(), vec![])?) } else { None };
+        Ok(PreparedStmt { tokens, arity, names, parsed })
+    }
+
+    pub(crate) fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub(crate) fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The compiled statement with `$name` placeholders bound from `params`.
+    pub(crate) fn bind_named(&self, params: &serde_json::Map<String, Value>) -> Result<MatchOrAgg, SqlError> {
+        if self.names.is_empty() && self.arity > 0 {
+            return Err(SqlError::InvalidValue(
+                "statement uses $1..$n placeholders, not named ones".into(),
+            ));
+        }
+        self.bind(&named_values(&self.names, params)?)
+    }
+
+    /// The compiled statement with `params` bound to `$1..$n`.
+    pub(crate) fn bind(&self, params: &[Value]) -> Result<MatchOrAgg, SqlError> {
+        if params.len() < self.arity {
+            return Err(match self.names.get(params.len()) {
+                Some(name) => SqlError::UnboundParam(name.clone()),
+                None => SqlError::ParamOutOfRange { index: self.arity, count: params.len() },
+            });
+        }
+        match &self.parsed {
+            Some(parsed) => Ok(parsed.clone()),
+            None => parse_tokens(self.tokens.clone(), params.to_vec()),
+        }
+    }
+}
+
 /// Return `true` when `MATCH` is the first token and the stream contains a `WITH`
 /// keyword or a `RETURN` followed by a `var.field` projection (dot after ident).
 ///
 /// Simple `MATCH (a)-[:e]->(b) RETURN a, b` does NOT match — it routes through
-/// `parse_match` instead.  `MATCH ... WITH ...` or `MATCH ... RETURN b._key AS k`
-/// matches and routes through `parse_match_return_with`.
+/// `parse_match` instead.  `MATCH ... WITH ...`, `MATCH ... RETURN b._key AS k`,
+/// a multi-hop chain or a RETURN naming the edge variable matches and routes
+/// through `parse_match_return_with`.
 fn is_match_with_or_return(tokens: &[Tok]) -> bool {
     if !matches!(tokens.first(), Some(Tok::Kw(Kw::Match))) {
         return false;
@@ -6028,11 +6110,36 @@ fn is_match_with_or_return(tokens: &[Tok]) -> bool {
                 Tok::LParen => depth += 1,
                 Tok::RParen => depth -= 1,
                 Tok::Kw(Kw::Where) if depth > 0 => return true,
-                Tok::Dash | Tok::Kw(Kw::Return) => break,
+                Tok::Dash | Tok::BackArrow | Tok::Kw(Kw::Return) => break,
                 _ => {}
             }
         }
     }
+    // A chain of more than one hop — `(a)-[:x]->(b)-[:y]->(c)` — returns
+    // tuples of bound nodes, which only the path executor can produce.
+    let hops = tokens.iter()
+        .take_while(|t| !matches!(t, Tok::Kw(Kw::Where | Kw::Return)))
+        .filter(|t| matches!(t, Tok::LBracket))
+        .count();
+    if hops > 1 {
+        return true;
+    }
+    // Returning the edge variable — `(a)-[r:x]->(b) RETURN a, r, b` — also
+    // needs bound tuples rather than destination nodes.
+    let edge_vars: Vec<&str> = tokens.windows(2)
+        .filter_map(|w| match w {
+            [Tok::LBracket, Tok::Ident(name)] => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    if let Some(ret) = tokens.iter().position(|t| matches!(t, Tok::Kw(Kw::Return))) {
+        let returns_edge = tokens[ret + 1..].iter()
+            .take_while(|t| matches!(t, Tok::Ident(_) | Tok::Comma))
+            .any(|t| matches!(t, Tok::Ident(name) if edge_vars.contains(&name.as_str())));
+        if returns_edge {
+            return true;
+        }
+    }
     // Check for RETURN with var.field projection or aggregate function.
     for (i, tok) in tokens.iter().enumerate() {
         if matches!(tok, Tok::Kw(Kw::Return)) {
@@ -6245,7 +6352,7 @@ fn parse_mutation_inner(sql: &str, params: Vec<Value>) -> Result<CompiledMutatio
                     let schema = parser.parse_create_table()?;
                     Ok(CompiledMutation::CreateTable {
                         collection: schema.collection.clone(),
-                        schema,
+                        schema: Box::new(schema),
                     })
                 }
             }
@@ -6295,8 +6402,9 @@ fn parse_mutation_inner(sql: &str, params: Vec<Value>) -> Result<CompiledMutatio
                         "SPATIAL" => IndexMethod::Spatial,
                         "HNSW"    => IndexMethod::Hnsw,
                         "SEARCH"  => IndexMethod::Search,
+                        "SIMHASH" => IndexMethod::SimHash,
                         other => return Err(SqlError::UnexpectedToken {
-                            expected: "BTREE, HASH, GIN, GIST, BM25, SPATIAL, HNSW, or SEARCH",
+                            expected: "BTREE, HASH, GIN, GIST, BM25, SPATIAL, HNSW, SEARCH, or SIMHASH",
                             got: other.to_string(),
                         }),
                     };
@@ -6330,8 +6438,9 @@ fn parse_mutation_inner(sql: &str, params: Vec<Value>) -> Result<CompiledMutatio
                 "spatial" => IndexMethod::Spatial,
                 "hnsw"    => IndexMethod::Hnsw,
                 "search"  => IndexMethod::Search,
+                "simhash" => IndexMethod::SimHash,
                 other => return Err(SqlError::UnexpectedToken {
-                    expected: "btree, hash, gin, gist, bm25, spatial, hnsw, or search",
+                    expected: "btree, hash, gin, gist, bm25, spatial, hnsw, search, or simhash",
                     got: other.to_string(),
                 }),
             };
@@ -6401,6 +6510,21 @@ pub fn parse_mutation_params(sql: &str, params: Vec<Value>) -> Result<CompiledMu
     parse_mutation_inner(sql, params)
 }
 
+/// Fuzzing entry point: run `input` through every SekejapQL parser —
+/// queries, mutations, `SHOW`, and the parameterised forms with one bound
+/// value — discarding the results.
+///
+/// Any input must come back as a value or a [`SqlError`]; a panic here is a
+/// parser bug. The `fuzz/` directory wires this into a cargo-fuzz target.
+pub fn parse_unchecked(input: &[u8]) {
+    let Ok(sql) = std::str::from_utf8(input) else { return };
+    let _ = parse_match_or_agg(sql);
+    let _ = parse_mutation(sql);
+    let _ = parse_show(sql);
+    let _ = parse_match_or_agg_params(sql, vec![Value::from(1)]);
+    let _ = parse_mutation_params(sql, vec![Value::from("x")]);
+}
+
 // ── Tests ─────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -6417,6 +6541,9 @@ mod tests {
                 Step::All => "All",
                 Step::Forward(_) => "Forward",
                 Step::Backward(_) => "Backward",
+                Step::Both(_) => "Both",
+                Step::HopFilter(_) => "HopFilter",
+                Step::EdgeWindow(..) => "EdgeWindow",
                 Step::Hops(_) => "Hops",
                 Step::HopsTyped { .. } => "HopsTyped",
                 Step::MinStrength(_) => "MinStrength",
@@ -6432,7 +6559,11 @@ mod tests {
                 Step::WhereIn(..) => "WhereIn",
                 Step::ArrayContains(..) => "ArrayContains",
                 Step::Like(..) => "Like",
+                Step::WhereContains(..) => "WhereContains",
+                Step::WhereStartsWith(..) => "WhereStartsWith",
+                Step::WhereRegex(..) => "WhereRegex",
                 Step::StDWithin(..) => "StDWithin",
+                Step::NearNode(..) => "NearNode",
                 Step::StContainsPoint(..) => "StContainsPoint",
                 Step::StWithin(..) => "StWithin",
                 Step::StContains(..) => "StContains",
@@ -6442,6 +6573,7 @@ mod tests {
                 Step::StArea(..) => "StArea",
                 Step::VectorNear { .. } => "VectorNear",
                 Step::SearchFilter(..) => "SearchFilter",
+                Step::Hybrid { .. } => "Hybrid",
                 Step::Bm25Filter(..) => "Bm25Filter",
                 Step::Bm25Sort(..) => "Bm25Sort",
                 Step::ScoreProject(..) => "ScoreProject",
@@ -6457,6 +6589,7 @@ mod tests {
                 Step::Sort(..) => "Sort",
                 Step::SortByVector { .. } => "SortByVector",
                 Step::SortByExpr { .. } => "SortByExpr",
+                Step::TopPerGroup { .. } => "TopPerGroup",
                 Step::Skip(_) => "Skip",
                 Step::Take(_) => "Take",
                 Step::Select(_) => "Select",
@@ -6907,6 +7040,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_regex_operator() {
+        let steps = parse_and_compile("SELECT * FROM artist WHERE name ~* '^the'").unwrap();
+        assert_eq!(step_names(&steps), ["Collection", "WhereRegex"]);
+        if let Step::WhereRegex(field, pattern) = &steps[1] {
+            assert_eq!(field, "name");
+            assert_eq!(pattern.as_str(), "(?i)^the");
+        } else {
+            panic!("expected WhereRegex step");
+        }
+        assert!(parse_and_compile("SELECT * FROM artist WHERE name ~ '('").is_err());
+    }
+
     #[test]
     fn match_with_limit() {
         let steps = parse_and_compile(
@@ -7204,6 +7350,35 @@ mod tests {
             other => panic!("expected DeleteEdge, got {other:?}"),
         }
     }
+
+    // ── parse_unchecked ──────────────────────────────────────────────────────
+
+    /// Every prefix and every one-character deletion of a spread of valid
+    /// statements must parse or fail cleanly.
+    #[test]
+    fn parse_unchecked_survives_truncated_and_damaged_statements() {
+        let seeds = [
+            "SELECT a, COUNT(*) AS n FROM users WHERE age > 30 AND name ILIKE 'a%' GROUP BY a ORDER BY n DESC LIMIT 5",
+            "SELECT * FROM venues WHERE VECTOR_NEAR(embedding, [0.1, 0.2], 3) AND ST_DWithin(geometry, 1.0, 2.0, 5)",
+            "MATCH (a:users)-[r:knows*1..3]->(b:users) WHERE a._key = $1 WITH b MATCH (b)<-[:lives_in]-(c) RETURN c._key AS k",
+            "SELECT a.x AS x, b.y AS y FROM MATCH SHORTEST (a)-[r*]->(b) WHERE a._key = 'p' AND b._key = 'q'",
+            "INSERT INTO users (_key, name, tags) VALUES ('u1', 'Ani', ['x', 'y']), ('u2', $1, NULL)",
+            "UPDATE users SET age = age + 1, name = 'B' WHERE _key IN ('u1', 'u2')",
+            "MATCH (p:people) WHERE p.grade < 80 INSERT (p)-[:member_of {w: 2}]->(classroom/A)",
+            "CREATE TABLE t (_key TEXT PRIMARY KEY, e VECTOR(4), g GEOMETRY) WITH (hash_index = [_key], vector_index = [e])",
+            "DELETE ('a')-[:KNOWS]->('b')",
+            "SHOW EDGES FROM bands TO venues",
+        ];
+        for seed in seeds {
+            let chars: Vec<(usize, char)> = seed.char_indices().collect();
+            for &(i, c) in &chars {
+                parse_unchecked(&seed.as_bytes()[..i]);
+                let damaged = format!("{}{}", &seed[..i], &seed[i + c.len_utf8()..]);
+                parse_unchecked(damaged.as_bytes());
+            }
+        }
+        parse_unchecked(&[0xff, 0xfe, b'S']);
+    }
 }
 
 #[cfg(test)]
@@ -7,9 +7,9 @@
 //!   topology.  Same as the original sekejap representation.  Used for
 //!   in-memory databases and when maximum edge-meta read speed is needed.
 //!
-//! - **Compact** — only the topology (other, type, strength) lives in RAM;
-//!   edge metadata is stored in an append-only `edge_meta.bin` file read via
-//!   mmap.  Cuts RAM ~2.7× per edge (64 → 24 bytes) and moves bulky JSON
+//! - **Compact** — only the topology (other, type, strength, creation time)
+//!   lives in RAM; edge metadata is stored in an append-only `edge_meta.bin`
+//!   file read via mmap.  Cuts RAM ~2× per edge (64 → 32 bytes) and moves bulky JSON
 //!   metadata to disk.
 //!
 //! The public API is identical for both modes — callers iterate `&[Edge]`
@@ -21,7 +21,7 @@ use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
-/// Compact edge stored in adjacency lists.  24 bytes on 64-bit.
+/// Compact edge stored in adjacency lists.  32 bytes on 64-bit.
 ///
 /// Used by both Fat and Compact modes — the only difference is where
 /// metadata lives (RAM vs disk), pointed to by `meta_id`.
@@ -30,12 +30,35 @@ pub(crate) struct Edge {
     pub other: u64,
     pub edge_type: u64,
     pub strength: f32,
+    /// When the edge was created (unix milliseconds); `0` if unknown, e.g.
+    /// edges replayed from a WAL written before timestamps were recorded.
+    pub created_unix: i64,
     /// Index into the meta store.  `u32::MAX` = no metadata.
     meta_id: u32,
 }
 
 const NO_META: u32 = u32::MAX;
 
+/// Most metadata entries one store can index: ids are `u32`, with
+/// [`NO_META`] reserved.
+pub(crate) const MAX_META_ENTRIES: u64 = NO_META as u64;
+
+/// Longest metadata entry, in bytes; lengths are stored as `u32`.
+pub(crate) const MAX_META_BYTES: u64 = u32::MAX as u64;
+
+/// Unmapped bytes appended to `edge_meta.bin` before the mmap is grown.
+#[cfg(unix)]
+const META_REMAP_GROWTH: u64 = 16 << 20;
+
+/// pread `len` bytes at `offset` — used for metadata past the current mmap.
+#[cfg(unix)]
+fn read_at(file: &std::fs::File, offset: u64, len: u32) -> Option<Vec<u8>> {
+    use std::os::unix::fs::FileExt;
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact_at(&mut buf, offset).ok()?;
+    Some(buf)
+}
+
 impl Edge {
     #[inline]
     pub fn has_meta(&self) -> bool {
@@ -73,7 +96,7 @@ enum MetaStore {
     #[cfg(unix)]
     Disk {
         /// (byte_offset, byte_len) per meta entry.
-        offsets: Vec<(u32, u16)>,
+        offsets: Vec<(u64, u32)>,
         file: std::fs::File,
         path: PathBuf,
         total_len: u64,
@@ -158,27 +181,30 @@ impl EdgeStore {
 
     // ── Edge insertion ───────────────────────────────────────────────────
 
-    /// Insert an edge without metadata.
+    /// Insert an edge without metadata. The type hash is `sk_hash(edge_type_name)`.
     pub fn link(
         &mut self,
         from_hash: u64,
         to_hash: u64,
-        edge_type: u64,
         edge_type_name: &str,
         strength: f32,
+        created_unix: i64,
     ) {
+        let edge_type = crate::sk_hash(edge_type_name);
         self.type_names
             .insert(edge_type, edge_type_name.to_string());
         let edge_fwd = Edge {
             other: to_hash,
             edge_type,
             strength,
+            created_unix,
             meta_id: NO_META,
         };
         let edge_rev = Edge {
             other: from_hash,
             edge_type,
             strength,
+            created_unix,
             meta_id: NO_META,
         };
         self.fwd.entry(from_hash).or_default().push(edge_fwd);
@@ -190,11 +216,12 @@ impl EdgeStore {
         &mut self,
         from_hash: u64,
         to_hash: u64,
-        edge_type: u64,
         edge_type_name: &str,
         strength: f32,
+        created_unix: i64,
         meta: Value,
     ) {
+        let edge_type = crate::sk_hash(edge_type_name);
         self.type_names
             .insert(edge_type, edge_type_name.to_string());
         let mid = self.store_meta(meta);
@@ -202,12 +229,14 @@ impl EdgeStore {
             other: to_hash,
             edge_type,
             strength,
+            created_unix,
             meta_id: mid,
         };
         let edge_rev = Edge {
             other: from_hash,
             edge_type,
             strength,
+            created_unix,
             meta_id: mid,
         };
         self.fwd.entry(from_hash).or_default().push(edge_fwd);
@@ -227,15 +256,21 @@ impl EdgeStore {
                 offsets,
                 file,
                 total_len,
+                mmap,
                 ..
             } => {
                 let json_bytes = serde_json::to_vec(&meta).unwrap_or_default();
-                let offset = *total_len as u32;
-                let len = json_bytes.len() as u16;
+                let offset = *total_len;
+                let len = json_bytes.len() as u32;
                 use std::os::unix::fs::FileExt;
                 file.write_all_at(&json_bytes, *total_len)
                     .expect("sekejap: edge meta disk write failed");
                 *total_len += json_bytes.len() as u64;
+                // Grow the mapping in steps; reads past it fall back to pread.
+                let mapped = mmap.as_ref().map_or(0, |m| m.len() as u64);
+                if *total_len - mapped >= META_REMAP_GROWTH {
+                    *mmap = super::mmap::MmapView::try_new(file, *total_len as usize);
+                }
                 let id = offsets.len() as u32;
                 offsets.push((offset, len));
                 id
@@ -301,6 +336,16 @@ impl EdgeStore {
         self.rev.get(&hash).map(|v| v.as_slice())
     }
 
+    /// Metadata entries stored, including those of removed edges until
+    /// `compact_meta` reclaims them.
+    pub fn meta_entries(&self) -> u64 {
+        match &self.meta {
+            MetaStore::Ram { metas } => metas.len() as u64,
+            #[cfg(unix)]
+            MetaStore::Disk { offsets, .. } => offsets.len() as u64,
+        }
+    }
+
     /// Resolve metadata for an edge.  Returns `None` if the edge has no meta
     /// or if the meta could not be read.
     pub fn edge_meta(&self, edge: &Edge) -> Option<Value> {
@@ -313,18 +358,17 @@ impl EdgeStore {
             }
             #[cfg(unix)]
             MetaStore::Disk {
-                offsets, mmap, ..
+                offsets, file, mmap, ..
             } => {
                 let &(offset, len) = offsets.get(edge.meta_id as usize)?;
                 if len == 0 {
                     return None;
                 }
-                if let Some(ref m) = mmap {
-                    let bytes = m.slice(offset as usize, len as usize)?;
-                    serde_json::from_slice(bytes).ok()
-                } else {
-                    None
+                if let Some(bytes) = mmap.as_ref().and_then(|m| m.slice(offset as usize, len as usize)) {
+                    return serde_json::from_slice(bytes).ok();
                 }
+                // Appended after the last remap — read it directly.
+                serde_json::from_slice(&read_at(file, offset, len)?).ok()
             }
         }
     }
@@ -358,6 +402,96 @@ impl EdgeStore {
         &self.type_names
     }
 
+    // ── Binary adjacency (edges.bin) ─────────────────────────────────────
+
+    /// Write the forward adjacency in CSR order — each source followed by
+    /// its run of edges — skipping edges for which `live(from, to)` is false.
+    /// Reverse lists are derived again on load. Returns the edges written.
+    pub fn write_adjacency(
+        &self,
+        w: &mut impl io::Write,
+        live: impl Fn(u64, u64) -> bool,
+    ) -> io::Result<usize> {
+        w.write_all(&(self.type_names.len() as u32).to_le_bytes())?;
+        for (hash, name) in &self.type_names {
+            w.write_all(&hash.to_le_bytes())?;
+            w.write_all(&(name.len() as u32).to_le_bytes())?;
+            w.write_all(name.as_bytes())?;
+        }
+        let runs: Vec<(u64, Vec<&Edge>)> = self.fwd.iter()
+            .map(|(&from, edges)| (from, edges.iter().filter(|e| live(from, e.other)).collect::<Vec<_>>()))
+            .filter(|(_, edges)| !edges.is_empty())
+            .collect();
+        w.write_all(&(runs.len() as u64).to_le_bytes())?;
+        let mut written = 0;
+        for (from, edges) in runs {
+            w.write_all(&from.to_le_bytes())?;
+            w.write_all(&(edges.len() as u32).to_le_bytes())?;
+            for e in edges {
+                w.write_all(&e.other.to_le_bytes())?;
+                w.write_all(&e.edge_type.to_le_bytes())?;
+                w.write_all(&e.strength.to_le_bytes())?;
+                w.write_all(&e.created_unix.to_le_bytes())?;
+                match self.edge_meta(e) {
+                    Some(meta) => {
+                        let bytes = serde_json::to_vec(&meta).unwrap_or_default();
+                        w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+                        w.write_all(&bytes)?;
+                    }
+                    None => w.write_all(&NO_META.to_le_bytes())?,
+                }
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Add every edge from a [`write_adjacency`](Self::write_adjacency)
+    /// stream. Returns the number of edges read.
+    pub fn read_adjacency(&mut self, r: &mut impl io::Read) -> io::Result<usize> {
+        fn take<const N: usize>(r: &mut impl io::Read) -> io::Result<[u8; N]> {
+            let mut buf = [0u8; N];
+            r.read_exact(&mut buf)?;
+            Ok(buf)
+        }
+        fn take_vec(r: &mut impl io::Read, len: u32) -> io::Result<Vec<u8>> {
+            let mut buf = vec![0u8; len as usize];
+            r.read_exact(&mut buf)?;
+            Ok(buf)
+        }
+        let invalid = |e: std::string::FromUtf8Error| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        for _ in 0..u32::from_le_bytes(take(r)?) {
+            let hash = u64::from_le_bytes(take(r)?);
+            let len = u32::from_le_bytes(take(r)?);
+            let name = String::from_utf8(take_vec(r, len)?).map_err(invalid)?;
+            self.type_names.insert(hash, name);
+        }
+        let mut read = 0;
+        for _ in 0..u64::from_le_bytes(take(r)?) {
+            let from = u64::from_le_bytes(take(r)?);
+            for _ in 0..u32::from_le_bytes(take(r)?) {
+                let other = u64::from_le_bytes(take(r)?);
+                let edge_type = u64::from_le_bytes(take(r)?);
+                let strength = f32::from_le_bytes(take(r)?);
+                let created_unix = i64::from_le_bytes(take(r)?);
+                let meta_len = u32::from_le_bytes(take(r)?);
+                let meta_id = if meta_len == NO_META {
+                    NO_META
+                } else {
+                    let meta = serde_json::from_slice(&take_vec(r, meta_len)?)?;
+                    self.store_meta(meta)
+                };
+                self.fwd.entry(from).or_default()
+                    .push(Edge { other, edge_type, strength, created_unix, meta_id });
+                self.rev.entry(other).or_default()
+                    .push(Edge { other: from, edge_type, strength, created_unix, meta_id });
+                read += 1;
+            }
+        }
+        Ok(read)
+    }
+
     // ── Compaction ───────────────────────────────────────────────────────
 
     /// Remap the metadata mmap to cover newly appended data.
@@ -423,7 +557,7 @@ impl EdgeStore {
                     .truncate(true)
                     .open(&tmp_path)?;
 
-                let mut new_offsets: Vec<(u32, u16)> = Vec::with_capacity(offsets.len());
+                let mut new_offsets: Vec<(u64, u32)> = Vec::with_capacity(offsets.len());
                 let mut id_remap: HashMap<u32, u32> = HashMap::new();
                 let mut write_pos: u64 = 0;
 
@@ -436,12 +570,16 @@ impl EdgeStore {
                     id_remap.insert(old_id as u32, new_id);
 
                     // Copy bytes from old file to new.
-                    if let Some(ref m) = mmap {
-                        if let Some(bytes) = m.slice(offset as usize, len as usize) {
-                            tmp_file.write_all_at(bytes, write_pos)?;
+                    match mmap.as_ref().and_then(|m| m.slice(offset as usize, len as usize)) {
+                        Some(bytes) => tmp_file.write_all_at(bytes, write_pos)?,
+                        None => {
+                            let bytes = read_at(file, offset, len).ok_or_else(|| {
+                                io::Error::new(io::ErrorKind::UnexpectedEof, "edge meta out of range")
+                            })?;
+                            tmp_file.write_all_at(&bytes, write_pos)?;
                         }
                     }
-                    new_offsets.push((write_pos as u32, len));
+                    new_offsets.push((write_pos, len));
                     write_pos += len as u64;
                 }
 
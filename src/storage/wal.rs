@@ -12,18 +12,22 @@
 //! On replay, a bad CRC stops the reader at that frame — everything
 //! before it is intact. The JSON payload is human-readable so the WAL
 //! can be inspected (or repaired) with any text tool.
+//!
+//! Frames go to a [`WalStore`]: `wal.log` in the data directory for
+//! [`CoreDB::open`](crate::CoreDB::open), or any caller-supplied store for
+//! [`CoreDB::open_with_wal_store`](crate::CoreDB::open_with_wal_store).
 
 use serde::{Deserialize, Serialize};
 use std::fs::{File, OpenOptions};
 use std::io::{self, BufReader, BufWriter, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // ── WAL entry ─────────────────────────────────────────────────────────────────
 
 /// A single mutation recorded in the WAL.
 ///
 /// The `op` tag is used as a discriminant in JSON: `{"op":"put",...}`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "op", rename_all = "snake_case")]
 pub(crate) enum WalEntry {
     Put {
@@ -33,11 +37,26 @@ pub(crate) enum WalEntry {
     Remove {
         slug: String,
     },
+    /// Soft delete: the node moves to the trash and can be restored.
+    SoftRemove {
+        slug: String,
+    },
+    /// Bring a soft-deleted node back from the trash.
+    Restore {
+        slug: String,
+    },
+    /// Permanently drop a soft-deleted node from the trash.
+    Purge {
+        slug: String,
+    },
     Link {
         from: String,
         to: String,
         edge_type: String,
         strength: f32,
+        /// Creation time (unix ms). Absent in WALs from older versions.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        created_unix: Option<i64>,
     },
     LinkMeta {
         from: String,
@@ -45,6 +64,9 @@ pub(crate) enum WalEntry {
         edge_type: String,
         strength: f32,
         meta: String,
+        /// As on `Link`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        created_unix: Option<i64>,
     },
     Unlink {
         from: String,
@@ -78,12 +100,39 @@ pub(crate) enum WalEntry {
         /// JSON-serialised `AlterTableOp` — keeps the WAL self-contained.
         op_json: String,
     },
+    /// Set (`Some`) or clear (`None`) a collection's implicit filter.
+    SetCollectionFilter {
+        collection: String,
+        filter: Option<String>,
+    },
+    /// Define (`Some`) or drop (`None`) a materialised edge aggregate.
+    SetEdgeAggregate {
+        collection: String,
+        field: String,
+        /// JSON-serialised `EdgeAggregate` — keeps the WAL self-contained.
+        aggregate_json: Option<String>,
+    },
+    /// Define (`Some`, the excluded fields) or drop (`None`) a projection profile.
+    SetProjectionProfile {
+        collection: String,
+        profile: String,
+        exclude: Option<Vec<String>>,
+    },
+    /// An HNSW index built with `CoreDB::build_hnsw_index` outside any
+    /// schema declaration.
+    BuildHnsw {
+        field: String,
+        m: usize,
+        ef_construction: usize,
+    },
     /// Transaction boundary: marks the start of an atomic group.
     /// All entries between `TxnBegin` and `TxnEnd` are replayed
     /// together or discarded together on crash recovery.
     TxnBegin,
     /// Transaction boundary: marks the end of an atomic group.
     TxnEnd,
+    /// Trailer of a `CoreDB::export_ndjson` stream; never written to the WAL.
+    Manifest(crate::ExportManifest),
     /// Forward-compatibility catch-all: entries written by a newer binary
     /// with an unknown `op` value are silently skipped on replay.
     #[serde(other)]
@@ -98,60 +147,145 @@ fn crc32(data: &[u8]) -> u32 {
     h.finalize()
 }
 
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Durable byte log that WAL frames are written to.
+///
+/// The database only appends encoded frames, asks for them to be made
+/// durable, reads the whole log back when it is opened, and swaps in a
+/// rewritten log on [`CoreDB::compact`](crate::CoreDB::compact). Frames are
+/// self-checking, so a store that loses a torn tail after a crash is fine:
+/// replay stops at the last intact frame.
+///
+/// Implement it over whatever the platform offers — for a browser build,
+/// an OPFS `FileSystemSyncAccessHandle` in a worker, or an IndexedDB
+/// object store whose pending appends are committed in `sync`.
+pub trait WalStore: Send + Sync {
+    /// Append `frame` to the end of the log.
+    fn append(&mut self, frame: &[u8]) -> io::Result<()>;
+    /// Make every appended frame survive a crash.
+    fn sync(&mut self) -> io::Result<()>;
+    /// The whole log, as appended so far.
+    fn read_all(&mut self) -> io::Result<Vec<u8>>;
+    /// Replace the whole log with `log`. Must be atomic: after a crash the
+    /// store holds either the old log or the new one.
+    fn replace(&mut self, log: &[u8]) -> io::Result<()>;
+}
+
+/// `wal.log` in a data directory.
+struct FileWalStore {
+    path: PathBuf,
+    inner: BufWriter<File>,
+}
+
+impl FileWalStore {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { path: path.to_path_buf(), inner: BufWriter::new(file) })
+    }
+}
+
+impl WalStore for FileWalStore {
+    fn append(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.inner.write_all(frame)?;
+        self.inner.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.inner.get_ref().sync_data()
+    }
+
+    fn read_all(&mut self) -> io::Result<Vec<u8>> {
+        self.inner.flush()?;
+        std::fs::read(&self.path)
+    }
+
+    fn replace(&mut self, log: &[u8]) -> io::Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        {
+            let mut f = File::create(&tmp)?;
+            f.write_all(log)?;
+            f.sync_all()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
+
 // ── Writer ────────────────────────────────────────────────────────────────────
 
+/// One CRC32-framed record.
+fn encode_frame(entry: &WalEntry) -> io::Result<Vec<u8>> {
+    let json =
+        serde_json::to_vec(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let len_bytes = (json.len() as u32).to_le_bytes();
+
+    // CRC32 over [length(4) || payload(N)]
+    let mut frame = Vec::with_capacity(8 + json.len());
+    frame.extend_from_slice(&[0; 4]);
+    frame.extend_from_slice(&len_bytes);
+    frame.extend_from_slice(&json);
+    let checksum = crc32(&frame[4..]).to_le_bytes();
+    frame[..4].copy_from_slice(&checksum);
+    Ok(frame)
+}
+
 pub(crate) struct WalWriter {
-    inner: BufWriter<File>,
+    store: Box<dyn WalStore>,
 }
 
 impl WalWriter {
     /// Open (or create) a WAL file in append mode.
     pub fn open(path: &Path) -> io::Result<Self> {
-        let file = OpenOptions::new().create(true).append(true).open(path)?;
-        Ok(Self {
-            inner: BufWriter::new(file),
-        })
+        Ok(Self::from_store(Box::new(FileWalStore::open(path)?)))
+    }
+
+    /// Write frames to a caller-supplied store.
+    pub fn from_store(store: Box<dyn WalStore>) -> Self {
+        Self { store }
     }
 
     /// Append one entry. Flushes to OS after every write.
     /// Call `sync()` if you need fsync-level durability.
     pub fn append(&mut self, entry: &WalEntry) -> io::Result<()> {
-        let json =
-            serde_json::to_vec(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        let len_bytes = (json.len() as u32).to_le_bytes();
-
-        // CRC32 over [length(4) || payload(N)]
-        let mut crc_input = Vec::with_capacity(4 + json.len());
-        crc_input.extend_from_slice(&len_bytes);
-        crc_input.extend_from_slice(&json);
-        let checksum = crc32(&crc_input).to_le_bytes();
-
-        self.inner.write_all(&checksum)?;
-        self.inner.write_all(&len_bytes)?;
-        self.inner.write_all(&json)?;
-        self.inner.flush()
+        self.store.append(&encode_frame(entry)?)
     }
 
     /// fsync — call after a batch of writes when you need
     /// guaranteed on-disk durability.
     pub fn sync(&mut self) -> io::Result<()> {
-        self.inner.flush()?;
-        self.inner.get_ref().sync_data()
+        self.store.sync()
+    }
+
+    /// A reader over every frame in the store.
+    pub fn reader(&mut self) -> io::Result<WalReader> {
+        Ok(WalReader { inner: Box::new(io::Cursor::new(self.store.read_all()?)) })
+    }
+
+    /// Atomically replace the log with `entries`.
+    pub fn rewrite(&mut self, entries: &[WalEntry]) -> io::Result<()> {
+        let mut log = Vec::new();
+        for entry in entries {
+            log.extend_from_slice(&encode_frame(entry)?);
+        }
+        self.store.replace(&log)
     }
 }
 
 // ── Reader ────────────────────────────────────────────────────────────────────
 
 pub(crate) struct WalReader {
-    inner: BufReader<File>,
+    inner: Box<dyn Read>,
 }
 
 impl WalReader {
     pub fn open(path: &Path) -> io::Result<Self> {
         let file = File::open(path)?;
         Ok(Self {
-            inner: BufReader::new(file),
+            inner: Box::new(BufReader::new(file)),
         })
     }
 
@@ -257,6 +391,7 @@ mod tests {
                 to: "bob".into(),
                 edge_type: "follows".into(),
                 strength: 1.0,
+                created_unix: Some(1),
             },
             WalEntry::Remove {
                 slug: "alice".into(),
@@ -166,6 +166,21 @@ impl GINIndex {
             .collect()
     }
 
+    /// Upper bound on the documents [`ilike`](Self::ilike) returns for
+    /// `pattern`: the smallest posting bitmap among its trigrams, or every
+    /// document for a pattern without trigrams. Reads bitmap sizes only.
+    pub fn estimate_ilike(&self, pattern: &str) -> usize {
+        let pattern_trigrams = extract_pattern_trigrams(pattern);
+        if pattern_trigrams.is_empty() {
+            return self.doc_count;
+        }
+        dedup_trigrams(&pattern_trigrams)
+            .iter()
+            .map(|t| self.postings.get(&hash_trigram(t)).map_or(0, |bm| bm.len() as usize))
+            .min()
+            .unwrap_or(0)
+    }
+
     /// Incrementally add a single document to the index.
     ///
     /// O(trigrams_in_text) — safe to call per-insert for new documents.
@@ -0,0 +1,39 @@
+//! Sortable node ids for [`CoreDB::put_auto`](crate::CoreDB::put_auto).
+//!
+//! Ids are [ULIDs](https://github.com/ulid/spec): 48 bits of Unix
+//! milliseconds from the database [`Clock`](crate::Clock) followed by 80
+//! random bits, written as 26 Crockford base32 characters, so ids sort by
+//! creation time both as bytes and as strings. Within one millisecond (or
+//! when the clock steps backwards) the random part is incremented instead of
+//! redrawn, keeping ids from one database strictly increasing.
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const RANDOM_BITS: u32 = 80;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+const TIME_MASK: u64 = (1 << 48) - 1;
+
+#[derive(Default)]
+pub(crate) struct UlidGen {
+    /// The last id handed out, as `(millis << 80) | random`.
+    last: u128,
+}
+
+impl UlidGen {
+    /// The next id at `now_millis`, greater than every id returned before.
+    pub(crate) fn next(&mut self, now_millis: i64) -> u128 {
+        let millis = (now_millis.max(0) as u64 & TIME_MASK) as u128;
+        let id = if millis > self.last >> RANDOM_BITS {
+            (millis << RANDOM_BITS) | (uuid::Uuid::new_v4().as_u128() & RANDOM_MASK)
+        } else {
+            // An exhausted random part carries into the next millisecond.
+            self.last + 1
+        };
+        self.last = id;
+        id
+    }
+}
+
+/// `id` as 26 Crockford base32 characters, most significant first.
+pub(crate) fn encode(id: u128) -> String {
+    (0..26).map(|i| ALPHABET[((id >> (125 - 5 * i)) & 31) as usize] as char).collect()
+}
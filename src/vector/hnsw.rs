@@ -175,11 +175,42 @@ impl HnswGraph {
     where
         V: IterableVectors,
     {
+        Self::build_with_progress::<D, V>(field_vecs, m, ef_construction, |_, _| {})
+    }
+
+    /// Like [`build()`](Self::build), calling `progress(done, total)` after
+    /// each inserted node.
+    pub fn build_with_progress<D: Distance, V: VectorAccess + IterableVectors>(
+        field_vecs: &V,
+        m: usize,
+        ef_construction: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Self {
+        Self::build_until::<D, V>(field_vecs, m, ef_construction, |done, total| {
+            progress(done, total);
+            true
+        })
+        .expect("build never stopped")
+    }
+
+    /// Like [`build_with_progress()`](Self::build_with_progress), stopping
+    /// with `None` as soon as `progress` returns `false`.
+    pub fn build_until<D: Distance, V: VectorAccess + IterableVectors>(
+        field_vecs: &V,
+        m: usize,
+        ef_construction: usize,
+        mut progress: impl FnMut(usize, usize) -> bool,
+    ) -> Option<Self> {
         let mut graph = Self::new(m);
-        for (node_id, _) in field_vecs.iter_vectors() {
+        let ids: Vec<u64> = field_vecs.iter_vectors().map(|(id, _)| id).collect();
+        let total = ids.len();
+        for (done, node_id) in ids.into_iter().enumerate() {
             graph.insert_node::<D, V>(node_id, field_vecs, ef_construction);
+            if !progress(done + 1, total) {
+                return None;
+            }
         }
-        graph
+        Some(graph)
     }
 
     /// Search for the `k` approximate nearest neighbours to `query`.
@@ -218,6 +249,41 @@ impl HnswGraph {
         results.into_iter().map(|c| c.id).collect()
     }
 
+    /// Like [`search`](Self::search), but only nodes for which `accept`
+    /// returns `true` are returned.
+    ///
+    /// Rejected nodes are still walked through, so the beam keeps expanding
+    /// until it holds `ef` accepted nodes or the graph is exhausted. A
+    /// restrictive filter therefore still yields a full `k` (when that many
+    /// nodes pass) instead of the few that survive an unfiltered top-k.
+    pub fn search_filtered<D: Distance, V: VectorAccess>(
+        &self,
+        query: &[f32],
+        vectors: &V,
+        k: usize,
+        ef: usize,
+        accept: impl Fn(u64) -> bool,
+    ) -> Vec<u64> {
+        let (mut ep_id, ep_level) = match self.entry_point {
+            Some(ep) => ep,
+            None => return vec![],
+        };
+
+        for level in (1..=ep_level).rev() {
+            let cands = search_layer::<D, V>(&self.nodes, query, ep_id, 1, level, vectors);
+            if let Some(best) = cands.into_iter().min_by(|a, b| {
+                a.dist.partial_cmp(&b.dist).unwrap_or(Ordering::Equal)
+            }) {
+                ep_id = best.id;
+            }
+        }
+
+        let mut results =
+            search_layer_filtered::<D, V>(&self.nodes, query, ep_id, ef.max(k), vectors, &accept);
+        results.truncate(k);
+        results.into_iter().map(|c| c.id).collect()
+    }
+
     /// Number of nodes in the graph.
     pub fn len(&self) -> usize {
         self.nodes.len()
@@ -503,6 +569,76 @@ fn search_layer<D: Distance, V: VectorAccess>(
     out
 }
 
+/// Layer-0 beam search that only keeps accepted nodes as results.
+///
+/// Every node is a stepping stone, but the stopping bound comes from the
+/// accepted results alone, so the search reaches past rejected regions.
+fn search_layer_filtered<D: Distance, V: VectorAccess>(
+    nodes: &HashMap<u64, Vec<Vec<u64>>>,
+    query: &[f32],
+    entry_point: u64,
+    ef: usize,
+    vectors: &V,
+    accept: &impl Fn(u64) -> bool,
+) -> Vec<MinCand> {
+    let d0 = match vectors.get(entry_point) {
+        Some(v) => D::eval(query, v),
+        None => return vec![],
+    };
+
+    let mut visited: HashSet<u64> = HashSet::new();
+    visited.insert(entry_point);
+
+    let mut to_visit: BinaryHeap<MinCand> = BinaryHeap::new();
+    to_visit.push(MinCand { id: entry_point, dist: d0 });
+
+    let mut results: BinaryHeap<MaxCand> = BinaryHeap::new();
+    if accept(entry_point) {
+        results.push(MaxCand { id: entry_point, dist: d0 });
+    }
+
+    while let Some(MinCand { id, dist: c_dist }) = to_visit.pop() {
+        let worst = results.peek().map(|r| r.dist).unwrap_or(f32::INFINITY);
+        if c_dist > worst && results.len() >= ef {
+            break;
+        }
+
+        let neighbours = nodes
+            .get(&id)
+            .and_then(|ls| ls.first())
+            .map(|ns| ns.as_slice())
+            .unwrap_or(&[]);
+
+        for &nb in neighbours {
+            if !visited.insert(nb) {
+                continue;
+            }
+            let d = match vectors.get(nb) {
+                Some(v) => D::eval(query, v),
+                None => continue,
+            };
+
+            let worst = results.peek().map(|r| r.dist).unwrap_or(f32::INFINITY);
+            if d < worst || results.len() < ef {
+                to_visit.push(MinCand { id: nb, dist: d });
+                if accept(nb) {
+                    results.push(MaxCand { id: nb, dist: d });
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out: Vec<MinCand> = results
+        .into_iter()
+        .map(|mc| MinCand { id: mc.id, dist: mc.dist })
+        .collect();
+    out.sort_by(|a, b| a.dist.partial_cmp(&b.dist).unwrap_or(Ordering::Equal));
+    out
+}
+
 /// Select up to `m` diverse neighbours using the paper's simple heuristic.
 ///
 /// Accepts a candidate whose closest already-selected neighbour is farther from
@@ -679,4 +815,18 @@ mod tests {
             ground_truth
         );
     }
+
+    #[test]
+    fn filtered_search_fills_k_from_accepted_nodes() {
+        let vecs = make_vecs(200, 16);
+        let graph = HnswGraph::build::<CosineDistance, _>(&vecs, 8, 100);
+        let query = vecs[&0].clone();
+
+        // Only one node in ten passes; an unfiltered top-10 would hold
+        // about one of them.
+        let accept = |id: u64| id % 10 == 3;
+        let results = graph.search_filtered::<CosineDistance, _>(&query, &vecs, 10, 20, accept);
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|&id| accept(id)));
+    }
 }
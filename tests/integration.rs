@@ -171,6 +171,46 @@ fn where_in_filter() {
     assert_eq!(hits, 2);
 }
 
+#[test]
+fn where_any_of_matches_across_fields() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"city":"Jakarta","region":"Java"}"#).unwrap();
+    db.put("b", r#"{"location":"Jakarta"}"#).unwrap();
+    db.put("c", r#"{"region":"Jakarta","city":"Jakarta"}"#).unwrap();
+    db.put("d", r#"{"city":"Bandung"}"#).unwrap();
+
+    let mut slugs: Vec<String> = db.all()
+        .where_any_of(&["city", "location", "region"], "Jakarta")
+        .collect()
+        .into_iter()
+        .map(|h| h.slug)
+        .collect();
+    slugs.sort();
+    assert_eq!(slugs, vec!["a", "b", "c"]);
+    assert_eq!(db.all().where_any_of(&[], "Jakarta").count(), 0);
+}
+
+#[test]
+fn where_any_of_uses_btree_indexes() {
+    let mut db = CoreDB::new();
+    db.put("places/p1", r#"{"_collection":"places","city":"Jakarta","region":"Java"}"#).unwrap();
+    db.put("places/p2", r#"{"_collection":"places","city":"Depok","region":"Jakarta"}"#).unwrap();
+    db.put("places/p3", r#"{"_collection":"places","city":"Jakarta","region":"Jakarta"}"#).unwrap();
+    db.put("places/p4", r#"{"_collection":"places","city":"Bandung","region":"Java"}"#).unwrap();
+    db.execute("CREATE INDEX ON places USING btree (city)").unwrap();
+    db.execute("CREATE INDEX ON places USING btree (region)").unwrap();
+
+    let hits = db.collection("places").where_any_of(&["city", "region"], "Jakarta").collect();
+    assert_eq!(hits.len(), 3, "p3 matches both fields but appears once");
+
+    let hits = db.collection("places")
+        .where_eq("region", "Java")
+        .where_any_of(&["city", "region"], "Jakarta")
+        .collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slug, "places/p1");
+}
+
 #[test]
 fn like_filter() {
     let mut db = CoreDB::new();
@@ -180,6 +220,60 @@ fn like_filter() {
     assert_eq!(db.all().like("email", "example.com").count(), 1);
 }
 
+// ── Nested field paths ────────────────────────────────────────────────────────
+
+#[test]
+fn nested_paths_in_filters_sort_and_select() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"coordinates":{"lat":-6.2,"lon":106.8},"entities":{"people":["Ani","Budi"]}}"#).unwrap();
+    db.put("b", r#"{"coordinates":{"lat":3.6,"lon":98.7},"entities":{"people":["Citra"]}}"#).unwrap();
+    db.put("c", r#"{"coordinates":{"lat":-7.8,"lon":110.4}}"#).unwrap();
+    db.put("d", r#"{"coordinates.lat":1.0}"#).unwrap();
+
+    assert_eq!(db.all().where_lt("coordinates.lat", 0.0).count(), 2);
+    assert_eq!(db.all().where_eq("entities.people[0]", "Ani").count(), 1);
+    assert_eq!(db.all().where_eq("entities.people[1]", "Budi").count(), 1);
+    assert_eq!(db.all().where_eq("entities.people[5]", "Budi").count(), 0);
+    // A literal dotted key still wins over path traversal.
+    assert_eq!(db.all().where_eq("coordinates.lat", 1.0).count(), 1);
+
+    let order: Vec<String> = db.all()
+        .where_gt("coordinates.lon", 0.0)
+        .sort("coordinates.lat", true)
+        .collect()
+        .into_iter()
+        .map(|h| h.slug)
+        .collect();
+    assert_eq!(order, vec!["c", "a", "b"]);
+
+    let hits = db.one("b").select(["coordinates.lon"]).collect();
+    assert_eq!(hits[0].payload.as_ref().unwrap()["coordinates.lon"], 98.7);
+}
+
+#[test]
+fn nested_path_btree_index() {
+    let mut db = CoreDB::new();
+    for i in 0..10 {
+        db.put(
+            &format!("pts/p{i}"),
+            &format!(r#"{{"_collection":"pts","_key":"p{i}","geo":{{"lat":{i}}}}}"#),
+        ).unwrap();
+    }
+    db.build_field_index("pts", "geo.lat");
+    assert_eq!(db.collection("pts").where_gte("geo.lat", 7.0).count(), 3);
+
+    // Index stays in sync on put / remove.
+    db.put("pts/p0", r#"{"_collection":"pts","_key":"p0","geo":{"lat":9}}"#).unwrap();
+    db.remove("pts/p9");
+    assert_eq!(db.collection("pts").where_eq("geo.lat", 9).count(), 1);
+    assert_eq!(db.collection("pts").where_eq("geo.lat", 0).count(), 0);
+
+    // SQL UPDATE of the parent field rebuilds the nested index.
+    db.execute("UPDATE pts SET geo = 'gone' WHERE _key = 'p1'").unwrap();
+    assert_eq!(db.collection("pts").where_eq("geo.lat", 1).count(), 0);
+    assert_eq!(db.collection("pts").where_between("geo.lat", 0.0, 9.0).count(), 8);
+}
+
 // ── Set algebra ───────────────────────────────────────────────────────────────
 
 #[test]
@@ -246,6 +340,62 @@ fn skip_and_take() {
     assert_eq!(hits[0].payload.as_ref().unwrap()["i"], 3);
 }
 
+#[test]
+fn top_per_group() {
+    let mut db = CoreDB::new();
+    let events = [
+        ("e1", "north", 3), ("e2", "north", 9), ("e3", "north", 5), ("e4", "north", 7),
+        ("e5", "south", 1), ("e6", "south", 4),
+        ("e7", "east", 2),
+    ];
+    for (slug, region, severity) in events {
+        db.put(slug, &format!(r#"{{"region":"{region}","severity":{severity}}}"#)).unwrap();
+    }
+    db.put("e8", r#"{"region":"south"}"#).unwrap();
+
+    let hits = db.all().top_per_group("region", "severity", 2).collect();
+    let mut by_region: std::collections::HashMap<String, Vec<String>> = Default::default();
+    for h in &hits {
+        let region = h.payload.as_ref().unwrap()["region"].as_str().unwrap().to_string();
+        by_region.entry(region).or_default().push(h.slug.clone());
+    }
+    assert_eq!(hits.len(), 5);
+    assert_eq!(by_region["north"], vec!["e2", "e4"]);
+    assert_eq!(by_region["south"], vec!["e6", "e5"], "missing sort field ranks last");
+    assert_eq!(by_region["east"], vec!["e7"]);
+
+    // Composes with filters and a global limit.
+    assert_eq!(db.all().where_gt("severity", 3.0).top_per_group("region", "severity", 1).count(), 2);
+    assert_eq!(db.all().top_per_group("region", "severity", 3).take(2).count(), 2);
+}
+
+#[test]
+fn sort_take_top_k_matches_full_sort() {
+    let mut db = CoreDB::new();
+    for i in 0..10_000u64 {
+        // Many ties on score; `_collection` lets the btree test below reuse the data.
+        db.put(
+            &format!("s/{i:05}"),
+            &format!(r#"{{"_collection":"s","score":{},"i":{i}}}"#, (i * 37) % 101),
+        ).unwrap();
+    }
+    let full: Vec<String> = db.collection("s").sort("score", false).collect()
+        .into_iter().map(|h| h.slug).collect();
+    let top: Vec<String> = db.collection("s").sort("score", false).take(10).collect()
+        .into_iter().map(|h| h.slug).collect();
+    assert_eq!(top, full[..10]);
+    let page: Vec<String> = db.collection("s").sort("score", false).skip(95).take(10).collect()
+        .into_iter().map(|h| h.slug).collect();
+    assert_eq!(page, full[95..105]);
+    assert_eq!(db.collection("s").sort("score", true).take(0).count(), 0);
+
+    // Range-indexed sort field: skip is honoured before truncating the index scan.
+    db.build_field_index("s", "i");
+    let page: Vec<u64> = db.collection("s").sort("i", true).skip(3).take(4).collect()
+        .into_iter().map(|h| h.payload.unwrap()["i"].as_u64().unwrap()).collect();
+    assert_eq!(page, vec![3, 4, 5, 6]);
+}
+
 #[test]
 fn select_projection() {
     let mut db = CoreDB::new();
@@ -291,6 +441,156 @@ fn link_meta_stores_metadata() {
     assert_eq!(meta["since"], 2020);
 }
 
+#[test]
+fn edge_weight_aggregates() {
+    let mut db = CoreDB::new();
+    for s in ["alert", "db", "net", "disk", "other"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("alert", "db", "caused_by", 0.8);
+    db.link("alert", "net", "caused_by", 0.4);
+    db.link("alert", "disk", "caused_by", 0.3);
+    db.link("other", "db", "caused_by", 0.9);
+
+    let sum = db.one("alert").forward("caused_by").sum_edge_weight();
+    assert!((sum - 1.5).abs() < 1e-6);
+    let avg = db.one("alert").forward("caused_by").avg_edge_weight().unwrap();
+    assert!((avg - 0.5).abs() < 1e-6);
+
+    // min_strength drops weak edges from the aggregate too.
+    let sum = db.one("alert").forward("caused_by").min_strength(0.35).sum_edge_weight();
+    assert!((sum - 1.2).abs() < 1e-6);
+
+    // Backward: both incoming edges of "db".
+    let sum = db.one("db").backward("caused_by").sum_edge_weight();
+    assert!((sum - 1.7).abs() < 1e-6);
+
+    assert_eq!(db.one("alert").sum_edge_weight(), 0.0);
+    assert!(db.one("disk").forward("caused_by").avg_edge_weight().is_none());
+}
+
+// ── Aggregate terminals ───────────────────────────────────────────────────────
+
+#[test]
+fn approx_distinct_estimates_cardinality() {
+    let mut db = CoreDB::new();
+    for i in 0..20_000 {
+        db.put(
+            &format!("ev/{i}"),
+            &format!(r#"{{"_collection":"ev","user":"u{}","tags":["t{}"]}}"#, i % 5_000, i % 7),
+        ).unwrap();
+    }
+    db.put("ev/null", r#"{"_collection":"ev","user":null}"#).unwrap();
+
+    let est = db.collection("ev").approx_distinct("user") as f64;
+    assert!((est - 5_000.0).abs() / 5_000.0 < 0.03, "estimate {est} too far from 5000");
+
+    // Small sets are exact via linear counting; arrays hash as whole values.
+    assert_eq!(db.collection("ev").approx_distinct("tags"), 7);
+    assert_eq!(db.collection("ev").where_eq("user", "u1").approx_distinct("user"), 1);
+    assert_eq!(db.collection("ev").approx_distinct("missing"), 0);
+}
+
+#[test]
+fn quantile_terminal() {
+    let mut db = CoreDB::new();
+    for i in 1..=100 {
+        db.put(&format!("req/{i}"), &format!(r#"{{"_collection":"req","ms":{i}}}"#)).unwrap();
+    }
+    db.put("req/bad", r#"{"_collection":"req","ms":"n/a"}"#).unwrap();
+
+    assert_eq!(db.collection("req").quantile("ms", 0.0), Some(1.0));
+    assert_eq!(db.collection("req").quantile("ms", 1.0), Some(100.0));
+    assert_eq!(db.collection("req").quantile("ms", 0.5), Some(50.5));
+    let p99 = db.collection("req").quantile("ms", 0.99).unwrap();
+    assert!((p99 - 99.01).abs() < 1e-9);
+    assert_eq!(db.collection("req").where_lt("ms", 11.0).quantile("ms", 0.5), Some(5.5));
+    assert_eq!(db.collection("req").quantile("missing", 0.5), None);
+}
+
+#[test]
+fn quantile_large_set_is_estimated() {
+    let mut db = CoreDB::new();
+    for i in 0..100_000u32 {
+        // A permutation of 0..100_000 so insertion order is not sorted.
+        let v = (i as u64 * 7_919) % 100_000;
+        db.put(&format!("{i}"), &format!(r#"{{"v":{v}}}"#)).unwrap();
+    }
+    let median = db.all().quantile("v", 0.5).unwrap();
+    assert!((median - 50_000.0).abs() < 1_500.0, "median {median}");
+}
+
+#[test]
+fn histogram_terminal() {
+    let mut db = CoreDB::new();
+    for (i, v) in [-3.0, 0.0, 4.9, 5.0, 12.5, 14.0, 99.0].iter().enumerate() {
+        db.put(&format!("m{i}"), &format!(r#"{{"_collection":"m","v":{v}}}"#)).unwrap();
+    }
+    db.put("m/text", r#"{"_collection":"m","v":"x"}"#).unwrap();
+
+    assert_eq!(
+        db.collection("m").histogram("v", 5.0),
+        vec![(-5.0, 1), (0.0, 2), (5.0, 1), (10.0, 2), (95.0, 1)]
+    );
+    assert_eq!(db.collection("m").where_lt("v", 10.0).histogram("v", 100.0), vec![(-100.0, 1), (0.0, 3)]);
+    assert!(db.collection("m").histogram("v", 0.0).is_empty());
+    assert!(db.collection("m").histogram("missing", 1.0).is_empty());
+}
+
+#[test]
+fn bucket_time_counts() {
+    let mut db = CoreDB::new();
+    let stamps = [
+        r#""2026-03-02T08:00:00Z""#,      // Monday
+        r#""2026-03-04T23:59:59+07:00""#, // Wednesday (16:59 UTC)
+        r#""2026-03-09""#,                // next Monday
+        "1772409600",                     // 2026-03-02T00:00:00Z, seconds
+        "1772409600000",                  // same instant, milliseconds
+        r#""not a date""#,
+    ];
+    for (i, at) in stamps.iter().enumerate() {
+        db.put(&format!("ev{i}"), &format!(r#"{{"at":{at}}}"#)).unwrap();
+    }
+
+    assert_eq!(
+        db.all().bucket_time("at", "week"),
+        vec![
+            ("2026-03-02T00:00:00+00:00".to_string(), 4),
+            ("2026-03-09T00:00:00+00:00".to_string(), 1),
+        ]
+    );
+    let days = db.all().bucket_time("at", "day");
+    assert_eq!(days.len(), 3);
+    assert_eq!(days[0], ("2026-03-02T00:00:00+00:00".to_string(), 3));
+    assert_eq!(db.all().bucket_time("at", "month"), vec![("2026-03-01T00:00:00+00:00".to_string(), 5)]);
+}
+
+#[test]
+fn any_and_none_test_a_predicate_over_the_set() {
+    use serde_json::json;
+    let mut db = CoreDB::new();
+    for i in 0..50 {
+        let severity = if i == 37 { "critical" } else { "minor" };
+        db.put(
+            &format!("inc/{i}"),
+            &format!(r#"{{"_collection":"inc","severity":"{severity}","open":{},"age":{i}}}"#, i % 2 == 1),
+        )
+        .unwrap();
+    }
+    let open = || db.collection("inc").where_eq("open", true);
+    assert!(open().any("severity", "critical").unwrap());
+    assert!(!db.collection("inc").where_eq("open", false).any("severity", "critical").unwrap());
+    assert!(open().none("severity", json!({"ilike": "CRIT%", "ne": "critical"})).unwrap());
+    assert!(open().any("age", json!({"gt": 40, "lte": 41})).unwrap());
+    assert!(!open().any("age", json!({"in": [2, 4, 60]})).unwrap());
+    assert!(open().any("owner", json!(null)).unwrap());
+    assert!(open().none("owner", json!({"is_null": false})).unwrap());
+
+    for bad in [json!({"gt": "x"}), json!({"near": 1}), json!({}), json!([1, 2])] {
+        assert!(matches!(open().any("age", bad), Err(sekejap::SqlError::InvalidValue(_))));
+    }
+}
+
 // ── Many nodes ────────────────────────────────────────────────────────────────
 
 #[test]
@@ -863,6 +1163,45 @@ fn spatial_grid_incremental_update() {
     assert_eq!(hits[0].slug, "p2");
 }
 
+#[test]
+fn spatial_filter_crossover_does_not_change_hits() {
+    let mut db = CoreDB::new();
+    for i in 0..200 {
+        let (lon, lat) = (144.90 + (i % 20) as f64 * 0.01, -37.90 + (i / 20) as f64 * 0.01);
+        let json = format!(
+            r#"{{"_collection":"{}","geometry":{{"type":"Point","coordinates":[{lon},{lat}]}}}}"#,
+            if i % 10 == 0 { "stops" } else { "shops" },
+        );
+        db.put(&format!("p{i}"), &json).unwrap();
+    }
+    db.build_spatial_index();
+    assert_eq!(db.near_filter_threshold(), None);
+
+    let slugs = |db: &CoreDB, coll: &str| {
+        let mut v: Vec<String> = db
+            .collection(coll)
+            .st_dwithin(-37.85, 144.99, 3.0)
+            .collect()
+            .into_iter()
+            .map(|h| h.slug)
+            .collect();
+        v.sort();
+        v
+    };
+    let adaptive = (slugs(&db, "stops"), slugs(&db, "shops"));
+    assert!(!adaptive.0.is_empty() && !adaptive.1.is_empty());
+
+    // Always per-node, then always via the grid.
+    for threshold in [usize::MAX, 0] {
+        db.reconfigure(sekejap::ConfigUpdate { near_filter_threshold: Some(Some(threshold)), ..Default::default() })
+            .unwrap();
+        assert_eq!((slugs(&db, "stops"), slugs(&db, "shops")), adaptive, "threshold {threshold}");
+    }
+
+    db.reconfigure(sekejap::ConfigUpdate { near_filter_threshold: Some(None), ..Default::default() }).unwrap();
+    assert_eq!(db.near_filter_threshold(), None);
+}
+
 // ── INSERT with geometry JSON tests ──────────────────────────────────────────
 
 #[test]
@@ -1309,6 +1648,63 @@ fn aggregate_count_star() {
     assert_eq!(p["count"], 5);
 }
 
+#[test]
+fn aggregate_median_count_distinct() {
+    let mut db = CoreDB::new();
+    let rows = [("1", "a", 10), ("2", "b", 40), ("3", "a", 20), ("4", "c", 30), ("5", "a", 50)];
+    for (k, cat, amount) in rows {
+        db.put(
+            &format!("sales/{k}"),
+            &format!(r#"{{"_collection":"sales","_key":"{k}","cat":"{cat}","amount":{amount}}}"#),
+        ).unwrap();
+    }
+
+    let hits = db
+        .query("SELECT MEDIAN(amount), COUNT(DISTINCT cat), MIN(amount), MAX(amount) FROM sales")
+        .unwrap()
+        .collect();
+    let p = hits[0].payload.as_ref().unwrap();
+    assert_eq!(p["median"].as_f64().unwrap(), 30.0);
+    assert_eq!(p["count_distinct"], 3);
+    assert_eq!(p["min"].as_f64().unwrap(), 10.0);
+    assert_eq!(p["max"].as_f64().unwrap(), 50.0);
+
+    let hits = db
+        .query("SELECT cat, MEDIAN(amount) AS mid, COUNT(DISTINCT amount) AS n FROM sales GROUP BY cat HAVING COUNT(DISTINCT amount) > 1")
+        .unwrap()
+        .collect();
+    assert_eq!(hits.len(), 1);
+    let p = hits[0].payload.as_ref().unwrap();
+    assert_eq!(p["cat"], "a");
+    assert_eq!(p["mid"].as_f64().unwrap(), 20.0);
+    assert_eq!(p["n"], 3);
+
+    // Index-only paths give the same answers.
+    db.execute("CREATE INDEX ON sales USING btree (cat)").unwrap();
+    db.execute("CREATE INDEX ON sales USING btree (amount)").unwrap();
+    let hits = db.query("SELECT COUNT(DISTINCT cat), MEDIAN(amount) FROM sales WHERE amount > 15").unwrap().collect();
+    let p = hits[0].payload.as_ref().unwrap();
+    assert_eq!(p["count_distinct"], 3);
+    assert_eq!(p["median"].as_f64().unwrap(), 35.0);
+}
+
+#[test]
+fn set_min_max_median_count_distinct() {
+    let mut db = CoreDB::new();
+    for (i, v) in [5, 1, 9, 3, 9].iter().enumerate() {
+        db.put(&format!("n{i}"), &format!(r#"{{"v":{v},"tag":"t{}"}}"#, v % 2)).unwrap();
+    }
+    db.put("none", r#"{"tag":null}"#).unwrap();
+
+    assert_eq!(db.all().min("v"), Some(1.0));
+    assert_eq!(db.all().max("v"), Some(9.0));
+    assert_eq!(db.all().median("v"), Some(5.0));
+    assert_eq!(db.all().count_distinct("v"), 4);
+    assert_eq!(db.all().count_distinct("tag"), 1);
+    assert_eq!(db.all().where_lt("v", 4.0).max("v"), Some(3.0));
+    assert_eq!(db.all().min("missing"), None);
+}
+
 #[test]
 fn aggregate_sum_avg() {
     let mut db = CoreDB::new();
@@ -1448,6 +1844,35 @@ fn hnsw_sql_vector_near() {
     assert!(slugs.contains("items/2"));
 }
 
+#[test]
+fn hnsw_collection_scoped_search_returns_full_k() {
+    let mut db = CoreDB::new();
+    // Articles crowd the query's neighbourhood; products sit further out.
+    for i in 0..1100 {
+        let t = i as f32 * 0.01;
+        db.put(&format!("articles/{i}"), r#"{"_collection":"articles"}"#).unwrap();
+        db.put_vector(&format!("articles/{i}"), "emb", &[1.0, t.sin() * 0.1, t.cos() * 0.1, 0.0])
+            .unwrap();
+        db.put(&format!("products/{i}"), r#"{"_collection":"products"}"#).unwrap();
+        db.put_vector(&format!("products/{i}"), "emb", &[0.2, t.sin(), t.cos(), 0.5]).unwrap();
+    }
+    db.build_hnsw_index("emb", 8, 40).unwrap();
+
+    let hits = db
+        .collection("products")
+        .vector_near("emb", vec![1.0, 0.0, 0.0, 0.0], 10)
+        .collect();
+    assert_eq!(hits.len(), 10);
+    assert!(hits.iter().all(|h| h.slug.starts_with("products/")));
+
+    // An empty scope must not fall back to a global search.
+    let none = db
+        .collection("missing")
+        .vector_near("emb", vec![1.0, 0.0, 0.0, 0.0], 10)
+        .collect();
+    assert!(none.is_empty());
+}
+
 #[test]
 fn hnsw_build_error_no_vectors() {
     let mut db = CoreDB::new();
@@ -1487,6 +1912,28 @@ fn hnsw_error_leaves_main_store_intact() {
     assert_eq!(vec_hits[0].slug, "nodes/1");
 }
 
+#[test]
+fn ingest_vectors_defers_hnsw_until_explicit_build() {
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE docs (_key TEXT)").unwrap();
+    db.execute("CREATE INDEX ON docs USING hnsw (emb)").unwrap();
+    let embs = [[1.0f32, 0.0, 0.0], [0.9, 0.1, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for i in 0..embs.len() {
+        db.put(&format!("docs/{i}"), &format!(r#"{{"_collection":"docs","_key":"{i}"}}"#)).unwrap();
+    }
+    let staged = db.ingest_vectors("emb", embs.iter().enumerate().map(|(i, e)| (format!("docs/{i}"), e.to_vec())));
+    assert_eq!(staged, 4);
+    assert_eq!(db.get_vector("docs/1", "emb"), Some(&[0.9f32, 0.1, 0.0][..]));
+
+    let mut calls = Vec::new();
+    db.build_hnsw_with("emb", 4, 50, |done, total| calls.push((done, total))).unwrap();
+    assert_eq!(calls, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+
+    let hits = db.collection("docs").vector_near("emb", vec![1.0, 0.0, 0.0], 2).collect();
+    let slugs: std::collections::HashSet<_> = hits.iter().map(|h| h.slug.as_str()).collect();
+    assert_eq!(slugs, ["docs/0", "docs/1"].into_iter().collect());
+}
+
 // ── WHERE parenthesized groups ─────────────────────────────────────────────────
 
 #[test]
@@ -1835,6 +2282,60 @@ fn transaction_with_link_and_remove() {
     assert!(!db.contains("nodes/c"));
 }
 
+#[test]
+fn transaction_reads_its_own_writes() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"v":1}"#).unwrap();
+    db.put("b", r#"{"v":1}"#).unwrap();
+
+    let mut txn = db.begin();
+    txn.put("a", r#"{"v":2}"#).unwrap();
+    txn.remove("b");
+    txn.put("c", r#"{"v":3}"#).unwrap();
+    assert_eq!(txn.get("a").as_deref(), Some(r#"{"v":2}"#));
+    assert!(!txn.contains("b"));
+    assert!(txn.contains("c"));
+    assert_eq!(txn.len(), 3);
+    txn.rollback();
+
+    assert!(db.get("a").unwrap().contains(r#""v":1"#));
+    assert!(db.contains("b"));
+    assert!(!db.contains("c"));
+}
+
+#[test]
+fn transaction_put_rejects_non_object_payload() {
+    let mut db = CoreDB::new();
+    let mut txn = db.begin();
+    txn.put("a", r#"{"ok":true}"#).unwrap();
+    assert!(txn.put("b", "[1,2,3]").is_err());
+    assert_eq!(txn.commit().unwrap(), 1);
+}
+
+#[test]
+fn mutate_ndjson_atomic_batch_is_all_or_nothing() {
+    let mut db = CoreDB::new();
+    let feed = r#"{"op":"txn_begin"}
+{"op":"put","slug":"a","payload":"{}"}
+{"op":"put","slug":"b","payload":"{}"}
+{"op":"link","from":"a","to":"b","edge_type":"rel","strength":1.0}
+{"op":"txn_end"}
+{"op":"txn_begin"}
+{"op":"put","slug":"c","payload":"{}"}
+{"op":"put","slug":"d","payload":"[1]"}
+{"op":"txn_end"}
+{"op":"txn_begin"}
+{"op":"put","slug":"e","payload":"{}"}
+"#;
+    let summary = db.mutate_ndjson(feed.as_bytes()).unwrap();
+    assert_eq!(summary.applied, 3);
+    assert_eq!(summary.failed, 3);
+    assert_eq!(summary.errors.iter().map(|(l, _)| *l).collect::<Vec<_>>(), vec![6, 10]);
+    assert_eq!(db.one("a").forward("rel").collect()[0].slug, "b");
+    assert!(!db.contains("c"));
+    assert!(!db.contains("e"));
+}
+
 // ── #3 btree ORDER BY index scan ──────────────────────────────────────────────
 
 #[test]
@@ -2066,6 +2567,63 @@ fn btree_index_no_false_positives() {
     assert_eq!(hits.len(), 5);
 }
 
+/// A filter after the seed step picks between the index and reading each
+/// payload by the size of the candidate set; both routes must agree with an
+/// unindexed database, and numeric ranges never match non-numeric values.
+#[test]
+fn index_and_payload_filters_agree() {
+    let load = |db: &mut CoreDB| {
+        for i in 0..400 {
+            let x = match i % 10 {
+                0 => r#""text""#.to_string(),
+                1 => "null".to_string(),
+                _ => i.to_string(),
+            };
+            let tag = if i < 8 { "rare" } else { "common" };
+            let name = if i % 3 == 0 { "North Cafe" } else { "South Bakery" };
+            db.put(
+                &format!("n/n{i}"),
+                &format!(r#"{{"_collection":"n","tag":"{tag}","x":{x},"name":"{name}"}}"#),
+            ).unwrap();
+        }
+    };
+    let mut plain = CoreDB::new();
+    load(&mut plain);
+    let mut indexed = CoreDB::new();
+    load(&mut indexed);
+    indexed.execute("CREATE INDEX ON n USING btree (tag)").unwrap();
+    indexed.execute("CREATE INDEX ON n USING btree (x)").unwrap();
+    indexed.execute("CREATE INDEX ON n USING gin (name)").unwrap();
+
+    let slugs = |db: &CoreDB, sql: &str| -> Vec<String> {
+        let mut slugs: Vec<String> = db.query(sql).unwrap().collect().into_iter().map(|h| h.slug).collect();
+        slugs.sort();
+        slugs
+    };
+    for tag in ["rare", "common"] {
+        for cond in [
+            "x > 4",
+            "x <= 300",
+            "x BETWEEN 5 AND 250",
+            "x BETWEEN 9 AND 3",
+            "x = 7",
+            "x IN (3, 5, 150)",
+            "name ILIKE '%cafe%'",
+        ] {
+            let sql = format!("SELECT * FROM n WHERE tag = '{tag}' AND {cond}");
+            assert_eq!(slugs(&indexed, &sql), slugs(&plain, &sql), "{sql}");
+        }
+    }
+    // Ranges that seed the query from the index.
+    for cond in ["x > 0", "x < 100", "x >= 5 AND x < 50", "x BETWEEN 9 AND 3"] {
+        let sql = format!("SELECT * FROM n WHERE {cond}");
+        assert_eq!(slugs(&indexed, &sql), slugs(&plain, &sql), "{sql}");
+    }
+    let numeric = slugs(&indexed, "SELECT * FROM n WHERE x > 0");
+    assert_eq!(numeric.len(), 320);
+    assert!(!numeric.contains(&"n/n10".to_string()) && !numeric.contains(&"n/n11".to_string()));
+}
+
 // ── Schema validation tests ───────────────────────────────────────────────────
 
 /// INSERT with correct types passes validation.
@@ -2793,6 +3351,51 @@ fn traverse_min_max() {
     assert!((sum_of_mins - 23.0).abs() < 1e-9);
 }
 
+/// Multi-relationship chain: every node is bound, hop labels filter
+/// destinations, and bare variables return whole nodes as tuples.
+#[test]
+fn match_multi_hop_chain_binds_each_node() {
+    let mut db = CoreDB::new();
+    db.put("events/quake", r#"{"_collection":"events","_key":"quake"}"#).unwrap();
+    db.put("events/tsunami", r#"{"_collection":"events","_key":"tsunami"}"#).unwrap();
+    db.put("people/ani", r#"{"_collection":"people","_key":"ani"}"#).unwrap();
+    db.put("geo/coast", r#"{"_collection":"geo","_key":"coast"}"#).unwrap();
+    db.put("notes/n1", r#"{"_collection":"notes","_key":"n1"}"#).unwrap();
+    db.link("events/quake", "events/tsunami", "causes", 1.0);
+    db.link("events/quake", "people/ani", "causes", 1.0);
+    db.link("events/tsunami", "geo/coast", "located_in", 1.0);
+    db.link("events/tsunami", "notes/n1", "located_in", 1.0);
+    db.link("people/ani", "geo/coast", "located_in", 1.0);
+
+    let keys = |sql: &str| -> Vec<String> {
+        let mut rows: Vec<String> = db.query(sql).unwrap().collect().into_iter()
+            .map(|h| {
+                let p = h.payload.unwrap();
+                ["a", "b", "c"].iter().map(|v| p[*v]["_key"].as_str().unwrap()).collect::<Vec<_>>().join(">")
+            })
+            .collect();
+        rows.sort();
+        rows
+    };
+    assert_eq!(
+        keys("MATCH (a:events)-[:causes]->(b)-[:located_in]->(c:geo) RETURN a, b, c"),
+        vec!["quake>ani>coast", "quake>tsunami>coast"],
+    );
+    assert_eq!(
+        keys("MATCH (a:events)-[:causes]->(b:events)-[:located_in]->(c:geo) RETURN a, b, c"),
+        vec!["quake>tsunami>coast"],
+    );
+
+    let hits = db.query(
+        "MATCH (a:events)-[:causes]->(b:events)-[:located_in]->(c) RETURN c._key AS place"
+    ).unwrap().collect();
+    let mut places: Vec<&str> = hits.iter()
+        .map(|h| h.payload.as_ref().unwrap()["place"].as_str().unwrap())
+        .collect();
+    places.sort();
+    assert_eq!(places, vec!["coast", "n1"]);
+}
+
 // ── MATCH + WITH pipeline tests ───────────────────────────────────────────────
 
 /// Basic pipeline: one MATCH, one RETURN with scalar projection.
@@ -3565,6 +4168,78 @@ fn gin_ilike_after_insert() {
     assert!(names.contains(&"The John Butler Trio"));
 }
 
+/// Text indexes are keyed by field alone and span every collection; queries
+/// must only see the entries of the collection they select.
+#[test]
+fn field_keyed_text_indexes_stay_within_the_queried_collection() {
+    let mut db = CoreDB::new();
+    for i in 0..300 {
+        let name = if i % 5 < 2 { "Cafe Cafe" } else { "Corner Shop" };
+        db.put(&format!("shops/s{i}"), &format!(r#"{{"_collection":"shops","name":"{name}","kind":"cafe"}}"#))
+            .unwrap();
+    }
+    db.put("venues/v1", r#"{"_collection":"venues","name":"Bar Uno","kind":"bar"}"#).unwrap();
+    db.put("venues/v2", r#"{"_collection":"venues","name":"Cafe Due","kind":"cafe"}"#).unwrap();
+    db.build_gin_index("name");
+    db.build_bm25_index("name");
+    db.build_field_index("venues", "kind");
+
+    let slugs = |sql: &str| -> Vec<String> {
+        db.query(sql).unwrap().collect().into_iter().map(|h| h.slug).collect()
+    };
+    // A LIMIT must not be spent on other collections' index hits.
+    assert_eq!(slugs("SELECT * FROM venues WHERE name ILIKE '%cafe%' LIMIT 1"), ["venues/v2"]);
+    // An index lookup that matched nothing leaves nothing for later filters.
+    assert!(slugs("SELECT * FROM venues WHERE kind = 'pub' AND name ILIKE '%cafe%'").is_empty());
+    // BM25 over a shared index scores this collection's documents, however
+    // many foreign documents outrank them.
+    assert_eq!(slugs("SELECT * FROM venues WHERE BM25(name, 'cafe') > 0.0"), ["venues/v2"]);
+    assert_eq!(slugs("SELECT * FROM venues ORDER BY BM25(name, 'cafe') DESC")[0], "venues/v2");
+}
+
+// ── Literal / regex string filters ────────────────────────────────────────────
+
+#[test]
+fn where_contains_starts_with_regex() {
+    let mut db = CoreDB::new();
+    db.put("svc/api-gateway", r#"{"name":"API Gateway 100%"}"#).unwrap();
+    db.put("svc/api-auth", r#"{"name":"Auth service"}"#).unwrap();
+    db.put("host/db-01", r#"{"name":"primary db"}"#).unwrap();
+
+    // `%` is literal, not a wildcard.
+    assert_eq!(db.all().where_contains("name", "100%").count(), 1);
+    assert_eq!(db.all().where_contains("name", "Gate").count(), 1);
+    assert_eq!(db.all().where_contains("name", "gate").count(), 0);
+    assert_eq!(db.all().where_starts_with("name", "Auth").count(), 1);
+    assert_eq!(db.all().where_starts_with("name", "service").count(), 0);
+
+    // `_slug` filters on node slugs.
+    assert_eq!(db.all().where_starts_with("_slug", "svc/").count(), 2);
+    assert_eq!(db.all().where_contains("_slug", "db-").count(), 1);
+
+    assert_eq!(db.all().where_regex("name", r"^(?i)api|db$").unwrap().count(), 2);
+    assert_eq!(db.all().where_regex("_slug", r"^svc/api-\w+$").unwrap().count(), 2);
+    assert!(db.all().where_regex("name", "(").is_err());
+}
+
+#[test]
+fn where_contains_with_gin_index_and_sql_regex() {
+    let mut db = CoreDB::new();
+    db.put("bands/b1", r#"{"_collection":"bands","name":"The Vines"}"#).unwrap();
+    db.put("bands/b2", r#"{"_collection":"bands","name":"the vines tribute"}"#).unwrap();
+    db.put("bands/b3", r#"{"_collection":"bands","name":"Avalanches"}"#).unwrap();
+    db.execute("CREATE INDEX ON bands USING gin (name)").unwrap();
+
+    // GIN narrows case-insensitively; the literal check keeps it case-sensitive.
+    let hits = db.collection("bands").where_contains("name", "Vines").collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slug, "bands/b1");
+
+    assert_eq!(db.query("SELECT * FROM bands WHERE name ~ '^The'").unwrap().count(), 1);
+    assert_eq!(db.query("SELECT * FROM bands WHERE name ~* '^the'").unwrap().count(), 2);
+    assert!(db.query("SELECT * FROM bands WHERE name ~ '['").is_err());
+}
+
 // ── Edge intrinsics: r._depth, r._path_keys ──────────────────────────────────
 
 /// `r._depth` counts hops from start.
@@ -3641,6 +4316,55 @@ fn edge_intrinsic_strength_aggregates() {
     assert!((min - 0.4).abs() < 1e-4, "min_strength should be 0.4, got {min}");
 }
 
+/// Returning the edge variable yields the crossed edge with its type,
+/// weight, metadata and timestamp alongside the bound nodes.
+#[test]
+fn match_returning_edge_variable_exposes_the_edge() {
+    let mut db = CoreDB::new();
+    db.put("events/quake", r#"{"_collection":"events","_key":"quake"}"#).unwrap();
+    db.put("events/fire", r#"{"_collection":"events","_key":"fire"}"#).unwrap();
+    db.link_meta("events/quake", "events/fire", "causes", 0.8, r#"{"lag_h":2}"#).unwrap();
+    let created = db.edges_from("events/quake")[0].created_unix;
+
+    let hits = db.query("MATCH (a:events)-[r:causes]->(b) RETURN a, r, b").unwrap().collect();
+    assert_eq!(hits.len(), 1);
+    let row = hits[0].payload.as_ref().unwrap();
+    assert_eq!(row["a"]["_key"], "quake");
+    assert_eq!(row["b"]["_key"], "fire");
+    let r = &row["r"];
+    assert_eq!((r["_from"].as_str(), r["_to"].as_str()), (Some("events/quake"), Some("events/fire")));
+    assert_eq!(r["_type"], "causes");
+    assert_eq!(r["_meta"]["lag_h"], 2);
+    assert_eq!(r["_created_unix"], created);
+    assert!((r["_strength"].as_f64().unwrap() - 0.8).abs() < 1e-6);
+}
+
+#[test]
+fn match_backward_edge_variable_reports_incoming_direction() {
+    let mut db = CoreDB::new();
+    for key in ["home", "blog", "wiki"] {
+        db.put(&format!("pages/{key}"), &format!(r#"{{"_collection":"pages","_key":"{key}"}}"#)).unwrap();
+    }
+    db.link("pages/blog", "pages/home", "links", 1.0);
+    db.link("pages/wiki", "pages/home", "links", 0.5);
+
+    let hits = db
+        .query("MATCH (p:pages)<-[r:links]-(src) WHERE p._key = 'home' RETURN p, r, src")
+        .unwrap()
+        .collect();
+    assert_eq!(hits.len(), 2);
+    for hit in &hits {
+        let row = hit.payload.as_ref().unwrap();
+        let r = &row["r"];
+        assert_eq!(r["_direction"], "in");
+        assert_eq!(r["_to"], "pages/home");
+        assert_eq!(r["_from"], format!("pages/{}", row["src"]["_key"].as_str().unwrap()));
+    }
+
+    let out = db.query("MATCH (a:pages)-[r:links]->(b) RETURN a, r, b").unwrap().collect();
+    assert!(out.iter().all(|h| h.payload.as_ref().unwrap()["r"]["_direction"] == "out"));
+}
+
 // ── MATCH SHORTEST ───────────────────────────────────────────────────────────
 
 /// Build a small graph that contains multiple paths of different lengths and
@@ -4395,6 +5119,40 @@ fn insert_without_key_no_schema_errors() {
     assert!(result.is_err(), "INSERT without _key and no schema must fail");
 }
 
+/// put_auto ids sort by creation time and never repeat, even within one
+/// millisecond or when the clock steps backwards.
+#[test]
+fn put_auto_generates_sortable_unique_slugs() {
+    use sekejap::ManualClock;
+    use std::sync::Arc;
+    let clock = Arc::new(ManualClock::new(1_700_000_000_000));
+    let mut db = CoreDB::new();
+    db.set_clock(clock.clone());
+
+    let first = db.put_auto("orders", r#"{"total":1}"#).unwrap();
+    let same_ms: Vec<String> = (0..500).map(|_| db.put_auto("orders", "{}").unwrap()).collect();
+    clock.advance(std::time::Duration::from_secs(1));
+    let later = db.put_auto("orders", "{}").unwrap();
+    clock.set(1_600_000_000_000);
+    let after_step_back = db.put_auto("orders", "{}").unwrap();
+
+    let mut all = vec![first.clone()];
+    all.extend(same_ms);
+    all.extend([later.clone(), after_step_back.clone()]);
+    assert!(all.windows(2).all(|w| w[0] < w[1]), "ids must strictly increase");
+    assert_eq!(db.collection("orders").count(), all.len());
+    // The first 10 characters encode the timestamp.
+    assert_eq!(first[7..17], all[500][7..17]);
+    assert_ne!(first[7..17], later[7..17]);
+    assert_eq!(later[7..17], after_step_back[7..17]);
+
+    let v: serde_json::Value = serde_json::from_str(&db.get(&first).unwrap()).unwrap();
+    assert_eq!(v["_collection"], "orders");
+    assert_eq!(v["_key"], first["orders/".len()..]);
+    assert_eq!(v["total"], 1);
+    assert!(db.put_auto("orders", "[1]").is_err());
+}
+
 // ── Parameter bindings ($1, $2, ...) ─────────────────────────────────────────
 
 #[test]
@@ -4952,13 +5710,100 @@ fn param_limit_and_offset() {
     assert_eq!(hits.len(), 2);
 }
 
-// ── Score projection in SELECT ───────────────────────────────────────────────
+// ── Prepared statements ─────────────────────────────────────────────────────
 
-/// `SELECT *, BM25(field, 'query') AS score FROM ...` — score in SELECT with alias.
 #[test]
-fn select_bm25_score_projection() {
+fn prepared_statements_are_cached_and_rebound() {
+    use serde_json::json;
     let mut db = CoreDB::new();
-    db.execute("CREATE TABLE articles (_key TEXT, title TEXT, body TEXT)").unwrap();
+    for i in 1..=5 {
+        db.put(&format!("items/{i}"), &format!(r#"{{"_collection":"items","val":{i}}}"#)).unwrap();
+    }
+    db.link("items/1", "items/2", "next", 1.0);
+
+    let above = db.prepare("SELECT * FROM items WHERE val > $1 ORDER BY val ASC LIMIT $2").unwrap();
+    assert_eq!(above.param_count(), 2);
+    let hits = db.query_prepared(&above, &[json!(2), json!(10)]).unwrap().collect();
+    assert_eq!(hits.iter().map(|h| h.slug.as_str()).collect::<Vec<_>>(), ["items/3", "items/4", "items/5"]);
+    let hits = db.query_prepared(&above, &[json!(0), json!(1)]).unwrap().collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slug, "items/1");
+    assert!(matches!(
+        db.query_prepared(&above, &[json!(2)]),
+        Err(sekejap::SqlError::ParamOutOfRange { index: 2, count: 1 })
+    ));
+
+    // Same text → the cached statement; results match an unprepared query.
+    let again = db.prepare("SELECT * FROM items WHERE val > $1 ORDER BY val ASC LIMIT $2").unwrap();
+    assert_eq!(again.sql(), above.sql());
+    let sql = "MATCH (a)-[:next]->(b) WHERE a._key = 'items/1' RETURN b";
+    let graph = db.prepare(sql).unwrap();
+    assert_eq!(graph.param_count(), 0);
+    for _ in 0..2 {
+        let prepared = db.query_prepared(&graph, &[]).unwrap().collect();
+        let direct = db.query(sql).unwrap().collect();
+        assert_eq!(prepared.len(), 1);
+        assert_eq!(prepared[0].slug, direct[0].slug);
+    }
+
+    // Statements without placeholders are checked when prepared.
+    assert!(db.prepare("SELECT * FROM").is_err());
+}
+
+#[test]
+fn named_params_bind_from_an_object() {
+    use sekejap::SqlError;
+    use serde_json::json;
+    let mut db = CoreDB::new();
+    for (i, name) in ["Ali", "Budi", "Citra"].iter().enumerate() {
+        db.put(&format!("users/{i}"), &format!(r#"{{"_collection":"users","name":"{name}","age":{}}}"#, 20 + i * 10))
+            .unwrap();
+    }
+    let params = json!({"name": "Budi", "min": 25, "unused": true});
+    let params = params.as_object().unwrap();
+
+    // A name used twice binds one value.
+    let hits = db.query_named(
+        "SELECT * FROM users WHERE age >= $min AND (name = $name OR age < $min)",
+        params,
+    ).unwrap().collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slug, "users/1");
+
+    // Unbound, mixed and positional-only uses are refused before running.
+    assert!(matches!(
+        db.query_named("SELECT * FROM users WHERE name = $nmae", params),
+        Err(SqlError::UnboundParam(n)) if n == "nmae"
+    ));
+    assert!(matches!(
+        db.query_named("SELECT * FROM users WHERE name = $name AND age > $1", params),
+        Err(SqlError::InvalidValue(_))
+    ));
+    assert!(matches!(
+        db.query_params("SELECT * FROM users WHERE name = $name", &[json!("Budi")]),
+        Err(SqlError::UnboundParam(n)) if n == "name"
+    ));
+
+    // Prepared statements keep the names and bind them per run.
+    let by_name = db.prepare("SELECT * FROM users WHERE name = $name AND age >= $min").unwrap();
+    assert_eq!(by_name.param_names(), ["name", "min"]);
+    assert_eq!(db.query_prepared_named(&by_name, params).unwrap().count(), 1);
+    let other = json!({"name": "Citra", "min": 0});
+    let hits = db.query_prepared_named(&by_name, other.as_object().unwrap()).unwrap().collect();
+    assert_eq!(hits[0].slug, "users/2");
+    assert!(matches!(
+        db.query_prepared_named(&by_name, json!({"name": "Ali"}).as_object().unwrap()),
+        Err(SqlError::UnboundParam(n)) if n == "min"
+    ));
+}
+
+// ── Score projection in SELECT ───────────────────────────────────────────────
+
+/// `SELECT *, BM25(field, 'query') AS score FROM ...` — score in SELECT with alias.
+#[test]
+fn select_bm25_score_projection() {
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE articles (_key TEXT, title TEXT, body TEXT)").unwrap();
     db.execute("INSERT INTO articles (_key, title, body) VALUES ('a1', 'rust guide', 'learn rust programming')").unwrap();
     db.execute("INSERT INTO articles (_key, title, body) VALUES ('a2', 'python guide', 'learn python scripting')").unwrap();
     db.execute("INSERT INTO articles (_key, title, body) VALUES ('a3', 'go guide', 'learn go programming')").unwrap();
@@ -5248,3 +6093,1963 @@ fn incremental_hnsw_remove_and_reinsert() {
         assert_ne!(key, "item25", "deleted item should not appear in results");
     }
 }
+
+// ── Soft delete / restore / purge ─────────────────────────────────────────────
+
+#[test]
+fn remove_soft_hides_node_until_restored() {
+    let mut db = CoreDB::new();
+    db.put("alice", r#"{"name":"Alice","age":30,"_collection":"users"}"#).unwrap();
+    db.put("bob",   r#"{"name":"Bob","_collection":"users"}"#).unwrap();
+    db.link_meta("alice", "bob", "follows", 0.5, r#"{"since":2020}"#).unwrap();
+    db.link("bob", "alice", "follows", 1.0);
+
+    assert!(db.remove_soft("alice"));
+    assert!(!db.contains("alice"));
+    assert!(db.is_trashed("alice"));
+    assert_eq!(db.collection("users").count(), 1);
+    assert!(db.one("bob").forward("follows").collect().is_empty());
+
+    assert!(db.restore("alice"));
+    assert!(!db.is_trashed("alice"));
+    assert!(db.get("alice").unwrap().contains("Alice"));
+    assert_eq!(db.collection("users").count(), 2);
+    assert_eq!(db.one("bob").forward("follows").collect()[0].slug, "alice");
+    let out = db.edges_from("alice");
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].meta.as_ref().unwrap()["since"], 2020);
+}
+
+#[test]
+fn restore_defers_edges_to_trashed_neighbours() {
+    let mut db = CoreDB::new();
+    db.put("a", "{}").unwrap();
+    db.put("b", "{}").unwrap();
+    db.link("a", "b", "rel", 1.0);
+    db.remove_soft("a");
+    db.remove_soft("b");
+
+    db.restore("a");
+    assert!(db.edges_from("a").is_empty());
+    db.restore("b");
+    assert_eq!(db.one("a").forward("rel").collect()[0].slug, "b");
+}
+
+#[test]
+fn purge_is_final() {
+    let mut db = CoreDB::new();
+    db.put("a", "{}").unwrap();
+    assert!(!db.purge("a"), "live nodes are not purged");
+    db.remove_soft("a");
+    assert!(db.purge("a"));
+    assert!(!db.restore("a"));
+    assert!(!db.contains("a"));
+}
+
+#[test]
+fn restore_refuses_when_slug_reused() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"v":1}"#).unwrap();
+    db.remove_soft("a");
+    db.put("a", r#"{"v":2}"#).unwrap();
+    assert!(!db.restore("a"));
+    assert!(db.get("a").unwrap().contains(r#""v":2"#));
+    assert!(db.is_trashed("a"));
+}
+
+#[test]
+fn remove_soft_refuses_to_overwrite_a_trashed_version() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"v":1}"#).unwrap();
+    assert!(db.remove_soft("a"));
+    db.put("a", r#"{"v":2}"#).unwrap();
+    assert!(!db.remove_soft("a"), "the first trashed version would be lost");
+    assert!(db.contains("a"));
+
+    assert!(db.purge("a"));
+    assert!(db.remove_soft("a"));
+    assert!(db.restore("a"));
+    assert!(db.get("a").unwrap().contains(r#""v":2"#));
+
+    // A refused trash operation is a failed line, not an applied one.
+    let feed = concat!(
+        r#"{"op":"soft_remove","slug":"nope"}"#, "\n",
+        r#"{"op":"restore","slug":"a"}"#, "\n",
+        r#"{"op":"purge","slug":"a"}"#, "\n",
+        r#"{"op":"soft_remove","slug":"a"}"#, "\n",
+    );
+    let summary = db.mutate_ndjson(feed.as_bytes()).unwrap();
+    assert_eq!((summary.applied, summary.failed), (1, 3), "{:?}", summary.errors);
+    assert_eq!(summary.errors[0].0, 1);
+    assert!(db.is_trashed("a"));
+}
+
+// ── NDJSON mutation stream ────────────────────────────────────────────────────
+
+#[test]
+fn mutate_ndjson_applies_and_summarizes() {
+    let mut db = CoreDB::new();
+    let feed = r#"{"op":"put","slug":"alice","payload":"{\"name\":\"Alice\",\"_collection\":\"users\"}"}
+{"op":"put","slug":"bob","payload":"{\"name\":\"Bob\",\"_collection\":\"users\"}"}
+
+{"op":"link","from":"alice","to":"bob","edge_type":"follows","strength":1.0}
+not json
+{"op":"put","slug":"bad","payload":"[1,2"}
+{"op":"put_vector","slug":"alice","field":"emb","data":[1.0,0.0]}
+{"op":"remove","slug":"bob"}
+"#;
+    let summary = db.mutate_ndjson(feed.as_bytes()).unwrap();
+    assert_eq!(summary.applied, 5);
+    assert_eq!(summary.failed, 2);
+    assert_eq!(summary.errors.iter().map(|(l, _)| *l).collect::<Vec<_>>(), vec![5, 6]);
+    assert!(db.contains("alice"));
+    assert!(!db.contains("bob"));
+    assert!(!db.contains("bad"));
+    assert_eq!(db.get_vector("alice", "emb"), Some(&[1.0_f32, 0.0][..]));
+}
+
+#[test]
+fn mutate_json_applies_single_documents_and_batches() {
+    let mut db = CoreDB::new();
+    let one = db.mutate_json(r#"{"op":"put","slug":"a","payload":"{}"}"#).unwrap();
+    assert_eq!(one, vec![Ok(())]);
+
+    let results = db.mutate_json(r#"{"mutations":[
+        {"op":"put","slug":"b","payload":"{}"},
+        {"op":"bogus"},
+        {"op":"link","from":"a","to":"b","edge_type":"knows","strength":1.0}
+    ]}"#).unwrap();
+    assert!(results[0].is_ok() && results[1].is_err() && results[2].is_ok());
+    assert_eq!(db.edges_from("a").len(), 1);
+
+    // Atomic batches apply nothing when any item fails.
+    let results = db.mutate_json(r#"{"atomic":true,"mutations":[
+        {"op":"put","slug":"c","payload":"{}"},
+        {"op":"put","slug":"d","payload":"[1,2"}
+    ]}"#).unwrap();
+    assert!(results.iter().all(|r| r.as_ref().is_err_and(|e| e.contains("atomic batch rejected"))));
+    assert!(!db.contains("c"));
+
+    assert!(db.mutate_json("[1,2").is_err());
+}
+
+// ── Collection implicit filters ───────────────────────────────────────────────
+
+#[test]
+fn collection_filter_applies_to_builder_sql_and_mutations() {
+    let mut db = CoreDB::new();
+    db.put("u/1", r#"{"_collection":"users","tenant":"a","name":"Ann"}"#).unwrap();
+    db.put("u/2", r#"{"_collection":"users","tenant":"b","name":"Bo"}"#).unwrap();
+    db.put("u/3", r#"{"_collection":"users","tenant":"a","name":"Cy","deleted":true}"#).unwrap();
+    db.set_collection_filter("users", Some("tenant = 'a' AND deleted IS NULL")).unwrap();
+    assert_eq!(db.collection_filter("users"), Some("tenant = 'a' AND deleted IS NULL"));
+
+    assert_eq!(db.collection("users").count(), 1);
+    let hits = db.query("SELECT name FROM users").unwrap().collect();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slug, "u/1");
+    assert_eq!(db.collection_unfiltered("users").count(), 3);
+
+    // DELETE cannot reach rows hidden by the filter.
+    assert_eq!(db.execute("DELETE FROM users WHERE name = 'Bo'").unwrap(), 0);
+    assert!(db.contains("u/2"));
+
+    db.set_collection_filter("users", None).unwrap();
+    assert_eq!(db.collection("users").count(), 3);
+}
+
+#[test]
+fn collection_filter_rejects_non_filters() {
+    let mut db = CoreDB::new();
+    assert!(db.set_collection_filter("users", Some("a = 1 ORDER BY b")).is_err());
+    assert!(db.set_collection_filter("users", Some("= =")).is_err());
+    assert_eq!(db.collection_filter("users"), None);
+}
+
+// ── Slug reverse resolution ──────────────────────────────────────────────────
+
+#[test]
+fn slug_of_resolves_hit_hashes() {
+    let mut db = CoreDB::new();
+    db.put("city/ubud", r#"{"name":"Ubud"}"#).unwrap();
+    let hits = db.one("city/ubud").collect();
+    assert_eq!(db.slug_of(hits[0].slug_hash), Some("city/ubud"));
+    db.remove("city/ubud");
+    assert_eq!(db.slug_of(hits[0].slug_hash), None);
+}
+
+// ── Merge upserts ────────────────────────────────────────────────────────────
+
+fn payload(db: &CoreDB, slug: &str) -> serde_json::Value {
+    serde_json::from_str(&db.get(slug).unwrap()).unwrap()
+}
+
+#[test]
+fn put_merge_strategies() {
+    use sekejap::MergeStrategy;
+    let mut db = CoreDB::new();
+
+    // Missing node: plain insert.
+    db.put_merge("p/1", r#"{"a":{"x":1},"tags":["t1"]}"#, MergeStrategy::Deep).unwrap();
+    assert_eq!(payload(&db, "p/1")["a"]["x"], 1);
+
+    db.put_merge("p/1", r#"{"a":{"y":2},"tags":["t2"]}"#, MergeStrategy::Deep).unwrap();
+    let v = payload(&db, "p/1");
+    assert_eq!(v["a"], serde_json::json!({"x":1,"y":2}));
+    assert_eq!(v["tags"], serde_json::json!(["t2"]));
+
+    db.put_merge("p/1", r#"{"tags":["t2","t3"]}"#, MergeStrategy::ArrayUnion).unwrap();
+    assert_eq!(payload(&db, "p/1")["tags"], serde_json::json!(["t2", "t3"]));
+
+    db.put_merge("p/1", r#"{"a":{"z":3}}"#, MergeStrategy::Shallow).unwrap();
+    assert_eq!(payload(&db, "p/1")["a"], serde_json::json!({"z":3}));
+
+    // Latest timestamp: an older observation only fills gaps.
+    let ts = MergeStrategy::LatestTimestamp("seen".into());
+    db.put("s/1", r#"{"seen":"2024-05-01","name":"new"}"#).unwrap();
+    db.put_merge("s/1", r#"{"seen":"2024-01-01","name":"old","phone":"123"}"#, ts.clone()).unwrap();
+    let v = payload(&db, "s/1");
+    assert_eq!(v["name"], "new");
+    assert_eq!(v["seen"], "2024-05-01");
+    assert_eq!(v["phone"], "123");
+    db.put_merge("s/1", r#"{"seen":"2024-06-01","name":"newest"}"#, ts).unwrap();
+    assert_eq!(payload(&db, "s/1")["name"], "newest");
+
+    assert!(db.put_merge("p/1", "{bad", MergeStrategy::Deep).is_err());
+}
+
+// ── Undirected traversal ─────────────────────────────────────────────────────
+
+#[test]
+fn both_traverses_edges_in_either_direction() {
+    let mut db = CoreDB::new();
+    for s in ["a", "b", "c", "d"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("a", "b", "similar_to", 0.9);
+    db.link("c", "b", "similar_to", 0.2);
+    db.link("b", "d", "other", 1.0);
+
+    let mut slugs: Vec<String> = db.one("b").both("similar_to").collect().into_iter().map(|h| h.slug).collect();
+    slugs.sort();
+    assert_eq!(slugs, ["a", "c"]);
+
+    // MinStrength applies to the edge crossed in either direction.
+    let strong = db.one("b").both("similar_to").min_strength(0.5).collect();
+    assert_eq!(strong.len(), 1);
+    assert_eq!(strong[0].slug, "a");
+
+    assert!((db.one("b").both("similar_to").sum_edge_weight() - 1.1).abs() < 1e-6);
+}
+
+#[test]
+fn fuse_merges_payload_and_rewires_edges() {
+    use sekejap::MergeStrategy;
+    let mut db = CoreDB::new();
+    db.put("p/1", r#"{"_collection":"people","name":"Ann","tags":["x"]}"#).unwrap();
+    db.put("p/2", r#"{"_collection":"people","phone":"123","tags":["y"]}"#).unwrap();
+    for s in ["org", "club", "friend"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("p/1", "org", "works_at", 1.0);
+    db.link("p/2", "org", "works_at", 1.0);
+    db.link("p/2", "club", "member_of", 0.5);
+    db.link("friend", "p/2", "knows", 0.7);
+    db.link("p/2", "p/1", "same_as", 1.0);
+
+    assert!(db.fuse("p/1", "p/2", MergeStrategy::ArrayUnion).unwrap());
+
+    let v = payload(&db, "p/1");
+    assert_eq!(v["name"], "Ann");
+    assert_eq!(v["phone"], "123");
+    assert_eq!(v["tags"], serde_json::json!(["x", "y"]));
+    assert!(db.is_trashed("p/2"));
+
+    assert_eq!(db.one("p/1").forward("works_at").count(), 1);
+    assert_eq!(db.one("p/1").forward("member_of").count(), 1);
+    assert_eq!(db.one("p/1").backward("knows").count(), 1);
+    assert_eq!(db.one("p/1").forward("same_as").count(), 0);
+
+    assert_eq!(v["_fused_from"], serde_json::json!(["p/2"]));
+
+    // Provenance lives on the primary, so purging the duplicate leaves no
+    // edge pointing at it.
+    assert!(db.purge("p/2"));
+    assert!(db.edges_from("p/1").iter().all(|e| e.to_slug.is_some()));
+    db.put("p/3", r#"{"_collection":"people","email":"a@x"}"#).unwrap();
+    db.put("p/4", r#"{"_collection":"people"}"#).unwrap();
+    assert!(db.fuse("p/4", "p/3", MergeStrategy::Deep).unwrap());
+    assert!(db.fuse("p/1", "p/4", MergeStrategy::Shallow).unwrap());
+    assert_eq!(payload(&db, "p/1")["_fused_from"], serde_json::json!(["p/2", "p/3", "p/4"]));
+
+    assert!(!db.fuse("p/1", "missing", MergeStrategy::Deep).unwrap());
+    assert!(!db.fuse("p/1", "p/1", MergeStrategy::Deep).unwrap());
+}
+
+#[test]
+fn failed_fuse_leaves_both_nodes_untouched() {
+    use sekejap::{MergeStrategy, Quota, QuotaExceeded};
+    let mut db = CoreDB::new();
+    db.put("p/1", r#"{"_collection":"people","name":"Ann"}"#).unwrap();
+    db.put("p/2", r#"{"_collection":"people","bio":"a rather long biography"}"#).unwrap();
+    db.put("org", "{}").unwrap();
+    db.link("p/2", "org", "works_at", 1.0);
+    db.set_quota("people", Quota { max_payload_bytes: Some(150), ..Default::default() });
+
+    let err = db.fuse("p/1", "p/2", MergeStrategy::Deep).unwrap_err();
+    assert!(matches!(QuotaExceeded::from_put_error(err), Ok(QuotaExceeded::PayloadBytes { .. })));
+    assert!(payload(&db, "p/1").get("bio").is_none());
+    assert!(!db.is_trashed("p/2"));
+    assert_eq!(db.one("p/2").forward("works_at").count(), 1);
+    assert_eq!(db.one("p/1").forward("works_at").count(), 0);
+}
+
+#[test]
+fn fuse_survives_wal_replay() {
+    use sekejap::MergeStrategy;
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("a", r#"{"x":1}"#).unwrap();
+        db.put("b", r#"{"y":2}"#).unwrap();
+        db.put("c", "{}").unwrap();
+        db.link("b", "c", "rel", 1.0);
+        db.fuse("a", "b", MergeStrategy::Shallow).unwrap();
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(payload(&db, "a")["y"], 2);
+    assert!(db.is_trashed("b"));
+    assert_eq!(db.one("a").forward("rel").count(), 1);
+}
+
+#[test]
+fn duplicate_candidates_combine_signals() {
+    use sekejap::DedupOptions;
+    let mut db = CoreDB::new();
+    db.put("p/1", r#"{"_collection":"people","name":"Ann Smith"}"#).unwrap();
+    db.put("p/2", r#"{"_collection":"people","name":"Ann Smith"}"#).unwrap();
+    db.put("p/3", r#"{"_collection":"people","name":"Bob Jones"}"#).unwrap();
+    db.put("p/4", r#"{"_collection":"people","name":"Robert Jones"}"#).unwrap();
+    db.put("acme", "{}").unwrap();
+    db.link("p/3", "acme", "works_at", 1.0);
+    db.link("p/4", "acme", "works_at", 1.0);
+    db.put_vector("p/1", "emb", &[1.0, 0.0]).unwrap();
+    db.put_vector("p/2", "emb", &[0.99, 0.05]).unwrap();
+    db.put_vector("p/3", "emb", &[0.0, 1.0]).unwrap();
+    db.put_vector("p/4", "emb", &[-1.0, 0.0]).unwrap();
+
+    let opts = DedupOptions {
+        vector_field: Some("emb".into()),
+        text_fields: vec!["name".into()],
+        ..Default::default()
+    };
+    let pairs = db.duplicate_candidates("people", &opts);
+    assert_eq!((pairs[0].a.as_str(), pairs[0].b.as_str()), ("p/1", "p/2"));
+    assert!(pairs[0].confidence > 0.95);
+    assert_eq!(pairs[0].text_overlap, Some(1.0));
+
+    // p/3–p/4 share an employer and a surname but have opposite-ish vectors.
+    let p34 = pairs.iter().find(|p| p.a == "p/3" && p.b == "p/4");
+    assert!(p34.is_none());
+    let loose = DedupOptions { min_confidence: 0.25, ..opts };
+    let pairs = db.duplicate_candidates("people", &loose);
+    let p34 = pairs.iter().find(|p| p.a == "p/3" && p.b == "p/4").unwrap();
+    assert_eq!(p34.shared_entities, 1);
+    assert!(pairs.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+}
+
+// ── Per-hop traversal predicates ─────────────────────────────────────────────
+
+#[test]
+fn where_each_hop_prunes_bfs() {
+    let mut db = CoreDB::new();
+    db.put("a", r#"{"type":"event"}"#).unwrap();
+    db.put("b", r#"{"type":"event"}"#).unwrap();
+    db.put("n", r#"{"type":"note"}"#).unwrap();
+    db.put("c", r#"{"type":"event"}"#).unwrap();
+    db.put("d", r#"{"type":"event"}"#).unwrap();
+    db.link("a", "b", "causes", 1.0);
+    db.link("b", "c", "causes", 1.0);
+    db.link("a", "n", "causes", 1.0);
+    db.link("n", "d", "causes", 1.0);
+
+    let slugs = |set: sekejap::Set<'_>| {
+        let mut v: Vec<String> = set.collect().into_iter().map(|h| h.slug).collect();
+        v.sort();
+        v
+    };
+    // Final-frontier filtering still reaches d through the note.
+    assert_eq!(slugs(db.one("a").hops_typed("causes", 3).where_eq("type", "event")), ["b", "c", "d"]);
+    // Per-hop filtering cuts the path at the note.
+    assert_eq!(
+        slugs(db.one("a").hops_typed("causes", 3).where_each_hop(|s| s.where_eq("type", "event"))),
+        ["b", "c"]
+    );
+    // Untyped hops keep the start node and prune the same way.
+    assert_eq!(
+        slugs(db.one("a").hops(3).where_each_hop(|s| s.where_eq("type", "event"))),
+        ["a", "b", "c"]
+    );
+    // After a single hop it is a plain filter on the frontier.
+    assert_eq!(
+        slugs(db.one("a").forward("causes").where_each_hop(|s| s.where_neq("type", "event"))),
+        ["n"]
+    );
+}
+
+// ── SimHash near duplicates ──────────────────────────────────────────────────
+
+#[test]
+fn simhash_near_duplicates_track_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = "Flooding closes the main road into Ubud after heavy overnight rain";
+    let b = "Flooding closes the main road into Ubud after heavy rain overnight";
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.execute("CREATE INDEX ON news USING simhash (title, body)").unwrap();
+        db.put("n/1", &format!(r#"{{"_collection":"news","body":"{a}"}}"#)).unwrap();
+        db.put("n/2", &format!(r#"{{"_collection":"news","body":"{b}"}}"#)).unwrap();
+        db.put("n/3", r#"{"_collection":"news","body":"Central bank holds interest rates steady"}"#).unwrap();
+        db.put("n/4", r#"{"_collection":"news","title":42}"#).unwrap();
+
+        let pairs = db.collection("news").near_duplicates(3);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0.as_str(), pairs[0].1.as_str()), ("n/1", "n/2"));
+        assert!(pairs[0].2 <= 3);
+        assert!(db.collection("news").near_duplicates(0).iter().all(|p| p.2 == 0));
+
+        // Updates re-fingerprint; the pair disappears.
+        db.execute("UPDATE news SET body = 'Completely unrelated sports coverage tonight' WHERE body LIKE '%rain overnight'").unwrap();
+        assert!(db.collection("news").near_duplicates(3).is_empty());
+        db.put("n/2", &format!(r#"{{"_collection":"news","body":"{b}"}}"#)).unwrap();
+    }
+    // Fingerprints are rebuilt on open.
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.collection("news").near_duplicates(3).len(), 1);
+}
+
+// ── Query traces ─────────────────────────────────────────────────────────────
+
+#[test]
+fn trace_times_steps_and_folds_sub_pipelines() {
+    let mut db = CoreDB::new();
+    for i in 0..3000 {
+        db.put(&format!("n/{i}"), &format!(r#"{{"_collection":"n","v":{},"w":{}}}"#, i % 10, i % 7)).unwrap();
+    }
+    let set = db
+        .collection("n")
+        .where_eq("v", 3)
+        .union(db.collection("n").where_eq("w", 0).where_eq("v", 4));
+    let trace = set.trace();
+    assert_eq!(trace.steps.len(), 3);
+    assert_eq!((trace.steps[0].rows_in, trace.steps[0].rows_out), (0, 3000));
+    assert_eq!(trace.steps[1].rows_out, 300);
+    let union = &trace.steps[2];
+    assert_eq!(union.children.len(), 3);
+    assert_eq!(union.children[2].rows_out, 43);
+    assert_eq!(union.rows_out, set.count());
+    assert!(union.elapsed >= union.children.iter().map(|c| c.elapsed).sum());
+    assert!(trace.elapsed() >= union.elapsed);
+
+    let folded = trace.to_folded();
+    assert!(!folded.is_empty());
+    for line in folded.lines() {
+        let (stack, micros) = line.rsplit_once(' ').unwrap();
+        assert!(stack.starts_with("query;"), "{line}");
+        assert!(micros.parse::<u64>().unwrap() > 0);
+    }
+    assert!(folded.lines().any(|l| l.starts_with("query;1 ")), "{folded}");
+}
+
+#[test]
+fn collect_traced_returns_the_hits_and_a_json_trace() {
+    let mut db = CoreDB::new();
+    for i in 0..20 {
+        db.put(&format!("n/{i}"), &format!(r#"{{"_collection":"n","v":{}}}"#, i % 4)).unwrap();
+    }
+    let (hits, trace) = db.query("SELECT * FROM n WHERE v = 1 LIMIT 3").unwrap().collect_traced();
+    assert_eq!(hits.len(), 3);
+    assert!(!trace.steps.is_empty());
+    assert_eq!(trace.steps[0].rows_out, 20);
+
+    let json = trace.to_json();
+    let steps = json["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), trace.steps.len());
+    assert_eq!(steps[0]["step"], trace.steps[0].step.as_str());
+    assert_eq!(steps[0]["rows_out"], 20);
+    assert!(steps.iter().all(|s| s["elapsed_us"].is_u64() && s["children"].is_array()));
+    assert_eq!(json["elapsed_us"], trace.elapsed().as_micros() as u64);
+}
+
+// ── Query limits ─────────────────────────────────────────────────────────────
+
+#[test]
+fn query_limits_database_and_per_call() {
+    use sekejap::{QueryLimits, SqlError};
+    let mut db = CoreDB::new();
+    for i in 0..10 {
+        db.put(&format!("u/{i}"), &format!(r#"{{"_collection":"users","g":{}}}"#, i % 2)).unwrap();
+    }
+    db.set_query_limits(QueryLimits { max_results: Some(5), ..Default::default() });
+    assert_eq!(db.query("SELECT * FROM users").unwrap().collect().len(), 5);
+    assert_eq!(db.query("SELECT * FROM users").unwrap().try_collect().unwrap_err().hits, 10);
+    assert_eq!(db.collection("users").collect().len(), 5, "result caps hold the builder API too");
+
+    // The cap applies to output rows, after aggregation.
+    let agg = db.query("SELECT COUNT(*) AS n FROM users").unwrap().collect();
+    assert_eq!(agg[0].payload.as_ref().unwrap()["n"], 10);
+
+    // Per-call limits only tighten.
+    let loose = QueryLimits { max_results: Some(50), ..Default::default() };
+    assert_eq!(db.query_with_limits("SELECT * FROM users", &[], &loose).unwrap().collect().len(), 5);
+    let strict = QueryLimits { max_results: Some(2), max_steps: Some(2), ..Default::default() };
+    assert_eq!(db.query_with_limits("SELECT * FROM users WHERE g = 1", &[], &strict).unwrap().collect().len(), 2);
+    assert!(matches!(
+        db.query_with_limits("SELECT * FROM users WHERE g = 1 AND g < 5", &[], &strict),
+        Err(SqlError::LimitExceeded(_))
+    ));
+    // Steps nested under HAVING count too.
+    let having = "SELECT g, COUNT(*) AS n FROM users GROUP BY g HAVING n > 1 AND n < 9";
+    let five = QueryLimits { max_steps: Some(5), ..Default::default() };
+    assert!(matches!(db.query_with_limits(having, &[], &five), Err(SqlError::LimitExceeded(_))));
+    let short = QueryLimits { max_sql_len: Some(20), ..Default::default() };
+    assert!(matches!(
+        db.query_with_limits("SELECT * FROM users WHERE g = 1", &[], &short),
+        Err(SqlError::LimitExceeded(_))
+    ));
+
+    db.set_query_limits(QueryLimits::default());
+    assert_eq!(db.query("SELECT * FROM users").unwrap().collect().len(), 10);
+}
+
+#[test]
+fn query_time_budget_and_cancellation() {
+    use sekejap::{CancelToken, InterruptCause, QueryError, QueryLimits, SqlError};
+    use std::time::Duration;
+    let mut db = CoreDB::new();
+    for i in 0..2_000 {
+        db.put(&format!("u/{i}"), &format!(r#"{{"_collection":"users","g":{}}}"#, i % 7)).unwrap();
+    }
+    let scan = "SELECT * FROM users WHERE g != 3";
+
+    // A spent budget stops the pipeline: run() says so, collect() has no rows to give.
+    let spent = QueryLimits { max_execution_ms: Some(0), ..Default::default() };
+    match db.query_with_limits(scan, &[], &spent).unwrap().run() {
+        Err(QueryError::Interrupted(i)) => {
+            assert_eq!(i.cause, InterruptCause::TimedOut { limit_ms: 0 });
+            assert!(i.partial.is_empty());
+        }
+        other => panic!("expected a timeout, got {other:?}"),
+    }
+    assert!(db.query_with_limits(scan, &[], &spent).unwrap().collect().is_empty());
+    assert!(db.query_with_limits("SELECT COUNT(*) AS n FROM users", &[], &spent).unwrap().run().is_err());
+    // Graph aggregates run inside the query call and fail there.
+    db.link("u/1", "u/2", "knows", 1.0);
+    assert!(matches!(
+        db.query_with_limits("SELECT b.g AS g FROM MATCH (a:users)-[:knows]->(b:users)", &[], &spent),
+        Err(SqlError::LimitExceeded(_))
+    ));
+
+    // A roomy budget changes nothing.
+    let roomy = QueryLimits { max_execution_ms: Some(60_000), ..Default::default() };
+    let full = db.query(scan).unwrap().collect();
+    assert_eq!(db.query_with_limits(scan, &[], &roomy).unwrap().run().unwrap().len(), full.len());
+    assert_eq!(db.collection("users").timeout(Duration::from_secs(60)).run().unwrap().len(), 2_000);
+
+    // A token cancelled from another thread stops the query watching it.
+    let token = CancelToken::new();
+    let query = db.collection("users").cancel_on(&token);
+    std::thread::scope(|s| {
+        s.spawn(|| token.cancel());
+    });
+    assert!(matches!(
+        query.run(),
+        Err(QueryError::Interrupted(i)) if i.cause == InterruptCause::Cancelled
+    ));
+    assert_eq!(db.collection("users").run().unwrap().len(), 2_000, "budgets do not outlive their run");
+}
+
+#[test]
+fn result_limits_refuse_or_cut_oversized_results() {
+    use sekejap::QueryLimits;
+    let mut db = CoreDB::new();
+    for i in 0..10 {
+        db.put(&format!("docs/{i}"), &format!(r#"{{"_collection":"docs","body":"{}"}}"#, "x".repeat(100))).unwrap();
+    }
+    db.set_query_limits(QueryLimits { max_results: Some(4), ..Default::default() });
+    let err = db.all().try_collect().unwrap_err();
+    assert_eq!(err.hits, 10);
+    assert!(err.payload_bytes > 1_000);
+    assert!(err.to_string().contains("paginate"), "{err}");
+    assert_eq!(db.all().collect().len(), 4, "collect() cuts instead of failing");
+    let page = db.query("SELECT * FROM docs LIMIT 4 OFFSET 4").unwrap().try_collect().unwrap();
+    assert_eq!(page.len(), 4);
+    // Aggregates load every payload but return one row.
+    assert_eq!(db.query("SELECT COUNT(*) AS n FROM docs").unwrap().try_collect().unwrap()[0].payload.as_ref().unwrap()["n"], 10);
+
+    db.set_query_limits(QueryLimits { max_payload_bytes: Some(500), ..Default::default() });
+    assert!(db.collection("docs").try_collect().is_err());
+    let fitted = db.collection("docs").collect();
+    assert!(!fitted.is_empty() && fitted.len() < 5);
+    assert_eq!(db.collection("docs").take(2).try_collect().unwrap().len(), 2);
+
+    db.set_query_limits(QueryLimits::default());
+    assert_eq!(db.all().try_collect().unwrap().len(), 10);
+}
+
+#[test]
+fn result_limits_cap_traversal_frontier() {
+    use sekejap::{QueryError, QueryLimits};
+    let mut db = CoreDB::new();
+    // A two-level fan-out: hub → 10 spokes → 10 leaves each.
+    db.put("g/hub", "{}").unwrap();
+    for i in 0..10 {
+        db.put(&format!("g/s{i}"), "{}").unwrap();
+        db.link("g/hub", &format!("g/s{i}"), "to", 1.0);
+        for j in 0..10 {
+            db.put(&format!("g/l{i}_{j}"), "{}").unwrap();
+            db.link(&format!("g/s{i}"), &format!("g/l{i}_{j}"), "to", 1.0);
+        }
+    }
+    assert_eq!(db.one("g/hub").hops(2).collect().len(), 111);
+    // Per-call limits reach traversals inside the query too.
+    let narrow = QueryLimits { max_frontier: Some(20), ..Default::default() };
+    let count = "MATCH (h)-[:to*2]->(l) WHERE h._key = 'g/hub' RETURN COUNT(*) AS n";
+    assert!(db.query_with_limits(count, &[], &narrow).is_err());
+    assert!(db.query(count).is_ok());
+
+    db.set_query_limits(narrow);
+    let err = db.one("g/hub").hops(2).try_collect().unwrap_err();
+    assert!(err.frontier > 20, "{err:?}");
+    assert!(err.to_string().contains("traversal too large"), "{err}");
+    assert!(matches!(db.one("g/hub").hops(2).run(), Err(QueryError::TooLarge(_))));
+    let cut = db.one("g/hub").hops(2).collect();
+    assert!(!cut.is_empty() && cut.len() <= 21, "collect() stops the BFS: {}", cut.len());
+    // Shallow traversals stay within the cap.
+    assert_eq!(db.one("g/hub").hops(1).try_collect().unwrap().len(), 11);
+
+    match db.query("MATCH (h)-[:to*2]->(l) WHERE h._key = 'g/hub' RETURN COUNT(*) AS n") {
+        Err(e) => assert!(e.to_string().contains("traversal too large"), "{e}"),
+        Ok(_) => panic!("MATCH over the frontier cap should fail"),
+    }
+    assert!(db.paths("g/hub", "g/l9_9", None, 3, 1).is_empty());
+    // A capped shortest-path search is an error, not "no path".
+    let shortest = |from: &str, to: &str| {
+        format!("SELECT r.length AS hops FROM MATCH SHORTEST (a)-[r*]->(b) WHERE a._key = '{from}' AND b._key = '{to}'")
+    };
+    match db.query(&shortest("g/hub", "g/l9_9")) {
+        Err(e) => assert!(e.to_string().contains("traversal too large"), "{e}"),
+        Ok(_) => panic!("SHORTEST over the frontier cap should fail"),
+    }
+    assert!(db.query(&shortest("g/l0_0", "g/hub")).unwrap().collect().is_empty());
+
+    db.set_query_limits(QueryLimits::default());
+    assert_eq!(db.paths("g/hub", "g/l9_9", None, 3, 1).len(), 1);
+    let hit = db.query(&shortest("g/hub", "g/l9_9")).unwrap().first().unwrap();
+    assert_eq!(hit.payload.unwrap()["hops"], 2);
+}
+
+// ── paths: simple-path enumeration ───────────────────────────────────────────
+
+#[test]
+fn paths_enumerates_simple_paths_shortest_first() {
+    let mut db = CoreDB::new();
+    for s in ["a/1", "a/2", "a/3", "a/4", "b/9"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("a/1", "a/2", "causes", 1.0);
+    db.link("a/2", "b/9", "causes", 1.0);
+    db.link("a/1", "a/3", "causes", 1.0);
+    db.link("a/3", "a/4", "causes", 1.0);
+    db.link("a/4", "b/9", "causes", 1.0);
+    db.link("a/4", "a/1", "causes", 1.0); // cycle back to the start
+    db.link("a/1", "b/9", "mentions", 1.0);
+
+    let all = db.paths("a/1", "b/9", Some("causes"), 6, 10);
+    assert_eq!(all, vec![
+        vec!["a/1", "a/2", "b/9"],
+        vec!["a/1", "a/3", "a/4", "b/9"],
+    ]);
+
+    // Untyped includes the direct `mentions` hop, which sorts first.
+    assert_eq!(db.paths("a/1", "b/9", None, 6, 10)[0], vec!["a/1", "b/9"]);
+    assert_eq!(db.paths("a/1", "b/9", Some("causes"), 2, 10).len(), 1);
+    assert_eq!(db.paths("a/1", "b/9", Some("causes"), 6, 1).len(), 1);
+    assert!(db.paths("b/9", "a/1", Some("causes"), 6, 10).is_empty());
+    assert!(db.paths("a/1", "missing/1", None, 6, 10).is_empty());
+}
+
+// ── Collection quotas ────────────────────────────────────────────────────────
+
+#[test]
+fn quotas_cap_nodes_and_payload_bytes_per_collection() {
+    use sekejap::{Quota, QuotaExceeded, SqlError};
+    let mut db = CoreDB::new();
+    db.set_quota("logs", Quota { max_nodes: Some(2), ..Default::default() });
+    db.put("l/1", r#"{"_collection":"logs","n":1}"#).unwrap();
+    db.put("l/2", r#"{"_collection":"logs","n":2}"#).unwrap();
+    let err = db.put("l/3", r#"{"_collection":"logs","n":3}"#).unwrap_err();
+    assert_eq!(
+        QuotaExceeded::from_put_error(err).ok(),
+        Some(QuotaExceeded::Nodes { collection: "logs".into(), limit: 2 })
+    );
+    assert!(!db.contains("l/3"));
+    // Updates don't add nodes, other collections are unaffected.
+    db.put("l/1", r#"{"_collection":"logs","n":10}"#).unwrap();
+    db.put("m/1", r#"{"_collection":"metrics"}"#).unwrap();
+    // Non-quota errors pass through untouched.
+    assert!(QuotaExceeded::from_put_error(db.put("x", "not json").unwrap_err()).is_err());
+
+    db.set_quota("docs", Quota { max_payload_bytes: Some(200), ..Default::default() });
+    let big = "x".repeat(150);
+    db.put("d/1", &format!(r#"{{"_collection":"docs","body":"{big}"}}"#)).unwrap();
+    let err = db.put("d/2", &format!(r#"{{"_collection":"docs","body":"{big}"}}"#)).unwrap_err();
+    assert!(matches!(QuotaExceeded::from_put_error(err), Ok(QuotaExceeded::PayloadBytes { .. })));
+    assert_eq!(db.collection_usage("docs").0, 1);
+
+    // SQL writes surface the typed error.
+    let err = db.execute("INSERT INTO logs (_key, n) VALUES ('9', 9)").unwrap_err();
+    assert!(matches!(err, SqlError::QuotaExceeded(QuotaExceeded::Nodes { .. })));
+
+    db.set_quota("logs", Quota::default());
+    assert!(db.quota("logs").is_none());
+    db.put("l/3", r#"{"_collection":"logs","n":3}"#).unwrap();
+}
+
+#[test]
+fn quotas_hold_across_transactions_and_atomic_batches() {
+    use sekejap::{Quota, QuotaExceeded};
+    let mut db = CoreDB::new();
+    db.set_quota("logs", Quota { max_nodes: Some(2), ..Default::default() });
+    db.put("l/1", r#"{"_collection":"logs"}"#).unwrap();
+
+    let mut txn = db.begin();
+    for i in 2..=5 {
+        txn.put(&format!("l/{i}"), r#"{"_collection":"logs"}"#).unwrap();
+    }
+    let err = txn.commit().unwrap_err();
+    assert_eq!(
+        QuotaExceeded::from_put_error(err).ok(),
+        Some(QuotaExceeded::Nodes { collection: "logs".into(), limit: 2 })
+    );
+    assert_eq!(db.collection_usage("logs").0, 1, "nothing applied");
+
+    // Removals in the same batch make room.
+    let mut txn = db.begin();
+    txn.remove("l/1");
+    txn.put("l/2", r#"{"_collection":"logs"}"#).unwrap();
+    txn.put("l/3", r#"{"_collection":"logs"}"#).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(db.collection_usage("logs").0, 2);
+
+    db.set_quota("docs", Quota { max_payload_bytes: Some(100), ..Default::default() });
+    let body = "x".repeat(60);
+    let results = db.mutate_json(&format!(r#"{{"atomic":true,"mutations":[
+        {{"op":"put","slug":"d/1","payload":"{{\"_collection\":\"docs\",\"b\":\"{body}\"}}"}},
+        {{"op":"put","slug":"d/2","payload":"{{\"_collection\":\"docs\",\"b\":\"{body}\"}}"}}
+    ]}}"#)).unwrap();
+    assert!(results.iter().all(|r| r.as_ref().is_err_and(|e| e.contains("quota exceeded"))), "{results:?}");
+    assert_eq!(db.collection_usage("docs").0, 0);
+}
+
+// ── Node TTL ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn expired_nodes_are_filtered_and_evicted() {
+    use std::time::Duration;
+    let mut db = CoreDB::new();
+    db.execute("CREATE INDEX ON events USING btree (kind)").unwrap();
+    db.put_with_ttl("ev/fresh", r#"{"_collection":"events","kind":"click"}"#, Duration::from_secs(3600)).unwrap();
+    db.put("ev/stale", r#"{"_collection":"events","kind":"click","_expires_unix":1000}"#).unwrap();
+    db.put("ev/keep", r#"{"_collection":"events","kind":"view"}"#).unwrap();
+    db.link("ev/fresh", "ev/stale", "next", 1.0);
+
+    // Expired nodes are still stored until evicted, but can be filtered out.
+    assert_eq!(db.collection("events").count(), 3);
+    let mut live: Vec<String> = db.collection("events").unexpired()
+        .collect().into_iter().map(|h| h.slug).collect();
+    live.sort();
+    assert_eq!(live, vec!["ev/fresh", "ev/keep"]);
+
+    assert_eq!(db.evict_expired(), 1);
+    assert!(!db.contains("ev/stale"));
+    assert_eq!(db.one("ev/fresh").forward("next").count(), 0);
+    let clicks = db.query("SELECT * FROM events WHERE kind = 'click'").unwrap().collect();
+    assert_eq!(clicks.len(), 1);
+    assert_eq!(db.evict_expired(), 0);
+
+    assert!(db.put_with_ttl("ev/bad", "[1]", Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn manual_clock_drives_stamps_and_expiry() {
+    use sekejap::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+    let clock = Arc::new(ManualClock::new(1_000_000));
+    let mut db = CoreDB::new();
+    db.set_clock(clock.clone());
+
+    db.put("ev/a", r#"{"_collection":"events"}"#).unwrap();
+    db.put_with_ttl("ev/b", r#"{"_collection":"events"}"#, Duration::from_secs(10)).unwrap();
+    db.link("ev/a", "ev/b", "next", 1.0);
+    let a: serde_json::Value = serde_json::from_str(&db.get("ev/a").unwrap()).unwrap();
+    assert_eq!((a["_created_unix"].as_i64(), a["_updated_unix"].as_i64()), (Some(1_000_000), Some(1_000_000)));
+    assert_eq!(db.edges_from("ev/a")[0].created_unix, 1_000_000);
+
+    clock.advance(Duration::from_secs(5));
+    db.put("ev/a", r#"{"_collection":"events","v":2}"#).unwrap();
+    let a: serde_json::Value = serde_json::from_str(&db.get("ev/a").unwrap()).unwrap();
+    assert_eq!((a["_created_unix"].as_i64(), a["_updated_unix"].as_i64()), (Some(1_000_000), Some(1_005_000)));
+    assert_eq!(db.collection("events").unexpired().count(), 2);
+    assert_eq!(db.evict_expired(), 0);
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(db.collection("events").unexpired().count(), 1);
+    assert_eq!(db.evict_expired(), 1);
+}
+
+// ── Unique constraints ───────────────────────────────────────────────────────
+
+#[test]
+fn unique_fields_reject_duplicate_values_in_collection() {
+    use sekejap::{SqlError, UniqueViolation};
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE users (_key TEXT PRIMARY KEY, email TEXT) WITH (unique: ['email'])").unwrap();
+    db.execute("INSERT INTO users (_key, email) VALUES ('ann', 'a@x.io'), ('bob', 'b@x.io')").unwrap();
+
+    let err = db.put("users/cy", r#"{"_collection":"users","_key":"cy","email":"a@x.io"}"#).unwrap_err();
+    let v = UniqueViolation::from_put_error(err).unwrap();
+    assert_eq!((v.field.as_str(), v.existing.as_str()), ("email", "users/ann"));
+    assert!(!db.contains("users/cy"));
+
+    let err = db.execute("INSERT INTO users (_key, email) VALUES ('cy', 'b@x.io')").unwrap_err();
+    assert!(matches!(err, SqlError::UniqueViolation(_)));
+    let err = db.execute("UPDATE users SET email = 'a@x.io' WHERE _key = 'bob'").unwrap_err();
+    assert!(matches!(err, SqlError::UniqueViolation(_)));
+
+    // Rewriting a node with its own value, missing values, and other
+    // collections are all fine.
+    db.execute("UPDATE users SET email = 'a@x.io' WHERE _key = 'ann'").unwrap();
+    db.put("users/dee", r#"{"_collection":"users","_key":"dee"}"#).unwrap();
+    db.put("users/eve", r#"{"_collection":"users","_key":"eve"}"#).unwrap();
+    db.put("staff/ann", r#"{"_collection":"staff","email":"a@x.io"}"#).unwrap();
+
+    // A freed value can be taken again.
+    db.remove("users/ann");
+    db.put("users/cy", r#"{"_collection":"users","_key":"cy","email":"a@x.io"}"#).unwrap();
+}
+
+#[test]
+fn unique_fields_hold_across_transactions_and_atomic_batches() {
+    use sekejap::UniqueViolation;
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE users (_key TEXT PRIMARY KEY, email TEXT) WITH (unique: ['email'])").unwrap();
+    db.put("users/ann", r#"{"_collection":"users","email":"a@x.io"}"#).unwrap();
+
+    // Against a stored node: nothing in the batch is applied.
+    let mut txn = db.begin();
+    txn.put("users/zed", r#"{"_collection":"users","email":"z@x.io"}"#).unwrap();
+    txn.put("users/bob", r#"{"_collection":"users","email":"a@x.io"}"#).unwrap();
+    let v = UniqueViolation::from_put_error(txn.commit().unwrap_err()).unwrap();
+    assert_eq!(v.existing, "users/ann");
+    assert!(!db.contains("users/zed"));
+
+    // Against another put of the same batch.
+    let mut txn = db.begin();
+    txn.put("users/bob", r#"{"_collection":"users","email":"b@x.io"}"#).unwrap();
+    txn.put("users/cy", r#"{"_collection":"users","email":"b@x.io"}"#).unwrap();
+    let v = UniqueViolation::from_put_error(txn.commit().unwrap_err()).unwrap();
+    assert_eq!(v.existing, "users/bob");
+
+    // Only what the batch leaves behind counts: a value freed or moved
+    // earlier in the batch can be taken.
+    let mut txn = db.begin();
+    txn.put("users/cy", r#"{"_collection":"users","email":"c@x.io"}"#).unwrap();
+    txn.put("users/cy", r#"{"_collection":"users","email":"a@x.io"}"#).unwrap();
+    txn.put("users/ann", r#"{"_collection":"users","email":"new@x.io"}"#).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(db.query("SELECT * FROM users WHERE email = 'a@x.io'").unwrap().count(), 1);
+
+    // Atomic mutation batches go through the same check.
+    let results = db.mutate_json(r#"{"atomic":true,"mutations":[
+        {"op":"put","slug":"users/dee","payload":"{\"_collection\":\"users\",\"email\":\"d@x.io\"}"},
+        {"op":"put","slug":"users/eve","payload":"{\"_collection\":\"users\",\"email\":\"a@x.io\"}"}
+    ]}"#).unwrap();
+    assert!(results.iter().all(|r| r.as_ref().is_err_and(|e| e.contains("unique constraint"))), "{results:?}");
+    assert!(!db.contains("users/dee"));
+    assert_eq!(db.collection("users").count(), 2);
+}
+
+// ── Edge creation-time windows ───────────────────────────────────────────────
+
+#[test]
+fn forward_since_restricts_traversal_to_edge_window() {
+    let mut db = CoreDB::new();
+    for s in ["svc/api", "svc/db", "svc/cache", "svc/queue"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("svc/api", "svc/db", "calls", 1.0);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let incident = chrono::Utc::now().timestamp_millis();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    db.link("svc/api", "svc/cache", "calls", 1.0);
+    db.link("svc/queue", "svc/cache", "calls", 1.0);
+
+    let since: Vec<String> = db.one("svc/api")
+        .forward_since("calls", incident, i64::MAX)
+        .collect().into_iter().map(|h| h.slug).collect();
+    assert_eq!(since, vec!["svc/cache"]);
+    assert_eq!(db.one("svc/api").forward_since("calls", 0, incident).count(), 1);
+    assert_eq!(db.one("svc/cache").backward_since("calls", incident, i64::MAX).count(), 2);
+    assert_eq!(db.one("svc/db").backward_since("calls", incident, i64::MAX).count(), 0);
+
+    // Edge hits from the window carry the timestamp.
+    let pairs = db.one("svc/api").forward_since("calls", incident, i64::MAX).edge_collect();
+    assert_eq!(pairs.len(), 1);
+    assert!(pairs[0].1.created_unix >= incident);
+}
+
+#[test]
+fn edge_collect_where_filters_on_edge_fields() {
+    let mut db = CoreDB::new();
+    for slug in ["acct/1", "acct/2", "acct/3", "acct/4"] {
+        db.put(slug, "{}").unwrap();
+    }
+    let now = 1_700_000_000_000_i64;
+    let hour = 3_600_000;
+    let feed = format!(
+        "{{\"op\":\"link\",\"from\":\"acct/1\",\"to\":\"acct/2\",\"edge_type\":\"pays\",\"strength\":0.9,\"created_unix\":{old}}}\n\
+         {{\"op\":\"link\",\"from\":\"acct/1\",\"to\":\"acct/2\",\"edge_type\":\"pays\",\"strength\":0.1,\"created_unix\":{recent}}}\n\
+         {{\"op\":\"link\",\"from\":\"acct/1\",\"to\":\"acct/3\",\"edge_type\":\"pays\",\"strength\":0.8,\"created_unix\":{recent}}}\n\
+         {{\"op\":\"link\",\"from\":\"acct/1\",\"to\":\"acct/4\",\"edge_type\":\"pays\",\"strength\":0.7,\"created_unix\":{old}}}\n",
+        old = now - 2 * hour,
+        recent = now - hour / 2,
+    );
+    assert_eq!(db.mutate_ndjson(feed.as_bytes()).unwrap().failed, 0);
+
+    // Edges created in the last hour: acct/2 matches through its second edge.
+    let mut recent: Vec<(String, f32)> = db.one("acct/1").forward("pays")
+        .edge_collect_where(|e| e.created_unix >= now - hour)
+        .into_iter().map(|(hit, e)| (hit.slug, e.strength)).collect();
+    recent.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(recent, vec![("acct/2".to_string(), 0.1), ("acct/3".to_string(), 0.8)]);
+
+    let heavy_recent = db.one("acct/1").forward("pays")
+        .edge_collect_where(|e| e.created_unix >= now - hour && e.strength > 0.5);
+    assert_eq!(heavy_recent.len(), 1);
+    assert_eq!(heavy_recent[0].0.slug, "acct/3");
+
+    assert_eq!(db.one("acct/1").forward("pays").edge_collect_where(|_| true).len(), 3);
+    assert!(db.one("acct/1").forward("pays")
+        .edge_collect_where(|e| e.edge_type.as_deref() == Some("owns")).is_empty());
+}
+
+// ── Authorization hook ───────────────────────────────────────────────────────
+
+#[test]
+fn authorizer_sees_access_kind_and_collections() {
+    use sekejap::{Access, SqlError};
+    use std::cell::RefCell;
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE users (_key TEXT PRIMARY KEY, name TEXT)").unwrap();
+    db.execute("INSERT INTO users (_key, name) VALUES ('alice', 'Alice')").unwrap();
+    db.put("orders/1", r#"{"_collection":"orders"}"#).unwrap();
+    db.link("orders/1", "users/alice", "placed_by", 1.0);
+
+    let seen: RefCell<Vec<(Access, Option<Vec<String>>)>> = RefCell::new(Vec::new());
+    let record = |access: Access, cols: Option<&[String]>| {
+        seen.borrow_mut().push((access, cols.map(|c| c.to_vec())));
+        Ok(())
+    };
+    db.query_as(&record, "SELECT * FROM users WHERE name = $1", &[serde_json::json!("Alice")]).unwrap();
+    db.query_as(&record, "SELECT * FROM orders", &[]).unwrap();
+    db.execute_as(&record, "UPDATE users SET name = 'A' WHERE name = 'Alice'", &[]).unwrap();
+    db.execute_as(&record, "CREATE INDEX ON orders USING btree (status)", &[]).unwrap();
+    assert_eq!(*seen.borrow(), vec![
+        (Access::Read, Some(vec!["users".to_string()])),
+        (Access::Read, Some(vec!["orders".to_string()])),
+        (Access::Write, Some(vec!["users".to_string()])),
+        (Access::Schema, Some(vec!["orders".to_string()])),
+    ]);
+
+    // A collection-scoped token: graph traversal is unscoped, so it is refused.
+    let users_only = |_: Access, cols: Option<&[String]>| match cols {
+        Some(c) if c.iter().all(|n| n == "users") => Ok(()),
+        _ => Err("scoped to users".to_string()),
+    };
+    assert_eq!(db.query_as(&users_only, "SELECT * FROM users", &[]).unwrap().count(), 1);
+    assert!(matches!(
+        db.query_as(&users_only, "SELECT * FROM orders", &[]),
+        Err(SqlError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        db.query_as(&users_only, "MATCH (o:orders)-[:placed_by]->(u:users) RETURN u", &[]),
+        Err(SqlError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        db.execute_as(&users_only, "DELETE FROM orders WHERE status = 'x'", &[]),
+        Err(SqlError::Unauthorized(_))
+    ));
+    assert!(db.contains("orders/1"));
+}
+
+#[test]
+fn authorizer_checks_mutation_documents() {
+    use sekejap::Access;
+    let mut db = CoreDB::new();
+    db.put("users/1", r#"{"_collection":"users"}"#).unwrap();
+    db.put("orders/1", r#"{"_collection":"orders"}"#).unwrap();
+    let users_writer = |access: Access, cols: Option<&[String]>| match (access, cols) {
+        (Access::Write, Some(c)) if c.iter().all(|n| n == "users") => Ok(()),
+        _ => Err("writes to users only".to_string()),
+    };
+    let feed = concat!(
+        r#"{"op":"put","slug":"users/2","payload":"{\"_collection\":\"users\"}"}"#, "\n",
+        r#"{"op":"put","slug":"users/1","payload":"{\"_collection\":\"orders\"}"}"#, "\n",
+        r#"{"op":"remove","slug":"orders/1"}"#, "\n",
+        r#"{"op":"link","from":"users/1","to":"orders/1","edge_type":"x","strength":1.0}"#, "\n",
+        r#"{"op":"drop_table","collection":"users"}"#, "\n",
+        r#"{"op":"txn_begin"}"#, "\n",
+        r#"{"op":"put","slug":"users/3","payload":"{\"_collection\":\"users\"}"}"#, "\n",
+        r#"{"op":"put","slug":"x","payload":"{}"}"#, "\n",
+        r#"{"op":"txn_end"}"#, "\n",
+    );
+    let summary = db.mutate_ndjson_as(&users_writer, feed.as_bytes()).unwrap();
+    assert_eq!((summary.applied, summary.failed), (1, 6), "{:?}", summary.errors);
+    assert!(db.contains("users/2") && !db.contains("users/3") && !db.contains("x"));
+    assert!(db.contains("orders/1"));
+    assert_eq!(db.collection("users").count(), 2, "users/1 was not moved out of users");
+}
+
+// ── NDJSON export ────────────────────────────────────────────────────────────
+
+#[test]
+fn export_ndjson_round_trips_through_mutate_ndjson() {
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE users (_key TEXT PRIMARY KEY, name TEXT, age INTEGER)").unwrap();
+    db.execute("CREATE INDEX ON users USING btree (age)").unwrap();
+    db.execute("INSERT INTO users (_key, name, age) VALUES ('bob', 'Bob', 41), ('ann', 'Ann', 29)").unwrap();
+    db.put_vector("users/ann", "embedding", &[1.0, 0.0]).unwrap();
+    db.put_vector("users/bob", "embedding", &[0.0, 1.0]).unwrap();
+    db.build_hnsw_index("embedding", 8, 50).unwrap();
+    db.link_meta("users/ann", "users/bob", "knows", 0.5, r#"{"since":2019}"#).unwrap();
+    db.link("users/bob", "users/ann", "knows", 1.0);
+    db.set_collection_filter("users", Some("age > 18")).unwrap();
+
+    let mut out = Vec::new();
+    let records = db.export_ndjson(&mut out).unwrap();
+    // One line per record plus the manifest.
+    assert_eq!(records + 1, out.iter().filter(|&&b| b == b'\n').count());
+
+    let mut copy = CoreDB::new();
+    let summary = copy.import_ndjson(out.as_slice()).unwrap();
+    assert_eq!(summary.failed, 0, "{:?}", summary.errors);
+
+    // Imports are ordinary writes, so only `_updated_unix` is re-stamped.
+    let payload = |d: &CoreDB, slug: &str| {
+        let mut v: serde_json::Value = serde_json::from_str(&d.get(slug).unwrap()).unwrap();
+        v.as_object_mut().unwrap().remove("_updated_unix");
+        v
+    };
+    assert_eq!(payload(&copy, "users/ann"), payload(&db, "users/ann"));
+    assert_eq!(payload(&copy, "users/bob"), payload(&db, "users/bob"));
+    assert_eq!(copy.get_vector("users/ann", "embedding"), Some(&[1.0, 0.0][..]));
+    assert_eq!(copy.collection_filter("users"), Some("age > 18"));
+    let plan = copy.explain("SELECT * FROM users WHERE age > 30").unwrap();
+    assert!(plan.iter().any(|h| h.payload.as_ref().and_then(|p| p.get("index")).is_some()));
+
+    // Vectors come back with the HNSW index built over them.
+    assert_eq!(copy.hnsw_fields(), ["embedding"]);
+    let near = copy.all().vector_near("embedding", vec![0.1, 1.0], 1).collect();
+    assert_eq!(near[0].slug, "users/bob");
+
+    let (orig, copied) = (db.edges_from("users/ann"), copy.edges_from("users/ann"));
+    assert_eq!(copied.len(), 1);
+    assert_eq!(copied[0].meta, orig[0].meta);
+    assert_eq!(copied[0].created_unix, orig[0].created_unix);
+
+    // Exporting unchanged data again yields the same bytes.
+    let mut again = Vec::new();
+    db.export_ndjson(&mut again).unwrap();
+    assert_eq!(again, out);
+}
+
+#[test]
+fn import_ndjson_rejects_truncated_or_corrupt_exports() {
+    use std::io::ErrorKind;
+    let mut db = CoreDB::new();
+    for i in 0..5 {
+        db.put(&format!("users/{i}"), &format!(r#"{{"_collection":"users","n":{i}}}"#)).unwrap();
+        db.put(&format!("posts/{i}"), &format!(r#"{{"_collection":"posts","n":{i}}}"#)).unwrap();
+    }
+    db.link("users/0", "posts/0", "wrote", 1.0);
+    let mut out = Vec::new();
+    db.export_ndjson(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    // Cut off before the manifest.
+    let truncated = lines[..lines.len() - 3].join("\n");
+    let mut target = CoreDB::new();
+    let err = target.import_ndjson(truncated.as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("manifest"), "{err}");
+
+    // A record dropped from the middle.
+    let mut dropped = lines.clone();
+    dropped.remove(2);
+    let err = target.import_ndjson(dropped.join("\n").as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("records"), "{err}");
+
+    // A flipped value inside one collection's rows.
+    let corrupt = text.replacen(r#"\"n\":3"#, r#"\"n\":9"#, 1);
+    assert_ne!(corrupt, text);
+    let err = target.import_ndjson(corrupt.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("collection 'posts'"), "{err}");
+
+    // Nothing was imported by any failed attempt.
+    assert_eq!(target.node_count(), 0);
+    let summary = target.import_ndjson(text.as_bytes()).unwrap();
+    assert_eq!(summary.failed, 0);
+    assert_eq!(target.node_count(), 10);
+}
+
+#[test]
+fn replication_ships_bootstrap_and_live_writes_to_a_follower() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let follower = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut db = CoreDB::new();
+        let summary = db.catch_up(std::io::BufReader::new(stream)).unwrap();
+        assert_eq!(summary.failed, 0, "{:?}", summary.errors);
+        db
+    });
+
+    let mut leader = CoreDB::new();
+    leader.put("p/1", r#"{"_collection":"people","name":"Ani"}"#).unwrap();
+    leader.put("p/2", r#"{"_collection":"people","name":"Budi"}"#).unwrap();
+    leader.replicate_to(std::net::TcpStream::connect(addr).unwrap()).unwrap();
+    assert!(leader.is_replicating());
+
+    leader.link("p/1", "p/2", "knows", 1.0);
+    leader.execute("BEGIN").unwrap();
+    leader.execute("INSERT INTO people (_key, name) VALUES ('3', 'Citra')").unwrap();
+    leader.execute("COMMIT").unwrap();
+    leader.remove("p/2");
+    leader.stop_replication().unwrap();
+    assert!(!leader.is_replicating());
+
+    let follower = follower.join().unwrap();
+    let names = |db: &CoreDB| {
+        let mut v: Vec<String> = db.all().collect().into_iter().map(|h| h.slug).collect();
+        v.sort();
+        v
+    };
+    assert_eq!(names(&follower), names(&leader));
+    assert_eq!(follower.edge_count(), leader.edge_count());
+
+    let mut busy = CoreDB::new();
+    busy.put("x", "{}").unwrap();
+    assert!(busy.catch_up(std::io::empty()).is_err());
+}
+
+#[test]
+fn replication_failure_stops_shipping_but_not_writes() {
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.starts_with(br#"{"op":"put","slug":"late""#) {
+                return Err(std::io::Error::other("link down"));
+            }
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut db = CoreDB::new();
+    db.replicate_to(Broken).unwrap();
+    db.put("late", "{}").unwrap();
+    assert!(db.contains("late"));
+    assert_eq!(db.stop_replication().unwrap_err().to_string(), "link down");
+    assert!(!db.is_replicating());
+    assert!(db.stop_replication().is_ok());
+}
+
+#[test]
+fn stalled_follower_does_not_block_writes() {
+    /// Accepts the bootstrap, then never returns from a write.
+    struct Stalled;
+    impl std::io::Write for Stalled {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.starts_with(br#"{"op":"put","slug":"w/"#) {
+                loop {
+                    std::thread::park();
+                }
+            }
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut db = CoreDB::new();
+    db.replicate_to(Stalled).unwrap();
+    for i in 0..2000 {
+        db.put(&format!("w/{i}"), "{}").unwrap();
+    }
+    assert_eq!(db.node_count(), 2000);
+    assert!(!db.is_replicating());
+    let err = db.stop_replication().unwrap_err();
+    assert!(err.to_string().contains("behind"), "{err}");
+}
+
+#[test]
+fn replicated_puts_keep_the_leaders_timestamps() {
+    use sekejap::ManualClock;
+    use std::sync::{Arc, Mutex};
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut leader = CoreDB::new();
+    leader.set_clock(Arc::new(ManualClock::new(1_000)));
+    leader.put("a", r#"{"v":1}"#).unwrap();
+    let stream = Shared::default();
+    leader.replicate_to(stream.clone()).unwrap();
+    leader.put("b", r#"{"v":2}"#).unwrap();
+    leader.stop_replication().unwrap();
+
+    let mut follower = CoreDB::new();
+    let shipped = stream.0.lock().unwrap().clone();
+    follower.catch_up(shipped.as_slice()).unwrap();
+    for slug in ["a", "b"] {
+        let stamps = |db: &CoreDB| {
+            let p: serde_json::Value = serde_json::from_str(&db.get(slug).unwrap()).unwrap();
+            (p["_created_unix"].clone(), p["_updated_unix"].clone())
+        };
+        assert_eq!(stamps(&follower), stamps(&leader), "{slug}");
+        assert_eq!(stamps(&follower).1, 1_000);
+    }
+}
+
+// ── Visualization export ─────────────────────────────────────────────────────
+
+#[test]
+fn to_viz_json_emits_nodes_and_links_within_the_set() {
+    let mut db = CoreDB::new();
+    db.put("svc/api", r#"{"_collection":"services","name":"API","tier":1}"#).unwrap();
+    db.put("svc/db", r#"{"_collection":"services","title":"Postgres"}"#).unwrap();
+    db.put("host/1", r#"{"zone":"a"}"#).unwrap();
+    db.link("svc/api", "svc/db", "calls", 0.8);
+    db.link("svc/api", "host/1", "runs_on", 1.0);
+
+    let viz = db.collection("services").to_viz_json(&["tier"]);
+    let mut nodes = viz["nodes"].as_array().unwrap().clone();
+    nodes.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    assert_eq!(nodes, vec![
+        serde_json::json!({"id":"svc/api","label":"API","group":"services","tier":1}),
+        serde_json::json!({"id":"svc/db","label":"Postgres","group":"services"}),
+    ]);
+    // host/1 is outside the set, so only the calls edge is a link.
+    assert_eq!(viz["links"], serde_json::json!([
+        {"source":"svc/api","target":"svc/db","value":0.8f32,"type":"calls"}
+    ]));
+
+    let viz = db.one("svc/api").forward("runs_on").to_viz_json(&[]);
+    assert_eq!(viz["nodes"], serde_json::json!([{"id":"host/1","label":"host/1","group":null}]));
+    assert_eq!(viz["links"], serde_json::json!([]));
+}
+
+// ── Spatial grid maintenance ─────────────────────────────────────────────────
+
+#[test]
+fn spatial_grid_follows_upserts_updates_and_column_changes() {
+    let point = |key: &str, lon: f64, lat: f64| format!(
+        r#"{{"_collection":"places","_key":"{key}","geometry":{{"type":"Point","coordinates":[{lon},{lat}]}}}}"#
+    );
+    let mut db = CoreDB::new();
+    db.execute("CREATE TABLE places (_key TEXT PRIMARY KEY, geometry GEO)").unwrap();
+    db.put("places/a", &point("a", 144.9631, -37.8102)).unwrap();
+    db.put("places/b", &point("b", 144.3617, -38.1499)).unwrap();
+    db.build_spatial_index();
+    assert!(db.spatial_stats().is_consistent());
+
+    // Upsert moves a far away: the old position no longer matches.
+    db.put("places/a", &point("a", 115.2625, -8.5069)).unwrap();
+    assert_eq!(db.all().near(-37.8102, 144.9631, 2.0).count(), 0);
+    assert_eq!(db.all().near(-8.5069, 115.2625, 2.0).count(), 1);
+
+    // SQL UPDATE of the geometry takes the per-row path.
+    db.execute_params(
+        "UPDATE places SET geometry = $1 WHERE _key = 'b'",
+        &[serde_json::json!({"type":"Point","coordinates":[144.9631, -37.8102]})],
+    ).unwrap();
+    assert_eq!(db.all().near(-38.1499, 144.3617, 2.0).count(), 0);
+    assert_eq!(db.all().near(-37.8102, 144.9631, 2.0).count(), 1);
+
+    db.remove("places/a");
+    let stats = db.spatial_stats();
+    assert_eq!((stats.indexed, stats.nodes_with_geometry), (1, 1));
+    assert!(stats.is_consistent(), "{stats:?}");
+
+    // Renaming or dropping the geometry column takes nodes out of the grid.
+    db.execute("ALTER TABLE places RENAME COLUMN geometry TO geom").unwrap();
+    let stats = db.spatial_stats();
+    assert_eq!((stats.indexed, stats.nodes_with_geometry), (0, 0));
+    assert!(stats.is_consistent(), "{stats:?}");
+    db.execute("ALTER TABLE places RENAME COLUMN geom TO geometry").unwrap();
+    assert_eq!(db.all().near(-37.8102, 144.9631, 2.0).count(), 1);
+    db.execute("ALTER TABLE places DROP COLUMN geometry").unwrap();
+    assert_eq!(db.all().near(-37.8102, 144.9631, 2.0).count(), 0);
+    assert!(db.spatial_stats().is_consistent());
+}
+
+#[test]
+fn spatial_index_keeps_points_at_zero_zero() {
+    // (0, 0) is a real place, not a "no coordinates" sentinel.
+    let mut db = CoreDB::new();
+    db.put("p/null_island", r#"{"_collection":"places","geometry":{"type":"Point","coordinates":[0.0,0.0]}}"#).unwrap();
+    db.put("p/gulf", r#"{"_collection":"places","geometry":{"type":"Point","coordinates":[0.5,-0.5]}}"#).unwrap();
+    db.put("p/none", r#"{"_collection":"places"}"#).unwrap();
+    db.build_spatial_index();
+    let stats = db.spatial_stats();
+    assert_eq!((stats.indexed, stats.nodes_with_geometry), (2, 2));
+    let hits = db.all().near(0.0, 0.0, 1.0).collect();
+    assert_eq!(hits.iter().map(|h| h.slug.as_str()).collect::<Vec<_>>(), ["p/null_island"]);
+    assert_eq!(db.all().near(0.0, 0.0, 100.0).count(), 2);
+}
+
+#[test]
+fn near_node_centres_on_the_anchor_and_excludes_it() {
+    let mut db = CoreDB::new();
+    let point = |coll: &str, lon: f64, lat: f64| {
+        format!(r#"{{"_collection":"{coll}","geometry":{{"type":"Point","coordinates":[{lon},{lat}]}}}}"#)
+    };
+    db.put("cafes/kopi", &point("cafes", 106.80, -6.20)).unwrap();
+    db.put("cafes/teh", &point("cafes", 106.81, -6.20)).unwrap();
+    db.put("shops/buku", &point("shops", 106.80, -6.21)).unwrap();
+    db.put("cafes/far", &point("cafes", 107.50, -6.90)).unwrap();
+    db.put("cafes/nowhere", r#"{"_collection":"cafes"}"#).unwrap();
+
+    let slugs = |set: sekejap::Set| {
+        let mut s: Vec<String> = set.collect().into_iter().map(|h| h.slug).collect();
+        s.sort();
+        s
+    };
+    // Without a grid the scan falls back to payload centroids.
+    assert_eq!(slugs(db.near_node("cafes/kopi", 2.0)), ["cafes/teh", "shops/buku"]);
+    db.build_spatial_index();
+    assert_eq!(slugs(db.near_node("cafes/kopi", 2.0)), ["cafes/teh", "shops/buku"]);
+    assert_eq!(slugs(db.collection("cafes").near_node("cafes/kopi", 2.0)), ["cafes/teh"]);
+    assert_eq!(db.near_node("cafes/missing", 2.0).count(), 0);
+    assert_eq!(db.near_node("cafes/nowhere", 2.0).count(), 0);
+}
+
+// ── Bulk edge ingestion ──────────────────────────────────────────────────────
+
+#[test]
+fn link_many_ingests_edges_with_optional_metadata() {
+    use sekejap::EdgeInsert;
+    let edge = |to: &str, meta: Option<&str>| EdgeInsert {
+        from: "hub".into(),
+        to: to.into(),
+        edge_type: "feeds".into(),
+        strength: 0.5,
+        props_json: meta.map(str::to_string),
+    };
+    let mut db = CoreDB::new();
+    let n = db.link_many(vec![edge("a", None), edge("b", Some(r#"{"lag":3}"#)), edge("c", None)]).unwrap();
+    assert_eq!(n, 3);
+    let mut hits = db.edges_from("hub");
+    hits.sort_by(|x, y| x.to_slug.cmp(&y.to_slug));
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[1].meta, Some(serde_json::json!({"lag":3})));
+    assert!(hits.iter().all(|h| h.created_unix == hits[0].created_unix && h.created_unix > 0));
+
+    // Invalid metadata stops the batch; earlier edges stay.
+    assert!(db.link_many(vec![edge("d", None), edge("e", Some("{bad")), edge("f", None)]).is_err());
+    assert_eq!(db.edges_from("hub").len(), 4);
+}
+
+// ── Edge upserts ─────────────────────────────────────────────────────────────
+
+#[test]
+fn relinking_replaces_edge_unless_parallel_edges_allowed() {
+    let mut db = CoreDB::new();
+    db.link("a", "b", "knows", 0.5);
+    let created = db.edges_from("a")[0].created_unix;
+    db.link_meta("a", "b", "knows", 0.9, r#"{"since":2020}"#).unwrap();
+    db.link("a", "b", "likes", 0.1);
+
+    let mut hits = db.edges_from("a");
+    hits.sort_by(|x, y| x.edge_type.cmp(&y.edge_type));
+    assert_eq!(hits.len(), 2);
+    assert_eq!((hits[0].strength, hits[0].created_unix), (0.9, created));
+    assert_eq!(hits[0].meta, Some(serde_json::json!({"since":2020})));
+    assert_eq!(db.edges_to("b").len(), 2);
+
+    // Transactions and bulk ingestion upsert the same way.
+    let mut txn = db.begin();
+    txn.link("a", "b", "knows", 0.3);
+    txn.commit().unwrap();
+    db.link_many((0..3).map(|_| sekejap::EdgeInsert {
+        from: "a".into(),
+        to: "b".into(),
+        edge_type: "likes".into(),
+        strength: 0.7,
+        props_json: None,
+    }))
+    .unwrap();
+    let hits = db.edges_from("a");
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().any(|h| h.strength == 0.3) && hits.iter().any(|h| h.strength == 0.7));
+
+    db.set_allow_parallel_edges(true);
+    db.link("a", "b", "knows", 0.2);
+    assert_eq!(db.edges_from("a").len(), 3);
+}
+
+// ── Pipeline mutations ───────────────────────────────────────────────────────
+
+#[test]
+fn mutate_where_updates_or_deletes_every_matching_node() {
+    use sekejap::SetMutation;
+    let mut db = CoreDB::new();
+    db.execute("CREATE INDEX ON orders USING btree (status)").unwrap();
+    for i in 0..6 {
+        let status = if i % 2 == 0 { "shipped" } else { "open" };
+        db.put(&format!("o/{i}"), &format!(r#"{{"_collection":"orders","status":"{status}","n":{i}}}"#))
+            .unwrap();
+    }
+
+    let op: SetMutation =
+        serde_json::from_str(r#"{"op":"set_field","field":"status","value":"archived"}"#).unwrap();
+    let n = db.mutate_where(|db| db.collection("orders").where_eq("status", "shipped"), op).unwrap();
+    assert_eq!(n, 3);
+    assert_eq!(db.collection("orders").where_eq("status", "archived").count(), 3);
+    assert_eq!(db.collection("orders").where_eq("status", "shipped").count(), 0);
+
+    let op: SetMutation = serde_json::from_str(r#"{"op":"delete"}"#).unwrap();
+    let n = db.mutate_where(|db| db.collection("orders").where_gt("n", 3.0), op).unwrap();
+    assert_eq!(n, 2);
+    assert!(db.get("o/4").is_none() && db.get("o/5").is_none());
+    assert_eq!(db.collection("orders").count(), 4);
+    assert_eq!(db.mutate_where(|db| db.collection("missing"), SetMutation::Delete).unwrap(), 0);
+}
+
+// ── Hit serialisation ────────────────────────────────────────────────────────
+
+#[test]
+fn hit_to_json_follows_options() {
+    use sekejap::HitJsonOptions;
+    let mut db = CoreDB::new();
+    db.put("docs/a", r#"{"_collection":"docs","meta":{"score":0.5}}"#).unwrap();
+    let hit = db.query("SELECT * FROM docs").unwrap().collect().remove(0);
+
+    let opts = HitJsonOptions {
+        slug: false,
+        payload_as_object: false,
+        lift: vec!["meta.score".into(), "absent".into()],
+        ..Default::default()
+    };
+    let json = hit.to_json(&opts);
+    assert_eq!(json.as_object().unwrap().len(), 2);
+    assert_eq!(json["meta.score"], 0.5);
+    let payload: serde_json::Value = serde_json::from_str(json["payload"].as_str().unwrap()).unwrap();
+    assert_eq!(payload["meta"]["score"], 0.5);
+
+    let opts: HitJsonOptions = serde_json::from_str(r#"{"payload":false}"#).unwrap();
+    assert_eq!(hit.to_json(&opts), serde_json::json!({"slug":"docs/a"}));
+}
+
+#[test]
+fn collect_as_decodes_payloads_and_names_the_bad_row() {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Doc {
+        title: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+    let mut db = CoreDB::new();
+    db.put("docs/a", r#"{"_collection":"docs","title":"Alpha","tags":["x"]}"#).unwrap();
+    db.put("docs/b", r#"{"_collection":"docs","title":"Beta"}"#).unwrap();
+    let docs: Vec<Doc> = db.query("SELECT * FROM docs ORDER BY title").unwrap().collect_as().unwrap();
+    assert_eq!(docs, [
+        Doc { title: "Alpha".into(), tags: vec!["x".into()] },
+        Doc { title: "Beta".into(), tags: vec![] },
+    ]);
+
+    db.put("docs/c", r#"{"_collection":"docs","title":7}"#).unwrap();
+    let err = db.collection("docs").collect_as::<Doc>().unwrap_err();
+    assert_eq!(err.slug, "docs/c");
+    assert!(err.to_string().contains("`docs/c`") && err.to_string().contains("Doc"), "{err}");
+}
+
+#[test]
+fn collect_as_with_meta_pairs_values_with_their_slugs() {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Doc {
+        title: String,
+    }
+    let mut db = CoreDB::new();
+    for (slug, title) in [("docs/a", "Alpha"), ("docs/b", "Beta"), ("docs/c", "Gamma")] {
+        db.put(slug, &format!(r#"{{"_collection":"docs","title":"{title}"}}"#)).unwrap();
+    }
+    let rows = db.collection("docs").sort("title", false).take(2).collect_as_with_meta::<Doc>().unwrap();
+    let got: Vec<(&str, &str)> = rows.iter().map(|(m, d)| (m.slug.as_str(), d.title.as_str())).collect();
+    assert_eq!(got, [("docs/c", "Gamma"), ("docs/b", "Beta")]);
+    let hits = db.collection("docs").sort("title", false).take(2).collect();
+    assert_eq!(rows[0].0.slug_hash, hits[0].slug_hash);
+
+    // Raw payloads and collected hits decode to the same values.
+    let raw: Vec<serde_json::Value> = db.collection("docs").collect_as().unwrap();
+    let projected: Vec<serde_json::Value> = db.query("SELECT * FROM docs").unwrap().collect_as().unwrap();
+    assert_eq!(raw, projected);
+
+    db.put("docs/d", r#"{"_collection":"docs","title":false}"#).unwrap();
+    let err = db.collection("docs").collect_as_with_meta::<Doc>().unwrap_err();
+    assert_eq!(err.slug, "docs/d");
+}
+
+// ── Traversal with edges ─────────────────────────────────────────────────────
+
+#[test]
+fn collect_with_edges_returns_one_row_per_crossed_edge() {
+    let mut db = CoreDB::new();
+    for s in ["quake", "storm", "fire", "flood"] {
+        db.put(s, "{}").unwrap();
+    }
+    db.link("quake", "fire", "causes", 0.4);
+    db.link("storm", "fire", "causes", 0.9);
+    db.link("storm", "flood", "causes", 0.6);
+    db.link_meta("quake", "flood", "causes", 0.2, r#"{"note":"minor"}"#).unwrap();
+
+    let mut rows: Vec<(String, String, f32)> = db.many(["quake", "storm"]).forward("causes")
+        .collect_with_edges()
+        .into_iter()
+        .map(|(hit, e)| (e.from_slug.unwrap(), hit.slug, e.strength))
+        .collect();
+    rows.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    assert_eq!(rows, vec![
+        ("quake".into(), "fire".into(), 0.4),
+        ("quake".into(), "flood".into(), 0.2),
+        ("storm".into(), "fire".into(), 0.9),
+        ("storm".into(), "flood".into(), 0.6),
+    ]);
+
+    // Edge filters after the traversal drop the weaker edges too.
+    let strong = db.many(["quake", "storm"]).forward("causes").min_strength(0.5).collect_with_edges();
+    assert_eq!(strong.len(), 2);
+    assert!(strong.iter().all(|(_, e)| e.from_slug.as_deref() == Some("storm")));
+
+    // Backward reports edges in their stored direction.
+    let back = db.one("flood").backward("causes").collect_with_edges();
+    assert_eq!(back.len(), 2);
+    assert!(back.iter().all(|(_, e)| e.to_slug.as_deref() == Some("flood")));
+    let minor = back.iter().find(|(hit, _)| hit.slug == "quake").unwrap();
+    assert_eq!(minor.1.meta, Some(serde_json::json!({"note":"minor"})));
+
+    assert!(db.one("quake").collect_with_edges().is_empty());
+}
+
+#[test]
+fn edge_collect_incoming_lists_every_edge_into_the_set() {
+    let mut db = CoreDB::new();
+    for s in ["home", "about", "blog", "wiki"] {
+        db.put(s, r#"{"_collection":"pages"}"#).unwrap();
+    }
+    db.link("blog", "home", "links", 1.0);
+    db.link_meta("wiki", "home", "cites", 0.5, r#"{"anchor":"intro"}"#).unwrap();
+    db.link("blog", "about", "links", 0.7);
+    db.link("home", "blog", "links", 0.3);
+
+    let mut rows: Vec<(String, String, String)> = db.many(["home", "about"])
+        .edge_collect_incoming()
+        .into_iter()
+        .map(|(hit, e)| (hit.slug, e.from_slug.unwrap(), e.edge_type.unwrap()))
+        .collect();
+    rows.sort();
+    assert_eq!(rows, vec![
+        ("about".into(), "blog".into(), "links".into()),
+        ("home".into(), "blog".into(), "links".into()),
+        ("home".into(), "wiki".into(), "cites".into()),
+    ]);
+
+    let cite = db.one("home").edge_collect_incoming().into_iter()
+        .find(|(_, e)| e.edge_type.as_deref() == Some("cites"))
+        .unwrap();
+    assert_eq!(cite.1.meta, Some(serde_json::json!({"anchor":"intro"})));
+
+    assert!(db.one("wiki").edge_collect_incoming().is_empty());
+}
+
+// ── Edge aggregates ──────────────────────────────────────────────────────────
+
+#[test]
+fn edge_aggregate_tracks_links_unlinks_removals_and_puts() {
+    use sekejap::EdgeAggregate;
+    let mut db = CoreDB::new();
+    db.put("geo/d1", r#"{"_collection":"geo","name":"North"}"#).unwrap();
+    db.put("inc/1", r#"{"_collection":"incidents"}"#).unwrap();
+    db.link("inc/1", "geo/d1", "located_in", 0.5);
+
+    db.set_edge_aggregate("geo", "incident_count", Some(EdgeAggregate::Count {
+        edge_type: "located_in".into(),
+        incoming: true,
+    }));
+    db.set_edge_aggregate("geo", "severity", Some(EdgeAggregate::SumStrength {
+        edge_type: "located_in".into(),
+        incoming: true,
+    }));
+    let d1 = |db: &CoreDB| -> serde_json::Value { serde_json::from_str(&db.get("geo/d1").unwrap()).unwrap() };
+    assert_eq!(d1(&db)["incident_count"], 1, "existing nodes are backfilled");
+
+    for i in 2..=3 {
+        db.put(&format!("inc/{i}"), r#"{"_collection":"incidents"}"#).unwrap();
+        db.link(&format!("inc/{i}"), "geo/d1", "located_in", 1.0);
+    }
+    db.link("inc/1", "geo/d1", "mentions", 1.0);
+    assert_eq!(d1(&db)["incident_count"], 3);
+    assert_eq!(d1(&db)["severity"], 2.5);
+
+    db.unlink("inc/2", "geo/d1", "located_in");
+    db.remove("inc/3");
+    assert_eq!(d1(&db)["incident_count"], 1);
+
+    // A plain overwrite cannot clobber the maintained field.
+    db.put("geo/d1", r#"{"_collection":"geo","name":"North (renamed)"}"#).unwrap();
+    assert_eq!(d1(&db)["incident_count"], 1);
+    assert_eq!(d1(&db)["name"], "North (renamed)");
+
+    db.set_edge_aggregate("geo", "incident_count", None);
+    assert!(db.edge_aggregate("geo", "incident_count").is_none());
+    db.link("inc/2", "geo/d1", "located_in", 1.0);
+    assert_eq!(d1(&db)["incident_count"], 1, "dropped aggregates are no longer updated");
+    assert_eq!(d1(&db)["severity"], 1.5);
+}
+
+// ── Projection profiles ──────────────────────────────────────────────────────
+
+#[test]
+fn projection_profile_strips_fields_per_collection() {
+    let mut db = CoreDB::new();
+    db.put("u/1", r#"{"_collection":"users","name":"Ani","secret":"s1","internal_id":7}"#).unwrap();
+    db.put("o/1", r#"{"_collection":"orgs","name":"Acme","secret":"kept"}"#).unwrap();
+    db.set_projection_profile("users", "public", Some(vec!["secret".into(), "internal_id".into()]));
+    assert_eq!(db.projection_profile("users", "public").unwrap(), ["secret", "internal_id"]);
+
+    let users = db.query("SELECT * FROM users").unwrap().profile("public").unwrap().collect();
+    let p = users[0].payload.as_ref().unwrap();
+    assert_eq!(p["name"], "Ani");
+    assert!(p.get("secret").is_none() && p.get("internal_id").is_none());
+
+    // Collections without the profile, and queries without one, are untouched.
+    let orgs = db.collection("orgs").profile("public").unwrap().collect();
+    assert_eq!(orgs[0].payload.as_ref().unwrap()["secret"], "kept");
+    let trusted = db.collection("users").first().unwrap();
+    assert_eq!(trusted.payload.unwrap()["secret"], "s1");
+    assert!(db.collection("users").profile("nope").is_err(), "an unknown profile must not redact nothing");
+
+    db.set_projection_profile("users", "public", None);
+    assert!(db.projection_profile("users", "public").is_none());
+    assert!(db.collection("users").profile("public").is_err());
+}
+
+#[test]
+fn projection_profile_refuses_projections_of_hidden_fields() {
+    let mut db = CoreDB::new();
+    db.put("u/1", r#"{"_collection":"users","name":"Ani","email":"ani@example.com"}"#).unwrap();
+    db.set_projection_profile("users", "public", Some(vec!["email".into()]));
+    for sql in [
+        "SELECT email AS e FROM users",
+        "SELECT email FROM users",
+        "SELECT UPPER(email) FROM users",
+        "SELECT email, COUNT(*) FROM users GROUP BY email",
+    ] {
+        let set = db.query(sql).unwrap();
+        assert!(set.profile("public").is_err(), "{sql}");
+    }
+    let agg = db.query("MATCH (u) WHERE u._key = 'u/1' RETURN COUNT(*) AS n").unwrap();
+    assert!(agg.profile("public").is_err(), "aggregate rows cannot be redacted per field");
+    assert!(db.query("SELECT COUNT(*) FROM users").unwrap().profile("public").is_ok());
+    let names = db.query("SELECT name AS n FROM users").unwrap().profile("public").unwrap().collect();
+    assert_eq!(names[0].payload.as_ref().unwrap()["n"], "Ani");
+
+    // A projection added after the profile cannot carry the field out either.
+    let late = db.collection("users").profile("public").unwrap().select(["email"]).collect();
+    assert!(late[0].payload.is_none());
+}
+
+#[test]
+fn projection_profile_hides_fields_from_every_terminal() {
+    use serde_json::{json, Value};
+    let mut db = CoreDB::new();
+    db.put("u/1", r#"{"_collection":"users","name":"Ani","salary":120,"hired":"2026-01-05"}"#).unwrap();
+    db.put("u/2", r#"{"_collection":"users","name":"Budi","salary":80,"hired":"2026-02-10"}"#).unwrap();
+    db.link("u/1", "u/2", "manages", 1.0);
+    db.set_projection_profile("users", "public", Some(vec!["salary".into(), "hired".into(), "name".into()]));
+    let public = || db.collection("users").profile("public").unwrap();
+
+    assert!(db.collection("users").any("salary", json!({"gt": 100})).unwrap());
+    assert!(!public().any("salary", json!({"gt": 100})).unwrap());
+    assert!(public().any("salary", Value::Null).unwrap());
+    assert!(!public().any("salary", json!({"in": [80, 120]})).unwrap());
+    assert_eq!(public().min("salary"), None);
+    assert_eq!(public().max("salary"), None);
+    assert_eq!(public().count_distinct("salary"), 0);
+    assert!(public().histogram("salary", 50.0).is_empty());
+    assert!(public().bucket_time("hired", "month").is_empty());
+    assert_eq!(db.collection("users").max("salary"), Some(120.0));
+
+    let viz = public().to_viz_json(&["salary"]);
+    let nodes = viz["nodes"].as_array().unwrap();
+    assert!(nodes.iter().all(|n| n.get("salary").is_none()));
+    assert!(nodes.iter().all(|n| n["label"] == n["id"]));
+
+    let pairs = db.one("u/1").forward("manages").profile("public").unwrap().edge_collect();
+    let payload = pairs[0].0.payload.as_ref().unwrap();
+    assert!(payload.get("salary").is_none() && payload.get("name").is_none());
+    let incoming = db.one("u/2").profile("public").unwrap().edge_collect_incoming();
+    assert!(incoming[0].0.payload.as_ref().unwrap().get("salary").is_none());
+}
+
+// ── Collection snapshot diffs ────────────────────────────────────────────────
+
+#[test]
+fn collection_diff_reports_joins_and_departures_since_a_token() {
+    use sekejap::CollectionSnapshot;
+    let mut db = CoreDB::new();
+    db.put("u/1", r#"{"_collection":"users"}"#).unwrap();
+    db.put("u/2", r#"{"_collection":"users"}"#).unwrap();
+    db.put("o/1", r#"{"_collection":"orgs"}"#).unwrap();
+
+    let initial = db.collection_diff("users", &CollectionSnapshot::default());
+    let mut added: Vec<&str> = initial.added.iter().map(|&h| db.slug_of(h).unwrap()).collect();
+    added.sort();
+    assert_eq!(added, ["u/1", "u/2"]);
+    assert!(initial.removed.is_empty());
+    let token = initial.snapshot.to_bytes();
+
+    // Edits that keep a node in place are not changes; moves and removes are.
+    db.put("u/1", r#"{"_collection":"users","name":"Ani"}"#).unwrap();
+    db.put("u/2", r#"{"_collection":"orgs"}"#).unwrap();
+    db.put("u/3", r#"{"_collection":"users"}"#).unwrap();
+    let u2 = db.collection("orgs").collect().into_iter().find(|h| h.slug == "u/2").unwrap().slug_hash;
+
+    let since = CollectionSnapshot::from_bytes(&token).unwrap();
+    assert_eq!(since, initial.snapshot);
+    let diff = db.collection_diff("users", &since);
+    assert_eq!(diff.added.iter().map(|&h| db.slug_of(h).unwrap()).collect::<Vec<_>>(), ["u/3"]);
+    assert_eq!(diff.removed, [u2]);
+    assert_eq!(diff.snapshot.len(), 2);
+    let quiet = db.collection_diff("users", &diff.snapshot);
+    assert!(quiet.added.is_empty() && quiet.removed.is_empty());
+    assert!(CollectionSnapshot::from_bytes(b"nope").is_err());
+}
+
+// ── CSV import ───────────────────────────────────────────────────────────────
+
+#[test]
+fn csv_import_maps_columns_and_loads_edges() {
+    use sekejap::{CsvEdgeMapping, CsvMapping, CsvType};
+
+    let nodes = "\u{feff}code,name,open,tags,rating,lat,lon,notes\r\n\
+        a1,\"Cafe \"\"Uno\"\"\",yes,\"[\"\"coffee\"\"]\",4.5,-37.81,144.96,\"two\nlines\"\r\n\
+        \r\n\
+        a2,Bar Due,0,,,-37.82,144.97,\r\n\
+        a3,Tre,true,[],3,,,x\r\n";
+    let mapping = CsvMapping { chunk_size: 2, ..CsvMapping::new("venues", "code") }
+        .point("lat", "lon")
+        .column("open", CsvType::Bool)
+        .column("tags", CsvType::Json)
+        .column("rating", CsvType::Float)
+        .column("notes", CsvType::Skip);
+    let mut db = CoreDB::new();
+    assert_eq!(db.import_csv(nodes.as_bytes(), &mapping).unwrap(), 3);
+    assert_eq!(db.collection("venues").count(), 3);
+
+    let a1: serde_json::Value = serde_json::from_str(&db.get("venues/a1").unwrap()).unwrap();
+    assert_eq!(a1["_key"], "a1");
+    assert_eq!(a1["name"], "Cafe \"Uno\"");
+    assert_eq!(a1["open"], true);
+    assert_eq!(a1["tags"], serde_json::json!(["coffee"]));
+    assert_eq!(a1["geometry"]["coordinates"], serde_json::json!([144.96, -37.81]));
+    assert!(a1.get("notes").is_none() && a1.get("lat").is_none());
+    let a2: serde_json::Value = serde_json::from_str(&db.get("venues/a2").unwrap()).unwrap();
+    assert_eq!(a2["open"], false);
+    assert!(a2.get("tags").is_none() && a2.get("rating").is_none());
+    let a3: serde_json::Value = serde_json::from_str(&db.get("venues/a3").unwrap()).unwrap();
+    assert!(a3.get("geometry").is_none());
+    assert_eq!(db.collection("venues").st_dwithin(-37.81, 144.96, 5.0).count(), 2);
+
+    let bad = "code,rating\nb1,4\nb2,lots\n";
+    let err = db.import_csv(bad.as_bytes(), &CsvMapping::new("venues", "code").column("rating", CsvType::Int)).unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{err}");
+    let err = db.import_csv(bad.as_bytes(), &CsvMapping::new("venues", "id")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let edges = "src;dst;rel\na1;a2;near\na2;a3;near\n";
+    let mapping = CsvEdgeMapping {
+        from_column: "src".into(),
+        to_column: "dst".into(),
+        type_column: "rel".into(),
+        from_collection: Some("venues".into()),
+        to_collection: Some("venues".into()),
+        delimiter: b';',
+        ..Default::default()
+    };
+    assert_eq!(db.import_csv_edges(edges.as_bytes(), &mapping).unwrap(), 2);
+    let out = db.edges_from("venues/a1");
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].strength, 1.0);
+}
+
+// ── Graph interop exports ────────────────────────────────────────────────────
+
+#[test]
+fn graphml_and_cypher_exports_carry_nodes_edges_and_types() {
+    let mut db = CoreDB::new();
+    db.put("people/ann", r#"{"_collection":"people","name":"Ann & \"Co\"","age":41,"tags":["a","b"]}"#).unwrap();
+    db.put("people/bo", r#"{"_collection":"people","name":"Bo's","age":37.5,"vip":true}"#).unwrap();
+    db.put("loose", r#"{"note":{"x":1}}"#).unwrap();
+    db.link("people/ann", "people/bo", "knows", 0.5);
+    db.link_meta("people/bo", "loose", "wrote up", 1.0, r#"{"since":2020}"#).unwrap();
+    db.link("people/ann", "ghost", "knows", 1.0);
+
+    let mut xml = Vec::new();
+    assert_eq!(db.export_graphml(&mut xml).unwrap(), 5);
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains(r#"attr.name="age" attr.type="double""#), "{xml}");
+    assert!(xml.contains(r#"attr.name="vip" attr.type="boolean""#));
+    assert!(xml.contains(r#"attr.name="since" attr.type="long""#));
+    assert!(xml.contains("Ann &amp; &quot;Co&quot;"));
+    assert!(xml.contains(r#"<data key="label">wrote up</data>"#));
+    assert!(!xml.contains("ghost"));
+    let mut again = Vec::new();
+    db.export_graphml(&mut again).unwrap();
+    assert_eq!(String::from_utf8(again).unwrap(), xml, "exports are deterministic");
+
+    let mut cypher = Vec::new();
+    assert_eq!(db.export_cypher(&mut cypher).unwrap(), 5);
+    let cypher = String::from_utf8(cypher).unwrap();
+    let lines: Vec<&str> = cypher.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("CREATE (:Node {_slug: 'loose', _created_unix: "));
+    assert!(lines[0].ends_with(r#", note: '{"x":1}'});"#));
+    assert!(lines[1].ends_with(r#", age: 41, name: 'Ann & "Co"', tags: ['a', 'b']});"#), "{}", lines[1]);
+    assert!(lines[2].contains(r"name: 'Bo\'s'"));
+    assert!(lines[3].starts_with("MATCH (a:people {_slug: 'people/ann'}), (b:people {_slug: 'people/bo'}) CREATE (a)-[:knows {weight: 0.5, created_unix: "));
+    assert!(lines[4].contains("(b:Node {_slug: 'loose'}) CREATE (a)-[:`wrote up` {weight: 1.0, created_unix: "));
+    assert!(lines[4].ends_with(", since: 2020}]->(b);"));
+}
+
+// ── Hybrid search ────────────────────────────────────────────────────────────
+
+#[test]
+fn hybrid_search_fuses_vector_and_keyword_rankings() {
+    use sekejap::Fusion;
+    let mut db = CoreDB::new();
+    let docs = [
+        ("docs/a", "rust ownership guide", [1.0, 0.0, 0.0]),
+        ("docs/b", "gardening with roses", [0.95, 0.05, 0.0]),
+        ("docs/c", "rust async runtime", [0.0, 1.0, 0.0]),
+        ("docs/d", "cooking fresh pasta", [0.0, 0.0, 1.0]),
+        ("docs/e", "hiking mountain trails", [0.0, 0.7, 0.7]),
+        ("docs/f", "painting old walls", [0.1, 0.0, 1.0]),
+        ("notes/n", "rust rust rust", [1.0, 0.0, 0.0]),
+    ];
+    for (slug, body, emb) in docs {
+        let coll = slug.split('/').next().unwrap();
+        db.put(slug, &format!(r#"{{"_collection":"{coll}","body":"{body}"}}"#)).unwrap();
+        db.put_vector(slug, "emb", &emb).unwrap();
+    }
+    db.build_bm25_index("body");
+    let q = vec![1.0, 0.0, 0.0];
+    let slugs = |hits: Vec<sekejap::Hit>| hits.into_iter().map(|h| h.slug).collect::<Vec<_>>();
+
+    // RRF: docs/a leads both lists; docs/b (vector only) and docs/c
+    // (keyword only) both survive, where an intersection would drop them.
+    let fused = slugs(db.collection("docs").hybrid("emb", q.clone(), "body", "rust", 3, Fusion::default()).collect());
+    assert_eq!(fused[0], "docs/a");
+    assert!(fused.contains(&"docs/b".to_string()) && fused.contains(&"docs/c".to_string()), "{fused:?}");
+    assert!(!fused.contains(&"notes/n".to_string()), "scoped to the collection");
+
+    // As a starter it ranks the whole database.
+    let all = slugs(db.all().hybrid("emb", q.clone(), "body", "rust", 2, Fusion::default()).collect());
+    assert!(all.contains(&"notes/n".to_string()), "{all:?}");
+
+    // Weighted sums lean to one side or the other.
+    let vector_only = slugs(db.collection("docs").hybrid("emb", q.clone(), "body", "rust", 2, Fusion::Weighted { vector: 1.0 }).collect());
+    assert_eq!(vector_only, ["docs/a", "docs/b"]);
+    let mut text_only = slugs(db.collection("docs").hybrid("emb", q.clone(), "body", "rust", 2, Fusion::Weighted { vector: 0.0 }).collect());
+    text_only.sort();
+    assert_eq!(text_only, ["docs/a", "docs/c"]);
+
+    // SQL: RRF by default, a sixth argument switches to a weighted sum.
+    let sql = slugs(db.query("SELECT * FROM docs WHERE HYBRID(emb, [1.0, 0.0, 0.0], body, 'rust', 3)").unwrap().collect());
+    assert_eq!(sql, fused);
+    let params = [serde_json::json!([1.0, 0.0, 0.0]), serde_json::json!("rust")];
+    let sql = slugs(db.query_params("SELECT * FROM docs WHERE HYBRID(emb, $1, body, $2, 2, 1.0)", &params).unwrap().collect());
+    assert_eq!(sql, vector_only);
+    assert!(db.query("SELECT * FROM docs WHERE HYBRID(emb, [1.0], body, 'rust', 2, 1.5)").is_err());
+}
+
+// ── Hit scores ───────────────────────────────────────────────────────────────
+
+#[test]
+fn hits_carry_the_score_of_the_step_that_ranked_them() {
+    use sekejap::{Fusion, HitJsonOptions};
+    let mut db = CoreDB::new();
+    for (slug, body, emb) in [
+        ("docs/a", "rust ownership guide", [1.0, 0.0]),
+        ("docs/b", "gardening with roses", [0.6, 0.8]),
+        ("docs/c", "rust async runtime", [0.0, 1.0]),
+        ("docs/d", "cooking fresh pasta", [-1.0, 0.0]),
+        ("docs/e", "hiking mountain trails", [-0.6, -0.8]),
+    ] {
+        db.put(slug, &format!(r#"{{"_collection":"docs","body":"{body}"}}"#)).unwrap();
+        db.put_vector(slug, "emb", &emb).unwrap();
+    }
+    db.build_bm25_index("body");
+
+    // Vector search: cosine distance, nearest first.
+    let near = db.collection("docs").vector_near("emb", vec![1.0, 0.0], 3).collect();
+    let dists: Vec<f32> = near.iter().map(|h| h.score.unwrap()).collect();
+    assert!(dists[0].abs() < 1e-6 && dists.windows(2).all(|w| w[0] <= w[1]), "{dists:?}");
+    // Scores survive later filters; unscored pipelines have none.
+    let kept = db.collection("docs").vector_near("emb", vec![1.0, 0.0], 3).where_eq("body", "rust async runtime").collect();
+    assert_eq!(kept[0].score, near.iter().find(|h| h.slug == "docs/c").and_then(|h| h.score));
+    assert!(db.collection("docs").collect().iter().all(|h| h.score.is_none()));
+
+    // BM25 and hybrid: higher is better.
+    let text = db.query("SELECT * FROM docs WHERE BM25(body, 'rust') > 0.0").unwrap().collect();
+    assert_eq!(text.len(), 2);
+    assert!(text.iter().all(|h| h.score.is_some_and(|s| s > 0.0)));
+    let fused = db.collection("docs").hybrid("emb", vec![1.0, 0.0], "body", "rust", 3, Fusion::default()).collect();
+    assert_eq!(fused[0].slug, "docs/a");
+    assert!(fused.windows(2).all(|w| w[0].score >= w[1].score), "best first");
+
+    // Projected rows keep the score; JSON includes it unless turned off.
+    let row = db.query("SELECT body FROM docs WHERE VECTOR_NEAR(emb, [0.0, 1.0], 1)").unwrap().collect().remove(0);
+    assert_eq!(row.slug, "docs/c");
+    let json = row.to_json(&HitJsonOptions::default());
+    assert!(json["score"].as_f64().unwrap().abs() < 1e-6, "{json}");
+    assert!(row.to_json(&HitJsonOptions { score: false, ..Default::default() }).get("score").is_none());
+
+    // Spatial radius filters: km from the point.
+    let db = setup_spatial_db();
+    let hits = db.collection("places").st_dwithin(-37.8102, 144.9631, 2.0).collect();
+    let central = hits.iter().find(|h| h.payload.as_ref().unwrap()["name"] == "Melbourne Central").unwrap();
+    assert!(central.score.unwrap() < 0.1);
+    assert!(hits.iter().all(|h| h.score.is_some_and(|km| km <= 2.0)));
+}
@@ -62,6 +62,31 @@ fn link_survives_reopen() {
     assert_eq!(hits[0].slug, "bob");
 }
 
+#[test]
+fn edge_timestamps_survive_reopen_and_compact() {
+    let dir = tmpdir();
+    let created = {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("alice", "{}").unwrap();
+        db.put("bob", "{}").unwrap();
+        db.link("alice", "bob", "follows", 1.0);
+        db.link_meta("bob", "alice", "follows", 1.0, r#"{"via":"app"}"#).unwrap();
+        let created = db.edges_from("alice")[0].created_unix;
+        assert!(created > 0);
+        created
+    };
+
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.edges_from("alice")[0].created_unix, created);
+    assert!(db.edges_from("bob")[0].created_unix >= created);
+    db.compact().unwrap();
+    drop(db);
+
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.edges_from("alice")[0].created_unix, created);
+    assert_eq!(db.one("alice").forward_since("follows", created, created).count(), 1);
+}
+
 #[test]
 fn link_meta_survives_reopen() {
     let dir = tmpdir();
@@ -255,6 +280,33 @@ fn transaction_rollback_leaves_wal_clean() {
     }
 }
 
+/// A commit whose WAL group was cut short (crash mid-commit) must replay as
+/// nothing at all.
+#[test]
+fn torn_transaction_commit_is_discarded_on_replay() {
+    let dir = tmpdir();
+
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("base/node", "{}").unwrap();
+        let mut txn = db.begin();
+        txn.put("t/a", "{}").unwrap();
+        txn.put("t/b", "{}").unwrap();
+        txn.commit().unwrap();
+    }
+
+    // Chop off the trailing txn_end frame.
+    let wal_path = dir.path().join("wal.log");
+    let data = std::fs::read(&wal_path).unwrap();
+    let end_frame = 8 + br#"{"op":"txn_end"}"#.len();
+    std::fs::write(&wal_path, &data[..data.len() - end_frame]).unwrap();
+
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert!(db.contains("base/node"));
+    assert!(!db.contains("t/a"));
+    assert!(!db.contains("t/b"));
+}
+
 // ── #2 HNSW persistence ───────────────────────────────────────────────────────
 
 /// HNSW graph must survive compact + cold reload — no rebuild needed on startup.
@@ -302,11 +354,11 @@ fn hnsw_graph_survives_wal_replay() {
         // No compact — data lives in WAL only
     }
 
-    // Cold reload: WAL replay should restore nodes + vectors.
-    // The HNSW graph was NOT in the WAL, but the vectors are — flat scan will work.
+    // Cold reload: WAL replay restores nodes + vectors, and the logged
+    // build_hnsw_index rebuilds the graph once replay is done.
     {
         let db = CoreDB::open(dir.path()).unwrap();
-        // Flat-scan fallback (no HNSW from WAL) still gives correct results
+        assert_eq!(db.hnsw_fields(), ["emb"]);
         let results = db
             .collection("docs")
             .vector_near("emb", vec![1.0, 0.0], 1)
@@ -368,6 +420,30 @@ fn btree_index_survives_wal_replay() {
     }
 }
 
+/// An index built through the API (not SQL) is declared and logged too, so it
+/// is still used after a plain reopen.
+#[test]
+fn api_built_field_index_survives_reopen() {
+    let dir = tmpdir();
+    let uses_index = |db: &CoreDB| {
+        db.explain("SELECT * FROM s WHERE i > 5").unwrap().iter()
+            .any(|h| h.payload.as_ref().and_then(|p| p.get("index")).is_some())
+    };
+
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        for i in 0..10 {
+            db.put(&format!("s/{i}"), &format!(r#"{{"_collection":"s","i":{i}}}"#)).unwrap();
+        }
+        db.build_field_index("s", "i");
+        assert!(uses_index(&db));
+    }
+
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert!(uses_index(&db));
+    assert_eq!(db.query("SELECT * FROM s WHERE i > 5").unwrap().count(), 4);
+}
+
 // ── #4 BM25 / GIN index persistence ──────────────────────────────────────────
 
 /// BM25 index must survive WAL-only cold reload and return ranked results.
@@ -528,3 +604,744 @@ fn hnsw_version_mismatch_triggers_rebuild() {
         assert_eq!(results[0].slug, "docs/d1");
     }
 }
+
+#[test]
+fn soft_delete_survives_wal_replay_and_compact() {
+    let dir = tmpdir();
+
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("a", r#"{"name":"A"}"#).unwrap();
+        db.put("b", r#"{"name":"B"}"#).unwrap();
+        db.link("a", "b", "rel", 1.0);
+        db.remove_soft("a");
+    }
+
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        assert!(!db.contains("a"));
+        assert!(db.is_trashed("a"));
+        db.compact().unwrap();
+    }
+
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    assert!(db.restore("a"));
+    assert!(db.get("a").unwrap().contains("\"A\""));
+    assert_eq!(db.one("a").forward("rel").collect()[0].slug, "b");
+}
+
+#[test]
+fn group_commit_wal_survives_reopen() {
+    use sekejap::{Config, WalConfig, WalSync};
+    let dir = tmpdir();
+    let config = || Config {
+        wal: WalConfig { sync: WalSync::Batch { max_entries: 8 } },
+        ..Config::default()
+    };
+
+    {
+        let mut db = CoreDB::open_with_config(dir.path(), config()).unwrap();
+        for i in 0..20 {
+            db.put(&format!("n{i}"), r#"{"_collection":"items"}"#).unwrap();
+        }
+        db.link("n0", "n1", "next", 1.0);
+        db.sync().unwrap();
+        db.remove("n19");
+    } // drop flushes the trailing partial group
+
+    let db = CoreDB::open_with_config(dir.path(), config()).unwrap();
+    assert_eq!(db.node_count(), 19);
+    assert_eq!(db.one("n0").forward("next").collect()[0].slug, "n1");
+}
+
+#[test]
+fn mutate_ndjson_is_logged_to_wal() {
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        let feed = "{\"op\":\"put\",\"slug\":\"a\",\"payload\":\"{}\"}\n\
+                    {\"op\":\"put\",\"slug\":\"b\",\"payload\":\"{}\"}\n\
+                    {\"op\":\"link\",\"from\":\"a\",\"to\":\"b\",\"edge_type\":\"rel\",\"strength\":1.0}\n";
+        let summary = db.mutate_ndjson(feed.as_bytes()).unwrap();
+        assert_eq!(summary.applied, 3);
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.node_count(), 2);
+    assert_eq!(db.one("a").forward("rel").collect()[0].slug, "b");
+}
+
+#[test]
+fn large_payloads_readable_as_payload_file_grows() {
+    let dir = tmpdir();
+    let blob = "x".repeat(1 << 20);
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        let items: Vec<(String, String)> = (0..48)
+            .map(|i| (format!("n{i}"), format!(r#"{{"i":{i},"blob":"{blob}"}}"#)))
+            .collect();
+        db.put_many(items.iter().map(|(s, j)| (s.as_str(), j.as_str()))).unwrap();
+        for i in [0, 31, 47] {
+            assert!(db.get(&format!("n{i}")).unwrap().contains(&format!(r#""i":{i}"#)));
+        }
+        db.compact().unwrap();
+        db.put("late", r#"{"v":1}"#).unwrap();
+        assert!(db.contains("late"));
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.node_count(), 49);
+    let last = db.get("n47").unwrap();
+    assert!(last.contains(r#""i":47"#) && last.contains(&blob));
+}
+
+#[test]
+fn collection_filter_survives_replay_and_compact() {
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("u/1", r#"{"_collection":"users","active":true}"#).unwrap();
+        db.put("u/2", r#"{"_collection":"users","active":false}"#).unwrap();
+        db.set_collection_filter("users", Some("active = true")).unwrap();
+    }
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        assert_eq!(db.collection("users").count(), 1);
+        db.compact().unwrap();
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.collection_filter("users"), Some("active = true"));
+    assert_eq!(db.collection("users").count(), 1);
+}
+
+#[test]
+fn compact_edge_meta_readable_immediately_and_unbounded() {
+    let dir = tmpdir();
+    let big = "m".repeat(100_000); // larger than a u16 length
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("a", "{}").unwrap();
+        db.put("b", "{}").unwrap();
+        db.put("c", "{}").unwrap();
+        db.link_meta("c", "c", "scratch", 1.0, r#"{"tmp":true}"#).unwrap();
+        db.link_meta("a", "b", "rel", 1.0, &format!(r#"{{"note":"{big}"}}"#)).unwrap();
+        db.link_meta("b", "a", "rel", 1.0, r#"{"since":2020}"#).unwrap();
+        // Visible before any remap/compact.
+        assert_eq!(db.edges_from("a")[0].meta.as_ref().unwrap()["note"].as_str().unwrap().len(), big.len());
+        assert_eq!(db.edges_from("b")[0].meta.as_ref().unwrap()["since"], 2020);
+        // Bad JSON is not a capacity error.
+        let err = db.link_meta("a", "b", "rel", 1.0, "{").unwrap_err();
+        assert!(sekejap::CapacityExceeded::from_link_error(err).is_err());
+        // Compaction drops the removed edge's entry and renumbers the rest.
+        db.unlink("c", "c", "scratch");
+        db.compact().unwrap();
+        assert_eq!(db.edges_from("a")[0].meta.as_ref().unwrap()["note"].as_str().unwrap(), big);
+        assert_eq!(db.edges_from("b")[0].meta.as_ref().unwrap()["since"], 2020);
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.edges_from("a")[0].meta.as_ref().unwrap()["note"].as_str().unwrap(), big);
+}
+
+#[test]
+fn upserts_rewrite_payload_in_place_when_it_fits() {
+    let dir = tmpdir();
+    let pay_len = || std::fs::metadata(dir.path().join("payloads.bin")).unwrap().len();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("a", r#"{"note":"a fairly long first version"}"#).unwrap();
+        db.put("b", "{}").unwrap();
+        db.link("a", "b", "rel", 1.0);
+        let before = pay_len();
+        for note in ["shorter", "tinier", "tiny", "x"] {
+            db.put("a", &format!(r#"{{"note":"{note}"}}"#)).unwrap();
+        }
+        assert_eq!(pay_len(), before);
+        assert!(db.get("a").unwrap().contains(r#""note":"x""#));
+        assert_eq!(db.edges_from("a").len(), 1);
+
+        // Growing past the old blob appends instead.
+        db.put("a", r#"{"note":"a version longer than any before it"}"#).unwrap();
+        assert!(pay_len() > before);
+
+        // Blobs a snapshot points at are never rewritten.
+        db.compact().unwrap();
+        let compacted = pay_len();
+        db.put("a", r#"{"note":"y"}"#).unwrap();
+        assert!(pay_len() > compacted);
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert!(db.get("a").unwrap().contains(r#""note":"y""#));
+    assert_eq!(db.edges_from("a").len(), 1);
+}
+
+#[test]
+fn edge_upserts_replay_the_same_whatever_the_reopen_setting() {
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.link("a", "b", "knows", 0.5);
+        db.link("a", "b", "knows", 0.9);
+    }
+    let config = sekejap::Config { allow_parallel_edges: true, ..Default::default() };
+    let db = CoreDB::open_with_config(dir.path(), config).unwrap();
+    let hits = db.edges_from("a");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].strength, 0.9);
+}
+
+#[test]
+fn builder_applies_every_setting_on_open() {
+    use sekejap::{QueryLimits, Quota, WalSync};
+    let dir = tmpdir();
+    let limits = QueryLimits { max_results: Some(5), ..Default::default() };
+    let quota = Quota { max_nodes: Some(1), ..Default::default() };
+    let builder = CoreDB::builder(dir.path())
+        .capacity(1_000)
+        .wal(WalSync::Never)
+        .limits(limits)
+        .quota("venues", quota)
+        .allow_parallel_edges(true);
+    assert_eq!(builder.config().capacity, 1_000);
+    let mut db = builder.open().unwrap();
+    assert_eq!(db.query_limits(), &limits);
+    assert_eq!(db.quota("venues"), Some(&quota));
+    assert!(db.allow_parallel_edges());
+    db.put("venues/a", r#"{"_collection":"venues"}"#).unwrap();
+    assert!(db.put("venues/b", r#"{"_collection":"venues"}"#).is_err());
+    drop(db);
+
+    let db = CoreDB::builder(dir.path()).read_only(true).open().unwrap();
+    assert!(db.contains("venues/a"));
+}
+
+#[test]
+fn reconfigure_changes_live_settings() {
+    use sekejap::{ConfigUpdate, Quota, WalSync};
+    let dir = tmpdir();
+    let mut db = CoreDB::builder(dir.path()).wal(WalSync::Batch { max_entries: 1_000 }).open().unwrap();
+    db.put("logs/1", r#"{"_collection":"logs","v":1}"#).unwrap();
+    db.link("logs/1", "logs/1", "self", 1.0);
+
+    let update = ConfigUpdate {
+        wal: Some(WalSync::Always),
+        quotas: [("logs".to_string(), Quota { max_nodes: Some(1), ..Default::default() })].into(),
+        history_retention: [("logs".to_string(), 2)].into(),
+        allow_parallel_edges: Some(true),
+        ..Default::default()
+    };
+    db.reconfigure(update).unwrap();
+    assert_eq!(db.wal_sync(), WalSync::Always);
+    assert_eq!(db.query_limits(), &Default::default(), "unset fields are left alone");
+    assert!(db.put("logs/2", r#"{"_collection":"logs"}"#).is_err());
+    db.put("logs/1", r#"{"_collection":"logs","v":2}"#).unwrap();
+    assert_eq!(db.history("logs/1").len(), 1);
+    db.link("logs/1", "logs/1", "self", 0.5);
+    assert_eq!(db.edges_from("logs/1").len(), 2);
+
+    db.reconfigure(ConfigUpdate { quotas: [("logs".to_string(), Quota::default())].into(), ..Default::default() })
+        .unwrap();
+    db.put("logs/2", r#"{"_collection":"logs"}"#).unwrap();
+}
+
+#[test]
+fn deferred_index_rebuild_reports_stages_and_catches_up_on_writes() {
+    use sekejap::{Config, OpenStage};
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.execute("CREATE TABLE venues (name TEXT)").unwrap();
+        db.put("venues/v1", r#"{"_collection":"venues","name":"Rod Laver Arena"}"#).unwrap();
+        db.execute("CREATE INDEX ON venues USING gin (name)").unwrap();
+        db.compact().unwrap();
+    }
+
+    let mut stages = Vec::new();
+    let config = Config { defer_index_rebuild: true, ..Config::default() };
+    let mut db = CoreDB::open_with_progress(dir.path(), config, |s| stages.push(s)).unwrap();
+    assert_eq!(stages, vec![OpenStage::Snapshot, OpenStage::WalReplay, OpenStage::SpatialGrid, OpenStage::Vectors]);
+    assert!(db.has_pending_indexes());
+    assert_eq!(db.query("SELECT * FROM venues").unwrap().count(), 1);
+
+    db.put("venues/v2", r#"{"_collection":"venues","name":"Laver Lane"}"#).unwrap();
+    db.rebuild_pending_indexes();
+    assert!(!db.has_pending_indexes());
+    assert_eq!(db.gin_ilike("name", "%laver%", None).len(), 2);
+}
+
+#[test]
+fn compact_persists_adjacency_and_spatial_grid_sidecars() {
+    let dir = tmpdir();
+    let place = |lat: f64, lon: f64| {
+        format!(r#"{{"_collection":"places","geometry":{{"type":"Point","coordinates":[{lon},{lat}]}}}}"#)
+    };
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("p/a", &place(-37.81, 144.96)).unwrap();
+        db.put("p/b", &place(-37.82, 144.97)).unwrap();
+        db.link("p/a", "p/b", "near", 0.5);
+        db.link_meta("p/b", "p/a", "near", 0.7, r#"{"km":1.4}"#).unwrap();
+        db.compact().unwrap();
+        // Written after the snapshot: replayed on top of the loaded sidecars.
+        db.put("p/c", &place(-37.815, 144.965)).unwrap();
+        db.link("p/c", "p/a", "near", 0.9);
+    }
+    let snap: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join("snapshot.json")).unwrap()).unwrap();
+    assert_eq!(snap["edges"], serde_json::json!([]));
+    assert!(dir.path().join("edges.bin").exists() && dir.path().join("spatial.bin").exists());
+
+    // Simulate a crash between the snapshot rename and the edges.bin rename.
+    std::fs::rename(dir.path().join("edges.bin"), dir.path().join("edges.bin.tmp")).unwrap();
+
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(db.edge_count(), 3);
+    assert_eq!(db.edges_from("p/b")[0].meta, Some(serde_json::json!({"km":1.4})));
+    assert_eq!(db.edges_to("p/a").len(), 2);
+    assert_eq!(db.collection("places").near(-37.815, 144.965, 5.0).count(), 3);
+    assert!(db.spatial_stats().is_consistent());
+    assert!(dir.path().join("edges.bin").exists());
+}
+
+#[test]
+fn health_reports_wal_corruption_backlog_and_pending_indexes() {
+    use sekejap::{Config, HealthStatus, HealthThresholds};
+    use std::io::Write;
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("a", r#"{"n":1}"#).unwrap();
+        assert_eq!(db.health().status, HealthStatus::Ok);
+        let tight = HealthThresholds { wal_backlog_bytes: 1, ..Default::default() };
+        let health = db.health_with(&tight);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert!(health.reasons[0].contains("WAL backlog"));
+    }
+    // A frame with a bad CRC at the tail of the WAL.
+    let mut wal = std::fs::OpenOptions::new().append(true).open(dir.path().join("wal.log")).unwrap();
+    wal.write_all(&[0, 0, 0, 0, 4, 0, 0, 0, b'j', b'u', b'n', b'k']).unwrap();
+    drop(wal);
+
+    let config = Config { defer_index_rebuild: true, ..Default::default() };
+    let mut db = CoreDB::open_with_config(dir.path(), config).unwrap();
+    assert!(db.get("a").is_some());
+    let health = db.health();
+    assert_eq!(health.status, HealthStatus::Unhealthy);
+    assert_eq!(health.reasons.len(), 2);
+    assert!(health.reasons[0].contains("corrupted") && health.reasons[1].contains("index rebuild"));
+
+    db.rebuild_pending_indexes();
+    db.compact().unwrap();
+    assert_eq!(db.health().status, HealthStatus::Ok);
+}
+
+#[test]
+fn node_history_survives_replay_compact_and_reopen() {
+    use sekejap::Config;
+    let dir = tmpdir();
+    let config = || Config {
+        history_retention: [("docs".to_string(), 2)].into_iter().collect(),
+        ..Config::default()
+    };
+    let revs = |db: &CoreDB| -> Vec<i64> {
+        db.history("docs/a").iter().map(|v| v.payload["rev"].as_i64().unwrap()).collect()
+    };
+
+    {
+        let mut db = CoreDB::open_with_config(dir.path(), config()).unwrap();
+        for rev in 1..=3 {
+            db.put("docs/a", &format!(r#"{{"_collection":"docs","rev":{rev}}}"#)).unwrap();
+        }
+        db.put("notes/n", r#"{"_collection":"notes","rev":1}"#).unwrap();
+        db.put("notes/n", r#"{"_collection":"notes","rev":2}"#).unwrap();
+        assert_eq!(revs(&db), [2, 1]);
+        assert!(db.history("notes/n").is_empty());
+        assert!(db.history("docs/a").iter().all(|v| v.updated_unix > 0));
+    }
+    {
+        // WAL replay rebuilds the retained versions.
+        let mut db = CoreDB::open_with_config(dir.path(), config()).unwrap();
+        assert_eq!(revs(&db), [2, 1]);
+        db.compact().unwrap();
+        assert_eq!(revs(&db), [2, 1]);
+        db.put("docs/a", r#"{"_collection":"docs","rev":4}"#).unwrap();
+        assert_eq!(revs(&db), [3, 2]);
+        db.compact().unwrap();
+    }
+    let mut db = CoreDB::open_with_config(dir.path(), config()).unwrap();
+    assert_eq!(revs(&db), [3, 2]);
+    assert_eq!(db.get("docs/a").map(|p| p.contains(r#""rev":4"#)), Some(true));
+
+    db.remove("docs/a");
+    assert!(db.history("docs/a").is_empty());
+}
+
+#[test]
+fn edge_aggregate_survives_replay_and_compact() {
+    use sekejap::EdgeAggregate;
+    let dir = tmpdir();
+    let count = |db: &CoreDB| -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&db.get("geo/d1").unwrap()).unwrap()["incident_count"].clone()
+    };
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("geo/d1", r#"{"_collection":"geo"}"#).unwrap();
+        db.set_edge_aggregate("geo", "incident_count", Some(EdgeAggregate::Count {
+            edge_type: "located_in".into(),
+            incoming: true,
+        }));
+        db.put("inc/1", "{}").unwrap();
+        db.link("inc/1", "geo/d1", "located_in", 1.0);
+    }
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        assert_eq!(count(&db), 1);
+        db.compact().unwrap();
+    }
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    assert_eq!(count(&db), 1);
+    db.put("inc/2", "{}").unwrap();
+    db.link("inc/2", "geo/d1", "located_in", 1.0);
+    assert_eq!(count(&db), 2);
+}
+
+#[test]
+fn projection_profile_survives_replay_and_compact() {
+    let dir = tmpdir();
+    let secret_hidden = |db: &CoreDB| {
+        let hit = db.collection("users").profile("public").unwrap().first().unwrap();
+        hit.payload.unwrap().get("secret").is_none()
+    };
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("u/1", r#"{"_collection":"users","secret":"s1"}"#).unwrap();
+        db.set_projection_profile("users", "public", Some(vec!["secret".into()]));
+    }
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        assert!(secret_hidden(&db));
+        db.compact().unwrap();
+    }
+    let db = CoreDB::open(dir.path()).unwrap();
+    assert!(secret_hidden(&db));
+}
+
+#[test]
+fn corrupt_payloads_are_reported_and_quarantined() {
+    use sekejap::PayloadFault;
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("u/good", r#"{"_collection":"users","name":"good"}"#).unwrap();
+        db.put("u/utf8", r#"{"_collection":"users","name":"AAAA"}"#).unwrap();
+        db.put("u/json", r#"{"_collection":"users","name":"BBBB"}"#).unwrap();
+        db.link("u/good", "u/json", "knows", 1.0);
+        db.compact().unwrap();
+    }
+    // Damage two blobs in place, as a bad disk would.
+    let path = dir.path().join("payloads.bin");
+    let mut bytes = std::fs::read(&path).unwrap();
+    let find = |bytes: &[u8], needle: &[u8]| bytes.windows(needle.len()).position(|w| w == needle).unwrap();
+    let a = find(&bytes, b"AAAA");
+    bytes[a + 1] = 0xff;
+    let b = find(&bytes, b"BBBB");
+    bytes[b + 4] = b'}';
+    std::fs::write(&path, bytes).unwrap();
+
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    let bad = db.verify_payloads();
+    assert_eq!(bad.iter().map(|e| e.slug.as_str()).collect::<Vec<_>>(), ["u/json", "u/utf8"]);
+    assert!(matches!(bad[0].fault, PayloadFault::InvalidJson(_)));
+    let utf8 = db.get_strict("u/utf8").unwrap_err();
+    assert_eq!(utf8.fault, PayloadFault::InvalidUtf8 { valid_up_to: a + 1 - utf8.offset as usize });
+    assert!(utf8.to_string().contains(&format!("at byte {}", a + 1)), "{utf8}");
+    assert!(db.get_strict("u/good").unwrap().is_some());
+    assert!(db.get_strict("u/missing").unwrap().is_none());
+
+    assert_eq!(db.quarantine_bad_payloads().len(), 2);
+    assert!(db.verify_payloads().is_empty());
+    assert_eq!(db.collection("users").count(), 1);
+    assert_eq!(db.edge_count(), 0);
+    assert!(!db.restore("u/json"), "unparsable records stay in the trash");
+    assert!(db.purge("u/json"));
+    assert!(db.quarantine_bad_payloads().is_empty());
+
+    // A blob cut off by a short file cannot be read at all; it is trashed
+    // all the same.
+    let dir = tmpdir();
+    {
+        let mut db = CoreDB::open(dir.path()).unwrap();
+        db.put("u/gone", r#"{"_collection":"users","name":"CCCC"}"#).unwrap();
+        db.compact().unwrap();
+    }
+    let path = dir.path().join("payloads.bin");
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    let bad = db.quarantine_bad_payloads();
+    assert_eq!(bad.len(), 1);
+    assert_eq!(bad[0].fault, PayloadFault::Unreadable);
+    assert!(!db.contains("u/gone") && db.is_trashed("u/gone"));
+    assert!(!db.restore("u/gone"));
+    assert!(db.quarantine_bad_payloads().is_empty());
+}
+
+#[test]
+fn read_only_replica_never_writes_and_refreshes_from_the_writer() {
+    let dir = tmpdir();
+    let listing = || {
+        let mut files: Vec<(String, Vec<u8>)> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap())
+            .filter(|e| e.file_type().unwrap().is_file())
+            .map(|e| (e.file_name().to_string_lossy().into_owned(), std::fs::read(e.path()).unwrap()))
+            .collect();
+        files.sort();
+        files
+    };
+
+    let mut writer = CoreDB::open(dir.path()).unwrap();
+    writer.put("a", r#"{"_collection":"t","n":1}"#).unwrap();
+    writer.link_meta("a", "a", "self", 1.0, r#"{"w":1}"#).unwrap();
+    writer.ingest_vectors("emb", vec![("a".to_string(), vec![1.0, 0.0])]);
+    writer.compact().unwrap();
+    // Written after the snapshot, so the replica replays them from the WAL.
+    writer.put("b", r#"{"_collection":"t","n":2}"#).unwrap();
+    writer.link_meta("a", "b", "next", 1.0, r#"{"w":2}"#).unwrap();
+    writer.sync().unwrap();
+
+    let before = listing();
+    let mut reader = CoreDB::open_read_only(dir.path()).unwrap();
+    assert_eq!(listing(), before, "opening a replica must not touch the writer's files");
+    assert!(reader.get("b").unwrap().contains(r#""n":2"#));
+    assert_eq!(reader.edges_from("a").len(), 2);
+    assert_eq!(reader.all().vector_near("emb", vec![1.0, 0.0], 1).collect()[0].slug, "a");
+    assert!(reader.compact().is_err());
+    assert!(!reader.refresh().unwrap());
+
+    writer.put("c", r#"{"_collection":"t","n":3}"#).unwrap();
+    writer.sync().unwrap();
+    assert!(reader.refresh().unwrap());
+    assert_eq!(reader.query("SELECT * FROM t").unwrap().count(), 3);
+
+    writer.remove("a");
+    writer.compact().unwrap();
+    assert!(reader.refresh().unwrap());
+    assert!(!reader.contains("a"));
+    assert!(reader.contains("c"));
+
+    assert!(CoreDB::new().refresh().is_err());
+}
+
+#[test]
+fn binary_backup_restores_everything_and_rejects_damage() {
+    let (live, backup) = (tmpdir(), tmpdir());
+    let mut db = CoreDB::open(live.path()).unwrap();
+    db.execute("CREATE TABLE docs (title TEXT)").unwrap();
+    db.put("docs/a", r#"{"_collection":"docs","title":"alpha"}"#).unwrap();
+    db.put("docs/b", r#"{"_collection":"docs","title":"beta"}"#).unwrap();
+    db.link_meta("docs/a", "docs/b", "cites", 0.5, r#"{"page":3}"#).unwrap();
+    db.ingest_vectors("emb", vec![("docs/a".to_string(), vec![1.0, 0.0]), ("docs/b".to_string(), vec![0.0, 1.0])]);
+    db.build_hnsw_index("emb", 16, 100).unwrap();
+    db.compact().unwrap();
+    db.put("docs/c", r#"{"_collection":"docs","title":"gamma"}"#).unwrap();
+
+    let manifest = db.backup_to(backup.path()).unwrap();
+    assert!(manifest.files.iter().any(|f| f.name == "payloads.bin"));
+    assert!(manifest.files.iter().all(|f| f.name != "db.lock"));
+    drop(db);
+
+    let restored = tmpdir();
+    CoreDB::restore_snapshot(backup.path(), restored.path()).unwrap();
+    let copy = CoreDB::open(restored.path()).unwrap();
+    assert_eq!(copy.node_count(), 3);
+    assert!(copy.table_schema("docs").is_some());
+    assert_eq!(copy.edges_from("docs/a")[0].meta.as_ref().unwrap()["page"], 3);
+    let near = copy.all().vector_near("emb", vec![0.0, 1.0], 1).collect();
+    assert_eq!(near[0].slug, "docs/b");
+
+    let err = CoreDB::restore_snapshot(backup.path(), restored.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    let payloads = backup.path().join("payloads.bin");
+    let mut bytes = std::fs::read(&payloads).unwrap();
+    bytes[0] ^= 0xff;
+    std::fs::write(&payloads, bytes).unwrap();
+    let err = CoreDB::restore_snapshot(backup.path(), tmpdir().path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("payloads.bin"), "{err}");
+
+    assert!(CoreDB::new().backup_to(tmpdir().path()).is_err());
+}
+
+#[test]
+fn incremental_backups_chain_across_reopen() {
+    let (live, files) = (tmpdir(), tmpdir());
+    let file = |name: &str| files.path().join(name);
+    let mut db = CoreDB::open(live.path()).unwrap();
+    assert_eq!(db.backup_incremental(file("x"), 5).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    db.put("docs/a", r#"{"_collection":"docs","v":1}"#).unwrap();
+    db.put("docs/b", r#"{"_collection":"docs","v":1}"#).unwrap();
+    db.put("docs/c", r#"{"_collection":"docs","v":1}"#).unwrap();
+    db.link("docs/a", "docs/b", "cites", 0.5);
+    db.link("docs/c", "docs/a", "cites", 0.5);
+    let full = db.backup_incremental(file("full"), 0).unwrap();
+    db.compact().unwrap();
+
+    // Day one: an edit, an upserted edge, an unlink, a removal, a new node.
+    db.put("docs/a", r#"{"_collection":"docs","v":2}"#).unwrap();
+    db.link("docs/a", "docs/b", "cites", 0.9);
+    db.unlink("docs/c", "docs/a", "cites");
+    db.remove("docs/b");
+    db.put("docs/d", r#"{"_collection":"docs","v":1}"#).unwrap();
+    db.link("docs/d", "docs/a", "cites", 0.3);
+    drop(db);
+
+    // The checkpoint survives a reopen that replays the WAL.
+    let mut db = CoreDB::open(live.path()).unwrap();
+    let day1 = db.backup_incremental(file("day1"), full).unwrap();
+    assert!(day1 > full);
+    db.execute("CREATE INDEX ON docs USING btree (v)").unwrap();
+    db.put("docs/e", r#"{"_collection":"docs","v":3}"#).unwrap();
+    let day2 = db.backup_incremental(file("day2"), day1).unwrap();
+    assert_eq!(db.backup_incremental(file("x"), full).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(db.backup_incremental(file("x"), day2 + 1).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    let mut copy = CoreDB::new();
+    for name in ["full", "day1", "day2"] {
+        copy.restore_incremental(file(name)).unwrap();
+    }
+    let mut slugs: Vec<String> = copy.collection("docs").collect().into_iter().map(|h| h.slug).collect();
+    slugs.sort();
+    assert_eq!(slugs, ["docs/a", "docs/c", "docs/d", "docs/e"]);
+    let a: serde_json::Value = serde_json::from_str(&copy.get("docs/a").unwrap()).unwrap();
+    assert_eq!(a["v"], 2);
+    assert!(copy.edges_from("docs/c").is_empty());
+    let into_a = copy.edges_to("docs/a");
+    assert_eq!((into_a.len(), into_a[0].from_slug.as_deref(), into_a[0].strength), (1, Some("docs/d"), 0.3));
+    assert_eq!(copy.query("SELECT * FROM docs WHERE v = 3").unwrap().count(), 1);
+    let day2_len = std::fs::metadata(file("day2")).unwrap().len();
+    assert!(day2_len < std::fs::metadata(file("full")).unwrap().len());
+}
+
+/// A [`sekejap::WalStore`] in shared memory, standing in for a browser
+/// store that outlives the database handle.
+#[derive(Clone, Default)]
+struct SharedLog(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl sekejap::WalStore for SharedLog {
+    fn append(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.0.lock().unwrap().extend_from_slice(frame);
+        Ok(())
+    }
+    fn sync(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+    fn read_all(&mut self) -> std::io::Result<Vec<u8>> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+    fn replace(&mut self, log: &[u8]) -> std::io::Result<()> {
+        *self.0.lock().unwrap() = log.to_vec();
+        Ok(())
+    }
+}
+
+#[test]
+fn wal_store_replays_writes_and_compacts() {
+    use sekejap::HealthStatus;
+    let log = SharedLog::default();
+    {
+        let mut db = CoreDB::open_with_wal_store(Box::new(log.clone())).unwrap();
+        db.execute("CREATE TABLE cafes (name TEXT, rating INTEGER)").unwrap();
+        db.execute("CREATE INDEX ON cafes USING btree (rating)").unwrap();
+        for i in 0..20 {
+            db.put(&format!("cafes/{i}"), &format!(r#"{{"_collection":"cafes","name":"c{i}","rating":{}}}"#, i % 5))
+                .unwrap();
+        }
+        db.link("cafes/1", "cafes/2", "near", 0.5);
+        db.remove("cafes/19");
+        let mut txn = db.begin();
+        txn.put("cafes/20", r#"{"_collection":"cafes","name":"c20","rating":4}"#).unwrap();
+        txn.commit().unwrap();
+    }
+
+    let db = CoreDB::open_with_wal_store(Box::new(log.clone())).unwrap();
+    assert_eq!(db.collection("cafes").count(), 20);
+    assert_eq!(db.query("SELECT * FROM cafes WHERE rating = 4").unwrap().count(), 4);
+    assert_eq!(db.edges_from("cafes/1").len(), 1);
+    drop(db);
+
+    // A torn last frame is dropped; everything before it survives.
+    let full = log.0.lock().unwrap().clone();
+    log.0.lock().unwrap().truncate(full.len() - 3);
+    let mut db = CoreDB::open_with_wal_store(Box::new(log.clone())).unwrap();
+    assert!(db.contains("cafes/18") && !db.contains("cafes/20"));
+    assert_eq!(db.health().status, HealthStatus::Unhealthy);
+
+    db.put("cafes/20", r#"{"_collection":"cafes","name":"c20","rating":4}"#).unwrap();
+    db.remove("cafes/0");
+    for _ in 0..3 {
+        for i in 1..19 {
+            db.put(&format!("cafes/{i}"), &format!(r#"{{"_collection":"cafes","name":"c{i}","rating":{}}}"#, i % 5))
+                .unwrap();
+        }
+    }
+    let before = log.0.lock().unwrap().len();
+    db.compact().unwrap();
+    assert!(log.0.lock().unwrap().len() < before);
+    assert_eq!(db.health().status, HealthStatus::Ok);
+    drop(db);
+
+    let db = CoreDB::open_with_wal_store(Box::new(log)).unwrap();
+    assert_eq!(db.collection("cafes").count(), 19);
+    assert_eq!(db.query("SELECT * FROM cafes WHERE rating = 4").unwrap().count(), 4);
+    assert_eq!(db.edges_from("cafes/1").len(), 1);
+}
+
+#[test]
+fn jobs_report_progress_completion_and_cancellation() {
+    use sekejap::{JobKind, JobState};
+    use std::sync::{Arc, Mutex};
+
+    let (dir, backup) = (tmpdir(), tmpdir());
+    let mut db = CoreDB::open(dir.path()).unwrap();
+    for i in 0..30 {
+        db.put(&format!("docs/{i}"), &format!(r#"{{"_collection":"docs","n":{i}}}"#)).unwrap();
+    }
+    db.ingest_vectors("emb", (0..40).map(|i| (format!("docs/{i}"), vec![i as f32, 1.0, 0.5])));
+    let jobs = db.jobs();
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&finished);
+    jobs.on_complete(move |job| seen.lock().unwrap().push((job.kind, job.state.clone())));
+
+    db.build_hnsw_index("emb", 8, 50).unwrap();
+    // Cancelled partway, a rebuild keeps the graph it would have replaced.
+    let err = db
+        .build_hnsw_with("emb", 8, 50, |done, _| {
+            if done == 10 {
+                let running = jobs.list().into_iter().find(|j| !j.state.is_finished()).unwrap();
+                assert!(jobs.cancel(running.id));
+            }
+        })
+        .unwrap_err();
+    assert!(err.contains("cancelled"), "{err}");
+    assert_eq!(db.hnsw_fields(), ["emb"]);
+    let cancelled = jobs.list().pop().unwrap();
+    assert_eq!((cancelled.state, cancelled.done, cancelled.total), (JobState::Cancelled, 10, 40));
+
+    let manifest = db.backup_to(backup.path()).unwrap();
+    let copied: u64 = manifest.files.iter().map(|f| f.len).sum();
+    let last = jobs.list().pop().unwrap();
+    assert_eq!((last.kind, last.done, last.total), (JobKind::Backup, copied, copied));
+    assert_eq!(last.target, backup.path().display().to_string());
+    db.compact().unwrap();
+
+    assert_eq!(
+        *finished.lock().unwrap(),
+        [
+            (JobKind::HnswBuild, JobState::Succeeded),
+            (JobKind::HnswBuild, JobState::Cancelled),
+            (JobKind::Backup, JobState::Succeeded),
+            (JobKind::Compact, JobState::Succeeded),
+        ]
+    );
+    let ids: Vec<u64> = jobs.list().iter().map(|j| j.id).collect();
+    assert_eq!(ids, [1, 2, 3, 4]);
+}
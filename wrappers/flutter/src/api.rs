@@ -1,7 +1,7 @@
 //! Public API surface bridged to Dart by flutter_rust_bridge.
 
 use flutter_rust_bridge::frb;
-use sekejap::CoreDB;
+use sekejap::{CoreDB, EdgeInsert, Hit, HitJsonOptions, JobRequest, Jobs};
 use serde_json::Value;
 use std::sync::Mutex;
 
@@ -16,22 +16,30 @@ pub fn init_app() {
 /// An open sekejap database instance.
 ///
 /// Created via [`db_open`] or [`db_new`]. Freed automatically when the Dart
-/// object is garbage-collected.
+/// object is garbage-collected. The job registry sits beside the mutex so
+/// [`db_jobs`] and [`db_cancel_job`] answer while a job holds the database.
 #[frb(opaque)]
-pub struct SekejapDb(Mutex<CoreDB>);
+pub struct SekejapDb(Mutex<CoreDB>, Jobs);
+
+impl SekejapDb {
+    fn wrap(db: CoreDB) -> Self {
+        let jobs = db.jobs();
+        SekejapDb(Mutex::new(db), jobs)
+    }
+}
 
 // ── Lifecycle ──────────────────────────────────────────────────────────────────
 
 /// Open a persistent database at `path` (a directory on the filesystem).
 pub fn db_open(path: String) -> Result<SekejapDb, String> {
     CoreDB::open(&path)
-        .map(|db| SekejapDb(Mutex::new(db)))
+        .map(SekejapDb::wrap)
         .map_err(|e| e.to_string())
 }
 
 /// Create an in-memory (non-persistent) database.
 pub fn db_new() -> SekejapDb {
-    SekejapDb(Mutex::new(CoreDB::new()))
+    SekejapDb::wrap(CoreDB::new())
 }
 
 // ── Mutations ──────────────────────────────────────────────────────────────────
@@ -62,6 +70,42 @@ pub fn db_link(db: &SekejapDb, from: String, to: String, edge_type: String, stre
     db.0.lock().unwrap().link(&from, &to, &edge_type, strength);
 }
 
+/// Create many edges with a single WAL sync.
+/// `edges_json` is a JSON array of
+/// `{"from":"a","to":"b","edge_type":"knows","strength":1.0,"meta":{...}}`;
+/// `strength` defaults to 1.0 and `meta` is optional.
+/// Returns the number of edges created.
+pub fn db_link_many(db: &SekejapDb, edges_json: String) -> Result<usize, String> {
+    let rows: Vec<Value> = serde_json::from_str(&edges_json)
+        .map_err(|e| format!("invalid edges JSON: {e}"))?;
+    let mut edges = Vec::with_capacity(rows.len());
+    for row in rows {
+        let field = |k: &str| row.get(k).and_then(Value::as_str).map(str::to_string)
+            .ok_or_else(|| format!("edge is missing \"{k}\": {row}"));
+        edges.push(EdgeInsert {
+            from: field("from")?,
+            to: field("to")?,
+            edge_type: field("edge_type")?,
+            strength: row.get("strength").and_then(Value::as_f64).unwrap_or(1.0) as f32,
+            props_json: row.get("meta").filter(|m| !m.is_null()).map(Value::to_string),
+        });
+    }
+    db.0.lock().unwrap()
+        .link_many(edges)
+        .map_err(|e| e.to_string())
+}
+
+/// Apply a mutation document or a `{"mutations":[...]}` batch (add
+/// `"atomic":true` for all-or-nothing). Returns a JSON array with one entry
+/// per document: `null` if applied, else the error message.
+pub fn db_mutate_json(db: &SekejapDb, json: String) -> Result<String, String> {
+    let results = db.0.lock().unwrap()
+        .mutate_json(&json)
+        .map_err(|e| e.to_string())?;
+    let out: Vec<Option<String>> = results.into_iter().map(Result::err).collect();
+    serde_json::to_string(&out).map_err(|e| e.to_string())
+}
+
 /// Remove a directed edge between two nodes.
 pub fn db_unlink(db: &SekejapDb, from: String, to: String, edge_type: String) {
     db.0.lock().unwrap().unlink(&from, &to, &edge_type);
@@ -76,10 +120,45 @@ pub fn db_query(db: &SekejapDb, sql: String) -> Result<String, String> {
         .query(&sql)
         .map_err(|e| format!("{e:?}"))?
         .collect();
-    let rows: Vec<_> = hits.into_iter().map(|h| {
-        serde_json::json!({ "slug": h.slug, "payload": h.payload })
-    }).collect();
-    Ok(serde_json::to_string(&rows).unwrap())
+    Ok(hits_json(&hits, &HitJsonOptions::default()))
+}
+
+/// Run a SELECT or MATCH query, shaping each row with `options_json` — a
+/// `HitJsonOptions` object such as `{"payload":false,"lift":["score"]}`.
+/// Omitted options keep their defaults.
+pub fn db_query_shaped(db: &SekejapDb, sql: String, options_json: String) -> Result<String, String> {
+    let options: HitJsonOptions = serde_json::from_str(&options_json)
+        .map_err(|e| format!("invalid options JSON: {e}"))?;
+    let hits = db.0.lock().unwrap()
+        .query(&sql)
+        .map_err(|e| format!("{e:?}"))?
+        .collect();
+    Ok(hits_json(&hits, &options))
+}
+
+/// Run a SELECT or MATCH query and time each step.
+/// Returns `{"rows":[...],"trace":{"elapsed_us":...,"steps":[...]}}`, each
+/// step carrying `rows_in`, `rows_out` and `elapsed_us`.
+pub fn db_query_with_trace(db: &SekejapDb, sql: String) -> Result<String, String> {
+    let (hits, trace) = db.0.lock().unwrap()
+        .query(&sql)
+        .map_err(|e| format!("{e:?}"))?
+        .collect_traced();
+    let rows: Vec<Value> = hits.iter().map(|h| h.to_json(&HitJsonOptions::default())).collect();
+    Ok(serde_json::json!({ "rows": rows, "trace": trace.to_json() }).to_string())
+}
+
+/// The plan of a SELECT without running it.
+/// Returns a JSON array: `[{"step":"...","detail":"...","index":"btree"}, ...]`,
+/// with `index` present only on steps answered from an index.
+pub fn db_explain(db: &SekejapDb, sql: String) -> Result<String, String> {
+    let plan: Vec<Value> = db.0.lock().unwrap()
+        .explain(&sql)
+        .map_err(|e| format!("{e:?}"))?
+        .into_iter()
+        .filter_map(|h| h.payload)
+        .collect();
+    Ok(Value::Array(plan).to_string())
 }
 
 /// Run a SELECT or MATCH query with parameter bindings ($1, $2, …).
@@ -92,10 +171,7 @@ pub fn db_query_params(db: &SekejapDb, sql: String, params_json: String) -> Resu
         .query_params(&sql, &params)
         .map_err(|e| format!("{e:?}"))?
         .collect();
-    let rows: Vec<_> = hits.into_iter().map(|h| {
-        serde_json::json!({ "slug": h.slug, "payload": h.payload })
-    }).collect();
-    Ok(serde_json::to_string(&rows).unwrap())
+    Ok(hits_json(&hits, &HitJsonOptions::default()))
 }
 
 /// Run a DDL/DML statement with parameter bindings ($1, $2, …).
@@ -124,10 +200,7 @@ pub fn db_show(db: &SekejapDb, sql: String) -> Result<String, String> {
     let hits = db.0.lock().unwrap()
         .show(&sql)
         .map_err(|e| format!("{e:?}"))?;
-    let rows: Vec<_> = hits.into_iter().map(|h| {
-        serde_json::json!({ "slug": h.slug, "payload": h.payload })
-    }).collect();
-    Ok(serde_json::to_string(&rows).unwrap())
+    Ok(hits_json(&hits, &HitJsonOptions::default()))
 }
 
 // ── Maintenance ────────────────────────────────────────────────────────────────
@@ -141,3 +214,33 @@ pub fn db_compact(db: &SekejapDb) -> Result<(), String> {
 pub fn db_sync(db: &SekejapDb) -> Result<(), String> {
     db.0.lock().unwrap().sync().map_err(|e| e.to_string())
 }
+
+/// Run a long operation as a job and return the finished job as JSON:
+/// `{"id","kind","target","state","done","total","progress","elapsed_ms"}`,
+/// plus `"error"` when it failed. `spec_json` is one of
+/// `{"kind":"backup","dest":dir}`, `{"kind":"restore","path":file}`,
+/// `{"kind":"compact"}`, `{"kind":"hnsw_build","field":f}` or
+/// `{"kind":"index_rebuild"}`.
+pub fn db_run_job(db: &SekejapDb, spec_json: String) -> Result<String, String> {
+    let spec: Value = serde_json::from_str(&spec_json).map_err(|e| format!("invalid job JSON: {e}"))?;
+    let request = JobRequest::from_json(&spec)?;
+    Ok(db.0.lock().unwrap().run_job(request).to_json().to_string())
+}
+
+/// Running and recent jobs as a JSON array, oldest first, including those
+/// started by direct calls such as [`db_compact`].
+pub fn db_jobs(db: &SekejapDb) -> String {
+    Value::Array(db.1.list().iter().map(|j| j.to_json()).collect()).to_string()
+}
+
+/// Ask job `id` to stop at its next safe point. Returns whether it was
+/// still running.
+pub fn db_cancel_job(db: &SekejapDb, id: u64) -> bool {
+    db.1.cancel(id)
+}
+
+/// Serialise query results as a JSON array of [`Hit::to_json`] objects.
+fn hits_json(hits: &[Hit], options: &HitJsonOptions) -> String {
+    let rows: Vec<Value> = hits.iter().map(|h| h.to_json(options)).collect();
+    serde_json::to_string(&rows).unwrap()
+}
@@ -0,0 +1,432 @@
+//! Node.js bindings for sekejap, built with napi-rs.
+//!
+//! Mirrors the Python wrapper: the database sits behind a mutex, every
+//! method is callable from JavaScript, and the `…Async` variants run on the
+//! libuv threadpool and return a `Promise`, so a large query or ingest does
+//! not block the event loop. Calls from several promises serialise on the
+//! mutex; `jobsJson` and `cancelJob` do not take it, so a `runJobAsync` in
+//! flight can be watched and stopped.
+//!
+//! ```js
+//! const { SekejapDB } = require('sekejap')
+//! const db = new SekejapDB('./data')          // omit the path for in-memory
+//! db.put('venues/corner', '{"_collection":"venues","suburb":"Richmond"}')
+//! const rows = JSON.parse(await db.queryJsonAsync('SELECT * FROM venues'))
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+
+use ::sekejap::{CoreDB, HitJsonOptions, JobRequest, Jobs};
+
+type Shared = Arc<Mutex<Option<CoreDB>>>;
+
+fn db_err(e: impl std::fmt::Display) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+/// Run `f` on the open database behind `db`.
+fn with_db<T>(db: &Shared, f: impl FnOnce(&mut CoreDB) -> Result<T>) -> Result<T> {
+    // A panic inside a call leaves the database itself usable.
+    let mut guard = db.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.as_mut().ok_or_else(|| Error::from_reason("DB is closed"))?)
+}
+
+/// Parse an optional JSON array of query parameters (`$1`, `$2`, …).
+fn parse_params(params_json: Option<&str>) -> Result<Vec<Value>> {
+    match params_json {
+        Some(json) => serde_json::from_str(json).map_err(db_err),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Run `sql`, applying projection `profile` if given.
+fn query_set<'a>(db: &'a CoreDB, sql: &str, params: &[Value], profile: Option<&str>) -> Result<::sekejap::Set<'a>> {
+    let set = db.query_params(sql, params).map_err(db_err)?;
+    match profile {
+        Some(p) => set.profile(p).map_err(db_err),
+        None => Ok(set),
+    }
+}
+
+fn query_json(db: &CoreDB, sql: &str, params: &[Value], profile: Option<&str>) -> Result<String> {
+    let options = HitJsonOptions::default();
+    let rows: Vec<Value> = query_set(db, sql, params, profile)?
+        .collect()
+        .iter()
+        .map(|h| h.to_json(&options))
+        .collect();
+    Ok(Value::Array(rows).to_string())
+}
+
+fn query_json_with_trace(
+    db: &CoreDB,
+    sql: &str,
+    params: &[Value],
+    profile: Option<&str>,
+    correlation_id: Option<String>,
+) -> Result<String> {
+    let options = HitJsonOptions::default();
+    let set = query_set(db, sql, params, profile)?;
+    let set = match correlation_id {
+        Some(id) => set.correlation_id(id),
+        None => set,
+    };
+    let (hits, trace) = set.collect_traced();
+    let rows: Vec<Value> = hits.iter().map(|h| h.to_json(&options)).collect();
+    Ok(serde_json::json!({ "rows": rows, "trace": trace.to_json() }).to_string())
+}
+
+fn explain_json(db: &CoreDB, sql: &str) -> Result<String> {
+    let plan: Vec<Value> = db.explain(sql).map_err(db_err)?
+        .into_iter()
+        .filter_map(|h| h.payload)
+        .collect();
+    Ok(Value::Array(plan).to_string())
+}
+
+fn parse_job(spec_json: &str) -> Result<JobRequest> {
+    let spec: Value = serde_json::from_str(spec_json).map_err(db_err)?;
+    JobRequest::from_json(&spec).map_err(db_err)
+}
+
+/// A count as a JavaScript number; exact up to 2^53.
+fn js_count(n: usize) -> f64 {
+    n as f64
+}
+
+fn execute(db: &mut CoreDB, sql: &str, params: &[Value]) -> Result<f64> {
+    let n = if params.is_empty() { db.execute(sql) } else { db.execute_params(sql, params) };
+    Ok(js_count(n.map_err(db_err)?))
+}
+
+/// Split a flat `Float32Array` into one row per slug.
+fn vector_rows(slugs: &[String], flat: &[f32]) -> Result<Vec<Vec<f32>>> {
+    if slugs.is_empty() || !flat.len().is_multiple_of(slugs.len()) {
+        return Err(Error::from_reason(format!(
+            "{} floats do not split evenly into {} vectors", flat.len(), slugs.len()
+        )));
+    }
+    Ok(flat.chunks(flat.len() / slugs.len()).map(<[f32]>::to_vec).collect())
+}
+
+fn backup(db: &CoreDB, path: &str) -> Result<f64> {
+    let file = File::create(path).map_err(db_err)?;
+    Ok(js_count(db.export_ndjson(BufWriter::new(file)).map_err(db_err)?))
+}
+
+fn load_backup(db: &mut CoreDB, path: &str) -> Result<f64> {
+    let file = File::open(path).map_err(db_err)?;
+    let summary = db.import_ndjson(BufReader::new(file)).map_err(db_err)?;
+    Ok(js_count(summary.applied))
+}
+
+// ── DbTask ────────────────────────────────────────────────────────────────────
+
+type DbOp<T> = Box<dyn FnOnce(&mut CoreDB) -> Result<T> + Send>;
+
+/// One database call run on the libuv threadpool by the `…Async` methods.
+pub struct DbTask<T> {
+    db: Shared,
+    op: Option<DbOp<T>>,
+}
+
+impl<T> DbTask<T> {
+    fn new(db: &Shared, op: impl FnOnce(&mut CoreDB) -> Result<T> + Send + 'static) -> AsyncTask<Self>
+    where
+        Self: Task,
+    {
+        AsyncTask::new(Self { db: Arc::clone(db), op: Some(Box::new(op)) })
+    }
+}
+
+impl<T: ToNapiValue + TypeName + Send + 'static> Task for DbTask<T> {
+    type Output = T;
+    type JsValue = T;
+
+    fn compute(&mut self) -> Result<T> {
+        let op = self.op.take().ok_or_else(|| Error::from_reason("task already ran"))?;
+        with_db(&self.db, op)
+    }
+
+    fn resolve(&mut self, _env: Env, output: T) -> Result<T> {
+        Ok(output)
+    }
+}
+
+// ── SekejapDB ─────────────────────────────────────────────────────────────────
+
+/// An embedded graph + document database.
+#[napi(js_name = "SekejapDB")]
+pub struct JsDB {
+    inner: Shared,
+    /// The database's job registry, reachable while a call holds `inner`.
+    jobs: Jobs,
+}
+
+#[napi]
+impl JsDB {
+    /// Open or create a database in `path`; omit it for an in-memory one.
+    #[napi(constructor)]
+    pub fn new(path: Option<String>) -> Result<Self> {
+        let db = match path {
+            Some(p) => CoreDB::open(p).map_err(db_err)?,
+            None => CoreDB::new(),
+        };
+        Ok(Self { jobs: db.jobs(), inner: Arc::new(Mutex::new(Some(db))) })
+    }
+
+    // ── Nodes ─────────────────────────────────────────────────────────────────
+
+    /// Store a node. `json` must contain `_collection` and `_key`.
+    #[napi]
+    pub fn put(&self, slug: String, json: String) -> Result<()> {
+        with_db(&self.inner, |db| db.put(&slug, &json).map(|_| ()).map_err(db_err))
+    }
+
+    /// `put` on the threadpool.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn put_async(&self, slug: String, json: String) -> AsyncTask<DbTask<()>> {
+        DbTask::new(&self.inner, move |db| db.put(&slug, &json).map(|_| ()).map_err(db_err))
+    }
+
+    /// Raw JSON payload of a node, or `null`.
+    #[napi]
+    pub fn get(&self, slug: String) -> Result<Option<String>> {
+        with_db(&self.inner, |db| Ok(db.get(&slug)))
+    }
+
+    /// Delete a node (and its edges).
+    #[napi]
+    pub fn remove(&self, slug: String) -> Result<()> {
+        with_db(&self.inner, |db| {
+            db.remove(&slug);
+            Ok(())
+        })
+    }
+
+    // ── Edges ─────────────────────────────────────────────────────────────────
+
+    /// Create a directed edge; `strength` defaults to 1.0.
+    #[napi]
+    pub fn link(&self, from: String, to: String, edge_type: String, strength: Option<f64>) -> Result<()> {
+        with_db(&self.inner, |db| {
+            db.link(&from, &to, &edge_type, strength.unwrap_or(1.0) as f32);
+            Ok(())
+        })
+    }
+
+    /// Remove a directed edge.
+    #[napi]
+    pub fn unlink(&self, from: String, to: String, edge_type: String) -> Result<()> {
+        with_db(&self.inner, |db| {
+            db.unlink(&from, &to, &edge_type);
+            Ok(())
+        })
+    }
+
+    // ── SQL ───────────────────────────────────────────────────────────────────
+
+    /// Run a query and return every hit in one JSON array string,
+    /// `[{"slug": …, "payload": {…}}, …]`. `paramsJson` is a JSON array bound
+    /// to `$1`, `$2`, …; `profile` names a projection profile whose excluded
+    /// fields are stripped from every hit.
+    #[napi]
+    pub fn query_json(&self, sql: String, params_json: Option<String>, profile: Option<String>) -> Result<String> {
+        let params = parse_params(params_json.as_deref())?;
+        with_db(&self.inner, |db| query_json(db, &sql, &params, profile.as_deref()))
+    }
+
+    /// `queryJson` on the threadpool.
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn query_json_async(
+        &self,
+        sql: String,
+        params_json: Option<String>,
+        profile: Option<String>,
+    ) -> Result<AsyncTask<DbTask<String>>> {
+        let params = parse_params(params_json.as_deref())?;
+        Ok(DbTask::new(&self.inner, move |db| query_json(db, &sql, &params, profile.as_deref())))
+    }
+
+    /// `queryJson`, returning `{"rows": […], "trace": {"correlation_id": …,
+    /// "elapsed_us": …, "steps": […]}}` with each executed step's `rows_in`,
+    /// `rows_out` and `elapsed_us`. `correlationId`, if given, is recorded in
+    /// the trace so it can be joined with the caller's request logs.
+    #[napi]
+    pub fn query_json_with_trace(
+        &self,
+        sql: String,
+        params_json: Option<String>,
+        profile: Option<String>,
+        correlation_id: Option<String>,
+    ) -> Result<String> {
+        let params = parse_params(params_json.as_deref())?;
+        with_db(&self.inner, |db| query_json_with_trace(db, &sql, &params, profile.as_deref(), correlation_id))
+    }
+
+    /// Declare projection profile `profile` for `collection`, leaving the
+    /// `exclude` fields out of hits when a query passes `profile`. Omitting
+    /// `exclude` drops the profile. Persisted.
+    #[napi]
+    pub fn set_projection_profile(&self, collection: String, profile: String, exclude: Option<Vec<String>>) -> Result<()> {
+        with_db(&self.inner, |db| {
+            db.set_projection_profile(&collection, &profile, exclude);
+            Ok(())
+        })
+    }
+
+    /// The plan of a `SELECT` as a JSON array of `{"step", "detail"}`
+    /// objects; steps answered from an index carry `"index"`. The query is
+    /// not run.
+    #[napi]
+    pub fn explain_json(&self, sql: String) -> Result<String> {
+        with_db(&self.inner, |db| explain_json(db, &sql))
+    }
+
+    /// Execute a mutating statement; returns the number of affected rows.
+    #[napi]
+    pub fn execute(&self, sql: String, params_json: Option<String>) -> Result<f64> {
+        let params = parse_params(params_json.as_deref())?;
+        with_db(&self.inner, |db| execute(db, &sql, &params))
+    }
+
+    /// `execute` on the threadpool.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn execute_async(&self, sql: String, params_json: Option<String>) -> Result<AsyncTask<DbTask<f64>>> {
+        let params = parse_params(params_json.as_deref())?;
+        Ok(DbTask::new(&self.inner, move |db| execute(db, &sql, &params)))
+    }
+
+    // ── Ingest ────────────────────────────────────────────────────────────────
+
+    /// Bulk-store one vector per slug for `field` from a flat `Float32Array`
+    /// of `slugs.length × dim` floats. The HNSW index is not maintained; call
+    /// `buildHnswIndex` afterwards. Returns the number of vectors stored.
+    #[napi]
+    pub fn ingest_vectors(&self, field: String, slugs: Vec<String>, vectors: Float32Array) -> Result<f64> {
+        let rows = vector_rows(&slugs, &vectors)?;
+        with_db(&self.inner, |db| Ok(js_count(db.ingest_vectors(&field, slugs.into_iter().zip(rows)))))
+    }
+
+    /// `ingestVectors` on the threadpool.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn ingest_vectors_async(
+        &self,
+        field: String,
+        slugs: Vec<String>,
+        vectors: Float32Array,
+    ) -> Result<AsyncTask<DbTask<f64>>> {
+        let rows = vector_rows(&slugs, &vectors)?;
+        Ok(DbTask::new(&self.inner, move |db| {
+            Ok(js_count(db.ingest_vectors(&field, slugs.into_iter().zip(rows))))
+        }))
+    }
+
+    /// Build (or rebuild) the HNSW index for vector `field`.
+    #[napi]
+    pub fn build_hnsw_index(&self, field: String, m: Option<u32>, ef_construction: Option<u32>) -> Result<()> {
+        with_db(&self.inner, |db| {
+            db.build_hnsw_index(&field, m.unwrap_or(16) as usize, ef_construction.unwrap_or(200) as usize)
+                .map_err(db_err)
+        })
+    }
+
+    // ── Backup ────────────────────────────────────────────────────────────────
+
+    /// Write the whole database to `path` as NDJSON (`CoreDB::export_ndjson`).
+    /// Returns the number of records written.
+    #[napi]
+    pub fn backup(&self, path: String) -> Result<f64> {
+        with_db(&self.inner, |db| backup(db, &path))
+    }
+
+    /// `backup` on the threadpool.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn backup_async(&self, path: String) -> AsyncTask<DbTask<f64>> {
+        DbTask::new(&self.inner, move |db| backup(db, &path))
+    }
+
+    /// Load an NDJSON backup written by `backup` into this database.
+    /// Returns the number of records applied.
+    #[napi]
+    pub fn load_backup(&self, path: String) -> Result<f64> {
+        with_db(&self.inner, |db| load_backup(db, &path))
+    }
+
+    /// `loadBackup` on the threadpool.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn load_backup_async(&self, path: String) -> AsyncTask<DbTask<f64>> {
+        DbTask::new(&self.inner, move |db| load_backup(db, &path))
+    }
+
+    // ── Stats / persistence / lifecycle ──────────────────────────────────────
+
+    #[napi]
+    pub fn node_count(&self) -> Result<f64> {
+        with_db(&self.inner, |db| Ok(js_count(db.node_count())))
+    }
+
+    #[napi]
+    pub fn edge_count(&self) -> Result<f64> {
+        with_db(&self.inner, |db| Ok(js_count(db.edge_count())))
+    }
+
+    /// Flush a snapshot and truncate the WAL.
+    #[napi]
+    pub fn compact(&self) -> Result<()> {
+        with_db(&self.inner, |db| db.compact().map_err(db_err))
+    }
+
+    /// `compact` on the threadpool.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn compact_async(&self) -> AsyncTask<DbTask<()>> {
+        DbTask::new(&self.inner, |db| db.compact().map_err(db_err))
+    }
+
+    // ── Jobs ─────────────────────────────────────────────────────────────────
+
+    /// Run a long operation as a job and return the finished job as JSON
+    /// (`{"id","kind","target","state","done","total","progress","elapsed_ms"}`,
+    /// plus `"error"` when it failed). `specJson` is one of
+    /// `{"kind":"backup","dest":dir}`, `{"kind":"restore","path":file}`,
+    /// `{"kind":"compact"}`, `{"kind":"hnsw_build","field":f}` or
+    /// `{"kind":"index_rebuild"}`.
+    #[napi]
+    pub fn run_job(&self, spec_json: String) -> Result<String> {
+        let request = parse_job(&spec_json)?;
+        with_db(&self.inner, |db| Ok(db.run_job(request).to_json().to_string()))
+    }
+
+    /// `runJob` on the threadpool; the promise settles when the job ends.
+    #[napi(ts_return_type = "Promise<string>")]
+    pub fn run_job_async(&self, spec_json: String) -> Result<AsyncTask<DbTask<String>>> {
+        let request = parse_job(&spec_json)?;
+        Ok(DbTask::new(&self.inner, move |db| Ok(db.run_job(request).to_json().to_string())))
+    }
+
+    /// Running and recent jobs as a JSON array, oldest first, including
+    /// those started by direct calls such as `compact`.
+    #[napi]
+    pub fn jobs_json(&self) -> String {
+        Value::Array(self.jobs.list().iter().map(|j| j.to_json()).collect()).to_string()
+    }
+
+    /// Ask job `id` to stop at its next safe point. Returns whether it was
+    /// still running.
+    #[napi]
+    pub fn cancel_job(&self, id: i64) -> bool {
+        u64::try_from(id).is_ok_and(|id| self.jobs.cancel(id))
+    }
+
+    /// Close the database; later calls throw "DB is closed".
+    #[napi]
+    pub fn close(&self) {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
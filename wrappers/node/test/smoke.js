@@ -0,0 +1,66 @@
+// Smoke test for the Node bindings. Build first (`npm run build:debug`),
+// then `npm test`.
+const assert = require('assert')
+const fs = require('fs')
+const os = require('os')
+const path = require('path')
+const { SekejapDB } = require('..')
+
+async function main() {
+  const db = new SekejapDB()
+  db.put('users/ani', '{"_collection":"users","name":"Ani","email":"ani@example.com"}')
+  db.put('users/budi', '{"_collection":"users","name":"Budi","email":"budi@example.com"}')
+  db.link('users/ani', 'users/budi', 'knows', 0.5)
+  assert.strictEqual(JSON.parse(db.get('users/ani')).name, 'Ani')
+  assert.strictEqual(db.get('users/nope'), null)
+  assert.strictEqual(db.nodeCount(), 2)
+  assert.strictEqual(db.edgeCount(), 1)
+
+  const rows = JSON.parse(db.queryJson('SELECT * FROM users WHERE name = $1', '["Budi"]'))
+  assert.deepStrictEqual(rows.map((r) => r.slug), ['users/budi'])
+  const traced = JSON.parse(db.queryJsonWithTrace('SELECT * FROM users', null, null, 'req-42'))
+  assert.strictEqual(traced.rows.length, 2)
+  assert.ok(Array.isArray(traced.trace.steps))
+  assert.strictEqual(traced.trace.correlation_id, 'req-42')
+  assert.ok(JSON.parse(db.explainJson('SELECT * FROM users')).length > 0)
+  assert.throws(() => db.queryJson('SELEKT'))
+
+  db.setProjectionProfile('users', 'public', ['email'])
+  const pub = JSON.parse(await db.queryJsonAsync('SELECT * FROM users', null, 'public'))
+  assert.ok(pub.every((r) => r.payload.email === undefined && r.payload.name))
+  assert.ok(JSON.parse(db.queryJson('SELECT * FROM users'))[0].payload.email)
+
+  assert.strictEqual(db.execute("DELETE FROM users WHERE name = 'Budi'"), 1)
+  assert.strictEqual(await db.executeAsync('DELETE FROM users WHERE name = $1', '["Nobody"]'), 0)
+
+  const vectors = new Float32Array([1, 0, 0, 1, 0.7, 0.7])
+  assert.strictEqual(db.ingestVectors('emb', ['v/1', 'v/2', 'v/3'], vectors), 3)
+  assert.throws(() => db.ingestVectors('emb', ['v/4', 'v/5'], new Float32Array([1, 2, 3])))
+  db.buildHnswIndex('emb', 8)
+
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'sekejap-node-'))
+  const file = path.join(dir, 'backup.ndjson')
+  const written = await db.backupAsync(file)
+  assert.ok(written > 0)
+  const copy = new SekejapDB()
+  assert.strictEqual(copy.loadBackup(file), written)
+  assert.strictEqual(JSON.parse(copy.get('users/ani')).email, 'ani@example.com')
+
+  const job = JSON.parse(await db.runJobAsync('{"kind":"compact"}'))
+  assert.strictEqual(job.state, 'succeeded')
+  assert.ok(JSON.parse(db.jobsJson()).some((j) => j.id === job.id))
+  assert.strictEqual(db.cancelJob(job.id), false)
+  assert.strictEqual(db.cancelJob(-1), false)
+
+  db.close()
+  assert.throws(() => db.nodeCount(), /DB is closed/)
+  fs.rmSync(dir, { recursive: true })
+}
+
+main().then(
+  () => console.log('ok'),
+  (e) => {
+    console.error(e)
+    process.exit(1)
+  },
+)
@@ -1,7 +1,10 @@
-from .sekejap import DB as _NativeDB, Hit, EdgeHit
-from ._dataframe import DataFrameAccessor
+import asyncio
+import functools
 
-__all__ = ["DB", "Hit", "EdgeHit", "DataFrameAccessor"]
+from .sekejap import DB as _NativeDB, Hit, EdgeHit, PreparedQuery
+from ._dataframe import DataFrameAccessor, _require_pandas, _require_pyarrow
+
+__all__ = ["DB", "Hit", "EdgeHit", "PreparedQuery", "DataFrameAccessor"]
 
 
 class DB(_NativeDB):
@@ -79,6 +82,23 @@ class DB(_NativeDB):
         for h in hits:
             print(json.loads(h.payload))
 
+    NumPy vectors (float32, read through the buffer protocol)::
+
+        db.put_vectors(slugs, embeddings, field="embedding")   # shape (n, dim)
+        db.build_hnsw_index("embedding")
+        hits = db.similar_np(query_vec, 10, field="embedding")
+
+    Async (heavy calls release the GIL, so they run truly in parallel on
+    the default executor)::
+
+        await db.aput("venues/corner_hotel", '{"suburb":"Richmond"}')
+        rows = json.loads(await db.aquery_json("SELECT * FROM venues"))
+
+    Columnar export (idx, slug, lat, lon, then payload fields)::
+
+        batch = db.query_to_arrow("SELECT * FROM venues", fields=["suburb"])
+        df = db.query_df("SELECT * FROM venues")
+
     Pandas / dataframe integration::
 
         df = db.df.query("SELECT * FROM venues")
@@ -93,3 +113,36 @@ class DB(_NativeDB):
         except AttributeError:
             self._df_accessor = DataFrameAccessor(self)
             return self._df_accessor
+
+    def query_to_arrow(self, sql: str, params=None, fields=None, profile=None):
+        """
+        Run a query and return a ``pyarrow.RecordBatch``.
+
+        Columns are ``idx`` (uint64 slug hash), ``slug``, ``lat`` / ``lon``
+        (centroid of the payload's GeoJSON ``geometry``, null when absent),
+        then the payload fields named in ``fields`` — every top-level field
+        when omitted. ``profile`` strips the fields a projection profile
+        excludes. Built from :meth:`query_columns`, with no JSON round-trip
+        per row.
+        """
+        pa = _require_pyarrow()
+        cols = self.query_columns(sql, params, fields, profile)
+        idx = pa.array(cols.pop("idx"), type=pa.uint64())
+        return pa.RecordBatch.from_pydict({"idx": idx, **cols})
+
+    def query_df(self, sql: str, params=None, fields=None, profile=None):
+        """Like :meth:`query_to_arrow`, but return a ``pandas.DataFrame``."""
+        pd = _require_pandas()
+        return pd.DataFrame(self.query_columns(sql, params, fields, profile))
+
+    async def aput(self, slug: str, json: str) -> None:
+        """Awaitable :meth:`put`, run on the event loop's default executor."""
+        return await _run(functools.partial(self.put, slug, json))
+
+    async def aquery_json(self, sql: str, params=None, **options) -> str:
+        """Awaitable :meth:`query_json`, run on the event loop's default executor."""
+        return await _run(functools.partial(self.query_json, sql, params, **options))
+
+
+def _run(call):
+    return asyncio.get_running_loop().run_in_executor(None, call)
@@ -24,6 +24,16 @@ def _require_pandas():
         )
 
 
+def _require_pyarrow():
+    try:
+        import pyarrow as pa
+        return pa
+    except ImportError:
+        raise ImportError(
+            "pyarrow is required for query_to_arrow — install it with: pip install pyarrow"
+        )
+
+
 class DataFrameAccessor:
     """
     Dataframe integration namespace.  Access via ``db.df``.
@@ -77,10 +87,7 @@ class DataFrameAccessor:
         hits = self._db.query(sql)
         rows = []
         for hit in hits:
-            if hit.payload:
-                row = json.loads(hit.payload)
-            else:
-                row = {}
+            row = hit.payload_value or {}
             row.setdefault("_slug", hit.slug)
             rows.append(row)
         df = pd.DataFrame(rows)
@@ -111,8 +118,7 @@ class DataFrameAccessor:
             collection:  Target collection name (e.g. ``"researchers"``).
             id_col:      Column used as ``_key``.  Defaults to ``"_key"``.
             mapping:     ``{df_column: schema_field}`` rename map.
-            batch_size:  Rows per ``put_many`` call (unused — kept for API
-                         compatibility; rows are inserted individually for now).
+            batch_size:  Rows sent to ``mutate_json`` per call.
 
         Returns:
             Number of nodes inserted.
@@ -129,6 +135,15 @@ class DataFrameAccessor:
         """
         mapping = mapping or {}
         count = 0
+        batch: list[dict[str, str]] = []
+
+        def flush() -> int:
+            results = self._db.mutate_json(json.dumps({"mutations": batch}))
+            errors = [e for e in results if e is not None]
+            if errors:
+                raise IOError(errors[0])
+            return len(results)
+
         for _, row in df.iterrows():
             record: dict[str, Any] = {}
             for col, val in row.items():
@@ -145,8 +160,12 @@ class DataFrameAccessor:
             record["_key"] = key
             slug = f"{collection}/{key}"
 
-            self._db.put(slug, json.dumps(record))
-            count += 1
+            batch.append({"op": "put", "slug": slug, "payload": json.dumps(record)})
+            if len(batch) >= batch_size:
+                count += flush()
+                batch = []
+        if batch:
+            count += flush()
         return count
 
     # ── Load edges from DataFrame ─────────────────────────────────────────────
@@ -181,7 +200,7 @@ class DataFrameAccessor:
             target_collection:    Prefix slugs with this collection name.
             weight_col:           Column for edge weight (default 1.0).
             meta_col:             Column with JSON metadata string.
-            batch_size:           Kept for API compatibility.
+            batch_size:           Edges sent to ``link_many`` per call.
 
         Returns:
             Number of edges inserted.
@@ -212,6 +231,7 @@ class DataFrameAccessor:
             raise ValueError("provide either edge_type or edge_type_col")
 
         count = 0
+        batch = []
         for _, row in df.iterrows():
             src = str(row[source_col])
             tgt = str(row[target_col])
@@ -223,13 +243,14 @@ class DataFrameAccessor:
 
             etype = edge_type if edge_type else str(row[edge_type_col])
             weight = float(row[weight_col]) if weight_col and weight_col in row else 1.0
-
-            if meta_col and meta_col in row and row[meta_col]:
-                self._db.link_meta(src, tgt, etype, weight, str(row[meta_col]))
-            else:
-                self._db.link(src, tgt, etype, weight)
-
-            count += 1
+            meta = str(row[meta_col]) if meta_col and meta_col in row and row[meta_col] else None
+
+            batch.append((src, tgt, etype, weight, meta))
+            if len(batch) >= batch_size:
+                count += self._db.link_many(batch)
+                batch = []
+        if batch:
+            count += self._db.link_many(batch)
         return count
 
     # ── Create collection from field spec ─────────────────────────────────────
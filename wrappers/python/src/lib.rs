@@ -1,12 +1,26 @@
 //! Python bindings for sekejap via PyO3.
+//!
+//! Every database call runs with the GIL released, so long queries, batch
+//! writes and index builds do not stall other Python threads or an asyncio
+//! loop. The database sits behind a mutex: concurrent calls from several
+//! threads queue up instead of failing. Job progress and cancellation
+//! bypass the mutex, so another thread can follow a backup or index build
+//! while it runs.
 
-use pyo3::exceptions::{PyIOError, PyTypeError};
+use std::sync::Mutex;
+
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyIOError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use serde_json::Value;
 
 use ::sekejap::CoreDB;
+use ::sekejap::JobRequest;
+use ::sekejap::Jobs;
+use ::sekejap::EdgeInsert;
 use ::sekejap::EdgeHit;
 use ::sekejap::Hit;
+use ::sekejap::HitJsonOptions;
 
 // ── PyHit ─────────────────────────────────────────────────────────────────────
 
@@ -16,31 +30,105 @@ use ::sekejap::Hit;
 ///     slug (str): The node's key path, e.g. ``"students/ali"``.
 ///     payload (str | None): Raw JSON string of the node payload, or ``None``.
 ///         Parse with ``json.loads(hit.payload)``.
+///     payload_value (dict | None): The payload converted straight to Python
+///         objects, skipping the JSON string round-trip.
 #[pyclass(name = "Hit")]
 #[derive(Clone)]
 pub struct PyHit {
-    #[pyo3(get)]
-    pub slug: String,
-    /// Raw JSON string — call json.loads() on the Python side.
-    #[pyo3(get)]
-    pub payload: Option<String>,
+    /// Payload is kept parsed; converted to a string or Python objects on access.
+    hit: Hit,
 }
 
 #[pymethods]
 impl PyHit {
+    #[getter]
+    fn slug(&self) -> String {
+        self.hit.slug.clone()
+    }
+
+    /// Raw JSON string — call json.loads() on the Python side.
+    #[getter]
+    fn payload(&self) -> Option<String> {
+        self.hit.payload.as_ref().map(|v| v.to_string())
+    }
+
+    /// Score from the last scoring step — cosine distance, BM25, hybrid
+    /// score or km — or ``None``.
+    #[getter]
+    fn score(&self) -> Option<f32> {
+        self.hit.score
+    }
+
+    #[getter]
+    fn payload_value(&self, py: Python<'_>) -> PyResult<PyObject> {
+        match &self.hit.payload {
+            Some(v) => json_to_py(py, v),
+            None => Ok(py.None()),
+        }
+    }
+
+    /// The hit as a dict, in the same shape the other bindings return.
+    ///
+    /// Args:
+    ///     slug: Include ``"slug"``.
+    ///     payload: Include ``"payload"``.
+    ///     payload_as_object: Nest the payload as a dict; ``False`` gives a JSON string.
+    ///     score: Include ``"score"`` when the hit has one.
+    ///     lift: Payload fields (dotted paths allowed) copied to the top level,
+    ///         e.g. ``["score"]``.
+    #[pyo3(signature = (slug=true, payload=true, payload_as_object=true, score=true, lift=None))]
+    fn to_dict(
+        &self,
+        py: Python<'_>,
+        slug: bool,
+        payload: bool,
+        payload_as_object: bool,
+        score: bool,
+        lift: Option<Vec<String>>,
+    ) -> PyResult<PyObject> {
+        let options = HitJsonOptions { slug, payload, payload_as_object, score, lift: lift.unwrap_or_default() };
+        json_to_py(py, &self.hit.to_json(&options))
+    }
+
     fn __repr__(&self) -> String {
-        let preview = self.payload.as_deref()
-            .map(|s| &s[..s.len().min(80)])
+        let payload = self.payload();
+        let preview = payload.as_deref()
+            .map(|s| &s[..s.floor_char_boundary(80)])
             .unwrap_or("None");
-        format!("Hit(slug={:?}, payload={})", self.slug, preview)
+        format!("Hit(slug={:?}, payload={})", self.hit.slug, preview)
     }
 }
 
 fn to_pyhit(h: Hit) -> PyHit {
-    PyHit {
-        slug: h.slug,
-        payload: h.payload.as_ref().map(|v| v.to_string()),
-    }
+    PyHit { hit: h }
+}
+
+/// Convert a `serde_json::Value` into the equivalent Python object.
+fn json_to_py(py: Python<'_>, v: &Value) -> PyResult<PyObject> {
+    use pyo3::types::{PyDict, PyList};
+    Ok(match v {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_py(py),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into_py(py),
+            None => n.as_f64().unwrap_or(f64::NAN).into_py(py),
+        },
+        Value::String(s) => s.into_py(py),
+        Value::Array(items) => {
+            let list = PyList::empty_bound(py);
+            for item in items {
+                list.append(json_to_py(py, item)?)?;
+            }
+            list.into_py(py)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new_bound(py);
+            for (k, item) in map {
+                dict.set_item(k, json_to_py(py, item)?)?;
+            }
+            dict.into_py(py)
+        }
+    })
 }
 
 // ── PyEdgeHit ─────────────────────────────────────────────────────────────────
@@ -66,6 +154,8 @@ pub struct PyEdgeHit {
     #[pyo3(get)]
     pub strength: f32,
     #[pyo3(get)]
+    pub created_unix: i64,
+    #[pyo3(get)]
     pub meta_json: Option<String>,
 }
 
@@ -79,12 +169,42 @@ impl PyEdgeHit {
     }
 }
 
+// ── PyPreparedQuery ───────────────────────────────────────────────────────────
+
+/// A statement parsed once by :meth:`DB.prepare`.
+///
+/// Attributes:
+///     sql (str): The statement text.
+///     param_count (int): How many values each run must bind (``$1..$n``).
+#[pyclass(name = "PreparedQuery", frozen)]
+pub struct PyPreparedQuery {
+    inner: ::sekejap::PreparedQuery,
+}
+
+#[pymethods]
+impl PyPreparedQuery {
+    #[getter]
+    fn sql(&self) -> &str {
+        self.inner.sql()
+    }
+
+    #[getter]
+    fn param_count(&self) -> usize {
+        self.inner.param_count()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PreparedQuery({:?})", self.inner.sql())
+    }
+}
+
 fn to_pyedgehit(e: EdgeHit) -> PyEdgeHit {
     PyEdgeHit {
         from_slug: e.from_slug,
         to_slug: e.to_slug,
         edge_type: e.edge_type,
         strength: e.strength,
+        created_unix: e.created_unix,
         meta_json: e.meta.as_ref().map(|v| v.to_string()),
     }
 }
@@ -93,6 +213,123 @@ fn db_err(e: impl std::fmt::Display) -> PyErr {
     PyIOError::new_err(e.to_string())
 }
 
+/// ``params`` as passed from Python: a dict binds ``$name`` placeholders,
+/// a list binds ``$1``, ``$2``, ….
+#[derive(FromPyObject)]
+enum PyParams {
+    Named(std::collections::HashMap<String, PyObject>),
+    Positional(Vec<PyObject>),
+}
+
+/// [`PyParams`] converted to JSON values.
+enum Params {
+    Positional(Vec<Value>),
+    Named(serde_json::Map<String, Value>),
+}
+
+fn py_params(py: Python<'_>, params: Option<PyParams>) -> PyResult<Params> {
+    Ok(match params {
+        None => Params::Positional(Vec::new()),
+        Some(PyParams::Positional(objs)) => Params::Positional(py_list_to_values(py, objs)?),
+        Some(PyParams::Named(map)) => {
+            let (names, objs): (Vec<String>, Vec<PyObject>) = map.into_iter().unzip();
+            Params::Named(names.into_iter().zip(py_list_to_values(py, objs)?).collect())
+        }
+    })
+}
+
+fn query_set<'db>(
+    db: &'db CoreDB,
+    sql: &str,
+    params: &Params,
+    limits: &::sekejap::QueryLimits,
+) -> PyResult<::sekejap::Set<'db>> {
+    match params {
+        Params::Positional(vals) => db.query_with_limits(sql, vals, limits),
+        Params::Named(vals) => db.query_named_with_limits(sql, vals, limits),
+    }
+    .map_err(db_err)
+}
+
+fn prepared_set<'db>(
+    db: &'db CoreDB,
+    prepared: &::sekejap::PreparedQuery,
+    params: &Params,
+) -> PyResult<::sekejap::Set<'db>> {
+    match params {
+        Params::Positional(vals) => db.query_prepared(prepared, vals),
+        Params::Named(vals) => db.query_prepared_named(prepared, vals),
+    }
+    .map_err(db_err)
+}
+
+/// Run `sql` under `limits`, applying projection `profile` if given.
+fn run_query(
+    db: &CoreDB,
+    sql: &str,
+    params: &Params,
+    limits: &::sekejap::QueryLimits,
+    profile: Option<&str>,
+) -> PyResult<Vec<Hit>> {
+    let set = query_set(db, sql, params, limits)?;
+    Ok(match profile {
+        Some(p) => set.profile(p).map_err(db_err)?.collect(),
+        None => set.collect(),
+    })
+}
+
+/// [`run_query`], also returning the run's per-step trace, tagged with
+/// `correlation_id` if given.
+fn run_query_traced(
+    db: &CoreDB,
+    sql: &str,
+    params: &Params,
+    limits: &::sekejap::QueryLimits,
+    profile: Option<&str>,
+    correlation_id: Option<&str>,
+) -> PyResult<(Vec<Hit>, ::sekejap::Trace)> {
+    let set = query_set(db, sql, params, limits)?;
+    let set = match correlation_id {
+        Some(id) => set.correlation_id(id),
+        None => set,
+    };
+    Ok(match profile {
+        Some(p) => set.profile(p).map_err(db_err)?.collect_traced(),
+        None => set.collect_traced(),
+    })
+}
+
+/// Top-level payload field names across `hits`, in first-seen order,
+/// leaving out the fixed columns of `DB.query_columns`.
+fn payload_fields(hits: &[Hit]) -> Vec<String> {
+    let mut seen: std::collections::HashSet<&str> = ["idx", "slug", "lat", "lon"].into();
+    hits.iter()
+        .filter_map(|h| h.payload.as_ref()?.as_object())
+        .flat_map(|obj| obj.keys())
+        .filter(|k| seen.insert(k.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Read a C-contiguous or strided `float32` buffer of `ndim` dimensions
+/// (1 = one vector, 2 = one vector per row) into per-row vectors.
+fn f32_rows(py: Python<'_>, obj: &Bound<'_, PyAny>, ndim: usize) -> PyResult<Vec<Vec<f32>>> {
+    let buf = PyBuffer::<f32>::get_bound(obj)?;
+    if buf.dimensions() != ndim {
+        return Err(PyValueError::new_err(format!(
+            "expected a {ndim}-D float32 array, got {} dimensions", buf.dimensions()
+        )));
+    }
+    let dim = *buf.shape().last().unwrap_or(&0);
+    if dim == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(match buf.as_slice(py) {
+        Some(cells) => cells.chunks(dim).map(|row| row.iter().map(|c| c.get()).collect()).collect(),
+        None => buf.to_vec(py)?.chunks(dim).map(<[f32]>::to_vec).collect(),
+    })
+}
+
 /// Convert a Python list of values to `Vec<serde_json::Value>`.
 fn py_list_to_values(py: Python<'_>, objs: Vec<PyObject>) -> PyResult<Vec<Value>> {
     objs.into_iter().map(|o| {
@@ -131,7 +368,9 @@ fn py_list_to_values(py: Python<'_>, objs: Vec<PyObject>) -> PyResult<Vec<Value>
 ///         print(h.slug, h.payload)   # payload is a JSON string
 #[pyclass(name = "DB", subclass)]
 pub struct PyDB {
-    inner: Option<CoreDB>,
+    inner: Mutex<Option<CoreDB>>,
+    /// The database's job registry, reachable while a call holds `inner`.
+    jobs: Jobs,
 }
 
 #[pymethods]
@@ -148,7 +387,7 @@ impl PyDB {
             Some(p) => CoreDB::open(p).map_err(db_err)?,
             None    => CoreDB::new(),
         };
-        Ok(Self { inner: Some(inner) })
+        Ok(Self { jobs: inner.jobs(), inner: Mutex::new(Some(inner)) })
     }
 
     /// Open a read-only database backed by S3.
@@ -212,48 +451,122 @@ impl PyDB {
             cache_dir.map(std::path::Path::new),
         ).map_err(db_err)?;
 
-        Ok(Self { inner: Some(inner) })
+        Ok(Self { jobs: inner.jobs(), inner: Mutex::new(Some(inner)) })
     }
 
     // ── Nodes ─────────────────────────────────────────────────────────────────
 
     /// Store a node. ``json`` must contain ``_collection`` and ``_key``.
-    fn put(&mut self, key: &str, json: &str) -> PyResult<()> {
-        self.db_mut()?.put(key, json).map(|_| ()).map_err(db_err)
+    fn put(&self, py: Python<'_>, key: &str, json: &str) -> PyResult<()> {
+        self.write(py, |db| db.put(key, json).map(|_| ()).map_err(db_err))
     }
 
     /// Retrieve a node's raw JSON string, or ``None``.
-    fn get(&self, key: &str) -> PyResult<Option<String>> {
-        Ok(self.db()?.get(key))
+    fn get(&self, py: Python<'_>, key: &str) -> PyResult<Option<String>> {
+        self.read(py, |db| Ok(db.get(key)))
     }
 
     /// Delete a node (and its edges).
-    fn remove(&mut self, key: &str) {
-        if let Some(db) = self.inner.as_mut() { db.remove(key); }
+    fn remove(&self, py: Python<'_>, key: &str) -> PyResult<()> {
+        self.write(py, |db| {
+            db.remove(key);
+            Ok(())
+        })
     }
 
     /// Return ``True`` if the node exists.
-    fn contains(&self, key: &str) -> PyResult<bool> {
-        Ok(self.db()?.contains(key))
+    fn contains(&self, py: Python<'_>, key: &str) -> PyResult<bool> {
+        self.read(py, |db| Ok(db.contains(key)))
     }
 
     // ── Edges ─────────────────────────────────────────────────────────────────
 
     /// Create a directed edge: ``from -[edge_type]-> to``.
-    fn link(&mut self, from: &str, to: &str, edge_type: &str, strength: f32) {
-        if let Some(db) = self.inner.as_mut() {
+    fn link(&self, py: Python<'_>, from: &str, to: &str, edge_type: &str, strength: f32) -> PyResult<()> {
+        self.write(py, |db| {
             db.link(from, to, edge_type, strength);
-        }
+            Ok(())
+        })
     }
 
     /// Create a directed edge with JSON metadata.
-    fn link_meta(&mut self, from: &str, to: &str, edge_type: &str, strength: f32, meta_json: &str) -> PyResult<()> {
-        self.db_mut()?.link_meta(from, to, edge_type, strength, meta_json).map_err(db_err)
+    fn link_meta(&self, py: Python<'_>, from: &str, to: &str, edge_type: &str, strength: f32, meta_json: &str) -> PyResult<()> {
+        self.write(py, |db| db.link_meta(from, to, edge_type, strength, meta_json).map_err(db_err))
+    }
+
+    /// Create many edges in one batch (a single WAL sync).
+    ///
+    /// ``edges`` is a list of ``(from, to, edge_type, strength, meta_json)``
+    /// tuples; ``meta_json`` may be ``None``. Returns the number created.
+    fn link_many(&self, py: Python<'_>, edges: Vec<(String, String, String, f32, Option<String>)>) -> PyResult<usize> {
+        let edges = edges.into_iter().map(|(from, to, edge_type, strength, props_json)| EdgeInsert {
+            from,
+            to,
+            edge_type,
+            strength,
+            props_json,
+        });
+        self.write(py, |db| db.link_many(edges).map_err(db_err))
     }
 
     /// Remove a directed edge.
-    fn unlink(&mut self, from: &str, to: &str, edge_type: &str) {
-        if let Some(db) = self.inner.as_mut() { db.unlink(from, to, edge_type); }
+    fn unlink(&self, py: Python<'_>, from: &str, to: &str, edge_type: &str) -> PyResult<()> {
+        self.write(py, |db| {
+            db.unlink(from, to, edge_type);
+            Ok(())
+        })
+    }
+
+    // ── Mutation documents ────────────────────────────────────────────────────
+
+    /// Apply a mutation document or a ``{"mutations": [...]}`` batch
+    /// (add ``"atomic": true`` for all-or-nothing). Returns one entry per
+    /// document: ``None`` if it was applied, else the error message.
+    fn mutate_json(&self, py: Python<'_>, json: &str) -> PyResult<Vec<Option<String>>> {
+        let results = self.write(py, |db| db.mutate_json(json).map_err(db_err))?;
+        Ok(results.into_iter().map(Result::err).collect())
+    }
+
+    // ── Vectors ───────────────────────────────────────────────────────────────
+
+    /// Build (or rebuild) the HNSW index for vector ``field``.
+    ///
+    /// ``m`` is the max connections per node, ``ef_construction`` the build
+    /// beam width. Runs without the GIL; on a large field this takes a while.
+    #[pyo3(signature = (field, m=16, ef_construction=200))]
+    fn build_hnsw_index(&self, py: Python<'_>, field: &str, m: usize, ef_construction: usize) -> PyResult<()> {
+        self.write(py, |db| db.build_hnsw_index(field, m, ef_construction).map_err(db_err))
+    }
+
+    /// Bulk-store one vector per slug from a 2-D ``float32`` array of shape
+    /// ``(len(slugs), dim)``, read through the buffer protocol — no Python
+    /// lists in between. Like ``CoreDB::ingest_vectors``, the HNSW index of
+    /// ``field`` is not maintained; call :meth:`build_hnsw_index` afterwards.
+    /// Returns the number of vectors stored.
+    #[pyo3(signature = (slugs, vectors, *, field))]
+    fn put_vectors(
+        &self,
+        py: Python<'_>,
+        slugs: Vec<String>,
+        vectors: &Bound<'_, PyAny>,
+        field: &str,
+    ) -> PyResult<usize> {
+        let rows = f32_rows(py, vectors, 2)?;
+        if rows.len() != slugs.len() {
+            return Err(PyValueError::new_err(format!(
+                "got {} slugs but {} vectors", slugs.len(), rows.len()
+            )));
+        }
+        self.write(py, |db| Ok(db.ingest_vectors(field, slugs.into_iter().zip(rows))))
+    }
+
+    /// Top-``k`` nodes by cosine similarity to a 1-D ``float32`` ``query``
+    /// array in vector ``field``, most similar first.
+    #[pyo3(signature = (query, k, *, field))]
+    fn similar_np(&self, py: Python<'_>, query: &Bound<'_, PyAny>, k: usize, field: &str) -> PyResult<Vec<PyHit>> {
+        let query = f32_rows(py, query, 1)?.pop().unwrap_or_default();
+        let hits = self.read(py, |db| Ok(db.all().vector_near(field, query, k).collect()))?;
+        Ok(hits.into_iter().map(to_pyhit).collect())
     }
 
     // ── SQL ───────────────────────────────────────────────────────────────────
@@ -318,22 +631,213 @@ impl PyDB {
     ///         FROM islands AS a, MATCH ('crews/straw_hats')-[:includes]->(b)
     ///     """)
     ///
-    /// Optionally pass ``params`` for ``$1``, ``$2``, … bindings.
+    /// Optionally pass ``params``: a list binds ``$1``, ``$2``, …, a dict
+    /// binds ``$name`` placeholders. Either way values never have to be
+    /// spliced into the SQL text.
     ///
     /// Example::
     ///
     ///     db.query("SELECT * FROM users WHERE name = $1 AND age > $2", ["Alice", 25])
-    #[pyo3(signature = (sql, params=None))]
-    fn query(&self, py: Python<'_>, sql: &str, params: Option<Vec<PyObject>>) -> PyResult<Vec<PyHit>> {
-        let hits: Vec<Hit> = if let Some(p) = params {
-            let vals = py_list_to_values(py, p)?;
-            self.db()?.query_params(sql, &vals).map_err(db_err)?.collect()
-        } else {
-            self.db()?.query(sql).map_err(db_err)?.collect()
-        };
+    ///     db.query("SELECT * FROM users WHERE name = $name", {"name": "Alice"})
+    ///
+    /// ``max_results``, ``max_steps`` and ``max_sql_len`` tighten the
+    /// database-wide query limits for this call only::
+    ///
+    ///     db.query(user_sql, max_results=100, max_sql_len=4096)
+    ///
+    /// ``profile`` names a projection profile (``CoreDB::set_projection_profile``)
+    /// whose excluded fields are stripped from every hit::
+    ///
+    ///     db.query("SELECT * FROM users", profile="public")
+    #[pyo3(signature = (sql, params=None, max_results=None, max_steps=None, max_sql_len=None, profile=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn query(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<PyParams>,
+        max_results: Option<usize>,
+        max_steps: Option<usize>,
+        max_sql_len: Option<usize>,
+        profile: Option<&str>,
+    ) -> PyResult<Vec<PyHit>> {
+        let vals = py_params(py, params)?;
+        let limits = ::sekejap::QueryLimits { max_results, max_steps, max_sql_len, ..Default::default() };
+        let hits = self.read(py, |db| run_query(db, sql, &vals, &limits, profile))?;
+        Ok(hits.into_iter().map(to_pyhit).collect())
+    }
+
+    /// Like :meth:`query`, but return every hit in one JSON array string,
+    /// ``[{"slug": ..., "payload": {...}}, ...]``, serialised with the GIL
+    /// released. Hand it straight to a web response, or ``json.loads`` it.
+    #[pyo3(signature = (sql, params=None, max_results=None, max_steps=None, max_sql_len=None, profile=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn query_json(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<PyParams>,
+        max_results: Option<usize>,
+        max_steps: Option<usize>,
+        max_sql_len: Option<usize>,
+        profile: Option<&str>,
+    ) -> PyResult<String> {
+        let vals = py_params(py, params)?;
+        let limits = ::sekejap::QueryLimits { max_results, max_steps, max_sql_len, ..Default::default() };
+        self.read(py, |db| {
+            let options = HitJsonOptions::default();
+            let rows: Vec<Value> = run_query(db, sql, &vals, &limits, profile)?
+                .iter()
+                .map(|h| h.to_json(&options))
+                .collect();
+            Ok(Value::Array(rows).to_string())
+        })
+    }
+
+    /// Like :meth:`query_json`, but return ``{"rows": [...], "trace": {...}}``
+    /// where ``trace`` holds each executed step with its ``rows_in``,
+    /// ``rows_out`` and ``elapsed_us``, the same shape the HTTP server sends
+    /// for ``"trace": true``::
+    ///
+    ///     out = json.loads(db.query_json_with_trace("SELECT * FROM venues WHERE suburb = 'Richmond'"))
+    ///     for step in out["trace"]["steps"]:
+    ///         print(step["step"], step["detail"], step["elapsed_us"])
+    ///
+    /// ``correlation_id`` is recorded as ``trace["correlation_id"]``, so the
+    /// timings can be joined with the application's request logs::
+    ///
+    ///     db.query_json_with_trace(sql, correlation_id=request.headers["X-Request-Id"])
+    #[pyo3(signature = (sql, params=None, max_results=None, max_steps=None, max_sql_len=None, profile=None, correlation_id=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn query_json_with_trace(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<PyParams>,
+        max_results: Option<usize>,
+        max_steps: Option<usize>,
+        max_sql_len: Option<usize>,
+        profile: Option<&str>,
+        correlation_id: Option<&str>,
+    ) -> PyResult<String> {
+        let vals = py_params(py, params)?;
+        let limits = ::sekejap::QueryLimits { max_results, max_steps, max_sql_len, ..Default::default() };
+        self.read(py, |db| {
+            let options = HitJsonOptions::default();
+            let (hits, trace) = run_query_traced(db, sql, &vals, &limits, profile, correlation_id)?;
+            let rows: Vec<Value> = hits.iter().map(|h| h.to_json(&options)).collect();
+            Ok(serde_json::json!({ "rows": rows, "trace": trace.to_json() }).to_string())
+        })
+    }
+
+    /// The plan of a ``SELECT`` as a JSON array, one ``{"step", "detail"}``
+    /// object per step; steps answered from an index carry ``"index"``
+    /// (e.g. ``"btree"``). The query is not run.
+    fn explain_json(&self, py: Python<'_>, sql: &str) -> PyResult<String> {
+        self.read(py, |db| {
+            let plan: Vec<Value> = db.explain(sql).map_err(db_err)?
+                .into_iter()
+                .filter_map(|h| h.payload)
+                .collect();
+            Ok(Value::Array(plan).to_string())
+        })
+    }
+
+    /// Parse ``sql`` once for repeated runs with :meth:`query_prepared`.
+    /// Statements are cached by their text, so preparing the same SQL
+    /// again is a lookup::
+    ///
+    ///     by_name = db.prepare("SELECT * FROM users WHERE name = $1")
+    ///     for name in names:
+    ///         hits = db.query_prepared(by_name, [name])
+    fn prepare(&self, py: Python<'_>, sql: &str) -> PyResult<PyPreparedQuery> {
+        let inner = self.read(py, |db| db.prepare(sql).map_err(db_err))?;
+        Ok(PyPreparedQuery { inner })
+    }
+
+    /// Run a statement from :meth:`prepare` with ``params`` bound to its
+    /// placeholders: a list for ``$1``, ``$2``, …, a dict for ``$name``.
+    #[pyo3(signature = (prepared, params=None))]
+    fn query_prepared(
+        &self,
+        py: Python<'_>,
+        prepared: &PyPreparedQuery,
+        params: Option<PyParams>,
+    ) -> PyResult<Vec<PyHit>> {
+        let vals = py_params(py, params)?;
+        let prepared = &prepared.inner;
+        let hits = self.read(py, |db| Ok(prepared_set(db, prepared, &vals)?.collect()))?;
         Ok(hits.into_iter().map(to_pyhit).collect())
     }
 
+    /// Like :meth:`query_prepared`, but return the hits as one JSON array
+    /// string, as :meth:`query_json` does.
+    #[pyo3(signature = (prepared, params=None))]
+    fn query_prepared_json(
+        &self,
+        py: Python<'_>,
+        prepared: &PyPreparedQuery,
+        params: Option<PyParams>,
+    ) -> PyResult<String> {
+        let vals = py_params(py, params)?;
+        let prepared = &prepared.inner;
+        self.read(py, |db| {
+            let options = HitJsonOptions::default();
+            let rows: Vec<Value> = prepared_set(db, prepared, &vals)?
+                .collect()
+                .iter()
+                .map(|h| h.to_json(&options))
+                .collect();
+            Ok(Value::Array(rows).to_string())
+        })
+    }
+
+    /// Run a query and return its results column by column, as a
+    /// ``{name: list}`` dict: ``idx`` (slug hash), ``slug``, ``lat`` / ``lon``
+    /// (GeoJSON ``geometry`` centroid, or ``None``), then one column per
+    /// payload field. ``fields`` picks and orders the payload columns; by
+    /// default every top-level field seen, in first-seen order. Missing
+    /// values are ``None``. ``profile`` is applied as in :meth:`query`.
+    /// Backs ``DB.query_to_arrow`` and ``DB.query_df``.
+    #[pyo3(signature = (sql, params=None, fields=None, profile=None))]
+    fn query_columns(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<PyParams>,
+        fields: Option<Vec<String>>,
+        profile: Option<&str>,
+    ) -> PyResult<PyObject> {
+        use pyo3::types::PyDict;
+        let vals = py_params(py, params)?;
+        let (hits, fields) = self.read(py, |db| {
+            let hits = run_query(db, sql, &vals, &::sekejap::QueryLimits::default(), profile)?;
+            let fields = fields.unwrap_or_else(|| payload_fields(&hits));
+            Ok((hits, fields))
+        })?;
+
+        let out = PyDict::new_bound(py);
+        let idx: Vec<u64> = hits.iter().map(|h| h.slug_hash).collect();
+        let slug: Vec<&str> = hits.iter().map(|h| h.slug.as_str()).collect();
+        let centroids: Vec<Option<(f64, f64)>> = hits.iter()
+            .map(|h| h.payload.as_ref().and_then(::sekejap::geo::extract_centroid))
+            .collect();
+        out.set_item("idx", idx)?;
+        out.set_item("slug", slug)?;
+        out.set_item("lat", centroids.iter().map(|c| c.map(|c| c.0)).collect::<Vec<_>>())?;
+        out.set_item("lon", centroids.iter().map(|c| c.map(|c| c.1)).collect::<Vec<_>>())?;
+        for field in &fields {
+            let column = hits.iter()
+                .map(|h| match h.payload.as_ref().and_then(|p| p.get(field)) {
+                    Some(v) => json_to_py(py, v),
+                    None => Ok(py.None()),
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            out.set_item(field, column)?;
+        }
+        Ok(out.into_py(py))
+    }
+
     /// Execute a mutating statement (INSERT / UPDATE / DELETE / CREATE / DROP).
     ///
     /// Returns the number of rows affected. Optionally pass ``params`` for ``$1``, ``$2``, … bindings.
@@ -342,12 +846,13 @@ impl PyDB {
     ///
     ///     db.execute("INSERT INTO users (_key, name, age) VALUES ($1, $2, $3)", ["u1", "Bob", 30])
     #[pyo3(signature = (sql, params=None))]
-    fn execute(&mut self, py: Python<'_>, sql: &str, params: Option<Vec<PyObject>>) -> PyResult<usize> {
-        if let Some(p) = params {
-            let vals = py_list_to_values(py, p)?;
-            self.db_mut()?.execute_params(sql, &vals).map_err(db_err)
-        } else {
-            self.db_mut()?.execute(sql).map_err(db_err)
+    fn execute(&self, py: Python<'_>, sql: &str, params: Option<Vec<PyObject>>) -> PyResult<usize> {
+        match params {
+            Some(p) => {
+                let vals = py_list_to_values(py, p)?;
+                self.write(py, |db| db.execute_params(sql, &vals).map_err(db_err))
+            }
+            None => self.write(py, |db| db.execute(sql).map_err(db_err)),
         }
     }
 
@@ -362,70 +867,154 @@ impl PyDB {
     ///     db.show("SHOW collection")                  # [{field, type, source, ...}, ...]
     ///
     /// Each hit's ``payload`` is a JSON string — use ``json.loads(hit.payload)``.
-    fn show(&self, sql: &str) -> PyResult<Vec<PyHit>> {
-        Ok(self.db()?.show(sql).map_err(db_err)?
-            .into_iter().map(to_pyhit).collect())
+    fn show(&self, py: Python<'_>, sql: &str) -> PyResult<Vec<PyHit>> {
+        let hits = self.read(py, |db| db.show(sql).map_err(db_err))?;
+        Ok(hits.into_iter().map(to_pyhit).collect())
     }
 
     // ── Introspection ─────────────────────────────────────────────────────────
 
     /// List all collection names in the database.
-    fn collection_names(&self) -> PyResult<Vec<String>> {
-        Ok(self.db()?.collection_names())
+    fn collection_names(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        self.read(py, |db| Ok(db.collection_names()))
     }
 
     /// Return distinct ``(from_collection, edge_type, to_collection)`` triples.
-    fn edge_schema(&self) -> PyResult<Vec<(String, String, String)>> {
-        Ok(self.db()?.edge_schema())
+    fn edge_schema(&self, py: Python<'_>) -> PyResult<Vec<(String, String, String)>> {
+        self.read(py, |db| Ok(db.edge_schema()))
     }
 
     /// Return distinct edge type names leaving a collection.
-    fn edge_types_from_collection(&self, collection: &str) -> PyResult<Vec<String>> {
-        Ok(self.db()?.edge_types_from_collection(collection))
+    fn edge_types_from_collection(&self, py: Python<'_>, collection: &str) -> PyResult<Vec<String>> {
+        self.read(py, |db| Ok(db.edge_types_from_collection(collection)))
     }
 
     /// DDL string for a collection schema, or ``None``.
-    fn schema_ddl(&self, collection: &str) -> PyResult<Option<String>> {
-        Ok(self.db()?.schema_ddl(collection))
+    fn schema_ddl(&self, py: Python<'_>, collection: &str) -> PyResult<Option<String>> {
+        self.read(py, |db| Ok(db.schema_ddl(collection)))
+    }
+
+    /// Declare projection profile ``profile`` for ``collection``, leaving the
+    /// ``exclude`` fields out of hits when a query passes ``profile=``.
+    /// ``exclude=None`` drops the profile. Persisted.
+    #[pyo3(signature = (collection, profile, exclude=None))]
+    fn set_projection_profile(
+        &self,
+        py: Python<'_>,
+        collection: &str,
+        profile: &str,
+        exclude: Option<Vec<String>>,
+    ) -> PyResult<()> {
+        self.write(py, |db| {
+            db.set_projection_profile(collection, profile, exclude);
+            Ok(())
+        })
     }
 
     /// Total number of nodes.
-    fn node_count(&self) -> PyResult<usize> { Ok(self.db()?.node_count()) }
+    fn node_count(&self, py: Python<'_>) -> PyResult<usize> { self.read(py, |db| Ok(db.node_count())) }
 
     /// Total number of edges.
-    fn edge_count(&self) -> PyResult<usize> { Ok(self.db()?.edge_count()) }
+    fn edge_count(&self, py: Python<'_>) -> PyResult<usize> { self.read(py, |db| Ok(db.edge_count())) }
 
     // ── Persistence ───────────────────────────────────────────────────────────
 
     /// Flush WAL snapshot and truncate the log.
-    fn compact(&mut self) -> PyResult<()> {
-        self.db_mut()?.compact().map_err(db_err)
+    fn compact(&self, py: Python<'_>) -> PyResult<()> {
+        self.write(py, |db| db.compact().map_err(db_err))
+    }
+
+    // ── Jobs ──────────────────────────────────────────────────────────────────
+
+    /// Run a long operation as a job and return the finished job as JSON:
+    /// ``{"id", "kind", "target", "state", "done", "total", "progress",
+    /// "elapsed_ms"}``, plus ``"error"`` when it failed.
+    ///
+    /// ``spec_json`` is one of ``{"kind": "backup", "dest": dir}``,
+    /// ``{"kind": "restore", "path": file}``, ``{"kind": "compact"}``,
+    /// ``{"kind": "hnsw_build", "field": f, "m": 16, "ef_construction": 200}``
+    /// or ``{"kind": "index_rebuild"}``. Direct calls such as
+    /// ``build_hnsw_index`` are tracked as jobs too.
+    ///
+    /// Example::
+    ///
+    ///     import threading, time
+    ///     t = threading.Thread(target=db.run_job, args=('{"kind":"hnsw_build","field":"emb"}',))
+    ///     t.start()
+    ///     while t.is_alive():
+    ///         print(json.loads(db.jobs_json())[-1]["progress"])
+    ///         time.sleep(0.5)
+    fn run_job(&self, py: Python<'_>, spec_json: &str) -> PyResult<String> {
+        let spec: Value = serde_json::from_str(spec_json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let request = JobRequest::from_json(&spec).map_err(PyValueError::new_err)?;
+        self.write(py, |db| Ok(db.run_job(request).to_json().to_string()))
+    }
+
+    /// Running and recent jobs as a JSON array, oldest first. Answers
+    /// while a job holds the database.
+    fn jobs_json(&self) -> String {
+        Value::Array(self.jobs.list().iter().map(|j| j.to_json()).collect()).to_string()
+    }
+
+    /// Ask job ``id`` to stop at its next safe point. Returns whether it
+    /// was still running.
+    fn cancel_job(&self, id: u64) -> bool {
+        self.jobs.cancel(id)
+    }
+
+    /// Call ``callback(job_json)`` each time a job finishes, on the thread
+    /// that ran it. Exceptions raised by the callback are printed and
+    /// otherwise ignored.
+    fn on_job_complete(&self, callback: PyObject) {
+        self.jobs.on_complete(move |job| {
+            Python::with_gil(|py| {
+                if let Err(e) = callback.call1(py, (job.to_json().to_string(),)) {
+                    e.print(py);
+                }
+            });
+        });
     }
 
     // ── Lifecycle ─────────────────────────────────────────────────────────────
 
-    fn close(&mut self) { self.inner.take(); }
+    fn close(&self, py: Python<'_>) {
+        // Waits for a call still running on another thread.
+        py.allow_threads(|| self.lock().take());
+    }
 
     fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> { slf }
-    fn __exit__(&mut self, _et: PyObject, _ev: PyObject, _tb: PyObject) -> bool {
-        self.close();
+    fn __exit__(&self, py: Python<'_>, _et: PyObject, _ev: PyObject, _tb: PyObject) -> bool {
+        self.close(py);
         false
     }
 
-    fn __repr__(&self) -> String {
-        if self.inner.is_some() { "DB(open)".into() } else { "DB(closed)".into() }
+    fn __repr__(&self, py: Python<'_>) -> String {
+        let open = py.allow_threads(|| self.lock().is_some());
+        if open { "DB(open)".into() } else { "DB(closed)".into() }
     }
 }
 
 impl PyDB {
-    fn db(&self) -> PyResult<&CoreDB> {
-        self.inner.as_ref().ok_or_else(|| PyIOError::new_err("DB is closed"))
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<CoreDB>> {
+        // A panic inside a call leaves the database itself usable.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `f` on the open database with the GIL released.
+    fn read<T: Send>(&self, py: Python<'_>, f: impl FnOnce(&CoreDB) -> PyResult<T> + Send) -> PyResult<T> {
+        py.allow_threads(|| f(self.lock().as_ref().ok_or_else(closed_err)?))
     }
-    fn db_mut(&mut self) -> PyResult<&mut CoreDB> {
-        self.inner.as_mut().ok_or_else(|| PyIOError::new_err("DB is closed"))
+
+    /// Like [`read`](Self::read), with write access.
+    fn write<T: Send>(&self, py: Python<'_>, f: impl FnOnce(&mut CoreDB) -> PyResult<T> + Send) -> PyResult<T> {
+        py.allow_threads(|| f(self.lock().as_mut().ok_or_else(closed_err)?))
     }
 }
 
+fn closed_err() -> PyErr {
+    PyIOError::new_err("DB is closed")
+}
+
 // ── Module ────────────────────────────────────────────────────────────────────
 
 #[pymodule]
@@ -433,5 +1022,6 @@ fn sekejap(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyDB>()?;
     m.add_class::<PyHit>()?;
     m.add_class::<PyEdgeHit>()?;
+    m.add_class::<PyPreparedQuery>()?;
     Ok(())
 }